@@ -0,0 +1,110 @@
+// Cell counts of a circuit and of its tagged ops, to catch performance regressions in tests
+//
+// With the `test-utils` feature, `Context::push_tag` and `Context::pop_tag` record the cells used between them in
+// `Context::tag_costs`. `CircuitCostReport::measure` runs a closure on a `RangeConfig` in shape mode, as
+// `Context::generate_witnesses` does, and reports the cells used in total and under each tag, e.g. "fp carry_mod".
+// A test can then assert that a change to an op does not make it more expensive than a budget.
+
+use crate::{
+    gates::{
+        params::{CircuitParams, CircuitShape},
+        range::{RangeConfig, RangeStrategy},
+    },
+    Context, ContextId, ContextParams,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{ConstraintSystem, Error},
+};
+use std::collections::BTreeMap;
+
+/// The cells used by a circuit and by each tag pushed while it was assigned
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitCostReport {
+    pub total: CircuitShape,
+    // the cells used between each `push_tag` and its `pop_tag`, summed over all occurrences of the tag
+    pub by_tag: BTreeMap<&'static str, CircuitShape>,
+}
+
+impl CircuitCostReport {
+    pub fn from_context<F: FieldExt>(ctx: &Context<'_, F>) -> Self {
+        Self { total: ctx.cells_used(), by_tag: ctx.tag_costs.clone() }
+    }
+
+    /// Runs `f` in shape mode on a `RangeConfig` configured with `strategy` and `params`, as `RangeCircuitBuilder`
+    /// does, and returns the cells it used. The lookup table is never loaded, so `params.degree` is not checked.
+    pub fn measure<F: FieldExt>(
+        strategy: RangeStrategy,
+        params: CircuitParams,
+        f: impl FnOnce(&RangeConfig<F>, &mut Context<'_, F>) -> Result<(), Error>,
+    ) -> Result<Self, Error> {
+        let mut meta = ConstraintSystem::<F>::default();
+        let config = RangeConfig::configure(
+            &mut meta,
+            strategy,
+            &[params.num_advice],
+            &[params.num_lookup_advice],
+            params.num_fixed,
+            params.lookup_bits,
+            ContextId::new("default"),
+        );
+        let context_params =
+            ContextParams { num_advice: vec![(ContextId::new("default"), params.num_advice)] };
+        Context::generate_witnesses(context_params, |ctx| {
+            f(&config, ctx)?;
+            Ok(Self::from_context(ctx))
+        })
+    }
+
+    /// The cells used under `tag`, which are all zero if it was never pushed
+    pub fn cost(&self, tag: &str) -> CircuitShape {
+        self.by_tag.get(tag).copied().unwrap_or_default()
+    }
+
+    pub fn assert_advice_at_most(&self, tag: &str, max: usize) {
+        let cells = self.cost(tag).advice_cells;
+        assert!(cells <= max, "{tag} uses {cells} advice cells, more than {max}");
+    }
+
+    pub fn assert_lookup_at_most(&self, tag: &str, max: usize) {
+        let cells = self.cost(tag).lookup_cells;
+        assert!(cells <= max, "{tag} uses {cells} lookup cells, more than {max}");
+    }
+
+    pub fn assert_fixed_at_most(&self, tag: &str, max: usize) {
+        let cells = self.cost(tag).fixed_cells;
+        assert!(cells <= max, "{tag} uses {cells} fixed cells, more than {max}");
+    }
+
+    /// Asserts that the whole circuit uses at most the cells of `max` of each kind
+    pub fn assert_total_at_most(&self, max: &CircuitShape) {
+        assert!(
+            self.total.advice_cells <= max.advice_cells
+                && self.total.lookup_cells <= max.lookup_cells
+                && self.total.fixed_cells <= max.fixed_cells,
+            "the circuit uses {:?}, more than {:?}",
+            self.total,
+            max
+        );
+    }
+}
+
+impl std::fmt::Display for CircuitCostReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut row = |name: &str, cost: &CircuitShape| {
+            writeln!(
+                f,
+                "{:<40} {:>10} advice {:>10} lookup {:>10} fixed",
+                name, cost.advice_cells, cost.lookup_cells, cost.fixed_cells
+            )
+        };
+        row("total", &self.total)?;
+        for (tag, cost) in self.by_tag.iter() {
+            row(tag, cost)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,75 @@
+use super::*;
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    QuantumCell::{Constant, Witness},
+};
+use halo2_proofs::{circuit::Value, halo2curves::bn256::Fr};
+
+const PARAMS: CircuitParams =
+    CircuitParams { degree: 10, num_advice: 2, num_lookup_advice: 1, num_fixed: 1, lookup_bits: 8 };
+
+#[test]
+fn test_cost_report() {
+    let report = CircuitCostReport::measure(
+        RangeStrategy::Vertical,
+        PARAMS,
+        |range: &RangeConfig<Fr>, ctx| {
+            ctx.push_tag("add");
+            let a = range.gate().add(
+                ctx,
+                &Witness(Value::known(Fr::from(300))),
+                &Constant(Fr::from(4)),
+            )?;
+            ctx.pop_tag();
+            ctx.push_tag("range check");
+            range.range_check(ctx, &a, 16)?;
+            // nested occurrences of a tag are only counted once
+            ctx.push_tag("range check");
+            range.range_check(ctx, &a, 16)?;
+            ctx.pop_tag();
+            ctx.pop_tag();
+            Ok(())
+        },
+    )
+    .unwrap();
+
+    // `a + 4 * 1 = out` takes 4 advice cells and the constants 4 and 1
+    assert_eq!(
+        report.cost("add"),
+        CircuitShape { advice_cells: 4, lookup_cells: 0, fixed_cells: 2 }
+    );
+    // each 16 bit range check copies its two 8 bit limbs to the lookup advice column
+    assert_eq!(report.cost("range check").lookup_cells, 4);
+    assert_eq!(report.cost("unused"), CircuitShape::default());
+    assert_eq!(report.total.lookup_cells, 4);
+    assert_eq!(
+        report.total.advice_cells,
+        report.cost("add").advice_cells + report.cost("range check").advice_cells
+    );
+
+    report.assert_advice_at_most("add", 4);
+    report.assert_lookup_at_most("range check", 4);
+    report.assert_fixed_at_most("add", 2);
+    report.assert_total_at_most(&CircuitShape {
+        advice_cells: report.total.advice_cells,
+        lookup_cells: 4,
+        fixed_cells: report.total.fixed_cells,
+    });
+}
+
+#[test]
+#[should_panic(expected = "add uses 4 advice cells, more than 3")]
+fn test_cost_report_over_budget() {
+    let report = CircuitCostReport::measure(
+        RangeStrategy::Vertical,
+        PARAMS,
+        |range: &RangeConfig<Fr>, ctx| {
+            ctx.push_tag("add");
+            range.gate().add(ctx, &Witness(Value::known(Fr::from(1))), &Constant(Fr::from(2)))?;
+            ctx.pop_tag();
+            Ok(())
+        },
+    )
+    .unwrap();
+    report.assert_advice_at_most("add", 3);
+}
@@ -0,0 +1,265 @@
+use super::{GateInstructions, RangeInstructions};
+use crate::{
+    utils::{biguint_to_fe, fe_to_biguint},
+    AssignedValue, Context, ContextId, QuantumCell,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+use num_bigint::BigUint;
+use std::marker::PhantomData;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseOp {
+    And,
+    Or,
+    Xor,
+}
+
+impl BitwiseOp {
+    pub fn eval(&self, a: u64, b: u64) -> u64 {
+        match self {
+            BitwiseOp::And => a & b,
+            BitwiseOp::Or => a | b,
+            BitwiseOp::Xor => a ^ b,
+        }
+    }
+}
+
+/// Lookup tables for bitwise operations on pairs of bytes
+///
+/// Each lookup is laid out in a single row `| a | b | out |` of the `advice` columns, and the selector for
+/// the operation enables the lookup of `(a, b, out)` into `(lhs, rhs, op(lhs, rhs))`.
+/// Since the tables only contain bytes, a lookup also constrains `a, b, out` to be in [0, 2^8).
+/// The table has 2^16 rows, so the circuit needs `k >= 17`.
+///
+/// The rows used are tracked in `ctx.advice_rows[context_id]`, so the `Context` must be created with
+/// `(context_id, 1)` in `ContextParams::num_advice`.
+#[derive(Clone, Debug)]
+pub struct ByteLookupConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 3],
+    pub q_and: Selector,
+    pub q_or: Selector,
+    pub q_xor: Selector,
+    // `table = [lhs, rhs, lhs & rhs, lhs | rhs, lhs ^ rhs]`
+    pub table: [TableColumn; 5],
+    pub context_id: ContextId,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ByteLookupConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, context_id: ContextId) -> Self {
+        let advice = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        for a in advice.iter() {
+            meta.enable_equality(*a);
+        }
+        let table = [
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+        ];
+        let config = Self {
+            advice,
+            q_and: meta.complex_selector(),
+            q_or: meta.complex_selector(),
+            q_xor: meta.complex_selector(),
+            table,
+            context_id,
+            _marker: PhantomData,
+        };
+        config.create_lookup(meta);
+        config
+    }
+
+    fn create_lookup(&self, meta: &mut ConstraintSystem<F>) {
+        for (q, out_table) in [(self.q_and, 2), (self.q_or, 3), (self.q_xor, 4)] {
+            meta.lookup("byte bitwise lookup", |meta| {
+                let q = meta.query_selector(q);
+                let a = meta.query_advice(self.advice[0], Rotation::cur());
+                let b = meta.query_advice(self.advice[1], Rotation::cur());
+                let out = meta.query_advice(self.advice[2], Rotation::cur());
+                // when `q = 0` this looks up `(0, 0, 0)`, which is in the table for every op
+                vec![
+                    (q.clone() * a, self.table[0]),
+                    (q.clone() * b, self.table[1]),
+                    (q * out, self.table[out_table]),
+                ]
+            });
+        }
+    }
+
+    pub fn load_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "byte bitwise lookup",
+            |mut table| {
+                for a in 0..256u64 {
+                    for b in 0..256u64 {
+                        let row = (a * 256 + b) as usize;
+                        let vals = [
+                            a,
+                            b,
+                            BitwiseOp::And.eval(a, b),
+                            BitwiseOp::Or.eval(a, b),
+                            BitwiseOp::Xor.eval(a, b),
+                        ];
+                        for (col, val) in self.table.iter().zip(vals) {
+                            table.assign_cell(
+                                || "byte bitwise table",
+                                *col,
+                                row,
+                                || Value::known(F::from(val)),
+                            )?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Returns `op(a, b)` and constrains `a, b, op(a, b)` to be bytes
+    pub fn assign_op(
+        &self,
+        ctx: &mut Context<'_, F>,
+        op: BitwiseOp,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let out_val = a.value().zip(b.value()).map(|(a, b)| {
+            let (a, b) = (a.get_lower_128() as u64, b.get_lower_128() as u64);
+            F::from(op.eval(a, b))
+        });
+        let row = ctx.advice_rows_get(&self.context_id)[0];
+        ctx.assign_cell(a.clone(), self.advice[0], &self.context_id, 0, row, 0)?;
+        ctx.assign_cell(b.clone(), self.advice[1], &self.context_id, 0, row, 0)?;
+        let out = ctx.assign_cell(
+            QuantumCell::Witness(out_val),
+            self.advice[2],
+            &self.context_id,
+            0,
+            row,
+            0,
+        )?;
+        let q = match op {
+            BitwiseOp::And => self.q_and,
+            BitwiseOp::Or => self.q_or,
+            BitwiseOp::Xor => self.q_xor,
+        };
+        q.enable(&mut ctx.region, row)?;
+        ctx.advice_rows_get_mut(&self.context_id)[0] += 1;
+
+        #[cfg(feature = "display")]
+        {
+            let count = ctx.op_count.entry(format!("byte {:?} lookup", op)).or_insert(0);
+            *count += 1;
+        }
+        Ok(out)
+    }
+}
+
+// Shifts and rotations of `width`-bit words, e.g. 32 or 64, held as single values.
+// The word is split as `a = hi * 2^s + lo` with `lo, hi` range checked by lookups, after which the results are
+// linear combinations of `lo, hi`. The split constrains `a < 2^width`, so the inputs need no separate range check.
+
+// returns `(hi, lo)` with `a = hi * 2^s + lo`, `lo < 2^s` and `hi < 2^(width - s)`
+fn split_word<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &AssignedValue<F>,
+    s: usize,
+    width: usize,
+) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+    assert!(0 < s && s < width && width < F::CAPACITY as usize);
+    let split = a.value().map(|a| {
+        let a = fe_to_biguint(a);
+        let lo = &a % (BigUint::from(1u64) << s);
+        (biguint_to_fe::<F>(&(a >> s)), biguint_to_fe::<F>(&lo))
+    });
+    // | lo | 2^s | hi | a |
+    let assigned = range.gate().assign_region_smart(
+        ctx,
+        vec![
+            Witness(split.map(|(_, lo)| lo)),
+            Constant(biguint_to_fe(&(BigUint::from(1u64) << s))),
+            Witness(split.map(|(hi, _)| hi)),
+            Existing(a),
+        ],
+        vec![0],
+        vec![],
+        vec![],
+    )?;
+    range.range_check(ctx, &assigned[0], s)?;
+    range.range_check(ctx, &assigned[2], width - s)?;
+    Ok((assigned[2].clone(), assigned[0].clone()))
+}
+
+/// Returns the `width`-bit word `a` rotated right by `s` bits, constraining `a < 2^width`
+pub fn rotate_right<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &AssignedValue<F>,
+    s: usize,
+    width: usize,
+) -> Result<AssignedValue<F>, Error> {
+    let s = s % width;
+    if s == 0 {
+        range.range_check(ctx, a, width)?;
+        return Ok(a.clone());
+    }
+    let (hi, lo) = split_word(range, ctx, a, s, width)?;
+    range.gate().mul_add(
+        ctx,
+        &Existing(&lo),
+        &Constant(biguint_to_fe(&(BigUint::from(1u64) << (width - s)))),
+        &Existing(&hi),
+    )
+}
+
+/// Returns the `width`-bit word `a` rotated left by `s` bits, constraining `a < 2^width`
+pub fn rotate_left<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &AssignedValue<F>,
+    s: usize,
+    width: usize,
+) -> Result<AssignedValue<F>, Error> {
+    rotate_right(range, ctx, a, width - s % width, width)
+}
+
+/// Returns `a >> s` for the `width`-bit word `a`, constraining `a < 2^width`
+pub fn shr<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &AssignedValue<F>,
+    s: usize,
+    width: usize,
+) -> Result<AssignedValue<F>, Error> {
+    if s == 0 || s >= width {
+        range.range_check(ctx, a, width)?;
+        return if s == 0 { Ok(a.clone()) } else { range.gate().load_zero(ctx) };
+    }
+    let (hi, _) = split_word(range, ctx, a, s, width)?;
+    Ok(hi)
+}
+
+/// Returns `(a << s) mod 2^width` for the `width`-bit word `a`, constraining `a < 2^width`
+pub fn shl<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &AssignedValue<F>,
+    s: usize,
+    width: usize,
+) -> Result<AssignedValue<F>, Error> {
+    if s == 0 || s >= width {
+        range.range_check(ctx, a, width)?;
+        return if s == 0 { Ok(a.clone()) } else { range.gate().load_zero(ctx) };
+    }
+    let (_, lo) = split_word(range, ctx, a, width - s, width)?;
+    range.gate().mul(ctx, &Existing(&lo), &Constant(biguint_to_fe(&(BigUint::from(1u64) << s))))
+}
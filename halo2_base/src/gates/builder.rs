@@ -0,0 +1,182 @@
+// Witness generation on worker threads
+//
+// A `Context` holds the `Region`, which cannot be shared across threads, so `ThreadBuilder` records the cells of a
+// chunk of independent operations without assigning them: each call to `assign_region` records a vertical block of
+// cells and the selectors of the basic gate `a + b * c = d` enabled on it, and returns `VirtualCell`s to refer to
+// them. The witnesses are computed when the builder is filled, which is the expensive part and can run on any
+// thread, while `ThreadBuilder::assign` later assigns the recorded blocks into a `Context` on the main thread.
+// Merging the builders in a fixed order makes the layout independent of the thread schedule.
+//
+// Only the basic gate is supported: besides `assign_region`, a builder has `add`, `sub`, `mul`, `mul_add` and
+// `inner_product`. It does not implement `GateInstructions` or `RangeInstructions`, whose methods take a `Context`,
+// so lookups, range checks and the chips built on those traits (e.g. `FpConfig`) still run on the main thread.
+// A computation can be moved to worker threads only if it is written against these methods.
+
+use super::GateInstructions;
+use crate::{AssignedValue, Context, QuantumCell};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+
+/// A cell of a `ThreadBuilder`: either its `i`-th input or the `i`-th cell it recorded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VirtualCell {
+    Input(usize),
+    Cell(usize),
+}
+
+/// The analogue of `QuantumCell` for a `ThreadBuilder`
+#[derive(Clone, Copy, Debug)]
+pub enum VirtualQuantumCell<F: FieldExt> {
+    Existing(VirtualCell),
+    Witness(Value<F>),
+    Constant(F),
+}
+
+#[derive(Clone, Debug)]
+pub struct ThreadBuilder<F: FieldExt> {
+    // the values of the inputs, which are passed as `AssignedValue`s to `assign`
+    pub inputs: Vec<Value<F>>,
+    // the value of each recorded cell
+    values: Vec<Value<F>>,
+    // the recorded blocks: the cells of each block and the offsets of the enabled gates
+    blocks: Vec<(Vec<VirtualQuantumCell<F>>, Vec<usize>)>,
+}
+
+impl<F: FieldExt> ThreadBuilder<F> {
+    pub fn new(inputs: Vec<Value<F>>) -> Self {
+        Self { inputs, values: Vec::new(), blocks: Vec::new() }
+    }
+
+    pub fn value(&self, cell: VirtualCell) -> Value<F> {
+        match cell {
+            VirtualCell::Input(i) => self.inputs[i],
+            VirtualCell::Cell(i) => self.values[i],
+        }
+    }
+
+    fn quantum_value(&self, cell: &VirtualQuantumCell<F>) -> Value<F> {
+        match cell {
+            VirtualQuantumCell::Existing(cell) => self.value(*cell),
+            VirtualQuantumCell::Witness(value) => *value,
+            VirtualQuantumCell::Constant(c) => Value::known(*c),
+        }
+    }
+
+    /// Records a vertical block of `inputs` with the basic gate enabled at `gate_offsets`, as in
+    /// `GateInstructions::assign_region_smart`
+    pub fn assign_region(
+        &mut self,
+        inputs: Vec<VirtualQuantumCell<F>>,
+        gate_offsets: Vec<usize>,
+    ) -> Vec<VirtualCell> {
+        let start = self.values.len();
+        for input in inputs.iter() {
+            let value = self.quantum_value(input);
+            self.values.push(value);
+        }
+        self.blocks.push((inputs, gate_offsets));
+        (start..self.values.len()).map(VirtualCell::Cell).collect()
+    }
+
+    // | c | a | b | a * b + c |
+    pub fn mul_add(
+        &mut self,
+        a: VirtualQuantumCell<F>,
+        b: VirtualQuantumCell<F>,
+        c: VirtualQuantumCell<F>,
+    ) -> VirtualCell {
+        let out = self.quantum_value(&a) * self.quantum_value(&b) + self.quantum_value(&c);
+        self.assign_region(vec![c, a, b, VirtualQuantumCell::Witness(out)], vec![0])[3]
+    }
+
+    pub fn add(&mut self, a: VirtualQuantumCell<F>, b: VirtualQuantumCell<F>) -> VirtualCell {
+        self.mul_add(a, VirtualQuantumCell::Constant(F::one()), b)
+    }
+
+    pub fn mul(&mut self, a: VirtualQuantumCell<F>, b: VirtualQuantumCell<F>) -> VirtualCell {
+        self.mul_add(a, b, VirtualQuantumCell::Constant(F::zero()))
+    }
+
+    // | a - b | b | 1 | a |
+    pub fn sub(&mut self, a: VirtualQuantumCell<F>, b: VirtualQuantumCell<F>) -> VirtualCell {
+        let out = self.quantum_value(&a) - self.quantum_value(&b);
+        self.assign_region(
+            vec![VirtualQuantumCell::Witness(out), b, VirtualQuantumCell::Constant(F::one()), a],
+            vec![0],
+        )[0]
+    }
+
+    // | 0 | a_0 | b_0 | acc_0 | a_1 | b_1 | acc_1 | ...
+    pub fn inner_product(
+        &mut self,
+        a: &[VirtualQuantumCell<F>],
+        b: &[VirtualQuantumCell<F>],
+    ) -> VirtualCell {
+        assert_eq!(a.len(), b.len());
+        assert!(!a.is_empty());
+        let mut cells = vec![VirtualQuantumCell::Constant(F::zero())];
+        let mut acc = Value::known(F::zero());
+        for (a, b) in a.iter().zip(b.iter()) {
+            acc = acc + self.quantum_value(a) * self.quantum_value(b);
+            cells.extend([*a, *b, VirtualQuantumCell::Witness(acc)]);
+        }
+        let gate_offsets = (0..a.len()).map(|i| 3 * i).collect();
+        *self.assign_region(cells, gate_offsets).last().unwrap()
+    }
+
+    /// Assigns the recorded blocks in order, where `inputs` are the cells with the values of `self.inputs`,
+    /// and returns the assigned cell of each `VirtualCell::Cell(i)` at index `i`
+    pub fn assign(
+        self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        inputs: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert_eq!(inputs.len(), self.inputs.len());
+        let mut assigned: Vec<AssignedValue<F>> = Vec::with_capacity(self.values.len());
+        for (block, gate_offsets) in self.blocks.into_iter() {
+            let cells = block
+                .iter()
+                .map(|cell| match cell {
+                    VirtualQuantumCell::Existing(VirtualCell::Input(i)) => {
+                        QuantumCell::Existing(&inputs[*i])
+                    }
+                    VirtualQuantumCell::Existing(VirtualCell::Cell(i)) => {
+                        QuantumCell::Existing(&assigned[*i])
+                    }
+                    VirtualQuantumCell::Witness(value) => QuantumCell::Witness(*value),
+                    VirtualQuantumCell::Constant(c) => QuantumCell::Constant(*c),
+                })
+                .collect();
+            let block = gate.assign_region_smart(ctx, cells, gate_offsets, vec![], vec![])?;
+            assigned.extend(block);
+        }
+        Ok(assigned)
+    }
+}
+
+/// Fills a `ThreadBuilder` for each of `jobs` with `f(builder, job)`, in parallel with the `parallel-witness` feature.
+/// The builders are returned in the order of `jobs`, to be assigned with `ThreadBuilder::assign`.
+pub fn parallelize<F, T, R>(
+    jobs: Vec<(Vec<Value<F>>, T)>,
+    f: impl Fn(&mut ThreadBuilder<F>, T) -> R + Send + Sync,
+) -> Vec<(ThreadBuilder<F>, R)>
+where
+    F: FieldExt,
+    T: Send,
+    R: Send,
+{
+    let run = |(inputs, job): (Vec<Value<F>>, T)| {
+        let mut builder = ThreadBuilder::new(inputs);
+        let out = f(&mut builder, job);
+        (builder, out)
+    };
+    #[cfg(feature = "parallel-witness")]
+    {
+        use rayon::prelude::*;
+        jobs.into_par_iter().map(run).collect()
+    }
+    #[cfg(not(feature = "parallel-witness"))]
+    {
+        jobs.into_iter().map(run).collect()
+    }
+}
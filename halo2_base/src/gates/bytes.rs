@@ -0,0 +1,63 @@
+// Shifts and masks of byte arrays by witness amounts
+//
+// Parsers of variable length encodings, e.g. DER or the calldata of the MODEXP precompile, read lengths from their
+// input and need the bytes they delimit at fixed positions. `shift_bytes` moves an array by a witness number of positions
+// with a barrel shifter over the bits of the shift, and `mask_bytes` zeros the entries from a witness length on. Neither
+// constrains the entries, so they apply to arrays of any cells, not only bytes.
+
+use super::{GateInstructions, RangeInstructions};
+use crate::{
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+/// Returns `bytes` moved `shift` positions towards the start if `to_start`, and towards the end otherwise, filling
+/// the vacated positions with zeros. `shift` is constrained to have at most `shift_bits` bits.
+pub fn shift_bytes<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+    shift: &AssignedValue<F>,
+    shift_bits: usize,
+    to_start: bool,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let gate = range.gate();
+    let zero = gate.load_zero(ctx)?;
+    let mut out = bytes.to_vec();
+    for (k, bit) in range.num_to_bits(ctx, shift, shift_bits)?.iter().enumerate() {
+        let step = 1usize << k;
+        let mut shifted = Vec::with_capacity(out.len());
+        for i in 0..out.len() {
+            let moved =
+                if to_start { out.get(i + step) } else { i.checked_sub(step).map(|j| &out[j]) };
+            shifted.push(gate.select(
+                ctx,
+                &Existing(moved.unwrap_or(&zero)),
+                &Existing(&out[i]),
+                &Existing(bit),
+            )?);
+        }
+        out = shifted;
+    }
+    Ok(out)
+}
+
+/// Returns `bytes` with the entries from index `len` on replaced by zeros, assuming `len` has at most `len_bits` bits
+/// and `bytes.len() <= 2^len_bits`
+pub fn mask_bytes<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+    len_bits: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    assert!(bytes.len() <= 1 << len_bits);
+    let mut out = Vec::with_capacity(bytes.len());
+    for (i, byte) in bytes.iter().enumerate() {
+        let is_in =
+            range.is_less_than(ctx, &Constant(F::from(i as u64)), &Existing(len), len_bits)?;
+        out.push(range.gate().mul(ctx, &Existing(byte), &Existing(&is_in))?);
+    }
+    Ok(out)
+}
@@ -0,0 +1,119 @@
+// A circuit with a single `RangeConfig`, assigned by a closure
+//
+// Most circuits built from these chips have the same `synthesize`: load the lookup table, skip the first pass of
+// `SimpleFloorPlanner`, create a `Context` in a single region, call the chips, and finalize. `RangeCircuitBuilder`
+// does all of that around a closure with the chip calls, so a test or a downstream circuit only writes the closure.
+//
+// halo2 has no circuit parameters at `configure`, so `RangeCircuitBuilder::new` stores the `CircuitParams` in a
+// thread-local which `configure` reads. The builder must therefore be created on the thread that runs keygen,
+// `MockProver::run` or the prover, before they call `configure`.
+
+use super::{
+    params::CircuitParams,
+    range::{RangeConfig, RangeStrategy},
+};
+use crate::{Context, ContextId, ContextParams};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use std::{cell::RefCell, marker::PhantomData};
+
+thread_local! {
+    // the parameters of the last `RangeCircuitBuilder` created on this thread, read by `configure`
+    static BUILDER_PARAMS: RefCell<Option<(RangeStrategy, CircuitParams)>> = RefCell::new(None);
+}
+
+#[derive(Clone, Debug)]
+pub struct RangeCircuitBuilder<F: FieldExt, Fun> {
+    pub strategy: RangeStrategy,
+    pub params: CircuitParams,
+    synthesize: Fun,
+    _marker: PhantomData<F>,
+}
+
+impl<F, Fun> RangeCircuitBuilder<F, Fun>
+where
+    F: FieldExt,
+    Fun: Fn(&RangeConfig<F>, &mut Context<'_, F>) -> Result<(), Error> + Clone,
+{
+    /// A circuit of `2^params.degree` rows which calls `synthesize` with the config and a `Context` with
+    /// `ContextId::new("default")`. The circuit loads the lookup table and calls `finalize` itself.
+    pub fn new(strategy: RangeStrategy, params: CircuitParams, synthesize: Fun) -> Self {
+        BUILDER_PARAMS.with(|builder_params| {
+            *builder_params.borrow_mut() = Some((strategy, params));
+        });
+        Self { strategy, params, synthesize, _marker: PhantomData }
+    }
+
+    /// A circuit with the vertical gate and the minimal degree at which `synthesize` fits in at most `max_advice`
+    /// advice columns, found by a dry run of `synthesize`, see `CircuitParams::auto_configure`
+    pub fn auto_configure(
+        lookup_bits: usize,
+        max_advice: usize,
+        synthesize: Fun,
+    ) -> Result<Self, Error> {
+        let params = CircuitParams::auto_configure(lookup_bits, max_advice, synthesize.clone())?;
+        Ok(Self::new(RangeStrategy::Vertical, params, synthesize))
+    }
+}
+
+impl<F, Fun> Circuit<F> for RangeCircuitBuilder<F, Fun>
+where
+    F: FieldExt,
+    Fun: Fn(&RangeConfig<F>, &mut Context<'_, F>) -> Result<(), Error> + Clone,
+{
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // the witnesses are captured by `synthesize`, which is kept: only the values of the cells depend on them
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let (strategy, params) = BUILDER_PARAMS
+            .with(|params| params.borrow().clone())
+            .expect("a RangeCircuitBuilder should be created on this thread before configure");
+        RangeConfig::configure(
+            meta,
+            strategy,
+            &[params.num_advice],
+            &[params.num_lookup_advice],
+            params.num_fixed,
+            params.lookup_bits,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "range circuit",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+                (self.synthesize)(&config, ctx)?;
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
@@ -1,19 +1,29 @@
 use super::{
-    AssignedValue, Context, GateInstructions,
+    bitwise::{BitwiseOp, ByteLookupConfig},
+    stats::CircuitStats,
+    AssignedValue, Context, ContextId, GateInstructions, InnerProductResult,
     QuantumCell::{self, Constant, Existing, Witness},
 };
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::Value,
-    plonk::{Advice, Column, ConstraintSystem, Error, FirstPhase, Fixed, SecondPhase, ThirdPhase},
+    circuit::{Cell, Layouter, Value},
+    plonk::{
+        Advice, Column, ConstraintSystem, Error, FirstPhase, Fixed, Instance, SecondPhase,
+        ThirdPhase,
+    },
     poly::Rotation,
 };
-use std::{marker::PhantomData, rc::Rc};
+use std::marker::PhantomData;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GateStrategy {
     Vertical,
     PlonkPlus,
+    // `Vertical` together with a dedicated gate for inner products with constants, see `BasicGateConfig`
+    VerticalDot,
+    // `Vertical` together with a wide gate spanning all advice columns of the first phase, used by `inner_product`,
+    // see `FlexGateConfig::q_horizontal`
+    Horizontal,
 }
 
 #[derive(Clone, Debug)]
@@ -34,6 +44,14 @@ pub struct BasicGateConfig<F: FieldExt> {
     // * a = value[0], b = value[1], c = value[2], d = value[3]
     // * the q_{} can be any fixed values in F, placed in two fixed columns
     // * it is crucial that q_io goes in its own selector column! we need it to be 0, 1 to turn on/off the gate
+
+    // If strategy is VerticalDot, then we have the basic vertical gate above and a second gate
+    // `q_dot * (acc + coeff * a - acc') = 0`
+    // where
+    // * acc = value[0], a = value[1], acc' = value[2]
+    // * q_dot = q_enable[1] is a simple selector and coeff = q_enable[2] is any fixed value in F
+    // Chaining this gate computes `acc + sum_j coeff_j * a_j` with two rows per term, which is the
+    // `acc' = acc + m_j * q_{i-j}` pattern in `carry_mod` and `check_carry_mod_to_zero` where `m_j` are limbs of the modulus
     pub q_enable: Vec<Column<Fixed>>,
     // one column to store the inputs and outputs of the gate
     pub value: Column<Advice>,
@@ -52,7 +70,7 @@ impl<F: FieldExt> BasicGateConfig<F> {
         let q = meta.fixed_column();
 
         match strategy {
-            GateStrategy::Vertical => {
+            GateStrategy::Vertical | GateStrategy::Horizontal => {
                 let config = Self { q_enable: vec![q], value, _marker: PhantomData };
                 config.create_gate(meta);
                 config
@@ -63,11 +81,19 @@ impl<F: FieldExt> BasicGateConfig<F> {
                 config.create_plonk_gate(meta);
                 config
             }
+            GateStrategy::VerticalDot => {
+                let q_dot = meta.fixed_column();
+                let coeff = meta.fixed_column();
+                let config = Self { q_enable: vec![q, q_dot, coeff], value, _marker: PhantomData };
+                config.create_gate(meta);
+                config.create_dot_gate(meta);
+                config
+            }
         }
     }
 
     fn create_gate(&self, meta: &mut ConstraintSystem<F>) {
-        assert_eq!(self.q_enable.len(), 1);
+        assert!(self.q_enable.len() == 1 || self.q_enable.len() == 3);
         meta.create_gate("1 column a * b + c = out", |meta| {
             let q = meta.query_fixed(self.q_enable[0], Rotation::cur());
 
@@ -80,6 +106,20 @@ impl<F: FieldExt> BasicGateConfig<F> {
         })
     }
 
+    fn create_dot_gate(&self, meta: &mut ConstraintSystem<F>) {
+        assert_eq!(self.q_enable.len(), 3);
+        meta.create_gate("1 column acc + coeff * a = acc'", |meta| {
+            let q_dot = meta.query_fixed(self.q_enable[1], Rotation::cur());
+            let coeff = meta.query_fixed(self.q_enable[2], Rotation::cur());
+
+            let acc = meta.query_advice(self.value, Rotation::cur());
+            let a = meta.query_advice(self.value, Rotation::next());
+            let acc_next = meta.query_advice(self.value, Rotation(2));
+
+            vec![q_dot * (acc + coeff * a - acc_next)]
+        })
+    }
+
     fn create_plonk_gate(&self, meta: &mut ConstraintSystem<F>) {
         assert_eq!(self.q_enable.len(), 2);
         meta.create_gate("plonk plus", |meta| {
@@ -109,7 +149,16 @@ pub struct FlexGateConfig<F: FieldExt> {
     pub num_advice: usize,
     strategy: GateStrategy,
     gate_len: usize,
-    pub context_id: Rc<String>,
+    pub context_id: ContextId,
+    // byte-pair bitwise lookup tables for `xor_bytes`, `and_bytes` and `or_bytes`, see `enable_byte_lookup`
+    pub byte_lookup: Option<ByteLookupConfig<F>>,
+    // If strategy is Horizontal, this is the selector of the horizontal gate on the `w` advice columns of the first phase
+    // `q_horizontal * (v_0 + v_1 * v_2 + v_3 * v_4 + ... + v_{2m - 1} * v_{2m} - v_0') = 0`
+    // where `v_j` is the current row of `basic_gates[j].value`, `v_0'` is the next row of `basic_gates[0].value`
+    // and `m = (w - 1) / 2`
+    pub q_horizontal: Option<Column<Fixed>>,
+    // instance column for the public inputs exposed with `Context::expose_public`, see `enable_instance`
+    pub instance: Option<Column<Instance>>,
 }
 
 impl<F: FieldExt> FlexGateConfig<F> {
@@ -118,7 +167,7 @@ impl<F: FieldExt> FlexGateConfig<F> {
         strategy: GateStrategy,
         num_advice: &[usize],
         num_fixed: usize,
-        context_id: String,
+        context_id: ContextId,
     ) -> Self {
         let mut constants = Vec::with_capacity(num_fixed);
         for _i in 0..num_fixed {
@@ -128,7 +177,10 @@ impl<F: FieldExt> FlexGateConfig<F> {
             constants.push(c);
         }
         match strategy {
-            GateStrategy::Vertical | GateStrategy::PlonkPlus => {
+            GateStrategy::Vertical
+            | GateStrategy::PlonkPlus
+            | GateStrategy::VerticalDot
+            | GateStrategy::Horizontal => {
                 let mut basic_gates = Vec::new();
                 for (phase, &num_columns) in num_advice.iter().enumerate() {
                     basic_gates.extend(
@@ -136,6 +188,14 @@ impl<F: FieldExt> FlexGateConfig<F> {
                             .map(|_| BasicGateConfig::configure(meta, strategy, phase as u8)),
                     );
                 }
+                let q_horizontal = if strategy == GateStrategy::Horizontal {
+                    assert!(num_advice[0] >= 3, "the horizontal gate needs 3 advice columns");
+                    let q = meta.fixed_column();
+                    Self::create_horizontal_gate(meta, q, &basic_gates[..num_advice[0]]);
+                    Some(q)
+                } else {
+                    None
+                };
                 let num_advice = basic_gates.len();
                 Self {
                     basic_gates,
@@ -143,22 +203,190 @@ impl<F: FieldExt> FlexGateConfig<F> {
                     num_advice,
                     strategy,
                     gate_len: 4,
-                    context_id: Rc::new(context_id),
+                    context_id,
+                    byte_lookup: None,
+                    q_horizontal,
+                    instance: None,
                 }
             }
         }
     }
+
+    fn create_horizontal_gate(
+        meta: &mut ConstraintSystem<F>,
+        q: Column<Fixed>,
+        gates: &[BasicGateConfig<F>],
+    ) {
+        meta.create_gate("horizontal acc + sum_i a_i * b_i = acc'", |meta| {
+            let q = meta.query_fixed(q, Rotation::cur());
+            let acc = meta.query_advice(gates[0].value, Rotation::cur());
+            let acc_next = meta.query_advice(gates[0].value, Rotation::next());
+            let sum = gates[1..].chunks_exact(2).fold(acc, |sum, pair| {
+                let a = meta.query_advice(pair[0].value, Rotation::cur());
+                let b = meta.query_advice(pair[1].value, Rotation::cur());
+                sum + a * b
+            });
+            vec![q * (sum - acc_next)]
+        })
+    }
 }
 
 impl<F: FieldExt> FlexGateConfig<F> {
-    /// call this at the very end of synthesize!
+    /// Adds the byte-pair bitwise lookup tables used by `xor_bytes`, `and_bytes` and `or_bytes`.
+    ///
+    /// The lookups are laid out in their own advice columns with context id `"{context_id}_bytes"`, so the `Context`
+    /// must be created with `(ContextId::new("{context_id}_bytes"), 1)` in `ContextParams::num_advice`. The tables have 2^16 rows,
+    /// so the circuit needs `k >= 17`.
+    pub fn enable_byte_lookup(&mut self, meta: &mut ConstraintSystem<F>) {
+        assert!(self.byte_lookup.is_none());
+        let context_id = ContextId::new(&format!("{}_bytes", self.context_id));
+        self.byte_lookup = Some(ByteLookupConfig::configure(meta, context_id));
+    }
+
+    /// Adds an instance column for the public inputs of the circuit
+    pub fn enable_instance(&mut self, meta: &mut ConstraintSystem<F>) {
+        assert!(self.instance.is_none());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        self.instance = Some(instance);
+    }
+
+    /// Constrains each `(cell, row)` of `cells`, usually `ctx.cells_to_expose`, to equal the instance column at `row`
+    ///
+    /// Must be called outside of `layouter.assign_region`, after the cells are assigned.
+    pub fn expose_public_cells(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cells: &[(Cell, usize)],
+    ) -> Result<(), Error> {
+        let instance =
+            self.instance.expect("instance column should be enabled with `enable_instance`");
+        for (cell, row) in cells.iter() {
+            layouter.constrain_instance(*cell, instance, *row)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the byte-pair lookup tables if they are enabled
+    pub fn load_byte_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        if let Some(byte_lookup) = &self.byte_lookup {
+            byte_lookup.load_lookup_table(layouter)?;
+        }
+        Ok(())
+    }
+
+    fn byte_op(
+        &self,
+        ctx: &mut Context<'_, F>,
+        op: BitwiseOp,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.byte_lookup
+            .as_ref()
+            .expect("byte lookup tables should be enabled with `enable_byte_lookup`")
+            .assign_op(ctx, op, a, b)
+    }
+
+    /// call this at the very end of the region!
     /// allocates constants to fixed columns
     /// returns (max rows used by a fixed column, total number of constants assigned)
     pub fn finalize(&self, ctx: &mut Context<'_, F>) -> Result<(usize, usize), Error> {
-        #[cfg(feature = "display")]
-        println!("{:#?}", ctx.op_count);
+        let stats = self.finalize_with_stats(ctx)?;
+        Ok((stats.fixed_rows, stats.num_constants))
+    }
 
-        ctx.assign_and_constrain_constants(&self.constants)
+    /// Same as `finalize`, returning the statistics of the circuit
+    pub fn finalize_with_stats(&self, ctx: &mut Context<'_, F>) -> Result<CircuitStats, Error> {
+        let (fixed_rows, num_constants) = ctx.assign_and_constrain_constants(&self.constants)?;
+        let stats = CircuitStats::from_context(ctx, &self.context_id);
+        Ok(CircuitStats { fixed_rows, num_constants, ..stats })
+    }
+
+    /// Inner product with the horizontal gate, in the first phase:
+    /// | acc_t | a_{mt} | b_{mt} | ... | a_{mt + m - 1} | b_{mt + m - 1} |
+    /// for `t = 0..ceil(k / m)` with `acc_0 = 0`, followed by the output `acc_{ceil(k / m)}` alone in the first column.
+    /// The rows start after the last used row of all the columns, and the pairs of the last row are padded with zeros.
+    fn inner_product_horizontal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        vec_a: &[QuantumCell<F>],
+        vec_b: &[QuantumCell<F>],
+    ) -> Result<(Vec<AssignedValue<F>>, Vec<AssignedValue<F>>, AssignedValue<F>), Error> {
+        let q = self.q_horizontal.expect("horizontal gate should be configured");
+        let width = self.basic_gates.iter().filter(|g| g.value.column_type().phase() == 0).count();
+        let m = (width - 1) / 2;
+        let k = vec_a.len();
+        let num_rows = (k + m - 1) / m;
+        let row_offset = *ctx.advice_rows_get(&self.context_id)[..width].iter().max().unwrap();
+
+        let mut a_assigned = Vec::with_capacity(k);
+        let mut b_assigned = Vec::with_capacity(k);
+        let mut acc_cell = Constant(F::zero());
+        for t in 0..num_rows {
+            let row = row_offset + t;
+            let mut acc = acc_cell.value().copied();
+            ctx.assign_cell(acc_cell, self.basic_gates[0].value, &self.context_id, 0, row, 0)?;
+            for i in 0..m {
+                let j = m * t + i;
+                let (a, b) = match vec_a.get(j) {
+                    Some(a) => (a.clone(), vec_b[j].clone()),
+                    None => (Constant(F::zero()), Constant(F::zero())),
+                };
+                acc = acc + a.value().zip(b.value()).map(|(a, b)| *a * b);
+                let a = ctx.assign_cell(
+                    a,
+                    self.basic_gates[2 * i + 1].value,
+                    &self.context_id,
+                    2 * i + 1,
+                    row,
+                    0,
+                )?;
+                let b = ctx.assign_cell(
+                    b,
+                    self.basic_gates[2 * i + 2].value,
+                    &self.context_id,
+                    2 * i + 2,
+                    row,
+                    0,
+                )?;
+                if j < k {
+                    a_assigned.push(a);
+                    b_assigned.push(b);
+                }
+            }
+            ctx.region.assign_fixed(|| "", q, row, || Value::known(F::one()))?;
+            acc_cell = Witness(acc);
+        }
+        let out = ctx.assign_cell(
+            acc_cell,
+            self.basic_gates[0].value,
+            &self.context_id,
+            0,
+            row_offset + num_rows,
+            0,
+        )?;
+
+        ctx.record_provenance(&self.context_id, 0, row_offset..row_offset + num_rows + 1);
+        for column_index in 1..width {
+            ctx.record_provenance(
+                &self.context_id,
+                column_index,
+                row_offset..row_offset + num_rows,
+            );
+        }
+        let advice_rows = ctx.advice_rows_get_mut(&self.context_id);
+        advice_rows[0] = row_offset + num_rows + 1;
+        for rows in advice_rows[1..width].iter_mut() {
+            *rows = row_offset + num_rows;
+        }
+
+        #[cfg(feature = "display")]
+        {
+            let count = ctx.op_count.entry("horizontal inner product".to_string()).or_insert(0);
+            *count += 1;
+        }
+        Ok((a_assigned, b_assigned, out))
     }
 
     /// returns leftmost `i` where `advice_rows[context_id][i]` is minimum amongst all `i` where `column[i]` is in phase `phase`
@@ -247,12 +475,11 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         }
 
         ctx.advice_rows_get_mut(&self.context_id)[gate_index] += inputs.len();
+        ctx.record_provenance(&self.context_id, gate_index, row_offset..row_offset + inputs.len());
 
         Ok(assignments)
     }
 
-    /// Only call this if ctx.region is not in shape mode, i.e., if not using simple layouter or ctx.first_pass = false
-    ///
     /// All indices in `gate_offsets`, `equality_offsets`, `external_equality` are with respect to `inputs` indices
     /// - `gate_offsets` specifies indices to enable selector for the gate; assume `gate_offsets` is sorted in increasing order
     /// - `equality_offsets` specifies pairs of indices to constrain equality
@@ -266,7 +493,10 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         external_equality: Vec<(&AssignedValue<F>, usize)>,
     ) -> Result<Vec<AssignedValue<F>>, Error> {
         let assignments = match self.strategy {
-            GateStrategy::Vertical | GateStrategy::PlonkPlus => self
+            GateStrategy::Vertical
+            | GateStrategy::PlonkPlus
+            | GateStrategy::VerticalDot
+            | GateStrategy::Horizontal => self
                 .assign_region(
                     ctx,
                     inputs,
@@ -401,21 +631,21 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         Ok(())
     }
     // Takes two vectors of `QuantumCell` and constrains a witness output to the inner product of `<vec_a, vec_b>`
-    // outputs are vec<(a_cell, a_relative_index)>, vec<(b_cell, b_relative_index)>, out_cell
-    fn inner_product(
+    fn inner_product_with_operands(
         &self,
         ctx: &mut Context<'_, F>,
         vec_a: &Vec<QuantumCell<F>>,
         vec_b: &Vec<QuantumCell<F>>,
-    ) -> Result<
-        (Option<Vec<AssignedValue<F>>>, Option<Vec<AssignedValue<F>>>, AssignedValue<F>),
-        Error,
-    > {
+    ) -> Result<InnerProductResult<F>, Error> {
         assert_eq!(vec_a.len(), vec_b.len());
         // don't try to call this function with empty inputs!
         if vec_a.len() == 0 {
             return Err(Error::Synthesis);
         }
+        if self.strategy == GateStrategy::Horizontal && ctx.current_phase() == 0 {
+            let (a, b, out) = self.inner_product_horizontal(ctx, vec_a, vec_b)?;
+            return Ok(InnerProductResult { a: Some(a), b: Some(b), out });
+        }
         // we will do special handling of the cases where one of the vectors is all constants
         if self.strategy == GateStrategy::PlonkPlus
             && vec_b.iter().all(|b| if matches!(b, Constant(_)) { true } else { false })
@@ -440,7 +670,8 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
             if start_ida == 1 && k == 1 {
                 // this is just a0 * 1 = a0; you're doing nothing, why are you calling this function?
                 let assignment = self.assign_region(ctx, vec![vec_a[0].clone()], vec![], None)?;
-                return Ok((Some(assignment.clone()), None, assignment[0].clone()));
+                let out = assignment[0].clone();
+                return Ok(InnerProductResult { a: Some(assignment), b: None, out });
             }
             let k_chunks = (k - start_ida + gate_segment - 1) / gate_segment;
             let mut cells = Vec::with_capacity(1 + (gate_segment + 1) * k_chunks);
@@ -481,14 +712,65 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
                         .clone(),
                 );
             }
-            return Ok((Some(a_assigned), None, assignments.last().unwrap().clone()));
+            let out = assignments.last().unwrap().clone();
+            return Ok(InnerProductResult { a: Some(a_assigned), b: None, out });
         }
 
-        if self.strategy == GateStrategy::PlonkPlus
+        if self.strategy == GateStrategy::VerticalDot
+            && vec_b.iter().all(|b| matches!(b, Constant(_)))
+        {
+            let vec_b: Vec<F> = vec_b
+                .iter()
+                .map(|b| if let Constant(c) = b { *c } else { unreachable!() })
+                .collect();
+            // | acc_0 | a_0 | acc_1 | a_1 | ... | a_{k-1} | acc_k |
+            // with the dot gate `acc_{j+1} = acc_j + b_j * a_j` enabled on the row of `acc_j`
+            // as above, if b0 == 1 we start from a0 instead of 0
+            let start_id = if vec_b[0] == F::one() { 1 } else { 0 };
+            let mut cells = Vec::with_capacity(2 * (vec_a.len() - start_id) + 1);
+            let mut sum =
+                if start_id == 1 { vec_a[0].value().copied() } else { Value::known(F::zero()) };
+            cells.push(if start_id == 1 { vec_a[0].clone() } else { Constant(F::zero()) });
+            for (a, b) in vec_a[start_id..].iter().zip(vec_b[start_id..].iter()) {
+                sum = sum + a.value().map(|a| *a * b);
+                cells.push(a.clone());
+                cells.push(Witness(sum));
+            }
+            let assignments = self.assign_region(ctx, cells, vec![], None)?;
+            let gate = &self.basic_gates[assignments[0].column()];
+            let row_offset = assignments[0].row();
+            for (j, b) in vec_b[start_id..].iter().enumerate() {
+                ctx.region.assign_fixed(
+                    || "",
+                    gate.q_enable[1],
+                    row_offset + 2 * j,
+                    || Value::known(F::one()),
+                )?;
+                ctx.region.assign_fixed(
+                    || "",
+                    gate.q_enable[2],
+                    row_offset + 2 * j,
+                    || Value::known(*b),
+                )?;
+            }
+
+            let mut a_assigned = Vec::with_capacity(vec_a.len());
+            if start_id == 1 {
+                a_assigned.push(assignments[0].clone());
+            }
+            for j in 0..(vec_a.len() - start_id) {
+                a_assigned.push(assignments[2 * j + 1].clone());
+            }
+            let out = assignments.last().unwrap().clone();
+            return Ok(InnerProductResult { a: Some(a_assigned), b: None, out });
+        }
+
+        if (self.strategy == GateStrategy::PlonkPlus || self.strategy == GateStrategy::VerticalDot)
             && vec_a.iter().all(|a| if matches!(a, Constant(_)) { true } else { false })
         {
-            let (b, a, out) = self.inner_product(ctx, vec_b, vec_a)?;
-            return Ok((a, b, out));
+            let InnerProductResult { a: b, b: a, out } =
+                self.inner_product_with_operands(ctx, vec_b, vec_a)?;
+            return Ok(InnerProductResult { a, b, out });
         }
 
         let mut cells: Vec<QuantumCell<F>> = Vec::with_capacity(3 * vec_a.len() + 1);
@@ -529,7 +811,8 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         }
         let b_assigned = if start_id == 1 { None } else { Some(b_assigned) };
 
-        Ok((Some(a_assigned), b_assigned, assignments.last().unwrap().clone()))
+        let out = assignments.last().unwrap().clone();
+        Ok(InnerProductResult { a: Some(a_assigned), b: b_assigned, out })
     }
 
     fn accumulated_product(
@@ -541,10 +824,45 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         assert!(vec_a.len() + 1 == vec_b.len() || (vec_a.len() == 0 && vec_b.len() == 0));
         let k = vec_b.len();
         match self.strategy {
+            // | b_1 | b_0 | a_0 | out_1 | b_2 | out_1 | a_1 | out_2 | ...
+            // a single region with the gate `a + b * c = d` every 4 cells (coefficients q_mul = 1,
+            // q_left = q_right = 0), where each `out_i` is copied to the next gate
             GateStrategy::PlonkPlus => {
-                todo!();
+                if k == 0 {
+                    return Ok(vec![]);
+                }
+                if k == 1 {
+                    return self.assign_region(ctx, vec![vec_b[0].clone()], vec![], None);
+                }
+                let mut cells = Vec::with_capacity(4 * (k - 1));
+                let mut gate_offsets = Vec::with_capacity(k - 1);
+                let mut acc = vec_b[0].value().copied();
+                for idx in 1..k {
+                    let prev = if idx == 1 { vec_b[0].clone() } else { Witness(acc) };
+                    acc = acc * vec_a[idx - 1].value() + vec_b[idx].value();
+                    cells.append(&mut vec![
+                        vec_b[idx].clone(),
+                        prev,
+                        vec_a[idx - 1].clone(),
+                        Witness(acc),
+                    ]);
+                    gate_offsets
+                        .push((4 * (idx as isize - 1), Some([F::one(), F::zero(), F::zero()])));
+                }
+                let assignments = self.assign_region(ctx, cells, gate_offsets, None)?;
+                for idx in 2..k {
+                    ctx.region.constrain_equal(
+                        assignments[4 * (idx - 2) + 3].cell(),
+                        assignments[4 * (idx - 1) + 1].cell(),
+                    )?;
+                }
+
+                let mut ret = Vec::with_capacity(k);
+                ret.push(assignments[1].clone());
+                ret.extend((1..k).map(|idx| assignments[4 * (idx - 1) + 3].clone()));
+                Ok(ret)
             }
-            GateStrategy::Vertical => {
+            GateStrategy::Vertical | GateStrategy::VerticalDot | GateStrategy::Horizontal => {
                 let mut ret = Vec::new();
                 for idx in 0..k {
                     if idx == 0 {
@@ -603,7 +921,7 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
 
                 Ok(assignments.last().unwrap().clone())
             }
-            GateStrategy::Vertical => {
+            GateStrategy::Vertical | GateStrategy::VerticalDot | GateStrategy::Horizontal => {
                 let mut a = Vec::with_capacity(k + 1);
                 let mut b = Vec::with_capacity(k + 1);
                 let mut prod_pair = Vec::with_capacity(k);
@@ -622,8 +940,7 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
                     a.push(Existing(&prod));
                     b.push(Constant(c));
                 }
-                let (_, _, out) = self.inner_product(ctx, &a, &b)?;
-                Ok(out)
+                self.inner_product(ctx, &a, &b)
             }
         }
     }
@@ -652,6 +969,33 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         Ok(assigned_cells.last().unwrap().clone())
     }
 
+    fn xor_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.byte_op(ctx, BitwiseOp::Xor, a, b)
+    }
+
+    fn and_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.byte_op(ctx, BitwiseOp::And, a, b)
+    }
+
+    fn or_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.byte_op(ctx, BitwiseOp::Or, a, b)
+    }
+
     // | 0 | a | b | out |
     fn and(
         &self,
@@ -688,7 +1032,7 @@ impl<F: FieldExt> GateInstructions<F> for FlexGateConfig<F> {
         match self.strategy {
             // | a - b | 1 | b | a |
             // | b | sel | a - b | out |
-            GateStrategy::Vertical => {
+            GateStrategy::Vertical | GateStrategy::VerticalDot | GateStrategy::Horizontal => {
                 let cells = vec![
                     QuantumCell::Witness(diff_val),
                     QuantumCell::Constant(F::from(1)),
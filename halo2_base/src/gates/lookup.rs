@@ -0,0 +1,194 @@
+use crate::{AssignedValue, Context, ContextId, QuantumCell};
+use ff::PrimeField;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+use std::collections::HashMap;
+
+/// A lookup table with user-defined contents: a list of rows, each a tuple of `width` field elements
+///
+/// Each lookup is laid out in a single row of one of the `advice` column groups, and the selector of the group
+/// enables the lookup of the tuple into the table. The table has an extra tag column which is 1 on the rows of the
+/// table and 0 on a padding row of zeros, so disabled rows always pass and enabled rows must match a row of
+/// the table. The table has `rows.len() + 1` rows, so the circuit needs `2^k` to exceed that plus the blinding rows.
+///
+/// The first `num_keys` entries of a tuple are its key: `query` looks up the rest of the tuple from the key
+/// out of circuit, which makes the table usable as a function, e.g. an S-box.
+///
+/// The rows used are tracked in `ctx.advice_rows[context_id]`, so the `Context` must be created with
+/// `(context_id, advice.len())` in `ContextParams::num_advice`.
+#[derive(Clone, Debug)]
+pub struct LookupTableConfig<F: FieldExt> {
+    // `advice[i]` is a group of `width` columns with its own lookup
+    pub advice: Vec<Vec<Column<Advice>>>,
+    pub q_lookup: Vec<Selector>,
+    // `table = [tag, columns of rows]`
+    pub table: Vec<TableColumn>,
+    pub rows: Vec<Vec<F>>,
+    pub num_keys: usize,
+    pub context_id: ContextId,
+    // the index in `rows` of each key, for `query`
+    index: HashMap<Vec<u8>, usize>,
+}
+
+fn key_bytes<F: FieldExt>(key: &[F]) -> Vec<u8> {
+    key.iter().flat_map(|x| x.to_repr().as_ref().to_vec()).collect()
+}
+
+impl<F: FieldExt> LookupTableConfig<F> {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        rows: Vec<Vec<F>>,
+        num_keys: usize,
+        num_advice: usize,
+        context_id: ContextId,
+    ) -> Self {
+        assert!(!rows.is_empty());
+        let width = rows[0].len();
+        assert!(rows.iter().all(|row| row.len() == width));
+        assert!(num_keys <= width);
+        assert!(num_advice > 0);
+
+        let advice = (0..num_advice)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        let a = meta.advice_column();
+                        meta.enable_equality(a);
+                        a
+                    })
+                    .collect()
+            })
+            .collect();
+        let q_lookup = (0..num_advice).map(|_| meta.complex_selector()).collect();
+        let table = (0..=width).map(|_| meta.lookup_table_column()).collect();
+        let mut index = HashMap::new();
+        for (i, row) in rows.iter().enumerate() {
+            index.entry(key_bytes(&row[..num_keys])).or_insert(i);
+        }
+
+        let config = Self { advice, q_lookup, table, rows, num_keys, context_id, index };
+        config.create_lookup(meta);
+        config
+    }
+
+    /// A table of single values, for `assert_in_set`
+    pub fn configure_set(
+        meta: &mut ConstraintSystem<F>,
+        set: Vec<F>,
+        num_advice: usize,
+        context_id: ContextId,
+    ) -> Self {
+        Self::configure(meta, set.into_iter().map(|x| vec![x]).collect(), 1, num_advice, context_id)
+    }
+
+    fn create_lookup(&self, meta: &mut ConstraintSystem<F>) {
+        for (columns, q) in self.advice.iter().zip(self.q_lookup.iter()) {
+            meta.lookup("dynamic lookup", |meta| {
+                let q = meta.query_selector(*q);
+                let mut lookup = vec![(q.clone(), self.table[0])];
+                for (column, table) in columns.iter().zip(self.table[1..].iter()) {
+                    let a = meta.query_advice(*column, Rotation::cur());
+                    lookup.push((q.clone() * a, *table));
+                }
+                lookup
+            });
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    pub fn load_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "dynamic lookup",
+            |mut table| {
+                // row 0 is the padding row of zeros with tag 0
+                let padding = vec![F::zero(); self.table.len()];
+                let rows = self.rows.iter().map(|row| [&[F::one()], &row[..]].concat());
+                for (offset, row) in std::iter::once(padding).chain(rows).enumerate() {
+                    for (col, val) in self.table.iter().zip(row) {
+                        table.assign_cell(
+                            || "dynamic lookup table",
+                            *col,
+                            offset,
+                            || Value::known(val),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Constrains `values` to be a row of the table and returns the assigned cells
+    pub fn lookup(
+        &self,
+        ctx: &mut Context<'_, F>,
+        values: &[QuantumCell<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert_eq!(values.len(), self.width());
+        let group = ctx.min_gate_index(&self.context_id);
+        let row = ctx.advice_rows_get(&self.context_id)[group];
+        let mut assigned = Vec::with_capacity(values.len());
+        for (value, column) in values.iter().zip(self.advice[group].iter()) {
+            assigned.push(ctx.assign_cell(
+                value.clone(),
+                *column,
+                &self.context_id,
+                group,
+                row,
+                0,
+            )?);
+        }
+        self.q_lookup[group].enable(&mut ctx.region, row)?;
+        ctx.advice_rows_get_mut(&self.context_id)[group] += 1;
+
+        #[cfg(feature = "display")]
+        {
+            let count = ctx.op_count.entry(format!("{} lookup", self.context_id)).or_insert(0);
+            *count += 1;
+        }
+        Ok(assigned)
+    }
+
+    /// Constrains `a` to be in a table of single values and returns its assigned cell
+    pub fn assert_in_set(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        Ok(self.lookup(ctx, &[a.clone()])?.pop().unwrap())
+    }
+
+    /// Returns the row of the table with key `keys`, after constraining it to be in the table.
+    /// The entries after the key are witnessed from the first row of `rows` with that key.
+    pub fn query(
+        &self,
+        ctx: &mut Context<'_, F>,
+        keys: &[QuantumCell<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert_eq!(keys.len(), self.num_keys);
+        let key_vals = keys.iter().fold(Value::known(vec![]), |acc, key| {
+            acc.zip(key.value()).map(|(mut acc, key)| {
+                acc.push(*key);
+                acc
+            })
+        });
+        let row = key_vals.map(|key| {
+            // a key not in the table gets a witness which fails the lookup
+            self.index.get(&key_bytes(&key)).map(|i| &self.rows[*i])
+        });
+        let mut values = keys.to_vec();
+        for j in self.num_keys..self.width() {
+            values.push(QuantumCell::Witness(
+                row.map(|row| row.map(|row| row[j]).unwrap_or_else(F::zero)),
+            ));
+        }
+        self.lookup(ctx, &values)
+    }
+}
@@ -1,12 +1,37 @@
 use self::{flex_gate::GateStrategy, range::RangeStrategy};
 use super::{
-    AssignedValue, Context, QuantumCell,
-    QuantumCell::{Constant, Existing},
+    memory::Memory,
+    utils::{biguint_to_fe, decompose_option, fe_to_biguint, modulus},
+    AssignedValue, Context, ContextId, QuantumCell,
+    QuantumCell::{Constant, Existing, Witness},
 };
-use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
-
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::Zero;
+
+pub mod bitwise;
+pub mod builder;
+pub mod bytes;
+pub mod circuit;
 pub mod flex_gate;
+pub mod lookup;
+pub mod params;
 pub mod range;
+pub mod rlc;
+pub mod stats;
+
+/// The cells of an inner product `out = <a, b>`
+///
+/// `a` (resp. `b`) has the cells where the entries of the first (resp. second) vector were assigned, in order, or is
+/// `None` if they were not all assigned, e.g. constants which the gate takes as selectors instead of cells. The cells
+/// are constrained to equal the inputs, so they can be used in place of `Witness` inputs.
+#[derive(Clone, Debug)]
+pub struct InnerProductResult<F: FieldExt> {
+    pub a: Option<Vec<AssignedValue<F>>>,
+    pub b: Option<Vec<AssignedValue<F>>>,
+    pub out: AssignedValue<F>,
+}
 
 pub trait GateInstructions<F: FieldExt> {
     fn strategy(&self) -> GateStrategy;
@@ -37,6 +62,28 @@ pub trait GateInstructions<F: FieldExt> {
         external_equality: Vec<(&AssignedValue<F>, usize)>,
     ) -> Result<Vec<AssignedValue<F>>, Error>;
 
+    /// Same as `assign_region_smart`, tagging the assigned rows with `tag`, see `Context::push_tag`
+    fn assign_region_smart_tagged(
+        &self,
+        ctx: &mut Context<'_, F>,
+        tag: &'static str,
+        inputs: Vec<QuantumCell<F>>,
+        gate_offsets: Vec<usize>,
+        equality_offsets: Vec<(usize, usize)>,
+        external_equality: Vec<(&AssignedValue<F>, usize)>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        ctx.push_tag(tag);
+        let assignments = self.assign_region_smart(
+            ctx,
+            inputs,
+            gate_offsets,
+            equality_offsets,
+            external_equality,
+        );
+        ctx.pop_tag();
+        assignments
+    }
+
     fn load_zero(&self, ctx: &mut Context<'_, F>) -> Result<AssignedValue<F>, Error>;
 
     fn add(
@@ -95,19 +142,88 @@ pub trait GateInstructions<F: FieldExt> {
         b: &QuantumCell<F>,
     ) -> Result<(), Error>;
 
+    /// Constrains `flag * a = 0`, i.e. `a = 0` if `flag` is nonzero
+    fn assert_zero_if(
+        &self,
+        ctx: &mut Context<'_, F>,
+        flag: &QuantumCell<F>,
+        a: &QuantumCell<F>,
+    ) -> Result<(), Error> {
+        // | 0 | flag | a | 0 |
+        self.assign_region_smart(
+            ctx,
+            vec![Constant(F::zero()), flag.clone(), a.clone(), Constant(F::zero())],
+            vec![0],
+            vec![],
+            vec![],
+        )?;
+        Ok(())
+    }
+
+    /// Constrains `flag * (a - b) = 0`, i.e. `a = b` if `flag` is nonzero
+    fn assert_equal_if(
+        &self,
+        ctx: &mut Context<'_, F>,
+        flag: &QuantumCell<F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<(), Error> {
+        let diff = self.sub(ctx, a, b)?;
+        self.assert_zero_if(ctx, flag, &Existing(&diff))
+    }
+
+    /// Constrains `a` to be one of `set` by constraining `prod_i (a - set[i]) = 0`, which costs O(set.len()).
+    /// For large sets, a `lookup::LookupTableConfig` built with `configure_set` is cheaper.
+    fn assert_in_set(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        set: &[QuantumCell<F>],
+    ) -> Result<(), Error> {
+        assert!(!set.is_empty());
+        let mut prod = self.sub(ctx, a, &set[0])?;
+        for x in set.iter().skip(1) {
+            let diff = self.sub(ctx, a, x)?;
+            prod = self.mul(ctx, &Existing(&prod), &Existing(&diff))?;
+        }
+        self.assert_is_const(ctx, &prod, F::zero());
+        Ok(())
+    }
+
     fn assert_is_const(&self, ctx: &mut Context<'_, F>, a: &AssignedValue<F>, constant: F) {
-        ctx.constants_to_assign.push((constant, Some(a.cell())));
+        ctx.assign_constant(constant, Some(a.cell()));
+    }
+
+    /// Constrains `a` to be 0 or 1
+    fn assert_bit(&self, ctx: &mut Context<'_, F>, a: &AssignedValue<F>) -> Result<(), Error> {
+        // 0 + a * a = a
+        self.assign_region_smart(
+            ctx,
+            vec![Constant(F::zero()), Existing(a), Existing(a), Existing(a)],
+            vec![0],
+            vec![],
+            vec![],
+        )?;
+        Ok(())
     }
 
+    /// Returns `<vec_a, vec_b>`
     fn inner_product(
         &self,
         ctx: &mut Context<'_, F>,
         vec_a: &Vec<QuantumCell<F>>,
         vec_b: &Vec<QuantumCell<F>>,
-    ) -> Result<
-        (Option<Vec<AssignedValue<F>>>, Option<Vec<AssignedValue<F>>>, AssignedValue<F>),
-        Error,
-    >;
+    ) -> Result<AssignedValue<F>, Error> {
+        Ok(self.inner_product_with_operands(ctx, vec_a, vec_b)?.out)
+    }
+
+    /// Same as `inner_product`, also returning the cells where `vec_a` and `vec_b` were assigned
+    fn inner_product_with_operands(
+        &self,
+        ctx: &mut Context<'_, F>,
+        vec_a: &Vec<QuantumCell<F>>,
+        vec_b: &Vec<QuantumCell<F>>,
+    ) -> Result<InnerProductResult<F>, Error>;
 
     // requires vec_b.len() == vec_a.len() + 1
     // returns
@@ -144,6 +260,30 @@ pub trait GateInstructions<F: FieldExt> {
         b: &QuantumCell<F>,
     ) -> Result<AssignedValue<F>, Error>;
 
+    /// Returns `a ^ b` and constrains `a, b` to be in [0, 2^8), using the byte-pair lookup tables
+    fn xor_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error>;
+
+    /// Returns `a & b` and constrains `a, b` to be in [0, 2^8), using the byte-pair lookup tables
+    fn and_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error>;
+
+    /// Returns `a | b` and constrains `a, b` to be in [0, 2^8), using the byte-pair lookup tables
+    fn or_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+    ) -> Result<AssignedValue<F>, Error>;
+
     fn not(&self, ctx: &mut Context<'_, F>, a: &QuantumCell<F>) -> Result<AssignedValue<F>, Error> {
         self.sub(ctx, &QuantumCell::Constant(F::from(1)), a)
     }
@@ -177,6 +317,31 @@ pub trait GateInstructions<F: FieldExt> {
         len: usize,
     ) -> Result<Vec<AssignedValue<F>>, Error>;
 
+    /// Returns `sum_i bits[i] * 2^i`, the number with little-endian bits `bits`, i.e. the inverse of
+    /// `RangeInstructions::num_to_bits`. If `check_bits`, each of `bits` is constrained to be 0 or 1 with
+    /// `assert_bit`; otherwise they are assumed to be bits already, e.g. from `num_to_bits`.
+    fn bits_to_num(
+        &self,
+        ctx: &mut Context<'_, F>,
+        bits: &[AssignedValue<F>],
+        check_bits: bool,
+    ) -> Result<AssignedValue<F>, Error> {
+        assert!(!bits.is_empty());
+        assert!(bits.len() <= F::NUM_BITS as usize);
+        if check_bits {
+            for bit in bits {
+                self.assert_bit(ctx, bit)?;
+            }
+        }
+        let mut pow = F::one();
+        let mut pows = Vec::with_capacity(bits.len());
+        for _ in 0..bits.len() {
+            pows.push(Constant(pow));
+            pow = pow + pow;
+        }
+        self.inner_product(ctx, &bits.iter().map(Existing).collect(), &pows)
+    }
+
     fn select_from_idx(
         &self,
         ctx: &mut Context<'_, F>,
@@ -184,13 +349,59 @@ pub trait GateInstructions<F: FieldExt> {
         idx: &QuantumCell<F>,
     ) -> Result<AssignedValue<F>, Error> {
         let ind = self.idx_to_indicator(ctx, idx, cells.len())?;
-        let (_, _, res) = self.inner_product(
+        let res = self.inner_product(
             ctx,
             cells,
             &ind.iter().map(|x| QuantumCell::Existing(&x)).collect(),
         )?;
         Ok(res)
     }
+
+    /// Returns `cells[idx]` where `bits` is the little endian bit decomposition of `idx`, using a tree of
+    /// `cells.len() - 1` selects instead of the indicator vector of `select_from_idx`.
+    /// Assumes `bits` are constrained to be bits. `cells` is padded with zeros to `2^bits.len()`, so
+    /// `idx >= cells.len()` returns 0 as in `select_from_idx`.
+    ///
+    /// For many accesses into the same `cells`, `load_rom` is cheaper.
+    fn select_from_idx_bits(
+        &self,
+        ctx: &mut Context<'_, F>,
+        cells: &Vec<QuantumCell<F>>,
+        bits: &Vec<QuantumCell<F>>,
+    ) -> Result<AssignedValue<F>, Error> {
+        assert!(!cells.is_empty());
+        assert!(cells.len() <= 1 << bits.len());
+        if bits.is_empty() {
+            return self.add(ctx, &cells[0], &Constant(F::zero()));
+        }
+        let zero = Constant(F::zero());
+        let mut layer = Vec::with_capacity((cells.len() + 1) / 2);
+        for pair in cells.chunks(2) {
+            layer.push(self.select(ctx, pair.get(1).unwrap_or(&zero), &pair[0], &bits[0])?);
+        }
+        for bit in bits.iter().skip(1) {
+            let mut next = Vec::with_capacity((layer.len() + 1) / 2);
+            for pair in layer.chunks(2) {
+                let right = pair.get(1).map_or(zero.clone(), Existing);
+                next.push(self.select(ctx, &right, &Existing(&pair[0]), bit)?);
+            }
+            layer = next;
+        }
+        Ok(layer.pop().unwrap())
+    }
+
+    /// Loads `values` into a read-only memory, for indexing into them many times with
+    /// `RangeInstructions::select_from_rom`. The reads are only constrained once `Memory::finalize` is called.
+    fn load_rom(
+        &self,
+        ctx: &mut Context<'_, F>,
+        values: &[AssignedValue<F>],
+    ) -> Result<Memory<F>, Error>
+    where
+        Self: Sized,
+    {
+        Memory::from_values(self, ctx, values)
+    }
 }
 
 pub trait RangeInstructions<F: FieldExt> {
@@ -230,6 +441,43 @@ pub trait RangeInstructions<F: FieldExt> {
         self.check_less_than(ctx, &Existing(&a), &Constant(F::from(b as u64)), range_bits)
     }
 
+    /// Constrains `a < 2^bits`, where the number of bits `bits` is a witness, e.g. the length of variable length data.
+    /// Also constrains `bits <= max_bits`.
+    ///
+    /// `2^bits` is selected from the constants `2^0, ..., 2^max_bits` with the indicator of `bits`, so this costs
+    /// `O(max_bits)` cells on top of `range_check(a, max_bits)`.
+    fn range_check_var(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        bits: &AssignedValue<F>,
+        max_bits: usize,
+    ) -> Result<(), Error> {
+        assert!(max_bits > 0 && max_bits < F::CAPACITY as usize);
+        let ind = self.idx_to_indicator_by_bits(ctx, bits, max_bits + 1)?;
+        let pow = self.gate().inner_product(
+            ctx,
+            &ind.iter().map(Existing).collect(),
+            &(0..=max_bits).map(|i| Constant(biguint_to_fe(&(BigUint::from(1u64) << i)))).collect(),
+        )?;
+        self.range_check(ctx, a, max_bits)?;
+        self.check_less_than(ctx, &Existing(a), &Existing(&pow), max_bits + 1)
+    }
+
+    // checks that a < b, does not require bit assumptions on a, b: both are range checked to `num_bits` bits
+    fn assert_less_than(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        self.range_check(ctx, a, num_bits)?;
+        self.range_check(ctx, b, num_bits)?;
+        self.check_less_than(ctx, &Existing(a), &Existing(b), num_bits)
+    }
+
+    // returns 1 if a < b and 0 otherwise, assuming a, b have at most `num_bits` bits
     fn is_less_than(
         &self,
         ctx: &mut Context<'_, F>,
@@ -238,6 +486,18 @@ pub trait RangeInstructions<F: FieldExt> {
         num_bits: usize,
     ) -> Result<AssignedValue<F>, Error>;
 
+    /// Returns `(a < b, a == b)`, assuming `a, b` have at most `num_bits` bits
+    ///
+    /// Both flags come from the one decomposition of `a - b` which `is_less_than` range checks, which is cheaper than
+    /// separate calls of `is_less_than` and `is_equal`.
+    fn compare(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+        num_bits: usize,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error>;
+
     fn is_less_than_safe(
         &self,
         ctx: &mut Context<'_, F>,
@@ -251,6 +511,53 @@ pub trait RangeInstructions<F: FieldExt> {
         self.is_less_than(ctx, &Existing(&a), &Constant(F::from(b as u64)), num_bits)
     }
 
+    /// Returns `(a / b, a % b)` for a constant divisor `b`, where `a` is an integer with at most `a_num_bits` bits
+    ///
+    /// The quotient is range checked to `a_num_bits` bits and the remainder to be less than `b`.
+    fn div_mod(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &BigUint,
+        a_num_bits: usize,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        assert!(!b.is_zero());
+        let b_num_bits = b.bits() as usize;
+        assert!(a_num_bits + b_num_bits < F::CAPACITY as usize);
+        let b_fe = biguint_to_fe::<F>(b);
+        let (quot, rem) = div_mod_witness(self.gate(), ctx, a, Value::known(b_fe))?;
+
+        self.range_check(ctx, &quot, a_num_bits)?;
+        self.range_check(ctx, &rem, b_num_bits)?;
+        self.check_less_than(ctx, &Existing(&rem), &Constant(b_fe), b_num_bits)?;
+        let prod = self.gate().mul_add(ctx, &Existing(&quot), &Constant(b_fe), &Existing(&rem))?;
+        self.gate().assert_equal(ctx, a, &Existing(&prod))?;
+        Ok((quot, rem))
+    }
+
+    /// Returns `(a / b, a % b)` for a variable divisor `b`, where `a` has at most `a_num_bits` bits
+    ///
+    /// Assumes `b` has at most `b_num_bits` bits. The remainder is constrained to be less than `b`, so `b = 0` is
+    /// unsatisfiable.
+    fn div_mod_var(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+        a_num_bits: usize,
+        b_num_bits: usize,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        assert!(a_num_bits + b_num_bits < F::CAPACITY as usize);
+        let (quot, rem) = div_mod_witness(self.gate(), ctx, a, b.value().copied())?;
+
+        self.range_check(ctx, &quot, a_num_bits)?;
+        self.range_check(ctx, &rem, b_num_bits)?;
+        self.check_less_than(ctx, &Existing(&rem), b, b_num_bits)?;
+        let prod = self.gate().mul_add(ctx, &Existing(&quot), b, &Existing(&rem))?;
+        self.gate().assert_equal(ctx, a, &Existing(&prod))?;
+        Ok((quot, rem))
+    }
+
     fn is_zero(
         &self,
         ctx: &mut Context<'_, F>,
@@ -270,6 +577,172 @@ pub trait RangeInstructions<F: FieldExt> {
         a: &AssignedValue<F>,
         range_bits: usize,
     ) -> Result<Vec<AssignedValue<F>>, Error>;
+
+    /// Returns the `F::NUM_BITS` little-endian bits of `a`, constrained to be the canonical representation, i.e. as an
+    /// integer the bits are less than the modulus. `num_to_bits(a, F::NUM_BITS)` alone also accepts the bits of
+    /// `a + modulus` when it fits in `F::NUM_BITS` bits.
+    fn num_to_bits_strict(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let num_bits = F::NUM_BITS as usize;
+        let bits = self.num_to_bits(ctx, a, num_bits)?;
+        let modulus = modulus::<F>();
+
+        // compare `bits` with the bits of the modulus from the most significant bit:
+        // `eq` is 1 if they are equal so far and `lt` is 1 if `bits` is already less
+        let gate = self.gate();
+        let mut eq = gate
+            .assign_region_smart(ctx, vec![Constant(F::one())], vec![], vec![], vec![])?
+            .pop()
+            .unwrap();
+        let mut lt = gate.load_zero(ctx)?;
+        for i in (0..num_bits).rev() {
+            let not_bit = gate.not(ctx, &Existing(&bits[i]))?;
+            if modulus.bit(i as u64) {
+                lt = gate.mul_add(ctx, &Existing(&eq), &Existing(&not_bit), &Existing(&lt))?;
+                eq = gate.mul(ctx, &Existing(&eq), &Existing(&bits[i]))?;
+            } else {
+                eq = gate.mul(ctx, &Existing(&eq), &Existing(&not_bit))?;
+            }
+        }
+        gate.assert_is_const(ctx, &lt, F::one());
+        Ok(bits)
+    }
+
+    /// Returns the `num_bytes` little-endian bytes of `a`, each range checked to be in [0, 2^8) with the lookup
+    /// table. Constrains `a < 2^{8 * num_bytes}`.
+    fn decompose_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        num_bytes: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert!(num_bytes > 0);
+        assert!(8 * num_bytes <= F::CAPACITY as usize);
+        let gate = self.gate();
+        let bytes = gate.assign_region_smart(
+            ctx,
+            decompose_option(&a.value().copied(), num_bytes, 8).into_iter().map(Witness).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        for byte in bytes.iter() {
+            self.range_check(ctx, byte, 8)?;
+        }
+        let pows = (0..num_bytes)
+            .map(|i| Constant(biguint_to_fe(&(BigUint::from(1u64) << (8 * i)))))
+            .collect();
+        let sum = gate.inner_product(ctx, &bytes.iter().map(Existing).collect(), &pows)?;
+        gate.assert_equal(ctx, &Existing(a), &Existing(&sum))?;
+        Ok(bytes)
+    }
+
+    /// Returns the indicator vector of `idx` of length `len`, i.e. `ind[i] = 1` if `i == idx` and 0 otherwise,
+    /// and constrains `idx < len`.
+    ///
+    /// `GateInstructions::idx_to_indicator` witnesses every entry and checks `ind[i] * (i - idx) = 0` for each `i`,
+    /// which costs two gates per entry and leaves `idx >= len` unconstrained. Here `idx` is decomposed into
+    /// `log2(len)` bits and the indicator is built from them with `bits_to_indicator`.
+    fn idx_to_indicator_by_bits(
+        &self,
+        ctx: &mut Context<'_, F>,
+        idx: &AssignedValue<F>,
+        len: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert!(len > 0);
+        let num_bits = std::cmp::max((usize::BITS - (len - 1).leading_zeros()) as usize, 1);
+        let bits = self.num_to_bits(ctx, idx, num_bits)?;
+        if len < 1 << num_bits {
+            self.check_less_than(ctx, &Existing(idx), &Constant(F::from(len as u64)), num_bits)?;
+        }
+        let mut ind = self.gate().bits_to_indicator(ctx, &bits.iter().map(Existing).collect())?;
+        ind.truncate(len);
+        Ok(ind)
+    }
+
+    /// Same as `idx_to_indicator_by_bits`, for large `len`
+    ///
+    /// `idx` is split with `div_mod` into a high and a low part of about `log2(len) / 2` bits each, which are range
+    /// checked with the lookup table, and `ind[i]` is the product of the entries of the indicators of the two parts.
+    /// This costs one `mul` per entry on top of the two indicators of length about `sqrt(len)`.
+    fn idx_to_indicator_by_lookup(
+        &self,
+        ctx: &mut Context<'_, F>,
+        idx: &AssignedValue<F>,
+        len: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert!(len > 0);
+        let num_bits = (usize::BITS - (len - 1).leading_zeros()) as usize;
+        if num_bits <= 2 {
+            return self.idx_to_indicator_by_bits(ctx, idx, len);
+        }
+        let low_bits = num_bits / 2;
+        let (high, low) =
+            self.div_mod(ctx, &Existing(idx), &(BigUint::from(1u64) << low_bits), num_bits)?;
+        // `high < ceil(len / 2^low_bits)`, so `idx < 2^num_bits` and the check of `idx < len` is sound
+        let high_ind =
+            self.idx_to_indicator_by_bits(ctx, &high, (len + (1 << low_bits) - 1) >> low_bits)?;
+        let low_ind = self.idx_to_indicator_by_bits(ctx, &low, 1 << low_bits)?;
+        if len % (1 << low_bits) != 0 {
+            self.check_less_than(ctx, &Existing(idx), &Constant(F::from(len as u64)), num_bits)?;
+        }
+        (0..len)
+            .map(|i| {
+                self.gate().mul(
+                    ctx,
+                    &Existing(&high_ind[i >> low_bits]),
+                    &Existing(&low_ind[i & ((1 << low_bits) - 1)]),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns `rom[idx]` for `rom` from `GateInstructions::load_rom`, in O(1) cells plus O(1) cells of
+    /// `Memory::finalize`. `idx` is range checked to the address bits of `rom`, and indices past the loaded values
+    /// return 0.
+    fn select_from_rom(
+        &self,
+        ctx: &mut Context<'_, F>,
+        rom: &mut Memory<F>,
+        idx: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error>
+    where
+        Self: Sized,
+    {
+        rom.read(self, ctx, idx)
+    }
+}
+
+// assigns the unconstrained quotient and remainder of `a` by `b` as integers
+fn div_mod_witness<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &QuantumCell<F>,
+    b: Value<F>,
+) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+    let quot_rem = a.value().zip(b).map(|(a, b)| {
+        let (a, b) = (fe_to_biguint(a), fe_to_biguint(&b));
+        if b.is_zero() {
+            (BigUint::zero(), a)
+        } else {
+            a.div_rem(&b)
+        }
+    });
+    let mut assigned = gate.assign_region_smart(
+        ctx,
+        vec![
+            Witness(quot_rem.as_ref().map(|(q, _)| biguint_to_fe(q))),
+            Witness(quot_rem.as_ref().map(|(_, r)| biguint_to_fe(r))),
+        ],
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    let rem = assigned.pop().unwrap();
+    Ok((assigned.pop().unwrap(), rem))
 }
 
 #[cfg(test)]
@@ -0,0 +1,141 @@
+// Automatic configuration of a `RangeConfig` from a dry run
+//
+// With the vertical gate, the number of cells a circuit assigns does not depend on the number of advice columns,
+// only how they are spread over the columns. So a single run of witness generation with any configuration that fits,
+// e.g. a `MockProver` with a large `k`, records the shape of the circuit: its advice cells, the cells copied to the
+// lookup advice columns, and its distinct constants. The parameters for any degree follow from the shape.
+// `CircuitShape::record` does the dry run in shape mode, see `Context::generate_witnesses`, so no prover and no
+// degree are needed. It uses two advice columns, since with one the lookups are enabled in place and not recorded.
+
+use super::range::{RangeConfig, RangeStrategy};
+use crate::{Context, ContextId, ContextParams};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::{ConstraintSystem, Error},
+};
+
+/// Rows at the end of every column which are reserved for blinding factors, with some margin
+pub const NUM_UNUSABLE_ROWS: usize = 10;
+
+/// The largest degree `CircuitParams::for_target` tries
+pub const MAX_DEGREE: u32 = 28;
+
+/// The cells used by a circuit, independent of its configuration
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitShape {
+    pub advice_cells: usize,
+    pub lookup_cells: usize,
+    pub fixed_cells: usize,
+}
+
+impl CircuitShape {
+    /// The shape of the circuit assigned in `ctx` under `context_id`, to be called after `RangeConfig::finalize`
+    /// with `num_constants` the number of constants it returned
+    pub fn from_context<F: FieldExt>(
+        ctx: &Context<'_, F>,
+        context_id: &ContextId,
+        num_constants: usize,
+    ) -> Self {
+        Self {
+            advice_cells: ctx.advice_rows_get(context_id).iter().sum(),
+            lookup_cells: ctx.cells_to_lookup.len(),
+            fixed_cells: num_constants,
+        }
+    }
+
+    /// Runs `f` in shape mode on a `RangeConfig` with the vertical gate and `lookup_bits`, and returns the shape of
+    /// the circuit it assigns, including the lookups and constants of `finalize`
+    pub fn record<F: FieldExt>(
+        lookup_bits: usize,
+        f: impl FnOnce(&RangeConfig<F>, &mut Context<'_, F>) -> Result<(), Error>,
+    ) -> Result<Self, Error> {
+        let mut meta = ConstraintSystem::<F>::default();
+        let config = RangeConfig::configure(
+            &mut meta,
+            RangeStrategy::Vertical,
+            &[2],
+            &[1],
+            1,
+            lookup_bits,
+            ContextId::new("default"),
+        );
+        let context_params = ContextParams { num_advice: vec![(ContextId::new("default"), 2)] };
+        Context::generate_witnesses(context_params, |ctx| {
+            f(&config, ctx)?;
+            Ok(config.finalize_with_stats(ctx)?.shape())
+        })
+    }
+}
+
+/// The parameters of a `RangeConfig` with the vertical gate and the degree of the circuit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitParams {
+    pub degree: u32,
+    pub num_advice: usize,
+    pub num_lookup_advice: usize,
+    pub num_fixed: usize,
+    pub lookup_bits: usize,
+}
+
+impl CircuitParams {
+    /// The fewest columns which fit `shape` in a circuit of `2^degree` rows
+    pub fn for_degree(shape: &CircuitShape, degree: u32, lookup_bits: usize) -> Self {
+        assert!(lookup_bits < degree as usize, "the lookup table must fit in the circuit");
+        let rows = (1usize << degree) - NUM_UNUSABLE_ROWS;
+        let columns = |cells: usize| (cells + rows - 1) / rows;
+        Self {
+            degree,
+            num_advice: std::cmp::max(columns(shape.advice_cells), 1),
+            num_lookup_advice: columns(shape.lookup_cells),
+            num_fixed: std::cmp::max(columns(shape.fixed_cells), 1),
+            lookup_bits,
+        }
+    }
+
+    /// The parameters with the minimal degree such that `shape` fits in at most `max_advice` advice columns
+    ///
+    /// Panics if no degree up to `MAX_DEGREE` is large enough.
+    pub fn for_target(shape: &CircuitShape, lookup_bits: usize, max_advice: usize) -> Self {
+        (lookup_bits as u32 + 1..=MAX_DEGREE)
+            .map(|degree| Self::for_degree(shape, degree, lookup_bits))
+            .find(|params| params.num_advice <= max_advice)
+            .expect("circuit does not fit in the maximum degree")
+    }
+
+    /// The parameters of `for_target` for the shape which `f` records in a dry run, see `CircuitShape::record`
+    pub fn auto_configure<F: FieldExt>(
+        lookup_bits: usize,
+        max_advice: usize,
+        f: impl FnOnce(&RangeConfig<F>, &mut Context<'_, F>) -> Result<(), Error>,
+    ) -> Result<Self, Error> {
+        let shape = CircuitShape::record(lookup_bits, f)?;
+        Ok(Self::for_target(&shape, lookup_bits, max_advice))
+    }
+
+    /// Loads parameters cached by `write`, or computes them with `f` and caches them at `path`
+    #[cfg(feature = "serde")]
+    pub fn load_or_else(
+        path: impl AsRef<std::path::Path>,
+        f: impl FnOnce() -> Self,
+    ) -> std::io::Result<Self> {
+        if let Ok(params) = Self::read(&path) {
+            return Ok(params);
+        }
+        let params = f();
+        params.write(path)?;
+        Ok(params)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn read(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let params_str = std::fs::read_to_string(path)?;
+        serde_json::from_str(&params_str).map_err(std::io::Error::from)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let params_str = serde_json::to_string(self).map_err(std::io::Error::from)?;
+        std::fs::write(path, params_str)
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     gates::{
         flex_gate::{FlexGateConfig, GateStrategy},
-        GateInstructions,
+        stats::CircuitStats,
+        GateInstructions, InnerProductResult,
     },
     utils::{biguint_to_fe, decompose_option, fe_to_biguint},
     AssignedValue,
@@ -17,16 +18,28 @@ use halo2_proofs::{
 };
 use num_bigint::BigUint;
 
-use super::{Context, RangeInstructions};
+use super::{Context, ContextId, RangeInstructions};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RangeStrategy {
     Vertical, // vanilla implementation with vertical basic gate(s)
     // CustomVerticalShort, // vertical basic gate(s) and vertical custom range gates of length 2,3
     PlonkPlus,
+    // vertical basic gate(s) together with the dedicated inner product gate, see `GateStrategy::VerticalDot`
+    VerticalDot,
+    // vertical basic gate(s) together with the wide inner product gate, see `GateStrategy::Horizontal`
+    Horizontal,
     // CustomHorizontal, // vertical basic gate and dedicated horizontal custom gate
 }
 
+/// An additional range lookup table of `lookup_bits` bits, with its own lookup advice columns in the first phase
+#[derive(Clone, Debug)]
+pub struct RangeTable {
+    pub lookup_bits: usize,
+    pub lookup: TableColumn,
+    pub lookup_advice: Vec<Column<Advice>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct RangeConfig<F: FieldExt> {
     // `lookup_advice` are special advice columns only used for lookups
@@ -41,12 +54,14 @@ pub struct RangeConfig<F: FieldExt> {
     pub q_lookup: Vec<Option<Selector>>,
     pub lookup: TableColumn,
     pub lookup_bits: usize,
+    // tables of other bit widths: `range_check` uses whichever table needs the fewest lookups
+    pub extra_tables: Vec<RangeTable>,
     // selector for custom range gate
     // `q_range[k][i]` stores the selector for a custom range gate of length `k`
     // pub q_range: HashMap<usize, Vec<Selector>>,
     pub gate: FlexGateConfig<F>,
     strategy: RangeStrategy,
-    pub context_id: String,
+    pub context_id: ContextId,
 }
 
 /*
@@ -80,7 +95,32 @@ impl<F: FieldExt> RangeConfig<F> {
         num_lookup_advice: &[usize],
         num_fixed: usize,
         lookup_bits: usize,
-        context_id: String,
+        context_id: ContextId,
+    ) -> Self {
+        Self::configure_with_extra_tables(
+            meta,
+            range_strategy,
+            num_advice,
+            num_lookup_advice,
+            num_fixed,
+            lookup_bits,
+            &[],
+            context_id,
+        )
+    }
+
+    /// Same as `configure`, with an additional range table for each `(lookup_bits, num_lookup_advice)` in
+    /// `extra_tables`. Cells checked against an additional table are copied to its `num_lookup_advice` columns
+    /// in the first phase. The circuit needs `k` larger than the bits of every table.
+    pub fn configure_with_extra_tables(
+        meta: &mut ConstraintSystem<F>,
+        range_strategy: RangeStrategy,
+        num_advice: &[usize],
+        num_lookup_advice: &[usize],
+        num_fixed: usize,
+        lookup_bits: usize,
+        extra_tables: &[(usize, usize)],
+        context_id: ContextId,
     ) -> Self {
         assert!(lookup_bits <= 28);
         let lookup = meta.lookup_table_column();
@@ -90,10 +130,12 @@ impl<F: FieldExt> RangeConfig<F> {
             match range_strategy {
                 RangeStrategy::Vertical => GateStrategy::Vertical,
                 RangeStrategy::PlonkPlus => GateStrategy::PlonkPlus,
+                RangeStrategy::VerticalDot => GateStrategy::VerticalDot,
+                RangeStrategy::Horizontal => GateStrategy::Horizontal,
             },
             num_advice,
             num_fixed,
-            context_id.clone(),
+            context_id,
         );
 
         let mut q_lookup = Vec::new();
@@ -115,11 +157,31 @@ impl<F: FieldExt> RangeConfig<F> {
                 }
             }
         }
+        let mut table_bits = vec![lookup_bits];
+        let extra_tables = extra_tables
+            .iter()
+            .map(|&(bits, num_columns)| {
+                assert!(bits > 0 && bits <= 28);
+                assert!(!table_bits.contains(&bits), "range tables should have distinct bits");
+                assert!(num_columns > 0);
+                table_bits.push(bits);
+                let lookup_advice = (0..num_columns)
+                    .map(|_| {
+                        let a = meta.advice_column();
+                        meta.enable_equality(a);
+                        a
+                    })
+                    .collect();
+                RangeTable { lookup_bits: bits, lookup: meta.lookup_table_column(), lookup_advice }
+            })
+            .collect();
+
         let config = Self {
             lookup_advice,
             q_lookup,
             lookup,
             lookup_bits,
+            extra_tables,
             gate,
             strategy: range_strategy,
             context_id,
@@ -154,42 +216,87 @@ impl<F: FieldExt> RangeConfig<F> {
                 vec![(a, self.lookup)]
             });
         }
+        for table in self.extra_tables.iter() {
+            for la in table.lookup_advice.iter() {
+                meta.lookup("extra table lookup", |meta| {
+                    let a = meta.query_advice(*la, Rotation::cur());
+                    vec![(a, table.lookup)]
+                });
+            }
+        }
     }
 
     pub fn load_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(
-            || format!("{} bit lookup", self.lookup_bits),
-            |mut table| {
-                for idx in 0..(1u32 << self.lookup_bits) {
-                    table.assign_cell(
-                        || "lookup table",
-                        self.lookup,
-                        idx as usize,
-                        || Value::known(F::from(idx as u64)),
-                    )?;
-                }
-                Ok(())
-            },
-        )?;
+        self.gate.load_byte_lookup_table(layouter)?;
+        let tables = std::iter::once((self.lookup_bits, self.lookup))
+            .chain(self.extra_tables.iter().map(|table| (table.lookup_bits, table.lookup)));
+        for (lookup_bits, lookup) in tables {
+            layouter.assign_table(
+                || format!("{} bit lookup", lookup_bits),
+                |mut table| {
+                    for idx in 0..(1u32 << lookup_bits) {
+                        table.assign_cell(
+                            || "lookup table",
+                            lookup,
+                            idx as usize,
+                            || Value::known(F::from(idx as u64)),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+        }
         Ok(())
     }
 
-    /// call this at the very end of synthesize!
+    /// call this at the very end of the region!
     /// returns (total number of constants assigned, total number of lookup cells assigned)
+    /// the rows used in the lookup advice columns of each phase are followed by those of each of `extra_tables`
     pub fn finalize(&self, ctx: &mut Context<'_, F>) -> Result<(usize, usize, Vec<usize>), Error> {
-        let (c_rows, c_count) = self.gate.finalize(ctx)?;
-        let lookup_rows = ctx.copy_and_lookup_cells(&[self.lookup_advice.clone()])?;
-        Ok((c_rows, c_count, lookup_rows))
+        let stats = self.finalize_with_stats(ctx)?;
+        Ok((stats.fixed_rows, stats.num_constants, stats.lookup_rows))
+    }
+
+    /// Same as `finalize`, returning the statistics of the circuit
+    pub fn finalize_with_stats(&self, ctx: &mut Context<'_, F>) -> Result<CircuitStats, Error> {
+        let stats = self.gate.finalize_with_stats(ctx)?;
+        let mut lookup_rows = ctx.copy_and_lookup_cells(&[self.lookup_advice.clone()])?;
+        for table in self.extra_tables.iter() {
+            lookup_rows
+                .push(ctx.copy_and_lookup_extra_cells(table.lookup_bits, &table.lookup_advice)?);
+        }
+        Ok(CircuitStats { lookup_cells: ctx.cells_to_lookup.len(), lookup_rows, ..stats })
+    }
+
+    /// the bits of the table with the fewest lookups to range check `range_bits` bits with limbs assigned in `phase`,
+    /// which is always the main table outside the first phase, as the additional tables are only in the first phase
+    fn table_bits_for(&self, range_bits: usize, phase: u8) -> usize {
+        if phase != 0 {
+            return self.lookup_bits;
+        }
+        let num_lookups = |bits: usize| {
+            let rem_bits = range_bits % bits;
+            (range_bits + bits - 1) / bits + (rem_bits > 1) as usize
+        };
+        std::iter::once(self.lookup_bits)
+            .chain(self.extra_tables.iter().map(|table| table.lookup_bits))
+            .min_by_key(|bits| num_lookups(*bits))
+            .unwrap()
     }
 
-    /// assuming this is called when ctx.region is not in shape mode
     /// `offset` is the offset of the cell in `ctx.region`
     /// `offset` is only used if there is a single advice column
     fn enable_lookup(
         &self,
         ctx: &mut Context<'_, F>,
         acell: AssignedValue<F>,
+        table_bits: usize,
     ) -> Result<(), Error> {
+        if table_bits != self.lookup_bits {
+            assert_eq!(acell.phase(), 0, "additional range tables are only in the first phase");
+            ctx.extra_cells_to_lookup.entry(table_bits).or_insert_with(Vec::new).push(acell);
+            return Ok(());
+        }
         let phase = acell.phase() as usize;
         if let Some(q) = &self.q_lookup[phase] {
             q.enable(&mut ctx.region, acell.row())?;
@@ -199,25 +306,27 @@ impl<F: FieldExt> RangeConfig<F> {
         Ok(())
     }
 
-    // returns the limbs
+    // returns the limbs of `table_bits` bits, checked against the table with `table_bits` bits
     fn range_check_simple(
         &self,
         ctx: &mut Context<'_, F>,
         a: &AssignedValue<F>,
         range_bits: usize,
+        table_bits: usize,
     ) -> Result<Vec<AssignedValue<F>>, Error> {
-        let k = (range_bits + self.lookup_bits - 1) / self.lookup_bits;
+        let k = (range_bits + table_bits - 1) / table_bits;
         // println!("range check {} bits {} len", range_bits, k);
-        let rem_bits = range_bits % self.lookup_bits;
+        let rem_bits = range_bits % table_bits;
 
-        let limbs = decompose_option(&a.value().map(|x| *x), k, self.lookup_bits);
-        let (limbs_assigned, _, acc) = self.gate.inner_product(
-            ctx,
-            &limbs.into_iter().map(|limb| Witness(limb)).collect(),
-            &(0..k)
-                .map(|i| Constant(biguint_to_fe(&(BigUint::from(1u64) << (i * self.lookup_bits)))))
-                .collect(),
-        )?;
+        let limbs = decompose_option(&a.value().map(|x| *x), k, table_bits);
+        let InnerProductResult { a: limbs_assigned, out: acc, .. } =
+            self.gate.inner_product_with_operands(
+                ctx,
+                &limbs.into_iter().map(|limb| Witness(limb)).collect(),
+                &(0..k)
+                    .map(|i| Constant(biguint_to_fe(&(BigUint::from(1u64) << (i * table_bits)))))
+                    .collect(),
+            )?;
         let limbs_assigned = limbs_assigned.unwrap();
 
         // the inner product above must equal `a`
@@ -225,7 +334,7 @@ impl<F: FieldExt> RangeConfig<F> {
 
         // range check all the limbs
         for i in 0..k {
-            self.enable_lookup(ctx, limbs_assigned[i].clone())?;
+            self.enable_lookup(ctx, limbs_assigned[i].clone(), table_bits)?;
         }
 
         // additional constraints for the last limb if rem_bits != 0
@@ -246,7 +355,7 @@ impl<F: FieldExt> RangeConfig<F> {
                 vec![],
             )?;
         } else if rem_bits > 1 {
-            let mult_val = biguint_to_fe(&(BigUint::from(1u64) << (self.lookup_bits - rem_bits)));
+            let mult_val = biguint_to_fe(&(BigUint::from(1u64) << (table_bits - rem_bits)));
             let assignments = self.gate.assign_region(
                 ctx,
                 vec![
@@ -258,7 +367,7 @@ impl<F: FieldExt> RangeConfig<F> {
                 vec![(0, None)],
                 None,
             )?;
-            self.enable_lookup(ctx, assignments.last().unwrap().clone())?;
+            self.enable_lookup(ctx, assignments.last().unwrap().clone(), table_bits)?;
         }
 
         Ok(limbs_assigned)
@@ -292,6 +401,63 @@ impl<F: FieldExt> RangeConfig<F> {
         self.range_check(ctx, &assignments[1], limb_bits - 1)?;
         Ok(assignments[0].clone())
     }
+
+    // assigns `a - b + 2^padded_bits`, where `padded_bits` is `num_bits` rounded up to a multiple of `lookup_bits`,
+    // and returns it with its `k + 1` limbs of `lookup_bits` bits: the top limb is 0 iff `a < b`
+    fn shifted_difference_limbs(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+        num_bits: usize,
+    ) -> Result<(AssignedValue<F>, Vec<AssignedValue<F>>), Error> {
+        // TODO: optimize this for PlonkPlus strategy
+        let k = (num_bits + self.lookup_bits - 1) / self.lookup_bits;
+        let padded_bits = k * self.lookup_bits;
+        let pow_padded = biguint_to_fe::<F>(&(BigUint::from(1u64) << padded_bits));
+
+        let shifted_val = a.value().zip(b.value()).map(|(&av, &bv)| av + pow_padded - bv);
+        let shifted_cell = match self.strategy {
+            RangeStrategy::Vertical | RangeStrategy::VerticalDot | RangeStrategy::Horizontal => {
+                let assignments = self.gate.assign_region_smart(
+                    ctx,
+                    vec![
+                        Witness(shifted_val),
+                        b.clone(),
+                        Constant(F::one()),
+                        Witness(a.value().map(|&av| av + pow_padded)),
+                        Constant(-pow_padded),
+                        Constant(F::one()),
+                        a.clone(),
+                    ],
+                    vec![0, 3],
+                    vec![],
+                    vec![],
+                )?;
+                assignments[0].clone()
+            }
+            RangeStrategy::PlonkPlus => {
+                let assignments = self.gate.assign_region(
+                    ctx,
+                    vec![a.clone(), Constant(pow_padded), b.clone(), Witness(shifted_val)],
+                    vec![(0, Some([F::zero(), F::one(), -F::one()]))],
+                    None,
+                )?;
+                assignments.last().unwrap().clone()
+            }
+        };
+
+        // check whether a - b + 2^padded_bits < 2^padded_bits ?
+        // since assuming a, b < 2^padded_bits we are guaranteed a - b + 2^padded_bits < 2^{padded_bits + 1}
+        // the limbs must have `lookup_bits` bits, so this uses the main table
+        let limbs = self.range_check_simple(
+            ctx,
+            &shifted_cell,
+            padded_bits + self.lookup_bits,
+            self.lookup_bits,
+        )?;
+        Ok((shifted_cell, limbs))
+    }
 }
 
 impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
@@ -308,7 +474,7 @@ impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
         self.lookup_bits
     }
 
-    // returns the limbs
+    // returns the limbs, which have the bits of whichever table needs the fewest lookups
     fn range_check(
         &self,
         ctx: &mut Context<'_, F>,
@@ -316,19 +482,18 @@ impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
         range_bits: usize,
     ) -> Result<Vec<AssignedValue<F>>, Error> {
         assert_ne!(range_bits, 0);
+        let table_bits = self.table_bits_for(range_bits, ctx.current_phase());
         #[cfg(feature = "display")]
         {
-            let key = format!(
-                "range check length {}",
-                (range_bits + self.lookup_bits - 1) / self.lookup_bits
-            );
+            let key = format!("range check length {}", (range_bits + table_bits - 1) / table_bits);
             let count = ctx.op_count.entry(key).or_insert(0);
             *count += 1;
         }
         match self.strategy {
-            RangeStrategy::Vertical | RangeStrategy::PlonkPlus => {
-                self.range_check_simple(ctx, a, range_bits)
-            }
+            RangeStrategy::Vertical
+            | RangeStrategy::PlonkPlus
+            | RangeStrategy::VerticalDot
+            | RangeStrategy::Horizontal => self.range_check_simple(ctx, a, range_bits, table_bits),
         }
     }
 
@@ -342,7 +507,7 @@ impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
     ) -> Result<(), Error> {
         let pow_of_two = biguint_to_fe::<F>(&(BigUint::from(1u64) << num_bits));
         let check_cell = match self.strategy {
-            RangeStrategy::Vertical => {
+            RangeStrategy::Vertical | RangeStrategy::VerticalDot | RangeStrategy::Horizontal => {
                 // | a + 2^(num_bits) - b | b | 1 | a + 2^(num_bits) | - 2^(num_bits) | 1 | a |
                 let cells = vec![
                     Witness(Value::known(pow_of_two) + a.value() - b.value()),
@@ -388,46 +553,25 @@ impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
         b: &QuantumCell<F>,
         num_bits: usize,
     ) -> Result<AssignedValue<F>, Error> {
-        // TODO: optimize this for PlonkPlus strategy
-        let k = (num_bits + self.lookup_bits - 1) / self.lookup_bits;
-        let padded_bits = k * self.lookup_bits;
-        let pow_padded = biguint_to_fe::<F>(&(BigUint::from(1u64) << padded_bits));
-
-        let shifted_val = a.value().zip(b.value()).map(|(&av, &bv)| av + pow_padded - bv);
-        let shifted_cell = match self.strategy {
-            RangeStrategy::Vertical => {
-                let assignments = self.gate.assign_region_smart(
-                    ctx,
-                    vec![
-                        Witness(shifted_val),
-                        b.clone(),
-                        Constant(F::one()),
-                        Witness(a.value().map(|&av| av + pow_padded)),
-                        Constant(-pow_padded),
-                        Constant(F::one()),
-                        a.clone(),
-                    ],
-                    vec![0, 3],
-                    vec![],
-                    vec![],
-                )?;
-                assignments[0].clone()
-            }
-            RangeStrategy::PlonkPlus => {
-                let assignments = self.gate.assign_region(
-                    ctx,
-                    vec![a.clone(), Constant(pow_padded), b.clone(), Witness(shifted_val)],
-                    vec![(0, Some([F::zero(), F::one(), -F::one()]))],
-                    None,
-                )?;
-                assignments.last().unwrap().clone()
-            }
-        };
+        let (_, limbs) = self.shifted_difference_limbs(ctx, a, b, num_bits)?;
+        self.is_zero(ctx, limbs.last().unwrap())
+    }
 
-        // check whether a - b + 2^padded_bits < 2^padded_bits ?
-        // since assuming a, b < 2^padded_bits we are guaranteed a - b + 2^padded_bits < 2^{padded_bits + 1}
-        let limbs = self.range_check(ctx, &shifted_cell, padded_bits + self.lookup_bits)?;
-        self.is_zero(ctx, &limbs[k])
+    fn compare(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &QuantumCell<F>,
+        b: &QuantumCell<F>,
+        num_bits: usize,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        let (shifted_cell, limbs) = self.shifted_difference_limbs(ctx, a, b, num_bits)?;
+        let lt = self.is_zero(ctx, limbs.last().unwrap())?;
+        // a == b iff a - b + 2^padded_bits == 2^padded_bits
+        let padded_bits = (limbs.len() - 1) * self.lookup_bits;
+        let pow_padded = biguint_to_fe::<F>(&(BigUint::from(1u64) << padded_bits));
+        let diff = self.gate.add(ctx, &Existing(&shifted_cell), &Constant(-pow_padded))?;
+        let eq = self.is_zero(ctx, &diff)?;
+        Ok((lt, eq))
     }
 
     // | out | a | inv | 1 | 0 | a | out | 0
@@ -482,7 +626,7 @@ impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
     ) -> Result<Vec<AssignedValue<F>>, Error> {
         let bits = decompose_option(&a.value().copied(), range_bits, 1usize);
         let bit_cells = match self.strategy {
-            RangeStrategy::Vertical => {
+            RangeStrategy::Vertical | RangeStrategy::VerticalDot | RangeStrategy::Horizontal => {
                 let mut enable_gates = Vec::new();
                 let mut cells = Vec::with_capacity(3 * range_bits - 2);
                 let mut running_sum = bits[0];
@@ -516,13 +660,14 @@ impl<F: FieldExt> RangeInstructions<F> for RangeConfig<F> {
                 assigned_bits
             }
             RangeStrategy::PlonkPlus => {
-                let (bit_cells, _, acc) = self.gate.inner_product(
-                    ctx,
-                    &bits.iter().map(|x| Witness(*x)).collect(),
-                    &(0..range_bits)
-                        .map(|i| Constant(biguint_to_fe(&(BigUint::from(1u64) << i))))
-                        .collect(),
-                )?;
+                let InnerProductResult { a: bit_cells, out: acc, .. } =
+                    self.gate.inner_product_with_operands(
+                        ctx,
+                        &bits.iter().map(|x| Witness(*x)).collect(),
+                        &(0..range_bits)
+                            .map(|i| Constant(biguint_to_fe(&(BigUint::from(1u64) << i))))
+                            .collect(),
+                    )?;
                 ctx.region.constrain_equal(a.cell(), acc.cell())?;
                 bit_cells.unwrap()
             }
@@ -0,0 +1,136 @@
+use super::{GateInstructions, RangeInstructions};
+use crate::{
+    AssignedValue, Context, ContextId, QuantumCell,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{
+        Advice, Challenge, Column, ConstraintSystem, Error, FirstPhase, SecondPhase, Selector,
+    },
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+/// A challenge `gamma` of the first phase, loaded into a second phase advice cell for random linear combinations
+///
+/// The cell is constrained by `q_challenge * (a - gamma) = 0`, after which it can be used as `Existing(&gamma)`
+/// in gates of a `FlexGateConfig` with second phase columns, i.e. configured with `num_advice = [_, n]`, `n > 0`.
+/// Second phase cells can only depend on first phase cells, so the caller must call `ctx.next_phase()` once all
+/// first phase cells are assigned.
+///
+/// The value of the challenge is only available from the `Layouter`: it must be inserted into
+/// `ctx.challenge[context_id]` with `get_challenge` before `load_challenge`. The `Context` must be created with
+/// `(context_id, 1)` in `ContextParams::num_advice`.
+#[derive(Clone, Debug)]
+pub struct RlcConfig<F: FieldExt> {
+    pub gamma: Challenge,
+    pub advice: Column<Advice>,
+    pub q_challenge: Selector,
+    pub context_id: ContextId,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> RlcConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, context_id: ContextId) -> Self {
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let advice = meta.advice_column_in(SecondPhase);
+        meta.enable_equality(advice);
+        let q_challenge = meta.selector();
+
+        meta.create_gate("rlc challenge", |meta| {
+            let q = meta.query_selector(q_challenge);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let gamma = meta.query_challenge(gamma);
+            vec![q * (a - gamma)]
+        });
+
+        Self { gamma, advice, q_challenge, context_id, _marker: PhantomData }
+    }
+
+    /// The value of the challenge, unknown until the first phase is committed
+    pub fn get_challenge(&self, layouter: &impl Layouter<F>) -> Value<F> {
+        layouter.get_challenge(self.gamma)
+    }
+
+    /// Assigns `ctx.challenge[context_id]` and constrains it to equal the challenge
+    pub fn load_challenge(&self, ctx: &mut Context<'_, F>) -> Result<AssignedValue<F>, Error> {
+        assert_eq!(ctx.current_phase(), 1, "the challenge is only usable in the second phase");
+        let gamma = *ctx.challenge_get(&self.context_id);
+        let row = ctx.advice_rows_get(&self.context_id)[0];
+        let assigned = ctx.assign_cell(Witness(gamma), self.advice, &self.context_id, 0, row, 1)?;
+        self.q_challenge.enable(&mut ctx.region, row)?;
+        ctx.advice_rows_get_mut(&self.context_id)[0] += 1;
+        Ok(assigned)
+    }
+}
+
+/// Returns `sum_i values[i] * gamma^(n - 1 - i)` for `n = values.len()`, assigned in the current phase
+pub fn rlc<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    values: &[QuantumCell<F>],
+    gamma: &AssignedValue<F>,
+) -> Result<AssignedValue<F>, Error> {
+    Ok(rlc_running(gate, ctx, values, gamma)?.pop().unwrap())
+}
+
+// returns the rlc of each prefix `values[..i]` for `i = 0..=values.len()`
+fn rlc_running<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    values: &[QuantumCell<F>],
+    gamma: &AssignedValue<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut running = Vec::with_capacity(values.len() + 1);
+    running.push(gate.load_zero(ctx)?);
+    for value in values.iter() {
+        let prev = running.last().unwrap();
+        let next = gate.mul_add(ctx, &Existing(prev), &Existing(gamma), value)?;
+        running.push(next);
+    }
+    Ok(running)
+}
+
+/// Returns the rlc of the first `len` entries of `values`, for a witness `len`
+///
+/// Constrains `len <= values.len()`.
+pub fn rlc_var<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    values: &[QuantumCell<F>],
+    len: &AssignedValue<F>,
+    gamma: &AssignedValue<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let gate = range.gate();
+    let running = rlc_running(gate, ctx, values, gamma)?;
+    let indicator = range.idx_to_indicator_by_bits(ctx, len, running.len())?;
+    let out = gate.inner_product(
+        ctx,
+        &running.iter().map(Existing).collect(),
+        &indicator.iter().map(Existing).collect(),
+    )?;
+    Ok(out)
+}
+
+/// Returns `gamma^n`, e.g. to concatenate rlcs: `rlc(a || b) = rlc(a) * gamma^len(b) + rlc(b)`
+pub fn gamma_pow<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    gamma: &AssignedValue<F>,
+    n: usize,
+) -> Result<AssignedValue<F>, Error> {
+    if n == 0 {
+        return gate.add(ctx, &Constant(F::one()), &Constant(F::zero()));
+    }
+    // square and multiply, from the most significant bit of `n`
+    let mut out = gamma.clone();
+    for i in (0..usize::BITS - 1 - n.leading_zeros()).rev() {
+        out = gate.mul(ctx, &Existing(&out), &Existing(&out))?;
+        if (n >> i) & 1 == 1 {
+            out = gate.mul(ctx, &Existing(&out), &Existing(gamma))?;
+        }
+    }
+    Ok(out)
+}
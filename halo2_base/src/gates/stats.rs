@@ -0,0 +1,58 @@
+use super::params::CircuitShape;
+use crate::{Context, ContextId};
+use halo2_proofs::arithmetic::FieldExt;
+use std::collections::BTreeMap;
+
+/// Statistics of a synthesized circuit, returned by `finalize_with_stats` of `FlexGateConfig` and `RangeConfig`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitStats {
+    // rows used by each advice column of the chip, as in `ctx.advice_rows[context_id]`
+    pub advice_rows: Vec<usize>,
+    // cells copied to the lookup advice columns of the range table
+    pub lookup_cells: usize,
+    // rows used by the lookup advice columns of each phase, followed by those of each additional range table
+    pub lookup_rows: Vec<usize>,
+    // max rows used by a fixed column
+    pub fixed_rows: usize,
+    // number of distinct constants assigned to the fixed columns
+    pub num_constants: usize,
+    // `ctx.op_count`, which is only collected with the `display` feature
+    pub op_count: BTreeMap<String, usize>,
+}
+
+impl CircuitStats {
+    /// The statistics of the advice cells and op counts of `ctx` under `context_id`, without any lookups or constants
+    pub fn from_context<F: FieldExt>(ctx: &Context<'_, F>, context_id: &ContextId) -> Self {
+        #[cfg(feature = "display")]
+        let op_count = ctx.op_count.iter().map(|(op, count)| (op.clone(), *count)).collect();
+        #[cfg(not(feature = "display"))]
+        let op_count = BTreeMap::new();
+        Self {
+            advice_rows: ctx.advice_rows_get(context_id).clone(),
+            op_count,
+            ..Default::default()
+        }
+    }
+
+    pub fn total_advice_cells(&self) -> usize {
+        self.advice_rows.iter().sum()
+    }
+
+    pub fn max_advice_rows(&self) -> usize {
+        self.advice_rows.iter().max().copied().unwrap_or(0)
+    }
+
+    pub fn shape(&self) -> CircuitShape {
+        CircuitShape {
+            advice_cells: self.total_advice_cells(),
+            lookup_cells: self.lookup_cells,
+            fixed_cells: self.num_constants,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
@@ -1,10 +1,17 @@
 use super::{
+    bitwise::{self, BitwiseOp},
+    builder::{parallelize, VirtualCell, VirtualQuantumCell},
+    bytes,
+    circuit::RangeCircuitBuilder,
     flex_gate::{FlexGateConfig, GateStrategy},
-    range, GateInstructions, RangeInstructions,
+    lookup,
+    params::{CircuitParams, CircuitShape},
+    range, rlc, GateInstructions, RangeInstructions,
 };
 use crate::{
-    Context, ContextParams,
-    QuantumCell::{Constant, Existing, Witness},
+    utils::{biguint_to_fe, fe_to_biguint, value_to_option},
+    AssignedValue, Context, ContextId, ContextParams,
+    QuantumCell::{Constant, Existing, ExistingOwned, Witness},
 };
 use halo2_proofs::{
     arithmetic::FieldExt, circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*,
@@ -33,7 +40,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
             GateStrategy::PlonkPlus,
             &[NUM_ADVICE],
             1,
-            "default".to_string(),
+            ContextId::new("default"),
         )
     }
 
@@ -55,7 +62,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
 
                 let mut aux = Context::new(
                     region,
-                    ContextParams { num_advice: vec![("default".to_string(), NUM_ADVICE)] },
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
                 );
                 let ctx = &mut aux;
 
@@ -85,6 +92,31 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
                     config.mul(ctx, &Existing(&c_cell), &Existing(&b_cell))?;
                 }
 
+                // test assert_equal_if, assert_zero_if
+                {
+                    config.assert_equal_if(
+                        ctx,
+                        &Constant(F::zero()),
+                        &Existing(&a_cell),
+                        &Existing(&b_cell),
+                    )?;
+                    let ab = config.mul(ctx, &Existing(&a_cell), &Existing(&b_cell))?;
+                    config.assert_equal_if(
+                        ctx,
+                        &Constant(F::one()),
+                        &Existing(&ab),
+                        &Existing(&c_cell),
+                    )?;
+                    config.assert_zero_if(ctx, &Existing(&ab), &Constant(F::zero()))?;
+                }
+
+                // test ExistingOwned
+                {
+                    let ab = config.mul(ctx, &Existing(&a_cell), &Existing(&b_cell))?;
+                    let out = config.sub(ctx, &ExistingOwned(ab), &Existing(&c_cell))?;
+                    config.assert_is_const(ctx, &out, F::zero());
+                }
+
                 // test idx_to_indicator
                 {
                     config.idx_to_indicator(ctx, &Constant(F::from(3)), 4)?;
@@ -92,7 +124,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
 
                 println!(
                     "maximum rows used by an advice column: {}",
-                    ctx.advice_rows["default"].iter().max().or(Some(&0)).unwrap(),
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().or(Some(&0)).unwrap(),
                 );
                 let (const_rows, _) = config.finalize(ctx)?;
                 println!("maximum rows used by a fixed column: {}", const_rows);
@@ -160,7 +192,7 @@ impl<F: FieldExt> Circuit<F> for RangeTestCircuit<F> {
             &[1],
             1,
             3,
-            "default".to_string(),
+            ContextId::new("default"),
         )
     }
 
@@ -180,7 +212,7 @@ impl<F: FieldExt> Circuit<F> for RangeTestCircuit<F> {
             |region| {
                 let mut aux = Context::new(
                     region,
-                    ContextParams { num_advice: vec![("default".to_string(), NUM_ADVICE)] },
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
                 );
                 if using_simple_floor_planner {
                     first_pass = !first_pass;
@@ -209,7 +241,7 @@ impl<F: FieldExt> Circuit<F> for RangeTestCircuit<F> {
 
                 let mut aux = Context::new(
                     region,
-                    ContextParams { num_advice: vec![("default".to_string(), NUM_ADVICE)] },
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
                 );
                 if using_simple_floor_planner {
                     first_pass = !first_pass;
@@ -222,6 +254,9 @@ impl<F: FieldExt> Circuit<F> for RangeTestCircuit<F> {
                 {
                     config.check_less_than(ctx, &Existing(&a), &Existing(&b), self.lt_bits)?;
                 }
+                {
+                    config.assert_less_than(ctx, &a, &b, self.lt_bits)?;
+                }
                 {
                     config.is_less_than(ctx, &Existing(&a), &Existing(&b), self.lt_bits)?;
                 }
@@ -234,10 +269,31 @@ impl<F: FieldExt> Circuit<F> for RangeTestCircuit<F> {
                 {
                     config.is_zero(ctx, &a)?;
                 }
+                {
+                    let divisor = num_bigint::BigUint::from(7u64);
+                    let (q, r) = config.div_mod(ctx, &Existing(&a), &divisor, self.range_bits)?;
+                    assert_eq!(
+                        value_to_option(q.value().zip(r.value()).map(|(q, r)| (*q, *r))),
+                        value_to_option(self.a.map(|a| div_rem_fe(&a, &F::from(7))))
+                    );
+                }
+                {
+                    let (q, r) = config.div_mod_var(
+                        ctx,
+                        &Existing(&b),
+                        &Existing(&a),
+                        self.range_bits,
+                        self.range_bits,
+                    )?;
+                    assert_eq!(
+                        value_to_option(q.value().zip(r.value()).map(|(q, r)| (*q, *r))),
+                        value_to_option(self.b.zip(self.a).map(|(b, a)| div_rem_fe(&b, &a)))
+                    );
+                }
 
                 println!(
                     "maximum rows used by an advice column: {}",
-                    ctx.advice_rows["default"].iter().max().unwrap()
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
                 );
 
                 let (const_rows, _, _) = config.finalize(ctx)?;
@@ -249,6 +305,12 @@ impl<F: FieldExt> Circuit<F> for RangeTestCircuit<F> {
     }
 }
 
+// the integer quotient and remainder of `a` by `b`
+fn div_rem_fe<F: FieldExt>(a: &F, b: &F) -> (F, F) {
+    let (a, b) = (fe_to_biguint(a), fe_to_biguint(b));
+    (biguint_to_fe(&(&a / &b)), biguint_to_fe(&(&a % &b)))
+}
+
 #[test]
 fn test_range() {
     let k = 11;
@@ -264,6 +326,96 @@ fn test_range() {
     //assert_eq!(prover.verify(), Ok(()));
 }
 
+// whether dividing `a` by `b` with `div_mod_var` if `var`, or else `div_mod` with `b` as a constant,
+// is satisfied for operands of at most 8 bits
+fn div_mod_verifies(a: u64, b: u64, var: bool) -> bool {
+    let params = CircuitParams {
+        degree: 10,
+        num_advice: 2,
+        num_lookup_advice: 1,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    let circuit = RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        move |config: &range::RangeConfig<Fr>, ctx: &mut Context<'_, Fr>| {
+            let cells = config.gate().assign_region_smart(
+                ctx,
+                vec![Witness(Value::known(Fr::from(a))), Witness(Value::known(Fr::from(b)))],
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            if var {
+                config.div_mod_var(ctx, &Existing(&cells[0]), &Existing(&cells[1]), 8, 8)?;
+            } else {
+                config.div_mod(ctx, &Existing(&cells[0]), &num_bigint::BigUint::from(b), 8)?;
+            }
+            Ok(())
+        },
+    );
+    MockProver::run(10, &circuit, vec![]).unwrap().verify().is_ok()
+}
+
+#[test]
+fn test_div_mod_unsatisfiable() {
+    assert!(div_mod_verifies(200, 3, false));
+    assert!(div_mod_verifies(200, 3, true));
+    // the remainder must be less than the divisor, which is impossible for 0
+    assert!(!div_mod_verifies(200, 0, true));
+    // the quotient of `a` above `a_num_bits` bits does not fit in `a_num_bits` bits
+    assert!(!div_mod_verifies(1 << 12, 3, false));
+    assert!(!div_mod_verifies(1 << 12, 3, true));
+}
+
+#[test]
+fn test_circuit_params() {
+    let shape = CircuitShape { advice_cells: 10_000, lookup_cells: 3_000, fixed_cells: 40 };
+    // 2^11 - NUM_UNUSABLE_ROWS = 2038 rows
+    let params = CircuitParams::for_degree(&shape, 11, 10);
+    assert_eq!(
+        params,
+        CircuitParams {
+            degree: 11,
+            num_advice: 5,
+            num_lookup_advice: 2,
+            num_fixed: 1,
+            lookup_bits: 10
+        }
+    );
+    // 10_000 cells fit in 3 columns of 2^12 - NUM_UNUSABLE_ROWS = 4086 rows
+    let params = CircuitParams::for_target(&shape, 10, 3);
+    assert_eq!((params.degree, params.num_advice), (12, 3));
+    let params = CircuitParams::for_target(&shape, 10, 1);
+    assert_eq!((params.degree, params.num_advice), (14, 1));
+}
+
+#[test]
+fn test_auto_configure() {
+    let synthesize = |range: &range::RangeConfig<Fr>, ctx: &mut Context<'_, Fr>| {
+        for i in 0..200u64 {
+            let a = range.gate().assign_region_smart(
+                ctx,
+                vec![Witness(Value::known(Fr::from(i * 300)))],
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            range.range_check(ctx, &a[0], 16)?;
+        }
+        Ok(())
+    };
+    let shape = CircuitShape::record(8, synthesize).unwrap();
+    assert!(shape.advice_cells > 0 && shape.lookup_cells >= 400);
+
+    let circuit = RangeCircuitBuilder::auto_configure(8, 2, synthesize).unwrap();
+    assert_eq!(circuit.params, CircuitParams::for_target(&shape, 8, 2));
+    assert!(circuit.params.num_advice <= 2);
+    let prover = MockProver::run(circuit.params.degree, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
 #[cfg(feature = "dev-graph")]
 #[test]
 fn plot_range() {
@@ -282,3 +434,1568 @@ fn plot_range() {
 
     halo2_proofs::dev::CircuitLayout::default().render(7, &circuit, &root).unwrap();
 }
+
+// the 4-bit S-box of the PRESENT block cipher
+const SBOX: [u64; 16] =
+    [0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2];
+
+struct SboxCircuit<F> {
+    inputs: Vec<Value<F>>,
+    // a pair `(x, y)` to look up, which is in the table iff `y = SBOX[x]`
+    pair: (Value<F>, Value<F>),
+}
+
+impl<F: FieldExt> Circuit<F> for SboxCircuit<F> {
+    type Config = lookup::LookupTableConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            inputs: vec![Value::unknown(); self.inputs.len()],
+            pair: (Value::unknown(), Value::unknown()),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let rows =
+            SBOX.iter().enumerate().map(|(x, y)| vec![F::from(x as u64), F::from(*y)]).collect();
+        lookup::LookupTableConfig::configure(meta, rows, 1, 2, ContextId::new("sbox"))
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "sbox",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("sbox"), 2)] },
+                );
+                let ctx = &mut aux;
+
+                for input in self.inputs.iter() {
+                    let out = config.query(ctx, &[Witness(*input)])?;
+                    if let Some(x) = value_to_option(*input) {
+                        let expected = F::from(SBOX[x.get_lower_128() as usize]);
+                        assert_eq!(value_to_option(out[1].value()), Some(&expected));
+                    }
+                }
+                config.lookup(ctx, &[Witness(self.pair.0), Witness(self.pair.1)])?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_dynamic_lookup() {
+    let k = 6;
+    let circuit = SboxCircuit::<Fr> {
+        inputs: (0..16).rev().map(|x| Value::known(Fr::from(x))).collect(),
+        pair: (Value::known(Fr::from(3)), Value::known(Fr::from(0xb))),
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_dynamic_lookup_invalid() {
+    let k = 6;
+    for pair in [(3, 0xc), (16, 0), (0, 0)] {
+        let circuit = SboxCircuit::<Fr> {
+            inputs: vec![Value::known(Fr::from(7))],
+            pair: (Value::known(Fr::from(pair.0)), Value::known(Fr::from(pair.1))),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
+
+struct ByteOpsCircuit<F> {
+    a: Value<F>,
+    b: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for ByteOpsCircuit<F> {
+    type Config = range::RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let mut config = range::RangeConfig::configure(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        );
+        config.gate.enable_byte_lookup(meta);
+        config
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "byte ops",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![
+                            (ContextId::new("default"), NUM_ADVICE),
+                            (ContextId::new("default_bytes"), 1),
+                        ],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let (a, b) = (Witness(self.a), Witness(self.b));
+                let outs = [
+                    (config.gate.xor_bytes(ctx, &a, &b)?, 0xa5 ^ 0x3c),
+                    (config.gate.and_bytes(ctx, &a, &b)?, 0xa5 & 0x3c),
+                    (config.gate.or_bytes(ctx, &a, &b)?, 0xa5 | 0x3c),
+                ];
+                for (out, expected) in outs.iter() {
+                    config.gate.assert_is_const(ctx, out, F::from(*expected as u64));
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_byte_ops() {
+    let k = 17;
+    let circuit =
+        ByteOpsCircuit::<Fr> { a: Value::known(Fr::from(0xa5)), b: Value::known(Fr::from(0x3c)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    // the operands are not bytes
+    let circuit = ByteOpsCircuit::<Fr> {
+        a: Value::known(Fr::from(0xa5 + 256)),
+        b: Value::known(Fr::from(0x3c)),
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct ByteLookupCircuit<F> {
+    a: u64,
+    b: u64,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for ByteLookupCircuit<F> {
+    type Config = bitwise::ByteLookupConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: self.a, b: self.b, _marker: std::marker::PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        bitwise::ByteLookupConfig::configure(meta, ContextId::new("bytes"))
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "byte lookup",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("bytes"), 1)] },
+                );
+                let ctx = &mut aux;
+
+                let a = Witness(Value::known(F::from(self.a)));
+                let b = Witness(Value::known(F::from(self.b)));
+                for op in [BitwiseOp::And, BitwiseOp::Or, BitwiseOp::Xor] {
+                    let out = config.assign_op(ctx, op, &a, &b)?;
+                    assert_eq!(
+                        value_to_option(out.value().copied()),
+                        Some(F::from(op.eval(self.a, self.b)))
+                    );
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_byte_lookup_config() {
+    for (a, b) in [(0xa5, 0x3c), (0, 0xff), (0xff, 0xff)] {
+        let circuit = ByteLookupCircuit::<Fr> { a, b, _marker: std::marker::PhantomData };
+        let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    // the operands are not bytes, so `(a, b, op(a, b))` is not in the table
+    let circuit = ByteLookupCircuit::<Fr> { a: 0x1a5, b: 0x3c, _marker: std::marker::PhantomData };
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct MultiTableCircuit<F> {
+    a: Value<F>,
+    // range checked in the second phase, where only the main table is available
+    b: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MultiTableCircuit<F> {
+    type Config = range::RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        range::RangeConfig::configure_with_extra_tables(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[2, 1],
+            &[1, 1],
+            1,
+            3,
+            &[(8, 1)],
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "multiple range tables",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), 3)] },
+                );
+                let ctx = &mut aux;
+
+                let a = config.gate.assign_region_smart(
+                    ctx,
+                    vec![Witness(self.a)],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                // 8 and 16 bits use the 8 bit table, 3 and 6 bits the 3 bit table
+                config.range_check(ctx, &a[0], 8)?;
+                config.range_check(ctx, &a[0], 16)?;
+                config.range_check(ctx, &a[0], 6)?;
+                config.is_less_than(ctx, &Existing(&a[0]), &Constant(F::from(200)), 8)?;
+                assert_eq!(ctx.extra_cells_to_lookup[&8].len(), 3);
+
+                // 8 bits use the 3 bit table in the second phase
+                ctx.next_phase();
+                let b = config.gate.assign_region_smart(
+                    ctx,
+                    vec![Witness(self.b)],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                config.range_check(ctx, &b[0], 8)?;
+                assert_eq!(ctx.extra_cells_to_lookup[&8].len(), 3);
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_multiple_range_tables() {
+    let k = 10;
+    let circuit =
+        MultiTableCircuit::<Fr> { a: Value::known(Fr::from(37)), b: Value::known(Fr::from(200)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    // not in [0, 2^6)
+    let circuit =
+        MultiTableCircuit::<Fr> { a: Value::known(Fr::from(100)), b: Value::known(Fr::from(200)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // not in [0, 2^8), checked in the second phase
+    let circuit =
+        MultiTableCircuit::<Fr> { a: Value::known(Fr::from(37)), b: Value::known(Fr::from(300)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct SelectCircuit<F> {
+    cells: Vec<Value<F>>,
+    idx: Value<F>,
+    // `cells[idx]`, or 0 if `idx` is out of bounds
+    expected: F,
+}
+
+impl<F: FieldExt> Circuit<F> for SelectCircuit<F> {
+    type Config = range::RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            cells: vec![Value::unknown(); self.cells.len()],
+            idx: Value::unknown(),
+            expected: self.expected,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        range::RangeConfig::configure(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            3,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "select",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut witnesses: Vec<_> = self.cells.iter().map(|x| Witness(*x)).collect();
+                witnesses.push(Witness(self.idx));
+                let mut cells =
+                    config.gate.assign_region_smart(ctx, witnesses, vec![], vec![], vec![])?;
+                let idx = cells.pop().unwrap();
+                let cells: Vec<_> = cells.iter().map(Existing).collect();
+
+                let bits = config.num_to_bits(ctx, &idx, 3)?;
+                let bits = bits.iter().map(Existing).collect();
+                let by_bits = config.gate.select_from_idx_bits(ctx, &cells, &bits)?;
+                let by_indicator = config.gate.select_from_idx(ctx, &cells, &Existing(&idx))?;
+                config.gate.assert_equal(ctx, &Existing(&by_bits), &Existing(&by_indicator))?;
+                config.gate.assert_is_const(ctx, &by_bits, self.expected);
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_select_from_idx_bits() {
+    let k = 8;
+    let cells: Vec<_> = (10..15).map(|x| Value::known(Fr::from(x))).collect();
+    for (idx, expected) in [(0, 10), (3, 13), (4, 14), (6, 0)] {
+        let circuit = SelectCircuit::<Fr> {
+            cells: cells.clone(),
+            idx: Value::known(Fr::from(idx)),
+            expected: Fr::from(expected),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+struct StrictBitsCircuit<F> {
+    a: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for StrictBitsCircuit<F> {
+    type Config = range::RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        range::RangeConfig::configure(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            3,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "strict bits",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let a = config.gate.assign_region_smart(
+                    ctx,
+                    vec![Witness(self.a)],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let bits = config.num_to_bits_strict(ctx, &a[0])?;
+                assert_eq!(bits.len(), F::NUM_BITS as usize);
+                if let Some(a) = value_to_option(self.a) {
+                    let a = crate::utils::fe_to_biguint(&a);
+                    for (i, bit) in bits.iter().enumerate() {
+                        config.gate.assert_is_const(ctx, bit, F::from(a.bit(i as u64) as u64));
+                    }
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_num_to_bits_strict() {
+    let k = 13;
+    for a in [Fr::from(0), Fr::from(0x1234), -Fr::from(1)] {
+        let circuit = StrictBitsCircuit::<Fr> { a: Value::known(a) };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+struct WordOpsCircuit<F> {
+    // `(a, width)`
+    words: Vec<(u64, usize)>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for WordOpsCircuit<F> {
+    type Config = range::RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { words: self.words.clone(), _marker: std::marker::PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        range::RangeConfig::configure(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "word ops",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                for &(a, width) in self.words.iter() {
+                    let a_cell = config.gate.assign_region_smart(
+                        ctx,
+                        vec![Witness(Value::known(F::from(a)))],
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?;
+                    let a_cell = &a_cell[0];
+                    let mask = if width == 64 { u64::MAX } else { (1u64 << width) - 1 };
+                    for s in [0, 1, 7, 13, width - 1, width] {
+                        let outs = [
+                            (
+                                bitwise::rotate_right(&config, ctx, a_cell, s, width)?,
+                                if width == 64 {
+                                    a.rotate_right(s as u32)
+                                } else {
+                                    (a as u32).rotate_right(s as u32) as u64
+                                },
+                            ),
+                            (
+                                bitwise::rotate_left(&config, ctx, a_cell, s, width)?,
+                                if width == 64 {
+                                    a.rotate_left(s as u32)
+                                } else {
+                                    (a as u32).rotate_left(s as u32) as u64
+                                },
+                            ),
+                            (
+                                bitwise::shr(&config, ctx, a_cell, s, width)?,
+                                a.checked_shr(s as u32).unwrap_or(0),
+                            ),
+                            (
+                                bitwise::shl(&config, ctx, a_cell, s, width)?,
+                                a.checked_shl(s as u32).unwrap_or(0) & mask,
+                            ),
+                        ];
+                        for (out, expected) in outs.iter() {
+                            config.gate.assert_is_const(ctx, out, F::from(*expected));
+                        }
+                    }
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_word_ops() {
+    let k = 12;
+    let circuit = WordOpsCircuit::<Fr> {
+        words: vec![(0x8badf00d, 32), (0xdeadbeef01234567, 64)],
+        _marker: std::marker::PhantomData,
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    // the input does not fit in the width
+    let circuit =
+        WordOpsCircuit::<Fr> { words: vec![(1 << 32, 32)], _marker: std::marker::PhantomData };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+// EVM opcodes of the PUSH1..PUSH4 instructions
+const OPCODES: [u64; 4] = [0x60, 0x61, 0x62, 0x63];
+
+struct SetCircuit<F> {
+    a: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SetCircuit<F> {
+    type Config = (FlexGateConfig<F>, lookup::LookupTableConfig<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let gate = FlexGateConfig::configure(
+            meta,
+            GateStrategy::Vertical,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        );
+        let set = OPCODES.iter().map(|x| F::from(*x)).collect();
+        let table =
+            lookup::LookupTableConfig::configure_set(meta, set, 1, ContextId::new("opcodes"));
+        (gate, table)
+    }
+
+    fn synthesize(
+        &self,
+        (gate, table): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        table.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "set",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![
+                            (ContextId::new("default"), NUM_ADVICE),
+                            (ContextId::new("opcodes"), 1),
+                        ],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let a = table.assert_in_set(ctx, &Witness(self.a))?;
+                let set: Vec<_> = OPCODES.iter().map(|x| Constant(F::from(*x))).collect();
+                gate.assert_in_set(ctx, &Existing(&a), &set)?;
+
+                gate.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_assert_in_set() {
+    let k = 6;
+    let circuit = SetCircuit::<Fr> { a: Value::known(Fr::from(0x62)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    let circuit = SetCircuit::<Fr> { a: Value::known(Fr::from(0x64)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct RlcCircuit<F> {
+    bytes: Vec<Value<F>>,
+    len: usize,
+}
+
+impl<F: FieldExt> Circuit<F> for RlcCircuit<F> {
+    type Config = (range::RangeConfig<F>, rlc::RlcConfig<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { bytes: vec![Value::unknown(); self.bytes.len()], len: self.len }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let range = range::RangeConfig::configure(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[NUM_ADVICE, 1],
+            &[1, 1],
+            1,
+            3,
+            ContextId::new("default"),
+        );
+        (range, rlc::RlcConfig::configure(meta, ContextId::new("rlc")))
+    }
+
+    fn synthesize(
+        &self,
+        (range, rlc_config): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        range.load_lookup_table(&mut layouter)?;
+        let gamma = rlc_config.get_challenge(&layouter);
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "rlc",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![
+                            (ContextId::new("default"), NUM_ADVICE + 1),
+                            (ContextId::new("rlc"), 1),
+                        ],
+                    },
+                );
+                let ctx = &mut aux;
+                let gate = range.gate();
+                ctx.challenge.insert(ContextId::new("rlc"), gamma);
+
+                let mut witnesses: Vec<_> = self.bytes.iter().map(|x| Witness(*x)).collect();
+                witnesses.push(Witness(Value::known(F::from(self.len as u64))));
+                let mut bytes = gate.assign_region_smart(ctx, witnesses, vec![], vec![], vec![])?;
+                let len = bytes.pop().unwrap();
+
+                ctx.next_phase();
+                let gamma_cell = rlc_config.load_challenge(ctx)?;
+                let prefix: Vec<_> = bytes[..self.len].iter().map(Existing).collect();
+                let all: Vec<_> = bytes.iter().map(Existing).collect();
+                let fixed_len = rlc::rlc(gate, ctx, &prefix, &gamma_cell)?;
+                let var_len = rlc::rlc_var(&range, ctx, &all, &len, &gamma_cell)?;
+                gate.assert_equal(ctx, &Existing(&fixed_len), &Existing(&var_len))?;
+
+                // rlc(a || b) = rlc(a) * gamma^len(b) + rlc(b)
+                let (a, b) = all.split_at(self.len / 2);
+                let rlc_a = rlc::rlc(gate, ctx, a, &gamma_cell)?;
+                let rlc_b = rlc::rlc(gate, ctx, b, &gamma_cell)?;
+                let pow = rlc::gamma_pow(gate, ctx, &gamma_cell, b.len())?;
+                let concat =
+                    gate.mul_add(ctx, &Existing(&rlc_a), &Existing(&pow), &Existing(&rlc_b))?;
+                let full = rlc::rlc(gate, ctx, &all, &gamma_cell)?;
+                gate.assert_equal(ctx, &Existing(&concat), &Existing(&full))?;
+
+                if let (Some(g), Some(out)) =
+                    (value_to_option(gamma), value_to_option(fixed_len.value()))
+                {
+                    let expected = self.bytes[..self.len]
+                        .iter()
+                        .fold(F::zero(), |acc, x| acc * g + value_to_option(*x).unwrap());
+                    assert_eq!(*out, expected);
+                }
+
+                range.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_rlc() {
+    let k = 10;
+    let bytes = b"rlp encoded header".iter().map(|b| Value::known(Fr::from(*b as u64))).collect();
+    let circuit = RlcCircuit::<Fr> { bytes, len: 11 };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_rlc_var_len_too_long() {
+    let params = CircuitParams {
+        degree: 10,
+        num_advice: 2,
+        num_lookup_advice: 1,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    // the rlc does not depend on `gamma` being a challenge, so a witness stands in for it
+    let circuit = RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        |config: &range::RangeConfig<Fr>, ctx| {
+            let gate = config.gate();
+            // values [1, 2, 3] with len 4 and gamma 5
+            let mut cells = gate.assign_region_smart(
+                ctx,
+                [1, 2, 3, 4, 5].map(|x| Witness(Value::known(Fr::from(x)))).to_vec(),
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            let gamma = cells.pop().unwrap();
+            let len = cells.pop().unwrap();
+            let values: Vec<_> = cells.iter().map(Existing).collect();
+            rlc::rlc_var(config, ctx, &values, &len, &gamma)?;
+            Ok(())
+        },
+    );
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct BuilderCircuit<F> {
+    inputs: Vec<Value<F>>,
+    chunk_size: usize,
+}
+
+impl<F: FieldExt> Circuit<F> for BuilderCircuit<F> {
+    type Config = FlexGateConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { inputs: vec![Value::unknown(); self.inputs.len()], chunk_size: self.chunk_size }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FlexGateConfig::configure(
+            meta,
+            GateStrategy::Vertical,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "builder",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let inputs = config.assign_region_smart(
+                    ctx,
+                    self.inputs.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+
+                // `sum_i x_i^2 + 1` of each chunk, on worker threads
+                let jobs = inputs
+                    .chunks(self.chunk_size)
+                    .map(|chunk| (chunk.iter().map(|x| x.value().copied()).collect(), chunk.len()))
+                    .collect();
+                let builders = parallelize(jobs, |builder, len| {
+                    let xs: Vec<_> = (0..len)
+                        .map(|i| VirtualQuantumCell::Existing(VirtualCell::Input(i)))
+                        .collect();
+                    let sum = builder.inner_product(&xs, &xs);
+                    builder.add(
+                        VirtualQuantumCell::Existing(sum),
+                        VirtualQuantumCell::Constant(F::one()),
+                    )
+                });
+
+                for ((builder, out), chunk) in
+                    builders.into_iter().zip(inputs.chunks(self.chunk_size))
+                {
+                    let assigned = builder.assign(&config, ctx, chunk)?;
+                    let out = match out {
+                        VirtualCell::Cell(i) => &assigned[i],
+                        VirtualCell::Input(_) => unreachable!(),
+                    };
+                    let xs: Vec<_> = chunk.iter().map(Existing).collect();
+                    let sum = config.inner_product(ctx, &xs, &xs)?;
+                    let expected = config.add(ctx, &Existing(&sum), &Constant(F::one()))?;
+                    config.assert_equal(ctx, &Existing(out), &Existing(&expected))?;
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_thread_builder() {
+    let k = 8;
+    let inputs = (1..=10).map(|x| Value::known(Fr::from(x))).collect();
+    let circuit = BuilderCircuit::<Fr> { inputs, chunk_size: 4 };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_dedup_constants() {
+    let params = CircuitParams {
+        degree: 7,
+        num_advice: NUM_ADVICE,
+        num_lookup_advice: 0,
+        num_fixed: 1,
+        lookup_bits: 3,
+    };
+    let circuit = RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        |config: &range::RangeConfig<Fr>, ctx| {
+            // each `add` uses the constant 1, and each `mul` the constant 0
+            let gate = config.gate();
+            let mut x =
+                gate.add(ctx, &Witness(Value::known(Fr::from(5))), &Constant(Fr::from(2)))?;
+            for _ in 0..10 {
+                x = gate.add(ctx, &Existing(&x), &Constant(Fr::from(2)))?;
+                x = gate.mul(ctx, &Existing(&x), &Constant(Fr::from(3)))?;
+            }
+            assert_eq!(ctx.num_constants(), 4);
+            Ok(())
+        },
+    );
+    let prover = MockProver::run(params.degree, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+struct PublicCircuit<F> {
+    a: Value<F>,
+    b: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for PublicCircuit<F> {
+    type Config = FlexGateConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let mut config = FlexGateConfig::configure(
+            meta,
+            GateStrategy::Vertical,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        );
+        config.enable_instance(meta);
+        config
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+        let mut cells_to_expose = vec![];
+
+        layouter.assign_region(
+            || "public",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let sum = config.add(ctx, &Witness(self.a), &Witness(self.b))?;
+                let prod = config.mul(ctx, &Witness(self.a), &Witness(self.b))?;
+                ctx.expose_public(&sum, 0);
+                ctx.expose_public(&prod, 1);
+
+                config.finalize(ctx)?;
+                cells_to_expose = ctx.cells_to_expose.clone();
+                Ok(())
+            },
+        )?;
+        config.expose_public_cells(&mut layouter, &cells_to_expose)
+    }
+}
+
+#[test]
+fn test_expose_public() {
+    let k = 6;
+    let circuit =
+        PublicCircuit::<Fr> { a: Value::known(Fr::from(3)), b: Value::known(Fr::from(4)) };
+    let prover = MockProver::run(k, &circuit, vec![vec![Fr::from(7), Fr::from(12)]]).unwrap();
+    prover.assert_satisfied();
+
+    let prover = MockProver::run(k, &circuit, vec![vec![Fr::from(7), Fr::from(13)]]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct MultiRegionCircuit<F> {
+    a: Value<F>,
+    b: Value<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MultiRegionCircuit<F> {
+    type Config = range::RangeConfig<F>;
+    type FloorPlanner = floor_planner::V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: Value::unknown(), b: Value::unknown() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        range::RangeConfig::configure(
+            meta,
+            range::RangeStrategy::Vertical,
+            &[2],
+            &[1],
+            1,
+            3,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        // no first pass to skip: the V1 floor planner needs the shape of each region
+        let (a, b) = layouter.assign_region(
+            || "load inputs",
+            |region| {
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), 2)] },
+                );
+                let ctx = &mut aux;
+                let a = config.gate.add(ctx, &Witness(self.a), &Constant(F::from(1)))?;
+                let b = config.gate.mul(ctx, &Witness(self.b), &Constant(F::from(2)))?;
+                config.finalize(ctx)?;
+                Ok((a, b))
+            },
+        )?;
+
+        layouter.assign_region(
+            || "range",
+            |region| {
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), 2)] },
+                );
+                let ctx = &mut aux;
+                config.range_check(ctx, &a, 8)?;
+                config.check_less_than(ctx, &Existing(&a), &Existing(&b), 8)?;
+                let is_zero = config.is_zero(ctx, &a)?;
+                config.gate.assert_is_const(ctx, &is_zero, F::zero());
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_multi_region_v1() {
+    let k = 8;
+    let circuit =
+        MultiRegionCircuit::<Fr> { a: Value::known(Fr::from(99)), b: Value::known(Fr::from(60)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+
+    // 200 is not less than 120
+    let circuit =
+        MultiRegionCircuit::<Fr> { a: Value::known(Fr::from(199)), b: Value::known(Fr::from(60)) };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn test_generate_witnesses() {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let config = range::RangeConfig::configure(
+        &mut meta,
+        range::RangeStrategy::Vertical,
+        &[2],
+        &[1],
+        1,
+        8,
+        ContextId::new("default"),
+    );
+    let params = ContextParams { num_advice: vec![(ContextId::new("default"), 2)] };
+    let stats = Context::generate_witnesses(params, |ctx| {
+        let a =
+            config.gate.add(ctx, &Witness(Value::known(Fr::from(300))), &Constant(Fr::from(1)))?;
+        let bits = config.num_to_bits(ctx, &a, 16)?;
+        assert_eq!(value_to_option(a.value().copied()), Some(Fr::from(301)));
+        assert_eq!(value_to_option(bits[0].value().copied()), Some(Fr::from(1)));
+        assert_eq!(value_to_option(bits[1].value().copied()), Some(Fr::from(0)));
+        config.range_check(ctx, &a, 16)?;
+        config.finalize_with_stats(ctx)
+    })
+    .unwrap();
+    // the two 8 bit limbs of the range check are copied to the lookup advice column
+    assert_eq!(stats.lookup_cells, 2);
+    assert!(stats.total_advice_cells() > 0);
+    assert!(stats.num_constants > 0);
+}
+
+#[cfg(feature = "provenance")]
+#[test]
+fn test_provenance() {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    let config = FlexGateConfig::configure(
+        &mut meta,
+        GateStrategy::Vertical,
+        &[NUM_ADVICE],
+        1,
+        ContextId::new("default"),
+    );
+    let params = ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] };
+    let log = Context::generate_witnesses(params, |ctx| {
+        let a = config.add(ctx, &Witness(Value::known(Fr::from(3))), &Constant(Fr::from(4)))?;
+        ctx.push_tag("square");
+        config.assign_region_smart_tagged(
+            ctx,
+            "mul",
+            vec![
+                Constant(Fr::from(0)),
+                Existing(&a),
+                Existing(&a),
+                Witness(Value::known(Fr::from(49))),
+            ],
+            vec![0],
+            vec![],
+            vec![],
+        )?;
+        ctx.pop_tag();
+        Ok(std::mem::take(&mut ctx.provenance))
+    })
+    .unwrap();
+    assert!(log.at_row(2).all(|block| block.tags.is_empty()));
+    let block = log.at_cell(&ContextId::new("default"), 0, 5).unwrap();
+    assert_eq!(block.rows, 4..8);
+    assert_eq!(block.tags, vec!["square", "mul"]);
+}
+
+#[test]
+fn test_gate_strategies() {
+    let params = CircuitParams {
+        degree: 9,
+        num_advice: 3,
+        num_lookup_advice: 1,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    for strategy in [
+        range::RangeStrategy::Vertical,
+        range::RangeStrategy::PlonkPlus,
+        range::RangeStrategy::VerticalDot,
+        range::RangeStrategy::Horizontal,
+    ] {
+        let circuit =
+            RangeCircuitBuilder::new(strategy, params, |config: &range::RangeConfig<Fr>, ctx| {
+                let gate = config.gate();
+                // constrains `a` to equal `expected`, and checks its witness
+                let check = |ctx: &mut Context<'_, Fr>, a: &AssignedValue<Fr>, expected: u64| {
+                    assert_eq!(value_to_option(a.value().copied()), Some(Fr::from(expected)));
+                    gate.assert_is_const(ctx, a, Fr::from(expected));
+                };
+                let a = Witness(Value::known(Fr::from(3)));
+                let b = Witness(Value::known(Fr::from(5)));
+                let bit = Witness(Value::known(Fr::from(1)));
+
+                let x = gate.add(ctx, &a, &b)?;
+                check(ctx, &x, 8);
+                let x = gate.sub(ctx, &b, &a)?;
+                check(ctx, &x, 2);
+                let x = gate.neg(ctx, &a)?;
+                assert_eq!(value_to_option(x.value().copied()), Some(-Fr::from(3)));
+                let x = gate.mul(ctx, &a, &b)?;
+                check(ctx, &x, 15);
+                let x = gate.mul_add(ctx, &a, &b, &Constant(Fr::from(2)))?;
+                check(ctx, &x, 17);
+                let x = gate.div_unsafe(ctx, &Constant(Fr::from(15)), &b)?;
+                check(ctx, &x, 3);
+
+                let vec_a = vec![a.clone(), b.clone(), Constant(Fr::from(7))];
+                let x =
+                    gate.inner_product(ctx, &vec_a, &vec![b.clone(), a.clone(), bit.clone()])?;
+                check(ctx, &x, 37);
+                let x = gate.inner_product(
+                    ctx,
+                    &vec_a,
+                    &vec![Constant(Fr::from(1)), Constant(Fr::from(2)), Constant(Fr::from(3))],
+                )?;
+                check(ctx, &x, 34);
+                let x = gate.sum_products_with_coeff_and_var(
+                    ctx,
+                    &[(Fr::from(2), a.clone(), b.clone()), (Fr::from(1), b.clone(), b.clone())],
+                    &Constant(Fr::from(4)),
+                )?;
+                check(ctx, &x, 59);
+
+                assert!(gate.accumulated_product(ctx, &vec![], &vec![])?.is_empty());
+                let prods = gate.accumulated_product(ctx, &vec![], &vec![a.clone()])?;
+                check(ctx, &prods[0], 3);
+                // [b_0, b_0 * a_0 + b_1, (b_0 * a_0 + b_1) * a_1 + b_2]
+                let prods = gate.accumulated_product(
+                    ctx,
+                    &vec![a.clone(), b.clone()],
+                    &vec![Constant(Fr::from(2)), bit.clone(), a.clone()],
+                )?;
+                for (prod, expected) in prods.iter().zip([2, 7, 38]) {
+                    check(ctx, prod, expected);
+                }
+
+                let x = gate.or(ctx, &bit, &Constant(Fr::from(0)))?;
+                check(ctx, &x, 1);
+                let x = gate.and(ctx, &bit, &Constant(Fr::from(0)))?;
+                check(ctx, &x, 0);
+                let x = gate.not(ctx, &bit)?;
+                check(ctx, &x, 0);
+                let x = gate.select(ctx, &a, &b, &bit)?;
+                check(ctx, &x, 3);
+                let x = gate.or_and(ctx, &Constant(Fr::from(0)), &bit, &bit)?;
+                check(ctx, &x, 1);
+
+                let ind = gate.bits_to_indicator(ctx, &vec![bit.clone(), bit.clone()])?;
+                for (x, expected) in ind.iter().zip([0, 0, 0, 1]) {
+                    check(ctx, x, expected);
+                }
+                let ind = gate.idx_to_indicator(ctx, &Constant(Fr::from(2)), 3)?;
+                for (x, expected) in ind.iter().zip([0, 0, 1]) {
+                    check(ctx, x, expected);
+                }
+                let x = gate.select_from_idx(ctx, &vec_a, &Witness(Value::known(Fr::from(1))))?;
+                check(ctx, &x, 5);
+
+                let x = config.is_equal(ctx, &a, &Constant(Fr::from(3)))?;
+                check(ctx, &x, 1);
+                let x = config.is_less_than(ctx, &a, &b, 8)?;
+                check(ctx, &x, 1);
+                for (x, y, lt, eq) in [(&a, &b, 1, 0), (&b, &a, 0, 0), (&b, &b, 0, 1)] {
+                    let (x_lt, x_eq) = config.compare(ctx, x, y, 8)?;
+                    check(ctx, &x_lt, lt);
+                    check(ctx, &x_eq, eq);
+                }
+                Ok(())
+            });
+        let prover = MockProver::run(params.degree, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+fn idx_to_indicator_circuit(
+    idx: u64,
+    len: usize,
+) -> RangeCircuitBuilder<
+    Fr,
+    impl Fn(&range::RangeConfig<Fr>, &mut Context<'_, Fr>) -> Result<(), Error> + Clone,
+> {
+    let params = CircuitParams {
+        degree: 10,
+        num_advice: 2,
+        num_lookup_advice: 1,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        move |config: &range::RangeConfig<Fr>, ctx| {
+            let idx = config
+                .gate()
+                .assign_region_smart(
+                    ctx,
+                    vec![Witness(Value::known(Fr::from(idx)))],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?
+                .pop()
+                .unwrap();
+            let by_bits = config.idx_to_indicator_by_bits(ctx, &idx, len)?;
+            let by_lookup = config.idx_to_indicator_by_lookup(ctx, &idx, len)?;
+            for ind in [by_bits, by_lookup] {
+                assert_eq!(ind.len(), len);
+                for (i, x) in ind.iter().enumerate() {
+                    let expected = Fr::from((i as u64 == idx) as u64);
+                    assert_eq!(value_to_option(x.value().copied()), Some(expected));
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+#[test]
+fn test_idx_to_indicator() {
+    for (idx, len) in [(0, 1), (2, 3), (5, 8), (17, 37), (99, 100)] {
+        let circuit = idx_to_indicator_circuit(idx, len);
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+#[test]
+fn test_idx_to_indicator_out_of_range() {
+    let circuit = idx_to_indicator_circuit(37, 37);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+fn bits_to_num_circuit(
+    bits: Vec<u64>,
+) -> RangeCircuitBuilder<
+    Fr,
+    impl Fn(&range::RangeConfig<Fr>, &mut Context<'_, Fr>) -> Result<(), Error> + Clone,
+> {
+    let params = CircuitParams {
+        degree: 9,
+        num_advice: NUM_ADVICE,
+        num_lookup_advice: 0,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        move |config: &range::RangeConfig<Fr>, ctx| {
+            let gate = config.gate();
+            let a = gate.assign_region_smart(
+                ctx,
+                vec![Witness(Value::known(Fr::from(0xb5)))],
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            // `num_to_bits` and `bits_to_num` are inverse
+            let a_bits = config.num_to_bits(ctx, &a[0], 8)?;
+            let num = gate.bits_to_num(ctx, &a_bits, false)?;
+            gate.assert_equal(ctx, &Existing(&a[0]), &Existing(&num))?;
+
+            let bits = gate.assign_region_smart(
+                ctx,
+                bits.iter().map(|b| Witness(Value::known(Fr::from(*b)))).collect(),
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            let num = gate.bits_to_num(ctx, &bits, true)?;
+            let expected = bits.iter().rev().fold(Fr::from(0), |acc, b| {
+                acc + acc + value_to_option(b.value().copied()).unwrap()
+            });
+            assert_eq!(value_to_option(num.value().copied()), Some(expected));
+            Ok(())
+        },
+    )
+}
+
+#[test]
+fn test_bits_to_num() {
+    let circuit = bits_to_num_circuit(vec![1, 0, 1, 1, 0, 0, 1]);
+    let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_bits_to_num_not_bits() {
+    let circuit = bits_to_num_circuit(vec![1, 2, 1]);
+    let prover = MockProver::run(9, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+fn range_check_var_circuit(
+    a: u64,
+    bits: u64,
+) -> RangeCircuitBuilder<
+    Fr,
+    impl Fn(&range::RangeConfig<Fr>, &mut Context<'_, Fr>) -> Result<(), Error> + Clone,
+> {
+    let params = CircuitParams {
+        degree: 10,
+        num_advice: 2,
+        num_lookup_advice: 1,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        move |config: &range::RangeConfig<Fr>, ctx| {
+            let cells = config.gate().assign_region_smart(
+                ctx,
+                vec![Witness(Value::known(Fr::from(a))), Witness(Value::known(Fr::from(bits)))],
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            config.range_check_var(ctx, &cells[0], &cells[1], 20)
+        },
+    )
+}
+
+#[test]
+fn test_range_check_var() {
+    for (a, bits) in [(0, 0), (1, 1), (255, 8), (256, 9), ((1 << 20) - 1, 20)] {
+        let circuit = range_check_var_circuit(a, bits);
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+    // `a >= 2^bits`, or `bits > max_bits`
+    for (a, bits) in [(1, 0), (256, 8), (1 << 20, 21)] {
+        let circuit = range_check_var_circuit(a, bits);
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
+
+fn shift_mask_circuit(
+    len: u64,
+    shift: u64,
+) -> RangeCircuitBuilder<
+    Fr,
+    impl Fn(&range::RangeConfig<Fr>, &mut Context<'_, Fr>) -> Result<(), Error> + Clone,
+> {
+    let params = CircuitParams {
+        degree: 10,
+        num_advice: 2,
+        num_lookup_advice: 1,
+        num_fixed: 1,
+        lookup_bits: 8,
+    };
+    RangeCircuitBuilder::new(
+        range::RangeStrategy::Vertical,
+        params,
+        move |config: &range::RangeConfig<Fr>, ctx| {
+            let input: Vec<u64> = (1..=6).collect();
+            let cells = config.gate().assign_region_smart(
+                ctx,
+                input
+                    .iter()
+                    .chain([len, shift].iter())
+                    .map(|x| Witness(Value::known(Fr::from(*x))))
+                    .collect(),
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            let (bytes, lens) = cells.split_at(input.len());
+            let masked = bytes::mask_bytes(config, ctx, bytes, &lens[0], 3)?;
+            let to_start = bytes::shift_bytes(config, ctx, &masked, &lens[1], 3, true)?;
+            let to_end = bytes::shift_bytes(config, ctx, &masked, &lens[1], 3, false)?;
+
+            let masked_native: Vec<u64> =
+                input.iter().map(|x| if *x <= len { *x } else { 0 }).collect();
+            let at = |i: i64| -> u64 {
+                if (0..input.len() as i64).contains(&i) {
+                    masked_native[i as usize]
+                } else {
+                    0
+                }
+            };
+            for i in 0..input.len() {
+                for (cell, expected) in [
+                    (&masked[i], masked_native[i]),
+                    (&to_start[i], at(i as i64 + shift as i64)),
+                    (&to_end[i], at(i as i64 - shift as i64)),
+                ] {
+                    // the witness bits of a shift with more than 3 bits are truncated
+                    if shift < 8 {
+                        assert_eq!(
+                            value_to_option(cell.value().copied()),
+                            Some(Fr::from(expected))
+                        );
+                    }
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+#[test]
+fn test_shift_mask_bytes() {
+    for (len, shift) in [(0, 0), (6, 0), (3, 2), (4, 5), (6, 7), (2, 6)] {
+        let circuit = shift_mask_circuit(len, shift);
+        let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+    // a shift with more than 3 bits
+    let circuit = shift_mask_circuit(6, 8);
+    let prover = MockProver::run(10, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,154 @@
+use super::{
+    sha256::{sha256, sha256_native, SHA256_BLOCK_SIZE},
+    word32::pow2,
+};
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+/// Length in bytes of a SHA-256 digest
+pub const SHA256_OUTPUT_SIZE: usize = 32;
+
+/// Out of circuit HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256_native(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut key =
+        if key.len() > SHA256_BLOCK_SIZE { sha256_native(key).to_vec() } else { key.to_vec() };
+    key.resize(SHA256_BLOCK_SIZE, 0);
+
+    let mut inner: Vec<u8> = key.iter().map(|k| k ^ IPAD).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = key.iter().map(|k| k ^ OPAD).collect();
+    outer.extend_from_slice(&sha256_native(&inner));
+    sha256_native(&outer)
+}
+
+/// Out of circuit HKDF-Extract with SHA-256 (RFC 5869)
+pub fn hkdf_extract_native(salt: &[u8], ikm: &[u8]) -> [u8; 32] {
+    hmac_sha256_native(salt, ikm)
+}
+
+/// Out of circuit HKDF-Expand with SHA-256 (RFC 5869)
+pub fn hkdf_expand_native(prk: &[u8], info: &[u8], length: usize) -> Vec<u8> {
+    assert!(length <= 255 * SHA256_OUTPUT_SIZE);
+    let mut okm = Vec::with_capacity(length);
+    let mut t = vec![];
+    for i in 1..=((length + SHA256_OUTPUT_SIZE - 1) / SHA256_OUTPUT_SIZE) as u8 {
+        let mut message = t;
+        message.extend_from_slice(info);
+        message.push(i);
+        t = hmac_sha256_native(prk, &message).to_vec();
+        okm.extend_from_slice(&t);
+    }
+    okm.truncate(length);
+    okm
+}
+
+// returns `(key ^ IPAD, key ^ OPAD)` for the key padded with zeros to the block size
+fn padded_keys<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    key: &[AssignedValue<F>],
+) -> Result<(Vec<AssignedValue<F>>, Vec<AssignedValue<F>>), Error> {
+    let gate = range.gate();
+    let mut ipad_key = Vec::with_capacity(SHA256_BLOCK_SIZE);
+    let mut opad_key = Vec::with_capacity(SHA256_BLOCK_SIZE);
+    for byte in key.iter() {
+        let bits = range.num_to_bits(ctx, byte, 8)?;
+        for (pad, out) in [(IPAD, &mut ipad_key), (OPAD, &mut opad_key)] {
+            // byte ^ pad = pad + sum_i (pad_i ? -1 : 1) * 2^i * bit_i
+            let mut terms = vec![Constant(F::one())];
+            let mut coeffs = vec![Constant(F::from(pad as u64))];
+            for (i, bit) in bits.iter().enumerate() {
+                terms.push(Existing(bit));
+                coeffs.push(Constant(if (pad >> i) & 1 == 1 { -pow2::<F>(i) } else { pow2(i) }));
+            }
+            let xored = gate.inner_product(ctx, &terms, &coeffs)?;
+            out.push(xored);
+        }
+    }
+    // the zero padding of the key xors to the pads themselves
+    let pad_len = SHA256_BLOCK_SIZE - key.len();
+    for (pad, out) in [(IPAD, &mut ipad_key), (OPAD, &mut opad_key)] {
+        out.extend(gate.assign_region_smart(
+            ctx,
+            vec![Constant(F::from(pad as u64)); pad_len],
+            vec![],
+            vec![],
+            vec![],
+        )?);
+    }
+    Ok((ipad_key, opad_key))
+}
+
+/// Input: `key` and `message` as bytes of known lengths. All bytes are range checked to be in [0, 2^8).
+///
+/// Output: the 32 bytes of HMAC-SHA256(`key`, `message`)
+pub fn hmac_sha256<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    key: &[AssignedValue<F>],
+    message: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let hashed_key;
+    let key = if key.len() > SHA256_BLOCK_SIZE {
+        hashed_key = sha256(range, ctx, key)?;
+        &hashed_key
+    } else {
+        key
+    };
+    let (mut inner, mut outer) = padded_keys(range, ctx, key)?;
+    inner.extend_from_slice(message);
+    outer.extend(sha256(range, ctx, &inner)?);
+    sha256(range, ctx, &outer)
+}
+
+/// HKDF-Extract with SHA-256: returns the pseudorandom key `HMAC(salt, ikm)`
+pub fn hkdf_extract<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    salt: &[AssignedValue<F>],
+    ikm: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    hmac_sha256(range, ctx, salt, ikm)
+}
+
+/// HKDF-Expand with SHA-256: returns `length` bytes of output keying material from the pseudorandom key `prk`
+/// and the context `info`
+pub fn hkdf_expand<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    prk: &[AssignedValue<F>],
+    info: &[AssignedValue<F>],
+    length: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    assert!(length <= 255 * SHA256_OUTPUT_SIZE);
+    let n = (length + SHA256_OUTPUT_SIZE - 1) / SHA256_OUTPUT_SIZE;
+    let counters = range.gate().assign_region_smart(
+        ctx,
+        (1..=n).map(|i| Constant(F::from(i as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+
+    let mut okm = Vec::with_capacity(n * SHA256_OUTPUT_SIZE);
+    let mut t = vec![];
+    for counter in counters {
+        // T(i) = HMAC(prk, T(i - 1) || info || i)
+        let mut message = t;
+        message.extend_from_slice(info);
+        message.push(counter);
+        t = hmac_sha256(range, ctx, prk, &message)?;
+        okm.extend_from_slice(&t);
+    }
+    okm.truncate(length);
+    Ok(okm)
+}
@@ -0,0 +1,148 @@
+use super::{hkdf_expand_native, hkdf_extract_native, hmac_sha256, hmac_sha256_native};
+use crate::{
+    gates::{
+        range::{RangeConfig, RangeStrategy},
+        GateInstructions,
+    },
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt, circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*,
+};
+
+#[derive(Default)]
+struct HmacCircuit<F> {
+    key: Vec<Value<F>>,
+    message: Vec<Value<F>>,
+    expected: Vec<u8>,
+}
+
+const NUM_ADVICE: usize = 8;
+
+impl<F: FieldExt> Circuit<F> for HmacCircuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            key: vec![Value::unknown(); self.key.len()],
+            message: vec![Value::unknown(); self.message.len()],
+            expected: self.expected.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "hmac",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let key = config.gate.assign_region_smart(
+                    ctx,
+                    self.key.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let message = config.gate.assign_region_smart(
+                    ctx,
+                    self.message.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let out = hmac_sha256(&config, ctx, &key, &message)?;
+                for (byte, expected) in out.iter().zip(self.expected.iter()) {
+                    config.gate.assert_is_const(ctx, byte, F::from(*expected as u64));
+                }
+
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_hmac_sha256_native() {
+    // test cases 2 and 6 of RFC 4231
+    assert_eq!(
+        hex(&hmac_sha256_native(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+        hex(&hmac_sha256_native(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First"
+        )),
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+}
+
+#[test]
+fn test_hkdf_native() {
+    // test case 1 of RFC 5869
+    let ikm = [0x0b; 22];
+    let salt: Vec<u8> = (0x00..=0x0c).collect();
+    let info: Vec<u8> = (0xf0..=0xf9).collect();
+    let prk = hkdf_extract_native(&salt, &ikm);
+    assert_eq!(hex(&prk), "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5");
+    assert_eq!(
+        hex(&hkdf_expand_native(&prk, &info, 42)),
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+    );
+}
+
+#[test]
+fn test_hmac_sha256() {
+    let key = b"Jefe";
+    let message = b"what do ya want for nothing?";
+    let to_values =
+        |bytes: &[u8]| bytes.iter().map(|b| Value::known(Fr::from(*b as u64))).collect();
+    let circuit = HmacCircuit::<Fr> {
+        key: to_values(key),
+        message: to_values(message),
+        expected: hmac_sha256_native(key, message).to_vec(),
+    };
+    let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
@@ -0,0 +1,311 @@
+// Keccak-256 as used by Ethereum, i.e. Keccak with capacity 512 and the original `0x01` padding instead of the
+// `0x06` of SHA3-256. The state is kept as 25 lanes of 64 little-endian bits: xor and and-not act on the bits and
+// the rotations and permutation of lanes are free.
+use super::word32::xor_bit;
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+// rotation offsets of the rho step, for the lane `x + 5 y`
+const ROTATIONS: [usize; 25] =
+    [0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14];
+
+/// Size in bytes of the blocks absorbed by the sponge
+pub const KECCAK_RATE: usize = 136;
+
+// pads a message of `input_len` bytes with `0x01`, zeros and a final `0x80` to a multiple of 136 bytes
+pub(crate) fn pad(input_len: usize) -> Vec<u8> {
+    let mut padding = vec![0u8; KECCAK_RATE - input_len % KECCAK_RATE];
+    padding[0] = 0x01;
+    *padding.last_mut().unwrap() |= 0x80;
+    padding
+}
+
+fn keccak_f_native(a: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        let c: Vec<u64> =
+            (0..5).map(|x| a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20]).collect();
+        for (i, lane) in a.iter_mut().enumerate() {
+            *lane ^= c[(i + 4) % 5] ^ c[(i + 1) % 5].rotate_left(1);
+        }
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y] as u32);
+            }
+        }
+        for (i, lane) in a.iter_mut().enumerate() {
+            let (x, y) = (i % 5, i / 5);
+            *lane = b[i] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+        }
+        a[0] ^= rc;
+    }
+}
+
+/// Out of circuit Keccak-256
+pub fn keccak256_native(input: &[u8]) -> [u8; 32] {
+    let mut message = input.to_vec();
+    message.extend(pad(input.len()));
+
+    let mut a = [0u64; 25];
+    for block in message.chunks(KECCAK_RATE) {
+        for (lane, bytes) in a.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        keccak_f_native(&mut a);
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, lane) in out.chunks_mut(8).zip(a.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    out
+}
+
+// 64 little-endian bits
+type Lane<F> = Vec<AssignedValue<F>>;
+
+fn xor_lanes<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &[AssignedValue<F>],
+    b: &[AssignedValue<F>],
+) -> Result<Lane<F>, Error> {
+    a.iter().zip(b.iter()).map(|(a, b)| xor_bit(gate, ctx, &Existing(a), &Existing(b))).collect()
+}
+
+// bits of `a` rotated left by `n`
+fn rotate_left<F: FieldExt>(a: &[AssignedValue<F>], n: usize) -> Lane<F> {
+    (0..64).map(|i| a[(i + 64 - n) % 64].clone()).collect()
+}
+
+fn keccak_f<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    mut a: Vec<Lane<F>>,
+) -> Result<Vec<Lane<F>>, Error> {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = Vec::with_capacity(5);
+        for x in 0..5 {
+            let mut column = xor_lanes(gate, ctx, &a[x], &a[x + 5])?;
+            for y in 2..5 {
+                column = xor_lanes(gate, ctx, &column, &a[x + 5 * y])?;
+            }
+            c.push(column);
+        }
+        let mut d = Vec::with_capacity(5);
+        for x in 0..5 {
+            d.push(xor_lanes(gate, ctx, &c[(x + 4) % 5], &rotate_left(&c[(x + 1) % 5], 1))?);
+        }
+        let mut theta = Vec::with_capacity(25);
+        for (i, lane) in a.iter().enumerate() {
+            theta.push(xor_lanes(gate, ctx, lane, &d[i % 5])?);
+        }
+
+        // rho and pi only move bits
+        let mut b = vec![vec![]; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    rotate_left(&theta[x + 5 * y], ROTATIONS[x + 5 * y]);
+            }
+        }
+
+        // chi: b[x] ^ (!b[x + 1] & b[x + 2]), where `!u & v` is `u ? 0 : v`
+        a = Vec::with_capacity(25);
+        for i in 0..25 {
+            let (x, y) = (i % 5, i / 5);
+            let (u, v) = (&b[(x + 1) % 5 + 5 * y], &b[(x + 2) % 5 + 5 * y]);
+            let mut lane = Vec::with_capacity(64);
+            for z in 0..64 {
+                let and_not =
+                    gate.select(ctx, &Constant(F::zero()), &Existing(&v[z]), &Existing(&u[z]))?;
+                lane.push(xor_bit(gate, ctx, &Existing(&b[i][z]), &Existing(&and_not))?);
+            }
+            a.push(lane);
+        }
+
+        // iota flips the bits of the first lane where the round constant is 1
+        for z in 0..64 {
+            if (rc >> z) & 1 == 1 {
+                let flipped = gate.not(ctx, &Existing(&a[0][z]))?;
+                a[0][z] = flipped;
+            }
+        }
+    }
+    Ok(a)
+}
+
+// returns the digest after each block of `padded`, whose length is a multiple of the rate
+fn absorb_blocks<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    padded: &[AssignedValue<F>],
+) -> Result<Vec<Vec<AssignedValue<F>>>, Error> {
+    let gate = range.gate();
+    let zero = gate.load_zero(ctx)?;
+    let mut state: Vec<Lane<F>> = vec![vec![zero; 64]; 25];
+    let mut digests = Vec::with_capacity(padded.len() / KECCAK_RATE);
+    for (idx, block) in padded.chunks(KECCAK_RATE).enumerate() {
+        for (i, bytes) in block.chunks(8).enumerate() {
+            // lanes are little-endian, so their little-endian bits are those of their bytes in order
+            let mut bits = Vec::with_capacity(64);
+            for byte in bytes.iter() {
+                bits.extend(range.num_to_bits(ctx, byte, 8)?);
+            }
+            state[i] = if idx == 0 { bits } else { xor_lanes(gate, ctx, &state[i], &bits)? };
+        }
+        state = keccak_f(gate, ctx, state)?;
+
+        let mut digest = Vec::with_capacity(32);
+        for byte_bits in state[..4].iter().flat_map(|lane| lane.chunks(8)) {
+            digest.push(gate.bits_to_num(ctx, byte_bits, false)?);
+        }
+        digests.push(digest);
+    }
+    Ok(digests)
+}
+
+/// Input: bytes of a message of known length. Each byte is range checked to be in [0, 2^8).
+///
+/// Output: the 32 bytes of Keccak-256(`input`)
+///
+/// The padding only depends on `input.len()`, so it is added as constants.
+pub fn keccak256<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let gate = range.gate();
+    let padding = gate.assign_region_smart(
+        ctx,
+        pad(input.len()).into_iter().map(|b| Constant(F::from(b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    let padded: Vec<_> = input.iter().chain(padding.iter()).cloned().collect();
+    let out = absorb_blocks(range, ctx, &padded)?.pop().unwrap();
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("keccak256".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(out)
+}
+
+/// Input: a buffer `input` and the message length `len`, which is a witness.
+/// The message is `input[..len]`, bytes of `input` after the message are ignored.
+///
+/// Output: the 32 bytes of Keccak-256(`input[..len]`)
+///
+/// Constrains `len <= input.len()`. The circuit only depends on `input.len()`, so one circuit hashes messages of
+/// any length up to `input.len()`, at the cost of absorbing `input.len() / 136 + 1` blocks.
+pub fn keccak256_var_len<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let gate = range.gate();
+    let max_len = input.len();
+    let num_blocks = max_len / KECCAK_RATE + 1;
+    let padded_len = KECCAK_RATE * num_blocks;
+
+    let len_bits = (usize::BITS - (max_len + 1).leading_zeros()) as usize;
+    range.check_less_than_safe(ctx, len, max_len + 1, len_bits)?;
+
+    // is_len[i] = (i == len)
+    let is_len = range.idx_to_indicator_by_lookup(ctx, len, padded_len)?;
+
+    // is_message[i] = (i < len) = 1 - sum_{j <= i} is_len[j]
+    let mut is_message: Vec<AssignedValue<F>> = Vec::with_capacity(max_len);
+    for (i, ind) in is_len.iter().take(max_len).enumerate() {
+        let prev = if i == 0 { Constant(F::one()) } else { Existing(&is_message[i - 1]) };
+        let next = gate.sub(ctx, &prev, &Existing(ind))?;
+        is_message.push(next);
+    }
+
+    // block b is the last one iff 136 * b <= len < 136 * (b + 1)
+    let mut last_block = Vec::with_capacity(num_blocks);
+    for window in is_len.chunks(KECCAK_RATE) {
+        let ind = gate.inner_product(
+            ctx,
+            &window.iter().map(|x| Existing(x)).collect(),
+            &vec![Constant(F::one()); window.len()],
+        )?;
+        last_block.push(ind);
+    }
+
+    // padded[i] = is_message[i] * input[i] + is_len[i] * 0x01 + last_block[i / 136] * 0x80 for the last byte of
+    // each block, so a message ending one byte before the end of its block gets the single padding byte 0x81
+    let mut padded = Vec::with_capacity(padded_len);
+    for i in 0..padded_len {
+        let mut terms = vec![Existing(&is_len[i])];
+        let mut coeffs = vec![Constant(F::one())];
+        if i < max_len {
+            terms.push(Existing(&is_message[i]));
+            coeffs.push(Existing(&input[i]));
+        }
+        if i % KECCAK_RATE == KECCAK_RATE - 1 {
+            terms.push(Existing(&last_block[i / KECCAK_RATE]));
+            coeffs.push(Constant(F::from(0x80)));
+        }
+        let byte = gate.inner_product(ctx, &terms, &coeffs)?;
+        padded.push(byte);
+    }
+
+    // the digest after the last block of the padded message
+    let digests = absorb_blocks(range, ctx, &padded)?;
+    let mut out = Vec::with_capacity(32);
+    for j in 0..32 {
+        let byte = gate.inner_product(
+            ctx,
+            &digests.iter().map(|digest| Existing(&digest[j])).collect(),
+            &last_block.iter().map(|x| Existing(x)).collect(),
+        )?;
+        out.push(byte);
+    }
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("keccak256_var_len".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(out)
+}
@@ -0,0 +1,191 @@
+use super::{keccak256, keccak256_native, keccak256_var_len};
+use crate::{
+    gates::{
+        range::{RangeConfig, RangeStrategy},
+        GateInstructions,
+    },
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    halo2curves::bn256::Fr,
+    plonk::*,
+};
+use sha3::{Digest, Keccak256};
+
+#[derive(Default)]
+struct KeccakCircuit<F> {
+    input: Vec<Value<F>>,
+    // the message length, for variable length hashing of `input`
+    var_len: Option<Value<F>>,
+    expected: Vec<u8>,
+}
+
+const NUM_ADVICE: usize = 8;
+
+impl<F: FieldExt> Circuit<F> for KeccakCircuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            input: vec![Value::unknown(); self.input.len()],
+            var_len: self.var_len.map(|_| Value::unknown()),
+            expected: self.expected.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "keccak",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let input = config.gate.assign_region_smart(
+                    ctx,
+                    self.input.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let out = match self.var_len {
+                    None => keccak256(&config, ctx, &input)?,
+                    Some(len) => {
+                        let len = config.gate.assign_region_smart(
+                            ctx,
+                            vec![Witness(len)],
+                            vec![],
+                            vec![],
+                            vec![],
+                        )?;
+                        keccak256_var_len(&config, ctx, &input, &len[0])?
+                    }
+                };
+                for (byte, expected) in out.iter().zip(self.expected.iter()) {
+                    config.gate.assert_is_const(ctx, byte, F::from(*expected as u64));
+                }
+
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_keccak256_native() {
+    for (input, expected) in [
+        ("", "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"),
+        ("abc", "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+    ] {
+        assert_eq!(hex(&keccak256_native(input.as_bytes())), expected);
+    }
+    // around the block boundaries, where the padding is the single byte 0x81 or a whole block
+    for len in [0, 1, 134, 135, 136, 137, 271, 272, 300] {
+        let input: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        assert_eq!(keccak256_native(&input)[..], Keccak256::digest(&input)[..]);
+    }
+}
+
+fn run(input: &[u8], k: u32) {
+    let circuit = KeccakCircuit::<Fr> {
+        input: input.iter().map(|b| Value::known(Fr::from(*b as u64))).collect(),
+        var_len: None,
+        expected: keccak256_native(input).to_vec(),
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_keccak256_one_block() {
+    run(b"abc", 18);
+}
+
+#[test]
+fn test_keccak256_two_blocks() {
+    // 136 bytes leave no room for the padding in the first block
+    run(&[0x61; 136], 19);
+}
+
+fn run_var_len(message: &[u8], max_len: usize, expected: &[u8]) -> Result<(), Vec<VerifyFailure>> {
+    // the buffer is filled up to `max_len` with bytes that must not affect the hash
+    let mut buffer = message.to_vec();
+    buffer.resize(max_len, 0xff);
+    let circuit = KeccakCircuit::<Fr> {
+        input: buffer.iter().map(|b| Value::known(Fr::from(*b as u64))).collect(),
+        var_len: Some(Value::known(Fr::from(message.len() as u64))),
+        expected: expected.to_vec(),
+    };
+    MockProver::run(19, &circuit, vec![]).unwrap().verify()
+}
+
+#[test]
+fn test_keccak256_var_len() {
+    // with max_len = 136 the padded message has one or two blocks
+    for len in [0, 3, 135, 136] {
+        let message = vec![0x61; len];
+        assert_eq!(run_var_len(&message, 136, &keccak256_native(&message)), Ok(()));
+    }
+}
+
+#[test]
+fn test_keccak256_var_len_wrong_digest() {
+    let mut expected = keccak256_native(b"abc");
+    expected[31] ^= 1;
+    assert!(run_var_len(b"abc", 136, &expected).is_err());
+}
+
+#[test]
+fn test_keccak256_var_len_past_buffer() {
+    // a length past the end of the buffer would hash implicit zero bytes
+    let mut message = vec![0x61; 136];
+    message.push(0);
+    let circuit = KeccakCircuit::<Fr> {
+        input: vec![Value::known(Fr::from(0x61)); 136],
+        var_len: Some(Value::known(Fr::from(137))),
+        expected: keccak256_native(&message).to_vec(),
+    };
+    assert!(MockProver::run(19, &circuit, vec![]).unwrap().verify().is_err());
+}
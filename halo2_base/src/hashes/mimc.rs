@@ -0,0 +1,138 @@
+use super::AlgebraicHasher;
+use crate::{
+    gates::GateInstructions,
+    utils::{biguint_to_fe, modulus},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+const SEED: &str = "mimcsponge";
+/// Number of Feistel rounds used by circomlib's `MiMCSponge`
+pub const MIMC_SPONGE_ROUNDS: usize = 220;
+
+/// The MiMC Feistel sponge of circomlib's `MiMCSponge(nInputs, nRounds, nOutputs)` with S-box `x^5`
+///
+/// The round constants are generated as in circomlibjs: `c = keccak256("mimcsponge")`, then `c = keccak256(c)`
+/// and `round_constants[i] = c mod p` for `0 < i < n_rounds - 1`, while the first and last constants are zero.
+/// For circom compatibility `F` must be the BN254 scalar field and `n_rounds = 220`.
+#[derive(Clone, Debug)]
+pub struct MimcSponge<F: FieldExt> {
+    pub round_constants: Vec<F>,
+    pub key: F,
+}
+
+impl<F: FieldExt> MimcSponge<F> {
+    pub fn new(n_rounds: usize) -> Self {
+        Self::with_key(n_rounds, F::zero())
+    }
+
+    pub fn with_key(n_rounds: usize, key: F) -> Self {
+        assert!(n_rounds > 1);
+        let p = modulus::<F>();
+        let mut round_constants = vec![F::zero(); n_rounds];
+        let mut c = Keccak256::digest(SEED.as_bytes());
+        for constant in round_constants.iter_mut().take(n_rounds - 1).skip(1) {
+            c = Keccak256::digest(c);
+            *constant = biguint_to_fe(&(BigUint::from_bytes_be(&c) % &p));
+        }
+        Self { round_constants, key }
+    }
+
+    fn feistel_native(&self, mut xl: F, mut xr: F) -> (F, F) {
+        let n_rounds = self.round_constants.len();
+        for (i, c) in self.round_constants.iter().enumerate() {
+            let t = self.key + xl + c;
+            let t5 = t.square().square() * t;
+            if i < n_rounds - 1 {
+                (xl, xr) = (xr + t5, xl);
+            } else {
+                xr += t5;
+            }
+        }
+        (xl, xr)
+    }
+
+    /// Out of circuit `MiMCSponge` with `inputs.len()` inputs and `n_outputs` outputs
+    pub fn hash_multi_native(&self, inputs: &[F], n_outputs: usize) -> Vec<F> {
+        let (mut xl, mut xr) = (F::zero(), F::zero());
+        for input in inputs {
+            (xl, xr) = self.feistel_native(xl + input, xr);
+        }
+        let mut outputs = vec![xl];
+        for _ in 1..n_outputs {
+            (xl, xr) = self.feistel_native(xl, xr);
+            outputs.push(xl);
+        }
+        outputs
+    }
+
+    fn feistel(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        xl: AssignedValue<F>,
+        xr: AssignedValue<F>,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        let n_rounds = self.round_constants.len();
+        let (mut xl, mut xr) = (xl, xr);
+        for (i, c) in self.round_constants.iter().enumerate() {
+            let t = gate.add(ctx, &Existing(&xl), &Constant(self.key + c))?;
+            let t2 = gate.mul(ctx, &Existing(&t), &Existing(&t))?;
+            let t4 = gate.mul(ctx, &Existing(&t2), &Existing(&t2))?;
+            // xr + t^5
+            let out = gate.mul_add(ctx, &Existing(&t4), &Existing(&t), &Existing(&xr))?;
+            if i < n_rounds - 1 {
+                (xl, xr) = (out, xl);
+            } else {
+                xr = out;
+            }
+        }
+        Ok((xl, xr))
+    }
+
+    /// In circuit `MiMCSponge` with `inputs.len()` inputs and `n_outputs` outputs
+    pub fn hash_multi(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        inputs: &[AssignedValue<F>],
+        n_outputs: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let mut xl = gate.load_zero(ctx)?;
+        let mut xr = xl.clone();
+        for input in inputs {
+            let xl_in = gate.add(ctx, &Existing(&xl), &Existing(input))?;
+            (xl, xr) = self.feistel(ctx, gate, xl_in, xr)?;
+        }
+        let mut outputs = vec![xl.clone()];
+        for _ in 1..n_outputs {
+            (xl, xr) = self.feistel(ctx, gate, xl, xr)?;
+            outputs.push(xl.clone());
+        }
+        Ok(outputs)
+    }
+}
+
+impl<F: FieldExt> Default for MimcSponge<F> {
+    fn default() -> Self {
+        Self::new(MIMC_SPONGE_ROUNDS)
+    }
+}
+
+impl<F: FieldExt> AlgebraicHasher<F> for MimcSponge<F> {
+    fn hash_native(&self, inputs: &[F]) -> F {
+        self.hash_multi_native(inputs, 1)[0]
+    }
+
+    fn hash(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        inputs: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        Ok(self.hash_multi(ctx, gate, inputs, 1)?.swap_remove(0))
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{gates::GateInstructions, AssignedValue, Context};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+pub mod hmac;
+pub mod keccak;
+pub mod mimc;
+pub mod poseidon;
+pub mod poseidon2;
+pub mod ripemd160;
+pub mod sha256;
+mod word32;
+
+#[cfg(test)]
+mod tests;
+
+/// A hash of native field elements to a native field element, with an in-circuit version that only uses `GateInstructions`
+///
+/// Gadgets that only need a collision resistant hash (e.g. Merkle trees) can be generic over this trait.
+pub trait AlgebraicHasher<F: FieldExt> {
+    /// Out of circuit hash, to be used by the prover
+    fn hash_native(&self, inputs: &[F]) -> F;
+
+    /// Constrains the output to equal `hash_native` of the values of `inputs`
+    fn hash(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        inputs: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error>;
+}
@@ -0,0 +1,344 @@
+use super::AlgebraicHasher;
+use crate::{
+    gates::GateInstructions,
+    utils::{biguint_to_fe, modulus},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+#[cfg(test)]
+mod tests;
+
+// Grain LFSR used to generate the round constants, as in the reference implementation of Poseidon
+// https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/generate_parameters_grain.sage
+pub(crate) struct Grain {
+    state: Vec<bool>,
+}
+
+impl Grain {
+    pub(crate) fn new(field_bits: usize, t: usize, r_f: usize, r_p: usize) -> Self {
+        let mut state = Vec::with_capacity(80);
+        // | field = 1 | sbox = 0 | field_bits | t | r_f | r_p | 1 ... 1 |
+        for (value, bits) in [
+            (1u64, 2),
+            (0, 4),
+            (field_bits as u64, 12),
+            (t as u64, 12),
+            (r_f as u64, 10),
+            (r_p as u64, 10),
+            ((1 << 30) - 1, 30),
+        ] {
+            state.extend((0..bits).rev().map(|i| (value >> i) & 1 == 1));
+        }
+        let mut grain = Self { state };
+        for _ in 0..160 {
+            grain.next_bit();
+        }
+        grain
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.remove(0);
+        self.state.push(bit);
+        bit
+    }
+
+    // bits are produced in pairs: if the first bit is 1 we output the second bit, otherwise we discard it
+    fn next_output_bit(&mut self) -> bool {
+        loop {
+            let first = self.next_bit();
+            let second = self.next_bit();
+            if first {
+                return second;
+            }
+        }
+    }
+
+    // samples `field_bits` bits in big endian order
+    fn next_bits(&mut self, field_bits: u64) -> BigUint {
+        let mut x = BigUint::zero();
+        for _ in 0..field_bits {
+            x = (x << 1usize) + BigUint::from(self.next_output_bit() as u8);
+        }
+        x
+    }
+
+    // samples `field_bits` bits in big endian order and rejects values `>= modulus`
+    pub(crate) fn next_field_element<F: FieldExt>(&mut self) -> F {
+        let p = modulus::<F>();
+        loop {
+            let x = self.next_bits(p.bits());
+            if x < p {
+                return biguint_to_fe(&x);
+            }
+        }
+    }
+
+    // samples `field_bits` bits in big endian order and reduces them mod `modulus`, as for the MDS matrix
+    fn next_field_element_reduced<F: FieldExt>(&mut self) -> F {
+        let p = modulus::<F>();
+        biguint_to_fe(&(self.next_bits(p.bits()) % p))
+    }
+}
+
+/// Parameters of the Poseidon permutation with state width `T = RATE + 1` and S-box `x^5`
+///
+/// The round constants and then the MDS matrix are generated by the Grain LFSR as in the reference implementation:
+/// the MDS matrix is the Cauchy matrix `mds[i][j] = 1 / (x_i + y_j)` for the next `2 T` samples `x_0, .., y_{T-1}`,
+/// which are sampled again while they are not distinct or some `x_i + y_j` is zero. The reference also samples
+/// again matrices that fail its security checks, which are not implemented here. The first matrices for `T = 3` and
+/// `T = 5` over BN254 pass them, so these widths match the reference test vectors.
+#[derive(Clone, Debug)]
+pub struct PoseidonSpec<F: FieldExt, const T: usize, const RATE: usize> {
+    pub r_f: usize,
+    pub r_p: usize,
+    pub round_constants: Vec<[F; T]>,
+    pub mds: [[F; T]; T],
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonSpec<F, T, RATE> {
+    pub fn new(r_f: usize, r_p: usize) -> Self {
+        assert_eq!(RATE + 1, T);
+        assert_eq!(r_f % 2, 0);
+        let mut grain = Grain::new(modulus::<F>().bits() as usize, T, r_f, r_p);
+        let round_constants = (0..r_f + r_p)
+            .map(|_| {
+                let mut constants = [F::zero(); T];
+                for c in constants.iter_mut() {
+                    *c = grain.next_field_element();
+                }
+                constants
+            })
+            .collect();
+
+        let mds = loop {
+            let samples: Vec<F> = (0..2 * T).map(|_| grain.next_field_element_reduced()).collect();
+            let (xs, ys) = samples.split_at(T);
+            let distinct = samples.iter().enumerate().all(|(i, x)| !samples[..i].contains(x));
+            if !distinct || xs.iter().any(|x| ys.iter().any(|y| *x + y == F::zero())) {
+                continue;
+            }
+            let mut mds = [[F::zero(); T]; T];
+            for (row, x) in mds.iter_mut().zip(xs.iter()) {
+                for (entry, y) in row.iter_mut().zip(ys.iter()) {
+                    *entry = (*x + y).invert().unwrap();
+                }
+            }
+            break mds;
+        };
+        Self { r_f, r_p, round_constants, mds }
+    }
+
+    fn is_full_round(&self, round: usize) -> bool {
+        round < self.r_f / 2 || round >= self.r_f / 2 + self.r_p
+    }
+
+    /// Applies the permutation to `state` out of circuit
+    pub fn permute(&self, state: &mut [F; T]) {
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (s, c) in state.iter_mut().zip(constants.iter()) {
+                *s += c;
+            }
+            if self.is_full_round(round) {
+                for s in state.iter_mut() {
+                    *s = s.square().square() * *s;
+                }
+            } else {
+                state[0] = state[0].square().square() * state[0];
+            }
+            let old = *state;
+            for (s, row) in state.iter_mut().zip(self.mds.iter()) {
+                *s = row.iter().zip(old.iter()).fold(F::zero(), |acc, (m, x)| acc + *m * x);
+            }
+        }
+    }
+}
+
+// initial capacity element of the variable length sponge
+pub(crate) fn initial_state<F: FieldExt, const T: usize>() -> [F; T] {
+    let mut state = [F::zero(); T];
+    state[0] = F::from_u128(1u128 << 64);
+    state
+}
+
+/// Out of circuit Poseidon sponge, to be used by the prover
+///
+/// Inputs are absorbed `RATE` at a time into `state[1..]`. On `squeeze` the remaining inputs followed by a `1` are absorbed,
+/// the state is permuted and `state[1]` is returned.
+#[derive(Clone, Debug)]
+pub struct PoseidonSponge<F: FieldExt, const T: usize, const RATE: usize> {
+    spec: PoseidonSpec<F, T, RATE>,
+    state: [F; T],
+    absorbing: Vec<F>,
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonSponge<F, T, RATE> {
+    pub fn new(r_f: usize, r_p: usize) -> Self {
+        Self::from_spec(PoseidonSpec::new(r_f, r_p))
+    }
+
+    pub fn from_spec(spec: PoseidonSpec<F, T, RATE>) -> Self {
+        Self { spec, state: initial_state(), absorbing: Vec::with_capacity(RATE) }
+    }
+
+    pub fn update(&mut self, inputs: &[F]) {
+        for input in inputs {
+            self.absorbing.push(*input);
+            if self.absorbing.len() == RATE {
+                for (s, x) in self.state.iter_mut().skip(1).zip(self.absorbing.iter()) {
+                    *s += x;
+                }
+                self.spec.permute(&mut self.state);
+                self.absorbing.clear();
+            }
+        }
+    }
+
+    pub fn squeeze(&mut self) -> F {
+        self.absorbing.push(F::one());
+        for (s, x) in self.state.iter_mut().skip(1).zip(self.absorbing.iter()) {
+            *s += x;
+        }
+        self.spec.permute(&mut self.state);
+        self.absorbing.clear();
+        self.state[1]
+    }
+}
+
+/// In circuit Poseidon sponge with the same behavior as `PoseidonSponge`
+#[derive(Clone, Debug)]
+pub struct PoseidonChip<F: FieldExt, const T: usize, const RATE: usize> {
+    spec: PoseidonSpec<F, T, RATE>,
+    state: Vec<AssignedValue<F>>,
+    absorbing: Vec<AssignedValue<F>>,
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE> {
+    pub fn new(
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        r_f: usize,
+        r_p: usize,
+    ) -> Result<Self, Error> {
+        Self::from_spec(ctx, gate, PoseidonSpec::new(r_f, r_p))
+    }
+
+    pub fn from_spec(
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        spec: PoseidonSpec<F, T, RATE>,
+    ) -> Result<Self, Error> {
+        let state = gate.assign_region_smart(
+            ctx,
+            initial_state::<F, T>().iter().map(|x| Constant(*x)).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(Self { spec, state, absorbing: Vec::with_capacity(RATE) })
+    }
+
+    pub fn update(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        inputs: &[AssignedValue<F>],
+    ) -> Result<(), Error> {
+        for input in inputs {
+            self.absorbing.push(input.clone());
+            if self.absorbing.len() == RATE {
+                let chunk = std::mem::take(&mut self.absorbing);
+                self.absorb(ctx, gate, &chunk, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn squeeze(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let chunk = std::mem::take(&mut self.absorbing);
+        self.absorb(ctx, gate, &chunk, true)?;
+        Ok(self.state[1].clone())
+    }
+
+    // adds `chunk` (followed by a `1` if `pad`) to `state[1..]` and permutes
+    fn absorb(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        chunk: &[AssignedValue<F>],
+        pad: bool,
+    ) -> Result<(), Error> {
+        for (i, x) in chunk.iter().enumerate() {
+            self.state[i + 1] = gate.add(ctx, &Existing(&self.state[i + 1]), &Existing(x))?;
+        }
+        if pad {
+            let i = chunk.len() + 1;
+            self.state[i] = gate.add(ctx, &Existing(&self.state[i]), &Constant(F::one()))?;
+        }
+        self.permute(ctx, gate)
+    }
+
+    fn permute(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+    ) -> Result<(), Error> {
+        for round in 0..self.spec.r_f + self.spec.r_p {
+            let constants = self.spec.round_constants[round];
+            let mut state = Vec::with_capacity(T);
+            for (i, (s, c)) in self.state.iter().zip(constants.iter()).enumerate() {
+                let s = gate.add(ctx, &Existing(s), &Constant(*c))?;
+                let s = if i == 0 || self.spec.is_full_round(round) {
+                    let s2 = gate.mul(ctx, &Existing(&s), &Existing(&s))?;
+                    let s4 = gate.mul(ctx, &Existing(&s2), &Existing(&s2))?;
+                    gate.mul(ctx, &Existing(&s4), &Existing(&s))?
+                } else {
+                    s
+                };
+                state.push(s);
+            }
+            let mut next = Vec::with_capacity(T);
+            for row in self.spec.mds.iter() {
+                let s = gate.inner_product(
+                    ctx,
+                    &state.iter().map(|s| Existing(s)).collect(),
+                    &row.iter().map(|m| Constant(*m)).collect(),
+                )?;
+                next.push(s);
+            }
+            self.state = next;
+        }
+        Ok(())
+    }
+}
+
+// each hash uses a fresh sponge, absorbing all of `inputs` and squeezing once
+impl<F: FieldExt, const T: usize, const RATE: usize> AlgebraicHasher<F>
+    for PoseidonSpec<F, T, RATE>
+{
+    fn hash_native(&self, inputs: &[F]) -> F {
+        let mut sponge = PoseidonSponge::from_spec(self.clone());
+        sponge.update(inputs);
+        sponge.squeeze()
+    }
+
+    fn hash(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        inputs: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        let mut chip = PoseidonChip::from_spec(ctx, gate, self.clone())?;
+        chip.update(ctx, gate, inputs)?;
+        chip.squeeze(ctx, gate)
+    }
+}
@@ -0,0 +1,137 @@
+use super::{PoseidonChip, PoseidonSponge};
+use crate::{
+    gates::{
+        flex_gate::{FlexGateConfig, GateStrategy},
+        GateInstructions,
+    },
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt, circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*,
+};
+
+const T: usize = 3;
+const RATE: usize = 2;
+const R_F: usize = 8;
+const R_P: usize = 57;
+
+#[derive(Default)]
+struct PoseidonCircuit<F> {
+    inputs: Vec<Value<F>>,
+    // number of inputs to absorb before each squeeze
+    chunks: Vec<usize>,
+    // challenges computed by the native sponge
+    expected: Vec<F>,
+}
+
+const NUM_ADVICE: usize = 1;
+
+impl<F: FieldExt> Circuit<F> for PoseidonCircuit<F> {
+    type Config = FlexGateConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            inputs: vec![Value::unknown(); self.inputs.len()],
+            chunks: self.chunks.clone(),
+            expected: self.expected.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FlexGateConfig::configure(
+            meta,
+            GateStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "poseidon",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let inputs = config.assign_region_smart(
+                    ctx,
+                    self.inputs.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+
+                let mut chip = PoseidonChip::<F, T, RATE>::new(ctx, &config, R_F, R_P)?;
+                let mut start = 0;
+                for (len, expected) in self.chunks.iter().zip(self.expected.iter()) {
+                    chip.update(ctx, &config, &inputs[start..start + len])?;
+                    start += len;
+                    let challenge = chip.squeeze(ctx, &config)?;
+                    config.assert_is_const(ctx, &challenge, *expected);
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_poseidon_chip() {
+    let k = 15;
+    let chunks = vec![0, 1, 2, 5];
+    let inputs: Vec<Fr> = (0..chunks.iter().sum::<usize>() as u64).map(Fr::from).collect();
+
+    let mut native = PoseidonSponge::<Fr, T, RATE>::new(R_F, R_P);
+    let mut start = 0;
+    let expected = chunks
+        .iter()
+        .map(|len| {
+            native.update(&inputs[start..start + len]);
+            start += len;
+            native.squeeze()
+        })
+        .collect();
+
+    let circuit = PoseidonCircuit::<Fr> {
+        inputs: inputs.into_iter().map(Value::known).collect(),
+        chunks,
+        expected,
+    };
+
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_poseidon_sponge_is_deterministic() {
+    let mut a = PoseidonSponge::<Fr, T, RATE>::new(R_F, R_P);
+    let mut b = PoseidonSponge::<Fr, T, RATE>::new(R_F, R_P);
+    a.update(&[Fr::from(1), Fr::from(2), Fr::from(3)]);
+    b.update(&[Fr::from(1), Fr::from(2)]);
+    b.update(&[Fr::from(3)]);
+    assert_eq!(a.squeeze(), b.squeeze());
+    // absorbing a different input changes the output
+    b.update(&[Fr::from(4)]);
+    a.update(&[Fr::from(5)]);
+    assert_ne!(a.squeeze(), b.squeeze());
+}
@@ -0,0 +1,248 @@
+use super::{
+    poseidon::{initial_state, Grain},
+    AlgebraicHasher,
+};
+use crate::{
+    gates::GateInstructions,
+    utils::modulus,
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+/// Parameters of the Poseidon2 permutation with state width `T = RATE + 1` and S-box `x^5`
+///
+/// As in the reference implementation, round constants are generated in round order by the same Grain LFSR as
+/// Poseidon, with `T` constants for each full round and a single one, for the first state element, per partial round.
+/// The external matrix is `circ(2, 1, .., 1)` for `T <= 3` and built from `M4` blocks for `T` divisible by 4.
+/// The internal matrix is `1 + diag(internal_diag_m_1)`, where `1` is the all ones matrix.
+/// For BN254 with `T = 3` the reference parameters are `r_f = 8, r_p = 56, internal_diag_m_1 = [1, 1, 2]`.
+#[derive(Clone, Debug)]
+pub struct Poseidon2Spec<F: FieldExt, const T: usize, const RATE: usize> {
+    pub r_f: usize,
+    pub r_p: usize,
+    pub external_round_constants: Vec<[F; T]>,
+    pub internal_round_constants: Vec<F>,
+    pub internal_diag_m_1: [F; T],
+}
+
+const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+impl<F: FieldExt, const T: usize, const RATE: usize> Poseidon2Spec<F, T, RATE> {
+    pub fn new(r_f: usize, r_p: usize, internal_diag_m_1: [F; T]) -> Self {
+        assert_eq!(RATE + 1, T);
+        assert_eq!(r_f % 2, 0);
+        assert!(T <= 3 || T % 4 == 0);
+        let mut grain = Grain::new(modulus::<F>().bits() as usize, T, r_f, r_p);
+        let mut external_round_constants = Vec::with_capacity(r_f);
+        let mut internal_round_constants = Vec::with_capacity(r_p);
+        for round in 0..r_f + r_p {
+            if round < r_f / 2 || round >= r_f / 2 + r_p {
+                let mut constants = [F::zero(); T];
+                for c in constants.iter_mut() {
+                    *c = grain.next_field_element();
+                }
+                external_round_constants.push(constants);
+            } else {
+                internal_round_constants.push(grain.next_field_element());
+            }
+        }
+        Self { r_f, r_p, external_round_constants, internal_round_constants, internal_diag_m_1 }
+    }
+
+    fn external_matrix(&self) -> [[F; T]; T] {
+        let mut m = [[F::zero(); T]; T];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = if T <= 3 {
+                    F::from(1 + (i == j) as u64)
+                } else {
+                    // diagonal blocks are 2 * M4, the others are M4
+                    F::from(M4[i % 4][j % 4] * (1 + (i / 4 == j / 4) as u64))
+                };
+            }
+        }
+        m
+    }
+
+    fn external_linear_layer_native(&self, m: &[[F; T]; T], state: &mut [F; T]) {
+        let old = *state;
+        for (s, row) in state.iter_mut().zip(m.iter()) {
+            *s = row.iter().zip(old.iter()).fold(F::zero(), |acc, (m, x)| acc + *m * x);
+        }
+    }
+
+    fn internal_linear_layer_native(&self, state: &mut [F; T]) {
+        let sum = state.iter().fold(F::zero(), |acc, x| acc + x);
+        for (s, d) in state.iter_mut().zip(self.internal_diag_m_1.iter()) {
+            *s = *s * d + sum;
+        }
+    }
+
+    /// Applies the permutation to `state` out of circuit
+    pub fn permute(&self, state: &mut [F; T]) {
+        let m = self.external_matrix();
+        let sbox = |x: F| x.square().square() * x;
+        let (first, last) = self.external_round_constants.split_at(self.r_f / 2);
+
+        self.external_linear_layer_native(&m, state);
+        for constants in first {
+            for (s, c) in state.iter_mut().zip(constants.iter()) {
+                *s = sbox(*s + c);
+            }
+            self.external_linear_layer_native(&m, state);
+        }
+        for c in self.internal_round_constants.iter() {
+            state[0] = sbox(state[0] + c);
+            self.internal_linear_layer_native(state);
+        }
+        for constants in last {
+            for (s, c) in state.iter_mut().zip(constants.iter()) {
+                *s = sbox(*s + c);
+            }
+            self.external_linear_layer_native(&m, state);
+        }
+    }
+
+    fn sbox(
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        x: &AssignedValue<F>,
+        c: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        let x = gate.add(ctx, &Existing(x), &Constant(c))?;
+        let x2 = gate.mul(ctx, &Existing(&x), &Existing(&x))?;
+        let x4 = gate.mul(ctx, &Existing(&x2), &Existing(&x2))?;
+        gate.mul(ctx, &Existing(&x4), &Existing(&x))
+    }
+
+    fn sum(
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        state: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        let sum = gate.inner_product(
+            ctx,
+            &state.iter().map(|x| Existing(x)).collect(),
+            &vec![Constant(F::one()); state.len()],
+        )?;
+        Ok(sum)
+    }
+
+    fn external_linear_layer(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        m: &[[F; T]; T],
+        state: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        if T <= 3 {
+            // circ(2, 1, .., 1) * x = x + sum(x)
+            let sum = Self::sum(ctx, gate, state)?;
+            state.iter().map(|x| gate.add(ctx, &Existing(x), &Existing(&sum))).collect()
+        } else {
+            m.iter()
+                .map(|row| {
+                    let out = gate.inner_product(
+                        ctx,
+                        &state.iter().map(|x| Existing(x)).collect(),
+                        &row.iter().map(|m| Constant(*m)).collect(),
+                    )?;
+                    Ok(out)
+                })
+                .collect()
+        }
+    }
+
+    fn internal_linear_layer(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        state: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let sum = Self::sum(ctx, gate, state)?;
+        state
+            .iter()
+            .zip(self.internal_diag_m_1.iter())
+            .map(|(x, d)| gate.mul_add(ctx, &Existing(x), &Constant(*d), &Existing(&sum)))
+            .collect()
+    }
+
+    /// Applies the permutation to `state` in circuit
+    pub fn permute_assigned(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        state: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert_eq!(state.len(), T);
+        let m = self.external_matrix();
+        let (first, last) = self.external_round_constants.split_at(self.r_f / 2);
+
+        let mut state = self.external_linear_layer(ctx, gate, &m, state)?;
+        for constants in first {
+            let sboxed = state
+                .iter()
+                .zip(constants.iter())
+                .map(|(x, c)| Self::sbox(ctx, gate, x, *c))
+                .collect::<Result<Vec<_>, _>>()?;
+            state = self.external_linear_layer(ctx, gate, &m, &sboxed)?;
+        }
+        for c in self.internal_round_constants.iter() {
+            state[0] = Self::sbox(ctx, gate, &state[0], *c)?;
+            state = self.internal_linear_layer(ctx, gate, &state)?;
+        }
+        for constants in last {
+            let sboxed = state
+                .iter()
+                .zip(constants.iter())
+                .map(|(x, c)| Self::sbox(ctx, gate, x, *c))
+                .collect::<Result<Vec<_>, _>>()?;
+            state = self.external_linear_layer(ctx, gate, &m, &sboxed)?;
+        }
+        Ok(state)
+    }
+}
+
+// Hashing uses the same sponge as `PoseidonSponge`: the capacity element is initialized to `2^64`, inputs followed
+// by a `1` are absorbed `RATE` at a time into `state[1..]` and the output is `state[1]`
+impl<F: FieldExt, const T: usize, const RATE: usize> AlgebraicHasher<F>
+    for Poseidon2Spec<F, T, RATE>
+{
+    fn hash_native(&self, inputs: &[F]) -> F {
+        let mut state = initial_state::<F, T>();
+        let mut padded = inputs.to_vec();
+        padded.push(F::one());
+        for chunk in padded.chunks(RATE) {
+            for (s, x) in state.iter_mut().skip(1).zip(chunk.iter()) {
+                *s += x;
+            }
+            self.permute(&mut state);
+        }
+        state[1]
+    }
+
+    fn hash(
+        &self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        inputs: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        let mut state = gate.assign_region_smart(
+            ctx,
+            initial_state::<F, T>().iter().map(|x| Constant(*x)).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        let mut padded: Vec<QuantumCell<F>> = inputs.iter().map(|x| Existing(x)).collect();
+        padded.push(Constant(F::one()));
+        for chunk in padded.chunks(RATE) {
+            for (i, x) in chunk.iter().enumerate() {
+                state[i + 1] = gate.add(ctx, &Existing(&state[i + 1]), x)?;
+            }
+            state = self.permute_assigned(ctx, gate, &state)?;
+        }
+        Ok(state.swap_remove(1))
+    }
+}
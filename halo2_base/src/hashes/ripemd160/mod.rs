@@ -0,0 +1,341 @@
+use super::word32::{
+    bits_to_word, load_constant_word, pad_var_len, pow2, rotate_left, select_state, sum_mod_word,
+    xor_bit, Word,
+};
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+// message word selection, rotation amounts and additive constants of the left and right lines
+// https://homes.esat.kuleuven.be/~bosselae/ripemd160.html
+const R_LEFT: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5,
+    2, 14, 11, 8, 3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, 1, 9, 11, 10, 0, 8, 12, 4,
+    13, 3, 7, 15, 14, 5, 6, 2, 4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const R_RIGHT: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, 6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12,
+    4, 9, 1, 2, 15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, 8, 6, 4, 1, 3, 11, 15, 0, 5,
+    12, 2, 13, 9, 7, 10, 14, 12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+const S_LEFT: [usize; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, 7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15,
+    9, 11, 7, 13, 12, 11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, 11, 12, 14, 15, 14,
+    15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, 9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const S_RIGHT: [usize; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, 9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12,
+    7, 6, 15, 13, 11, 9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, 15, 5, 8, 11, 14, 14,
+    6, 14, 6, 9, 12, 9, 12, 5, 15, 8, 8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+const K_LEFT: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K_RIGHT: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+const INITIAL_STATE: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+// the boolean function used in round `j / 16` of the left line; the right line uses them in reverse order
+fn f_native(round: usize, x: u32, y: u32, z: u32) -> u32 {
+    match round {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        3 => (x & z) | (y & !z),
+        4 => x ^ (y | !z),
+        _ => unreachable!(),
+    }
+}
+
+// pads `input` with `0x80`, zeros and the little-endian bit length to a multiple of 64 bytes
+fn pad(input_len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80u8];
+    while (input_len + padding.len()) % 64 != 56 {
+        padding.push(0);
+    }
+    padding.extend_from_slice(&((input_len as u64) * 8).to_le_bytes());
+    padding
+}
+
+/// Out of circuit RIPEMD-160
+pub fn ripemd160_native(input: &[u8]) -> [u8; 20] {
+    let mut message = input.to_vec();
+    message.extend(pad(input.len()));
+
+    let mut h = INITIAL_STATE;
+    for block in message.chunks(64) {
+        let x: Vec<u32> =
+            block.chunks(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+        let [mut al, mut bl, mut cl, mut dl, mut el] = h;
+        let [mut ar, mut br, mut cr, mut dr, mut er] = h;
+        for j in 0..80 {
+            let round = j / 16;
+            let t = al
+                .wrapping_add(f_native(round, bl, cl, dl))
+                .wrapping_add(x[R_LEFT[j]])
+                .wrapping_add(K_LEFT[round])
+                .rotate_left(S_LEFT[j] as u32)
+                .wrapping_add(el);
+            (al, el, dl, cl, bl) = (el, dl, cl.rotate_left(10), bl, t);
+            let t = ar
+                .wrapping_add(f_native(4 - round, br, cr, dr))
+                .wrapping_add(x[R_RIGHT[j]])
+                .wrapping_add(K_RIGHT[round])
+                .rotate_left(S_RIGHT[j] as u32)
+                .wrapping_add(er);
+            (ar, er, dr, cr, br) = (er, dr, cr.rotate_left(10), br, t);
+        }
+        h = [
+            h[1].wrapping_add(cl).wrapping_add(dr),
+            h[2].wrapping_add(dl).wrapping_add(er),
+            h[3].wrapping_add(el).wrapping_add(ar),
+            h[4].wrapping_add(al).wrapping_add(br),
+            h[0].wrapping_add(bl).wrapping_add(cr),
+        ];
+    }
+
+    let mut out = [0u8; 20];
+    for (chunk, word) in out.chunks_mut(4).zip(h.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+// returns the bits of `f_round(x, y, z)`
+fn f_bits<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    round: usize,
+    x: &Word<F>,
+    y: &Word<F>,
+    z: &Word<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut out = Vec::with_capacity(32);
+    for ((x, y), z) in x.bits.iter().zip(y.bits.iter()).zip(z.bits.iter()) {
+        let (x, y, z) = (Existing(x), Existing(y), Existing(z));
+        let bit = match round {
+            0 => {
+                let t = xor_bit(gate, ctx, &x, &y)?;
+                xor_bit(gate, ctx, &Existing(&t), &z)?
+            }
+            // (x & y) | (!x & z) = x ? y : z
+            1 => gate.select(ctx, &y, &z, &x)?,
+            2 => {
+                let not_y = gate.not(ctx, &y)?;
+                let t = gate.or(ctx, &x, &Existing(&not_y))?;
+                xor_bit(gate, ctx, &Existing(&t), &z)?
+            }
+            // (x & z) | (y & !z) = z ? x : y
+            3 => gate.select(ctx, &x, &y, &z)?,
+            4 => {
+                let not_z = gate.not(ctx, &z)?;
+                let t = gate.or(ctx, &y, &Existing(&not_z))?;
+                xor_bit(gate, ctx, &x, &Existing(&t))?
+            }
+            _ => unreachable!(),
+        };
+        out.push(bit);
+    }
+    Ok(out)
+}
+
+// one step of either line: returns `rol(a + f(b, c, d) + x + k, s) + e`
+fn step<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    round: usize,
+    state: &[Word<F>; 5],
+    x: &Word<F>,
+    k: u32,
+    s: usize,
+) -> Result<Word<F>, Error> {
+    let [a, b, c, d, e] = state;
+    let f = f_bits(range.gate(), ctx, round, b, c, d)?;
+
+    let mut terms = vec![Existing(&a.value), Existing(&x.value), Constant(F::from(k as u64))];
+    let mut coeffs = vec![Constant(F::one()); 3];
+    for (i, bit) in f.iter().enumerate() {
+        terms.push(Existing(bit));
+        coeffs.push(Constant(pow2(i)));
+    }
+    // the sum of four 32-bit words is less than 2^34
+    let sum = sum_mod_word(range, ctx, terms, coeffs, 2)?;
+
+    let mut terms = vec![Existing(&e.value)];
+    let mut coeffs = vec![Constant(F::one())];
+    for i in 0..32 {
+        terms.push(Existing(&sum.bits[(i + 32 - s) % 32]));
+        coeffs.push(Constant(pow2(i)));
+    }
+    sum_mod_word(range, ctx, terms, coeffs, 1)
+}
+
+fn compress<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    h: &[Word<F>; 5],
+    x: &[Word<F>],
+) -> Result<[Word<F>; 5], Error> {
+    let gate = range.gate();
+    let mut left = h.clone();
+    let mut right = h.clone();
+    for j in 0..80 {
+        let round = j / 16;
+        for (state, r, s, k, f_round) in [
+            (&mut left, R_LEFT, S_LEFT, K_LEFT, round),
+            (&mut right, R_RIGHT, S_RIGHT, K_RIGHT, 4 - round),
+        ] {
+            let t = step(range, ctx, f_round, state, &x[r[j]], k[round], s[j])?;
+            let [_, b, c, d, e] = state.clone();
+            let c_rot = rotate_left(gate, ctx, &c, 10)?;
+            *state = [e, t, b, c_rot, d];
+        }
+    }
+
+    let mut out = Vec::with_capacity(5);
+    for i in 0..5 {
+        // h'[i] = h[i + 1] + left[i + 2] + right[i + 3]
+        let terms = vec![
+            Existing(&h[(i + 1) % 5].value),
+            Existing(&left[(i + 2) % 5].value),
+            Existing(&right[(i + 3) % 5].value),
+        ];
+        out.push(sum_mod_word(range, ctx, terms, vec![Constant(F::one()); 3], 2)?);
+    }
+    Ok(out.try_into().unwrap())
+}
+
+// returns the state after each block of `padded`, whose length is a multiple of 64 bytes
+fn compress_blocks<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    padded: &[AssignedValue<F>],
+) -> Result<Vec<[Word<F>; 5]>, Error> {
+    let gate = range.gate();
+    // little-endian bits of each message byte
+    let mut message_bits = Vec::with_capacity(8 * padded.len());
+    for byte in padded.iter() {
+        message_bits.extend(range.num_to_bits(ctx, byte, 8)?);
+    }
+
+    let mut h: [Word<F>; 5] = INITIAL_STATE
+        .iter()
+        .map(|w| load_constant_word(gate, ctx, *w))
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .unwrap();
+    let mut states = Vec::with_capacity(padded.len() / 64);
+    for block in message_bits.chunks(512) {
+        let x = block
+            .chunks(32)
+            .map(|bits| bits_to_word(gate, ctx, bits.to_vec()))
+            .collect::<Result<Vec<_>, _>>()?;
+        h = compress(range, ctx, &h, &x)?;
+        states.push(h.clone());
+    }
+    Ok(states)
+}
+
+// the 20 bytes of the digest of state `h`
+fn state_to_bytes<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    h: &[Word<F>; 5],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut out = Vec::with_capacity(20);
+    for word in h.iter() {
+        for byte_bits in word.bits.chunks(8) {
+            let byte = gate.bits_to_num(ctx, byte_bits, false)?;
+            out.push(byte);
+        }
+    }
+    Ok(out)
+}
+
+/// Input: bytes of a message of known length. Each byte is range checked to be in [0, 2^8).
+///
+/// Output: the 20 bytes of RIPEMD-160(`input`)
+///
+/// The padding only depends on `input.len()`, so it is added as constants.
+pub fn ripemd160<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let gate = range.gate();
+    let padding = gate.assign_region_smart(
+        ctx,
+        pad(input.len()).into_iter().map(|b| Constant(F::from(b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    let padded: Vec<_> = input.iter().chain(padding.iter()).cloned().collect();
+    let states = compress_blocks(range, ctx, &padded)?;
+    let out = state_to_bytes(gate, ctx, states.last().unwrap())?;
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("ripemd160".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(out)
+}
+
+/// Input: a buffer of at most `max_len` bytes and the message length `len`, which is a witness.
+/// The message is `input[..len]`, bytes of `input` after the message are ignored.
+///
+/// Output: the 20 bytes of RIPEMD-160(`input[..len]`)
+///
+/// Constrains `len <= input.len()`. As for `sha256_var_len`, the circuit only depends on `input.len()` and `max_len`.
+pub fn ripemd160_var_len<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+    max_len: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let (padded, last_block) = pad_var_len(range, ctx, input, len, max_len, false)?;
+    let states = compress_blocks(range, ctx, &padded)?;
+    let h = select_state(range, ctx, &states, &last_block)?;
+    let out = state_to_bytes(range.gate(), ctx, &h)?;
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("ripemd160_var_len".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(out)
+}
+
+/// Incremental RIPEMD-160 of a message whose length is a witness, up to `max_len` bytes
+///
+/// `update` buffers message bytes and `finalize` hashes the first `len` of them with `ripemd160_var_len`.
+#[derive(Clone, Debug)]
+pub struct Ripemd160VarLenChip<F: FieldExt> {
+    max_len: usize,
+    buffer: Vec<AssignedValue<F>>,
+}
+
+impl<F: FieldExt> Ripemd160VarLenChip<F> {
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, buffer: Vec::with_capacity(max_len) }
+    }
+
+    pub fn update(&mut self, bytes: &[AssignedValue<F>]) {
+        assert!(self.buffer.len() + bytes.len() <= self.max_len, "buffer exceeds max_len");
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn finalize(
+        self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        len: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        ripemd160_var_len(range, ctx, &self.buffer, len, self.max_len)
+    }
+}
@@ -0,0 +1,176 @@
+use super::{ripemd160, ripemd160_native, Ripemd160VarLenChip};
+use crate::{
+    gates::{
+        range::{RangeConfig, RangeStrategy},
+        GateInstructions,
+    },
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt, circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*,
+};
+
+#[derive(Default)]
+struct Ripemd160Circuit<F> {
+    input: Vec<Value<F>>,
+    // the message length and maximum length, for variable length hashing of `input`
+    var_len: Option<(Value<F>, usize)>,
+    expected: Vec<u8>,
+}
+
+const NUM_ADVICE: usize = 4;
+
+impl<F: FieldExt> Circuit<F> for Ripemd160Circuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            input: vec![Value::unknown(); self.input.len()],
+            var_len: self.var_len.map(|(_, max_len)| (Value::unknown(), max_len)),
+            expected: self.expected.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "ripemd160",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let input = config.gate.assign_region_smart(
+                    ctx,
+                    self.input.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let out = match self.var_len {
+                    None => ripemd160(&config, ctx, &input)?,
+                    Some((len, max_len)) => {
+                        let len = config.gate.assign_region_smart(
+                            ctx,
+                            vec![Witness(len)],
+                            vec![],
+                            vec![],
+                            vec![],
+                        )?;
+                        let mut chip = Ripemd160VarLenChip::new(max_len);
+                        chip.update(&input);
+                        chip.finalize(&config, ctx, &len[0])?
+                    }
+                };
+                for (byte, expected) in out.iter().zip(self.expected.iter()) {
+                    config.gate.assert_is_const(ctx, byte, F::from(*expected as u64));
+                }
+
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_ripemd160_native() {
+    // test vectors from https://homes.esat.kuleuven.be/~bosselae/ripemd160.html
+    for (input, expected) in [
+        ("", "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+        ("abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+        ("message digest", "5d0689ef49d2fae572b881b123a85ffa21595f36"),
+        (
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "12a053384a9c0c88e405a06c27dcf49ada62eb2b",
+        ),
+    ] {
+        assert_eq!(hex(&ripemd160_native(input.as_bytes())), expected);
+    }
+}
+
+fn run(input: &[u8], k: u32) {
+    let circuit = Ripemd160Circuit::<Fr> {
+        input: input.iter().map(|b| Value::known(Fr::from(*b as u64))).collect(),
+        var_len: None,
+        expected: ripemd160_native(input).to_vec(),
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_ripemd160_one_block() {
+    run(b"abc", 16);
+}
+
+#[test]
+fn test_ripemd160_two_blocks() {
+    // 56 bytes do not leave room for the length in the first block
+    run(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", 17);
+}
+
+fn run_var_len(message: &[u8], max_len: usize, k: u32) {
+    // the buffer is filled up to `max_len` with bytes that must not affect the hash
+    let mut buffer = message.to_vec();
+    buffer.resize(max_len, 0xff);
+    let circuit = Ripemd160Circuit::<Fr> {
+        input: buffer.iter().map(|b| Value::known(Fr::from(*b as u64))).collect(),
+        var_len: Some((Value::known(Fr::from(message.len() as u64)), max_len)),
+        expected: ripemd160_native(message).to_vec(),
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_ripemd160_var_len() {
+    // with max_len = 64 the padded message has one or two blocks
+    for message in
+        [&b""[..], &b"abc"[..], &b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"[..]]
+    {
+        run_var_len(message, 64, 18);
+    }
+}
+
+#[test]
+fn test_ripemd160_var_len_max() {
+    run_var_len(&[0x61; 64], 64, 18);
+}
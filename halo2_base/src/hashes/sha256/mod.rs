@@ -0,0 +1,362 @@
+use super::word32::{
+    bits_to_word, load_constant_word, pad_var_len, pow2, select_state, sum_mod_word, xor_bit, Word,
+};
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Size in bytes of the blocks processed by the compression function
+pub const SHA256_BLOCK_SIZE: usize = 64;
+
+// pads a message of `input_len` bytes with `0x80`, zeros and the big-endian bit length to a multiple of 64 bytes
+pub(crate) fn pad(input_len: usize) -> Vec<u8> {
+    let mut padding = vec![0x80u8];
+    while (input_len + padding.len()) % SHA256_BLOCK_SIZE != 56 {
+        padding.push(0);
+    }
+    padding.extend_from_slice(&((input_len as u64) * 8).to_be_bytes());
+    padding
+}
+
+/// Out of circuit SHA-256
+pub fn sha256_native(input: &[u8]) -> [u8; 32] {
+    let mut message = input.to_vec();
+    message.extend(pad(input.len()));
+
+    let mut h = INITIAL_STATE;
+    for block in message.chunks(SHA256_BLOCK_SIZE) {
+        let mut w: Vec<u32> =
+            block.chunks(4).map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]])).collect();
+        for t in 16..64 {
+            let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+            let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+            w.push(s1.wrapping_add(w[t - 7]).wrapping_add(s0).wrapping_add(w[t - 16]));
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for t in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(ROUND_CONSTANTS[t])
+                .wrapping_add(w[t]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            (hh, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_mut(4).zip(h.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+// bits of `rotr(a, n)`
+fn rotr<'a, F: FieldExt>(a: &'a Word<F>, n: usize) -> Vec<QuantumCell<'a, F>> {
+    (0..32).map(|i| Existing(&a.bits[(i + n) % 32])).collect()
+}
+
+// bits of `a >> n`
+fn shr<'a, F: FieldExt>(a: &'a Word<F>, n: usize) -> Vec<QuantumCell<'a, F>> {
+    (0..32)
+        .map(|i| if i + n < 32 { Existing(&a.bits[i + n]) } else { Constant(F::zero()) })
+        .collect()
+}
+
+// bits of `x ^ y ^ z`
+fn xor3<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    x: &[QuantumCell<F>],
+    y: &[QuantumCell<F>],
+    z: &[QuantumCell<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut out = Vec::with_capacity(32);
+    for ((x, y), z) in x.iter().zip(y.iter()).zip(z.iter()) {
+        let t = xor_bit(gate, ctx, x, y)?;
+        let bit = match z {
+            // the bits shifted in by `shr` are zero
+            Constant(c) if *c == F::zero() => t,
+            _ => xor_bit(gate, ctx, &Existing(&t), z)?,
+        };
+        out.push(bit);
+    }
+    Ok(out)
+}
+
+// bits of `(e & f) ^ (!e & g)`, i.e. `e ? f : g`
+fn ch<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    e: &Word<F>,
+    f: &Word<F>,
+    g: &Word<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    (0..32)
+        .map(|i| {
+            gate.select(ctx, &Existing(&f.bits[i]), &Existing(&g.bits[i]), &Existing(&e.bits[i]))
+        })
+        .collect()
+}
+
+// bits of `(a & b) ^ (a & c) ^ (b & c)`
+fn maj<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &Word<F>,
+    b: &Word<F>,
+    c: &Word<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut out = Vec::with_capacity(32);
+    for ((a, b), c) in a.bits.iter().zip(b.bits.iter()).zip(c.bits.iter()) {
+        // maj = ab + c * (a ^ b) where a ^ b = a + b - 2ab
+        let ab = gate.mul(ctx, &Existing(a), &Existing(b))?;
+        let a_plus_b = gate.add(ctx, &Existing(a), &Existing(b))?;
+        let a_xor_b =
+            gate.mul_add(ctx, &Constant(-F::from(2)), &Existing(&ab), &Existing(&a_plus_b))?;
+        out.push(gate.mul_add(ctx, &Existing(c), &Existing(&a_xor_b), &Existing(&ab))?);
+    }
+    Ok(out)
+}
+
+// appends the terms and coefficients of the value of a word given by its bits
+fn push_bits<'a, F: FieldExt>(
+    terms: &mut Vec<QuantumCell<'a, F>>,
+    coeffs: &mut Vec<QuantumCell<'a, F>>,
+    bits: &'a [AssignedValue<F>],
+) {
+    for (i, bit) in bits.iter().enumerate() {
+        terms.push(Existing(bit));
+        coeffs.push(Constant(pow2(i)));
+    }
+}
+
+fn compress<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    h: &[Word<F>; 8],
+    block: Vec<Word<F>>,
+) -> Result<[Word<F>; 8], Error> {
+    let gate = range.gate();
+    let mut w = block;
+    for t in 16..64 {
+        let s0 = xor3(gate, ctx, &rotr(&w[t - 15], 7), &rotr(&w[t - 15], 18), &shr(&w[t - 15], 3))?;
+        let s1 = xor3(gate, ctx, &rotr(&w[t - 2], 17), &rotr(&w[t - 2], 19), &shr(&w[t - 2], 10))?;
+        let mut terms = vec![Existing(&w[t - 7].value), Existing(&w[t - 16].value)];
+        let mut coeffs = vec![Constant(F::one()); 2];
+        push_bits(&mut terms, &mut coeffs, &s0);
+        push_bits(&mut terms, &mut coeffs, &s1);
+        // the sum of four words is less than 2^34
+        let next = sum_mod_word(range, ctx, terms, coeffs, 2)?;
+        w.push(next);
+    }
+
+    let mut state = h.clone();
+    for (t, w) in w.iter().enumerate() {
+        let [a, b, c, d, e, f, g, hh] = &state;
+        let s1 = xor3(gate, ctx, &rotr(e, 6), &rotr(e, 11), &rotr(e, 25))?;
+        let ch = ch(gate, ctx, e, f, g)?;
+        let s0 = xor3(gate, ctx, &rotr(a, 2), &rotr(a, 13), &rotr(a, 22))?;
+        let maj = maj(gate, ctx, a, b, c)?;
+
+        // t1 = h + s1 + ch + k + w
+        let mut t1_terms = vec![
+            Existing(&hh.value),
+            Existing(&w.value),
+            Constant(F::from(ROUND_CONSTANTS[t] as u64)),
+        ];
+        let mut t1_coeffs = vec![Constant(F::one()); 3];
+        push_bits(&mut t1_terms, &mut t1_coeffs, &s1);
+        push_bits(&mut t1_terms, &mut t1_coeffs, &ch);
+
+        // e' = d + t1, the sum of six words is less than 2^35
+        let mut terms = t1_terms.clone();
+        let mut coeffs = t1_coeffs.clone();
+        terms.push(Existing(&d.value));
+        coeffs.push(Constant(F::one()));
+        let new_e = sum_mod_word(range, ctx, terms, coeffs, 3)?;
+
+        // a' = t1 + s0 + maj, the sum of seven words is less than 2^35
+        let (mut terms, mut coeffs) = (t1_terms, t1_coeffs);
+        push_bits(&mut terms, &mut coeffs, &s0);
+        push_bits(&mut terms, &mut coeffs, &maj);
+        let new_a = sum_mod_word(range, ctx, terms, coeffs, 3)?;
+
+        state = [new_a, a.clone(), b.clone(), c.clone(), new_e, e.clone(), f.clone(), g.clone()];
+    }
+
+    let mut out = Vec::with_capacity(8);
+    for (h, x) in h.iter().zip(state.iter()) {
+        out.push(sum_mod_word(
+            range,
+            ctx,
+            vec![Existing(&h.value), Existing(&x.value)],
+            vec![Constant(F::one()); 2],
+            1,
+        )?);
+    }
+    Ok(out.try_into().unwrap())
+}
+
+// returns the state after each block of `padded`, whose length is a multiple of the block size
+fn compress_blocks<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    padded: &[AssignedValue<F>],
+) -> Result<Vec<[Word<F>; 8]>, Error> {
+    let gate = range.gate();
+    // little-endian bits of each message byte
+    let mut byte_bits = Vec::with_capacity(padded.len());
+    for byte in padded.iter() {
+        byte_bits.push(range.num_to_bits(ctx, byte, 8)?);
+    }
+
+    let mut h: [Word<F>; 8] = INITIAL_STATE
+        .iter()
+        .map(|w| load_constant_word(gate, ctx, *w))
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .unwrap();
+    let mut states = Vec::with_capacity(padded.len() / SHA256_BLOCK_SIZE);
+    for block in byte_bits.chunks(SHA256_BLOCK_SIZE) {
+        // words are big-endian, so the little-endian bits of a word start with its last byte
+        let words = block
+            .chunks(4)
+            .map(|bytes| bits_to_word(gate, ctx, bytes.iter().rev().flatten().cloned().collect()))
+            .collect::<Result<Vec<_>, _>>()?;
+        h = compress(range, ctx, &h, words)?;
+        states.push(h.clone());
+    }
+    Ok(states)
+}
+
+// the 32 bytes of the digest of state `h`
+fn state_to_bytes<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    h: &[Word<F>; 8],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut out = Vec::with_capacity(32);
+    for word in h.iter() {
+        for byte_bits in word.bits.chunks(8).rev() {
+            let byte = gate.bits_to_num(ctx, byte_bits, false)?;
+            out.push(byte);
+        }
+    }
+    Ok(out)
+}
+
+/// Input: bytes of a message of known length. Each byte is range checked to be in [0, 2^8).
+///
+/// Output: the 32 bytes of SHA-256(`input`)
+///
+/// The padding only depends on `input.len()`, so it is added as constants.
+pub fn sha256<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let gate = range.gate();
+    let padding = gate.assign_region_smart(
+        ctx,
+        pad(input.len()).into_iter().map(|b| Constant(F::from(b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    let padded: Vec<_> = input.iter().chain(padding.iter()).cloned().collect();
+    let states = compress_blocks(range, ctx, &padded)?;
+    let out = state_to_bytes(gate, ctx, states.last().unwrap())?;
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("sha256".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(out)
+}
+
+/// Input: a buffer of at most `max_len` bytes and the message length `len`, which is a witness.
+/// The message is `input[..len]`, bytes of `input` after the message are ignored.
+///
+/// Output: the 32 bytes of SHA-256(`input[..len]`)
+///
+/// Constrains `len <= input.len()`. The circuit only depends on `input.len()` and `max_len`, so one circuit hashes
+/// messages of any length up to `max_len`, at the cost of compressing `(max_len + 72) / 64` blocks.
+pub fn sha256_var_len<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+    max_len: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let (padded, last_block) = pad_var_len(range, ctx, input, len, max_len, true)?;
+    let states = compress_blocks(range, ctx, &padded)?;
+    let h = select_state(range, ctx, &states, &last_block)?;
+    let out = state_to_bytes(range.gate(), ctx, &h)?;
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("sha256_var_len".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(out)
+}
+
+/// Incremental SHA-256 of a message whose length is a witness, up to `max_len` bytes
+///
+/// `update` buffers message bytes and `finalize` hashes the first `len` of them with `sha256_var_len`, which
+/// constrains `len` to be at most the number of buffered bytes.
+/// The buffer may be longer than the message, e.g. a fixed size array with the message at its start.
+#[derive(Clone, Debug)]
+pub struct Sha256VarLenChip<F: FieldExt> {
+    max_len: usize,
+    buffer: Vec<AssignedValue<F>>,
+}
+
+impl<F: FieldExt> Sha256VarLenChip<F> {
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, buffer: Vec::with_capacity(max_len) }
+    }
+
+    pub fn update(&mut self, bytes: &[AssignedValue<F>]) {
+        assert!(self.buffer.len() + bytes.len() <= self.max_len, "buffer exceeds max_len");
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn finalize(
+        self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        len: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        sha256_var_len(range, ctx, &self.buffer, len, self.max_len)
+    }
+}
@@ -0,0 +1,196 @@
+use super::{sha256, sha256_native, Sha256VarLenChip};
+use crate::{
+    gates::{
+        range::{RangeConfig, RangeStrategy},
+        GateInstructions,
+    },
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    halo2curves::bn256::Fr,
+    plonk::*,
+};
+
+#[derive(Default)]
+struct Sha256Circuit<F> {
+    input: Vec<Value<F>>,
+    // the message length and maximum length, for variable length hashing of `input`
+    var_len: Option<(Value<F>, usize)>,
+    expected: Vec<u8>,
+}
+
+const NUM_ADVICE: usize = 4;
+
+impl<F: FieldExt> Circuit<F> for Sha256Circuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            input: vec![Value::unknown(); self.input.len()],
+            var_len: self.var_len.map(|(_, max_len)| (Value::unknown(), max_len)),
+            expected: self.expected.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "sha256",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let input = config.gate.assign_region_smart(
+                    ctx,
+                    self.input.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let out = match self.var_len {
+                    None => sha256(&config, ctx, &input)?,
+                    Some((len, max_len)) => {
+                        let len = config.gate.assign_region_smart(
+                            ctx,
+                            vec![Witness(len)],
+                            vec![],
+                            vec![],
+                            vec![],
+                        )?;
+                        let mut chip = Sha256VarLenChip::new(max_len);
+                        chip.update(&input);
+                        chip.finalize(&config, ctx, &len[0])?
+                    }
+                };
+                for (byte, expected) in out.iter().zip(self.expected.iter()) {
+                    config.gate.assert_is_const(ctx, byte, F::from(*expected as u64));
+                }
+
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_sha256_native() {
+    // test vectors from FIPS 180-2
+    for (input, expected) in [
+        ("", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        ("abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ] {
+        assert_eq!(hex(&sha256_native(input.as_bytes())), expected);
+    }
+}
+
+fn run(input: &[u8], k: u32) {
+    let circuit = Sha256Circuit::<Fr> {
+        input: input.iter().map(|b| Value::known(Fr::from(*b as u64))).collect(),
+        var_len: None,
+        expected: sha256_native(input).to_vec(),
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sha256_one_block() {
+    run(b"abc", 17);
+}
+
+#[test]
+fn test_sha256_two_blocks() {
+    // 56 bytes do not leave room for the length in the first block
+    run(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", 18);
+}
+
+fn run_var_len(message: &[u8], max_len: usize, k: u32) {
+    // the buffer is filled up to `max_len` with bytes that must not affect the hash
+    let mut buffer = message.to_vec();
+    buffer.resize(max_len, 0xff);
+    verify_var_len(&buffer, message.len(), max_len, &sha256_native(message), k).unwrap();
+}
+
+fn verify_var_len(
+    buffer: &[u8],
+    len: usize,
+    max_len: usize,
+    expected: &[u8],
+    k: u32,
+) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = Sha256Circuit::<Fr> {
+        input: buffer.iter().map(|b| Value::known(Fr::from(*b as u64))).collect(),
+        var_len: Some((Value::known(Fr::from(len as u64)), max_len)),
+        expected: expected.to_vec(),
+    };
+    MockProver::run(k, &circuit, vec![]).unwrap().verify()
+}
+
+#[test]
+fn test_sha256_var_len() {
+    // with max_len = 64 the padded message has one or two blocks
+    for message in
+        [&b""[..], &b"abc"[..], &b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"[..]]
+    {
+        run_var_len(message, 64, 19);
+    }
+}
+
+#[test]
+fn test_sha256_var_len_max() {
+    run_var_len(&[0x61; 64], 64, 19);
+}
+
+#[test]
+fn test_sha256_var_len_past_buffer() {
+    // only 3 bytes are buffered, so a length of 4 must not hash the message "abc\0"
+    let expected = sha256_native(b"abc\0");
+    assert!(verify_var_len(b"abc", 4, 64, &expected, 19).is_err());
+    assert!(verify_var_len(b"abc\0", 4, 64, &expected, 19).is_ok());
+}
@@ -0,0 +1,215 @@
+use super::{mimc::MimcSponge, poseidon::PoseidonSpec, poseidon2::Poseidon2Spec, AlgebraicHasher};
+use crate::{
+    gates::{
+        flex_gate::{FlexGateConfig, GateStrategy},
+        GateInstructions,
+    },
+    utils::{biguint_to_fe, fe_to_biguint},
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*};
+use num_bigint::BigUint;
+
+struct HasherCircuit<H> {
+    hasher: H,
+    inputs: Vec<Value<Fr>>,
+    expected: Fr,
+}
+
+const NUM_ADVICE: usize = 1;
+
+impl<H: AlgebraicHasher<Fr> + Clone> Circuit<Fr> for HasherCircuit<H> {
+    type Config = FlexGateConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            hasher: self.hasher.clone(),
+            inputs: vec![Value::unknown(); self.inputs.len()],
+            expected: self.expected,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        FlexGateConfig::configure(
+            meta,
+            GateStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "hash",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let inputs = config.assign_region_smart(
+                    ctx,
+                    self.inputs.iter().map(|x| Witness(*x)).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let out = self.hasher.hash(ctx, &config, &inputs)?;
+                config.assert_is_const(ctx, &out, self.expected);
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn run<H: AlgebraicHasher<Fr> + Clone>(hasher: H, k: u32) {
+    // covers inputs that do and do not fill the rate
+    for len in [1, 2, 3] {
+        let inputs: Vec<Fr> = (1..=len as u64).map(Fr::from).collect();
+        let circuit = HasherCircuit {
+            expected: hasher.hash_native(&inputs),
+            hasher: hasher.clone(),
+            inputs: inputs.into_iter().map(Value::known).collect(),
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+#[test]
+fn test_mimc_sponge() {
+    run(MimcSponge::<Fr>::default(), 15);
+}
+
+#[test]
+fn test_poseidon_hasher() {
+    run(PoseidonSpec::<Fr, 3, 2>::new(8, 57), 15);
+}
+
+#[test]
+fn test_poseidon2_hasher() {
+    run(Poseidon2Spec::<Fr, 3, 2>::new(8, 56, [Fr::from(1), Fr::from(1), Fr::from(2)]), 15);
+}
+
+fn from_hex(hex: &str) -> Fr {
+    biguint_to_fe(&BigUint::parse_bytes(hex.as_bytes(), 16).unwrap())
+}
+
+#[test]
+fn test_poseidon_permutation_vectors() {
+    // `poseidonperm_x5_254_3` and `poseidonperm_x5_254_5` from the test vectors of the reference implementation,
+    // https://extgit.iaik.tugraz.at/krypto/hadeshash/-/blob/master/code/test_vectors.txt
+    let mut state = [Fr::from(0), Fr::from(1), Fr::from(2)];
+    PoseidonSpec::<Fr, 3, 2>::new(8, 57).permute(&mut state);
+    assert_eq!(
+        state,
+        [
+            from_hex("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"),
+            from_hex("0fca49b798923ab0239de1c9e7a4a9a2210312b6a2f616d18b5a87f9b628ae29"),
+            from_hex("0e7ae82e40091e63cbd4f16a6d16310b3729d4b6e138fcf54110e2867045a30c"),
+        ]
+    );
+
+    let mut state = [0u64, 1, 2, 3, 4].map(Fr::from);
+    PoseidonSpec::<Fr, 5, 4>::new(8, 60).permute(&mut state);
+    assert_eq!(
+        state,
+        [
+            from_hex("299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465"),
+            from_hex("1148aaef609aa338b27dafd89bb98862d8bb2b429aceac47d86206154ffe053d"),
+            from_hex("24febb87fed7462e23f6665ff9a0111f4044c38ee1672c1ac6b0637d34f24907"),
+            from_hex("0eb08f6d809668a981c186beaf6110060707059576406b248e5d9cf6e78b3d3e"),
+            from_hex("07748bc6877c9b82c8b98666ee9d0626ec7f5be4205f79ee8528ef1c4a376fc7"),
+        ]
+    );
+}
+
+#[test]
+fn test_poseidon2_permutation_vectors() {
+    // the BN254 instance with `T = 3` of the reference implementation,
+    // https://github.com/HorizenLabs/poseidon2/blob/main/plain_implementations/src/poseidon2/poseidon2_instance_bn256.rs
+    let spec = Poseidon2Spec::<Fr, 3, 2>::new(8, 56, [Fr::from(1), Fr::from(1), Fr::from(2)]);
+    assert_eq!(
+        spec.external_round_constants[0][0],
+        from_hex("1d066a255517b7fd8bddd3a93f7804ef7f8fcde48bb4c37a59a09a1a97052816")
+    );
+    assert_eq!(
+        spec.internal_round_constants[0],
+        from_hex("1a1d063e54b1e764b63e1855bff015b8cedd192f47308731499573f23597d4b5")
+    );
+    let mut state = [Fr::from(0), Fr::from(1), Fr::from(2)];
+    spec.permute(&mut state);
+    assert_eq!(
+        state,
+        [
+            from_hex("0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033"),
+            from_hex("303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570"),
+            from_hex("1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8"),
+        ]
+    );
+}
+
+#[test]
+fn test_mimc_sponge_vectors() {
+    // the empty subtrees of Tornado Cash, whose Merkle tree hashes with circomlib's `MiMCSponge(2, 220, 1)`:
+    // `zeros(0) = keccak256("tornado") mod p` and `zeros(i + 1) = MiMCSponge(zeros(i), zeros(i))`, see
+    // https://github.com/tornadocash/tornado-core/blob/master/contracts/MerkleTreeWithHistory.sol
+    let zeros = [
+        "2fe54c60d3acabf3343a35b6eba15db4821b340f76e741e2249685ed4899af6c",
+        "256a6135777eee2fd26f54b8b7037a25439d5235caee224154186d2b8a52e31d",
+        "1151949895e82ab19924de92c40a3d6f7bcb60d92b00504b8199613683f0c200",
+        "20121ee811489ff8d61f09fb89e313f14959a0f28bb428a20dba6b0b068b3bdb",
+    ]
+    .map(from_hex);
+    let mimc = MimcSponge::<Fr>::default();
+    for level in zeros.windows(2) {
+        assert_eq!(mimc.hash_native(&[level[0], level[0]]), level[1]);
+    }
+
+    // and in circuit
+    let circuit =
+        HasherCircuit { hasher: mimc, inputs: vec![Value::known(zeros[0]); 2], expected: zeros[1] };
+    MockProver::run(15, &circuit, vec![]).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_mimc_multi_output() {
+    let mimc = MimcSponge::<Fr>::default();
+    let outputs = mimc.hash_multi_native(&[Fr::from(1), Fr::from(2)], 2);
+    assert_eq!(outputs[0], mimc.hash_native(&[Fr::from(1), Fr::from(2)]));
+    assert_ne!(outputs[0], outputs[1]);
+}
+
+#[test]
+fn test_mimc_constants_match_circomlib() {
+    // first constants of circomlib's `mimcsponge.circom`
+    let mimc = MimcSponge::<Fr>::default();
+    assert_eq!(mimc.round_constants[0], Fr::from(0));
+    assert_eq!(
+        fe_to_biguint(&mimc.round_constants[1]).to_string(),
+        "7120861356467848435263064379192047478074060781135320967663101236819528304084"
+    );
+    assert_eq!(
+        fe_to_biguint(&mimc.round_constants[2]).to_string(),
+        "5024705281721889198577876690145313457398658950011302225525409148828000436681"
+    );
+    assert_eq!(mimc.round_constants[219], Fr::from(0));
+}
@@ -0,0 +1,198 @@
+// Helpers for hashes operating on 32-bit words, which are kept both as little-endian bits and as values:
+// bitwise operations act on the bits, additions mod 2^32 on the values, and rotations are free on the bits.
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+// A 32-bit word, stored both as its little-endian bits and as its value
+#[derive(Clone, Debug)]
+pub(crate) struct Word<F: FieldExt> {
+    pub bits: Vec<AssignedValue<F>>,
+    pub value: AssignedValue<F>,
+}
+
+pub(crate) fn pow2<F: FieldExt>(i: usize) -> F {
+    F::from_u128(1u128 << i)
+}
+
+pub(crate) fn load_constant_word<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    w: u32,
+) -> Result<Word<F>, Error> {
+    let mut cells: Vec<QuantumCell<F>> =
+        (0..32).map(|i| Constant(F::from(((w >> i) & 1) as u64))).collect();
+    cells.push(Constant(F::from(w as u64)));
+    let mut assigned = gate.assign_region_smart(ctx, cells, vec![], vec![], vec![])?;
+    let value = assigned.pop().unwrap();
+    Ok(Word { bits: assigned, value })
+}
+
+// composes the value of a word from its bits, which are assumed to be boolean
+pub(crate) fn bits_to_word<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bits: Vec<AssignedValue<F>>,
+) -> Result<Word<F>, Error> {
+    let value = gate.bits_to_num(ctx, &bits, false)?;
+    Ok(Word { bits, value })
+}
+
+pub(crate) fn rotate_left<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &Word<F>,
+    s: usize,
+) -> Result<Word<F>, Error> {
+    let bits = (0..32).map(|i| a.bits[(i + 32 - s) % 32].clone()).collect();
+    bits_to_word(gate, ctx, bits)
+}
+
+// returns the word `sum_i coeffs[i] * terms[i] mod 2^32`
+// assumes the sum is in [0, 2^{32 + carry_bits})
+pub(crate) fn sum_mod_word<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    terms: Vec<QuantumCell<F>>,
+    coeffs: Vec<QuantumCell<F>>,
+    carry_bits: usize,
+) -> Result<Word<F>, Error> {
+    let gate = range.gate();
+    let sum = gate.inner_product(ctx, &terms, &coeffs)?;
+    let mut bits = range.num_to_bits(ctx, &sum, 32 + carry_bits)?;
+    let carries = bits.split_off(32);
+
+    // value = sum - 2^32 * carry
+    let mut terms = vec![Existing(&sum)];
+    let mut coeffs = vec![Constant(F::one())];
+    for (i, carry) in carries.iter().enumerate() {
+        terms.push(Existing(carry));
+        coeffs.push(Constant(-pow2::<F>(32 + i)));
+    }
+    let value = gate.inner_product(ctx, &terms, &coeffs)?;
+    Ok(Word { bits, value })
+}
+
+pub(crate) fn xor_bit<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &QuantumCell<F>,
+    b: &QuantumCell<F>,
+) -> Result<AssignedValue<F>, Error> {
+    // a ^ b = a * (1 - 2b) + b
+    let one_minus_two_b = gate.mul_add(ctx, &Constant(-F::from(2)), b, &Constant(F::one()))?;
+    gate.mul_add(ctx, a, &Existing(&one_minus_two_b), b)
+}
+
+// Merkle-Damgard padding of a message whose length `len` is a witness, for 64 byte blocks and a 64-bit bit length
+// appended in the given byte order. `input` is the message buffer of `input.len() <= max_len` bytes, bytes at
+// positions `>= len` are ignored. Constrains `len <= input.len()`, so the message never reads past the buffer.
+//
+// Returns the padded buffer of `(max_len + 72) / 64` blocks, which is the padded message followed by arbitrary
+// blocks, together with the indicator of the last block of the padded message.
+pub(crate) fn pad_var_len<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    input: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+    max_len: usize,
+    big_endian: bool,
+) -> Result<(Vec<AssignedValue<F>>, Vec<AssignedValue<F>>), Error> {
+    assert!(input.len() <= max_len);
+    let gate = range.gate();
+    let num_blocks = (max_len + 72) / 64;
+    let padded_len = 64 * num_blocks;
+
+    let len_bits = (usize::BITS - max_len.leading_zeros()).max(1) as usize;
+    let bits = range.num_to_bits(ctx, len, len_bits)?;
+    range.check_less_than(
+        ctx,
+        &Existing(len),
+        &Constant(F::from(input.len() as u64 + 1)),
+        len_bits + 1,
+    )?;
+
+    // is_len[i] = (i == len)
+    let is_len = range.idx_to_indicator_by_lookup(ctx, len, padded_len)?;
+
+    // is_message[i] = (i < len) = 1 - sum_{j <= i} is_len[j]
+    let mut is_message: Vec<AssignedValue<F>> = Vec::with_capacity(input.len());
+    for (i, ind) in is_len.iter().take(input.len()).enumerate() {
+        let prev = if i == 0 { Constant(F::one()) } else { Existing(&is_message[i - 1]) };
+        let next = gate.sub(ctx, &prev, &Existing(ind))?;
+        is_message.push(next);
+    }
+
+    // block b is the last one iff 64 * b - 8 <= len < 64 * b + 56
+    let mut last_block = Vec::with_capacity(num_blocks);
+    for b in 0..num_blocks {
+        let window = &is_len[(64 * b).saturating_sub(8)..(64 * b + 56).min(padded_len)];
+        let ind = gate.inner_product(
+            ctx,
+            &window.iter().map(|x| Existing(x)).collect(),
+            &vec![Constant(F::one()); window.len()],
+        )?;
+        last_block.push(ind);
+    }
+
+    // little-endian bytes of the bit length 8 * len, `None` for bytes that are always zero
+    let mut len_bytes = Vec::with_capacity(8);
+    for k in 0..8 {
+        let (terms, coeffs): (Vec<_>, Vec<_>) = (0..8)
+            .filter(|j| 8 * k + j >= 3 && 8 * k + j - 3 < len_bits)
+            .map(|j| (Existing(&bits[8 * k + j - 3]), Constant(pow2::<F>(j))))
+            .unzip();
+        len_bytes.push(if terms.is_empty() {
+            None
+        } else {
+            Some(gate.inner_product(ctx, &terms, &coeffs)?)
+        });
+    }
+    if big_endian {
+        len_bytes.reverse();
+    }
+
+    // padded[i] = is_message[i] * input[i] + is_len[i] * 0x80 + last_block[i / 64] * len_byte
+    let mut padded = Vec::with_capacity(padded_len);
+    for i in 0..padded_len {
+        let mut terms = vec![Existing(&is_len[i])];
+        let mut coeffs = vec![Constant(F::from(0x80))];
+        if i < input.len() {
+            terms.push(Existing(&is_message[i]));
+            coeffs.push(Existing(&input[i]));
+        }
+        if i % 64 >= 56 {
+            if let Some(byte) = &len_bytes[i % 64 - 56] {
+                terms.push(Existing(&last_block[i / 64]));
+                coeffs.push(Existing(byte));
+            }
+        }
+        let byte = gate.inner_product(ctx, &terms, &coeffs)?;
+        padded.push(byte);
+    }
+    Ok((padded, last_block))
+}
+
+// returns the state `sum_b indicator[b] * states[b]`, i.e. the state selected by the one-hot `indicator`
+pub(crate) fn select_state<F: FieldExt, const N: usize>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    states: &[[Word<F>; N]],
+    indicator: &[AssignedValue<F>],
+) -> Result<[Word<F>; N], Error> {
+    let gate = range.gate();
+    let mut out = Vec::with_capacity(N);
+    for i in 0..N {
+        let value = gate.inner_product(
+            ctx,
+            &states.iter().map(|state| Existing(&state[i].value)).collect(),
+            &indicator.iter().map(|x| Existing(x)).collect(),
+        )?;
+        let bits = range.num_to_bits(ctx, &value, 32)?;
+        out.push(Word { bits, value });
+    }
+    Ok(out.try_into().unwrap())
+}
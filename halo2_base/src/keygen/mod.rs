@@ -0,0 +1,103 @@
+// Serialization of keys together with the parameters of their circuit
+//
+// A key file holds the degree of the circuit, the circuit parameters used to configure it as JSON, e.g. a
+// `CircuitParams`, and the verifying key. Reading checks the file against the parameters and the degree the caller
+// expects, so a key is never silently used with a circuit configured differently. The circuit must already be set
+// up to be configured with the expected parameters, as `VerifyingKey::read` configures it to parse the key.
+//
+// The proving key has no serialization in the pinned halo2 version, so `read_pk` rebuilds it from the stored
+// verifying key, which saves `keygen_vk`.
+
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{keygen_pk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{self, Read, Write};
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Writes `vk` together with `circuit_params`, the parameters its circuit was configured with
+pub fn write_vk<C: CurveAffine, P: Serialize>(
+    writer: &mut impl Write,
+    circuit_params: &P,
+    vk: &VerifyingKey<C>,
+) -> io::Result<()> {
+    let params_json = serde_json::to_vec(circuit_params)?;
+    writer.write_all(&vk.get_domain().k().to_le_bytes())?;
+    writer.write_all(&(params_json.len() as u64).to_le_bytes())?;
+    writer.write_all(&params_json)?;
+    vk.write(writer)
+}
+
+/// Reads the circuit parameters at the start of a key file written by `write_vk`, e.g. to configure the circuit
+/// before reading the key
+pub fn read_circuit_params<P: DeserializeOwned>(reader: &mut impl Read) -> io::Result<(u32, P)> {
+    let mut k = [0u8; 4];
+    reader.read_exact(&mut k)?;
+    let mut len = [0u8; 8];
+    reader.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+    // the length is untrusted, so the buffer grows with the bytes actually read instead of being allocated upfront
+    let mut params_json = Vec::new();
+    reader.by_ref().take(len).read_to_end(&mut params_json)?;
+    if params_json.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok((u32::from_le_bytes(k), serde_json::from_slice(&params_json)?))
+}
+
+/// Reads a verifying key written by `write_vk` for `circuit`, which is only used for its type.
+/// Returns `InvalidData` if the key was written for other circuit parameters than `expected` or another degree
+/// than that of `params`.
+pub fn read_vk<'params, C, ConcreteCircuit, P>(
+    reader: &mut impl Read,
+    params: &impl Params<'params, C>,
+    _circuit: &ConcreteCircuit,
+    expected: &P,
+) -> io::Result<VerifyingKey<C>>
+where
+    C: CurveAffine,
+    ConcreteCircuit: Circuit<C::Scalar>,
+    P: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let (k, circuit_params) = read_circuit_params::<P>(reader)?;
+    if &circuit_params != expected {
+        return Err(invalid_data(format!(
+            "key written for circuit params {:?}, expected {:?}",
+            circuit_params, expected
+        )));
+    }
+    if k != params.k() {
+        return Err(invalid_data(format!(
+            "key written for degree {}, params have {}",
+            k,
+            params.k()
+        )));
+    }
+    VerifyingKey::read::<_, ConcreteCircuit>(reader, params)
+}
+
+/// Reads a key file written by `write_vk` as `read_vk` does and rebuilds the proving key of `circuit` from it
+pub fn read_pk<'params, C, ParamsScheme, ConcreteCircuit, P>(
+    reader: &mut impl Read,
+    params: &ParamsScheme,
+    circuit: &ConcreteCircuit,
+    expected: &P,
+) -> io::Result<ProvingKey<C>>
+where
+    C: CurveAffine,
+    ParamsScheme: Params<'params, C>,
+    ConcreteCircuit: Circuit<C::Scalar>,
+    P: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let vk = read_vk(reader, params, circuit, expected)?;
+    keygen_pk(params, vk, circuit)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,82 @@
+use super::*;
+use crate::gates::{
+    circuit::RangeCircuitBuilder,
+    params::CircuitParams,
+    range::{RangeConfig, RangeStrategy},
+    GateInstructions, RangeInstructions,
+};
+use crate::QuantumCell::Witness;
+use halo2_proofs::{
+    circuit::Value,
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::keygen_vk,
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use rand::rngs::OsRng;
+
+const PARAMS: CircuitParams =
+    CircuitParams { degree: 10, num_advice: 2, num_lookup_advice: 1, num_fixed: 1, lookup_bits: 8 };
+
+fn circuit() -> impl Circuit<Fr> {
+    RangeCircuitBuilder::new(RangeStrategy::Vertical, PARAMS, |range: &RangeConfig<Fr>, ctx| {
+        let a = range.gate().assign_region_smart(
+            ctx,
+            vec![Witness(Value::known(Fr::from(1000)))],
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        range.range_check(ctx, &a[0], 12)
+    })
+}
+
+fn vk_bytes(vk: &VerifyingKey<G1Affine>) -> Vec<u8> {
+    let mut bytes = vec![];
+    vk.write(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn test_key_roundtrip() {
+    let params = ParamsKZG::<Bn256>::setup(PARAMS.degree, OsRng);
+    let circuit = circuit();
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let mut file = vec![];
+    write_vk(&mut file, &PARAMS, &vk).unwrap();
+
+    assert_eq!(
+        read_circuit_params::<CircuitParams>(&mut &file[..]).unwrap(),
+        (PARAMS.degree, PARAMS)
+    );
+    let read = read_vk(&mut &file[..], &params, &circuit, &PARAMS).unwrap();
+    assert_eq!(vk_bytes(&read), vk_bytes(&vk));
+    let pk = read_pk(&mut &file[..], &params, &circuit, &PARAMS).unwrap();
+    assert_eq!(vk_bytes(pk.get_vk()), vk_bytes(&vk));
+}
+
+#[test]
+fn test_key_mismatch() {
+    let params = ParamsKZG::<Bn256>::setup(PARAMS.degree, OsRng);
+    let circuit = circuit();
+    let vk = keygen_vk(&params, &circuit).unwrap();
+    let mut file = vec![];
+    write_vk(&mut file, &PARAMS, &vk).unwrap();
+
+    let other = CircuitParams { num_advice: 3, ..PARAMS };
+    let err = read_vk(&mut &file[..], &params, &circuit, &other).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let larger = ParamsKZG::<Bn256>::setup(PARAMS.degree + 1, OsRng);
+    let err = read_vk(&mut &file[..], &larger, &circuit, &PARAMS).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_read_circuit_params_truncated() {
+    // a length far beyond the bytes that follow is an error, not an allocation of that size
+    let mut file = PARAMS.degree.to_le_bytes().to_vec();
+    file.extend(u64::MAX.to_le_bytes());
+    file.extend(b"{}");
+    let err = read_circuit_params::<CircuitParams>(&mut &file[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -1,18 +1,101 @@
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{AssignedCell, Cell, Region, Value},
+    circuit::{
+        layouter::{RegionLayouter, RegionShape},
+        AssignedCell, Cell, Region, Value,
+    },
     plonk::{Advice, Column, Error, Fixed},
 };
 use num_bigint::BigUint;
-use std::{borrow::Borrow, collections::HashMap, marker::PhantomData, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData, rc::Rc, sync::Mutex};
 use utils::fe_to_biguint;
 
+#[cfg(feature = "test-utils")]
+pub mod cost;
 pub mod gates;
+pub mod hashes;
+#[cfg(feature = "serde")]
+pub mod keygen;
+pub mod memory;
+pub mod merkle;
+pub mod mpt;
+#[cfg(feature = "provenance")]
+pub mod provenance;
+pub mod safe_types;
+pub mod ssz;
 pub mod utils;
 
+// the names of all `ContextId`s, in the order they were registered, and the id of each name
+#[derive(Default)]
+struct ContextRegistry {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+}
+
+// `None` until the first id is registered, since `HashMap::new` is not `const`
+static CONTEXT_REGISTRY: Mutex<Option<ContextRegistry>> = Mutex::new(None);
+
+thread_local! {
+    // the ids this thread has already looked up, so repeated calls of `ContextId::new` skip the lock
+    static CONTEXT_IDS: RefCell<HashMap<String, ContextId>> = RefCell::new(HashMap::new());
+}
+
+/// The key of the columns of a chip/config in `Context::advice_rows`
+///
+/// Ids are registered by name in a global registry, so `ContextId::new` with the same name always returns the same id.
+/// Each thread caches the ids it has looked up, so only the first call with a name on a thread takes the lock of the
+/// registry. The id is a `usize` that is cheap to copy and hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContextId(usize);
+
+impl ContextId {
+    pub fn new(name: &str) -> Self {
+        if let Some(id) = CONTEXT_IDS.with(|ids| ids.borrow().get(name).copied()) {
+            return id;
+        }
+        let id = {
+            let mut registry = CONTEXT_REGISTRY.lock().unwrap();
+            let registry = registry.get_or_insert_with(ContextRegistry::default);
+            match registry.ids.get(name) {
+                Some(id) => Self(*id),
+                None => {
+                    let id = registry.names.len();
+                    registry.names.push(name.to_string());
+                    registry.ids.insert(name.to_string(), id);
+                    Self(id)
+                }
+            }
+        };
+        CONTEXT_IDS.with(|ids| ids.borrow_mut().insert(name.to_string(), id));
+        id
+    }
+
+    pub fn name(&self) -> String {
+        let registry = CONTEXT_REGISTRY.lock().unwrap();
+        // every id is created by `ContextId::new`, which registers it
+        registry.as_ref().unwrap().names[self.0].clone()
+    }
+}
+
+// the id named "default", which most circuits use for their single chip
+impl Default for ContextId {
+    fn default() -> Self {
+        Self::new("default")
+    }
+}
+
+impl std::fmt::Display for ContextId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum QuantumCell<'a, F: FieldExt> {
     Existing(&'a AssignedValue<F>),
+    // same as `Existing`, for a cell which is not borrowed, e.g. the output of another gate call
+    // `AssignedValue` is cheap to clone, so this avoids keeping the cell alive in a separate binding
+    ExistingOwned(AssignedValue<F>),
     Witness(Value<F>),
     Constant(F),
 }
@@ -21,17 +104,30 @@ impl<F: FieldExt> QuantumCell<'_, F> {
     pub fn value(&self) -> Value<&F> {
         match self {
             Self::Existing(a) => a.value(),
+            Self::ExistingOwned(a) => a.value(),
             Self::Witness(a) => a.as_ref(),
             Self::Constant(a) => Value::known(a),
         }
     }
 }
 
+impl<'a, F: FieldExt> From<&'a AssignedValue<F>> for QuantumCell<'a, F> {
+    fn from(a: &'a AssignedValue<F>) -> Self {
+        Self::Existing(a)
+    }
+}
+
+impl<F: FieldExt> From<AssignedValue<F>> for QuantumCell<'_, F> {
+    fn from(a: AssignedValue<F>) -> Self {
+        Self::ExistingOwned(a)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct AssignedValue<F: FieldExt> {
     pub cell: Rc<Cell>,
     pub value: Rc<Value<F>>,
-    pub context_id: Rc<String>,
+    pub context_id: ContextId,
     column_index: usize,
     row_offset: usize,
     // the phase is provided for convenience; a more rigorous way to check the phase is to identify the column the cell is in using `column_index` and `row_offset` and call `column.phase()`
@@ -42,7 +138,7 @@ impl<F: FieldExt> AssignedValue<F> {
     pub fn new(
         cell: Cell,
         value: Value<F>,
-        context_id: Rc<String>,
+        context_id: ContextId,
         column_index: usize,
         row_offset: usize,
         phase: u8,
@@ -59,7 +155,7 @@ impl<F: FieldExt> AssignedValue<F> {
 
     pub fn from_assigned(
         assigned: AssignedCell<F, F>,
-        context_id: Rc<String>,
+        context_id: ContextId,
         column_index: usize,
         row_offset: usize,
         phase: u8,
@@ -74,8 +170,8 @@ impl<F: FieldExt> AssignedValue<F> {
         }
     }
 
-    pub fn context_id(&self) -> &String {
-        self.context_id.borrow()
+    pub fn context_id(&self) -> ContextId {
+        self.context_id
     }
 
     pub fn column(&self) -> usize {
@@ -120,28 +216,54 @@ impl<F: FieldExt> AssignedValue<F> {
 // The reason we have a `Context` is that we will need to mutably borrow `advice_rows` (etc.) to update row count
 // The `Circuit` trait takes in `Config` as an input that is NOT mutable, so we must pass around &mut Context everywhere for function calls
 // We follow halo2wrong's convention of having `Context` also include the `Region` to be passed around, instead of a `Layouter`, so that everything happens within a single `layouter.assign_region` call. This allows us to circumvent the Halo2 layouter and use our own "pseudo-layouter", which is more specialized (and hence faster) for our specific gates
+// All rows in a `Context` are offsets within its region, so a circuit may also create a new `Context` in each of several regions, e.g. to be laid out by `floor_planner::V1` next to other gadgets. Each region must then be finalized on its own (constants, lookups), and cells may be used across regions as `Existing`, which copies them
+// The region may be in shape mode, as in the first pass of `SimpleFloorPlanner` or the measurement pass of `floor_planner::V1`: everything is assigned as usual, only witness generation runs twice. With `SimpleFloorPlanner` and a single region, the first pass can be skipped by returning early, since the region starts at row 0 anyway
 #[derive(Debug)]
 pub struct Context<'a, F: FieldExt> {
     pub region: Region<'a, F>, // I don't see a reason to use Box<Region<'a, F>> since we will pass mutable reference of `Context` anyways
 
-    // `advice_rows[context_id][column_index]` keeps track of the number of rows used in the `column_index`-th column of the chip/config specified by the string `context_id`
-    pub advice_rows: HashMap<String, Vec<usize>>,
+    // `advice_rows[context_id][column_index]` keeps track of the number of rows used in the `column_index`-th column of the chip/config specified by `context_id`
+    pub advice_rows: HashMap<ContextId, Vec<usize>>,
 
-    // `constants_to_assign` is a vector keeping track of all constants that we use throughout
-    // we load them all in one go using fn `load_constants`
-    // if we have (c, Some(cell)) in the vector then we also constrain the loaded cell for `c` to equal `cell`
-    pub constants_to_assign: Vec<(F, Option<Cell>)>,
+    // `constants_to_assign` keeps track of all constants that we use throughout, added with `assign_constant`
+    // it has an entry `(c, cells)` for each distinct constant `c`, in order of first use, with the cells to constrain to equal `c`
+    // we load them all in one go using fn `assign_and_constrain_constants`, where each `c` takes a single fixed cell
+    pub constants_to_assign: Vec<(F, Vec<Cell>)>,
+    // the index in `constants_to_assign` of each constant
+    constant_indices: HashMap<BigUint, usize>,
     pub zero_cell: Option<AssignedValue<F>>,
 
-    pub challenge: HashMap<String, Value<F>>,
+    pub challenge: HashMap<ContextId, Value<F>>,
 
     // `cells_to_lookup` is a vector keeping track of all cells that we want to enable lookup for. When there is more than 1 advice column we will copy_advice all of these cells to the single lookup enabled column and do lookups there
     pub cells_to_lookup: Vec<AssignedValue<F>>,
+    // `cells_to_expose` are the cells to constrain to equal the instance column, with their rows in the column, see `expose_public`
+    pub cells_to_expose: Vec<(Cell, usize)>,
+    // `extra_cells_to_lookup[lookup_bits]` are the cells to look up in the additional range table with `lookup_bits` bits, see `RangeConfig::extra_tables`
+    pub extra_cells_to_lookup: HashMap<usize, Vec<AssignedValue<F>>>,
 
     current_phase: u8,
 
+    // if true, only the values of the cells are computed: constants, lookups and public cells are counted but never
+    // assigned or constrained, see `generate_witnesses`
+    pub witness_gen_only: bool,
+
     #[cfg(feature = "display")]
     pub op_count: HashMap<String, usize>,
+
+    // the tags pushed with `push_tag`, outermost first
+    #[cfg(feature = "provenance")]
+    tags: Vec<&'static str>,
+    // the rows assigned so far and the tags at the time, see `record_provenance`
+    #[cfg(feature = "provenance")]
+    pub provenance: provenance::ProvenanceLog,
+
+    // the tags pushed with `push_tag` and the cells used when each was pushed, see `tag_costs`
+    #[cfg(feature = "test-utils")]
+    cost_marks: Vec<(&'static str, gates::params::CircuitShape)>,
+    // the cells used under each tag, counting nested occurrences of a tag once, see `cost::CircuitCostReport`
+    #[cfg(feature = "test-utils")]
+    pub tag_costs: std::collections::BTreeMap<&'static str, gates::params::CircuitShape>,
 }
 
 impl<'a, F: FieldExt> std::fmt::Display for Context<'a, F> {
@@ -153,7 +275,7 @@ impl<'a, F: FieldExt> std::fmt::Display for Context<'a, F> {
 // a single struct to package any configuration parameters we will need for constructing a new `Context`
 #[derive(Clone, Debug)]
 pub struct ContextParams {
-    pub num_advice: Vec<(String, usize)>,
+    pub num_advice: Vec<(ContextId, usize)>,
 }
 
 impl<'a, F: FieldExt> Context<'a, F> {
@@ -167,15 +289,102 @@ impl<'a, F: FieldExt> Context<'a, F> {
             region,
             advice_rows,
             constants_to_assign: Vec::new(),
+            constant_indices: HashMap::new(),
             zero_cell: None,
             challenge: HashMap::new(),
             cells_to_lookup: Vec::new(),
+            extra_cells_to_lookup: HashMap::new(),
+            cells_to_expose: Vec::new(),
             current_phase: 0u8,
+            witness_gen_only: false,
             #[cfg(feature = "display")]
             op_count: HashMap::new(),
+            #[cfg(feature = "provenance")]
+            tags: Vec::new(),
+            #[cfg(feature = "provenance")]
+            provenance: provenance::ProvenanceLog::default(),
+            #[cfg(feature = "test-utils")]
+            cost_marks: Vec::new(),
+            #[cfg(feature = "test-utils")]
+            tag_costs: std::collections::BTreeMap::new(),
+        }
+    }
+
+    /// Runs `f` on a `Context` which is not part of any circuit and only computes the values of the cells, e.g. to
+    /// check the outputs of chips or count their cells without running `MockProver` on a large circuit.
+    ///
+    /// The region is a `RegionShape`, which records the columns used but ignores all assignments and constraints,
+    /// and the `Context` has `witness_gen_only` set, so `finalize` only returns the statistics of the circuit.
+    /// Values read back from the region, e.g. by `AssignedValue::from_assigned`, are unknown.
+    pub fn generate_witnesses<T>(
+        params: ContextParams,
+        f: impl FnOnce(&mut Context<'_, F>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut shape = RegionShape::new(0.into());
+        let region = Region::from(&mut shape as &mut dyn RegionLayouter<F>);
+        let mut ctx = Context::new(region, params);
+        ctx.witness_gen_only = true;
+        f(&mut ctx)
+    }
+
+    /// Tags the rows assigned until the matching `pop_tag`, e.g. with the name of an op, see `provenance`.
+    /// Tags nest, and only have an effect with the `provenance` or `test-utils` features.
+    pub fn push_tag(&mut self, _tag: &'static str) {
+        #[cfg(feature = "provenance")]
+        self.tags.push(_tag);
+        #[cfg(feature = "test-utils")]
+        {
+            let mark = self.cells_used();
+            self.cost_marks.push((_tag, mark));
         }
     }
 
+    pub fn pop_tag(&mut self) {
+        #[cfg(feature = "provenance")]
+        self.tags.pop().expect("pop_tag should match a push_tag");
+        #[cfg(feature = "test-utils")]
+        {
+            let (tag, mark) = self.cost_marks.pop().expect("pop_tag should match a push_tag");
+            // a tag nested in itself, e.g. by a recursive op, is only counted at its outermost occurrence
+            if self.cost_marks.iter().all(|(outer, _)| *outer != tag) {
+                let used = self.cells_used();
+                let cost = self.tag_costs.entry(tag).or_default();
+                cost.advice_cells += used.advice_cells - mark.advice_cells;
+                cost.lookup_cells += used.lookup_cells - mark.lookup_cells;
+                cost.fixed_cells += used.fixed_cells - mark.fixed_cells;
+            }
+        }
+    }
+
+    /// The cells used so far by all chips: advice cells, cells to look up in any range table, and distinct constants
+    #[cfg(feature = "test-utils")]
+    pub fn cells_used(&self) -> gates::params::CircuitShape {
+        gates::params::CircuitShape {
+            advice_cells: self.advice_rows.values().flatten().sum(),
+            lookup_cells: self.cells_to_lookup.len()
+                + self.extra_cells_to_lookup.values().map(Vec::len).sum::<usize>(),
+            fixed_cells: self.num_constants(),
+        }
+    }
+
+    /// With the `provenance` feature, records that `rows` of the advice column `column_index` of `context_id` were
+    /// assigned under the current tags
+    pub fn record_provenance(
+        &mut self,
+        _context_id: &ContextId,
+        _column_index: usize,
+        _rows: std::ops::Range<usize>,
+    ) {
+        #[cfg(feature = "provenance")]
+        self.provenance.blocks.push(provenance::Provenance {
+            context_id: *_context_id,
+            column_index: _column_index,
+            rows: _rows,
+            tags: self.tags.clone(),
+            backtrace: std::backtrace::Backtrace::capture(),
+        });
+    }
+
     pub fn next_phase(&mut self) {
         self.current_phase += 1;
     }
@@ -184,24 +393,53 @@ impl<'a, F: FieldExt> Context<'a, F> {
         self.current_phase
     }
 
-    pub fn advice_rows_get(&self, id: &String) -> &Vec<usize> {
+    pub fn advice_rows_get(&self, id: &ContextId) -> &Vec<usize> {
         self.advice_rows
             .get(id)
             .expect(format!("context_id {} should have advice rows", id).as_str())
     }
 
-    pub fn advice_rows_get_mut(&mut self, id: &String) -> &mut Vec<usize> {
+    pub fn advice_rows_get_mut(&mut self, id: &ContextId) -> &mut Vec<usize> {
         self.advice_rows
             .get_mut(id)
             .expect(format!("context_id {} should have advice rows", id).as_str())
     }
 
-    pub fn challenge_get(&self, id: &String) -> &Value<F> {
+    pub fn challenge_get(&self, id: &ContextId) -> &Value<F> {
         self.challenge.get(id).expect(format!("challenge {} should exist", id).as_str())
     }
 
+    /// Adds `c` to the constants loaded by `assign_and_constrain_constants`, constraining `cell` to equal it if given.
+    /// Each distinct constant is only loaded once, however many times it is added.
+    pub fn assign_constant(&mut self, c: F, cell: Option<Cell>) {
+        let index = *self.constant_indices.entry(fe_to_biguint(&c)).or_insert_with(|| {
+            self.constants_to_assign.push((c, Vec::new()));
+            self.constants_to_assign.len() - 1
+        });
+        if let Some(cell) = cell {
+            if !self.witness_gen_only {
+                self.constants_to_assign[index].1.push(cell);
+            }
+        }
+    }
+
+    /// Makes `a` the public input at `row` of the instance column.
+    ///
+    /// The `Region` cannot constrain cells to the instance column, so the cells are only recorded in `cells_to_expose`.
+    /// They must be passed to `FlexGateConfig::expose_public_cells` after the region is assigned.
+    pub fn expose_public(&mut self, a: &AssignedValue<F>, row: usize) {
+        if !self.witness_gen_only {
+            self.cells_to_expose.push((a.cell(), row));
+        }
+    }
+
+    /// The number of distinct constants added so far
+    pub fn num_constants(&self) -> usize {
+        self.constants_to_assign.len()
+    }
+
     /// returns leftmost `i` where `advice_rows[context_id][i]` is minimum amongst all `i`
-    pub fn min_gate_index(&self, context_id: &String) -> usize {
+    pub fn min_gate_index(&self, context_id: &ContextId) -> usize {
         self.advice_rows
             .get(context_id)
             .unwrap()
@@ -212,29 +450,22 @@ impl<'a, F: FieldExt> Context<'a, F> {
             .unwrap()
     }
 
-    /// Assuming that this is only called if ctx.region is not in shape mode!
     pub fn assign_cell(
         &mut self,
         input: QuantumCell<F>,
         column: Column<Advice>,
-        context_id: &Rc<String>,
+        context_id: &ContextId,
         column_index: usize,
         row_offset: usize,
         phase: u8,
     ) -> Result<AssignedValue<F>, Error> {
         match input {
-            QuantumCell::Existing(acell) => Ok(AssignedValue {
-                cell: Rc::new(
-                    acell
-                        .copy_advice(|| "gate: copy advice", &mut self.region, column, row_offset)?
-                        .cell(),
-                ),
-                value: acell.value.clone(),
-                context_id: context_id.clone(),
-                column_index,
-                row_offset,
-                phase,
-            }),
+            QuantumCell::Existing(acell) => {
+                self.copy_cell(acell, column, context_id, column_index, row_offset, phase)
+            }
+            QuantumCell::ExistingOwned(acell) => {
+                self.copy_cell(&acell, column, context_id, column_index, row_offset, phase)
+            }
             QuantumCell::Witness(val) => Ok(AssignedValue {
                 cell: Rc::new(
                     self.region
@@ -242,7 +473,7 @@ impl<'a, F: FieldExt> Context<'a, F> {
                         .cell(),
                 ),
                 value: Rc::new(val),
-                context_id: context_id.clone(),
+                context_id: *context_id,
                 column_index,
                 row_offset,
                 phase,
@@ -252,11 +483,11 @@ impl<'a, F: FieldExt> Context<'a, F> {
                     .region
                     .assign_advice(|| "gate: assign const", column, row_offset, || Value::known(c))?
                     .cell();
-                self.constants_to_assign.push((c, Some(cell)));
+                self.assign_constant(c, Some(cell));
                 Ok(AssignedValue {
                     cell: Rc::new(cell),
                     value: Rc::new(Value::known(c)),
-                    context_id: context_id.clone(),
+                    context_id: *context_id,
                     column_index,
                     row_offset,
                     phase,
@@ -265,45 +496,64 @@ impl<'a, F: FieldExt> Context<'a, F> {
         }
     }
 
-    /// call this at the very end of synthesize!
-    /// assumes self.region is not in shape mode
+    // copies `acell` to `column` at `row_offset` and constrains the copy to equal it
+    fn copy_cell(
+        &mut self,
+        acell: &AssignedValue<F>,
+        column: Column<Advice>,
+        context_id: &ContextId,
+        column_index: usize,
+        row_offset: usize,
+        phase: u8,
+    ) -> Result<AssignedValue<F>, Error> {
+        Ok(AssignedValue {
+            cell: Rc::new(
+                acell
+                    .copy_advice(|| "gate: copy advice", &mut self.region, column, row_offset)?
+                    .cell(),
+            ),
+            value: acell.value.clone(),
+            context_id: *context_id,
+            column_index,
+            row_offset,
+            phase,
+        })
+    }
+
+    /// call this at the very end of the region!
     pub fn assign_and_constrain_constants(
         &mut self,
         fixed_columns: &Vec<Column<Fixed>>,
     ) -> Result<(usize, usize), Error> {
+        if self.witness_gen_only {
+            let num_constants = self.constants_to_assign.len();
+            return Ok((num_constants / fixed_columns.len(), num_constants));
+        }
         // load constants cyclically over `fixed_columns.len()` columns
-        let mut assigned: HashMap<BigUint, AssignedCell<F, F>> = HashMap::new();
+        // the constants are distinct, so each takes a single fixed cell
         let mut col = 0;
         let mut offset = 0;
 
-        for (c, ocell) in &self.constants_to_assign {
-            let c_big = fe_to_biguint(c);
-            let c_cell = if let Some(c_cell) = assigned.get(&c_big) {
-                c_cell.clone()
-            } else {
-                let c_cell = self.region.assign_fixed(
-                    || "load constant",
-                    fixed_columns[col],
-                    offset,
-                    || Value::known(c.clone()),
-                )?;
-                assigned.insert(c_big, c_cell.clone());
-                col += 1;
-                if col == fixed_columns.len() {
-                    col = 0;
-                    offset += 1;
-                }
-                c_cell
-            };
-            if let Some(cell) = ocell {
-                self.region.constrain_equal(c_cell.cell(), cell.clone())?;
+        for (c, cells) in &self.constants_to_assign {
+            let c_cell = self.region.assign_fixed(
+                || "load constant",
+                fixed_columns[col],
+                offset,
+                || Value::known(*c),
+            )?;
+            for cell in cells {
+                self.region.constrain_equal(c_cell.cell(), *cell)?;
+            }
+            col += 1;
+            if col == fixed_columns.len() {
+                col = 0;
+                offset += 1;
             }
         }
-        Ok((offset, assigned.len()))
+        Ok((offset, self.constants_to_assign.len()))
     }
 
-    /// call this at the very end of synthesize!
-    /// assumes self.region is not in shape mode
+    /// call this at the very end of the region!
     pub fn copy_and_lookup_cells(
         &mut self,
         lookup_advice: &[Vec<Column<Advice>>],
@@ -314,12 +564,14 @@ impl<'a, F: FieldExt> Context<'a, F> {
         for acell in &self.cells_to_lookup {
             let phase = acell.phase as usize;
             assert!(phase < NUM_PHASE);
-            acell.copy_advice(
-                || "copy lookup cell",
-                &mut self.region,
-                lookup_advice[phase][col[phase]],
-                offset[phase],
-            )?;
+            if !self.witness_gen_only {
+                acell.copy_advice(
+                    || "copy lookup cell",
+                    &mut self.region,
+                    lookup_advice[phase][col[phase]],
+                    offset[phase],
+                )?;
+            }
             col[phase] += 1;
             if col[phase] == lookup_advice[phase].len() {
                 col[phase] = 0;
@@ -328,4 +580,29 @@ impl<'a, F: FieldExt> Context<'a, F> {
         }
         Ok(offset.to_vec())
     }
+
+    /// call this at the very end of the region!
+    /// copies the cells to look up in the additional range table with `lookup_bits` bits to its `lookup_advice` columns
+    /// and returns the number of rows used
+    pub fn copy_and_lookup_extra_cells(
+        &mut self,
+        lookup_bits: usize,
+        lookup_advice: &[Column<Advice>],
+    ) -> Result<usize, Error> {
+        let cells = match self.extra_cells_to_lookup.get(&lookup_bits) {
+            Some(cells) => cells,
+            None => return Ok(0),
+        };
+        if !self.witness_gen_only {
+            for (i, acell) in cells.iter().enumerate() {
+                acell.copy_advice(
+                    || "copy extra lookup cell",
+                    &mut self.region,
+                    lookup_advice[i % lookup_advice.len()],
+                    i / lookup_advice.len(),
+                )?;
+            }
+        }
+        Ok((cells.len() + lookup_advice.len() - 1) / lookup_advice.len())
+    }
 }
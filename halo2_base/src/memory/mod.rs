@@ -0,0 +1,261 @@
+// In circuit read/write memory, checked with a permutation argument over the trace of accesses
+//
+// Each access is logged as a tuple `(address, timestamp, value, is_write)`, where the timestamps are the constants
+// `0, 1, 2, ...` in the order of the accesses, and costs O(1) cells. `finalize` witnesses the trace sorted by
+// `(address, timestamp)`, checks that it is sorted and that every read returns the last value written to its
+// address (or 0 if there is none), and checks that it is a permutation of the logged trace:
+// `prod (beta - fingerprint(access)) = prod (beta - fingerprint(sorted access))`, where
+// `fingerprint = address + gamma * timestamp + gamma^2 * value + gamma^3 * is_write`.
+// The challenges are derived in circuit by hashing both traces, so the sorted trace is committed before they are known.
+// The total cost is O(n) cells plus hashing 6n elements for n accesses, instead of O(size) per access with
+// `select_from_idx`.
+
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    hashes::AlgebraicHasher,
+    utils::fe_to_biguint,
+    AssignedValue, Context, QuantumCell,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct MemoryAccess<F: FieldExt> {
+    pub address: AssignedValue<F>,
+    pub timestamp: usize,
+    pub value: AssignedValue<F>,
+    pub is_write: bool,
+}
+
+/// A memory of field elements at addresses in `[0, 2^addr_bits)`, initialized to zero
+///
+/// The accesses are only constrained by `finalize`, which must be called once all accesses are made.
+#[derive(Clone, Debug)]
+pub struct Memory<F: FieldExt> {
+    pub addr_bits: usize,
+    pub log: Vec<MemoryAccess<F>>,
+    // out of circuit contents of the memory, used to witness reads
+    state: HashMap<BigUint, F>,
+}
+
+impl<F: FieldExt> Memory<F> {
+    pub fn new(addr_bits: usize) -> Self {
+        assert!(addr_bits > 0 && addr_bits < F::CAPACITY as usize);
+        Self { addr_bits, log: Vec::new(), state: HashMap::new() }
+    }
+
+    /// A read-only memory (ROM) holding `values[i]` at address `i`, for indexing into `values` many times:
+    /// each `read` costs O(1) cells and `finalize` costs O(values.len() + reads) cells in total.
+    /// Addresses from `values.len()` up to the next power of two read 0.
+    pub fn from_values(
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        values: &[AssignedValue<F>],
+    ) -> Result<Self, Error> {
+        assert!(!values.is_empty());
+        let addr_bits = usize::BITS as usize - (values.len() - 1).leading_zeros() as usize;
+        let mut memory = Self::new(std::cmp::max(addr_bits, 1));
+        let addresses = gate.assign_region_smart(
+            ctx,
+            (0..values.len()).map(|i| Constant(F::from(i as u64))).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        for (address, value) in addresses.iter().zip(values.iter()) {
+            memory.log_write(address, value);
+        }
+        Ok(memory)
+    }
+
+    /// Returns the value at `address` and range checks `address`
+    pub fn read(
+        &mut self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        address: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        range.range_check(ctx, address, self.addr_bits)?;
+        let value = address
+            .value()
+            .map(|addr| self.state.get(&fe_to_biguint(addr)).copied().unwrap_or_else(F::zero));
+        let value =
+            range.gate().assign_region_smart(ctx, vec![Witness(value)], vec![], vec![], vec![])?;
+        let value = value.into_iter().next().unwrap();
+        self.log.push(MemoryAccess {
+            address: address.clone(),
+            timestamp: self.log.len(),
+            value: value.clone(),
+            is_write: false,
+        });
+        Ok(value)
+    }
+
+    /// Writes `value` to `address` and range checks `address`
+    pub fn write(
+        &mut self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        address: &AssignedValue<F>,
+        value: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        range.range_check(ctx, address, self.addr_bits)?;
+        self.log_write(address, value);
+        Ok(())
+    }
+
+    // logs a write without range checking `address`
+    fn log_write(&mut self, address: &AssignedValue<F>, value: &AssignedValue<F>) {
+        let state = &mut self.state;
+        address
+            .value()
+            .zip(value.value())
+            .map(|(addr, val)| state.insert(fe_to_biguint(addr), *val));
+        self.log.push(MemoryAccess {
+            address: address.clone(),
+            timestamp: self.log.len(),
+            value: value.clone(),
+            is_write: true,
+        });
+    }
+
+    /// Constrains all accesses in `log` to be consistent with a read/write memory
+    ///
+    /// `hasher` derives the challenges of the permutation argument.
+    pub fn finalize(
+        &self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        hasher: &impl AlgebraicHasher<F>,
+    ) -> Result<(), Error> {
+        if self.log.is_empty() {
+            return Ok(());
+        }
+        let gate = range.gate();
+        let n = self.log.len();
+
+        // witness the trace sorted by (address, timestamp)
+        let trace = self.log.iter().fold(Value::known(Vec::with_capacity(n)), |acc, access| {
+            acc.zip(access.address.value().zip(access.value.value())).map(
+                |(mut acc, (addr, val))| {
+                    acc.push((*addr, *val));
+                    acc
+                },
+            )
+        });
+        let sorted = trace.map(|trace| {
+            let mut sorted: Vec<_> = trace
+                .into_iter()
+                .zip(self.log.iter())
+                .map(|((addr, val), access)| (addr, access.timestamp, val, access.is_write))
+                .collect();
+            sorted.sort_by_key(|(addr, timestamp, _, _)| (fe_to_biguint(addr), *timestamp));
+            sorted
+        });
+        let mut witnesses = Vec::with_capacity(4 * n);
+        for i in 0..n {
+            let row = sorted.as_ref().map(|sorted| sorted[i]);
+            witnesses.push(Witness(row.map(|row| row.0)));
+            witnesses.push(Witness(row.map(|row| F::from(row.1 as u64))));
+            witnesses.push(Witness(row.map(|row| row.2)));
+            witnesses.push(Witness(row.map(|row| F::from(row.3 as u64))));
+        }
+        let sorted = gate.assign_region_smart(ctx, witnesses, vec![], vec![], vec![])?;
+        let sorted: Vec<_> = sorted.chunks(4).collect();
+
+        // check the sorted trace is sorted and every read returns the last value written
+        let time_bits = usize::BITS as usize - n.leading_zeros() as usize;
+        let diff_bits = std::cmp::max(self.addr_bits, time_bits);
+        for i in 0..n {
+            let (addr, timestamp, val, is_write) =
+                (&sorted[i][0], &sorted[i][1], &sorted[i][2], &sorted[i][3]);
+            let diff = if i == 0 {
+                val.clone()
+            } else {
+                let (prev_addr, prev_timestamp, prev_val) =
+                    (&sorted[i - 1][0], &sorted[i - 1][1], &sorted[i - 1][2]);
+                let same = range.is_equal(ctx, &Existing(addr), &Existing(prev_addr))?;
+                // the timestamps increase at the same address and the addresses increase otherwise
+                let time_diff = gate.sub(ctx, &Existing(timestamp), &Existing(prev_timestamp))?;
+                let addr_diff = gate.sub(ctx, &Existing(addr), &Existing(prev_addr))?;
+                let step = gate.select(
+                    ctx,
+                    &Existing(&time_diff),
+                    &Existing(&addr_diff),
+                    &Existing(&same),
+                )?;
+                let step = gate.sub(ctx, &Existing(&step), &Constant(F::one()))?;
+                range.range_check(ctx, &step, diff_bits)?;
+
+                let expected = gate.mul(ctx, &Existing(&same), &Existing(prev_val))?;
+                gate.sub(ctx, &Existing(val), &Existing(&expected))?
+            };
+            // a read has `diff = 0`
+            let diff_if_write = gate.mul(ctx, &Existing(&diff), &Existing(is_write))?;
+            gate.assert_equal(ctx, &Existing(&diff), &Existing(&diff_if_write))?;
+        }
+
+        // check the sorted trace is a permutation of the log
+        let mut inputs = Vec::with_capacity(6 * n);
+        for access in self.log.iter() {
+            inputs.push(access.address.clone());
+            inputs.push(access.value.clone());
+        }
+        inputs.extend(sorted.iter().flat_map(|row| row.iter().cloned()));
+        let gamma = hasher.hash(ctx, gate, &inputs)?;
+        let beta = hasher.hash(ctx, gate, &[gamma.clone()])?;
+        let gamma_sq = gate.mul(ctx, &Existing(&gamma), &Existing(&gamma))?;
+        let gamma_cube = gate.mul(ctx, &Existing(&gamma_sq), &Existing(&gamma))?;
+        let powers =
+            vec![Constant(F::one()), Existing(&gamma), Existing(&gamma_sq), Existing(&gamma_cube)];
+
+        let log_rows = self
+            .log
+            .iter()
+            .map(|access| {
+                vec![
+                    Existing(&access.address),
+                    Constant(F::from(access.timestamp as u64)),
+                    Existing(&access.value),
+                    Constant(F::from(access.is_write as u64)),
+                ]
+            })
+            .collect();
+        let sorted_rows = sorted.iter().map(|row| row.iter().map(Existing).collect()).collect();
+        let lhs = fingerprint_product(gate, ctx, &beta, &powers, log_rows)?;
+        let rhs = fingerprint_product(gate, ctx, &beta, &powers, sorted_rows)?;
+        gate.assert_equal(ctx, &Existing(&lhs), &Existing(&rhs))?;
+
+        #[cfg(feature = "display")]
+        {
+            let count = ctx.op_count.entry("memory access".to_string()).or_insert(0);
+            *count += n;
+        }
+        Ok(())
+    }
+}
+
+// returns `prod_i (beta - <rows[i], powers>)`
+fn fingerprint_product<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    beta: &AssignedValue<F>,
+    powers: &Vec<QuantumCell<F>>,
+    rows: Vec<Vec<QuantumCell<F>>>,
+) -> Result<AssignedValue<F>, Error> {
+    let mut prod: Option<AssignedValue<F>> = None;
+    for row in rows.iter() {
+        let fingerprint = gate.inner_product(ctx, row, powers)?;
+        let term = gate.sub(ctx, &Existing(beta), &Existing(&fingerprint))?;
+        prod = Some(match prod {
+            Some(prod) => gate.mul(ctx, &Existing(&prod), &Existing(&term))?,
+            None => term,
+        });
+    }
+    Ok(prod.unwrap())
+}
@@ -0,0 +1,248 @@
+use super::*;
+use crate::{
+    gates::range::{RangeConfig, RangeStrategy},
+    hashes::poseidon::PoseidonSpec,
+    ContextId, ContextParams,
+};
+use halo2_proofs::{circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*};
+
+const NUM_ADVICE: usize = 4;
+const ADDR_BITS: usize = 4;
+
+#[derive(Clone, Copy)]
+enum Op {
+    Read(u64),
+    Write(u64, u64),
+}
+
+struct MemoryCircuit {
+    hasher: PoseidonSpec<Fr, 3, 2>,
+    ops: Vec<Op>,
+    // the value returned by each read, in order
+    expected: Vec<u64>,
+    // appends a read of address 0 claiming this value, bypassing `Memory::read`
+    forged_read: Option<u64>,
+}
+
+impl Circuit<Fr> for MemoryCircuit {
+    type Config = RangeConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            hasher: self.hasher.clone(),
+            ops: self.ops.clone(),
+            expected: self.expected.clone(),
+            forged_read: self.forged_read,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "memory",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+                let witness = |ctx: &mut Context<'_, Fr>, x: u64| {
+                    config
+                        .gate
+                        .assign_region_smart(
+                            ctx,
+                            vec![Witness(Value::known(Fr::from(x)))],
+                            vec![],
+                            vec![],
+                            vec![],
+                        )
+                        .map(|mut assigned| assigned.pop().unwrap())
+                };
+
+                let mut memory = Memory::new(ADDR_BITS);
+                let mut expected = self.expected.iter();
+                for op in self.ops.iter() {
+                    match *op {
+                        Op::Read(addr) => {
+                            let addr = witness(ctx, addr)?;
+                            let value = memory.read(&config, ctx, &addr)?;
+                            config.gate.assert_is_const(
+                                ctx,
+                                &value,
+                                Fr::from(*expected.next().unwrap()),
+                            );
+                        }
+                        Op::Write(addr, value) => {
+                            let addr = witness(ctx, addr)?;
+                            let value = witness(ctx, value)?;
+                            memory.write(&config, ctx, &addr, &value)?;
+                        }
+                    }
+                }
+                if let Some(value) = self.forged_read {
+                    let address = witness(ctx, 0)?;
+                    let value = witness(ctx, value)?;
+                    let timestamp = memory.log.len();
+                    memory.log.push(MemoryAccess { address, timestamp, value, is_write: false });
+                }
+                memory.finalize(&config, ctx, &self.hasher)?;
+
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn circuit(forged_read: Option<u64>) -> MemoryCircuit {
+    let ops = vec![
+        Op::Read(3),
+        Op::Write(3, 7),
+        Op::Write(0, 11),
+        Op::Read(3),
+        Op::Write(15, 2),
+        Op::Write(3, 9),
+        Op::Read(0),
+        Op::Read(3),
+        Op::Read(15),
+        Op::Read(8),
+    ];
+    MemoryCircuit {
+        hasher: PoseidonSpec::new(8, 57),
+        ops,
+        expected: vec![0, 7, 11, 9, 2, 0],
+        forged_read,
+    }
+}
+
+#[test]
+fn test_memory() {
+    let prover = MockProver::run(15, &circuit(None), vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_memory_forged_read() {
+    // the last write to address 0 is 11
+    let prover = MockProver::run(15, &circuit(Some(11)), vec![]).unwrap();
+    prover.assert_satisfied();
+    let prover = MockProver::run(15, &circuit(Some(12)), vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+struct RomCircuit {
+    hasher: PoseidonSpec<Fr, 3, 2>,
+    values: Vec<u64>,
+    indices: Vec<u64>,
+}
+
+impl Circuit<Fr> for RomCircuit {
+    type Config = RangeConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            hasher: self.hasher.clone(),
+            values: self.values.clone(),
+            indices: self.indices.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "rom",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut witnesses: Vec<_> =
+                    self.values.iter().map(|x| Witness(Value::known(Fr::from(*x)))).collect();
+                witnesses.extend(self.indices.iter().map(|i| Witness(Value::known(Fr::from(*i)))));
+                let mut values =
+                    config.gate.assign_region_smart(ctx, witnesses, vec![], vec![], vec![])?;
+                let indices = values.split_off(self.values.len());
+
+                let mut rom = config.gate.load_rom(ctx, &values)?;
+                for (idx, i) in indices.iter().zip(self.indices.iter()) {
+                    let value = config.select_from_rom(ctx, &mut rom, idx)?;
+                    let expected = self.values.get(*i as usize).copied().unwrap_or(0);
+                    config.gate.assert_is_const(ctx, &value, Fr::from(expected));
+                }
+                rom.finalize(&config, ctx, &self.hasher)?;
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_rom() {
+    let circuit = RomCircuit {
+        hasher: PoseidonSpec::new(8, 57),
+        values: vec![5, 8, 13, 21, 34],
+        indices: vec![4, 0, 4, 2, 7, 1],
+    };
+    let prover = MockProver::run(15, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
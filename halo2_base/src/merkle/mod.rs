@@ -0,0 +1,132 @@
+use crate::{
+    gates::GateInstructions, hashes::AlgebraicHasher, AssignedValue, Context, QuantumCell::Existing,
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+pub mod sparse;
+
+#[cfg(test)]
+mod tests;
+
+/// Out of circuit binary Merkle tree with `hash(left, right)` as the node hash
+///
+/// `layers[0]` are the leaves and `layers[depth]` is the root. The number of leaves must be a power of two.
+#[derive(Clone, Debug)]
+pub struct MerkleTree<F: FieldExt> {
+    pub layers: Vec<Vec<F>>,
+}
+
+impl<F: FieldExt> MerkleTree<F> {
+    pub fn new(hasher: &impl AlgebraicHasher<F>, leaves: Vec<F>) -> Self {
+        assert!(leaves.len().is_power_of_two());
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hasher.hash_native(&[pair[0], pair[1]]))
+                .collect();
+            layers.push(next);
+        }
+        Self { layers }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.layers[self.depth()][0]
+    }
+
+    /// Returns the siblings of the path from leaf `index` to the root, starting at the leaf
+    pub fn proof(&self, index: usize) -> Vec<F> {
+        (0..self.depth()).map(|level| self.layers[level][(index >> level) ^ 1]).collect()
+    }
+}
+
+/// Out of circuit root of the path from `leaf` with the given `siblings`, where bit `i` of `index` is 1 if the
+/// node at level `i` is a right child
+pub fn compute_root_native<F: FieldExt>(
+    hasher: &impl AlgebraicHasher<F>,
+    leaf: F,
+    siblings: &[F],
+    index: usize,
+) -> F {
+    siblings.iter().enumerate().fold(leaf, |node, (level, sibling)| {
+        if (index >> level) & 1 == 1 {
+            hasher.hash_native(&[*sibling, node])
+        } else {
+            hasher.hash_native(&[node, *sibling])
+        }
+    })
+}
+
+/// Input: `leaf`, the `siblings` of its path from the leaf to the root and `index_bits`, where `index_bits[i]` is 1
+/// if the node at level `i` is a right child. `index_bits` are constrained to be bits.
+///
+/// Output: the root of the path
+pub fn compute_root<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    hasher: &impl AlgebraicHasher<F>,
+    leaf: &AssignedValue<F>,
+    siblings: &[AssignedValue<F>],
+    index_bits: &[AssignedValue<F>],
+) -> Result<AssignedValue<F>, Error> {
+    assert_eq!(siblings.len(), index_bits.len());
+    let mut node = leaf.clone();
+    for (sibling, bit) in siblings.iter().zip(index_bits.iter()) {
+        gate.assert_bit(ctx, bit)?;
+        let left = gate.select(ctx, &Existing(sibling), &Existing(&node), &Existing(bit))?;
+        let right = gate.select(ctx, &Existing(&node), &Existing(sibling), &Existing(bit))?;
+        node = hasher.hash(ctx, gate, &[left, right])?;
+    }
+    Ok(node)
+}
+
+/// Constrains that `leaf` is included in the tree with root `root` at the position given by `index_bits`,
+/// see `compute_root`
+pub fn verify_path<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    hasher: &impl AlgebraicHasher<F>,
+    leaf: &AssignedValue<F>,
+    siblings: &[AssignedValue<F>],
+    index_bits: &[AssignedValue<F>],
+    root: &AssignedValue<F>,
+) -> Result<(), Error> {
+    let computed = compute_root(gate, ctx, hasher, leaf, siblings, index_bits)?;
+    ctx.region.constrain_equal(computed.cell(), root.cell())?;
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("merkle path length {}", siblings.len());
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+    Ok(())
+}
+
+/// A leaf together with its Merkle path, as taken by `verify_path`
+#[derive(Clone, Debug)]
+pub struct MerklePath<F: FieldExt> {
+    pub leaf: AssignedValue<F>,
+    pub siblings: Vec<AssignedValue<F>>,
+    pub index_bits: Vec<AssignedValue<F>>,
+}
+
+/// Constrains that every path in `paths` is included in the tree with root `root`
+pub fn verify_batch<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    hasher: &impl AlgebraicHasher<F>,
+    paths: &[MerklePath<F>],
+    root: &AssignedValue<F>,
+) -> Result<(), Error> {
+    for path in paths {
+        verify_path(gate, ctx, hasher, &path.leaf, &path.siblings, &path.index_bits, root)?;
+    }
+    Ok(())
+}
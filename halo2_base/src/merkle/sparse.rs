@@ -0,0 +1,194 @@
+use super::compute_root;
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    hashes::AlgebraicHasher,
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use std::collections::HashMap;
+
+/// Returns `defaults` where `defaults[0] = empty_leaf` and `defaults[i + 1] = hash(defaults[i], defaults[i])`,
+/// i.e. `defaults[i]` is the root of an empty subtree of depth `i`
+pub fn default_nodes<F: FieldExt>(
+    hasher: &impl AlgebraicHasher<F>,
+    empty_leaf: F,
+    depth: usize,
+) -> Vec<F> {
+    let mut defaults = vec![empty_leaf];
+    for i in 0..depth {
+        defaults.push(hasher.hash_native(&[defaults[i], defaults[i]]));
+    }
+    defaults
+}
+
+/// Out of circuit sparse Merkle tree of depth `depth` with leaves indexed by keys in [0, 2^depth)
+///
+/// Leaves that were never set are `empty_leaf`; only nodes that differ from the default node of their level are stored.
+#[derive(Clone, Debug)]
+pub struct SparseMerkleTree<F: FieldExt> {
+    pub depth: usize,
+    pub defaults: Vec<F>,
+    // (level, index) -> node
+    nodes: HashMap<(usize, u64), F>,
+}
+
+impl<F: FieldExt> SparseMerkleTree<F> {
+    pub fn new(hasher: &impl AlgebraicHasher<F>, empty_leaf: F, depth: usize) -> Self {
+        assert!(depth <= 64);
+        Self { depth, defaults: default_nodes(hasher, empty_leaf, depth), nodes: HashMap::new() }
+    }
+
+    fn node(&self, level: usize, index: u64) -> F {
+        *self.nodes.get(&(level, index)).unwrap_or(&self.defaults[level])
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    pub fn get(&self, key: u64) -> F {
+        self.node(0, key)
+    }
+
+    /// Returns the siblings of the path from leaf `key` to the root, starting at the leaf
+    pub fn proof(&self, key: u64) -> Vec<F> {
+        (0..self.depth).map(|level| self.node(level, (key >> level) ^ 1)).collect()
+    }
+
+    /// Sets leaf `key` to `value` and recomputes the nodes on its path
+    pub fn update(&mut self, hasher: &impl AlgebraicHasher<F>, key: u64, value: F) {
+        assert!(self.depth == 64 || key >> self.depth == 0);
+        let mut node = value;
+        for level in 0..=self.depth {
+            let index = if level == 64 { 0 } else { key >> level };
+            if node == self.defaults[level] {
+                self.nodes.remove(&(level, index));
+            } else {
+                self.nodes.insert((level, index), node);
+            }
+            if level < self.depth {
+                let sibling = self.node(level, index ^ 1);
+                node = if index & 1 == 1 {
+                    hasher.hash_native(&[sibling, node])
+                } else {
+                    hasher.hash_native(&[node, sibling])
+                };
+            }
+        }
+    }
+}
+
+/// The path of a key in a sparse Merkle tree: `key_bits[i]` is 1 if the node at level `i` is a right child
+#[derive(Clone, Debug)]
+pub struct SparseMerkleProof<F: FieldExt> {
+    pub key_bits: Vec<AssignedValue<F>>,
+    pub siblings: Vec<AssignedValue<F>>,
+}
+
+/// In circuit verification of sparse Merkle tree proofs and transitions for a tree with the given `empty_leaf`
+#[derive(Clone, Debug)]
+pub struct SparseMerkleChip<'a, F: FieldExt, H: AlgebraicHasher<F>> {
+    pub hasher: &'a H,
+    pub empty_leaf: F,
+}
+
+impl<'a, F: FieldExt, H: AlgebraicHasher<F>> SparseMerkleChip<'a, F, H> {
+    pub fn construct(hasher: &'a H, empty_leaf: F) -> Self {
+        Self { hasher, empty_leaf }
+    }
+
+    /// Returns the `depth` little-endian bits of `key` and constrains `key < 2^depth`
+    pub fn key_to_bits(
+        &self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        key: &AssignedValue<F>,
+        depth: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        range.num_to_bits(ctx, key, depth)
+    }
+
+    fn load_empty_leaf(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let leaf =
+            gate.assign_region_smart(ctx, vec![Constant(self.empty_leaf)], vec![], vec![], vec![])?;
+        Ok(leaf[0].clone())
+    }
+
+    fn compute_root(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        proof: &SparseMerkleProof<F>,
+        value: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        compute_root(gate, ctx, self.hasher, value, &proof.siblings, &proof.key_bits)
+    }
+
+    /// Constrains that the leaf at `proof.key_bits` is `value` in the tree with root `root`
+    pub fn verify_membership(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        proof: &SparseMerkleProof<F>,
+        value: &AssignedValue<F>,
+        root: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        let computed = self.compute_root(gate, ctx, proof, value)?;
+        ctx.region.constrain_equal(computed.cell(), root.cell())
+    }
+
+    /// Constrains that the leaf at `proof.key_bits` is empty in the tree with root `root`
+    pub fn verify_non_membership(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        proof: &SparseMerkleProof<F>,
+        root: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        let empty = self.load_empty_leaf(gate, ctx)?;
+        self.verify_membership(gate, ctx, proof, &empty, root)
+    }
+
+    /// Constrains that the leaf at `proof.key_bits` is `old_value` in the tree with root `old_root`
+    /// and returns the root of the tree after setting the leaf to `new_value`
+    ///
+    /// Both roots are computed from the same siblings, so no other leaf changes.
+    pub fn verify_update(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        proof: &SparseMerkleProof<F>,
+        old_value: &AssignedValue<F>,
+        new_value: &AssignedValue<F>,
+        old_root: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.verify_membership(gate, ctx, proof, old_value, old_root)?;
+        let new_root = self.compute_root(gate, ctx, proof, new_value)?;
+
+        #[cfg(feature = "display")]
+        {
+            let key = format!("sparse merkle update depth {}", proof.siblings.len());
+            let count = ctx.op_count.entry(key).or_insert(0);
+            *count += 1;
+        }
+        Ok(new_root)
+    }
+
+    /// Same as `verify_update` where the leaf must be empty in the tree with root `old_root`
+    pub fn verify_insert(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        proof: &SparseMerkleProof<F>,
+        value: &AssignedValue<F>,
+        old_root: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let empty = self.load_empty_leaf(gate, ctx)?;
+        self.verify_update(gate, ctx, proof, &empty, value, old_root)
+    }
+}
@@ -0,0 +1,275 @@
+use super::{
+    compute_root_native,
+    sparse::{default_nodes, SparseMerkleChip, SparseMerkleProof, SparseMerkleTree},
+    verify_batch, MerklePath, MerkleTree,
+};
+use crate::{
+    gates::{
+        flex_gate::{FlexGateConfig, GateStrategy},
+        GateInstructions,
+    },
+    hashes::poseidon::PoseidonSpec,
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*};
+
+const NUM_ADVICE: usize = 2;
+
+#[derive(Clone)]
+struct PathWitness {
+    leaf: Value<Fr>,
+    siblings: Vec<Value<Fr>>,
+    index: Value<usize>,
+}
+
+struct MerkleCircuit {
+    hasher: PoseidonSpec<Fr, 3, 2>,
+    depth: usize,
+    paths: Vec<PathWitness>,
+    root: Value<Fr>,
+}
+
+impl Circuit<Fr> for MerkleCircuit {
+    type Config = FlexGateConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        let path = PathWitness {
+            leaf: Value::unknown(),
+            siblings: vec![Value::unknown(); self.depth],
+            index: Value::unknown(),
+        };
+        Self {
+            hasher: self.hasher.clone(),
+            depth: self.depth,
+            paths: vec![path; self.paths.len()],
+            root: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        FlexGateConfig::configure(
+            meta,
+            GateStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "merkle",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut paths = Vec::with_capacity(self.paths.len());
+                for path in self.paths.iter() {
+                    let mut cells = vec![Witness(path.leaf)];
+                    cells.extend(path.siblings.iter().map(|x| Witness(*x)));
+                    cells.extend(
+                        (0..self.depth).map(|i| {
+                            Witness(path.index.map(|idx| Fr::from(((idx >> i) & 1) as u64)))
+                        }),
+                    );
+                    let mut assigned =
+                        config.assign_region_smart(ctx, cells, vec![], vec![], vec![])?;
+                    let index_bits = assigned.split_off(1 + self.depth);
+                    let siblings = assigned.split_off(1);
+                    paths.push(MerklePath { leaf: assigned.pop().unwrap(), siblings, index_bits });
+                }
+                let root = config.assign_region_smart(
+                    ctx,
+                    vec![Witness(self.root)],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+
+                verify_batch(&config, ctx, &self.hasher, &paths, &root[0])?;
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn circuit(indices: &[usize], tamper: bool) -> MerkleCircuit {
+    let hasher = PoseidonSpec::<Fr, 3, 2>::new(8, 57);
+    let leaves: Vec<Fr> = (0..8u64).map(|i| Fr::from(100 + i)).collect();
+    let tree = MerkleTree::new(&hasher, leaves.clone());
+
+    let paths = indices
+        .iter()
+        .map(|&index| {
+            let siblings = tree.proof(index);
+            assert_eq!(compute_root_native(&hasher, leaves[index], &siblings, index), tree.root());
+            let leaf = if tamper { leaves[index] + Fr::from(1) } else { leaves[index] };
+            PathWitness {
+                leaf: Value::known(leaf),
+                siblings: siblings.into_iter().map(Value::known).collect(),
+                index: Value::known(index),
+            }
+        })
+        .collect();
+    MerkleCircuit { hasher, depth: tree.depth(), paths, root: Value::known(tree.root()) }
+}
+
+#[test]
+fn test_merkle_verify_batch() {
+    let prover = MockProver::run(16, &circuit(&[0, 5, 6], false), vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_merkle_wrong_leaf() {
+    let prover = MockProver::run(16, &circuit(&[3], true), vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[derive(Clone)]
+struct SparseMerkleCircuit {
+    hasher: PoseidonSpec<Fr, 3, 2>,
+    // (key, old value, new value, siblings) of each transition, applied in order
+    updates: Vec<(u64, Fr, Fr, Vec<Fr>)>,
+    // key and siblings of a key that is empty in the final tree
+    absent: (u64, Vec<Fr>),
+    // roots before and after each transition
+    roots: Vec<Fr>,
+}
+
+impl Circuit<Fr> for SparseMerkleCircuit {
+    type Config = FlexGateConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        FlexGateConfig::configure(
+            meta,
+            GateStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            1,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "sparse merkle",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+                let chip = SparseMerkleChip::construct(&self.hasher, Fr::from(0));
+
+                let load = |ctx: &mut Context<'_, Fr>, key: u64, siblings: &[Fr]| {
+                    let mut cells: Vec<_> = (0..siblings.len())
+                        .map(|i| Witness(Value::known(Fr::from((key >> i) & 1))))
+                        .collect();
+                    cells.extend(siblings.iter().map(|x| Witness(Value::known(*x))));
+                    let mut key_bits =
+                        config.assign_region_smart(ctx, cells, vec![], vec![], vec![])?;
+                    let siblings = key_bits.split_off(siblings.len());
+                    Ok::<_, Error>(SparseMerkleProof { key_bits, siblings })
+                };
+
+                let roots = config.assign_region_smart(
+                    ctx,
+                    self.roots.iter().map(|x| Witness(Value::known(*x))).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                for (i, (key, old_value, new_value, siblings)) in self.updates.iter().enumerate() {
+                    let proof = load(ctx, *key, siblings)?;
+                    let values = config.assign_region_smart(
+                        ctx,
+                        vec![Witness(Value::known(*old_value)), Witness(Value::known(*new_value))],
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?;
+                    let new_root = if *old_value == Fr::from(0) {
+                        chip.verify_insert(&config, ctx, &proof, &values[1], &roots[i])?
+                    } else {
+                        chip.verify_update(&config, ctx, &proof, &values[0], &values[1], &roots[i])?
+                    };
+                    ctx.region.constrain_equal(new_root.cell(), roots[i + 1].cell())?;
+                }
+                let proof = load(ctx, self.absent.0, &self.absent.1)?;
+                chip.verify_non_membership(&config, ctx, &proof, roots.last().unwrap())?;
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_sparse_merkle_transitions() {
+    let hasher = PoseidonSpec::<Fr, 3, 2>::new(8, 57);
+    let depth = 3;
+    let mut tree = SparseMerkleTree::new(&hasher, Fr::from(0), depth);
+    assert_eq!(tree.root(), default_nodes(&hasher, Fr::from(0), depth)[depth]);
+    tree.update(&hasher, 2, Fr::from(7));
+
+    let mut roots = vec![tree.root()];
+    let mut updates = vec![];
+    for (key, value) in [(5, Fr::from(11)), (2, Fr::from(8))] {
+        updates.push((key, tree.get(key), value, tree.proof(key)));
+        tree.update(&hasher, key, value);
+        roots.push(tree.root());
+    }
+    let circuit = SparseMerkleCircuit { hasher, updates, absent: (6, tree.proof(6)), roots };
+
+    let prover = MockProver::run(16, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_sparse_merkle_update_to_default() {
+    // setting a leaf back to the empty leaf restores the empty root
+    let hasher = PoseidonSpec::<Fr, 3, 2>::new(8, 57);
+    let mut tree = SparseMerkleTree::new(&hasher, Fr::from(0), 4);
+    let empty_root = tree.root();
+    tree.update(&hasher, 9, Fr::from(1));
+    assert_ne!(tree.root(), empty_root);
+    tree.update(&hasher, 9, Fr::from(0));
+    assert_eq!(tree.root(), empty_root);
+}
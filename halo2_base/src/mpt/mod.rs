@@ -0,0 +1,614 @@
+// Ethereum Merkle-Patricia trie proofs, as returned by `eth_getProof`
+//
+// `verify_proof` decodes the RLP encoded trie nodes and walks the nibble path of a key through a proof, returning
+// the proven value or `None` for an exclusion proof.
+//
+// `verify_proof_assigned` verifies a proof of inclusion in circuit. Rather than decoding nodes of variable length
+// and type, the prover witnesses the fields of each node (`ProofWitness`), and each node is re-encoded from them with
+// the in-circuit RLP encoders, hashed with `keccak256_var_len` and checked against the reference in its parent, or
+// the root. Each of the `max_depth` slots of the path is encoded as a branch, an extension and a leaf, and a
+// witnessed type selects one: a branch descends to the child at the next nibble of the key, picked with
+// `select_from_idx_bits`, and an extension to its child. The paths of extensions and leaves are encoded from the key
+// itself, so a node only hashes to its reference if its path matches the key.
+//
+// Nodes shorter than 32 bytes are embedded in their parent instead of referenced by hash, which the circuit does not
+// support. They do not occur in the state trie, whose leaves hold accounts, and in storage tries they only occur
+// for leaves deep below the root.
+
+use self::rlp::{
+    concat_assigned, encode_bytes_assigned, encode_list_assigned, encode_uint_assigned,
+    encode_var_bytes_assigned, AssignedVarBytes, RlpItem,
+};
+use crate::{
+    gates::{
+        bytes::{mask_bytes, shift_bytes},
+        GateInstructions, RangeInstructions,
+    },
+    hashes::keccak,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use sha3::{Digest, Keccak256};
+
+pub mod rlp;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MptError {
+    InvalidRlp,
+    UnexpectedList,
+    UnexpectedBytes,
+    // a node has neither 2 nor 17 items
+    InvalidNode,
+    // the hash of a proof node does not match the reference in its parent (or the root)
+    HashMismatch { depth: usize },
+    // the proof ends before the path reaches a leaf or an empty child
+    IncompleteProof,
+    // the proof has nodes after the path ended
+    UnusedProofNodes,
+    // the proof shows that the key is not in the trie, where a proof of inclusion was expected
+    NotIncluded,
+    // a node on the path embeds its child instead of referencing it by hash
+    EmbeddedNode,
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&Keccak256::digest(data));
+    out
+}
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0xf]).collect()
+}
+
+// decodes the hex prefix encoding of the path of a leaf or extension node, returning `(is_leaf, nibbles)`
+fn decode_compact(path: &[u8]) -> Result<(bool, Vec<u8>), MptError> {
+    let nibbles = to_nibbles(path);
+    let flag = *nibbles.first().ok_or(MptError::InvalidNode)?;
+    if flag > 3 {
+        return Err(MptError::InvalidNode);
+    }
+    let is_leaf = flag >= 2;
+    // an even path has a zero padding nibble after the flag
+    let skip = if flag % 2 == 1 { 1 } else { 2 };
+    Ok((is_leaf, nibbles[skip..].to_vec()))
+}
+
+/// Verifies that `proof` is a path of the trie with root hash `root` for `key`
+///
+/// Returns the value stored at `key`, or `None` if the proof shows that `key` is not in the trie.
+/// `key` is the raw trie key, i.e. `keccak256(address)` for the state trie and `keccak256(slot)` for storage tries.
+pub fn verify_proof(
+    root: &[u8; 32],
+    key: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, MptError> {
+    let nibbles = to_nibbles(key);
+    let mut path = &nibbles[..];
+    let mut proof_nodes = proof.iter().enumerate();
+
+    // the next node is either referenced by hash, and is the next proof node, or embedded in its parent
+    let (depth, first) = proof_nodes.next().ok_or(MptError::IncompleteProof)?;
+    if &keccak256(first) != root {
+        return Err(MptError::HashMismatch { depth });
+    }
+    let mut node = rlp::decode(first)?;
+
+    let value = loop {
+        let items = node.as_list()?;
+        let child = match items.len() {
+            17 => match path.split_first() {
+                None => break items[16].as_bytes()?,
+                Some((nibble, rest)) => {
+                    path = rest;
+                    items[*nibble as usize].clone()
+                }
+            },
+            2 => {
+                let (is_leaf, node_path) = decode_compact(items[0].as_bytes()?)?;
+                if is_leaf {
+                    if path != node_path.as_slice() {
+                        break &[][..];
+                    }
+                    break items[1].as_bytes()?;
+                }
+                if !path.starts_with(&node_path) {
+                    break &[][..];
+                }
+                path = &path[node_path.len()..];
+                items[1].clone()
+            }
+            _ => return Err(MptError::InvalidNode),
+        };
+        match child {
+            RlpItem::Bytes(b) if b.is_empty() => break &[][..],
+            RlpItem::Bytes(hash) => {
+                let (depth, next) = proof_nodes.next().ok_or(MptError::IncompleteProof)?;
+                if keccak256(next)[..] != *hash {
+                    return Err(MptError::HashMismatch { depth });
+                }
+                node = rlp::decode(next)?;
+            }
+            RlpItem::List(_) => node = child,
+        }
+    };
+
+    if proof_nodes.next().is_some() {
+        return Err(MptError::UnusedProofNodes);
+    }
+    Ok(if value.is_empty() { None } else { Some(value.to_vec()) })
+}
+
+/// An account of the state trie, with `nonce` and `balance` as big-endian bytes without leading zeros
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: Vec<u8>,
+    pub balance: Vec<u8>,
+    pub storage_root: [u8; 32],
+    pub code_hash: [u8; 32],
+}
+
+fn to_hash(bytes: &[u8]) -> Result<[u8; 32], MptError> {
+    bytes.try_into().map_err(|_| MptError::InvalidRlp)
+}
+
+/// Verifies an account proof against the state root and returns the account, or `None` if it does not exist
+pub fn verify_account_proof(
+    state_root: &[u8; 32],
+    address: &[u8; 20],
+    proof: &[Vec<u8>],
+) -> Result<Option<Account>, MptError> {
+    let value = match verify_proof(state_root, &keccak256(address), proof)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let account = rlp::decode(&value)?;
+    let fields = account.as_list()?;
+    if fields.len() != 4 {
+        return Err(MptError::InvalidRlp);
+    }
+    Ok(Some(Account {
+        nonce: fields[0].as_bytes()?.to_vec(),
+        balance: fields[1].as_bytes()?.to_vec(),
+        storage_root: to_hash(fields[2].as_bytes()?)?,
+        code_hash: to_hash(fields[3].as_bytes()?)?,
+    }))
+}
+
+/// Verifies a storage proof against the storage root of an account and returns the big-endian value of the slot,
+/// which is empty if the slot is not set
+pub fn verify_storage_proof(
+    storage_root: &[u8; 32],
+    slot: &[u8; 32],
+    proof: &[Vec<u8>],
+) -> Result<Vec<u8>, MptError> {
+    match verify_proof(storage_root, &keccak256(slot), proof)? {
+        Some(value) => Ok(rlp::decode(&value)?.as_bytes()?.to_vec()),
+        None => Ok(vec![]),
+    }
+}
+
+/// A node above the leaf of a proof of inclusion, decoded into the fields `verify_proof_assigned` encodes it from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeWitness {
+    /// the hashes of the 16 children, `None` for empty children
+    Branch(Vec<Option<[u8; 32]>>),
+    /// the number of nibbles of the path and the hash of the child
+    Extension { path_len: usize, child: [u8; 32] },
+}
+
+/// A proof of inclusion decoded for `AssignedProof::load`: the nodes above the leaf and the value of the leaf
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofWitness {
+    pub nodes: Vec<NodeWitness>,
+    pub value: Vec<u8>,
+}
+
+fn to_child_hash(item: &RlpItem<'_>) -> Result<[u8; 32], MptError> {
+    match item {
+        RlpItem::Bytes(hash) => to_hash(hash),
+        RlpItem::List(_) => Err(MptError::EmbeddedNode),
+    }
+}
+
+impl ProofWitness {
+    /// Decodes `proof`, which must be a proof of inclusion of `key` in the trie with root hash `root` whose nodes
+    /// all reference their children by hash
+    pub fn new(root: &[u8; 32], key: &[u8], proof: &[Vec<u8>]) -> Result<Self, MptError> {
+        if verify_proof(root, key, proof)?.is_none() {
+            return Err(MptError::NotIncluded);
+        }
+        let (leaf, path) = proof.split_last().ok_or(MptError::IncompleteProof)?;
+        let mut nodes = Vec::with_capacity(path.len());
+        for node in path {
+            let node = rlp::decode(node)?;
+            let items = node.as_list()?;
+            nodes.push(if items.len() == 17 {
+                // keys have the same length, so branches on the path of a key have no value
+                if !items[16].as_bytes()?.is_empty() {
+                    return Err(MptError::InvalidNode);
+                }
+                let children = items[..16]
+                    .iter()
+                    .map(|item| match item {
+                        RlpItem::Bytes(b) if b.is_empty() => Ok(None),
+                        item => to_child_hash(item).map(Some),
+                    })
+                    .collect::<Result<_, _>>()?;
+                NodeWitness::Branch(children)
+            } else {
+                let (_, path) = decode_compact(items[0].as_bytes()?)?;
+                NodeWitness::Extension { path_len: path.len(), child: to_child_hash(&items[1])? }
+            });
+        }
+        // the last proof node is not the leaf if the leaf is embedded in it
+        let leaf = rlp::decode(leaf)?;
+        let items = leaf.as_list()?;
+        if items.len() != 2 || !decode_compact(items[0].as_bytes()?)?.0 {
+            return Err(MptError::EmbeddedNode);
+        }
+        Ok(Self { nodes, value: items[1].as_bytes()?.to_vec() })
+    }
+}
+
+/// The fields of a node above the leaf, with all fields zero for the types the node does not have
+#[derive(Clone, Debug)]
+pub struct AssignedNode<F: FieldExt> {
+    pub is_branch: AssignedValue<F>,
+    pub child_is_empty: Vec<AssignedValue<F>>,
+    pub children: Vec<Vec<AssignedValue<F>>>,
+    pub path_len: AssignedValue<F>,
+    pub child: Vec<AssignedValue<F>>,
+}
+
+/// A proof of inclusion with `depth` nodes, including the leaf, of which the first `depth - 1` of `nodes` are
+/// the nodes above the leaf
+#[derive(Clone, Debug)]
+pub struct AssignedProof<F: FieldExt> {
+    pub depth: AssignedValue<F>,
+    pub nodes: Vec<AssignedNode<F>>,
+    pub value: AssignedVarBytes<F>,
+}
+
+impl<F: FieldExt> AssignedProof<F> {
+    /// Assigns `witness` for paths of at most `max_depth` nodes and values of at most `max_value_len` bytes
+    ///
+    /// Nothing is constrained here, `verify_proof_assigned` constrains the proof.
+    pub fn load(
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        witness: Value<&ProofWitness>,
+        max_depth: usize,
+        max_value_len: usize,
+    ) -> Result<Self, Error> {
+        assert!(max_depth > 0 && max_value_len > 0);
+        witness.assert_if_known(|w| w.nodes.len() < max_depth && w.value.len() <= max_value_len);
+        let byte = |b: u8| F::from(b as u64);
+
+        let mut cells = vec![Witness(witness.map(|w| F::from(w.nodes.len() as u64 + 1)))];
+        for i in 0..max_depth - 1 {
+            let node = witness.map(|w| w.nodes.get(i));
+            let is_branch = node.map(|n| matches!(n, Some(NodeWitness::Branch(_))));
+            cells.push(Witness(is_branch.map(|b| F::from(b as u64))));
+            for c in 0..16 {
+                let child = node.map(|n| match n {
+                    Some(NodeWitness::Branch(children)) => children[c],
+                    _ => None,
+                });
+                cells.push(Witness(child.map(|h| F::from(h.is_none() as u64))));
+                cells.extend((0..32).map(|j| Witness(child.map(|h| byte(h.map_or(0, |h| h[j]))))));
+            }
+            let extension = node.map(|n| match n {
+                Some(NodeWitness::Extension { path_len, child }) => (*path_len, *child),
+                _ => (0, [0; 32]),
+            });
+            cells.push(Witness(extension.map(|(len, _)| F::from(len as u64))));
+            cells.extend((0..32).map(|j| Witness(extension.map(|(_, h)| byte(h[j])))));
+        }
+        cells.push(Witness(witness.map(|w| F::from(w.value.len() as u64))));
+        cells.extend(
+            (0..max_value_len)
+                .map(|j| Witness(witness.map(|w| byte(w.value.get(j).copied().unwrap_or(0))))),
+        );
+
+        let mut cells = gate.assign_region_smart(ctx, cells, vec![], vec![], vec![])?.into_iter();
+        let mut next = || cells.next().unwrap();
+        let depth = next();
+        let mut nodes = Vec::with_capacity(max_depth - 1);
+        for _ in 0..max_depth - 1 {
+            let is_branch = next();
+            let mut child_is_empty = Vec::with_capacity(16);
+            let mut children = Vec::with_capacity(16);
+            for _ in 0..16 {
+                child_is_empty.push(next());
+                children.push((0..32).map(|_| next()).collect());
+            }
+            let path_len = next();
+            let child = (0..32).map(|_| next()).collect();
+            nodes.push(AssignedNode { is_branch, child_is_empty, children, path_len, child });
+        }
+        let len = next();
+        let bytes = (0..max_value_len).map(|_| next()).collect();
+        Ok(Self { depth, nodes, value: AssignedVarBytes { bytes, len } })
+    }
+}
+
+// the hex prefix encoding of the path `nibbles[..len]` of a leaf or extension node, for `len < 2^7` and
+// `nibbles[len..]` zero: the flag nibble `2 * is_leaf + len % 2`, a zero nibble if `len` is even, then the path
+fn compact_path_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    nibbles: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+    is_leaf: bool,
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert!(nibbles.len() <= 64);
+    let gate = range.gate();
+    let bits = range.num_to_bits(ctx, len, 7)?;
+    let is_odd = &bits[0];
+    let zero = gate.load_zero(ctx)?;
+    let nibble = |i: usize| Existing(nibbles.get(i).unwrap_or(&zero));
+
+    // for an odd path the first byte also holds the first nibble, and each byte is shifted by one nibble
+    let flag = gate.mul_add(
+        ctx,
+        &Existing(is_odd),
+        &Constant(F::from(16)),
+        &Constant(F::from(if is_leaf { 0x20 } else { 0 })),
+    )?;
+    let mut bytes = vec![gate.mul_add(ctx, &Existing(is_odd), &nibble(0), &Existing(&flag))?];
+    for j in 1..33 {
+        let odd = gate.mul_add(ctx, &nibble(2 * j - 1), &Constant(F::from(16)), &nibble(2 * j))?;
+        let even =
+            gate.mul_add(ctx, &nibble(2 * j - 2), &Constant(F::from(16)), &nibble(2 * j - 1))?;
+        bytes.push(gate.select(ctx, &Existing(&odd), &Existing(&even), &Existing(is_odd))?);
+    }
+    // len / 2 + 1 bytes
+    let half = gate.inner_product(
+        ctx,
+        &bits[1..].iter().map(|x| Existing(x)).collect(),
+        &(0..6).map(|i| Constant(F::from(1 << i))).collect(),
+    )?;
+    let len = gate.add(ctx, &Existing(&half), &Constant(F::one()))?;
+    Ok(AssignedVarBytes { bytes, len })
+}
+
+// the candidate whose flag is set, assuming at most one is, or the empty string if none is
+fn select_node<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    candidates: &[(&AssignedValue<F>, AssignedVarBytes<F>)],
+) -> Result<AssignedVarBytes<F>, Error> {
+    let max_len = candidates.iter().map(|(_, node)| node.bytes.len()).max().unwrap();
+    let flags = candidates.iter().map(|(flag, _)| Existing(flag)).collect();
+    let mut bytes = Vec::with_capacity(max_len);
+    for j in 0..max_len {
+        let column = candidates
+            .iter()
+            .map(|(_, node)| node.bytes.get(j).map_or(Constant(F::zero()), Existing))
+            .collect();
+        bytes.push(gate.inner_product(ctx, &column, &flags)?);
+    }
+    let lens = candidates.iter().map(|(_, node)| Existing(&node.len)).collect();
+    let len = gate.inner_product(ctx, &lens, &flags)?;
+    Ok(AssignedVarBytes { bytes, len })
+}
+
+/// Verifies in circuit that `proof` proves the inclusion of the 32 byte `key` in the trie with root hash `root`
+/// and returns the value stored at `key`
+///
+/// Each of the `proof.nodes.len() + 1` slots costs the keccak of a branch, which is 4 blocks, so the depth should be
+/// kept close to that of the trie.
+pub fn verify_proof_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    root: &[AssignedValue<F>],
+    key: &[AssignedValue<F>],
+    proof: &AssignedProof<F>,
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert_eq!(root.len(), 32);
+    assert_eq!(key.len(), 32);
+    let gate = range.gate();
+    let max_depth = proof.nodes.len() + 1;
+
+    // the nibbles of the key, most significant first
+    let mut nibbles = Vec::with_capacity(64);
+    for byte in key {
+        let bits = range.num_to_bits(ctx, byte, 8)?;
+        nibbles.push(gate.bits_to_num(ctx, &bits[4..], false)?);
+        nibbles.push(gate.bits_to_num(ctx, &bits[..4], false)?);
+    }
+
+    // slot i holds the leaf iff i == depth - 1, and a node iff i < depth
+    let last = gate.sub(ctx, &Existing(&proof.depth), &Constant(F::one()))?;
+    let is_leaf = range.idx_to_indicator_by_bits(ctx, &last, max_depth)?;
+    let mut is_active = vec![is_leaf[max_depth - 1].clone()];
+    for flag in is_leaf[..max_depth - 1].iter().rev() {
+        let active = gate.add(ctx, &Existing(flag), &Existing(is_active.last().unwrap()))?;
+        is_active.push(active);
+    }
+    is_active.reverse();
+
+    let max_value_len = proof.value.bytes.len();
+    let len_bits = (usize::BITS - (max_value_len + 1).leading_zeros()) as usize;
+    range.check_less_than_safe(ctx, &proof.value.len, max_value_len + 1, len_bits)?;
+    let value = AssignedVarBytes {
+        bytes: mask_bytes(range, ctx, &proof.value.bytes, &proof.value.len, len_bits)?,
+        len: proof.value.len.clone(),
+    };
+    let value_item = encode_var_bytes_assigned(range, ctx, &value)?;
+
+    let mut reference = root.to_vec();
+    // the number of nibbles of the key consumed by the nodes above the current slot
+    let mut pos = gate.load_zero(ctx)?;
+    for i in 0..max_depth {
+        // the rest of the key, followed by zeros
+        let rest = shift_bytes(range, ctx, &nibbles, &pos, 7, true)?;
+
+        let leaf_len = gate.sub(ctx, &Constant(F::from(64)), &Existing(&pos))?;
+        let leaf_path = compact_path_assigned(range, ctx, &rest, &leaf_len, true)?;
+        let leaf_path = encode_var_bytes_assigned(range, ctx, &leaf_path)?;
+        let payload = concat_assigned(range, ctx, &[leaf_path, value_item.clone()])?;
+        let leaf = encode_list_assigned(range, ctx, &payload)?;
+
+        let (node, next) = match proof.nodes.get(i) {
+            // the last slot can only hold the leaf
+            None => (select_node(gate, ctx, &[(&is_leaf[i], leaf)])?, None),
+            Some(node) => {
+                let (node, child, step) =
+                    inner_node_assigned(range, ctx, node, &rest, &is_leaf[i], &is_active[i], leaf)?;
+                (node, Some((child, step)))
+            }
+        };
+        let hash = keccak::keccak256_var_len(range, ctx, &node.bytes, &node.len)?;
+        // the slots after the leaf are not checked
+        for (h, r) in hash.iter().zip(reference.iter()) {
+            gate.assert_equal_if(ctx, &Existing(&is_active[i]), &Existing(h), &Existing(r))?;
+        }
+        if let Some((child, step)) = next {
+            reference = child;
+            pos = gate.add(ctx, &Existing(&pos), &Existing(&step))?;
+        }
+    }
+    Ok(value)
+}
+
+/// The fields of an account, see `Account`, as big-endian bytes: 8 bytes of nonce, 32 of balance and the two hashes
+#[derive(Clone, Debug)]
+pub struct AssignedAccount<F: FieldExt> {
+    pub nonce: Vec<AssignedValue<F>>,
+    pub balance: Vec<AssignedValue<F>>,
+    pub storage_root: Vec<AssignedValue<F>>,
+    pub code_hash: Vec<AssignedValue<F>>,
+}
+
+/// Verifies in circuit that `proof` proves `account` to be the account at the 20 byte `address` in the state trie
+/// with root hash `state_root`
+///
+/// The fields of `account` are constrained to be bytes by the hash of the leaf, which holds their encoding.
+pub fn verify_account_proof_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    state_root: &[AssignedValue<F>],
+    address: &[AssignedValue<F>],
+    account: &AssignedAccount<F>,
+    proof: &AssignedProof<F>,
+) -> Result<(), Error> {
+    assert_eq!(address.len(), 20);
+    let gate = range.gate();
+    let key = keccak::keccak256(range, ctx, address)?;
+    let value = verify_proof_assigned(range, ctx, state_root, &key, proof)?;
+    let fields = [
+        encode_uint_assigned(range, ctx, &account.nonce)?,
+        encode_uint_assigned(range, ctx, &account.balance)?,
+        encode_bytes_assigned(gate, ctx, &account.storage_root)?,
+        encode_bytes_assigned(gate, ctx, &account.code_hash)?,
+    ];
+    let payload = concat_assigned(range, ctx, &fields)?;
+    let encoded = encode_list_assigned(range, ctx, &payload)?;
+    value.assert_equal(gate, ctx, &encoded)
+}
+
+/// Verifies in circuit that `proof` proves the big-endian `value` to be stored at the 32 byte `slot` of the storage
+/// trie with root hash `storage_root`
+///
+/// `value` must be nonzero: unset slots have no leaf, and their proofs of exclusion are not supported.
+pub fn verify_storage_proof_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    storage_root: &[AssignedValue<F>],
+    slot: &[AssignedValue<F>],
+    value: &[AssignedValue<F>],
+    proof: &AssignedProof<F>,
+) -> Result<(), Error> {
+    assert_eq!(slot.len(), 32);
+    let key = keccak::keccak256(range, ctx, slot)?;
+    let leaf_value = verify_proof_assigned(range, ctx, storage_root, &key, proof)?;
+    let encoded = encode_uint_assigned(range, ctx, value)?;
+    leaf_value.assert_equal(range.gate(), ctx, &encoded)
+}
+
+// the node in a slot above the last one, selected from its encodings as a branch, an extension and the leaf `leaf`,
+// with the reference to its child and the number of nibbles of the key it consumes
+fn inner_node_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    node: &AssignedNode<F>,
+    rest: &[AssignedValue<F>],
+    is_leaf: &AssignedValue<F>,
+    is_active: &AssignedValue<F>,
+    leaf: AssignedVarBytes<F>,
+) -> Result<(AssignedVarBytes<F>, Vec<AssignedValue<F>>, AssignedValue<F>), Error> {
+    let gate = range.gate();
+    // the types are exclusive and only set for active slots iff `is_ext` is a bit
+    gate.assert_bit(ctx, &node.is_branch)?;
+    let leaf_or_branch = gate.add(ctx, &Existing(is_leaf), &Existing(&node.is_branch))?;
+    let is_ext = gate.sub(ctx, &Existing(is_active), &Existing(&leaf_or_branch))?;
+    gate.assert_bit(ctx, &is_ext)?;
+
+    // an empty child is encoded as the empty string 0x80, otherwise as 0xa0 || hash
+    let mut items = Vec::with_capacity(17);
+    for (is_empty, hash) in node.child_is_empty.iter().zip(node.children.iter()) {
+        gate.assert_bit(ctx, is_empty)?;
+        let not_empty = gate.not(ctx, &Existing(is_empty))?;
+        let header = gate.mul_add(
+            ctx,
+            &Existing(&not_empty),
+            &Constant(F::from(0x20)),
+            &Constant(F::from(0x80)),
+        )?;
+        let len =
+            gate.mul_add(ctx, &Existing(&not_empty), &Constant(F::from(32)), &Constant(F::one()))?;
+        let mut bytes = vec![header];
+        for byte in hash {
+            bytes.push(gate.mul(ctx, &Existing(byte), &Existing(&not_empty))?);
+        }
+        items.push(AssignedVarBytes { bytes, len });
+    }
+    items.push(AssignedVarBytes::constant(gate, ctx, &[0x80])?);
+    let payload = concat_assigned(range, ctx, &items)?;
+    let branch = encode_list_assigned(range, ctx, &payload)?;
+
+    // a branch descends to its child at the next nibble, which must not be empty
+    let nibble_bits = range.num_to_bits(ctx, &rest[0], 4)?;
+    let nibble_bits = nibble_bits.iter().map(|x| Existing(x)).collect();
+    let is_empty = gate.select_from_idx_bits(
+        ctx,
+        &node.child_is_empty.iter().map(|x| Existing(x)).collect(),
+        &nibble_bits,
+    )?;
+    let descends_to_empty = gate.mul(ctx, &Existing(&is_empty), &Existing(&node.is_branch))?;
+    gate.assert_is_const(ctx, &descends_to_empty, F::zero());
+
+    let path = mask_bytes(range, ctx, &rest, &node.path_len, 7)?;
+    let path = compact_path_assigned(range, ctx, &path, &node.path_len, false)?;
+    let path = encode_var_bytes_assigned(range, ctx, &path)?;
+    let child = encode_bytes_assigned(gate, ctx, &node.child)?;
+    let payload = concat_assigned(range, ctx, &[path, child])?;
+    let extension = encode_list_assigned(range, ctx, &payload)?;
+
+    let mut next_reference = Vec::with_capacity(32);
+    for j in 0..32 {
+        let column = node.children.iter().map(|hash| Existing(&hash[j])).collect();
+        let branch_child = gate.select_from_idx_bits(ctx, &column, &nibble_bits)?;
+        next_reference.push(gate.select(
+            ctx,
+            &Existing(&branch_child),
+            &Existing(&node.child[j]),
+            &Existing(&node.is_branch),
+        )?);
+    }
+    let ext_step = gate.mul(ctx, &Existing(&is_ext), &Existing(&node.path_len))?;
+    let step = gate.add(ctx, &Existing(&node.is_branch), &Existing(&ext_step))?;
+
+    let node = select_node(
+        gate,
+        ctx,
+        &[(&node.is_branch, branch), (&is_ext, extension), (is_leaf, leaf)],
+    )?;
+    Ok((node, next_reference, step))
+}
@@ -0,0 +1,390 @@
+use super::MptError;
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+/// A decoded RLP item, borrowing the bytes of the encoding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RlpItem<'a> {
+    Bytes(&'a [u8]),
+    List(Vec<RlpItem<'a>>),
+}
+
+impl<'a> RlpItem<'a> {
+    pub fn as_bytes(&self) -> Result<&'a [u8], MptError> {
+        match self {
+            RlpItem::Bytes(bytes) => Ok(bytes),
+            RlpItem::List(_) => Err(MptError::UnexpectedList),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[RlpItem<'a>], MptError> {
+        match self {
+            RlpItem::List(items) => Ok(items),
+            RlpItem::Bytes(_) => Err(MptError::UnexpectedBytes),
+        }
+    }
+}
+
+// returns `(is_list, header length, payload length)` of the item starting at `data[0]`
+fn header(data: &[u8]) -> Result<(bool, usize, usize), MptError> {
+    let prefix = *data.first().ok_or(MptError::InvalidRlp)?;
+    let long_len = |len_of_len: usize| -> Result<usize, MptError> {
+        if len_of_len > 8 || data.len() < 1 + len_of_len {
+            return Err(MptError::InvalidRlp);
+        }
+        Ok(data[1..1 + len_of_len].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize))
+    };
+    match prefix {
+        0x00..=0x7f => Ok((false, 0, 1)),
+        0x80..=0xb7 => Ok((false, 1, (prefix - 0x80) as usize)),
+        0xb8..=0xbf => {
+            let len_of_len = (prefix - 0xb7) as usize;
+            Ok((false, 1 + len_of_len, long_len(len_of_len)?))
+        }
+        0xc0..=0xf7 => Ok((true, 1, (prefix - 0xc0) as usize)),
+        0xf8..=0xff => {
+            let len_of_len = (prefix - 0xf7) as usize;
+            Ok((true, 1 + len_of_len, long_len(len_of_len)?))
+        }
+    }
+}
+
+// decodes the item at the start of `data` and returns it with the length of its encoding
+fn decode_prefix(data: &[u8]) -> Result<(RlpItem<'_>, usize), MptError> {
+    let (is_list, header_len, payload_len) = header(data)?;
+    let end = header_len.checked_add(payload_len).ok_or(MptError::InvalidRlp)?;
+    if data.len() < end {
+        return Err(MptError::InvalidRlp);
+    }
+    let payload = &data[header_len..end];
+    if !is_list {
+        // single bytes below 0x80 are their own encoding
+        let bytes = if header_len == 0 { &data[..1] } else { payload };
+        return Ok((RlpItem::Bytes(bytes), end));
+    }
+    let mut items = vec![];
+    let mut offset = 0;
+    while offset < payload.len() {
+        let (item, len) = decode_prefix(&payload[offset..])?;
+        items.push(item);
+        offset += len;
+    }
+    Ok((RlpItem::List(items), end))
+}
+
+/// Decodes `data`, which must be exactly one RLP item
+pub fn decode(data: &[u8]) -> Result<RlpItem<'_>, MptError> {
+    let (item, len) = decode_prefix(data)?;
+    if len != data.len() {
+        return Err(MptError::InvalidRlp);
+    }
+    Ok(item)
+}
+
+fn encode_length(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len_bytes: Vec<u8> = len.to_be_bytes().iter().copied().skip_while(|b| *b == 0).collect();
+    let mut out = vec![offset + 55 + len_bytes.len() as u8];
+    out.extend(len_bytes);
+    out
+}
+
+/// Encodes a byte string
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = encode_length(bytes.len(), 0x80);
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Encodes an integer given by its big-endian bytes, which is the byte string without leading zeros
+pub fn encode_uint(be_bytes: &[u8]) -> Vec<u8> {
+    let stripped: Vec<u8> = be_bytes.iter().copied().skip_while(|b| *b == 0).collect();
+    encode_bytes(&stripped)
+}
+
+/// Encodes a list of already encoded `items`
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut out = encode_length(payload.len(), 0xc0);
+    out.extend(payload);
+    out
+}
+
+/// A byte string of variable length `len`, stored in `bytes` followed by zeros
+///
+/// The in-circuit encoders below only produce byte strings where `bytes[len..]` are constrained to be zero,
+/// which `concat_assigned` relies on.
+#[derive(Clone, Debug)]
+pub struct AssignedVarBytes<F: FieldExt> {
+    pub bytes: Vec<AssignedValue<F>>,
+    pub len: AssignedValue<F>,
+}
+
+impl<F: FieldExt> AssignedVarBytes<F> {
+    /// Byte string with the constant length `bytes.len()`
+    pub fn fixed(
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        bytes: Vec<AssignedValue<F>>,
+    ) -> Result<Self, Error> {
+        let len = gate.assign_region_smart(
+            ctx,
+            vec![Constant(F::from(bytes.len() as u64))],
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(Self { bytes, len: len[0].clone() })
+    }
+
+    /// Byte string with constant bytes
+    pub fn constant(
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        bytes: &[u8],
+    ) -> Result<Self, Error> {
+        let bytes = gate.assign_region_smart(
+            ctx,
+            bytes.iter().map(|b| Constant(F::from(*b as u64))).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Self::fixed(gate, ctx, bytes)
+    }
+
+    /// Constrains `self` and `other` to be the same byte string, relying on both being followed by zeros
+    pub fn assert_equal(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        other: &Self,
+    ) -> Result<(), Error> {
+        gate.assert_equal(ctx, &Existing(&self.len), &Existing(&other.len))?;
+        for i in 0..self.bytes.len().max(other.bytes.len()) {
+            let a = self.bytes.get(i).map_or(Constant(F::zero()), Existing);
+            let b = other.bytes.get(i).map_or(Constant(F::zero()), Existing);
+            gate.assert_equal(ctx, &a, &b)?;
+        }
+        Ok(())
+    }
+}
+
+/// In circuit `encode_uint` of an integer given by its big-endian bytes, which are assumed to be in [0, 2^8)
+///
+/// The output has `be_bytes.len() + 1` bytes and length in [1, be_bytes.len() + 1].
+pub fn encode_uint_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    be_bytes: &[AssignedValue<F>],
+) -> Result<AssignedVarBytes<F>, Error> {
+    let w = be_bytes.len();
+    assert!(w > 0 && w < 56);
+    let gate = range.gate();
+
+    // leading[i] = 1 iff be_bytes[..=i] are all zero
+    let mut leading: Vec<AssignedValue<F>> = Vec::with_capacity(w);
+    for (i, byte) in be_bytes.iter().enumerate() {
+        let is_zero = range.is_zero(ctx, byte)?;
+        let next = if i == 0 {
+            is_zero
+        } else {
+            gate.and(ctx, &Existing(&leading[i - 1]), &Existing(&is_zero))?
+        };
+        leading.push(next);
+    }
+    let num_zeros = gate.inner_product(
+        ctx,
+        &leading.iter().map(|x| Existing(x)).collect(),
+        &vec![Constant(F::one()); w],
+    )?;
+    let len = gate.sub(ctx, &Constant(F::from(w as u64)), &Existing(&num_zeros))?;
+
+    // stripped[j] = be_bytes[j + num_zeros], followed by zeros
+    let shift = range.idx_to_indicator_by_bits(ctx, &num_zeros, w + 1)?;
+    let mut stripped = Vec::with_capacity(w);
+    for j in 0..w {
+        let byte = gate.inner_product(
+            ctx,
+            &be_bytes[j..].iter().map(|x| Existing(x)).collect(),
+            &shift[..w - j].iter().map(|x| Existing(x)).collect(),
+        )?;
+        stripped.push(byte);
+    }
+
+    // a single byte below 0x80 is its own encoding, otherwise the header is 0x80 + len
+    let len_is_one = range.is_equal(ctx, &Existing(&len), &Constant(F::one()))?;
+    let is_small = range.is_less_than(ctx, &Existing(&stripped[0]), &Constant(F::from(0x80)), 8)?;
+    let is_single = gate.and(ctx, &Existing(&len_is_one), &Existing(&is_small))?;
+    let not_single = gate.not(ctx, &Existing(&is_single))?;
+
+    let header = gate.add(ctx, &Existing(&len), &Constant(F::from(0x80)))?;
+    let mut bytes = vec![gate.select(
+        ctx,
+        &Existing(&stripped[0]),
+        &Existing(&header),
+        &Existing(&is_single),
+    )?];
+    for byte in stripped.iter() {
+        bytes.push(gate.mul(ctx, &Existing(byte), &Existing(&not_single))?);
+    }
+    let len_plus_one = gate.add(ctx, &Existing(&len), &Constant(F::one()))?;
+    let len =
+        gate.select(ctx, &Constant(F::one()), &Existing(&len_plus_one), &Existing(&is_single))?;
+    Ok(AssignedVarBytes { bytes, len })
+}
+
+/// In circuit `encode_bytes` of a byte string of constant length other than 1, so the header is a constant
+pub fn encode_bytes_assigned<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert_ne!(bytes.len(), 1, "the encoding of a single byte depends on its value");
+    let mut out = gate.assign_region_smart(
+        ctx,
+        encode_length(bytes.len(), 0x80).into_iter().map(|b| Constant(F::from(b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    out.extend_from_slice(bytes);
+    AssignedVarBytes::fixed(gate, ctx, out)
+}
+
+/// In circuit `encode_bytes` of a byte string of variable length, for strings of at most `2^16 - 1` bytes which
+/// are assumed to be in [0, 2^8)
+pub fn encode_var_bytes_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    s: &AssignedVarBytes<F>,
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert!(!s.bytes.is_empty() && s.bytes.len() < 1 << 16);
+    let gate = range.gate();
+
+    // a single byte below 0x80 is its own encoding, so its header is empty
+    let len_is_one = range.is_equal(ctx, &Existing(&s.len), &Constant(F::one()))?;
+    let is_small = range.is_less_than(ctx, &Existing(&s.bytes[0]), &Constant(F::from(0x80)), 8)?;
+    let is_single = gate.and(ctx, &Existing(&len_is_one), &Existing(&is_small))?;
+    let not_single = gate.not(ctx, &Existing(&is_single))?;
+
+    let mut header = header_assigned(range, ctx, &s.len, 0x80)?;
+    header.bytes[0] = gate.mul(ctx, &Existing(&header.bytes[0]), &Existing(&not_single))?;
+    header.len = gate.mul(ctx, &Existing(&header.len), &Existing(&not_single))?;
+    concat_assigned(range, ctx, &[header, s.clone()])
+}
+
+/// Concatenates byte strings of variable lengths
+///
+/// Each item is shifted to its offset with an indicator, so this costs about `sum_i items[i].bytes.len()` times
+/// the maximum length of the output.
+pub fn concat_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    items: &[AssignedVarBytes<F>],
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert!(items.iter().all(|item| !item.bytes.is_empty()));
+    let gate = range.gate();
+    let max_len: usize = items.iter().map(|item| item.bytes.len()).sum();
+
+    // the offset of each item, which is at most the sum of the maximum lengths of the previous items
+    let mut shifts = Vec::with_capacity(items.len());
+    let mut offset: Option<AssignedValue<F>> = None;
+    let mut max_offset = 0;
+    for item in items.iter() {
+        let shift = match &offset {
+            None => None,
+            Some(offset) => Some(range.idx_to_indicator_by_lookup(ctx, offset, max_offset + 1)?),
+        };
+        shifts.push(shift);
+        offset = Some(match offset {
+            None => item.len.clone(),
+            Some(offset) => gate.add(ctx, &Existing(&offset), &Existing(&item.len))?,
+        });
+        max_offset += item.bytes.len();
+    }
+
+    // out[i] = sum_k sum_j items[k].bytes[j] * (offset_k + j == i)
+    let mut terms: Vec<Vec<QuantumCell<F>>> = vec![vec![]; max_len];
+    let mut coeffs: Vec<Vec<QuantumCell<F>>> = vec![vec![]; max_len];
+    for (item, shift) in items.iter().zip(shifts.iter()) {
+        for (j, byte) in item.bytes.iter().enumerate() {
+            match shift {
+                // the first item is at offset 0
+                None => {
+                    terms[j].push(Existing(byte));
+                    coeffs[j].push(Constant(F::one()));
+                }
+                Some(shift) => {
+                    for (o, ind) in shift.iter().enumerate() {
+                        terms[o + j].push(Existing(byte));
+                        coeffs[o + j].push(Existing(ind));
+                    }
+                }
+            }
+        }
+    }
+    let mut bytes = Vec::with_capacity(max_len);
+    for (terms, coeffs) in terms.iter().zip(coeffs.iter()) {
+        let byte = gate.inner_product(ctx, terms, coeffs)?;
+        bytes.push(byte);
+    }
+    let len = match offset {
+        Some(len) => len,
+        None => gate.load_zero(ctx)?,
+    };
+    Ok(AssignedVarBytes { bytes, len })
+}
+
+/// In circuit `encode_list` of the concatenation `payload` of encoded items, for payloads of at most
+/// `2^16 - 1` bytes
+pub fn encode_list_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    payload: &AssignedVarBytes<F>,
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert!(payload.bytes.len() < 1 << 16);
+    let header = header_assigned(range, ctx, &payload.len, 0xc0)?;
+    concat_assigned(range, ctx, &[header, payload.clone()])
+}
+
+// the header of a byte string (`offset = 0x80`) or list (`offset = 0xc0`) with a payload of `len < 2^16` bytes:
+// `offset + len` for len < 56, then `offset + 0x38 || len` for len < 256 and `offset + 0x39 || len` as 2 bytes
+fn header_assigned<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    len: &AssignedValue<F>,
+    offset: u64,
+) -> Result<AssignedVarBytes<F>, Error> {
+    let gate = range.gate();
+    let bits = range.num_to_bits(ctx, len, 16)?;
+    let lo = gate.bits_to_num(ctx, &bits[..8], false)?;
+    let hi = gate.bits_to_num(ctx, &bits[8..], false)?;
+    let is_short = range.is_less_than(ctx, &Existing(len), &Constant(F::from(56)), 16)?;
+    let is_one_byte = range.is_zero(ctx, &hi)?;
+
+    let short_header = gate.add(ctx, &Existing(len), &Constant(F::from(offset)))?;
+    let long_header = gate.sub(ctx, &Constant(F::from(offset + 0x39)), &Existing(&is_one_byte))?;
+    let byte0 =
+        gate.select(ctx, &Existing(&short_header), &Existing(&long_header), &Existing(&is_short))?;
+    let not_short = gate.not(ctx, &Existing(&is_short))?;
+    let len_byte = gate.select(ctx, &Existing(&lo), &Existing(&hi), &Existing(&is_one_byte))?;
+    let byte1 = gate.mul(ctx, &Existing(&len_byte), &Existing(&not_short))?;
+    let lo_if_two_bytes =
+        gate.select(ctx, &Constant(F::zero()), &Existing(&lo), &Existing(&is_one_byte))?;
+    let byte2 = gate.mul(ctx, &Existing(&lo_if_two_bytes), &Existing(&not_short))?;
+
+    let long_len = gate.sub(ctx, &Constant(F::from(3)), &Existing(&is_one_byte))?;
+    let header_len =
+        gate.select(ctx, &Constant(F::one()), &Existing(&long_len), &Existing(&is_short))?;
+    Ok(AssignedVarBytes { bytes: vec![byte0, byte1, byte2], len: header_len })
+}
@@ -0,0 +1,235 @@
+use super::{
+    keccak256,
+    rlp::{self, encode_bytes, encode_list, AssignedVarBytes, RlpItem},
+    to_nibbles, verify_proof, verify_proof_assigned, verify_storage_proof,
+    verify_storage_proof_assigned, AssignedProof, MptError, NodeWitness, ProofWitness,
+};
+use crate::{
+    gates::{circuit::RangeCircuitBuilder, range::RangeConfig, GateInstructions},
+    Context,
+    QuantumCell::{self, Witness},
+};
+use halo2_proofs::{circuit::Value, dev::MockProver, halo2curves::bn256::Fr, plonk::Error};
+
+// hex prefix encoding of a path
+fn compact(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = 2 * is_leaf as u8;
+    let mut padded = if nibbles.len() % 2 == 1 { vec![flag + 1] } else { vec![flag, 0] };
+    padded.extend_from_slice(nibbles);
+    padded.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+}
+
+fn leaf(nibbles: &[u8], value: &[u8]) -> Vec<u8> {
+    encode_list(&[encode_bytes(&compact(nibbles, true)), encode_bytes(value)])
+}
+
+#[test]
+fn test_rlp_decode() {
+    assert_eq!(rlp::decode(&[0x0f]).unwrap(), RlpItem::Bytes(&[0x0f]));
+    assert_eq!(rlp::decode(&[0x80]).unwrap(), RlpItem::Bytes(&[]));
+    assert_eq!(
+        rlp::decode(&encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")])).unwrap(),
+        RlpItem::List(vec![RlpItem::Bytes(b"cat"), RlpItem::Bytes(b"dog")])
+    );
+    let long = [7u8; 60];
+    assert_eq!(rlp::decode(&encode_bytes(&long)).unwrap(), RlpItem::Bytes(&long));
+    // truncated payload and trailing bytes
+    assert_eq!(rlp::decode(&[0x83, b'd', b'o']), Err(MptError::InvalidRlp));
+    assert_eq!(rlp::decode(&[0x0f, 0x0f]), Err(MptError::InvalidRlp));
+}
+
+#[test]
+fn test_single_leaf_trie() {
+    // a storage trie with one slot, keyed by the hash of the slot and storing the RLP of the value
+    let slot = [1u8; 32];
+    let key = keccak256(&slot);
+    let value = encode_bytes(&[0x05]);
+    let node = leaf(&to_nibbles(&key), &value);
+    let root = keccak256(&node);
+    let proof = vec![node];
+
+    assert_eq!(verify_proof(&root, &key, &proof), Ok(Some(value)));
+    assert_eq!(verify_storage_proof(&root, &slot, &proof), Ok(vec![0x05]));
+    assert_eq!(verify_storage_proof(&root, &[2u8; 32], &proof), Ok(vec![]));
+    assert_eq!(verify_proof(&[0u8; 32], &key, &proof), Err(MptError::HashMismatch { depth: 0 }));
+}
+
+#[test]
+fn test_branch_trie() {
+    // two keys that differ in their first nibble, below a branch node
+    let mut key_a = [0x11u8; 32];
+    let mut key_b = [0x11u8; 32];
+    key_a[0] = 0x12;
+    key_b[0] = 0x92;
+    let (nibbles_a, nibbles_b) = (to_nibbles(&key_a), to_nibbles(&key_b));
+    let leaf_a = leaf(&nibbles_a[1..], &encode_bytes(&[0x2a]));
+    let leaf_b = leaf(&nibbles_b[1..], &encode_bytes(&[0x01, 0x00]));
+
+    let mut children = vec![encode_bytes(&[]); 17];
+    children[nibbles_a[0] as usize] = encode_bytes(&keccak256(&leaf_a));
+    children[nibbles_b[0] as usize] = encode_bytes(&keccak256(&leaf_b));
+    let branch = encode_list(&children);
+    let root = keccak256(&branch);
+
+    assert_eq!(
+        verify_proof(&root, &key_a, &[branch.clone(), leaf_a.clone()]),
+        Ok(Some(encode_bytes(&[0x2a])))
+    );
+    assert_eq!(
+        verify_proof(&root, &key_b, &[branch.clone(), leaf_b.clone()]),
+        Ok(Some(encode_bytes(&[0x01, 0x00])))
+    );
+    // a key whose first nibble is an empty child of the branch
+    let mut key_c = key_a;
+    key_c[0] = 0x52;
+    assert_eq!(verify_proof(&root, &key_c, &[branch.clone()]), Ok(None));
+
+    // the wrong leaf for the path, a missing leaf and an extra node
+    assert_eq!(
+        verify_proof(&root, &key_a, &[branch.clone(), leaf_b]),
+        Err(MptError::HashMismatch { depth: 1 })
+    );
+    assert_eq!(verify_proof(&root, &key_a, &[branch.clone()]), Err(MptError::IncompleteProof));
+    assert_eq!(verify_proof(&root, &key_c, &[branch, leaf_a]), Err(MptError::UnusedProofNodes));
+}
+
+#[test]
+fn test_rlp_encode_uint() {
+    assert_eq!(rlp::encode_uint(&[0, 0]), vec![0x80]);
+    assert_eq!(rlp::encode_uint(&[0, 0x7f]), vec![0x7f]);
+    assert_eq!(rlp::encode_uint(&[0, 0x80]), vec![0x81, 0x80]);
+    assert_eq!(rlp::encode_uint(&[0x04, 0x00]), vec![0x82, 0x04, 0x00]);
+}
+
+fn witness_bytes(bytes: &[u8]) -> Vec<QuantumCell<'static, Fr>> {
+    bytes.iter().map(|b| Witness(Value::known(Fr::from(*b as u64)))).collect()
+}
+
+// whether `verify_proof_assigned` accepts `witness` for paths of at most `max_depth` nodes and returns `value`
+fn verifies_in_circuit(
+    root: [u8; 32],
+    key: [u8; 32],
+    witness: ProofWitness,
+    max_depth: usize,
+    value: Vec<u8>,
+) -> bool {
+    let synthesize = move |range: &RangeConfig<Fr>, ctx: &mut Context<'_, Fr>| {
+        let gate = range.gate();
+        let root = gate.assign_region_smart(ctx, witness_bytes(&root), vec![], vec![], vec![])?;
+        let key = gate.assign_region_smart(ctx, witness_bytes(&key), vec![], vec![], vec![])?;
+        let proof = AssignedProof::load(gate, ctx, Value::known(&witness), max_depth, 8)?;
+        let out = verify_proof_assigned(range, ctx, &root, &key, &proof)?;
+        let expected = AssignedVarBytes::constant(gate, ctx, &value)?;
+        out.assert_equal(gate, ctx, &expected)
+    };
+    let circuit = RangeCircuitBuilder::auto_configure(8, 16, synthesize).unwrap();
+    MockProver::run(circuit.params.degree, &circuit, vec![]).unwrap().verify().is_ok()
+}
+
+#[test]
+fn test_verify_proof_assigned_single_leaf() {
+    let key = keccak256(&[1u8; 32]);
+    let value = encode_bytes(&[0x05]);
+    let node = leaf(&to_nibbles(&key), &value);
+    let root = keccak256(&node);
+    let witness = ProofWitness::new(&root, &key, &[node]).unwrap();
+    assert_eq!(witness, ProofWitness { nodes: vec![], value: value.clone() });
+
+    assert!(verifies_in_circuit(root, key, witness.clone(), 1, value.clone()));
+    let mut wrong_root = root;
+    wrong_root[0] ^= 1;
+    assert!(!verifies_in_circuit(wrong_root, key, witness.clone(), 1, value));
+    assert!(!verifies_in_circuit(root, key, witness, 1, encode_bytes(&[0x06])));
+}
+
+#[test]
+fn test_verify_storage_proof_assigned() {
+    let slot = [1u8; 32];
+    let node = leaf(&to_nibbles(&keccak256(&slot)), &encode_bytes(&[0x05]));
+    let root = keccak256(&node);
+    let witness = ProofWitness::new(&root, &keccak256(&slot), &[node]).unwrap();
+
+    let verifies = |value: u8| {
+        let witness = witness.clone();
+        let synthesize = move |range: &RangeConfig<Fr>, ctx: &mut Context<'_, Fr>| {
+            let gate = range.gate();
+            let root =
+                gate.assign_region_smart(ctx, witness_bytes(&root), vec![], vec![], vec![])?;
+            let slot =
+                gate.assign_region_smart(ctx, witness_bytes(&slot), vec![], vec![], vec![])?;
+            let mut be_bytes = [0u8; 32];
+            be_bytes[31] = value;
+            let value =
+                gate.assign_region_smart(ctx, witness_bytes(&be_bytes), vec![], vec![], vec![])?;
+            let proof = AssignedProof::load(gate, ctx, Value::known(&witness), 1, 8)?;
+            verify_storage_proof_assigned(range, ctx, &root, &slot, &value, &proof)
+        };
+        let circuit = RangeCircuitBuilder::auto_configure(8, 16, synthesize).unwrap();
+        MockProver::run(circuit.params.degree, &circuit, vec![]).unwrap().verify().is_ok()
+    };
+    assert!(verifies(0x05));
+    assert!(!verifies(0x06));
+}
+
+#[test]
+fn test_verify_proof_assigned_branch() {
+    // the trie of `test_branch_trie`, with leaf paths of odd length
+    let mut key_a = [0x11u8; 32];
+    let mut key_b = [0x11u8; 32];
+    key_a[0] = 0x12;
+    key_b[0] = 0x92;
+    let (nibbles_a, nibbles_b) = (to_nibbles(&key_a), to_nibbles(&key_b));
+    let (value_a, value_b) = (encode_bytes(&[0x2a]), encode_bytes(&[0x01, 0x00]));
+    let leaf_a = leaf(&nibbles_a[1..], &value_a);
+    let leaf_b = leaf(&nibbles_b[1..], &value_b);
+
+    let mut children = vec![encode_bytes(&[]); 17];
+    children[nibbles_a[0] as usize] = encode_bytes(&keccak256(&leaf_a));
+    children[nibbles_b[0] as usize] = encode_bytes(&keccak256(&leaf_b));
+    let branch = encode_list(&children);
+    let root = keccak256(&branch);
+
+    let witness_a = ProofWitness::new(&root, &key_a, &[branch.clone(), leaf_a]).unwrap();
+    let witness_b = ProofWitness::new(&root, &key_b, &[branch.clone(), leaf_b]).unwrap();
+    assert!(matches!(&witness_a.nodes[..], [NodeWitness::Branch(_)]));
+    let mut key_c = key_a;
+    key_c[0] = 0x52;
+    assert_eq!(ProofWitness::new(&root, &key_c, &[branch]), Err(MptError::NotIncluded));
+
+    // a third slot which is not used
+    assert!(verifies_in_circuit(root, key_a, witness_a, 3, value_a.clone()));
+    // the proof of another key
+    assert!(!verifies_in_circuit(root, key_a, witness_b, 3, value_a));
+}
+
+#[test]
+fn test_verify_proof_assigned_extension() {
+    // two keys which share their first 3 nibbles, below an extension with that path and a branch
+    let mut key_a = [0x34u8; 32];
+    let mut key_b = [0x34u8; 32];
+    key_a[1] = 0x42;
+    key_b[1] = 0x47;
+    let (nibbles_a, nibbles_b) = (to_nibbles(&key_a), to_nibbles(&key_b));
+    let value_a = encode_bytes(&[0x83; 5]);
+    let leaf_a = leaf(&nibbles_a[4..], &value_a);
+    let leaf_b = leaf(&nibbles_b[4..], &encode_bytes(&[0x07]));
+
+    let mut children = vec![encode_bytes(&[]); 17];
+    children[nibbles_a[3] as usize] = encode_bytes(&keccak256(&leaf_a));
+    children[nibbles_b[3] as usize] = encode_bytes(&keccak256(&leaf_b));
+    let branch = encode_list(&children);
+    let extension = encode_list(&[
+        encode_bytes(&compact(&nibbles_a[..3], false)),
+        encode_bytes(&keccak256(&branch)),
+    ]);
+    let root = keccak256(&extension);
+
+    let proof = [extension, branch, leaf_a];
+    assert_eq!(verify_proof(&root, &key_a, &proof), Ok(Some(value_a.clone())));
+    let witness = ProofWitness::new(&root, &key_a, &proof).unwrap();
+    assert_eq!(
+        witness.nodes[0],
+        NodeWitness::Extension { path_len: 3, child: keccak256(&proof[1]) }
+    );
+    assert!(verifies_in_circuit(root, key_a, witness, 3, value_a));
+}
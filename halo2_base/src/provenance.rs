@@ -0,0 +1,60 @@
+// Provenance of assigned rows, for debugging failed constraints
+//
+// With the `provenance` feature, every block of rows assigned by `GateInstructions::assign_region` is recorded in
+// `Context::provenance` together with the tags pushed with `Context::push_tag` at the time, e.g. the name of the op,
+// and a backtrace if backtraces are enabled by `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`. A `MockProver` failure has
+// the offset of the failing row in its region, which `ProvenanceLog::at_row` maps back to the ops assigned there.
+// Without the feature nothing is recorded and tagging is free.
+
+use crate::ContextId;
+use std::{backtrace::Backtrace, ops::Range};
+
+/// A block of rows of an advice column and the ops which assigned it
+#[derive(Debug)]
+pub struct Provenance {
+    pub context_id: ContextId,
+    pub column_index: usize,
+    pub rows: Range<usize>,
+    // the tags pushed when the rows were assigned, outermost first
+    pub tags: Vec<&'static str>,
+    pub backtrace: Backtrace,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tags =
+            if self.tags.is_empty() { "untagged".to_string() } else { self.tags.join(" > ") };
+        write!(
+            f,
+            "{} column {} rows {}..{}: {}",
+            self.context_id, self.column_index, self.rows.start, self.rows.end, tags
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProvenanceLog {
+    pub blocks: Vec<Provenance>,
+}
+
+impl ProvenanceLog {
+    /// The blocks of all advice columns which contain `row`, e.g. the offset of a `MockProver` failure.
+    /// Gates may span several rows, so the blocks containing the rows just before `row` may be relevant too.
+    pub fn at_row(&self, row: usize) -> impl Iterator<Item = &Provenance> {
+        self.blocks.iter().filter(move |block| block.rows.contains(&row))
+    }
+
+    /// The block of the advice column `column_index` of `context_id` which contains `row`
+    pub fn at_cell(
+        &self,
+        context_id: &ContextId,
+        column_index: usize,
+        row: usize,
+    ) -> Option<&Provenance> {
+        self.blocks.iter().find(|block| {
+            block.context_id == *context_id
+                && block.column_index == column_index
+                && block.rows.contains(&row)
+        })
+    }
+}
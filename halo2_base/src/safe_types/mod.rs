@@ -0,0 +1,92 @@
+// Byte arrays and integers whose cells are known to be in range
+//
+// A range check constrains a cell once, but the invariant is lost when the cell is passed on as an `AssignedValue`,
+// so gadgets which exchange bytes either re-check them at every boundary or trust their caller. The types here can
+// only be constructed by functions which constrain the range of their cells, so a gadget taking e.g. a
+// `SafeBytes32` can rely on its bytes being bytes without checking them again.
+
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+/// 32 cells, each constrained to be a byte, e.g. a hash digest or a 256-bit word in big-endian
+#[derive(Clone, Debug)]
+pub struct SafeBytes32<F: FieldExt> {
+    bytes: Vec<AssignedValue<F>>,
+}
+
+impl<F: FieldExt> SafeBytes32<F> {
+    /// Range checks each of `bytes` to be in [0, 2^8)
+    pub fn new(
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        bytes: Vec<AssignedValue<F>>,
+    ) -> Result<Self, Error> {
+        assert_eq!(bytes.len(), 32);
+        for byte in bytes.iter() {
+            range.range_check(ctx, byte, 8)?;
+        }
+        Ok(Self { bytes })
+    }
+
+    /// The constant bytes `bytes`, which need no range check
+    pub fn load_constant(
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        bytes: &[u8; 32],
+    ) -> Result<Self, Error> {
+        let bytes = gate.assign_region_smart(
+            ctx,
+            bytes.iter().map(|b| Constant(F::from(*b as u64))).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(Self { bytes })
+    }
+
+    pub fn bytes(&self) -> &[AssignedValue<F>] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<AssignedValue<F>> {
+        self.bytes
+    }
+}
+
+/// A cell constrained to be in [0, 2^64)
+#[derive(Clone, Debug)]
+pub struct SafeUint64<F: FieldExt> {
+    value: AssignedValue<F>,
+}
+
+impl<F: FieldExt> SafeUint64<F> {
+    /// Range checks `value` to be in [0, 2^64)
+    pub fn new(
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+        value: AssignedValue<F>,
+    ) -> Result<Self, Error> {
+        range.range_check(ctx, &value, 64)?;
+        Ok(Self { value })
+    }
+
+    /// The 8 little-endian bytes of the number, each range checked with `decompose_bytes`
+    pub fn to_le_bytes(
+        &self,
+        range: &impl RangeInstructions<F>,
+        ctx: &mut Context<'_, F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        range.decompose_bytes(ctx, &self.value, 8)
+    }
+
+    pub fn value(&self) -> &AssignedValue<F> {
+        &self.value
+    }
+}
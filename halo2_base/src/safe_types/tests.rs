@@ -0,0 +1,89 @@
+use super::*;
+use crate::{
+    gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+    },
+    utils::value_to_option,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{circuit::Value, dev::MockProver, halo2curves::bn256::Fr};
+
+const PARAMS: CircuitParams =
+    CircuitParams { degree: 10, num_advice: 2, num_lookup_advice: 1, num_fixed: 1, lookup_bits: 8 };
+
+fn load_witnesses(
+    range: &RangeConfig<Fr>,
+    ctx: &mut Context<'_, Fr>,
+    values: &[u64],
+) -> Result<Vec<AssignedValue<Fr>>, Error> {
+    range.gate.assign_region_smart(
+        ctx,
+        values.iter().map(|v| Witness(Value::known(Fr::from(*v)))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )
+}
+
+#[test]
+fn test_decompose_bytes() {
+    let circuit = RangeCircuitBuilder::new(
+        RangeStrategy::Vertical,
+        PARAMS,
+        |range: &RangeConfig<Fr>, ctx| {
+            let x = 0x0123_4567_89ab_cdefu64;
+            let a = load_witnesses(range, ctx, &[x])?.pop().unwrap();
+            let bytes = range.decompose_bytes(ctx, &a, 8)?;
+            for (byte, expected) in bytes.iter().zip(x.to_le_bytes()) {
+                assert_eq!(value_to_option(byte.value().copied()), Some(Fr::from(expected as u64)));
+            }
+
+            let a = SafeUint64::new(range, ctx, a)?;
+            let bytes = a.to_le_bytes(range, ctx)?;
+            let bytes = SafeBytes32::new(
+                range,
+                ctx,
+                bytes.into_iter().chain(load_witnesses(range, ctx, &[0; 24])?).collect(),
+            )?;
+            assert_eq!(bytes.bytes().len(), 32);
+            Ok(())
+        },
+    );
+    let prover = MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn test_decompose_bytes_overflow() {
+    // 2^16 does not fit in 2 bytes
+    let circuit = RangeCircuitBuilder::new(
+        RangeStrategy::Vertical,
+        PARAMS,
+        |range: &RangeConfig<Fr>, ctx| {
+            let a = load_witnesses(range, ctx, &[1 << 16])?.pop().unwrap();
+            range.decompose_bytes(ctx, &a, 2)?;
+            Ok(())
+        },
+    );
+    let prover = MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn test_safe_bytes_not_bytes() {
+    let circuit = RangeCircuitBuilder::new(
+        RangeStrategy::Vertical,
+        PARAMS,
+        |range: &RangeConfig<Fr>, ctx| {
+            let mut bytes = vec![7; 32];
+            bytes[5] = 256;
+            let bytes = load_witnesses(range, ctx, &bytes)?;
+            SafeBytes32::new(range, ctx, bytes)?;
+            Ok(())
+        },
+    );
+    let prover = MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
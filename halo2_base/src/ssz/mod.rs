@@ -0,0 +1,215 @@
+// SSZ Merkleization (hash_tree_root) of the Ethereum consensus specs, with SHA-256 as the node hash
+//
+// Values are handled as 32-byte chunks: basic values are serialized in little-endian and packed into chunks,
+// containers are Merkleized from the roots of their fields and lists additionally mix in their length.
+// https://github.com/ethereum/consensus-specs/blob/dev/ssz/simple-serialize.md#merkleization
+
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    hashes::sha256::{sha256, sha256_native},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+pub const BYTES_PER_CHUNK: usize = 32;
+
+/// Out of circuit `zero_hashes[i]`, the root of a tree of depth `i` with zero chunks as leaves
+pub fn zero_hashes_native(depth: usize) -> Vec<[u8; 32]> {
+    let mut zero_hashes = vec![[0u8; 32]];
+    for i in 0..depth {
+        zero_hashes.push(hash_pair_native(&zero_hashes[i], &zero_hashes[i]));
+    }
+    zero_hashes
+}
+
+pub fn hash_pair_native(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut input = left.to_vec();
+    input.extend_from_slice(right);
+    sha256_native(&input)
+}
+
+/// Out of circuit `pack`: right pads serialized basic values with zeros to a multiple of 32 bytes
+pub fn pack_native(bytes: &[u8]) -> Vec<[u8; 32]> {
+    bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(|chunk| {
+            let mut out = [0u8; 32];
+            out[..chunk.len()].copy_from_slice(chunk);
+            out
+        })
+        .collect()
+}
+
+// the depth of the tree with `limit` leaves, padded to a power of two
+fn depth(limit: usize) -> usize {
+    limit.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Out of circuit `merkleize(chunks, limit)`, where `limit = None` uses `chunks.len()` as the limit
+pub fn merkleize_native(chunks: &[[u8; 32]], limit: Option<usize>) -> [u8; 32] {
+    let limit = limit.unwrap_or(chunks.len());
+    assert!(chunks.len() <= limit);
+    let depth = depth(limit);
+    let zero_hashes = zero_hashes_native(depth);
+    if chunks.is_empty() {
+        return zero_hashes[depth];
+    }
+    let mut layer = chunks.to_vec();
+    for zero_hash in zero_hashes.iter().take(depth) {
+        if layer.len() % 2 == 1 {
+            layer.push(*zero_hash);
+        }
+        layer = layer.chunks(2).map(|pair| hash_pair_native(&pair[0], &pair[1])).collect();
+    }
+    layer[0]
+}
+
+/// Out of circuit `mix_in_length(root, length)`
+pub fn mix_in_length_native(root: &[u8; 32], length: usize) -> [u8; 32] {
+    let mut length_chunk = [0u8; 32];
+    length_chunk[..8].copy_from_slice(&(length as u64).to_le_bytes());
+    hash_pair_native(root, &length_chunk)
+}
+
+/// Returns `SHA-256(left || right)`. The bytes of `left` and `right` are range checked to be in [0, 2^8).
+pub fn hash_pair<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    left: &[AssignedValue<F>],
+    right: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    assert_eq!(left.len(), BYTES_PER_CHUNK);
+    assert_eq!(right.len(), BYTES_PER_CHUNK);
+    let input: Vec<_> = left.iter().chain(right.iter()).cloned().collect();
+    sha256(range, ctx, &input)
+}
+
+fn load_chunk<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    chunk: &[u8; 32],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    gate.assign_region_smart(
+        ctx,
+        chunk.iter().map(|b| Constant(F::from(*b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )
+}
+
+/// Right pads serialized basic values with constant zeros to chunks of 32 bytes
+pub fn pack<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+) -> Result<Vec<Vec<AssignedValue<F>>>, Error> {
+    let mut chunks: Vec<Vec<_>> = bytes.chunks(BYTES_PER_CHUNK).map(|c| c.to_vec()).collect();
+    if let Some(last) = chunks.last_mut() {
+        let zeros = load_chunk(gate, ctx, &[0u8; 32])?;
+        let pad_len = BYTES_PER_CHUNK - last.len();
+        last.extend_from_slice(&zeros[..pad_len]);
+    }
+    Ok(chunks)
+}
+
+/// Input: `value` in [0, 2^{8 * num_bytes})
+///
+/// Output: the chunk with the little-endian serialization of `value` as a `uint{8 * num_bytes}`, which is its
+/// `hash_tree_root`. Constrains `value` to fit in `num_bytes` bytes.
+pub fn uint_to_chunk<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    value: &AssignedValue<F>,
+    num_bytes: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    assert!(num_bytes <= BYTES_PER_CHUNK);
+    assert!(8 * num_bytes < F::NUM_BITS as usize);
+    let gate = range.gate();
+    let mut chunk = range.decompose_bytes(ctx, value, num_bytes)?;
+    let zeros = load_chunk(gate, ctx, &[0u8; 32])?;
+    chunk.extend_from_slice(&zeros[num_bytes..]);
+    Ok(chunk)
+}
+
+/// In circuit `merkleize(chunks, limit)`, where `limit = None` uses `chunks.len()` as the limit
+///
+/// Subtrees without chunks are replaced by constant zero hashes, so this costs about `chunks.len() + depth`
+/// hashes rather than `limit`.
+pub fn merkleize<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    chunks: &[Vec<AssignedValue<F>>],
+    limit: Option<usize>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let limit = limit.unwrap_or(chunks.len());
+    assert!(chunks.len() <= limit);
+    let depth = depth(limit);
+    let zero_hashes = zero_hashes_native(depth);
+
+    if chunks.is_empty() {
+        return load_chunk(range.gate(), ctx, &zero_hashes[depth]);
+    }
+    let mut layer = chunks.to_vec();
+    for zero_hash in zero_hashes.iter().take(depth) {
+        if layer.len() % 2 == 1 {
+            layer.push(load_chunk(range.gate(), ctx, zero_hash)?);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(range, ctx, &pair[0], &pair[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    Ok(layer.swap_remove(0))
+}
+
+/// In circuit `mix_in_length(root, length)` for `length` in [0, 2^64)
+pub fn mix_in_length<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    root: &[AssignedValue<F>],
+    length: &AssignedValue<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let length_chunk = uint_to_chunk(range, ctx, length, 8)?;
+    hash_pair(range, ctx, root, &length_chunk)
+}
+
+/// `hash_tree_root` of a container given the `hash_tree_root` of each of its fields
+pub fn container_root<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    field_roots: &[Vec<AssignedValue<F>>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    merkleize(range, ctx, field_roots, None)
+}
+
+/// `hash_tree_root` of a `List[uint{8 * elem_bytes}, limit]` given the serialization `bytes` of its elements
+///
+/// The length of the list is `bytes.len() / elem_bytes`, which is fixed by the circuit.
+pub fn basic_list_root<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+    elem_bytes: usize,
+    limit: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    assert_eq!(bytes.len() % elem_bytes, 0);
+    let length = bytes.len() / elem_bytes;
+    assert!(length <= limit);
+
+    let chunk_limit = (limit * elem_bytes + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
+    let chunks = pack(range.gate(), ctx, bytes)?;
+    let root = merkleize(range, ctx, &chunks, Some(chunk_limit))?;
+    let length = range.gate().assign_region_smart(
+        ctx,
+        vec![Constant(F::from(length as u64))],
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    mix_in_length(range, ctx, &root, &length[0])
+}
@@ -0,0 +1,173 @@
+use super::*;
+use crate::{
+    gates::range::{RangeConfig, RangeStrategy},
+    ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{circuit::*, dev::MockProver, halo2curves::bn256::Fr, plonk::*};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_zero_hashes() {
+    let zero_hashes = zero_hashes_native(3);
+    assert_eq!(
+        hex(&zero_hashes[1]),
+        "f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"
+    );
+    assert_eq!(
+        hex(&zero_hashes[2]),
+        "db56114e00fdd4c1f85c892bf35ac9a89289aaecb1ebd0a96cde606a748b5d71"
+    );
+    assert_eq!(
+        hex(&zero_hashes[3]),
+        "c78009fdf07fc56a11f122370658a353aaa542ed63e44c4bc15ff4cd105ab33c"
+    );
+}
+
+#[test]
+fn test_merkleize_native() {
+    let chunks = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let zero = [0u8; 32];
+    assert_eq!(merkleize_native(&chunks[..1], None), chunks[0]);
+    assert_eq!(merkleize_native(&[], Some(4)), zero_hashes_native(2)[2]);
+    assert_eq!(
+        merkleize_native(&chunks, None),
+        hash_pair_native(
+            &hash_pair_native(&chunks[0], &chunks[1]),
+            &hash_pair_native(&chunks[2], &zero)
+        )
+    );
+    // a limit larger than the number of chunks adds zero subtrees
+    assert_eq!(
+        merkleize_native(&chunks, Some(8)),
+        hash_pair_native(&merkleize_native(&chunks, None), &zero_hashes_native(2)[2])
+    );
+}
+
+// hash_tree_root of `Checkpoint { epoch: uint64, root: Bytes32 }`
+fn checkpoint_root_native(epoch: u64, root: &[u8; 32]) -> [u8; 32] {
+    let epoch = pack_native(&epoch.to_le_bytes())[0];
+    merkleize_native(&[epoch, *root], None)
+}
+
+// hash_tree_root of a `List[uint64, limit]`
+fn uint64_list_root_native(values: &[u64], limit: usize) -> [u8; 32] {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let root = merkleize_native(&pack_native(&bytes), Some((limit * 8 + 31) / 32));
+    mix_in_length_native(&root, values.len())
+}
+
+const NUM_ADVICE: usize = 8;
+const LIST_LIMIT: usize = 8;
+
+struct SszCircuit<F> {
+    epoch: Value<F>,
+    root: Vec<Value<F>>,
+    list: Vec<Value<F>>,
+    expected_checkpoint_root: [u8; 32],
+    expected_list_root: [u8; 32],
+}
+
+impl<F: FieldExt> Circuit<F> for SszCircuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            epoch: Value::unknown(),
+            root: vec![Value::unknown(); self.root.len()],
+            list: vec![Value::unknown(); self.list.len()],
+            expected_checkpoint_root: self.expected_checkpoint_root,
+            expected_list_root: self.expected_list_root,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::PlonkPlus,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            8,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "ssz",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut witnesses = vec![Witness(self.epoch)];
+                witnesses.extend(self.root.iter().map(|x| Witness(*x)));
+                witnesses.extend(self.list.iter().map(|x| Witness(*x)));
+                let mut assigned =
+                    config.gate.assign_region_smart(ctx, witnesses, vec![], vec![], vec![])?;
+                let list = assigned.split_off(1 + self.root.len());
+                let root = assigned.split_off(1);
+
+                let epoch = uint_to_chunk(&config, ctx, &assigned[0], 8)?;
+                let checkpoint_root = container_root(&config, ctx, &[epoch, root])?;
+                let list_root = basic_list_root(&config, ctx, &list, 8, LIST_LIMIT)?;
+
+                for (out, expected) in [
+                    (checkpoint_root, self.expected_checkpoint_root),
+                    (list_root, self.expected_list_root),
+                ] {
+                    for (byte, expected) in out.iter().zip(expected.iter()) {
+                        config.gate.assert_is_const(ctx, byte, F::from(*expected as u64));
+                    }
+                }
+
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_ssz_roots() {
+    let epoch = 194048u64;
+    let root = [0xabu8; 32];
+    let list = [1u64, 2, 0xdeadbeef];
+    let to_values =
+        |bytes: &[u8]| bytes.iter().map(|b| Value::known(Fr::from(*b as u64))).collect();
+
+    let circuit = SszCircuit::<Fr> {
+        epoch: Value::known(Fr::from(epoch)),
+        root: to_values(&root),
+        list: to_values(&list.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>()),
+        expected_checkpoint_root: checkpoint_root_native(epoch, &root),
+        expected_list_root: uint64_list_root_native(&list, LIST_LIMIT),
+    };
+    let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
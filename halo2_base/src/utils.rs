@@ -161,6 +161,45 @@ pub fn value_to_option<V>(value: Value<V>) -> Option<V> {
     v
 }
 
+/// `f` applied to each of `items`, in parallel with the `parallel-witness` feature
+///
+/// Each call hands the items to the rayon pool, which costs more than it saves for a handful of conversions, so
+/// collect the items of many calls, e.g. a whole table, into one batch.
+pub fn batch_map<T, R>(items: &[T], f: impl Fn(&T) -> R + Send + Sync) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    #[cfg(feature = "parallel-witness")]
+    {
+        use rayon::prelude::*;
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel-witness"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+/// `fe_to_biguint` of each of `fes`, in parallel with the `parallel-witness` feature
+pub fn fe_to_biguint_batch<F: PrimeField>(fes: &[F]) -> Vec<BigUint> {
+    batch_map(fes, fe_to_biguint)
+}
+
+/// `biguint_to_fe` of each of `es`, in parallel with the `parallel-witness` feature
+pub fn biguint_to_fe_batch<F: PrimeField>(es: &[BigUint]) -> Vec<F> {
+    batch_map(es, biguint_to_fe)
+}
+
+/// `decompose_bigint` of each of `es`, in parallel with the `parallel-witness` feature
+pub fn decompose_bigint_batch<F: PrimeField>(
+    es: &[BigInt],
+    number_of_limbs: usize,
+    bit_len: usize,
+) -> Vec<Vec<F>> {
+    batch_map(es, |e| decompose_bigint(e, number_of_limbs, bit_len))
+}
+
 /// Compute the represented value by a vector of values and a bit length.
 ///
 /// This function is used to compute the value of an integer
@@ -176,3 +215,20 @@ fn test_signed_roundtrip() {
     use halo2_proofs::halo2curves::bn256::Fr;
     assert_eq!(fe_to_bigint(&bigint_to_fe::<Fr>(&-BigInt::one())), -BigInt::one());
 }
+
+#[cfg(test)]
+#[test]
+fn test_batch_conversions() {
+    use halo2_proofs::halo2curves::bn256::Fr;
+    let es: Vec<BigInt> = (0..20i64)
+        .map(|i| (BigInt::from(i * 0x1234_5678_9abc) << (7 * i)) * (1 - 2 * (i % 2)))
+        .collect();
+    let limbs = decompose_bigint_batch::<Fr>(&es, 4, 88);
+    for (e, limbs) in es.iter().zip(limbs.iter()) {
+        assert_eq!(limbs, &decompose_bigint::<Fr>(e, 4, 88));
+    }
+    let fes: Vec<Fr> = es.iter().map(bigint_to_fe).collect();
+    let biguints = fe_to_biguint_batch(&fes);
+    assert_eq!(biguints, fes.iter().map(fe_to_biguint).collect::<Vec<_>>());
+    assert_eq!(biguint_to_fe_batch::<Fr>(&biguints), fes);
+}
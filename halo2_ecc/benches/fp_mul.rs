@@ -0,0 +1,100 @@
+// Proving time of one `a * b` in the BN254 base field for each `FpStrategy`
+//
+// Run with `cargo +nightly bench -p halo2_ecc --bench fp_mul`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use group::ff::Field;
+use halo2_base::{
+    gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+    },
+    utils::{fe_to_bigint, modulus},
+    Context,
+};
+use halo2_ecc::fields::{
+    fp::{FpConfig, FpStrategy},
+    FieldChip,
+};
+use halo2_proofs::{
+    circuit::Value,
+    halo2curves::bn256::{Bn256, Fq, Fr, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk, Circuit, Error},
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::ProverSHPLONK,
+    },
+    transcript::{Blake2bWrite, Challenge255, TranscriptWriterBuffer},
+};
+use rand::rngs::OsRng;
+
+const PARAMS: CircuitParams = CircuitParams {
+    degree: 13,
+    num_advice: 1,
+    num_lookup_advice: 1,
+    num_fixed: 1,
+    lookup_bits: 11,
+};
+
+fn mul_circuit(strategy: FpStrategy, a: Fq, b: Fq) -> impl Circuit<Fr> + Clone {
+    let range_strategy = match strategy {
+        FpStrategy::Simple | FpStrategy::Barrett => RangeStrategy::Vertical,
+        FpStrategy::SimplePlus => RangeStrategy::PlonkPlus,
+        FpStrategy::SimpleDot => RangeStrategy::VerticalDot,
+        FpStrategy::SimpleHorizontal => RangeStrategy::Horizontal,
+    };
+    RangeCircuitBuilder::new(
+        range_strategy,
+        PARAMS,
+        move |range: &RangeConfig<Fr>, ctx: &mut Context<'_, Fr>| -> Result<(), Error> {
+            let mut chip = FpConfig::<Fr, Fq>::construct(range.clone(), 88, 3, modulus::<Fq>());
+            chip.strategy = strategy.clone();
+            let a = chip.load_private(ctx, Value::known(fe_to_bigint(&a)))?;
+            let b = chip.load_private(ctx, Value::known(fe_to_bigint(&b)))?;
+            chip.mul(ctx, &a, &b)?;
+            Ok(())
+        },
+    )
+}
+
+fn bench_fp_mul(c: &mut Criterion) {
+    let params = ParamsKZG::<Bn256>::setup(PARAMS.degree, OsRng);
+    let a = Fq::random(OsRng);
+    let b = Fq::random(OsRng);
+
+    let mut group = c.benchmark_group("fp mul");
+    group.sample_size(10);
+    for strategy in
+        [FpStrategy::Simple, FpStrategy::SimplePlus, FpStrategy::Barrett, FpStrategy::SimpleDot]
+    {
+        // keygen and proving both configure from the params registered by the latest `RangeCircuitBuilder::new`,
+        // so each strategy is built, keyed and proven before the next one
+        let circuit = mul_circuit(strategy.clone(), a, b);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("prove", format!("{:?}", strategy)),
+            &circuit,
+            |bencher, circuit| {
+                bencher.iter(|| {
+                    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+                    create_proof::<KZGCommitmentScheme<_>, ProverSHPLONK<'_, _>, _, _, _, _>(
+                        &params,
+                        &pk,
+                        &[circuit.clone()],
+                        &[&[]],
+                        OsRng,
+                        &mut transcript,
+                    )
+                    .unwrap();
+                    transcript.finalize()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_fp_mul);
+criterion_main!(benches);
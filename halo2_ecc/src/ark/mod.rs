@@ -0,0 +1,220 @@
+// Interop with arkworks, behind the `ark` feature
+//
+// Conversions between the field elements and short Weierstrass points of arkworks and the `ff`/halo2curves types
+// taken by `FieldChip::load_private` and `EccChip::assign_point`, and helpers which load arkworks values, or points
+// serialized with `CanonicalSerialize` such as those of a proof or verifying key, as private witnesses.
+//
+// Field elements are converted through their integer representative, so the two fields must have the same modulus.
+// Points are converted through their affine coordinates and must be on the same curve.
+
+use crate::{
+    ecc::{EccChip, EccPoint},
+    fields::{fp2::Fp2Chip, FieldChip, FieldExtConstructor, FieldExtPoint, PrimeFieldChip},
+};
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_serialize::{CanonicalDeserialize, Compress, SerializationError, Validate};
+use ff::PrimeField;
+use group::prime::PrimeCurveAffine;
+use halo2_base::{
+    utils::{biguint_to_fe, fe_to_biguint, modulus, value_to_option},
+    Context,
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field, FieldExt},
+    circuit::Value,
+    plonk::Error,
+};
+use num_bigint::{BigInt, BigUint};
+
+/// The element of `F` with the same integer representative as `a`
+pub fn fe_from_ark<A: ark_ff::PrimeField, F: PrimeField>(a: &A) -> F {
+    debug_assert_eq!(Into::<BigUint>::into(A::MODULUS), modulus::<F>());
+    biguint_to_fe(&(*a).into())
+}
+
+/// The arkworks element with the same integer representative as `fe`
+pub fn fe_to_ark<F: PrimeField, A: ark_ff::PrimeField>(fe: &F) -> A {
+    debug_assert_eq!(Into::<BigUint>::into(A::MODULUS), modulus::<F>());
+    A::from(fe_to_biguint(fe))
+}
+
+/// Converts an element `c0 + c1 * u` of a quadratic extension, e.g. `ark_bn254::Fq2` to `Fq2`
+pub fn fp2_from_ark<A, Fp, Fp2>(a: &A) -> Fp2
+where
+    A: ark_ff::Field,
+    Fp: PrimeField,
+    Fp2: FieldExtConstructor<Fp, 2>,
+{
+    let coeffs: Vec<Fp> = a.to_base_prime_field_elements().map(|c| fe_from_ark(&c)).collect();
+    assert_eq!(coeffs.len(), 2, "not a quadratic extension");
+    Fp2::new([coeffs[0], coeffs[1]])
+}
+
+/// Converts an element `c0 + c1 * u` of a quadratic extension, e.g. `Fq2` to `ark_bn254::Fq2`
+pub fn fp2_to_ark<Fp, Fp2, A>(a: &Fp2) -> A
+where
+    Fp: PrimeField,
+    Fp2: FieldExtConstructor<Fp, 2>,
+    A: ark_ff::Field,
+{
+    let coeffs: Vec<A::BasePrimeField> = a.coeffs().iter().map(|c| fe_to_ark(c)).collect();
+    A::from_base_prime_field_elems(&coeffs).expect("not a quadratic extension")
+}
+
+/// Converts a point over a prime field, e.g. `ark_bn254::G1Affine` to `G1Affine`; panics if it is not on the curve
+pub fn affine_from_ark<P, C>(p: &Affine<P>) -> C
+where
+    P: SWCurveConfig,
+    P::BaseField: ark_ff::PrimeField,
+    C: CurveAffine,
+{
+    if p.infinity {
+        return C::identity();
+    }
+    C::from_xy(fe_from_ark(&p.x), fe_from_ark(&p.y)).unwrap()
+}
+
+/// Converts a point over a prime field, e.g. `G1Affine` to `ark_bn254::G1Affine`; panics if it is not on the curve
+/// or not in the prime order subgroup of arkworks
+pub fn affine_to_ark<C, P>(p: &C) -> Affine<P>
+where
+    C: CurveAffine,
+    P: SWCurveConfig,
+    P::BaseField: ark_ff::PrimeField,
+{
+    let coordinates = p.coordinates();
+    if bool::from(coordinates.is_none()) {
+        return Affine::identity();
+    }
+    let coordinates = coordinates.unwrap();
+    Affine::new(fe_to_ark(coordinates.x()), fe_to_ark(coordinates.y()))
+}
+
+/// Loads `a` as a private witness of `chip`
+pub fn load_fp<F, FC, A>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    a: Value<A>,
+) -> Result<FC::FieldPoint, Error>
+where
+    F: FieldExt,
+    FC: FieldChip<F>,
+    FC::FieldType: PrimeField,
+    A: ark_ff::PrimeField,
+{
+    chip.load_private(ctx, FC::fe_to_witness(&a.map(|a| fe_from_ark(&a))))
+}
+
+/// Loads a point over a prime field as `EccChip::assign_point` does. As there, `p` cannot be the point at infinity.
+pub fn load_point<F, FC, P>(
+    chip: &EccChip<F, FC>,
+    ctx: &mut Context<'_, F>,
+    p: Value<Affine<P>>,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    F: FieldExt,
+    FC: FieldChip<F>,
+    FC::FieldType: PrimeField,
+    P: SWCurveConfig,
+    P::BaseField: ark_ff::PrimeField,
+{
+    p.as_ref().map(|p| assert!(!p.infinity, "cannot load the point at infinity"));
+    let x = p.as_ref().map(|p| fe_from_ark(&p.x));
+    let y = p.as_ref().map(|p| fe_from_ark(&p.y));
+    chip.load_private(ctx, (x, y))
+}
+
+/// Loads a point over a quadratic extension, e.g. an `ark_bn254::G2Affine`. `p` cannot be the point at infinity.
+pub fn load_g2_point<F, FpChip, Fp2, P>(
+    chip: &EccChip<F, Fp2Chip<'_, F, FpChip, Fp2>>,
+    ctx: &mut Context<'_, F>,
+    p: Value<Affine<P>>,
+) -> Result<EccPoint<F, FieldExtPoint<FpChip::FieldPoint>>, Error>
+where
+    F: FieldExt,
+    FpChip: PrimeFieldChip<F, WitnessType = Value<BigInt>, ConstantType = BigInt>,
+    FpChip::FieldType: PrimeField,
+    Fp2: Field + FieldExtConstructor<FpChip::FieldType, 2>,
+    P: SWCurveConfig,
+{
+    p.as_ref().map(|p| assert!(!p.infinity, "cannot load the point at infinity"));
+    let x = p.as_ref().map(|p| fp2_from_ark::<_, FpChip::FieldType, Fp2>(&p.x));
+    let y = p.as_ref().map(|p| fp2_from_ark::<_, FpChip::FieldType, Fp2>(&p.y));
+    chip.load_private(ctx, (x, y))
+}
+
+/// Reads `num_points` consecutive points serialized with `CanonicalSerialize`, as in an arkworks proof, checking
+/// that each is on the curve and in the prime order subgroup
+pub fn deserialize_points<P: SWCurveConfig>(
+    bytes: &[u8],
+    num_points: usize,
+    compress: Compress,
+) -> Result<Vec<Affine<P>>, SerializationError> {
+    let mut reader = bytes;
+    (0..num_points)
+        .map(|_| Affine::<P>::deserialize_with_mode(&mut reader, compress, Validate::Yes))
+        .collect()
+}
+
+// the points of `bytes` as witnesses, which are unknown if `bytes` is
+fn deserialize_values<P: SWCurveConfig>(
+    bytes: Value<&[u8]>,
+    num_points: usize,
+    compress: Compress,
+) -> Result<Vec<Value<Affine<P>>>, Error> {
+    match value_to_option(bytes) {
+        Some(bytes) => Ok(deserialize_points(bytes, num_points, compress)
+            .map_err(|_| Error::Synthesis)?
+            .into_iter()
+            .map(Value::known)
+            .collect()),
+        None => Ok(vec![Value::unknown(); num_points]),
+    }
+}
+
+/// Loads the `num_points` consecutive points over a prime field serialized in `bytes`, see `deserialize_points`.
+/// Returns `Error::Synthesis` if the bytes do not deserialize.
+pub fn load_serialized_points<F, FC, P>(
+    chip: &EccChip<F, FC>,
+    ctx: &mut Context<'_, F>,
+    bytes: Value<&[u8]>,
+    num_points: usize,
+    compress: Compress,
+) -> Result<Vec<EccPoint<F, FC::FieldPoint>>, Error>
+where
+    F: FieldExt,
+    FC: FieldChip<F>,
+    FC::FieldType: PrimeField,
+    P: SWCurveConfig,
+    P::BaseField: ark_ff::PrimeField,
+{
+    deserialize_values::<P>(bytes, num_points, compress)?
+        .into_iter()
+        .map(|p| load_point(chip, ctx, p))
+        .collect()
+}
+
+/// Loads the `num_points` consecutive points over a quadratic extension serialized in `bytes`, see
+/// `deserialize_points`. Returns `Error::Synthesis` if the bytes do not deserialize.
+pub fn load_serialized_g2_points<F, FpChip, Fp2, P>(
+    chip: &EccChip<F, Fp2Chip<'_, F, FpChip, Fp2>>,
+    ctx: &mut Context<'_, F>,
+    bytes: Value<&[u8]>,
+    num_points: usize,
+    compress: Compress,
+) -> Result<Vec<EccPoint<F, FieldExtPoint<FpChip::FieldPoint>>>, Error>
+where
+    F: FieldExt,
+    FpChip: PrimeFieldChip<F, WitnessType = Value<BigInt>, ConstantType = BigInt>,
+    FpChip::FieldType: PrimeField,
+    Fp2: Field + FieldExtConstructor<FpChip::FieldType, 2>,
+    P: SWCurveConfig,
+{
+    deserialize_values::<P>(bytes, num_points, compress)?
+        .into_iter()
+        .map(|p| load_g2_point(chip, ctx, p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,194 @@
+use super::*;
+use crate::{
+    bigint::CRTInteger,
+    fields::fp::{FpConfig, FpStrategy},
+};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::CanonicalSerialize;
+use group::{Curve, Group};
+use halo2_base::{ContextId, ContextParams};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::{Fr, G1Affine, G2Affine, G1, G2},
+    plonk::{Circuit, ConstraintSystem},
+};
+use halo2curves::bn256::{Fq, Fq2};
+
+const K: u32 = 14;
+const NUM_ADVICE: usize = 2;
+const NUM_FIXED: usize = 1;
+const LOOKUP_BITS: usize = 13;
+const NUM_POINTS: usize = 3;
+
+fn value_of(x: &CRTInteger<Fr>) -> BigInt {
+    value_to_option(x.value.clone()).unwrap()
+}
+
+fn int_of<F: PrimeField>(fe: &F) -> BigInt {
+    BigInt::from(fe_to_biguint(fe))
+}
+
+#[test]
+fn test_field_conversions() {
+    for n in [0u64, 1, 7, u64::MAX] {
+        let a = -Fr::from(n);
+        let b: ark_bn254::Fr = fe_to_ark(&a);
+        assert_eq!(b, -ark_bn254::Fr::from(n));
+        assert_eq!(fe_from_ark::<_, Fr>(&b), a);
+    }
+    let a = Fq2 { c0: Fq::from(3), c1: -Fq::from(5) };
+    let b: ark_bn254::Fq2 = fp2_to_ark(&a);
+    assert_eq!(b, ark_bn254::Fq2::new(3u64.into(), -ark_bn254::Fq::from(5u64)));
+    assert_eq!(fp2_from_ark::<_, Fq, Fq2>(&b), a);
+}
+
+#[test]
+fn test_point_conversions() {
+    for n in [1u64, 2, 12345] {
+        let p = (ark_bn254::G1Affine::generator() * ark_bn254::Fr::from(n)).into_affine();
+        let q = (G1::generator() * Fr::from(n)).to_affine();
+        assert_eq!(affine_from_ark::<_, G1Affine>(&p), q);
+        assert_eq!(affine_to_ark::<_, ark_bn254::g1::Config>(&q), p);
+    }
+    assert_eq!(
+        affine_from_ark::<_, G1Affine>(&ark_bn254::G1Affine::identity()),
+        G1Affine::identity()
+    );
+    assert_eq!(
+        affine_to_ark::<_, ark_bn254::g1::Config>(&G1Affine::identity()),
+        ark_bn254::G1Affine::identity()
+    );
+}
+
+#[derive(Default)]
+struct LoadCircuit {
+    // `NUM_POINTS` G1 points followed by a G2 point, serialized by arkworks
+    g1_bytes: Option<Vec<u8>>,
+    g2_bytes: Option<Vec<u8>>,
+    compress: bool,
+    // the same points as halo2curves computes them
+    expected: Option<(Vec<G1Affine>, G2Affine)>,
+}
+
+impl Circuit<Fr> for LoadCircuit {
+    type Config = FpConfig<Fr, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { compress: self.compress, ..Default::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            LOOKUP_BITS,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let fp2_chip = Fp2Chip::<Fr, FpConfig<Fr, Fq>, Fq2>::construct(&config);
+        let g1_chip = EccChip::construct(&config);
+        let g2_chip = EccChip::construct(&fp2_chip);
+        let compress = if self.compress { Compress::Yes } else { Compress::No };
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "ark",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let g1_bytes = match &self.g1_bytes {
+                    Some(bytes) => Value::known(bytes.as_slice()),
+                    None => Value::unknown(),
+                };
+                let g2_bytes = match &self.g2_bytes {
+                    Some(bytes) => Value::known(bytes.as_slice()),
+                    None => Value::unknown(),
+                };
+                let g1_points = load_serialized_points::<_, _, ark_bn254::g1::Config>(
+                    &g1_chip, ctx, g1_bytes, NUM_POINTS, compress,
+                )?;
+                let g2_points = load_serialized_g2_points::<_, _, _, ark_bn254::g2::Config>(
+                    &g2_chip, ctx, g2_bytes, 1, compress,
+                )?;
+                let scalar = load_fp(&config, ctx, Value::known(ark_bn254::Fq::from(5u64)))?;
+                assert_eq!(value_of(&scalar), BigInt::from(5));
+
+                if let Some((g1_expected, g2_expected)) = &self.expected {
+                    for (p, q) in g1_points.iter().zip(g1_expected.iter()) {
+                        assert_eq!(value_of(&p.x), int_of(&q.x));
+                        assert_eq!(value_of(&p.y), int_of(&q.y));
+                    }
+                    let p = &g2_points[0];
+                    assert_eq!(value_of(&p.x.coeffs[0]), int_of(&g2_expected.x.c0));
+                    assert_eq!(value_of(&p.x.coeffs[1]), int_of(&g2_expected.x.c1));
+                    assert_eq!(value_of(&p.y.coeffs[0]), int_of(&g2_expected.y.c0));
+                    assert_eq!(value_of(&p.y.coeffs[1]), int_of(&g2_expected.y.c1));
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn load_circuit(compress: bool) -> LoadCircuit {
+    let mode = if compress { Compress::Yes } else { Compress::No };
+    let mut g1_bytes = vec![];
+    let mut g1_expected = vec![];
+    for n in 1..=NUM_POINTS as u64 {
+        let p = (ark_bn254::G1Affine::generator() * ark_bn254::Fr::from(n)).into_affine();
+        p.serialize_with_mode(&mut g1_bytes, mode).unwrap();
+        g1_expected.push((G1::generator() * Fr::from(n)).to_affine());
+    }
+    let mut g2_bytes = vec![];
+    let q = (ark_bn254::G2Affine::generator() * ark_bn254::Fr::from(7u64)).into_affine();
+    q.serialize_with_mode(&mut g2_bytes, mode).unwrap();
+    let g2_expected = (G2::generator() * Fr::from(7)).to_affine();
+    LoadCircuit {
+        g1_bytes: Some(g1_bytes),
+        g2_bytes: Some(g2_bytes),
+        compress,
+        expected: Some((g1_expected, g2_expected)),
+    }
+}
+
+#[test]
+fn test_load_serialized_points() {
+    for compress in [false, true] {
+        let circuit = load_circuit(compress);
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[test]
+fn test_load_serialized_points_invalid() {
+    let mut circuit = load_circuit(false);
+    // not a point on the curve
+    circuit.g1_bytes.as_mut().unwrap()[0] ^= 1;
+    assert!(MockProver::run(K, &circuit, vec![]).is_err());
+}
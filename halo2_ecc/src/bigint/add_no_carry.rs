@@ -1,5 +1,9 @@
-use super::{CRTInteger, OverflowInteger};
-use halo2_base::{gates::GateInstructions, Context, QuantumCell::Existing};
+use super::{CRTInteger, FixedCRTInteger, FixedOverflowInteger, OverflowInteger};
+use halo2_base::{
+    gates::GateInstructions,
+    Context,
+    QuantumCell::{Constant, Existing},
+};
 use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
 use std::cmp;
 
@@ -48,3 +52,41 @@ pub fn crt<F: FieldExt>(
     let out_val = a.value.as_ref().zip(b.value.as_ref()).map(|(a, b)| a + b);
     Ok(CRTInteger::construct(out_trunc, out_native, out_val))
 }
+
+/// Input: `a` and a constant `b` with at most as many limbs as `a`
+/// Output: `a + b`, where the limbs of `b` are used as constants instead of being loaded as a witness integer
+/// Zero limbs of `b` are skipped, so adding a constant with few nonzero limbs is cheap
+pub fn assign_fixed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &FixedOverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    assert!(b.limbs.len() <= a.limbs.len());
+    let mut out_limbs = a.limbs.clone();
+    for (out_limb, b_limb) in out_limbs.iter_mut().zip(b.limbs.iter()) {
+        if *b_limb != F::zero() {
+            *out_limb = gate.add(ctx, &Existing(out_limb), &Constant(*b_limb))?;
+        }
+    }
+    Ok(OverflowInteger::construct(
+        out_limbs,
+        &a.max_limb_size + b.max_abs_limb(),
+        a.limb_bits,
+        &a.max_size + b.abs_value(),
+    ))
+}
+
+pub fn crt_fixed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    b: &FixedCRTInteger<F>,
+) -> Result<CRTInteger<F>, Error> {
+    assert_eq!(a.truncation.limbs.len(), b.truncation.limbs.len());
+    let out_trunc = assign_fixed(gate, ctx, &a.truncation, &b.truncation)?;
+    let out_native = gate.add(ctx, &Existing(&a.native), &Constant(b.native))?;
+    let out_val = a.value.as_ref().map(|a| a + &b.value);
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
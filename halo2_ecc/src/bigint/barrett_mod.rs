@@ -0,0 +1,154 @@
+use super::{
+    add_no_carry, mul_no_carry,
+    shift::{assign_bits, check_zero, shift_no_carry},
+    sub_no_carry, BigIntConfig, CRTInteger, FixedOverflowInteger, OverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{bigint_to_fe, value_to_option},
+    Context,
+    QuantumCell::{Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::One;
+
+// we add `offset = ceil(max_size / modulus) * modulus` to the input to make it non-negative
+fn offset(max_size: &BigUint, modulus: &BigUint) -> BigUint {
+    (max_size + modulus - 1usize) / modulus * modulus
+}
+
+/// Returns whether Barrett reduction with `num_limbs` limbs of `limb_bits` bits applies to an input of absolute value at most `max_size`:
+/// we need `2^{limb_bits * (num_limbs - 1)} <= modulus < 2^{limb_bits * num_limbs}` and
+/// `max_size + offset < 2^{2 * limb_bits * num_limbs}`
+pub fn is_supported(
+    max_size: &BigUint,
+    modulus: &BigUint,
+    num_limbs: usize,
+    limb_bits: usize,
+) -> bool {
+    let n = limb_bits;
+    let k = num_limbs;
+    modulus.bits() as usize > n * (k - 1)
+        && modulus.bits() as usize <= n * k
+        && max_size + offset(max_size, modulus) < BigUint::one() << (2 * n * k)
+}
+
+/// Input: `a` is an `OverflowInteger` with "signed" limbs that represents its value exactly, i.e., it is NOT truncated
+/// Output: `a (mod modulus)` as a proper BigInt with `num_limbs` limbs
+///
+/// Let `b = 2^limb_bits` and `k = num_limbs`. We compute `x = a + offset >= 0` as a proper BigInt with `2k` limbs and
+/// `q = floor( floor(x / b^{k-1}) * mu / b^{k+1} )` where `mu = floor(b^{2k} / modulus)` is a constant.
+/// The multiplication by `mu` only needs the top `k + 1` limbs of `x`, and we only keep the top limbs of the product.
+/// Barrett's bound says `x - q * modulus` is in `[0, 3 * modulus)`, so we witness `e` in `[0, 3)` and
+/// constrain `x = out + (q + e) * modulus` and `out < modulus`.
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    modulus: &BigUint,
+    num_limbs: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    let n = a.limb_bits;
+    let k = num_limbs;
+    assert!(is_supported(&a.max_size, modulus, k, n));
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("barrett_mod length {}", k);
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    let offset = offset(&a.max_size, modulus);
+    let mu = (BigUint::one() << (2 * n * k)) / modulus;
+
+    // witness generation
+    let x_val = a.to_bigint().map(|a| a + BigInt::from(offset.clone()));
+    let t_val = x_val.as_ref().map(|x| (x >> (n * (k - 1))) * BigInt::from(mu.clone()));
+    let t_lo_val = t_val.as_ref().map(|t| t & ((BigInt::one() << (n * (k + 1))) - 1usize));
+    let q_val = t_val.as_ref().map(|t| t >> (n * (k + 1)));
+    let (out_val, e_val) = if let (Some(x), Some(q)) =
+        (value_to_option(x_val.clone()), value_to_option(q_val.clone()))
+    {
+        let r = x - q * BigInt::from(modulus.clone());
+        let (e, out) = r.div_rem(&BigInt::from(modulus.clone()));
+        assert!(e < BigInt::from(3u32));
+        (Value::known(out), Value::known(bigint_to_fe::<F>(&e)))
+    } else {
+        (Value::unknown(), Value::unknown())
+    };
+
+    // constrain `x = a + offset` with `x` proper of `2k` limbs
+    let x = assign_bits(range, ctx, &x_val, 2 * n * k, n)?;
+    let offset_assigned = FixedOverflowInteger::from_native(BigInt::from(offset), 2 * k, n)
+        .assign(range.gate(), ctx)?;
+    let a_plus_offset = add_no_carry::assign(range.gate(), ctx, a, &offset_assigned)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &x, &a_plus_offset)?;
+    check_zero(range, ctx, &check)?;
+
+    // constrain `x_hi * mu = t_lo + q * b^{k+1}`
+    let x_hi = OverflowInteger::construct(
+        x.limbs[k - 1..].to_vec(),
+        x.max_limb_size.clone(),
+        n,
+        x.max_size.clone() >> (n * (k - 1)),
+    );
+    let mu_assigned =
+        FixedOverflowInteger::from_native(BigInt::from(mu), k + 1, n).assign(range.gate(), ctx)?;
+    let t = mul_no_carry::assign(range.gate(), ctx, &x_hi, &mu_assigned)?;
+    let t_lo = assign_bits(range, ctx, &t_lo_val, n * (k + 1), n)?;
+    let q = assign_bits(range, ctx, &q_val, n * (k + 1), n)?;
+    let q_shifted = shift_no_carry(range.gate(), ctx, &q, n * (k + 1))?;
+    let t_check = add_no_carry::assign(range.gate(), ctx, &t_lo, &q_shifted)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &t, &t_check)?;
+    check_zero(range, ctx, &check)?;
+
+    // constrain `x = out + (q + e) * modulus` with `e` in [0, 3) and `out < modulus`
+    let e = range.gate().assign_region_smart(ctx, vec![Witness(e_val)], vec![], vec![], vec![])?;
+    range.check_less_than_safe(ctx, &e[0], 3, 2)?;
+    let mut q_plus_e = q.clone();
+    q_plus_e.limbs[0] = range.gate().add(ctx, &Existing(&q.limbs[0]), &Existing(&e[0]))?;
+    q_plus_e.max_limb_size += 2usize;
+    q_plus_e.max_size += 2usize;
+    let mod_assigned = FixedOverflowInteger::from_native(BigInt::from(modulus.clone()), k, n)
+        .assign(range.gate(), ctx)?;
+    let prod = mul_no_carry::assign(range.gate(), ctx, &q_plus_e, &mod_assigned)?;
+    let out = assign_bits(range, ctx, &out_val, modulus.bits() as usize, n)?;
+    let out_check = add_no_carry::assign(range.gate(), ctx, &out, &prod)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &x, &out_check)?;
+    check_zero(range, ctx, &check)?;
+
+    let (_, underflow) = super::sub::assign(range, ctx, &out, &mod_assigned)?;
+    range.gate().assert_is_const(ctx, &underflow, F::one());
+
+    Ok(OverflowInteger::construct(out.limbs, out.max_limb_size, n, modulus - 1usize))
+}
+
+/// Input: `a, b` are `CRTInteger`s whose `truncation`s represent their values exactly, i.e., they were not truncated
+/// Output: `a * b (mod modulus)` as a `CRTInteger` with proper `truncation`, using Barrett reduction on the full product
+/// We constrain `a.native, b.native` to equal the evaluations of `a.truncation, b.truncation` so the CRT representation is consistent
+pub fn mul_crt<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    b: &CRTInteger<F>,
+    modulus: &BigUint,
+) -> Result<CRTInteger<F>, Error> {
+    let n = a.truncation.limb_bits;
+    let k = a.truncation.limbs.len();
+    for x in [a, b] {
+        let native = OverflowInteger::evaluate(range.gate(), chip, ctx, &x.truncation.limbs, n)?;
+        range.gate().assert_equal(ctx, &Existing(&native), &Existing(&x.native))?;
+    }
+    let prod = mul_no_carry::assign(range.gate(), ctx, &a.truncation, &b.truncation)?;
+    let out = assign(range, ctx, &prod, modulus, k)?;
+    let out_native = OverflowInteger::evaluate(range.gate(), chip, ctx, &out.limbs, n)?;
+    let out_val = a.value.as_ref().zip(b.value.as_ref()).map(|(a, b)| {
+        let m = BigInt::from(modulus.clone());
+        (a * b).mod_floor(&m)
+    });
+    Ok(CRTInteger::construct(out, out_native, out_val))
+}
@@ -2,7 +2,7 @@ use super::{CRTInteger, OverflowInteger};
 use halo2_base::{
     gates::{GateInstructions, RangeInstructions},
     AssignedValue, Context,
-    QuantumCell::Existing,
+    QuantumCell::{Existing, ExistingOwned},
 };
 use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
 
@@ -22,19 +22,12 @@ pub fn assign<F: FieldExt>(
     assert_eq!(limb_bits_a, limb_bits_b);
     let k = k_a;
 
-    let mut eq = Vec::with_capacity(k);
-    for idx in 0..k {
+    let mut eq = range.is_equal(ctx, &Existing(&a.limbs[0]), &Existing(&b.limbs[0]))?;
+    for idx in 1..k {
         let eq_limb = range.is_equal(ctx, &Existing(&a.limbs[idx]), &Existing(&b.limbs[idx]))?;
-        eq.push(eq_limb);
+        eq = range.gate().and(ctx, &ExistingOwned(eq_limb), &ExistingOwned(eq))?;
     }
-
-    let mut partials = Vec::with_capacity(k);
-    partials.push(eq[0].clone());
-    for idx in 0..(k - 1) {
-        let new = range.gate().and(ctx, &Existing(&eq[idx + 1]), &Existing(&partials[idx]))?;
-        partials.push(new);
-    }
-    Ok(partials[k - 1].clone())
+    Ok(eq)
 }
 
 pub fn crt<F: FieldExt>(
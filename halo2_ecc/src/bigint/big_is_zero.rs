@@ -2,7 +2,7 @@ use super::{CRTInteger, OverflowInteger};
 use halo2_base::{
     gates::{GateInstructions, RangeInstructions},
     AssignedValue, Context,
-    QuantumCell::Existing,
+    QuantumCell::{Existing, ExistingOwned},
 };
 use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
 
@@ -14,18 +14,12 @@ pub fn assign<F: FieldExt>(
 ) -> Result<AssignedValue<F>, Error> {
     let k = a.limbs.len();
 
-    let mut partial = None;
-    for idx in 0..k {
+    let mut partial = range.is_zero(ctx, &a.limbs[0])?;
+    for idx in 1..k {
         let limb_is_zero = range.is_zero(ctx, &a.limbs[idx])?;
-        if let Some(prev) = partial {
-            let new = range.gate().and(ctx, &Existing(&limb_is_zero), &Existing(&prev))?;
-            partial = Some(new);
-        } else {
-            partial = Some(limb_is_zero);
-        }
+        partial = range.gate().and(ctx, &ExistingOwned(limb_is_zero), &ExistingOwned(partial))?;
     }
-
-    Ok(partial.unwrap())
+    Ok(partial)
 }
 
 pub fn crt<F: FieldExt>(
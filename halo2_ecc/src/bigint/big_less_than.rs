@@ -1,16 +1,136 @@
-use super::OverflowInteger;
-use halo2_base::{gates::RangeInstructions, AssignedValue, Context};
+use super::{big_is_zero, OverflowInteger};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::decompose_biguint,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
 use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
 
-// given OverflowInteger<F>'s `a` and `b` of the same shape,
+// given OverflowInteger<F>'s `a` and `b` in proper representation with the same `limb_bits`,
 // returns whether `a < b`
+// if the limb counts differ, the shorter integer is logically padded with zero limbs
 pub fn assign<F: FieldExt>(
     range: &impl RangeInstructions<F>,
     ctx: &mut Context<'_, F>,
     a: &OverflowInteger<F>,
     b: &OverflowInteger<F>,
 ) -> Result<AssignedValue<F>, Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    let k = std::cmp::min(a.limbs.len(), b.limbs.len());
+    let low = |x: &OverflowInteger<F>| {
+        OverflowInteger::construct(
+            x.limbs[..k].to_vec(),
+            x.max_limb_size.clone(),
+            x.limb_bits,
+            std::cmp::min(x.max_size.clone(), (BigUint::from(1u64) << (x.limb_bits * k)) - 1usize),
+        )
+    };
+    let high_is_zero = |ctx: &mut Context<'_, F>, x: &OverflowInteger<F>| {
+        let high = OverflowInteger::construct(
+            x.limbs[k..].to_vec(),
+            x.max_limb_size.clone(),
+            x.limb_bits,
+            x.max_size.clone() >> (x.limb_bits * k),
+        );
+        big_is_zero::assign(range, ctx, &high)
+    };
+
     // a < b iff a - b has underflow
-    let (_, underflow) = super::sub::assign(range, ctx, a, b)?;
-    Ok(underflow)
+    if a.limbs.len() == b.limbs.len() {
+        let (_, underflow) = super::sub::assign(range, ctx, a, b)?;
+        Ok(underflow)
+    } else if a.limbs.len() > b.limbs.len() {
+        // a < b iff the extra limbs of `a` are zero and the bottom limbs of `a` are less than `b`
+        let (_, lo_lt) = super::sub::assign(range, ctx, &low(a), b)?;
+        let hi_zero = high_is_zero(ctx, a)?;
+        range.gate().and(ctx, &Existing(&lo_lt), &Existing(&hi_zero))
+    } else {
+        // a < b iff the extra limbs of `b` are nonzero or `a` is less than the bottom limbs of `b`
+        let (_, lo_lt) = super::sub::assign(range, ctx, a, &low(b))?;
+        let hi_zero = high_is_zero(ctx, b)?;
+        let hi_nonzero = range.gate().not(ctx, &Existing(&hi_zero))?;
+        range.gate().or(ctx, &Existing(&lo_lt), &Existing(&hi_nonzero))
+    }
+}
+
+// given OverflowInteger<F> `a` in proper representation and a constant `b`,
+// returns whether `a < b`
+// only the borrow chain of `a - b` is computed and the limbs of `b` are used as constants,
+// so `b` is never assigned as a witness integer
+pub fn assign_constant<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &BigUint,
+) -> Result<AssignedValue<F>, Error> {
+    let n = a.limb_bits;
+    let k = a.limbs.len();
+    // every proper `a` is less than `b >= 2^{n * k}`
+    if b.bits() as usize > n * k {
+        let one = range.gate().assign_region_smart(
+            ctx,
+            vec![Constant(F::one())],
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        return Ok(one[0].clone());
+    }
+    let b_limbs = decompose_biguint::<F>(b, k, n);
+
+    let mut borrow: Option<AssignedValue<F>> = None;
+    for (a_limb, b_limb) in a.limbs.iter().zip(b_limbs.iter()) {
+        let lt = match borrow {
+            None => range.is_less_than(ctx, &Existing(a_limb), &Constant(*b_limb), n)?,
+            Some(borrow) => {
+                let b_plus_borrow =
+                    range.gate().add(ctx, &Existing(&borrow), &Constant(*b_limb))?;
+                range.is_less_than(ctx, &Existing(a_limb), &Existing(&b_plus_borrow), n + 1)?
+            }
+        };
+        borrow = Some(lt);
+    }
+    Ok(borrow.unwrap())
+}
+
+// given OverflowInteger<F>'s `a` and `b` in proper representation with the same `limb_bits` and number of limbs,
+// returns `(a < b, a == b)`
+// the limbs are compared lexicographically from the most significant one with `RangeInstructions::compare`,
+// which gives the order and the equality of each pair of limbs from one decomposition
+pub fn compare<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &OverflowInteger<F>,
+) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    assert_eq!(a.limbs.len(), b.limbs.len());
+    compare_limbs(range, ctx, &a.limbs, &b.limbs, a.limb_bits)
+}
+
+// given little-endian limb vectors `a` and `b` of the same length, with limbs of at most `limb_bits` bits,
+// returns `(a < b, a == b)` in lexicographic order from the last limb
+pub fn compare_limbs<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &[AssignedValue<F>],
+    b: &[AssignedValue<F>],
+    limb_bits: usize,
+) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+    assert_eq!(a.len(), b.len());
+    assert!(!a.is_empty());
+    let gate = range.gate();
+    let mut limbs = a.iter().zip(b.iter()).rev();
+    let (a_top, b_top) = limbs.next().unwrap();
+    let (mut lt, mut eq) = range.compare(ctx, &Existing(a_top), &Existing(b_top), limb_bits)?;
+    for (a_limb, b_limb) in limbs {
+        let (limb_lt, limb_eq) =
+            range.compare(ctx, &Existing(a_limb), &Existing(b_limb), limb_bits)?;
+        // a < b iff the higher limbs are less, or they are equal and this limb is less
+        lt = gate.or_and(ctx, &Existing(&lt), &Existing(&eq), &Existing(&limb_lt))?;
+        eq = gate.mul(ctx, &Existing(&eq), &Existing(&limb_eq))?;
+    }
+    Ok((lt, eq))
 }
@@ -0,0 +1,106 @@
+use super::OverflowInteger;
+use halo2_base::{
+    gates::{
+        bitwise::{BitwiseOp, ByteLookupConfig},
+        GateInstructions, InnerProductResult,
+    },
+    utils::{biguint_to_fe, decompose_option},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use num_traits::One;
+
+// witnesses the little-endian bytes of `limb` and constrains that they compose to `limb`
+// the bytes are NOT range checked here: the byte lookup they are used in will constrain them to be bytes
+fn limb_to_bytes<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    limb: &AssignedValue<F>,
+    pows: &Vec<QuantumCell<F>>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let byte_vals = decompose_option::<F>(&limb.value().copied(), pows.len(), 8);
+    let InnerProductResult { a: byte_cells, out: limb_check, .. } = gate
+        .inner_product_with_operands(ctx, &byte_vals.iter().map(|x| Witness(*x)).collect(), pows)?;
+    ctx.region.constrain_equal(limb.cell(), limb_check.cell())?;
+    Ok(byte_cells.unwrap())
+}
+
+/// Input: `a` and `b` are `OverflowInteger`s in proper representation with the same number of limbs,
+/// and `limb_bits` divisible by 8
+/// Output: `op(a, b)` computed bytewise using the byte lookup table, in proper representation
+pub fn assign<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    lookup: &ByteLookupConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &OverflowInteger<F>,
+    op: BitwiseOp,
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    assert_eq!(a.limbs.len(), b.limbs.len());
+    let n = a.limb_bits;
+    assert_eq!(n % 8, 0);
+    let k = a.limbs.len();
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("bitwise {:?} length {}", op, k);
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    let pows: Vec<QuantumCell<F>> =
+        (0..n / 8).map(|i| Constant(biguint_to_fe(&(BigUint::one() << (8 * i))))).collect();
+
+    let mut out_limbs = Vec::with_capacity(k);
+    for (a_limb, b_limb) in a.limbs.iter().zip(b.limbs.iter()) {
+        let a_bytes = limb_to_bytes(gate, ctx, a_limb, &pows)?;
+        let b_bytes = limb_to_bytes(gate, ctx, b_limb, &pows)?;
+        let mut out_bytes = Vec::with_capacity(a_bytes.len());
+        for (a_byte, b_byte) in a_bytes.iter().zip(b_bytes.iter()) {
+            out_bytes.push(lookup.assign_op(ctx, op, &Existing(a_byte), &Existing(b_byte))?);
+        }
+        let out_limb =
+            gate.inner_product(ctx, &out_bytes.iter().map(|x| Existing(x)).collect(), &pows)?;
+        out_limbs.push(out_limb);
+    }
+
+    Ok(OverflowInteger::construct(
+        out_limbs,
+        (BigUint::one() << n) - 1usize,
+        n,
+        (BigUint::one() << (n * k)) - 1usize,
+    ))
+}
+
+pub fn and<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    lookup: &ByteLookupConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assign(gate, lookup, ctx, a, b, BitwiseOp::And)
+}
+
+pub fn or<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    lookup: &ByteLookupConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assign(gate, lookup, ctx, a, b, BitwiseOp::Or)
+}
+
+pub fn xor<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    lookup: &ByteLookupConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assign(gate, lookup, ctx, a, b, BitwiseOp::Xor)
+}
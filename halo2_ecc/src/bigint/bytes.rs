@@ -0,0 +1,113 @@
+use super::{BigIntConfig, CRTInteger, OverflowInteger};
+use halo2_base::{
+    gates::{GateInstructions, InnerProductResult, RangeInstructions},
+    utils::{biguint_to_fe, decompose_option, fe_to_bigint},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Zero};
+
+/// Input: `a` is a `CRTInteger` whose truncation is a proper representation of `a.value`, with `limb_bits` divisible by 8
+/// Output: the `num_bytes` little-endian bytes of `a`, each range checked to be in [0, 2^8)
+/// We constrain each limb to equal the composition of its bytes, and constrain all bytes at index `>= num_bytes` to be zero
+pub fn to_bytes_le<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    num_bytes: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let n = a.truncation.limb_bits;
+    assert_eq!(n % 8, 0);
+    let bytes_per_limb = n / 8;
+    let k = a.truncation.limbs.len();
+    assert!(num_bytes <= k * bytes_per_limb);
+
+    let pows: Vec<QuantumCell<F>> = (0..bytes_per_limb)
+        .map(|i| Constant(biguint_to_fe(&(BigUint::one() << (8 * i)))))
+        .collect();
+
+    let mut bytes = Vec::with_capacity(k * bytes_per_limb);
+    for limb in a.truncation.limbs.iter() {
+        let byte_vals = decompose_option::<F>(&limb.value().copied(), bytes_per_limb, 8);
+        let InnerProductResult { a: byte_cells, out: limb_check, .. } =
+            range.gate().inner_product_with_operands(
+                ctx,
+                &byte_vals.iter().map(|x| Witness(*x)).collect(),
+                &pows,
+            )?;
+        let byte_cells = byte_cells.unwrap();
+        ctx.region.constrain_equal(limb.cell(), limb_check.cell())?;
+        for byte in byte_cells.iter() {
+            range.range_check(ctx, byte, 8)?;
+        }
+        bytes.extend(byte_cells);
+    }
+    for byte in bytes[num_bytes..].iter() {
+        range.gate().assert_is_const(ctx, byte, F::zero());
+    }
+    bytes.truncate(num_bytes);
+    Ok(bytes)
+}
+
+/// Input: `bytes` in little-endian order; each byte is range checked to be in [0, 2^8)
+/// Output: the `CRTInteger` with `num_limbs` limbs of `limb_bits` bits represented by `bytes`, in proper representation
+pub fn from_bytes_le<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+    limb_bits: usize,
+    num_limbs: usize,
+) -> Result<CRTInteger<F>, Error> {
+    assert_eq!(limb_bits % 8, 0);
+    let bytes_per_limb = limb_bits / 8;
+    assert!(bytes.len() <= num_limbs * bytes_per_limb);
+
+    for byte in bytes.iter() {
+        range.range_check(ctx, byte, 8)?;
+    }
+
+    let mut limbs = Vec::with_capacity(num_limbs);
+    for i in 0..num_limbs {
+        let start = std::cmp::min(i * bytes_per_limb, bytes.len());
+        let end = std::cmp::min(start + bytes_per_limb, bytes.len());
+        let limb = if start == end {
+            let zero = range.gate().assign_region_smart(
+                ctx,
+                vec![Constant(F::zero())],
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            zero[0].clone()
+        } else {
+            let limb = range.gate().inner_product(
+                ctx,
+                &bytes[start..end].iter().map(|x| Existing(x)).collect(),
+                &(0..end - start)
+                    .map(|j| Constant(biguint_to_fe(&(BigUint::one() << (8 * j)))))
+                    .collect(),
+            )?;
+            limb
+        };
+        limbs.push(limb);
+    }
+
+    let native = OverflowInteger::evaluate(range.gate(), chip, ctx, &limbs, limb_bits)?;
+    let value = bytes.iter().rev().fold(Value::known(BigInt::zero()), |acc, byte| {
+        acc.zip(byte.value()).map(|(acc, x)| (acc << 8) + fe_to_bigint(x))
+    });
+    let max_size = (BigUint::one() << (8 * bytes.len())) - 1usize;
+    Ok(CRTInteger::construct(
+        OverflowInteger::construct(
+            limbs,
+            (BigUint::one() << limb_bits) - 1usize,
+            limb_bits,
+            max_size,
+        ),
+        native,
+        value,
+    ))
+}
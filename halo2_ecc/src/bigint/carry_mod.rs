@@ -1,6 +1,8 @@
-use super::{check_carry_to_zero, BigIntConfig, BigIntStrategy, CRTInteger, OverflowInteger};
+use super::{
+    check_carry_to_zero, mul_no_carry, BigIntConfig, BigIntStrategy, CRTInteger, OverflowInteger,
+};
 use halo2_base::{
-    gates::{range::RangeStrategy, GateInstructions, RangeInstructions},
+    gates::{range::RangeStrategy, GateInstructions, InnerProductResult, RangeInstructions},
     utils::{
         biguint_to_fe, decompose_bigint_option, decompose_biguint, modulus as native_modulus,
         value_to_option,
@@ -82,6 +84,8 @@ pub fn assign<F: FieldExt>(
     let mut quot_assigned: Vec<AssignedValue<F>> = Vec::with_capacity(m);
     let mut out_assigned: Vec<AssignedValue<F>> = Vec::with_capacity(num_limbs);
     let mut check_assigned: Vec<AssignedValue<F>> = Vec::with_capacity(k_prod);
+    let mod_vals: Vec<Value<F>> = mod_vec.iter().map(|x| Value::known(*x)).collect();
+    let prod_vals = mul_no_carry::partial_products(&mod_vals, &quotient_vec, k_prod);
 
     for i in 0..k_prod {
         let (mod_cell, quot_cell, out_cell, check_cell) = {
@@ -116,7 +120,7 @@ pub fn assign<F: FieldExt>(
                     prod_computation.push(Witness(quotient_vec[i - j]));
                 };
 
-                prod_val = prod_val + Value::known(mod_vec[j]) * &quotient_vec[i - j];
+                prod_val = prod_vals[i][j - startj];
                 prod_computation.push(Witness(prod_val));
 
                 offset += 3;
@@ -349,15 +353,16 @@ pub fn crt<F: FieldExt>(
         BigIntStrategy::Simple => {
             for i in 0..k {
                 let (quot_cell, out_cell, check_cell) = {
-                    let (quot_assigned, _, prod) = range.gate().inner_product(
-                        ctx,
-                        &quot_assigned[0..i]
-                            .iter()
-                            .map(|a| Existing(&a))
-                            .chain([Witness(quot_vec[i])])
-                            .collect(),
-                        &mod_vec[0..=i].iter().rev().map(|c| Constant(*c)).collect(),
-                    )?;
+                    let InnerProductResult { a: quot_assigned, out: prod, .. } =
+                        range.gate().inner_product_with_operands(
+                            ctx,
+                            &quot_assigned[0..i]
+                                .iter()
+                                .map(|a| Existing(&a))
+                                .chain([Witness(quot_vec[i])])
+                                .collect(),
+                            &mod_vec[0..=i].iter().rev().map(|c| Constant(*c)).collect(),
+                        )?;
                     let gate_index = prod.column();
 
                     let out_cell;
@@ -367,7 +372,9 @@ pub fn crt<F: FieldExt>(
                     let check_val = temp1 + out_vec[i];
 
                     match range.strategy() {
-                        RangeStrategy::Vertical => {
+                        RangeStrategy::Vertical
+                        | RangeStrategy::VerticalDot
+                        | RangeStrategy::Horizontal => {
                             // transpose of:
                             // | prod | -1 | a | prod - a | 1 | out | prod - a + out
                             // where prod is at relative row `offset`
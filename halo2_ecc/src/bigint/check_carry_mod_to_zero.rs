@@ -1,8 +1,8 @@
 use super::BigIntConfig;
-use super::{check_carry_to_zero, CRTInteger, OverflowInteger};
+use super::{check_carry_to_zero, mul_no_carry, CRTInteger, OverflowInteger};
 use crate::bigint::{carry_mod::get_carry_witness, BigIntStrategy};
 use halo2_base::{
-    gates::{GateInstructions, RangeInstructions},
+    gates::{flex_gate::GateStrategy, GateInstructions, InnerProductResult, RangeInstructions},
     utils::{
         biguint_to_fe, decompose_bigint_option, decompose_biguint, modulus as native_modulus,
         value_to_option,
@@ -74,6 +74,8 @@ pub fn assign<F: FieldExt>(
     let mut mod_assigned: Vec<AssignedValue<F>> = Vec::with_capacity(mod_vec.len());
     let mut quot_assigned: Vec<AssignedValue<F>> = Vec::with_capacity(m);
     let mut check_assigned: Vec<AssignedValue<F>> = Vec::with_capacity(k_prod);
+    let mod_vals: Vec<Value<F>> = mod_vec.iter().map(|x| Value::known(*x)).collect();
+    let prod_vals = mul_no_carry::partial_products(&mod_vals, &quotient_vec, k_prod);
 
     for i in 0..k_prod {
         let (mod_cell, quot_cell, check_cell) = {
@@ -107,7 +109,7 @@ pub fn assign<F: FieldExt>(
                     prod_computation.push(Witness(quotient_vec[i - j]));
                 };
 
-                prod_val = prod_val + Value::known(mod_vec[j]) * &quotient_vec[i - j];
+                prod_val = prod_vals[i][j - startj];
                 prod_computation.push(Witness(prod_val));
 
                 offset += 3;
@@ -272,35 +274,56 @@ pub fn crt<F: FieldExt>(
     match chip.strategy {
         BigIntStrategy::Simple => {
             for i in 0..k {
-                let (quot_cell, check_cell) = {
-                    let (quot_assigned, _, prod) = range.gate().inner_product(
-                        ctx,
-                        &quot_assigned[0..i]
-                            .iter()
-                            .map(|a| Existing(&a))
-                            .chain([Witness(quot_vec[i])])
-                            .collect(),
-                        &mod_vec[0..=i].iter().rev().map(|c| Constant(*c)).collect(),
-                    )?;
-                    let gate_index = prod.column();
-
-                    // perform step 2: compute prod - a + out
-                    // transpose of:
-                    // | prod | -1 | a | prod - a |
-                    let check_val = prod.value().copied() - a.truncation.limbs[i].value();
-                    let assignments = range.gate().assign_region(
-                        ctx,
-                        vec![
-                            Constant(-F::from(1)),
-                            Existing(&a.truncation.limbs[i]),
-                            Witness(check_val),
-                        ],
-                        vec![(-1, None)],
-                        Some(gate_index),
-                    )?;
-
-                    (quot_assigned.unwrap()[i].clone(), assignments[2].clone())
-                };
+                let (quot_cell, check_cell) =
+                    if range.gate().strategy() == GateStrategy::VerticalDot {
+                        // the dot gate absorbs step 2 into the inner product:
+                        // `prod - a = sum_j mod_vec[j] * quot[i - j] + (-1) * a`
+                        let InnerProductResult { a: quot_assigned, out: check, .. } =
+                            range.gate().inner_product_with_operands(
+                                ctx,
+                                &quot_assigned[0..i]
+                                    .iter()
+                                    .map(|a| Existing(&a))
+                                    .chain([Witness(quot_vec[i]), Existing(&a.truncation.limbs[i])])
+                                    .collect(),
+                                &mod_vec[0..=i]
+                                    .iter()
+                                    .rev()
+                                    .map(|c| Constant(*c))
+                                    .chain([Constant(-F::one())])
+                                    .collect(),
+                            )?;
+                        (quot_assigned.unwrap()[i].clone(), check)
+                    } else {
+                        let InnerProductResult { a: quot_assigned, out: prod, .. } =
+                            range.gate().inner_product_with_operands(
+                                ctx,
+                                &quot_assigned[0..i]
+                                    .iter()
+                                    .map(|a| Existing(&a))
+                                    .chain([Witness(quot_vec[i])])
+                                    .collect(),
+                                &mod_vec[0..=i].iter().rev().map(|c| Constant(*c)).collect(),
+                            )?;
+                        let gate_index = prod.column();
+
+                        // perform step 2: compute prod - a + out
+                        // transpose of:
+                        // | prod | -1 | a | prod - a |
+                        let check_val = prod.value().copied() - a.truncation.limbs[i].value();
+                        let assignments = range.gate().assign_region(
+                            ctx,
+                            vec![
+                                Constant(-F::from(1)),
+                                Existing(&a.truncation.limbs[i]),
+                                Witness(check_val),
+                            ],
+                            vec![(-1, None)],
+                            Some(gate_index),
+                        )?;
+
+                        (quot_assigned.unwrap()[i].clone(), assignments[2].clone())
+                    };
                 quot_assigned.push(quot_cell);
                 check_assigned.push(check_cell);
             }
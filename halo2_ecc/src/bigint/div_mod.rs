@@ -0,0 +1,120 @@
+use super::{
+    add_no_carry, big_less_than, check_carry_to_zero, mul_no_carry, sub_no_carry, BigIntConfig,
+    CRTInteger, OverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{decompose_bigint_option, value_to_option},
+    AssignedValue, Context,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// Input: `a` is an `OverflowInteger` with non-negative value, possibly with overflow limbs
+/// Input: `b` is an `OverflowInteger` in proper representation, i.e., all limbs in [0, 2^limb_bits)
+/// Output: `(q, r)` such that `a = q * b + r` and `0 <= r < b`
+/// * `q` has `ceil(a.max_size.bits() / limb_bits)` limbs and `r` has `b.limbs.len()` limbs, both in proper representation
+/// * since we constrain `r < b`, this also constrains `b != 0`
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &OverflowInteger<F>,
+) -> Result<(OverflowInteger<F>, OverflowInteger<F>), Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    let n = a.limb_bits;
+    let k_a = a.limbs.len();
+    let k_b = b.limbs.len();
+    assert!(k_a > 0);
+    assert!(k_b > 0);
+    // q <= a.max_size since b >= 1
+    let k_q = std::cmp::max((a.max_size.bits() as usize + n - 1) / n, 1);
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("div_mod length {} by {}", k_a, k_b);
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    // these are witness vectors:
+    let (q_vec, r_vec) = if let (Some(a_big), Some(b_big)) =
+        (value_to_option(a.to_bigint()), value_to_option(b.to_bigint()))
+    {
+        // if `b = 0` the witness does not matter since `r < b` will fail
+        let (q, r) =
+            if b_big.is_zero() { (BigInt::zero(), a_big) } else { a_big.div_mod_floor(&b_big) };
+        (
+            decompose_bigint_option::<F>(&Value::known(q), k_q, n),
+            decompose_bigint_option::<F>(&Value::known(r), k_b, n),
+        )
+    } else {
+        (vec![Value::unknown(); k_q], vec![Value::unknown(); k_b])
+    };
+
+    let q_assigned = range.gate().assign_region_smart(
+        ctx,
+        q_vec.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    let r_assigned = range.gate().assign_region_smart(
+        ctx,
+        r_vec.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    // range check limbs of `q` and `r` are in [0, 2^n)
+    for limb in q_assigned.iter().chain(r_assigned.iter()) {
+        range.range_check(ctx, limb, n)?;
+    }
+
+    let limb_max = (BigUint::one() << n) - 1usize;
+    let q = OverflowInteger::construct(
+        q_assigned,
+        limb_max.clone(),
+        n,
+        (BigUint::one() << (n * k_q)) - 1usize,
+    );
+    let r =
+        OverflowInteger::construct(r_assigned, limb_max, n, (BigUint::one() << (n * k_b)) - 1usize);
+
+    // check that `q * b + r - a == 0` after carry
+    let prod = mul_no_carry::assign(range.gate(), ctx, &q, b)?;
+    let prod_plus_r = add_no_carry::assign(range.gate(), ctx, &prod, &r)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &prod_plus_r, a)?;
+    check_carry_to_zero::assign(range, ctx, &check)?;
+
+    // check that `r < b`
+    let lt = big_less_than::assign(range, ctx, &r, b)?;
+    range.gate().assert_is_const(ctx, &lt, F::one());
+
+    Ok((q, r))
+}
+
+/// Input: `a` and `b` are `CRTInteger`s whose truncations are proper representations of `a.value` and `b.value`
+/// Output: `(q, r)` as `CRTInteger`s such that `a = q * b + r` and `0 <= r < b`
+pub fn crt<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    b: &CRTInteger<F>,
+) -> Result<(CRTInteger<F>, CRTInteger<F>), Error> {
+    let (q_trunc, r_trunc) = assign(range, ctx, &a.truncation, &b.truncation)?;
+    let q_native: AssignedValue<F> =
+        OverflowInteger::evaluate(range.gate(), chip, ctx, &q_trunc.limbs, q_trunc.limb_bits)?;
+    let r_native: AssignedValue<F> =
+        OverflowInteger::evaluate(range.gate(), chip, ctx, &r_trunc.limbs, r_trunc.limb_bits)?;
+    let q_val = q_trunc.to_bigint();
+    let r_val = r_trunc.to_bigint();
+    Ok((
+        CRTInteger::construct(q_trunc, q_native, q_val),
+        CRTInteger::construct(r_trunc, r_native, r_val),
+    ))
+}
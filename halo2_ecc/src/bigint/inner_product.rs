@@ -1,6 +1,9 @@
-use super::{CRTInteger, OverflowInteger};
+use super::{CRTInteger, FixedCRTInteger, OverflowInteger};
 use halo2_base::{
-    gates::GateInstructions, utils::fe_to_bigint, AssignedValue, Context, QuantumCell::Existing,
+    gates::GateInstructions,
+    utils::fe_to_bigint,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
 };
 use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
 use num_bigint::{BigInt, BigUint};
@@ -25,7 +28,7 @@ pub fn assign<F: FieldExt>(
             int_limbs.push(Existing(&a[int_idx].limbs[idx]));
         }
         let limb_res = gate.inner_product(ctx, &int_limbs, &coeffs_quantum)?;
-        out_limbs.push(limb_res.2.clone());
+        out_limbs.push(limb_res);
     }
 
     let max_limb_size =
@@ -53,7 +56,7 @@ pub fn crt<F: FieldExt>(
         for int_idx in 0..length {
             int_limbs.push(Existing(&a[int_idx].truncation.limbs[idx]));
         }
-        let (_, _, limb_res) = gate.inner_product(ctx, &int_limbs, &coeffs_quantum)?;
+        let limb_res = gate.inner_product(ctx, &int_limbs, &coeffs_quantum)?;
         out_limbs.push(limb_res);
     }
 
@@ -66,10 +69,50 @@ pub fn crt<F: FieldExt>(
     let out_trunc =
         OverflowInteger::construct(out_limbs, max_limb_size, a[0].truncation.limb_bits, max_size);
     let a_native = a.iter().map(|x| Existing(&x.native)).collect();
-    let (_, _, out_native) = gate.inner_product(ctx, &a_native, &coeffs_quantum)?;
+    let out_native = gate.inner_product(ctx, &a_native, &coeffs_quantum)?;
     let out_val = a.iter().zip(coeffs.iter()).fold(Value::known(BigInt::from(0)), |acc, (x, y)| {
         acc.zip(x.value.as_ref()).zip(y.value()).map(|((a, x), y)| a + x * fe_to_bigint(y))
     });
 
     Ok(CRTInteger::construct(out_trunc, out_native, out_val))
 }
+
+/// Returns `sum_i coeffs[i] * a[i]` for constant integers `a`, e.g. selects a precomputed multiple of a fixed base by
+/// an indicator vector `coeffs`. Each limb and the native part is one inner product with the constants of `a` in the
+/// fixed columns, so nothing of `a` is assigned as advice.
+/// The bounds of the output are the maxima over `a`, so they only hold when `coeffs` is an indicator vector.
+pub fn fixed_crt<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &[FixedCRTInteger<F>],
+    coeffs: &Vec<AssignedValue<F>>,
+) -> Result<CRTInteger<F>, Error> {
+    let length = coeffs.len();
+    let k = a[0].truncation.limbs.len();
+    assert_eq!(length, a.len());
+
+    // the constants go second, where the gate can keep them in its fixed columns
+    let coeffs_quantum = coeffs.iter().map(|x| Existing(&x)).collect();
+    let mut out_limbs = Vec::with_capacity(k);
+    for idx in 0..k {
+        let int_limbs = a.iter().map(|x| Constant(x.truncation.limbs[idx])).collect();
+        let limb_res = gate.inner_product(ctx, &coeffs_quantum, &int_limbs)?;
+        out_limbs.push(limb_res);
+    }
+
+    let max_limb_size = a
+        .iter()
+        .fold(BigUint::from(0u64), |acc, x| cmp::max(acc, x.truncation.max_limb_size.clone()));
+    let max_size =
+        a.iter().fold(BigUint::from(0u64), |acc, x| cmp::max(acc, x.truncation.to_bigint()));
+
+    let out_trunc =
+        OverflowInteger::construct(out_limbs, max_limb_size, a[0].truncation.limb_bits, max_size);
+    let a_native = a.iter().map(|x| Constant(x.native)).collect();
+    let out_native = gate.inner_product(ctx, &coeffs_quantum, &a_native)?;
+    let out_val = a.iter().zip(coeffs.iter()).fold(Value::known(BigInt::from(0)), |acc, (x, y)| {
+        acc.zip(y.value()).map(|(a, y)| a + &x.value * fe_to_bigint(y))
+    });
+
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
@@ -1,7 +1,7 @@
 use halo2_base::{
     gates::{flex_gate::FlexGateConfig, GateInstructions},
     utils::{bigint_to_fe, biguint_to_fe, decompose_bigint, fe_to_bigint, fe_to_biguint},
-    AssignedValue, Context,
+    AssignedValue, Context, ContextId,
     QuantumCell::{Constant, Existing},
 };
 use halo2_proofs::{
@@ -11,23 +11,36 @@ use halo2_proofs::{
 };
 use num_bigint::{BigInt, BigUint};
 use num_traits::Zero;
-use std::{marker::PhantomData, rc::Rc};
+use std::marker::PhantomData;
 
 pub mod add_no_carry;
+pub mod barrett_mod;
 pub mod big_is_equal;
 pub mod big_is_zero;
 pub mod big_less_than;
+pub mod bitwise;
+pub mod bytes;
 pub mod carry_mod;
 pub mod check_carry_mod_to_zero;
 pub mod check_carry_to_zero;
+pub mod div_mod;
 pub mod inner_product;
+pub mod mod_inverse;
+pub mod mont_reduce;
 pub mod mul_no_carry;
 pub mod negative;
+#[cfg(feature = "proptest")]
+pub mod prop;
+pub mod resize;
 pub mod scalar_mul_and_add_no_carry;
 pub mod scalar_mul_no_carry;
 pub mod select;
+pub mod shift;
+pub mod signed;
+pub mod sqrt;
 pub mod sub;
 pub mod sub_no_carry;
+pub mod var_carry_mod;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum BigIntStrategy {
@@ -87,7 +100,7 @@ impl<F: FieldExt> OverflowInteger<F> {
         match chip.strategy {
             BigIntStrategy::Simple => {
                 // Constrain `out_native = sum_i out_assigned[i] * 2^{n*i}` in `F`
-                let (_, _, native) =
+                let native =
                     gate.inner_product(ctx, &limbs.iter().map(|a| Existing(a)).collect(), &pows)?;
                 Ok(native)
             }
@@ -95,6 +108,31 @@ impl<F: FieldExt> OverflowInteger<F> {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct SignedOverflowInteger<F: FieldExt> {
+    // keep track of a signed integer in sign-magnitude form as `(-1)^sign * magnitude`
+    // `sign` is a bit, `magnitude` is in proper representation
+    // zero is always represented with `sign = 0`, so that the representation is unique
+    pub sign: AssignedValue<F>,
+    pub magnitude: OverflowInteger<F>,
+}
+
+impl<F: FieldExt> SignedOverflowInteger<F> {
+    pub fn construct(sign: AssignedValue<F>, magnitude: OverflowInteger<F>) -> Self {
+        Self { sign, magnitude }
+    }
+
+    pub fn to_bigint(&self) -> Value<BigInt> {
+        self.magnitude.to_bigint().zip(self.sign.value()).map(|(mag, sign)| {
+            if *sign == F::zero() {
+                mag
+            } else {
+                -mag
+            }
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FixedOverflowInteger<F: FieldExt> {
     pub limbs: Vec<F>,
@@ -122,6 +160,25 @@ impl<F: FieldExt> FixedOverflowInteger<F> {
             .fold(BigUint::zero(), |acc, x| (acc << self.limb_bits) + fe_to_biguint(x))
     }
 
+    /// Returns the absolute value of the represented integer, interpreting limbs as signed
+    pub fn abs_value(&self) -> BigUint {
+        self.limbs
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |acc, x| (acc << self.limb_bits) + fe_to_bigint(x))
+            .magnitude()
+            .clone()
+    }
+
+    /// Returns the max absolute value of a limb, interpreting limbs as signed
+    pub fn max_abs_limb(&self) -> BigUint {
+        self.limbs
+            .iter()
+            .map(|x| fe_to_bigint(x).magnitude().clone())
+            .max()
+            .unwrap_or_else(BigUint::zero)
+    }
+
     pub fn assign(
         &self,
         gate: &impl GateInstructions<F>,
@@ -167,6 +224,16 @@ impl<F: FieldExt> CRTInteger<F> {
     ) -> Self {
         Self { truncation, native, value }
     }
+
+    /// Exposes the limbs of `self` as the public inputs at rows `row..row + k` of the instance column, where
+    /// `k` is the number of limbs, and returns the next row. The limbs are only unique if `self` is in
+    /// proper representation and reduced, e.g. after `enforce_less_than_p`.
+    pub fn expose_public(&self, ctx: &mut Context<'_, F>, row: usize) -> usize {
+        for (i, limb) in self.truncation.limbs.iter().enumerate() {
+            ctx.expose_public(limb, row + i);
+        }
+        row + self.truncation.limbs.len()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -236,7 +303,7 @@ impl<F: FieldExt> FixedCRTInteger<F> {
 pub struct BigIntConfig<F: FieldExt> {
     // everything is empty if strategy is `Simple` or `SimplePlus`
     strategy: BigIntStrategy,
-    context_id: Rc<String>,
+    context_id: ContextId,
     _marker: PhantomData<F>,
 }
 
@@ -247,12 +314,15 @@ impl<F: FieldExt> BigIntConfig<F> {
         _limb_bits: usize,
         _num_limbs: usize,
         _gate: &FlexGateConfig<F>,
-        context_id: String,
+        context_id: ContextId,
     ) -> Self {
         // let mut q_dot_constant = HashMap::new();
         match strategy {
             _ => {}
         }
-        Self { strategy, _marker: PhantomData, context_id: Rc::new(context_id) }
+        Self { strategy, _marker: PhantomData, context_id }
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,83 @@
+use super::{big_less_than, div_mod, mul_no_carry, BigIntConfig, CRTInteger, OverflowInteger};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{decompose_bigint_option, value_to_option},
+    Context,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// Input: `a` and `m` are `OverflowInteger`s in proper representation, where `m` is a witness modulus
+/// Output: `a_inv` in proper representation with `m.limbs.len()` limbs such that `a * a_inv = 1 (mod m)` and `0 <= a_inv < m`
+/// * constraints will fail if `gcd(a, m) != 1`
+/// * we constrain `a * a_inv = q * m + 1` using `div_mod`
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    m: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(a.limb_bits, m.limb_bits);
+    let n = m.limb_bits;
+    let k = m.limbs.len();
+
+    // this is a witness vector:
+    let inv_vec = if let (Some(a_big), Some(m_big)) =
+        (value_to_option(a.to_bigint()), value_to_option(m.to_bigint()))
+    {
+        let egcd = a_big.extended_gcd(&m_big);
+        // if no inverse exists the witness does not matter since `a * a_inv = 1 (mod m)` will fail
+        let inv = if egcd.gcd.is_one() { egcd.x.mod_floor(&m_big) } else { BigInt::zero() };
+        decompose_bigint_option::<F>(&Value::known(inv), k, n)
+    } else {
+        vec![Value::unknown(); k]
+    };
+
+    let inv_assigned = range.gate().assign_region_smart(
+        ctx,
+        inv_vec.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    // range check limbs of `a_inv` are in [0, 2^n)
+    for limb in inv_assigned.iter() {
+        range.range_check(ctx, limb, n)?;
+    }
+    let a_inv = OverflowInteger::construct(
+        inv_assigned,
+        (BigUint::one() << n) - 1usize,
+        n,
+        (BigUint::one() << (n * k)) - 1usize,
+    );
+
+    // check that `a_inv < m`
+    let lt = big_less_than::assign(range, ctx, &a_inv, m)?;
+    range.gate().assert_is_const(ctx, &lt, F::one());
+
+    // check that `a * a_inv (mod m) == 1`
+    let prod = mul_no_carry::assign(range.gate(), ctx, a, &a_inv)?;
+    let (_, rem) = div_mod::assign(range, ctx, &prod, m)?;
+    for (i, limb) in rem.limbs.iter().enumerate() {
+        range.gate().assert_is_const(ctx, limb, if i == 0 { F::one() } else { F::zero() });
+    }
+
+    Ok(a_inv)
+}
+
+pub fn crt<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    m: &CRTInteger<F>,
+) -> Result<CRTInteger<F>, Error> {
+    let out_trunc = assign(range, ctx, &a.truncation, &m.truncation)?;
+    let out_native =
+        OverflowInteger::evaluate(range.gate(), chip, ctx, &out_trunc.limbs, out_trunc.limb_bits)?;
+    let out_val = out_trunc.to_bigint();
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
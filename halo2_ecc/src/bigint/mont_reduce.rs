@@ -0,0 +1,111 @@
+use super::{
+    add_no_carry, mul_no_carry,
+    shift::{assign_bits, check_zero, shift_no_carry},
+    sub_no_carry, BigIntConfig, CRTInteger, FixedOverflowInteger, OverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::value_to_option,
+    Context,
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+/// Input: `a` is an `OverflowInteger` with "signed" limbs that represents its value exactly, i.e., it is NOT truncated,
+/// and `modulus` is odd with exactly `num_limbs` limbs
+/// Output: `a * R^{-1} (mod modulus)` as a proper BigInt with `num_limbs` limbs, where `R = 2^{limb_bits * num_limbs}`
+///
+/// This is Montgomery reduction (REDC): dividing by `R` is just a shift by `num_limbs` limbs.
+/// We first add `offset = ceil(a.max_size / modulus) * modulus` so that `t = a + offset >= 0`.
+/// Then we witness `q` in `[0, R)` with `t + q * modulus = 0 (mod R)`, `out` in `[0, modulus)` and a small `s >= 0` and constrain
+/// `t + q * modulus = (out + s * modulus) * R`
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    modulus: &BigUint,
+    num_limbs: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    let n = a.limb_bits;
+    let k = num_limbs;
+    assert!(modulus.is_odd());
+    assert!(modulus.bits() as usize <= n * k);
+    assert!(modulus.bits() as usize > n * (k - 1));
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("mont_reduce length {}", a.limbs.len());
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    let r = BigUint::one() << (n * k);
+    let offset = (&a.max_size + modulus - 1usize) / modulus * modulus;
+    let t_max = &a.max_size + &offset;
+    // `s * modulus * R <= t + q * modulus < t_max + R * modulus`
+    let s_max = &t_max / (modulus * &r) + 1usize;
+    let s_bits = std::cmp::max(s_max.bits() as usize, 1);
+
+    // witness generation
+    let t_val = a.to_bigint().map(|a| a + BigInt::from(offset.clone()));
+    let (q_val, out_val, s_val) = if let Some(t) = value_to_option(t_val) {
+        let r = BigInt::from(r.clone());
+        let modulus = BigInt::from(modulus.clone());
+        // `-modulus^{-1} (mod R)`
+        let neg_inv = (-modulus.extended_gcd(&r).x).mod_floor(&r);
+        let q = (&t * neg_inv).mod_floor(&r);
+        let u = (t + &q * &modulus) / &r;
+        let (s, out) = u.div_rem(&modulus);
+        (Value::known(q), Value::known(out), Value::known(s))
+    } else {
+        (Value::unknown(), Value::unknown(), Value::unknown())
+    };
+
+    let q = assign_bits(range, ctx, &q_val, n * k, n)?;
+    let out = assign_bits(range, ctx, &out_val, modulus.bits() as usize, n)?;
+    let s = assign_bits(range, ctx, &s_val, s_bits, n)?;
+    let mod_assigned = FixedOverflowInteger::from_native(BigInt::from(modulus.clone()), k, n)
+        .assign(range.gate(), ctx)?;
+
+    // constrain `out < modulus`
+    let (_, underflow) = super::sub::assign(range, ctx, &out, &mod_assigned)?;
+    range.gate().assert_is_const(ctx, &underflow, F::one());
+
+    // constrain `a + offset + q * modulus - (out + s * modulus) * R = 0`
+    let lhs = if offset.is_zero() {
+        a.clone()
+    } else {
+        let offset_limbs = (offset.bits() as usize + n - 1) / n;
+        let offset_assigned =
+            FixedOverflowInteger::from_native(BigInt::from(offset), offset_limbs, n)
+                .assign(range.gate(), ctx)?;
+        add_no_carry::assign(range.gate(), ctx, a, &offset_assigned)?
+    };
+    let q_mod = mul_no_carry::assign(range.gate(), ctx, &q, &mod_assigned)?;
+    let lhs = add_no_carry::assign(range.gate(), ctx, &lhs, &q_mod)?;
+    let s_mod = mul_no_carry::assign(range.gate(), ctx, &s, &mod_assigned)?;
+    let rhs = add_no_carry::assign(range.gate(), ctx, &out, &s_mod)?;
+    let rhs = shift_no_carry(range.gate(), ctx, &rhs, n * k)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &lhs, &rhs)?;
+    check_zero(range, ctx, &check)?;
+
+    Ok(OverflowInteger::construct(out.limbs, out.max_limb_size, n, modulus - 1usize))
+}
+
+/// Input: `a` is a `CRTInteger` whose `truncation` represents its value exactly (it may have more than `num_limbs` limbs)
+/// Output: `a * R^{-1} (mod modulus)` as a `CRTInteger` with proper `truncation` of `num_limbs` limbs
+pub fn crt<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    modulus: &BigUint,
+    num_limbs: usize,
+) -> Result<CRTInteger<F>, Error> {
+    let out = assign(range, ctx, &a.truncation, modulus, num_limbs)?;
+    let out_native = OverflowInteger::evaluate(range.gate(), chip, ctx, &out.limbs, out.limb_bits)?;
+    let out_val = out.to_bigint();
+    Ok(CRTInteger::construct(out, out_native, out_val))
+}
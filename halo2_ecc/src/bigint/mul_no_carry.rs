@@ -1,13 +1,47 @@
-use super::{BigIntConfig, CRTInteger, OverflowInteger};
+use super::{BigIntConfig, CRTInteger, FixedCRTInteger, FixedOverflowInteger, OverflowInteger};
 use halo2_base::{
     gates::GateInstructions,
     utils::modulus as native_modulus,
-    Context,
+    AssignedValue, Context,
     QuantumCell::{self, Constant, Existing, Witness},
 };
 use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
 use num_bigint::BigUint;
 
+fn limb_values<F: FieldExt>(a: &OverflowInteger<F>) -> Vec<Value<F>> {
+    a.limbs.iter().map(|limb| limb.value().copied()).collect()
+}
+
+/// Returns the witness values of the running sums `sum_{j' <= j} a[j'] * b[i - j']` for each output limb `i < k_out` of `a * b`,
+/// where `j'` starts from `max(0, i + 1 - b.len())`
+/// These only depend on the witnesses and not on the order cells are assigned in, so with the `parallel-witness`
+/// feature they are computed in parallel across output limbs
+pub(crate) fn partial_products<F: FieldExt>(
+    a: &[Value<F>],
+    b: &[Value<F>],
+    k_out: usize,
+) -> Vec<Vec<Value<F>>> {
+    let limb = |i: usize| {
+        let startj = if i >= b.len() { i - b.len() + 1 } else { 0 };
+        let mut acc = Value::known(F::zero());
+        (startj..std::cmp::min(i + 1, a.len()))
+            .map(|j| {
+                acc = acc + a[j] * b[i - j];
+                acc
+            })
+            .collect()
+    };
+    #[cfg(feature = "parallel-witness")]
+    {
+        use rayon::prelude::*;
+        (0..k_out).into_par_iter().map(limb).collect()
+    }
+    #[cfg(not(feature = "parallel-witness"))]
+    {
+        (0..k_out).map(limb).collect()
+    }
+}
+
 pub fn assign<F: FieldExt>(
     gate: &impl GateInstructions<F>,
     ctx: &mut Context<'_, F>,
@@ -25,6 +59,7 @@ pub fn assign<F: FieldExt>(
             <= native_modulus::<F>() / 2u32
     );
     let mut out_limbs = Vec::with_capacity(k_out);
+    let prod_vals = partial_products(&limb_values(a), &limb_values(b), k_out);
 
     for i in 0..k_out {
         let out_cell = {
@@ -34,7 +69,6 @@ pub fn assign<F: FieldExt>(
             let mut enable_gates = Vec::new();
 
             let mut offset = 0;
-            let mut prod_val = Value::known(F::zero());
             for j in startj..=i {
                 if j >= k_a {
                     break;
@@ -43,11 +77,10 @@ pub fn assign<F: FieldExt>(
 
                 let a_cell = &a.limbs[j];
                 let b_cell = &b.limbs[i - j];
-                prod_val = prod_val + a_cell.value().copied() * b_cell.value();
 
                 prod_computation.push(Existing(a_cell));
                 prod_computation.push(Existing(b_cell));
-                prod_computation.push(Witness(prod_val));
+                prod_computation.push(Witness(prod_vals[i][j - startj]));
 
                 offset += 3;
             }
@@ -86,6 +119,7 @@ pub fn truncate<F: FieldExt>(
 
     assert!(BigUint::from(k) * &a.max_limb_size * &b.max_limb_size <= native_modulus::<F>() / 2u32);
     let mut out_limbs = Vec::with_capacity(k);
+    let prod_vals = partial_products(&limb_values(a), &limb_values(b), k);
 
     for i in 0..k {
         let out_cell = {
@@ -95,17 +129,15 @@ pub fn truncate<F: FieldExt>(
             let mut enable_gates = Vec::new();
 
             let mut offset = 0;
-            let mut prod_val = Value::known(F::zero());
             for j in 0..std::cmp::min(i + 1, k) {
                 enable_gates.push(offset);
 
                 let a_cell = &a.limbs[j];
                 let b_cell = &b.limbs[i - j];
-                prod_val = prod_val + a_cell.value().copied() * b_cell.value();
 
                 prod_computation.push(Existing(a_cell));
                 prod_computation.push(Existing(b_cell));
-                prod_computation.push(Witness(prod_val));
+                prod_computation.push(Witness(prod_vals[i][j]));
 
                 offset += 3;
             }
@@ -136,3 +168,98 @@ pub fn crt<F: FieldExt>(
 
     Ok(CRTInteger::construct(out_trunc, out_native, out_val))
 }
+
+// the first `k_out` limbs of `a * b`
+// each output limb is an inner product of limbs of `a` with constant limbs of `b`; zero limbs of `b` are skipped
+fn fixed_product<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &FixedOverflowInteger<F>,
+    k_out: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let k_a = a.limbs.len();
+    let k_b = b.limbs.len();
+    let mut out_limbs = Vec::with_capacity(k_out);
+    for i in 0..k_out {
+        let startj = if i >= k_b { i - k_b + 1 } else { 0 };
+        let (a_cells, b_cells): (Vec<_>, Vec<_>) = (startj..std::cmp::min(i + 1, k_a))
+            .filter(|&j| b.limbs[i - j] != F::zero())
+            .map(|j| (Existing(&a.limbs[j]), Constant(b.limbs[i - j])))
+            .unzip();
+        let out_cell = if a_cells.is_empty() {
+            gate.assign_region_smart(ctx, vec![Constant(F::zero())], vec![], vec![], vec![])?[0]
+                .clone()
+        } else {
+            gate.inner_product(ctx, &a_cells, &b_cells)?
+        };
+        out_limbs.push(out_cell);
+    }
+    Ok(out_limbs)
+}
+
+/// Same as `assign` where `b` is a constant: the limbs of `b` are never loaded as a witness integer
+pub fn assign_fixed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &FixedOverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    let k_a = a.limbs.len();
+    let k_b = b.limbs.len();
+    assert!(k_a > 0);
+    assert!(k_b > 0);
+    let max_limb_size =
+        BigUint::from(std::cmp::min(k_a, k_b)) * &a.max_limb_size * b.max_abs_limb();
+    assert!(max_limb_size <= native_modulus::<F>() / 2u32);
+    let out_limbs = fixed_product(gate, ctx, a, b, k_a + k_b - 1)?;
+    Ok(OverflowInteger::construct(
+        out_limbs,
+        max_limb_size,
+        a.limb_bits,
+        &a.max_size * b.abs_value(),
+    ))
+}
+
+/// Same as `truncate` where `b` is a constant
+pub fn truncate_fixed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    b: &FixedOverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(a.limb_bits, b.limb_bits);
+    let k = a.limbs.len();
+    assert!(k > 0);
+    assert_eq!(k, b.limbs.len());
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("mul_no_carry(truncate_fixed) length {}", k);
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    let max_limb_size = BigUint::from(k) * &a.max_limb_size * b.max_abs_limb();
+    assert!(max_limb_size <= native_modulus::<F>() / 2u32);
+    let out_limbs = fixed_product(gate, ctx, a, b, k)?;
+    Ok(OverflowInteger::construct(
+        out_limbs,
+        max_limb_size,
+        a.limb_bits,
+        &a.max_size * b.abs_value(),
+    ))
+}
+
+pub fn crt_fixed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    b: &FixedCRTInteger<F>,
+) -> Result<CRTInteger<F>, Error> {
+    let out_trunc = truncate_fixed(gate, ctx, &a.truncation, &b.truncation)?;
+    let out_native = gate.mul(ctx, &Existing(&a.native), &Constant(b.native))?;
+    let out_val = a.value.as_ref().map(|a| a * &b.value);
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
@@ -0,0 +1,158 @@
+// Property-based testing of the bigint and field chips with `proptest`
+//
+// `overflow_input` generates the limbs of an `OverflowInteger` with a random number of limbs, each signed and with
+// a random number of bits up to an overflow level, like the outputs of the `_no_carry` ops. `OverflowInput` loads
+// them as an `OverflowInteger` or `CRTInteger`, `assert_crt_eq` checks the output of a chip against num-bigint, and
+// `verify` runs the assignments under `MockProver`, so a `proptest!` test covers many configurations at once.
+
+use super::{BigIntConfig, CRTInteger, OverflowInteger};
+use halo2_base::{
+    gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+        GateInstructions,
+    },
+    utils::{bigint_to_fe, value_to_option},
+    Context,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, dev::MockProver, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use proptest::{collection::vec, prelude::*, test_runner::TestCaseError};
+use std::ops::RangeInclusive;
+
+/// The limbs of an `OverflowInteger`, each of absolute value less than `2^max_limb_bits`
+#[derive(Clone, Debug)]
+pub struct OverflowInput {
+    pub limbs: Vec<BigInt>,
+    pub limb_bits: usize,
+    pub max_limb_bits: usize,
+}
+
+impl OverflowInput {
+    pub fn value(&self) -> BigInt {
+        self.limbs.iter().rev().fold(BigInt::zero(), |acc, limb| (acc << self.limb_bits) + limb)
+    }
+
+    pub fn max_limb_size(&self) -> BigUint {
+        (BigUint::one() << self.max_limb_bits) - 1usize
+    }
+
+    /// The bound on the absolute value of any integer with the same limb count and limb sizes
+    pub fn max_size(&self) -> BigUint {
+        (0..self.limbs.len())
+            .fold(BigUint::zero(), |acc, i| acc + (self.max_limb_size() << (self.limb_bits * i)))
+    }
+
+    /// Assigns the limbs as witnesses, without range checks
+    pub fn load<F: FieldExt>(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let limbs = gate.assign_region_smart(
+            ctx,
+            self.limbs.iter().map(|limb| Witness(Value::known(bigint_to_fe(limb)))).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(OverflowInteger::construct(limbs, self.max_limb_size(), self.limb_bits, self.max_size()))
+    }
+
+    /// Assigns the limbs as witnesses, with the native value constrained to their evaluation
+    pub fn load_crt<F: FieldExt>(
+        &self,
+        gate: &impl GateInstructions<F>,
+        ctx: &mut Context<'_, F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        let truncation = self.load(gate, ctx)?;
+        let native = OverflowInteger::evaluate(
+            gate,
+            &BigIntConfig::default(),
+            ctx,
+            &truncation.limbs,
+            self.limb_bits,
+        )?;
+        Ok(CRTInteger::construct(truncation, native, Value::known(self.value())))
+    }
+}
+
+/// A uniformly random integer in `[0, 2^bits)`
+pub fn biguint(bits: usize) -> impl Strategy<Value = BigUint> {
+    vec(any::<u32>(), (bits + 31) / 32)
+        .prop_map(move |words| BigUint::new(words) % (BigUint::one() << bits))
+}
+
+/// A random integer with exactly `bits` bits, e.g. a modulus of a given size
+pub fn biguint_with_bits(bits: usize) -> impl Strategy<Value = BigUint> {
+    assert!(bits > 0);
+    biguint(bits - 1).prop_map(move |n| n + (BigUint::one() << (bits - 1)))
+}
+
+/// `num_limbs` limbs of absolute value less than `2^max_limb_bits`, which are negative at random if `signed`
+pub fn limbs(
+    num_limbs: usize,
+    max_limb_bits: usize,
+    signed: bool,
+) -> impl Strategy<Value = Vec<BigInt>> {
+    vec((biguint(max_limb_bits), any::<bool>()), num_limbs).prop_map(move |limbs| {
+        limbs
+            .into_iter()
+            .map(|(limb, negative)| {
+                let limb = BigInt::from(limb);
+                if signed && negative {
+                    -limb
+                } else {
+                    limb
+                }
+            })
+            .collect()
+    })
+}
+
+/// An `OverflowInput` with `limb_bits` bits per limb and a number of limbs and overflow level, the bits of the
+/// largest limb, drawn from the ranges. Limbs are proper if `max_limb_bits` is `limb_bits` and `signed` is false.
+pub fn overflow_input(
+    limb_bits: usize,
+    num_limbs: RangeInclusive<usize>,
+    max_limb_bits: RangeInclusive<usize>,
+    signed: bool,
+) -> impl Strategy<Value = OverflowInput> {
+    (num_limbs, max_limb_bits).prop_flat_map(move |(num_limbs, max_limb_bits)| {
+        limbs(num_limbs, max_limb_bits, signed).prop_map(move |limbs| OverflowInput {
+            limbs,
+            limb_bits,
+            max_limb_bits,
+        })
+    })
+}
+
+/// Asserts that the value of `a` is `expected`, and so are its limbs modulo `2^(limb_bits * num_limbs)` and its
+/// native value modulo the modulus of `F`
+pub fn assert_crt_eq<F: FieldExt>(a: &CRTInteger<F>, expected: &BigInt) {
+    assert_eq!(value_to_option(a.value.clone()).as_ref(), Some(expected));
+    let truncation_modulus = BigInt::one() << (a.truncation.limb_bits * a.truncation.limbs.len());
+    let truncation = value_to_option(a.truncation.to_bigint()).unwrap();
+    assert_eq!(truncation.mod_floor(&truncation_modulus), expected.mod_floor(&truncation_modulus));
+    assert_eq!(value_to_option(a.native.value().copied()), Some(bigint_to_fe::<F>(expected)));
+}
+
+/// Runs `synthesize` under `MockProver` in a `RangeCircuitBuilder` with the vertical gate, failing the test case
+/// if any constraint fails. `synthesize` may also assert on the values of its outputs, e.g. with `assert_crt_eq`.
+pub fn verify<F, Fun>(params: CircuitParams, synthesize: Fun) -> Result<(), TestCaseError>
+where
+    F: FieldExt,
+    Fun: Fn(&RangeConfig<F>, &mut Context<'_, F>) -> Result<(), Error> + Clone,
+{
+    let circuit = RangeCircuitBuilder::new(RangeStrategy::Vertical, params, synthesize);
+    let prover = MockProver::run(params.degree, &circuit, vec![])
+        .map_err(|e| TestCaseError::fail(format!("{:?}", e)))?;
+    prover.verify().map_err(|failures| TestCaseError::fail(format!("{:#?}", failures)))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,137 @@
+use super::*;
+use crate::{
+    bigint::{add_no_carry, carry_mod, mul_no_carry, sub_no_carry},
+    fields::{fp::FpConfig, FieldChip},
+};
+use halo2_base::utils::modulus;
+use halo2_proofs::halo2curves::bn256::{Fq, Fr};
+
+const PARAMS: CircuitParams = CircuitParams {
+    degree: 13,
+    num_advice: 4,
+    num_lookup_advice: 1,
+    num_fixed: 1,
+    lookup_bits: 12,
+};
+
+// `FpConfig` over the base field of BN254 needs more rows for the range checks of its outputs
+const FP_PARAMS: CircuitParams = CircuitParams {
+    degree: 15,
+    num_advice: 4,
+    num_lookup_advice: 1,
+    num_fixed: 1,
+    lookup_bits: 14,
+};
+
+// an element of the base field of BN254, as an integer in `[0, p)`
+fn fq() -> impl Strategy<Value = BigUint> {
+    biguint(256).prop_map(|a| a % modulus::<Fq>())
+}
+
+// a pair of inputs with the same limb count, as the `_no_carry` ops expect
+fn input_pair(
+    limb_bits: usize,
+    max_limb_bits: RangeInclusive<usize>,
+) -> impl Strategy<Value = (OverflowInput, OverflowInput)> {
+    overflow_input(limb_bits, 2..=5, max_limb_bits, true).prop_flat_map(move |a| {
+        let b = limbs(a.limbs.len(), a.max_limb_bits, true).prop_map(move |limbs| OverflowInput {
+            limbs,
+            limb_bits,
+            max_limb_bits: a.max_limb_bits,
+        });
+        (Just(a.clone()), b)
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(8))]
+
+    #[test]
+    fn prop_add_sub_no_carry((a, b) in input_pair(88, 88..=120)) {
+        verify(PARAMS, move |range: &RangeConfig<Fr>, ctx| {
+            let a_assigned = a.load_crt(range.gate(), ctx)?;
+            let b_assigned = b.load_crt(range.gate(), ctx)?;
+            let sum = add_no_carry::crt(range.gate(), ctx, &a_assigned, &b_assigned)?;
+            assert_crt_eq(&sum, &(a.value() + b.value()));
+            let diff = sub_no_carry::crt(range.gate(), ctx, &a_assigned, &b_assigned)?;
+            assert_crt_eq(&diff, &(a.value() - b.value()));
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn prop_mul_no_carry((a, b) in input_pair(64, 1..=64)) {
+        verify(PARAMS, move |range: &RangeConfig<Fr>, ctx| {
+            let a_assigned = a.load_crt(range.gate(), ctx)?;
+            let b_assigned = b.load_crt(range.gate(), ctx)?;
+            let prod = mul_no_carry::crt(
+                range.gate(),
+                &BigIntConfig::default(),
+                ctx,
+                &a_assigned,
+                &b_assigned,
+            )?;
+            assert_crt_eq(&prod, &(a.value() * b.value()));
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn prop_carry_mod(
+        (a, modulus) in overflow_input(88, 3..=4, 88..=178, true).prop_flat_map(|a| {
+            // `carry_mod::crt` expects the modulus to have more bits than all limbs but the last, and the quotient
+            // to need more limbs than that but fewer bits than all limbs, which for the 254 bit native modulus
+            // holds with moduli of 256 to 330 bits
+            let min_bits = std::cmp::max(88 * (a.limbs.len() - 1) + 1, 256);
+            let max_bits = std::cmp::min(88 * a.limbs.len(), 330);
+            (Just(a), (min_bits..=max_bits).prop_flat_map(biguint_with_bits))
+        })
+    ) {
+        verify(PARAMS, move |range: &RangeConfig<Fr>, ctx| {
+            let a_assigned = a.load_crt(range.gate(), ctx)?;
+            let out =
+                carry_mod::crt(range, &BigIntConfig::default(), ctx, &a_assigned, &modulus)?;
+            let expected = a.value().mod_floor(&BigInt::from(modulus.clone()));
+            assert_crt_eq(&out, &expected);
+            // the output is proper, so its limbs are those of the reduced value
+            assert_eq!(value_to_option(out.truncation.to_bigint()), Some(expected));
+            Ok(())
+        })?;
+    }
+
+    #[test]
+    fn prop_fp_chip_ops(a in fq(), b in fq()) {
+        prop_assume!(!b.is_zero());
+        verify(FP_PARAMS, move |range: &RangeConfig<Fr>, ctx| {
+            let chip = FpConfig::<Fr, Fq>::construct(range.clone(), 88, 3, modulus::<Fq>());
+            let p = BigInt::from(modulus::<Fq>());
+            let (a, b) = (BigInt::from(a.clone()), BigInt::from(b.clone()));
+            let a_assigned = chip.load_private(ctx, Value::known(a.clone()))?;
+            let b_assigned = chip.load_private(ctx, Value::known(b.clone()))?;
+
+            // the `_no_carry` outputs keep the exact value, and the carried ones are reduced mod `p`
+            let sum = chip.add_no_carry(ctx, &a_assigned, &b_assigned)?;
+            assert_crt_eq(&sum, &(&a + &b));
+            assert_crt_eq(&chip.carry_mod(ctx, &sum)?, &(&a + &b).mod_floor(&p));
+            let diff = chip.sub_no_carry(ctx, &a_assigned, &b_assigned)?;
+            assert_crt_eq(&diff, &(&a - &b));
+            assert_crt_eq(&chip.carry_mod(ctx, &diff)?, &(&a - &b).mod_floor(&p));
+            let prod = chip.mul_no_carry(ctx, &a_assigned, &b_assigned)?;
+            assert_crt_eq(&prod, &(&a * &b));
+            assert_crt_eq(&chip.mul(ctx, &a_assigned, &b_assigned)?, &(&a * &b).mod_floor(&p));
+            assert_crt_eq(&chip.negate(ctx, &a_assigned)?, &(-&a).mod_floor(&p));
+
+            // `a / b` is the witness `q` with `q * b = a (mod p)`
+            let quot = chip.divide(ctx, &a_assigned, &b_assigned)?;
+            let b_inv = b.modpow(&(&p - 2u64), &p);
+            assert_crt_eq(&quot, &(&a * &b_inv).mod_floor(&p));
+
+            let zero = chip.carry_mod(ctx, &chip.sub_no_carry(ctx, &a_assigned, &a_assigned)?)?;
+            let is_zero = chip.is_zero(ctx, &zero)?;
+            assert_eq!(value_to_option(is_zero.value().copied()), Some(Fr::from(1)));
+            let is_equal = chip.is_equal(ctx, &a_assigned, &b_assigned)?;
+            assert_eq!(value_to_option(is_equal.value().copied()), Some(Fr::from((a == b) as u64)));
+            Ok(())
+        })?;
+    }
+}
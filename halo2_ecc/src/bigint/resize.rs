@@ -0,0 +1,56 @@
+use super::{CRTInteger, OverflowInteger};
+use halo2_base::{gates::GateInstructions, Context, QuantumCell::Constant};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use num_traits::One;
+use std::cmp;
+
+/// Input: `a` is an `OverflowInteger` with `k` limbs
+/// Output: `a` with `new_k` limbs of the same `limb_bits`
+/// * if `new_k >= k`, we pad `a` with zero limbs
+/// * if `new_k < k`, we constrain the dropped limbs `a[new_k..k]` to be zero, so the value of `a` is unchanged
+pub fn assign<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    new_k: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    assert!(new_k > 0);
+    let k = a.limbs.len();
+    let n = a.limb_bits;
+
+    let mut out_limbs = a.limbs[..cmp::min(k, new_k)].to_vec();
+    if new_k > k {
+        let zeros = gate.assign_region_smart(
+            ctx,
+            vec![Constant(F::zero()); new_k - k],
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        out_limbs.extend(zeros);
+    } else {
+        for limb in a.limbs[new_k..].iter() {
+            gate.assert_is_const(ctx, limb, F::zero());
+        }
+    }
+
+    // if `a` is proper, the dropped limbs being zero also bounds the value by the remaining limbs
+    let max_size = if new_k < k && a.max_limb_size < (BigUint::one() << n) {
+        cmp::min(a.max_size.clone(), (BigUint::one() << (n * new_k)) - 1usize)
+    } else {
+        a.max_size.clone()
+    };
+    Ok(OverflowInteger::construct(out_limbs, a.max_limb_size.clone(), n, max_size))
+}
+
+/// Resizes `a.truncation` to `new_k` limbs; `a.native` and `a.value` are unchanged
+pub fn crt<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    new_k: usize,
+) -> Result<CRTInteger<F>, Error> {
+    let out_trunc = assign(gate, ctx, &a.truncation, new_k)?;
+    Ok(CRTInteger::construct(out_trunc, a.native.clone(), a.value.clone()))
+}
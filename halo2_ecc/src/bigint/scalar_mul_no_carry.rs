@@ -2,10 +2,11 @@ use super::{CRTInteger, OverflowInteger};
 use halo2_base::{
     gates::GateInstructions,
     utils::fe_to_bigint,
-    Context,
+    AssignedValue, Context,
     QuantumCell::{Constant, Existing},
 };
 use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::{BigInt, BigUint};
 use num_traits::Signed;
 
 pub fn assign<F: FieldExt>(
@@ -62,3 +63,92 @@ pub fn crt<F: FieldExt>(
         out_val,
     ))
 }
+
+// multiplies a single limb by a small signed constant `c`
+// `c = 1, -1, 2` avoid loading `c` as a constant cell
+fn mul_limb_small<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    limb: &AssignedValue<F>,
+    c: i128,
+) -> Result<AssignedValue<F>, Error> {
+    match c {
+        1 => Ok(limb.clone()),
+        -1 => gate.neg(ctx, &Existing(limb)),
+        2 => gate.add(ctx, &Existing(limb), &Existing(limb)),
+        _ => {
+            let c_fe = F::from_u128(c.unsigned_abs());
+            let c_fe = if c < 0 { -c_fe } else { c_fe };
+            gate.mul(ctx, &Existing(limb), &Constant(c_fe))
+        }
+    }
+}
+
+fn assign_small<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    c: i128,
+) -> Result<OverflowInteger<F>, Error> {
+    assert!(!a.limbs.is_empty());
+    let out_limbs =
+        a.limbs.iter().map(|limb| mul_limb_small(gate, ctx, limb, c)).collect::<Result<_, _>>()?;
+    let c_abs = BigUint::from(c.unsigned_abs());
+    Ok(OverflowInteger::construct(
+        out_limbs,
+        &a.max_limb_size * &c_abs,
+        a.limb_bits,
+        &a.max_size * &c_abs,
+    ))
+}
+
+fn crt_small<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    c: i128,
+) -> Result<CRTInteger<F>, Error> {
+    let out_trunc = assign_small(gate, ctx, &a.truncation, c)?;
+    let out_native = mul_limb_small(gate, ctx, &a.native, c)?;
+    let out_val = a.value.as_ref().map(|a| a * BigInt::from(c));
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
+
+/// Same as `assign` with `b = F::from(c)`, but `max_limb_size, max_size` are scaled by `c` directly
+/// and multiplication by `1, 2` does not use a constant cell
+pub fn assign_u64<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    c: u64,
+) -> Result<OverflowInteger<F>, Error> {
+    assign_small(gate, ctx, a, c as i128)
+}
+
+/// Same as `assign` with `b = c` as a signed field element, for small `c` such as `-1, -2, 3`
+pub fn assign_small_signed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    c: i64,
+) -> Result<OverflowInteger<F>, Error> {
+    assign_small(gate, ctx, a, c as i128)
+}
+
+pub fn crt_u64<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    c: u64,
+) -> Result<CRTInteger<F>, Error> {
+    crt_small(gate, ctx, a, c as i128)
+}
+
+pub fn crt_small_signed<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    c: i64,
+) -> Result<CRTInteger<F>, Error> {
+    crt_small(gate, ctx, a, c as i128)
+}
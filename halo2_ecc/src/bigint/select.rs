@@ -1,6 +1,9 @@
-use super::{CRTInteger, OverflowInteger};
+use super::{inner_product, CRTInteger, OverflowInteger};
 use halo2_base::{
-    gates::GateInstructions, utils::fe_to_bigint, AssignedValue, Context, QuantumCell::Existing,
+    gates::{GateInstructions, RangeInstructions},
+    utils::fe_to_bigint,
+    AssignedValue, Context,
+    QuantumCell::Existing,
 };
 use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
 use num_bigint::BigInt;
@@ -61,3 +64,60 @@ pub fn crt<F: FieldExt>(
     });
     Ok(CRTInteger::construct(out_trunc, out_native, out_val))
 }
+
+/// Input: `indicator` is a vector of bits with exactly one 1, constrained elsewhere (e.g. by `idx_to_indicator`)
+/// Output: the integer `a[i]` where `indicator[i] = 1`
+pub fn select_by_indicator<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &Vec<OverflowInteger<F>>,
+    indicator: &Vec<AssignedValue<F>>,
+) -> Result<OverflowInteger<F>, Error> {
+    assert!(a.iter().all(|x| x.limbs.len() == a[0].limbs.len()));
+    inner_product::assign(gate, ctx, a, indicator)
+}
+
+pub fn crt_select_by_indicator<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &Vec<CRTInteger<F>>,
+    indicator: &Vec<AssignedValue<F>>,
+) -> Result<CRTInteger<F>, Error> {
+    assert!(a.iter().all(|x| x.truncation.limbs.len() == a[0].truncation.limbs.len()));
+    inner_product::crt(gate, ctx, a, indicator)
+}
+
+// both indicators constrain `idx < len`; the one from the lookup is cheaper from about 16 entries on
+fn indicator_from_idx<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    idx: &AssignedValue<F>,
+    len: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    if len < 16 {
+        range.idx_to_indicator_by_bits(ctx, idx, len)
+    } else {
+        range.idx_to_indicator_by_lookup(ctx, idx, len)
+    }
+}
+
+/// Output: `a[idx]`; constrains `idx` to be in `[0, a.len())`
+pub fn select_from_idx<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &Vec<OverflowInteger<F>>,
+    idx: &AssignedValue<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    let indicator = indicator_from_idx(range, ctx, idx, a.len())?;
+    select_by_indicator(range.gate(), ctx, a, &indicator)
+}
+
+pub fn crt_select_from_idx<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &Vec<CRTInteger<F>>,
+    idx: &AssignedValue<F>,
+) -> Result<CRTInteger<F>, Error> {
+    let indicator = indicator_from_idx(range, ctx, idx, a.len())?;
+    crt_select_by_indicator(range.gate(), ctx, a, &indicator)
+}
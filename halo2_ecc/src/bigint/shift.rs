@@ -0,0 +1,167 @@
+use super::{
+    add_no_carry, check_carry_to_zero, scalar_mul_no_carry, sub_no_carry, OverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{biguint_to_fe, decompose_bigint_option},
+    Context,
+    QuantumCell::{Constant, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_traits::One;
+
+// assigns `value` as a proper BigInt of `num_bits` bits with limbs of `limb_bits` bits
+// all limbs are range checked to be in [0, 2^limb_bits), except the last limb which is range checked to the remaining bits
+pub(crate) fn assign_bits<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    value: &Value<BigInt>,
+    num_bits: usize,
+    limb_bits: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    assert!(num_bits > 0);
+    let n = limb_bits;
+    let k = (num_bits + n - 1) / n;
+    let vals = decompose_bigint_option::<F>(value, k, n);
+    let assigned = range.gate().assign_region_smart(
+        ctx,
+        vals.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    for (i, limb) in assigned.iter().enumerate() {
+        let bits = if i == k - 1 { num_bits - n * (k - 1) } else { n };
+        range.range_check(ctx, limb, bits)?;
+    }
+    Ok(OverflowInteger::construct(
+        assigned,
+        (BigUint::one() << n) - 1usize,
+        n,
+        (BigUint::one() << num_bits) - 1usize,
+    ))
+}
+
+// returns `a * 2^shift` as an OverflowInteger with the same limb_bits, without any range checks
+pub(crate) fn shift_no_carry<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    shift: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    let n = a.limb_bits;
+    let (num_zero_limbs, rem_bits) = (shift / n, shift % n);
+    let scaled = if rem_bits == 0 {
+        a.clone()
+    } else {
+        scalar_mul_no_carry::assign(gate, ctx, a, biguint_to_fe(&(BigUint::one() << rem_bits)))?
+    };
+    let mut limbs = Vec::with_capacity(num_zero_limbs + scaled.limbs.len());
+    if num_zero_limbs > 0 {
+        let zeros = gate.assign_region_smart(
+            ctx,
+            vec![Constant(F::zero()); num_zero_limbs],
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        limbs.extend(zeros);
+    }
+    limbs.extend(scaled.limbs);
+    Ok(OverflowInteger::construct(
+        limbs,
+        scaled.max_limb_size,
+        n,
+        scaled.max_size << (num_zero_limbs * n),
+    ))
+}
+
+// checks that `a` carries to exactly zero
+pub(crate) fn check_zero<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+) -> Result<(), Error> {
+    if a.limbs.len() == 1 {
+        range.gate().assert_is_const(ctx, &a.limbs[0], F::zero());
+        Ok(())
+    } else {
+        check_carry_to_zero::assign(range, ctx, a)
+    }
+}
+
+/// Input: `a` is an `OverflowInteger` in proper representation, i.e., all limbs in [0, 2^limb_bits)
+/// Output: the bits of `a` in the range `[lo, hi)`, i.e., `floor(a / 2^lo) mod 2^{hi - lo}`, in proper representation
+/// We witness `low = a mod 2^lo`, `out` and `high = floor(a / 2^hi)` with range checks and constrain
+/// `a = low + out * 2^lo + high * 2^hi`
+pub fn extract_bits<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    lo: usize,
+    hi: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    assert!(lo < hi);
+    let n = a.limb_bits;
+    let total_bits = a.max_size.bits() as usize;
+    let high_bits = total_bits.saturating_sub(hi);
+
+    let a_val = a.to_bigint();
+    let mask = |bits: usize| (BigInt::one() << bits) - 1usize;
+    let out_val = a_val.as_ref().map(|a| (a >> lo) & mask(hi - lo));
+
+    let out = assign_bits(range, ctx, &out_val, hi - lo, n)?;
+    let shifted_out = shift_no_carry(range.gate(), ctx, &out, lo)?;
+    let mut check = sub_no_carry::assign(range.gate(), ctx, &shifted_out, a)?;
+    if lo > 0 {
+        let low_val = a_val.as_ref().map(|a| a & mask(lo));
+        let low = assign_bits(range, ctx, &low_val, lo, n)?;
+        check = add_no_carry::assign(range.gate(), ctx, &check, &low)?;
+    }
+    if high_bits > 0 {
+        let high_val = a_val.as_ref().map(|a| a >> hi);
+        let high = assign_bits(range, ctx, &high_val, high_bits, n)?;
+        let shifted_high = shift_no_carry(range.gate(), ctx, &high, hi)?;
+        check = add_no_carry::assign(range.gate(), ctx, &check, &shifted_high)?;
+    }
+    check_zero(range, ctx, &check)?;
+
+    Ok(out)
+}
+
+/// Input: `a` is an `OverflowInteger` in proper representation with `k` limbs
+/// Output: `floor(a / 2^shift)` in proper representation
+pub fn shr_const<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    shift: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    let total_bits = std::cmp::max(a.max_size.bits() as usize, a.limb_bits * a.limbs.len());
+    assert!(shift < total_bits);
+    extract_bits(range, ctx, a, shift, total_bits)
+}
+
+/// Input: `a` is an `OverflowInteger` in proper representation
+/// Output: `a * 2^shift` in proper representation, re-limbed so that no bits are lost
+/// * to get fixed-width semantics (e.g., EVM `SHL`), follow with `extract_bits(out, 0, width)`
+pub fn shl_const<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    shift: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    let n = a.limb_bits;
+    let out_bits = a.max_size.bits() as usize + shift;
+    if out_bits == shift {
+        // `a.max_size = 0` so `a` is identically zero
+        return Ok(a.clone());
+    }
+    let out_val = a.to_bigint().map(|a| a << shift);
+    let out = assign_bits(range, ctx, &out_val, out_bits, n)?;
+    let shifted_a = shift_no_carry(range.gate(), ctx, a, shift)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &out, &shifted_a)?;
+    check_zero(range, ctx, &check)?;
+    Ok(out)
+}
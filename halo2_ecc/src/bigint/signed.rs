@@ -0,0 +1,175 @@
+use super::{
+    add_no_carry, big_is_zero, mul_no_carry,
+    shift::{assign_bits, check_zero},
+    sub_no_carry, OverflowInteger, SignedOverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::BigInt;
+use num_traits::Signed;
+
+// witnesses the sign bit of `value` and constrains it to be zero when `magnitude` is zero
+fn assign_sign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    value: &Value<BigInt>,
+    magnitude: &OverflowInteger<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let sign_val = value.as_ref().map(|v| if v.is_negative() { F::one() } else { F::zero() });
+    let sign =
+        range.gate().assign_region_smart(ctx, vec![Witness(sign_val)], vec![], vec![], vec![])?;
+    let sign = sign[0].clone();
+    range.range_check(ctx, &sign, 1)?;
+    let mag_is_zero = big_is_zero::assign(range, ctx, magnitude)?;
+    let sign_and_zero = range.gate().mul(ctx, &Existing(&sign), &Existing(&mag_is_zero))?;
+    range.gate().assert_is_const(ctx, &sign_and_zero, F::zero());
+    Ok(sign)
+}
+
+/// Input: a signed integer `value` with `abs(value) < 2^num_bits`
+/// Output: `value` as a `SignedOverflowInteger` with limbs of `limb_bits` bits
+/// The magnitude is range checked to `num_bits` bits and the sign is constrained to be a bit
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    value: Value<BigInt>,
+    num_bits: usize,
+    limb_bits: usize,
+) -> Result<SignedOverflowInteger<F>, Error> {
+    let magnitude = assign_bits(range, ctx, &value.as_ref().map(|v| v.abs()), num_bits, limb_bits)?;
+    let sign = assign_sign(range, ctx, &value, &magnitude)?;
+    Ok(SignedOverflowInteger::construct(sign, magnitude))
+}
+
+/// Output: the limbs of `(-1)^sign * magnitude` as an `OverflowInteger` with signed limbs, without carrying
+pub fn to_overflow_integer<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    // sgn = 1 - 2 * sign
+    let sgn = gate.mul_add(ctx, &Existing(&a.sign), &Constant(-F::from(2)), &Constant(F::one()))?;
+    let mut out_limbs = Vec::with_capacity(a.magnitude.limbs.len());
+    for limb in a.magnitude.limbs.iter() {
+        out_limbs.push(gate.mul(ctx, &Existing(limb), &Existing(&sgn))?);
+    }
+    Ok(OverflowInteger::construct(
+        out_limbs,
+        a.magnitude.max_limb_size.clone(),
+        a.magnitude.limb_bits,
+        a.magnitude.max_size.clone(),
+    ))
+}
+
+pub fn neg<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+) -> Result<SignedOverflowInteger<F>, Error> {
+    // -0 = 0 must keep `sign = 0`
+    let mag_is_zero = big_is_zero::assign(range, ctx, &a.magnitude)?;
+    let sign = range.gate().or(ctx, &Existing(&a.sign), &Existing(&mag_is_zero))?;
+    let sign = range.gate().not(ctx, &Existing(&sign))?;
+    Ok(SignedOverflowInteger::construct(sign, a.magnitude.clone()))
+}
+
+// witnesses `out = a + sgn * b` and constrains `out - a - sgn * b` to carry to zero
+fn add_with_sign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+    b: &SignedOverflowInteger<F>,
+    is_sub: bool,
+) -> Result<SignedOverflowInteger<F>, Error> {
+    let n = a.magnitude.limb_bits;
+    assert_eq!(n, b.magnitude.limb_bits);
+    let max_size = &a.magnitude.max_size + &b.magnitude.max_size;
+    let num_bits = std::cmp::max(max_size.bits() as usize, 1);
+
+    let out_val = a.to_bigint().zip(b.to_bigint()).map(|(a, b)| if is_sub { a - b } else { a + b });
+    let out = assign(range, ctx, out_val, num_bits, n)?;
+
+    let a_limbs = to_overflow_integer(range.gate(), ctx, a)?;
+    let b_limbs = to_overflow_integer(range.gate(), ctx, b)?;
+    let out_limbs = to_overflow_integer(range.gate(), ctx, &out)?;
+    let expected = if is_sub {
+        sub_no_carry::assign(range.gate(), ctx, &a_limbs, &b_limbs)?
+    } else {
+        add_no_carry::assign(range.gate(), ctx, &a_limbs, &b_limbs)?
+    };
+    let check = sub_no_carry::assign(range.gate(), ctx, &out_limbs, &expected)?;
+    check_zero(range, ctx, &check)?;
+    Ok(out)
+}
+
+/// Input: `a, b` are `SignedOverflowInteger`s with the same `limb_bits`
+/// Output: `a + b`, whose magnitude has enough limbs to hold `a.max_size + b.max_size`
+pub fn add<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+    b: &SignedOverflowInteger<F>,
+) -> Result<SignedOverflowInteger<F>, Error> {
+    add_with_sign(range, ctx, a, b, false)
+}
+
+/// Input: `a, b` are `SignedOverflowInteger`s with the same `limb_bits`
+/// Output: `a - b`, whose magnitude has enough limbs to hold `a.max_size + b.max_size`
+pub fn sub<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+    b: &SignedOverflowInteger<F>,
+) -> Result<SignedOverflowInteger<F>, Error> {
+    add_with_sign(range, ctx, a, b, true)
+}
+
+/// Input: `a, b` are `SignedOverflowInteger`s with the same `limb_bits`
+/// Output: `a * b`, whose magnitude has enough limbs to hold `a.max_size * b.max_size`
+pub fn mul<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+    b: &SignedOverflowInteger<F>,
+) -> Result<SignedOverflowInteger<F>, Error> {
+    let n = a.magnitude.limb_bits;
+    assert_eq!(n, b.magnitude.limb_bits);
+    let max_size = &a.magnitude.max_size * &b.magnitude.max_size;
+    let num_bits = std::cmp::max(max_size.bits() as usize, 1);
+
+    let out_mag_val = a.magnitude.to_bigint().zip(b.magnitude.to_bigint()).map(|(a, b)| a * b);
+    let out_mag = assign_bits(range, ctx, &out_mag_val, num_bits, n)?;
+    let prod = mul_no_carry::assign(range.gate(), ctx, &a.magnitude, &b.magnitude)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &out_mag, &prod)?;
+    check_zero(range, ctx, &check)?;
+
+    // sign = (a.sign XOR b.sign) AND (out != 0)
+    let sign_prod = range.gate().mul(ctx, &Existing(&a.sign), &Existing(&b.sign))?;
+    let sign_sum = range.gate().add(ctx, &Existing(&a.sign), &Existing(&b.sign))?;
+    let sign_xor = range.gate().mul_add(
+        ctx,
+        &Existing(&sign_prod),
+        &Constant(-F::from(2)),
+        &Existing(&sign_sum),
+    )?;
+    let out_is_zero = big_is_zero::assign(range, ctx, &out_mag)?;
+    let out_is_nonzero = range.gate().not(ctx, &Existing(&out_is_zero))?;
+    let sign = range.gate().and(ctx, &Existing(&sign_xor), &Existing(&out_is_nonzero))?;
+    Ok(SignedOverflowInteger::construct(sign, out_mag))
+}
+
+/// Output: whether `a < b`
+/// Since zero has a unique representation, `a < b` iff `a - b` has sign bit 1
+pub fn is_less_than<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &SignedOverflowInteger<F>,
+    b: &SignedOverflowInteger<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let diff = sub(range, ctx, a, b)?;
+    Ok(diff.sign)
+}
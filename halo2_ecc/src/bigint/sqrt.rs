@@ -0,0 +1,116 @@
+use super::{
+    add_no_carry, check_carry_to_zero, mul_no_carry, scalar_mul_no_carry, sub_no_carry,
+    BigIntConfig, CRTInteger, OverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{decompose_bigint_option, value_to_option},
+    Context,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Signed, Zero};
+
+// assigns `vals` as witnesses and range checks each limb to be in [0, 2^limb_bits)
+fn assign_proper<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    vals: &Vec<Value<F>>,
+    limb_bits: usize,
+) -> Result<OverflowInteger<F>, Error> {
+    let k = vals.len();
+    let assigned = range.gate().assign_region_smart(
+        ctx,
+        vals.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    for limb in assigned.iter() {
+        range.range_check(ctx, limb, limb_bits)?;
+    }
+    Ok(OverflowInteger::construct(
+        assigned,
+        (BigUint::one() << limb_bits) - 1usize,
+        limb_bits,
+        (BigUint::one() << (limb_bits * k)) - 1usize,
+    ))
+}
+
+/// Input: `a` is an `OverflowInteger` with non-negative value, possibly with overflow limbs
+/// Output: `s = floor(sqrt(a))` in proper representation
+/// We constrain `s^2 <= a < (s + 1)^2` by constraining
+/// * `a = s^2 + r`
+/// * `2 * s = r + d`
+/// where `r, d` are range checked to be non-negative, so `0 <= r <= 2 * s`
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    let n = a.limb_bits;
+    // s < 2^{ceil(a.max_size.bits() / 2)}
+    let s_bits = (a.max_size.bits() as usize + 1) / 2;
+    let k_s = std::cmp::max((s_bits + n - 1) / n, 1);
+    // r, d <= 2 * s < 2^{n * k_s + 1}
+    let k_r = k_s + 1;
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("sqrt length {}", a.limbs.len());
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    // these are witness vectors:
+    let (s_vec, r_vec, d_vec) = if let Some(a_big) = value_to_option(a.to_bigint()) {
+        // if `a < 0` the witness does not matter since `a = s^2 + r` will fail
+        let (s, r) = if a_big.is_negative() {
+            (BigInt::zero(), BigInt::zero())
+        } else {
+            let s = a_big.sqrt();
+            let r = &a_big - &s * &s;
+            (s, r)
+        };
+        let d = BigInt::from(2u32) * &s - &r;
+        (
+            decompose_bigint_option::<F>(&Value::known(s), k_s, n),
+            decompose_bigint_option::<F>(&Value::known(r), k_r, n),
+            decompose_bigint_option::<F>(&Value::known(d), k_r, n),
+        )
+    } else {
+        (vec![Value::unknown(); k_s], vec![Value::unknown(); k_r], vec![Value::unknown(); k_r])
+    };
+
+    let s = assign_proper(range, ctx, &s_vec, n)?;
+    let r = assign_proper(range, ctx, &r_vec, n)?;
+    let d = assign_proper(range, ctx, &d_vec, n)?;
+
+    // check that `s^2 + r - a == 0` after carry
+    let s_sq = mul_no_carry::assign(range.gate(), ctx, &s, &s)?;
+    let s_sq_plus_r = add_no_carry::assign(range.gate(), ctx, &s_sq, &r)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &s_sq_plus_r, a)?;
+    check_carry_to_zero::assign(range, ctx, &check)?;
+
+    // check that `2 * s - r - d == 0` after carry
+    let two_s = scalar_mul_no_carry::assign(range.gate(), ctx, &s, F::from(2))?;
+    let two_s_minus_r = sub_no_carry::assign(range.gate(), ctx, &two_s, &r)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &two_s_minus_r, &d)?;
+    check_carry_to_zero::assign(range, ctx, &check)?;
+
+    Ok(s)
+}
+
+pub fn crt<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+) -> Result<CRTInteger<F>, Error> {
+    let out_trunc = assign(range, ctx, &a.truncation)?;
+    let out_native =
+        OverflowInteger::evaluate(range.gate(), chip, ctx, &out_trunc.limbs, out_trunc.limb_bits)?;
+    let out_val = out_trunc.to_bigint();
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
@@ -0,0 +1,463 @@
+use super::*;
+use ff::Field;
+use halo2_base::{
+    gates::{
+        bitwise::ByteLookupConfig,
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+        RangeInstructions,
+    },
+    utils::{decompose_bigint, value_to_option},
+    ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::{MockProver, VerifyFailure},
+    halo2curves::bn256::Fr,
+    plonk::Circuit,
+};
+use num_bigint::RandBigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed};
+use rand::rngs::OsRng;
+
+const PARAMS: CircuitParams = CircuitParams {
+    degree: 13,
+    num_advice: 4,
+    num_lookup_advice: 1,
+    num_fixed: 1,
+    lookup_bits: 12,
+};
+const LIMB_BITS: usize = 64;
+
+// assigns `limbs` as an `OverflowInteger` with limbs of `LIMB_BITS` bits, without range checks
+fn load_limbs(
+    gate: &impl GateInstructions<Fr>,
+    ctx: &mut Context<'_, Fr>,
+    limbs: &[BigInt],
+) -> Result<OverflowInteger<Fr>, Error> {
+    let assigned = gate.assign_region_smart(
+        ctx,
+        limbs.iter().map(|limb| Witness(Value::known(bigint_to_fe(limb)))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    Ok(OverflowInteger::construct(
+        assigned,
+        (BigUint::one() << LIMB_BITS) - 1usize,
+        LIMB_BITS,
+        (BigUint::one() << (LIMB_BITS * limbs.len())) - 1usize,
+    ))
+}
+
+// assigns `value` with `num_limbs` limbs, which are all negative if `value` is
+fn load(
+    gate: &impl GateInstructions<Fr>,
+    ctx: &mut Context<'_, Fr>,
+    value: &BigInt,
+    num_limbs: usize,
+) -> Result<OverflowInteger<Fr>, Error> {
+    let limbs = decompose_bigint::<Fr>(value, num_limbs, LIMB_BITS);
+    load_limbs(gate, ctx, &limbs.iter().map(fe_to_bigint).collect::<Vec<_>>())
+}
+
+fn load_crt(
+    gate: &impl GateInstructions<Fr>,
+    ctx: &mut Context<'_, Fr>,
+    value: &BigInt,
+    num_limbs: usize,
+) -> Result<CRTInteger<Fr>, Error> {
+    let truncation = load(gate, ctx, value, num_limbs)?;
+    let native = OverflowInteger::evaluate(
+        gate,
+        &BigIntConfig::default(),
+        ctx,
+        &truncation.limbs,
+        LIMB_BITS,
+    )?;
+    Ok(CRTInteger::construct(truncation, native, Value::known(value.clone())))
+}
+
+fn value(a: &OverflowInteger<Fr>) -> BigInt {
+    value_to_option(a.to_bigint()).unwrap()
+}
+
+fn verify<Fun>(synthesize: Fun) -> Result<(), Vec<VerifyFailure>>
+where
+    Fun: Fn(&RangeConfig<Fr>, &mut Context<'_, Fr>) -> Result<(), Error> + Clone,
+{
+    let circuit = RangeCircuitBuilder::new(RangeStrategy::Vertical, PARAMS, synthesize);
+    MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap().verify()
+}
+
+fn check_div_mod(a: BigInt, b: BigInt) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 3)?;
+        let b_int = load(range.gate(), ctx, &b, 2)?;
+        let (q, r) = div_mod::assign(range, ctx, &a_int, &b_int)?;
+        if !b.is_zero() {
+            assert_eq!((value(&q), value(&r)), a.div_mod_floor(&b));
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn test_div_mod() {
+    let a = BigInt::from(OsRng.gen_biguint(190));
+    check_div_mod(a.clone(), BigInt::from(OsRng.gen_biguint(100))).unwrap();
+    check_div_mod(a.clone(), BigInt::one()).unwrap();
+    check_div_mod(BigInt::from(5u64), a.clone() >> 64).unwrap();
+
+    // there is no `0 <= r < 0`
+    assert!(check_div_mod(a, BigInt::zero()).is_err());
+}
+
+fn check_mod_inverse(a: BigInt, m: BigInt) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 2)?;
+        let m_int = load(range.gate(), ctx, &m, 2)?;
+        let a_inv = mod_inverse::assign(range, ctx, &a_int, &m_int)?;
+        if a.gcd(&m).is_one() {
+            assert!((&a * value(&a_inv)).mod_floor(&m).is_one());
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn test_mod_inverse() {
+    // 2^127 - 1 is prime
+    let m = (BigInt::one() << 127) - 1;
+    check_mod_inverse(BigInt::from(OsRng.gen_biguint(126)), m.clone()).unwrap();
+    check_mod_inverse(BigInt::one(), m.clone()).unwrap();
+
+    // `a` is not invertible
+    assert!(check_mod_inverse(BigInt::from(6u64), BigInt::from(9u64)).is_err());
+    assert!(check_mod_inverse(BigInt::zero(), m).is_err());
+}
+
+fn check_sqrt(a: BigInt) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 3)?;
+        let s = sqrt::assign(range, ctx, &a_int)?;
+        if !a.is_negative() {
+            assert_eq!(value(&s), a.sqrt());
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn test_sqrt() {
+    let s = BigInt::from(OsRng.gen_biguint(90));
+    check_sqrt(&s * &s).unwrap();
+    check_sqrt(&s * &s - 1).unwrap();
+    check_sqrt(BigInt::from(OsRng.gen_biguint(190))).unwrap();
+    check_sqrt(BigInt::zero()).unwrap();
+
+    // a negative integer has no square root
+    assert!(check_sqrt(BigInt::from(-5)).is_err());
+}
+
+fn check_var_carry_mod(a: BigInt, m: BigInt) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 3)?;
+        let m_int = load(range.gate(), ctx, &m, 2)?;
+        let out = var_carry_mod::assign(range, ctx, &a_int, &m_int)?;
+        if m.is_positive() {
+            assert_eq!(value(&out), a.mod_floor(&m));
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn test_var_carry_mod() {
+    let a = BigInt::from(OsRng.gen_biguint(150));
+    let m = BigInt::from(OsRng.gen_biguint(100));
+    check_var_carry_mod(a.clone(), m.clone()).unwrap();
+    // the limbs of `a` are negative
+    check_var_carry_mod(-a.clone(), m).unwrap();
+
+    // there is no `0 <= out < 0`
+    assert!(check_var_carry_mod(a, BigInt::zero()).is_err());
+}
+
+fn check_bytes(a: BigInt, num_bytes: usize) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_crt = load_crt(range.gate(), ctx, &a, 3)?;
+        let a_bytes = bytes::to_bytes_le(range, ctx, &a_crt, num_bytes)?;
+        if a.bits() as usize <= 8 * num_bytes {
+            let mut expected = a.to_biguint().unwrap().to_bytes_le();
+            expected.resize(num_bytes, 0);
+            let byte_values: Vec<_> =
+                a_bytes.iter().map(|x| value_to_option(x.value().copied()).unwrap()).collect();
+            assert_eq!(
+                byte_values,
+                expected.into_iter().map(|b| Fr::from(b as u64)).collect::<Vec<_>>()
+            );
+        }
+
+        let a_again =
+            bytes::from_bytes_le(range, &BigIntConfig::default(), ctx, &a_bytes, LIMB_BITS, 3)?;
+        for (x, y) in a_again.truncation.limbs.iter().zip(a_crt.truncation.limbs.iter()) {
+            range.gate().assert_equal(ctx, &Existing(x), &Existing(y))?;
+        }
+        range.gate().assert_equal(ctx, &Existing(&a_again.native), &Existing(&a_crt.native))?;
+        Ok(())
+    })
+}
+
+#[test]
+fn test_bytes() {
+    let a = BigInt::from(OsRng.gen_biguint(160));
+    check_bytes(a.clone(), 20).unwrap();
+    check_bytes(a, 24).unwrap();
+    check_bytes(BigInt::zero(), 1).unwrap();
+
+    // the byte at index 20 is not zero
+    assert!(check_bytes(BigInt::one() << 160, 20).is_err());
+}
+
+#[test]
+fn test_shift() {
+    let a = BigInt::from(OsRng.gen_biguint(150));
+    let mask = |bits: usize| (BigInt::one() << bits) - 1;
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 3)?;
+        let out = shift::extract_bits(range, ctx, &a_int, 10, 100)?;
+        assert_eq!(value(&out), (&a >> 10) & mask(90));
+        let out = shift::extract_bits(range, ctx, &a_int, 0, 64)?;
+        assert_eq!(value(&out), &a & mask(64));
+        let out = shift::shr_const(range, ctx, &a_int, 70)?;
+        assert_eq!(value(&out), &a >> 70);
+        let out = shift::shl_const(range, ctx, &a_int, 70)?;
+        assert_eq!(value(&out), &a << 70);
+        Ok(())
+    })
+    .unwrap();
+
+    // `a` does not fit in its `max_size`, so there are no bits above it to extract
+    let result = verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &(BigInt::one() << 100), 2)?;
+        let a_int = OverflowInteger::construct(
+            a_int.limbs,
+            a_int.max_limb_size,
+            LIMB_BITS,
+            (BigUint::one() << LIMB_BITS) - 1usize,
+        );
+        shift::extract_bits(range, ctx, &a_int, 0, 64)?;
+        Ok(())
+    });
+    assert!(result.is_err());
+}
+
+#[derive(Clone)]
+struct BitwiseConfig<F: FieldExt> {
+    range: RangeConfig<F>,
+    lookup: ByteLookupConfig<F>,
+}
+
+// the limbs of `a` and `b` may be improper, to check that they are constrained to be bytes
+#[derive(Clone)]
+struct BitwiseCircuit {
+    a: Vec<BigInt>,
+    b: Vec<BigInt>,
+}
+
+impl Circuit<Fr> for BitwiseCircuit {
+    type Config = BitwiseConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let range = RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[PARAMS.num_advice],
+            &[PARAMS.num_lookup_advice],
+            PARAMS.num_fixed,
+            PARAMS.lookup_bits,
+            ContextId::new("default"),
+        );
+        let lookup = ByteLookupConfig::configure(meta, ContextId::new("bytes"));
+        BitwiseConfig { range, lookup }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        config.lookup.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "bitwise",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![
+                            (ContextId::new("default"), PARAMS.num_advice),
+                            (ContextId::new("bytes"), 1),
+                        ],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let gate = config.range.gate();
+                let a = load_limbs(gate, ctx, &self.a)?;
+                let b = load_limbs(gate, ctx, &self.b)?;
+                let is_proper =
+                    self.a.iter().chain(self.b.iter()).all(|x| x.bits() as usize <= LIMB_BITS);
+                let (a_val, b_val) = (value(&a), value(&b));
+                let and = bitwise::and(gate, &config.lookup, ctx, &a, &b)?;
+                let or = bitwise::or(gate, &config.lookup, ctx, &a, &b)?;
+                let xor = bitwise::xor(gate, &config.lookup, ctx, &a, &b)?;
+                if is_proper {
+                    assert_eq!(value(&and), &a_val & &b_val);
+                    assert_eq!(value(&or), &a_val | &b_val);
+                    assert_eq!(value(&xor), &a_val ^ &b_val);
+                }
+
+                config.range.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_bitwise() {
+    let limbs = |x: &BigInt| {
+        decompose_bigint::<Fr>(x, 2, LIMB_BITS).iter().map(fe_to_bigint).collect::<Vec<_>>()
+    };
+    let a = BigInt::from(OsRng.gen_biguint(128));
+    let b = BigInt::from(OsRng.gen_biguint(100));
+    let circuit = BitwiseCircuit { a: limbs(&a), b: limbs(&b) };
+    MockProver::run(17, &circuit, vec![]).unwrap().assert_satisfied();
+
+    // the first limb of `a` has more than `LIMB_BITS` bits, so its bytes do not compose to it
+    let mut a_limbs = limbs(&a);
+    a_limbs[0] = BigInt::one() << LIMB_BITS;
+    let circuit = BitwiseCircuit { a: a_limbs, b: limbs(&b) };
+    assert!(MockProver::run(17, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_signed() {
+    let a = -BigInt::from(OsRng.gen_biguint(100));
+    let b = BigInt::from(OsRng.gen_biguint(80));
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let signed_value = |x: &SignedOverflowInteger<Fr>| value_to_option(x.to_bigint()).unwrap();
+        let a_int = signed::assign(range, ctx, Value::known(a.clone()), 100, LIMB_BITS)?;
+        let b_int = signed::assign(range, ctx, Value::known(b.clone()), 100, LIMB_BITS)?;
+        let zero = signed::assign(range, ctx, Value::known(BigInt::zero()), 8, LIMB_BITS)?;
+
+        assert_eq!(signed_value(&signed::add(range, ctx, &a_int, &b_int)?), &a + &b);
+        assert_eq!(signed_value(&signed::sub(range, ctx, &a_int, &b_int)?), &a - &b);
+        assert_eq!(signed_value(&signed::sub(range, ctx, &b_int, &a_int)?), &b - &a);
+        assert_eq!(signed_value(&signed::mul(range, ctx, &a_int, &b_int)?), &a * &b);
+        assert_eq!(signed_value(&signed::neg(range, ctx, &a_int)?), -&a);
+
+        // zero has sign 0, also as `-0` and as a product with a negative integer
+        let neg_zero = signed::neg(range, ctx, &zero)?;
+        range.gate().assert_is_const(ctx, &neg_zero.sign, Fr::zero());
+        let prod = signed::mul(range, ctx, &a_int, &zero)?;
+        range.gate().assert_is_const(ctx, &prod.sign, Fr::zero());
+
+        let lt = signed::is_less_than(range, ctx, &a_int, &b_int)?;
+        range.gate().assert_is_const(ctx, &lt, Fr::one());
+        let lt = signed::is_less_than(range, ctx, &b_int, &a_int)?;
+        range.gate().assert_is_const(ctx, &lt, Fr::zero());
+        Ok(())
+    })
+    .unwrap();
+
+    // the magnitude does not fit in 8 bits
+    let result = verify(|range: &RangeConfig<Fr>, ctx| {
+        signed::assign(range, ctx, Value::known(BigInt::from(-256)), 8, LIMB_BITS)?;
+        Ok(())
+    });
+    assert!(result.is_err());
+}
+
+fn check_resize(a: BigInt, new_k: usize) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 3)?;
+        let out = resize::assign(range.gate(), ctx, &a_int, new_k)?;
+        assert_eq!(out.limbs.len(), new_k);
+        if a.bits() as usize <= LIMB_BITS * new_k {
+            assert_eq!(value(&out), a);
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn test_resize() {
+    let a = BigInt::from(OsRng.gen_biguint(100));
+    check_resize(a.clone(), 2).unwrap();
+    check_resize(a.clone(), 3).unwrap();
+    check_resize(a, 5).unwrap();
+
+    // the dropped limb is not zero
+    let a = BigInt::from(OsRng.gen_biguint(190)) | (BigInt::one() << 189);
+    assert!(check_resize(a, 2).is_err());
+}
+
+#[test]
+fn test_partial_products() {
+    let a: Vec<_> = (0..3).map(|_| Value::known(Fr::random(OsRng))).collect();
+    let b: Vec<_> = (0..2).map(|_| Value::known(Fr::random(OsRng))).collect();
+    let prods = mul_no_carry::partial_products(&a, &b, 4);
+    assert_eq!(prods.len(), 4);
+    for (i, prod) in prods.iter().enumerate() {
+        // the running sums of the terms `a[j] * b[i - j]` of the schoolbook product
+        let mut acc = Value::known(Fr::zero());
+        let terms: Vec<_> = (0..a.len())
+            .filter(|j| *j <= i && i - j < b.len())
+            .map(|j| {
+                acc = acc + a[j] * b[i - j];
+                value_to_option(acc).unwrap()
+            })
+            .collect();
+        let prod: Vec<_> = prod.iter().map(|x| value_to_option(*x).unwrap()).collect();
+        assert_eq!(prod, terms);
+    }
+}
+
+fn check_mul(a: BigInt, b: BigInt, c: BigInt) -> Result<(), Vec<VerifyFailure>> {
+    verify(move |range: &RangeConfig<Fr>, ctx| {
+        let a_int = load(range.gate(), ctx, &a, 3)?;
+        let b_int = load(range.gate(), ctx, &b, 2)?;
+        let c_int = load(range.gate(), ctx, &c, 5)?;
+        let prod = mul_no_carry::assign(range.gate(), ctx, &a_int, &b_int)?;
+        assert_eq!(value(&prod), &a * &b);
+        let check = sub_no_carry::assign(range.gate(), ctx, &prod, &c_int)?;
+        check_carry_to_zero::assign(range, ctx, &check)
+    })
+}
+
+#[test]
+fn test_mul_no_carry() {
+    let a = BigInt::from(OsRng.gen_biguint(190));
+    let b = BigInt::from(OsRng.gen_biguint(128));
+    check_mul(a.clone(), b.clone(), &a * &b).unwrap();
+
+    // the product is not `c`
+    assert!(check_mul(a.clone(), b.clone(), &a * &b + 1).is_err());
+}
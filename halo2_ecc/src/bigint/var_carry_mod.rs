@@ -0,0 +1,144 @@
+use super::{
+    add_no_carry, big_less_than, carry_mod::get_carry_witness, check_carry_to_zero, mul_no_carry,
+    sub_no_carry, BigIntConfig, CRTInteger, OverflowInteger,
+};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{biguint_to_fe, decompose_bigint_option, value_to_option},
+    Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Signed, Zero};
+
+/// Same as `carry_mod::assign` except the modulus is an assigned `OverflowInteger` instead of a constant
+///
+/// Input `a` is `OverflowInteger` with "signed" limbs
+/// Input `modulus` is `OverflowInteger` in proper representation, i.e., all limbs in [0, 2^limb_bits)
+/// Output is `a (mod modulus)` as a proper BigInt with `modulus.limbs.len()` limbs
+/// We constrain `a = out + modulus * quotient`, range check `out` and `quotient`, and constrain `out < modulus`
+/// * since we constrain `out < modulus`, this also constrains `modulus != 0`
+pub fn assign<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+    modulus: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(a.limb_bits, modulus.limb_bits);
+    let n = a.limb_bits;
+    let k = modulus.limbs.len();
+    // since modulus >= 1, |quot| <= a.max_size
+    let m = std::cmp::max((a.max_size.bits() as usize + n - 1) / n, 1);
+
+    #[cfg(feature = "display")]
+    {
+        let key = format!("var_carry_mod length {}", a.limbs.len());
+        let count = ctx.op_count.entry(key).or_insert(0);
+        *count += 1;
+    }
+
+    // these are witness vectors:
+    let (out_vec, quot_vec) = if let (Some(a_big), Some(mod_big)) =
+        (value_to_option(a.to_bigint()), value_to_option(modulus.to_bigint()))
+    {
+        // if `modulus <= 0` the witness does not matter since `out < modulus` will fail
+        let (out, quot) = if mod_big.is_positive() {
+            let (out, quot) = get_carry_witness(&a_big, &mod_big.to_biguint().unwrap());
+            (BigInt::from(out), quot)
+        } else {
+            (BigInt::zero(), BigInt::zero())
+        };
+        (
+            decompose_bigint_option::<F>(&Value::known(out), k, n),
+            decompose_bigint_option::<F>(&Value::known(quot), m, n),
+        )
+    } else {
+        (vec![Value::unknown(); k], vec![Value::unknown(); m])
+    };
+
+    let out_assigned = range.gate().assign_region_smart(
+        ctx,
+        out_vec.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+    let quot_assigned = range.gate().assign_region_smart(
+        ctx,
+        quot_vec.iter().map(|x| Witness(*x)).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+
+    // range check limbs of `out` are in [0, 2^n)
+    for out_cell in out_assigned.iter() {
+        range.range_check(ctx, out_cell, n)?;
+    }
+    let limb_base: F = biguint_to_fe(&(BigUint::one() << n));
+    // range check that quot_cell in quot_assigned is in [-2^n, 2^n)
+    for quot_cell in quot_assigned.iter() {
+        // compute quot_cell + 2^n and range check with n + 1 bits
+        let quot_shift = {
+            let out_val = quot_cell.value().map(|&a| a + limb_base);
+            // | quot_cell | 2^n | 1 | quot_cell + 2^n |
+            let shift_computation = range.gate().assign_region_smart(
+                ctx,
+                vec![
+                    Existing(quot_cell),
+                    Constant(limb_base),
+                    Constant(F::one()),
+                    Witness(out_val),
+                ],
+                vec![0],
+                vec![],
+                vec![],
+            )?;
+            shift_computation[3].clone()
+        };
+        range.range_check(ctx, &quot_shift, n + 1)?;
+    }
+
+    let out_max_limb_size = (BigUint::one() << n) - 1usize;
+    let out = OverflowInteger::construct(
+        out_assigned,
+        out_max_limb_size.clone(),
+        n,
+        (BigUint::one() << (n * k)) - 1usize,
+    );
+    let quot = OverflowInteger::construct(
+        quot_assigned,
+        BigUint::one() << n,
+        n,
+        (BigUint::one() << (n * m)) - 1usize,
+    );
+
+    // check that `out + modulus * quotient - a == 0` after carry
+    let prod = mul_no_carry::assign(range.gate(), ctx, &quot, modulus)?;
+    let prod_plus_out = add_no_carry::assign(range.gate(), ctx, &prod, &out)?;
+    let check = sub_no_carry::assign(range.gate(), ctx, &prod_plus_out, a)?;
+    check_carry_to_zero::assign(range, ctx, &check)?;
+
+    // check that `out < modulus`
+    let lt = big_less_than::assign(range, ctx, &out, modulus)?;
+    range.gate().assert_is_const(ctx, &lt, F::one());
+
+    Ok(out)
+}
+
+/// Input: `a` and `modulus` are `CRTInteger`s whose truncations are full representations of `a.value` and `modulus.value`
+/// Output: `a (mod modulus)` as a `CRTInteger` in proper representation
+pub fn crt<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    chip: &BigIntConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+    modulus: &CRTInteger<F>,
+) -> Result<CRTInteger<F>, Error> {
+    let out_trunc = assign(range, ctx, &a.truncation, &modulus.truncation)?;
+    let out_native =
+        OverflowInteger::evaluate(range.gate(), chip, ctx, &out_trunc.limbs, out_trunc.limb_bits)?;
+    let out_val = out_trunc.to_bigint();
+    Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+}
@@ -0,0 +1,163 @@
+use crate::bigint::{big_less_than, OverflowInteger};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    hashes::sha256::{sha256, sha256_native},
+    utils::biguint_to_fe,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use num_traits::One;
+
+#[cfg(test)]
+mod tests;
+
+pub const BLOCK_HEADER_SIZE: usize = 80;
+// 256-bit integers (hashes and targets) are compared as 4 limbs of 64 bits
+const LIMB_BITS: usize = 64;
+const NUM_LIMBS: usize = 4;
+
+/// The fields of a serialized block header, each as its little-endian bytes
+#[derive(Clone, Debug)]
+pub struct BlockHeader<F: FieldExt> {
+    pub version: Vec<AssignedValue<F>>,
+    pub prev_block_hash: Vec<AssignedValue<F>>,
+    pub merkle_root: Vec<AssignedValue<F>>,
+    pub time: Vec<AssignedValue<F>>,
+    pub bits: Vec<AssignedValue<F>>,
+    pub nonce: Vec<AssignedValue<F>>,
+}
+
+impl<F: FieldExt> BlockHeader<F> {
+    /// Splits the 80 bytes of a header into its fields. This adds no constraints.
+    pub fn parse(header: &[AssignedValue<F>]) -> Self {
+        assert_eq!(header.len(), BLOCK_HEADER_SIZE);
+        Self {
+            version: header[0..4].to_vec(),
+            prev_block_hash: header[4..36].to_vec(),
+            merkle_root: header[36..68].to_vec(),
+            time: header[68..72].to_vec(),
+            bits: header[72..76].to_vec(),
+            nonce: header[76..80].to_vec(),
+        }
+    }
+}
+
+/// Out of circuit double SHA-256 of a block header. The block hash is usually displayed in reversed byte order.
+pub fn block_hash_native(header: &[u8]) -> [u8; 32] {
+    assert_eq!(header.len(), BLOCK_HEADER_SIZE);
+    sha256_native(&sha256_native(header))
+}
+
+/// Out of circuit target of the compact difficulty `bits`: `mantissa * 256^(exponent - 3)`
+///
+/// Panics on the encodings rejected by `target_from_bits`: a negative mantissa or an exponent outside [3, 32].
+pub fn target_from_bits_native(bits: u32) -> BigUint {
+    let exponent = bits >> 24;
+    let mantissa = bits & 0x00ffffff;
+    assert!(mantissa < 0x00800000, "negative target");
+    assert!((3..=32).contains(&exponent), "unsupported exponent");
+    BigUint::from(mantissa) << (8 * (exponent - 3))
+}
+
+/// Input: the 80 bytes of a block header. Each byte is range checked to be in [0, 2^8).
+///
+/// Output: the 32 bytes of SHA-256(SHA-256(`header`)), which is the little-endian encoding of the hash as an integer
+pub fn block_hash<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    header: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    assert_eq!(header.len(), BLOCK_HEADER_SIZE);
+    let hash = sha256(range, ctx, header)?;
+    sha256(range, ctx, &hash)
+}
+
+// composes 32 little-endian bytes into an integer in proper representation with 64-bit limbs
+fn le_bytes_to_integer<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(bytes.len(), 32);
+    let bytes_per_limb = LIMB_BITS / 8;
+    let mut limbs = Vec::with_capacity(NUM_LIMBS);
+    for chunk in bytes.chunks(bytes_per_limb) {
+        let limb = gate.inner_product(
+            ctx,
+            &chunk.iter().map(|x| Existing(x)).collect(),
+            &(0..bytes_per_limb)
+                .map(|j| Constant(biguint_to_fe(&(BigUint::one() << (8 * j)))))
+                .collect(),
+        )?;
+        limbs.push(limb);
+    }
+    Ok(OverflowInteger::construct(
+        limbs,
+        (BigUint::one() << LIMB_BITS) - 1usize,
+        LIMB_BITS,
+        (BigUint::one() << (LIMB_BITS * NUM_LIMBS)) - 1usize,
+    ))
+}
+
+/// Input: the 4 little-endian bytes of the compact difficulty `bits` of a header, each assumed to be in [0, 2^8)
+///
+/// Output: the target `mantissa * 256^(exponent - 3)` in proper representation with 4 limbs of 64 bits
+///
+/// Constrains the mantissa to be non-negative, i.e. `bits[2] < 0x80`, and the exponent `bits[3]` to be in [3, 32].
+/// This covers every valid proof of work target, which is positive and less than `2^256`.
+pub fn target_from_bits<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bits: &[AssignedValue<F>],
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(bits.len(), 4);
+    let gate = range.gate();
+    let mantissa = &bits[..3];
+    range.range_check(ctx, &mantissa[2], 7)?;
+
+    // the mantissa starts at byte `shift = exponent - 3` of the target, with `shift < 30`
+    let shift = gate.sub(ctx, &Existing(&bits[3]), &Constant(F::from(3)))?;
+    let ind = range.idx_to_indicator_by_bits(ctx, &shift, 30)?;
+
+    // target[j] = sum_k mantissa[k] * (shift == j - k)
+    let mut target = Vec::with_capacity(32);
+    for j in 0..32 {
+        let (terms, coeffs): (Vec<_>, Vec<_>) = (0..3)
+            .filter(|k| *k <= j && j - k < ind.len())
+            .map(|k| (Existing(&mantissa[k]), Existing(&ind[j - k])))
+            .unzip();
+        let byte = gate.inner_product(ctx, &terms, &coeffs)?;
+        target.push(byte);
+    }
+    le_bytes_to_integer(gate, ctx, &target)
+}
+
+/// Input: the 80 bytes of a block header. Each byte is range checked to be in [0, 2^8).
+///
+/// Output: the block hash, after constraining that it is at most the target encoded by the `bits` of the header
+///
+/// This only checks the proof of work of a single header. Chaining headers through `prev_block_hash` and checking
+/// that `bits` follows the difficulty adjustment rules is left to the caller.
+pub fn verify_pow<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    header: &[AssignedValue<F>],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    // the bytes of `header` are range checked by the hash
+    let hash = block_hash(range, ctx, header)?;
+    let parsed = BlockHeader::parse(header);
+    let target = target_from_bits(range, ctx, &parsed.bits)?;
+
+    let hash_int = le_bytes_to_integer(range.gate(), ctx, &hash)?;
+    let target_lt_hash = big_less_than::assign(range, ctx, &target, &hash_int)?;
+    range.gate().assert_is_const(ctx, &target_lt_hash, F::zero());
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("bitcoin_verify_pow".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(hash)
+}
@@ -0,0 +1,145 @@
+use super::*;
+use halo2_base::{
+    gates::range::{RangeConfig, RangeStrategy},
+    utils::{fe_to_biguint, value_to_option},
+    ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+
+const NUM_ADVICE: usize = 8;
+const NUM_LOOKUP_ADVICE: usize = 1;
+const NUM_FIXED: usize = 1;
+const LOOKUP_BITS: usize = 8;
+
+// the genesis block header
+const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+fn from_hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+}
+
+#[derive(Default)]
+struct PowCircuit<F> {
+    header: Option<Vec<u8>>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for PowCircuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[NUM_LOOKUP_ADVICE],
+            NUM_FIXED,
+            LOOKUP_BITS,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "bitcoin pow",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let header_values = match &self.header {
+                    Some(header) => {
+                        header.iter().map(|b| Value::known(F::from(*b as u64))).collect()
+                    }
+                    None => vec![Value::unknown(); BLOCK_HEADER_SIZE],
+                };
+                let header = config.gate.assign_region_smart(
+                    ctx,
+                    header_values.into_iter().map(Witness).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let hash = verify_pow(&config, ctx, &header)?;
+                if let Some(expected) = &self.header {
+                    let expected = block_hash_native(expected);
+                    for (byte, expected) in hash.iter().zip(expected.iter()) {
+                        if let Some(byte) = value_to_option(byte.value()) {
+                            assert_eq!(fe_to_biguint(byte), BigUint::from(*expected));
+                        }
+                    }
+                }
+
+                config.finalize(ctx)?;
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_block_hash_native() {
+    let mut hash = block_hash_native(&from_hex(GENESIS_HEADER));
+    hash.reverse();
+    assert_eq!(hash.to_vec(), from_hex(GENESIS_HASH));
+}
+
+#[test]
+fn test_target_from_bits_native() {
+    assert_eq!(target_from_bits_native(0x1d00ffff), BigUint::from(0xffffu64) << 208);
+    assert_eq!(target_from_bits_native(0x03123456), BigUint::from(0x123456u64));
+}
+
+#[test]
+fn test_verify_pow() {
+    let k = 18;
+    let circuit = PowCircuit::<Fr> { header: Some(from_hex(GENESIS_HEADER)), ..Default::default() };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn test_verify_pow_invalid_nonce() {
+    let k = 18;
+    let mut header = from_hex(GENESIS_HEADER);
+    header[76] ^= 1;
+    // the hash of the modified header is above the target
+    assert!(
+        BigUint::from_bytes_le(&block_hash_native(&header)) > target_from_bits_native(0x1d00ffff)
+    );
+    let circuit = PowCircuit::<Fr> { header: Some(header), ..Default::default() };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
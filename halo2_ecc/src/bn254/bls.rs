@@ -0,0 +1,122 @@
+// BLS signatures over BN254 and their aggregation
+//
+// Public keys are in G2 and signatures and message hashes in G1: the signature `[sk] H(m)` of the public key
+// `[sk] G2` is valid iff `e(sig, G2) = e(H(m), pk)`. An aggregate signature is the sum of the signatures of its
+// signers. If they all signed the same message it is checked against the sum of their public keys,
+// `e(sig, G2) = e(H(m), sum_i pk_i)`, and otherwise with the multi-pairing `e(sig, G2) = prod_i e(H(m_i), pk_i)`.
+// Either way the pairings share one final exponentiation. Aggregation is only sound for public keys in the subgroup
+// of order r (and, for a common message, with proofs of possession), so each public key is checked to be on the
+// twist and in the subgroup.
+//
+// The message hashes are G1 points supplied by the caller, as there is no hash-to-curve chip for G1. Ethereum's sync
+// committee signs over BLS12-381, which this crate does not implement.
+#![allow(non_snake_case)]
+
+use super::{
+    g2::{G2Chip, G2Point},
+    pairing::PairingChip,
+    precompiles::curve_flags,
+    Fp12Chip, FpChip, FpPoint, FqPoint,
+};
+use crate::{
+    ecc::{self, EccPoint},
+    fields::FieldChip,
+};
+use group::{prime::PrimeCurveAffine, Curve, Group};
+use halo2_base::{
+    gates::GateInstructions, utils::fe_to_biguint, AssignedValue, Context, QuantumCell::Existing,
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field, FieldExt},
+    circuit::Value,
+    halo2curves::bn256::{Fr, G1Affine, G2Affine, G2},
+    plonk::Error,
+};
+use halo2curves::bn256::Fq12;
+use num_bigint::BigInt;
+use rand_core::OsRng;
+
+/// Returns whether `signature` is a valid aggregate signature of the signers with `pubkeys`, see the module comment.
+///
+/// `msg_hashes` has a single point if all signers signed the same message, and one point per public key otherwise.
+/// The public key at index `i` which is not in G2 is replaced by the constant `[2^i] G2`, so that the sum of the
+/// public keys has no exceptional case unless valid keys are related. The sum starts from a random point of the
+/// twist, which is subtracted at the end, so repeated public keys are summed without doubling. The coordinates of
+/// all points are assumed to be less than p, e.g. loaded by `PrecompileChip::load_g1` and `load_g2`.
+pub fn verify_aggregate<F: FieldExt>(
+    fp_chip: &FpChip<F>,
+    ctx: &mut Context<'_, F>,
+    pubkeys: &[G2Point<F>],
+    msg_hashes: &[EccPoint<F, FpPoint<F>>],
+    signature: &EccPoint<F, FpPoint<F>>,
+) -> Result<AssignedValue<F>, Error> {
+    assert!(!pubkeys.is_empty());
+    assert!(msg_hashes.len() == 1 || msg_hashes.len() == pubkeys.len());
+    let gate = fp_chip.range.gate();
+    let g2_chip = G2Chip::construct(fp_chip);
+    let fp12_chip = Fp12Chip::construct(fp_chip);
+    let pairing_chip = PairingChip::construct(fp_chip);
+
+    let mut flags = Vec::with_capacity(pubkeys.len() + msg_hashes.len() + 1);
+    let g1_b = fp_chip.load_constant(ctx, BigInt::from(fe_to_biguint(&G1Affine::b())))?;
+    for P in msg_hashes.iter().chain([signature]) {
+        // the G1 points only enter the line functions, so they need no replacement
+        let (is_on_curve, _) = curve_flags(fp_chip, ctx, P, &g1_b)?;
+        flags.push(is_on_curve);
+    }
+
+    let mut safe_pubkeys = Vec::with_capacity(pubkeys.len());
+    let mut power = Fr::one();
+    for pubkey in pubkeys.iter() {
+        let replacement = g2_chip.load_constant(ctx, (G2Affine::generator() * power).into())?;
+        power = power.double();
+        // `(0, 0)` is not on the twist, so the infinity is rejected too
+        let is_on_curve = g2_chip.is_on_curve(ctx, pubkey)?;
+        let on_curve = ecc::select(&g2_chip.fp2_chip, ctx, pubkey, &replacement, &is_on_curve)?;
+        let in_subgroup = g2_chip.is_in_subgroup(ctx, &on_curve)?;
+        let is_valid = gate.and(ctx, &Existing(&is_on_curve), &Existing(&in_subgroup))?;
+        safe_pubkeys.push(ecc::select(&g2_chip.fp2_chip, ctx, pubkey, &replacement, &is_valid)?);
+        flags.push(is_valid);
+    }
+
+    let mut pairs = Vec::with_capacity(pubkeys.len() + 1);
+    if msg_hashes.len() == 1 {
+        // `add_unequal` would reject `pk + pk`, but not `R + pk + pk` for a random `R` except with negligible
+        // probability. `R` only needs to be on the twist for the group law to hold, as it cancels out.
+        let offset = g2_chip.load_private(ctx, Value::known(G2::random(OsRng).to_affine()))?;
+        for coeff in offset.x.coeffs.iter().chain(offset.y.coeffs.iter()) {
+            fp_chip.enforce_less_than_p(ctx, coeff)?;
+        }
+        let offset_on_curve = g2_chip.is_on_curve(ctx, &offset)?;
+        gate.assert_is_const(ctx, &offset_on_curve, F::one());
+
+        let mut acc = offset.clone();
+        for pubkey in safe_pubkeys.iter() {
+            acc = g2_chip.add_unequal(ctx, &acc, pubkey, true)?;
+        }
+        let apk = g2_chip.sub_unequal(ctx, &acc, &offset, true)?;
+        pairs.push((apk, &msg_hashes[0]));
+    } else {
+        pairs.extend(safe_pubkeys.into_iter().zip(msg_hashes.iter()));
+    }
+    let neg_generator = g2_chip.load_constant(ctx, -G2Affine::generator())?;
+    pairs.push((neg_generator, signature));
+
+    let mut f: Option<FqPoint<F>> = None;
+    for (Q, P) in pairs.iter() {
+        let f_pair = pairing_chip.miller_loop(ctx, Q, P)?;
+        f = Some(match f {
+            None => f_pair,
+            Some(f) => fp12_chip.mul(ctx, &f, &f_pair)?,
+        });
+    }
+    let f = fp12_chip.final_exp(ctx, &f.unwrap())?;
+    let one = fp12_chip.load_constant(ctx, Fq12::one())?;
+    flags.push(fp12_chip.is_equal(ctx, &f, &one)?);
+
+    let mut is_valid = flags[0].clone();
+    for flag in flags[1..].iter() {
+        is_valid = gate.and(ctx, &Existing(&is_valid), &Existing(flag))?;
+    }
+    Ok(is_valid)
+}
@@ -11,7 +11,7 @@ use halo2_base::{
     QuantumCell::{Constant, Existing},
 };
 use halo2_proofs::{arithmetic::FieldExt, halo2curves::bn256::BN_X, plonk::Error};
-use halo2curves::bn256::{Fq, Fq2, FROBENIUS_COEFF_FQ12_C1};
+use halo2curves::bn256::{Fq, Fq12, Fq2, FROBENIUS_COEFF_FQ12_C1};
 use num_bigint::{BigInt, BigUint};
 
 const XI_0: u64 = 9;
@@ -400,4 +400,35 @@ impl<'a, F: FieldExt> Fp12Chip<'a, F> {
         let f = self.hard_part_BN(ctx, &f0)?;
         Ok(f)
     }
+
+    /// Constrains `a` to be in GT, the subgroup of order r of Fp12^*, for an `a` supplied as a witness rather than
+    /// output by `final_exp`. Checks that `a` is unitary, `a^{p^6 + 1} = 1`, that it is in the cyclotomic subgroup
+    /// GΦ₁₂, `a^{p^4} a = a^{p^2}`, and then that `a^p = a^{6x^2}`, which holds in GΦ₁₂ exactly on GT for BN curves
+    /// since `p = 6x^2 (mod r)` (Scott, https://eprint.iacr.org/2021/1130.pdf)
+    pub fn assert_in_gt(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &<Self as FieldChip<F>>::FieldPoint,
+    ) -> Result<(), Error> {
+        let gate = self.fp_chip.range.gate();
+        // unitarity also rules out `a = 0`, which satisfies the cyclotomic identity
+        let one = self.load_constant(ctx, Fq12::one())?;
+        let a_conj = self.conjugate(ctx, a)?;
+        let norm = self.mul(ctx, &a_conj, a)?;
+        let is_unitary = self.is_equal(ctx, &norm, &one)?;
+        gate.assert_is_const(ctx, &is_unitary, F::one());
+
+        let ap2 = self.frobenius_map(ctx, a, 2)?;
+        let ap4 = self.frobenius_map(ctx, a, 4)?;
+        let ap4_a = self.mul(ctx, &ap4, a)?;
+        let is_cyclotomic = self.is_equal(ctx, &ap4_a, &ap2)?;
+        gate.assert_is_const(ctx, &is_cyclotomic, F::one());
+
+        let exp = 6u128 * BN_X as u128 * BN_X as u128;
+        let a_exp = self.cyclotomic_pow(ctx, a, vec![exp as u64, (exp >> 64) as u64])?;
+        let ap = self.frobenius_map(ctx, a, 1)?;
+        let is_in_gt = self.is_equal(ctx, &ap, &a_exp)?;
+        gate.assert_is_const(ctx, &is_in_gt, F::one());
+        Ok(())
+    }
 }
@@ -0,0 +1,233 @@
+// G2 of BN254: the subgroup of order r of the twist `y^2 = x^3 + 3 / (9 + u)` over Fp2
+//
+// `G2Chip` gathers the operations on G2 points, which otherwise need an `EccChip` over an `Fp2Chip` wired by hand
+// and the constants of the twist. Points are `G2Point`s, with each coordinate `c_0 + c_1 u` stored as `[c_0, c_1]`.
+// As with `EccChip`, the point at infinity has no representation and the group operations assume their exceptional
+// cases do not occur; points of the twist outside G2 are representable, so a point supplied as a witness should go
+// through `assert_in_subgroup` (or `is_in_subgroup`) before it is used as a G2 element.
+#![allow(non_snake_case)]
+
+use super::{pairing::twisted_frobenius, precompiles::curve_flags, Fp2Chip, FpChip, FqPoint};
+use crate::{
+    ecc::{EccChip, EccPoint},
+    fields::FieldChip,
+};
+use halo2_base::{
+    gates::GateInstructions,
+    utils::{biguint_to_fe, fe_to_biguint, modulus},
+    AssignedValue, Context,
+    QuantumCell::Existing,
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Value,
+    halo2curves::bn256::{self, G2Affine, BN_X},
+    plonk::Error,
+};
+use halo2curves::bn256::{Fq2, FROBENIUS_COEFF_FQ12_C1};
+use num_bigint::BigUint;
+
+pub type G2Point<F> = EccPoint<F, FqPoint<F>>;
+pub type G2EccChip<'a, F> = EccChip<'a, F, Fp2Chip<'a, F>>;
+
+// go from pse/pairing::bn256::Fq2 to forked public Fq2
+pub(crate) fn convert_fp2(a: bn256::Fq2) -> Fq2 {
+    Fq2 { c0: biguint_to_fe(&fe_to_biguint(&a.c0)), c1: biguint_to_fe(&fe_to_biguint(&a.c1)) }
+}
+
+pub struct G2Chip<'a, F: FieldExt> {
+    pub fp2_chip: Fp2Chip<'a, F>,
+}
+
+impl<'a, F: FieldExt> G2Chip<'a, F> {
+    pub fn construct(fp_chip: &'a FpChip<F>) -> Self {
+        Self { fp2_chip: Fp2Chip::construct(fp_chip) }
+    }
+
+    /// Does not constrain the point to be on the twist or in G2
+    pub fn load_private(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: Value<G2Affine>,
+    ) -> Result<G2Point<F>, Error> {
+        let x = Q.map(|Q| convert_fp2(Q.x));
+        let y = Q.map(|Q| convert_fp2(Q.y));
+        EccChip::construct(&self.fp2_chip).load_private(ctx, (x, y))
+    }
+
+    pub fn load_constant(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: G2Affine,
+    ) -> Result<G2Point<F>, Error> {
+        let x = self.fp2_chip.load_constant(ctx, convert_fp2(Q.x))?;
+        let y = self.fp2_chip.load_constant(ctx, convert_fp2(Q.y))?;
+        Ok(EccPoint::construct(x, y))
+    }
+
+    /// Returns whether `Q` is on the twist, for `Q` with coordinates less than p
+    pub fn is_on_curve(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: &G2Point<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let b = self.fp2_chip.load_constant(ctx, convert_fp2(G2Affine::b()))?;
+        let (is_on_curve, _) = curve_flags(&self.fp2_chip, ctx, Q, &b)?;
+        Ok(is_on_curve)
+    }
+
+    /// Returns whether `Q`, a point on the twist, is in G2.
+    ///
+    /// With `r = 2m + 1`, `[2m]Q = -Q` exactly when `[r]Q = O`, and `[m]Q` has no exceptional cases for `Q` in G2.
+    /// For `Q` on the twist outside G2 the multiplication may hit an exceptional case, which makes the circuit
+    /// unsatisfiable rather than return 0; such points have no component in G2 at all.
+    pub fn is_in_subgroup(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: &G2Point<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let gate = self.fp2_chip.fp_chip.range.gate();
+        let m = (modulus::<bn256::Fr>() - 1usize) >> 1;
+        let mQ = EccChip::construct(&self.fp2_chip).scalar_mult_constant(ctx, Q, &m)?;
+        let two_mQ = self.double(ctx, &mQ)?;
+
+        let x_is_equal = self.fp2_chip.is_equal(ctx, &two_mQ.x, &Q.x)?;
+        let y_sum = self.fp2_chip.add_no_carry(ctx, &two_mQ.y, &Q.y)?;
+        let y_sum = self.fp2_chip.carry_mod(ctx, &y_sum)?;
+        let y_is_opposite = self.fp2_chip.is_zero(ctx, &y_sum)?;
+        gate.and(ctx, &Existing(&x_is_equal), &Existing(&y_is_opposite))
+    }
+
+    /// Constrains `Q` to be on the twist and in G2
+    pub fn assert_in_subgroup(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: &G2Point<F>,
+    ) -> Result<(), Error> {
+        let gate = self.fp2_chip.fp_chip.range.gate();
+        let is_on_curve = self.is_on_curve(ctx, Q)?;
+        gate.assert_is_const(ctx, &is_on_curve, F::one());
+        let in_subgroup = self.is_in_subgroup(ctx, Q)?;
+        gate.assert_is_const(ctx, &in_subgroup, F::one());
+        Ok(())
+    }
+
+    /// Loads `Q` and constrains it to be in G2, with coordinates less than p
+    pub fn assign_point_checked(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: Value<G2Affine>,
+    ) -> Result<G2Point<F>, Error> {
+        let Q = self.load_private(ctx, Q)?;
+        for coeff in Q.x.coeffs.iter().chain(Q.y.coeffs.iter()) {
+            self.fp2_chip.fp_chip.enforce_less_than_p(ctx, coeff)?;
+        }
+        self.assert_in_subgroup(ctx, &Q)?;
+        Ok(Q)
+    }
+
+    pub fn negate(&self, ctx: &mut Context<'_, F>, Q: &G2Point<F>) -> Result<G2Point<F>, Error> {
+        EccChip::construct(&self.fp2_chip).negate(ctx, Q)
+    }
+
+    /// Assumes that `P.x != Q.x`, and constrains it if `is_strict`
+    pub fn add_unequal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &G2Point<F>,
+        Q: &G2Point<F>,
+        is_strict: bool,
+    ) -> Result<G2Point<F>, Error> {
+        EccChip::construct(&self.fp2_chip).add_unequal(ctx, P, Q, is_strict)
+    }
+
+    /// Assumes that `P.x != Q.x`, and constrains it if `is_strict`
+    pub fn sub_unequal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &G2Point<F>,
+        Q: &G2Point<F>,
+        is_strict: bool,
+    ) -> Result<G2Point<F>, Error> {
+        EccChip::construct(&self.fp2_chip).sub_unequal(ctx, P, Q, is_strict)
+    }
+
+    pub fn double(&self, ctx: &mut Context<'_, F>, Q: &G2Point<F>) -> Result<G2Point<F>, Error> {
+        EccChip::construct(&self.fp2_chip).double(ctx, Q)
+    }
+
+    pub fn is_equal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &G2Point<F>,
+        Q: &G2Point<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        EccChip::construct(&self.fp2_chip).is_equal(ctx, P, Q)
+    }
+
+    // `Fp2Chip` has no `assert_equal`, so this constrains `is_equal` instead
+    pub fn assert_equal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &G2Point<F>,
+        Q: &G2Point<F>,
+    ) -> Result<(), Error> {
+        let is_equal = self.is_equal(ctx, P, Q)?;
+        self.fp2_chip.fp_chip.range.gate().assert_is_const(ctx, &is_equal, F::one());
+        Ok(())
+    }
+
+    /// The endomorphism `psi = twist^{-1} o Frob_p o twist` of the twist, which acts on G2 as `[p]`
+    pub fn psi(&self, ctx: &mut Context<'_, F>, Q: &G2Point<F>) -> Result<G2Point<F>, Error> {
+        // coeff[1][j] = ((9 + u)^{(p - 1) / 6})^j, as in `miller_loop_BN`
+        let c2 = FROBENIUS_COEFF_FQ12_C1[1] * FROBENIUS_COEFF_FQ12_C1[1];
+        let c3 = c2 * FROBENIUS_COEFF_FQ12_C1[1];
+        let c2 = self.fp2_chip.load_constant(ctx, c2)?;
+        let c3 = self.fp2_chip.load_constant(ctx, c3)?;
+        twisted_frobenius(&EccChip::construct(&self.fp2_chip), ctx, Q, &c2, &c3)
+    }
+
+    /// Maps `Q` on the twist into G2 with `[x]Q + psi([3x]Q) + psi^2([x]Q) + psi^3(Q)`, a multiple of the
+    /// cofactor `2p - r` by a polynomial in `psi` (Fuentes-Castaneda, Knapp and Rodriguez-Henriquez, 2011), which
+    /// costs one 63-bit scalar multiplication instead of a 254-bit one.
+    ///
+    /// The additions are strict, so the circuit is unsatisfiable in their exceptional cases, which need `Q` with
+    /// a small order and do not occur for the output of `map_to_g2`.
+    pub fn clear_cofactor(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: &G2Point<F>,
+    ) -> Result<G2Point<F>, Error> {
+        let xQ = EccChip::construct(&self.fp2_chip).scalar_mult_constant(
+            ctx,
+            Q,
+            &BigUint::from(BN_X),
+        )?;
+        let two_xQ = self.double(ctx, &xQ)?;
+        let three_xQ = self.add_unequal(ctx, &two_xQ, &xQ, true)?;
+
+        let psi_3xQ = self.psi(ctx, &three_xQ)?;
+        let psi_xQ = self.psi(ctx, &xQ)?;
+        let psi2_xQ = self.psi(ctx, &psi_xQ)?;
+        let mut psi3_Q = self.psi(ctx, Q)?;
+        for _ in 0..2 {
+            psi3_Q = self.psi(ctx, &psi3_Q)?;
+        }
+
+        let mut out = self.add_unequal(ctx, &xQ, &psi_3xQ, true)?;
+        out = self.add_unequal(ctx, &out, &psi2_xQ, true)?;
+        self.add_unequal(ctx, &out, &psi3_Q, true)
+    }
+
+    /// `[scalar]Q` for the scalar with limbs of `max_bits` bits `scalar`, see `EccChip::scalar_mult`
+    pub fn scalar_mult(
+        &self,
+        ctx: &mut Context<'_, F>,
+        Q: &G2Point<F>,
+        scalar: &Vec<AssignedValue<F>>,
+        max_bits: usize,
+        window_bits: usize,
+    ) -> Result<G2Point<F>, Error> {
+        EccChip::construct(&self.fp2_chip).scalar_mult(ctx, Q, scalar, max_bits, window_bits)
+    }
+}
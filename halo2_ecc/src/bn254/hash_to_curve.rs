@@ -0,0 +1,481 @@
+// Hashing to G2 of BN254
+//
+// `hash_to_g2` follows hash_to_curve of RFC 9380 with the Shallue-van de Woestijne map: the message is expanded by
+// expand_message_xmd over SHA-256 into two elements `u_0, u_1` of Fp2, each is mapped to a point of the twist, and
+// the sum of the points is mapped into G2 by `G2Chip::clear_cofactor`. The twist has `A = 0`, so the simplified SWU
+// map would need an isogeny, which SvdW avoids. The RFC defines no suite for BN254, so the outputs are specified by
+// `hash_to_g2_native`. The square roots and the square tests of the map are witnesses: the prover shows that `a` is
+// not a square with a square root of `xi a` for the non-square `xi = 9 + u`.
+//
+// BLS12-381 is not implemented by this crate, so neither is its hash to G2.
+#![allow(non_snake_case)]
+
+use super::{
+    g2::{convert_fp2, G2Chip, G2Point},
+    Fp2Chip, FpChip, FpPoint, FqPoint,
+};
+use crate::{
+    bigint::{CRTInteger, OverflowInteger},
+    fields::{FieldChip, FieldExtPoint},
+};
+use ff::Field;
+use group::Curve;
+use halo2_base::{
+    gates::{range::RangeConfig, GateInstructions, RangeInstructions},
+    hashes::sha256::{sha256, sha256_native, SHA256_BLOCK_SIZE},
+    utils::{biguint_to_fe, fe_to_biguint, modulus},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Value,
+    halo2curves::bn256::{self, G2Affine, BN_X, G2},
+    plonk::Error,
+};
+use halo2curves::bn256::{Fq, Fq2, FROBENIUS_COEFF_FQ12_C1};
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Zero};
+
+// the bytes reduced to an element of Fp by hash_to_field: ceil((ceil(log2(p)) + 128) / 8)
+pub const HASH_TO_FIELD_LEN: usize = 48;
+const SHA256_OUTPUT_LEN: usize = 32;
+
+fn fq2(c0: u64) -> Fq2 {
+    Fq2 { c0: Fq::from(c0), c1: Fq::zero() }
+}
+
+// the non-square `9 + u`
+fn xi() -> Fq2 {
+    Fq2 { c0: Fq::from(9), c1: Fq::one() }
+}
+
+// go from forked public Fq2 to pse/pairing::bn256::Fq2
+fn to_pse_fp2(a: Fq2) -> bn256::Fq2 {
+    bn256::Fq2 {
+        c0: biguint_to_fe(&fe_to_biguint(&a.c0)),
+        c1: biguint_to_fe(&fe_to_biguint(&a.c1)),
+    }
+}
+
+// the right side `x^3 + b` of the twist
+fn twist_g(x: &Fq2) -> Fq2 {
+    x.square() * x + convert_fp2(G2Affine::b())
+}
+
+fn is_square(a: &Fq2) -> bool {
+    bool::from(a.sqrt().is_some())
+}
+
+// sgn0 of RFC 9380 for Fp2: `sgn0(a_0) || (a_0 == 0 && sgn0(a_1))`, with the parity as `sgn0` of Fp
+fn sgn0(a: &Fq2) -> bool {
+    let sign_0 = fe_to_biguint(&a.c0).bit(0);
+    let zero_0 = bool::from(a.c0.is_zero());
+    let sign_1 = fe_to_biguint(&a.c1).bit(0);
+    sign_0 || (zero_0 && sign_1)
+}
+
+// the constants `[Z, c1, c2, c3, c4]` of the Shallue-van de Woestijne map, with `Z` found as in RFC 9380, H.1
+fn svdw_constants() -> [Fq2; 5] {
+    let (two, three, four) = (fq2(2), fq2(3), fq2(4));
+    let z = (1..)
+        .flat_map(|ctr| [fq2(ctr), -fq2(ctr)])
+        .find(|z| {
+            let gz = twist_g(z);
+            if bool::from(gz.is_zero()) {
+                return false;
+            }
+            // -(3 Z^2 + 4 A) / (4 g(Z)) with A = 0
+            let h = -(three * z.square()) * (four * gz).invert().unwrap();
+            !bool::from(h.is_zero())
+                && is_square(&h)
+                && (is_square(&gz) || is_square(&twist_g(&(-*z * two.invert().unwrap()))))
+        })
+        .unwrap();
+    let c1 = twist_g(&z);
+    let c2 = -z * two.invert().unwrap();
+    let mut c3 = (-c1 * three * z.square()).sqrt().unwrap();
+    if sgn0(&c3) {
+        c3 = -c3;
+    }
+    let c4 = -four * c1 * (three * z.square()).invert().unwrap();
+    [z, c1, c2, c3, c4]
+}
+
+/// Out of circuit expand_message_xmd of RFC 9380 with SHA-256
+pub fn expand_message_xmd_native(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let ell = (len + SHA256_OUTPUT_LEN - 1) / SHA256_OUTPUT_LEN;
+    assert!(dst.len() <= 255 && ell <= 255 && len < 1 << 16);
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let b_0 = sha256_native(
+        &[&[0u8; SHA256_BLOCK_SIZE][..], msg, &(len as u16).to_be_bytes(), &[0], &dst_prime]
+            .concat(),
+    );
+    let mut b_i = sha256_native(&[&b_0[..], &[1], &dst_prime].concat());
+    let mut out = b_i.to_vec();
+    for i in 2..=ell {
+        let xor: Vec<_> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = sha256_native(&[&xor[..], &[i as u8], &dst_prime].concat());
+        out.extend(b_i);
+    }
+    out.truncate(len);
+    out
+}
+
+/// Out of circuit hash_to_field of RFC 9380 for two elements of Fp2
+pub fn hash_to_field_native(msg: &[u8], dst: &[u8]) -> [Fq2; 2] {
+    let bytes = expand_message_xmd_native(msg, dst, 4 * HASH_TO_FIELD_LEN);
+    let e: Vec<Fq> = bytes
+        .chunks(HASH_TO_FIELD_LEN)
+        .map(|chunk| biguint_to_fe(&(BigUint::from_bytes_be(chunk) % modulus::<Fq>())))
+        .collect();
+    [Fq2 { c0: e[0], c1: e[1] }, Fq2 { c0: e[2], c1: e[3] }]
+}
+
+/// Out of circuit Shallue-van de Woestijne map of RFC 9380 from Fp2 to the twist, as in `map_to_g2`
+pub fn map_to_g2_native(u: Fq2) -> G2Affine {
+    let [z, c1, c2, c3, c4] = svdw_constants();
+    let tv1 = u.square() * c1;
+    let tv2 = Fq2::one() + tv1;
+    let tv1 = Fq2::one() - tv1;
+    let tv3 = Option::from((tv1 * tv2).invert()).unwrap_or_else(Fq2::zero);
+    let tv4 = u * tv1 * tv3 * c3;
+    let x1 = c2 - tv4;
+    let x2 = c2 + tv4;
+    let x3 = (tv2.square() * tv3).square() * c4 + z;
+    let x = if is_square(&twist_g(&x1)) {
+        x1
+    } else if is_square(&twist_g(&x2)) {
+        x2
+    } else {
+        x3
+    };
+    let mut y = twist_g(&x).sqrt().unwrap();
+    if sgn0(&u) != sgn0(&y) {
+        y = -y;
+    }
+    G2Affine { x: to_pse_fp2(x), y: to_pse_fp2(y) }
+}
+
+fn psi_native(Q: &G2Affine) -> G2Affine {
+    let c2 = FROBENIUS_COEFF_FQ12_C1[1] * FROBENIUS_COEFF_FQ12_C1[1];
+    let c3 = c2 * FROBENIUS_COEFF_FQ12_C1[1];
+    let conjugate = |a: bn256::Fq2| bn256::Fq2 { c0: a.c0, c1: -a.c1 };
+    G2Affine { x: conjugate(Q.x) * to_pse_fp2(c2), y: conjugate(Q.y) * to_pse_fp2(c3) }
+}
+
+/// Out of circuit `G2Chip::clear_cofactor`
+pub fn clear_cofactor_native(Q: &G2Affine) -> G2Affine {
+    let xQ = (*Q * bn256::Fr::from(BN_X)).to_affine();
+    let three_xQ = (xQ * bn256::Fr::from(3)).to_affine();
+    let psi3_Q = psi_native(&psi_native(&psi_native(Q)));
+    (G2::from(xQ) + psi_native(&three_xQ) + psi_native(&psi_native(&xQ)) + psi3_Q).to_affine()
+}
+
+/// Out of circuit `hash_to_g2`
+pub fn hash_to_g2_native(msg: &[u8], dst: &[u8]) -> G2Affine {
+    let [u_0, u_1] = hash_to_field_native(msg, dst);
+    let R = G2::from(map_to_g2_native(u_0)) + map_to_g2_native(u_1);
+    clear_cofactor_native(&R.to_affine())
+}
+
+fn load_bytes<F: FieldExt>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[u8],
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    gate.assign_region_smart(
+        ctx,
+        bytes.iter().map(|b| Constant(F::from(*b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )
+}
+
+// `a ^ b` for the bytes `a, b`, bit by bit
+fn xor_byte<F: FieldExt>(
+    range: &RangeConfig<F>,
+    ctx: &mut Context<'_, F>,
+    a: &AssignedValue<F>,
+    b: &AssignedValue<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let gate = range.gate();
+    let a_bits = range.num_to_bits(ctx, a, 8)?;
+    let b_bits = range.num_to_bits(ctx, b, 8)?;
+    let mut bits = Vec::with_capacity(8);
+    for (a_bit, b_bit) in a_bits.iter().zip(b_bits.iter()) {
+        let not_b = gate.not(ctx, &Existing(b_bit))?;
+        bits.push(gate.select(ctx, &Existing(&not_b), &Existing(b_bit), &Existing(a_bit))?);
+    }
+    gate.bits_to_num(ctx, &bits, false)
+}
+
+/// expand_message_xmd of RFC 9380 with SHA-256: `len` uniform bytes from the message bytes `msg` and the domain
+/// separation tag `dst`, which is a constant of the circuit. The bytes of `msg` are range checked.
+pub fn expand_message_xmd<F: FieldExt>(
+    range: &RangeConfig<F>,
+    ctx: &mut Context<'_, F>,
+    msg: &[AssignedValue<F>],
+    dst: &[u8],
+    len: usize,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let ell = (len + SHA256_OUTPUT_LEN - 1) / SHA256_OUTPUT_LEN;
+    assert!(dst.len() <= 255 && ell <= 255 && len < 1 << 16);
+    let gate = range.gate();
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+
+    let prefix = load_bytes(gate, ctx, &[0u8; SHA256_BLOCK_SIZE])?;
+    let suffix =
+        load_bytes(gate, ctx, &[&(len as u16).to_be_bytes()[..], &[0], &dst_prime].concat())?;
+    let b_0 = sha256(range, ctx, &[prefix, msg.to_vec(), suffix].concat())?;
+
+    let mut out: Vec<AssignedValue<F>> = Vec::with_capacity(ell * SHA256_OUTPUT_LEN);
+    for i in 1..=ell {
+        let mut input = if i == 1 {
+            b_0.clone()
+        } else {
+            let b_prev = &out[out.len() - SHA256_OUTPUT_LEN..];
+            let mut xor = Vec::with_capacity(SHA256_OUTPUT_LEN);
+            for (a, b) in b_0.iter().zip(b_prev.iter()) {
+                xor.push(xor_byte(range, ctx, a, b)?);
+            }
+            xor
+        };
+        input.extend(load_bytes(gate, ctx, &[&[i as u8][..], &dst_prime].concat())?);
+        out.extend(sha256(range, ctx, &input)?);
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+// the integer with the big-endian `bytes` as a `CRTInteger` with the limbs of `fp_chip`, which must hold it;
+// `limb_bits` need not be a multiple of 8, so the limbs are recomposed from the bits of the bytes
+fn be_bytes_to_crt<F: FieldExt>(
+    fp_chip: &FpChip<F>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+) -> Result<CRTInteger<F>, Error> {
+    let gate = fp_chip.range.gate();
+    let mut bits = Vec::with_capacity(8 * bytes.len());
+    for byte in bytes.iter().rev() {
+        bits.extend(fp_chip.range.num_to_bits(ctx, byte, 8)?);
+    }
+    assert!(bits.len() <= fp_chip.limb_bits * fp_chip.num_limbs);
+    let mut limbs = Vec::with_capacity(fp_chip.num_limbs);
+    for limb_bits in bits.chunks(fp_chip.limb_bits) {
+        limbs.push(gate.bits_to_num(ctx, limb_bits, false)?);
+    }
+    while limbs.len() < fp_chip.num_limbs {
+        limbs.push(gate.load_zero(ctx)?);
+    }
+    let native =
+        OverflowInteger::evaluate(gate, &fp_chip.bigint_chip, ctx, &limbs, fp_chip.limb_bits)?;
+    let value = limbs.iter().rev().fold(Value::known(BigInt::zero()), |acc, limb| {
+        acc.zip(limb.value())
+            .map(|(acc, limb)| (acc << fp_chip.limb_bits) + BigInt::from(fe_to_biguint(limb)))
+    });
+    Ok(CRTInteger::construct(
+        OverflowInteger::construct(
+            limbs,
+            BigUint::one() << fp_chip.limb_bits,
+            fp_chip.limb_bits,
+            (BigUint::one() << bits.len()) - 1usize,
+        ),
+        native,
+        value,
+    ))
+}
+
+/// hash_to_field of RFC 9380 with expand_message_xmd over SHA-256: two elements of Fp2 from `msg` and `dst`, as
+/// in `expand_message_xmd`. The coordinates are reduced, but not necessarily less than p.
+pub fn hash_to_field<F: FieldExt>(
+    fp_chip: &FpChip<F>,
+    ctx: &mut Context<'_, F>,
+    msg: &[AssignedValue<F>],
+    dst: &[u8],
+) -> Result<[FqPoint<F>; 2], Error> {
+    let bytes = expand_message_xmd(&fp_chip.range, ctx, msg, dst, 4 * HASH_TO_FIELD_LEN)?;
+    // an element is `hi 2^256 + lo` for its first 16 bytes `hi` and its last 32 bytes `lo`
+    let shift = fp_chip.load_constant(ctx, BigInt::from((BigUint::one() << 256) % &fp_chip.p))?;
+    let mut coeffs: Vec<FpPoint<F>> = Vec::with_capacity(4);
+    for chunk in bytes.chunks(HASH_TO_FIELD_LEN) {
+        let hi = be_bytes_to_crt(fp_chip, ctx, &chunk[..HASH_TO_FIELD_LEN - 32])?;
+        let lo = be_bytes_to_crt(fp_chip, ctx, &chunk[HASH_TO_FIELD_LEN - 32..])?;
+        let e = fp_chip.mul_no_carry(ctx, &hi, &shift)?;
+        let e = fp_chip.add_no_carry(ctx, &e, &lo)?;
+        coeffs.push(fp_chip.carry_mod(ctx, &e)?);
+    }
+    let u_1 = FieldExtPoint::construct(coeffs.split_off(2));
+    let u_0 = FieldExtPoint::construct(coeffs);
+    Ok([u_0, u_1])
+}
+
+// sgn0 of RFC 9380 for Fp2, see `sgn0`
+fn sgn0_assigned<F: FieldExt>(
+    fp_chip: &FpChip<F>,
+    ctx: &mut Context<'_, F>,
+    a: &FqPoint<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let mut parities = Vec::with_capacity(2);
+    for coeff in a.coeffs.iter() {
+        // the parity of `coeff` is that of its canonical representative
+        fp_chip.enforce_less_than_p(ctx, coeff)?;
+        let (_, parity) = fp_chip.range.div_mod(
+            ctx,
+            &Existing(&coeff.truncation.limbs[0]),
+            &BigUint::from(2u64),
+            fp_chip.limb_bits,
+        )?;
+        parities.push(parity);
+    }
+    let a0_is_zero = fp_chip.is_zero(ctx, &a.coeffs[0])?;
+    fp_chip.range.gate().or_and(
+        ctx,
+        &Existing(&parities[0]),
+        &Existing(&a0_is_zero),
+        &Existing(&parities[1]),
+    )
+}
+
+// returns whether `a` is a square in Fp2, where the prover supplies a square root of `a` if it is a square and of
+// `xi a` otherwise. For `a = 0` either claim has a root, but the right side of the twist is never 0, as `x^3 + b`
+// has no root in Fp2.
+fn is_square_assigned<F: FieldExt>(
+    fp2_chip: &Fp2Chip<F>,
+    ctx: &mut Context<'_, F>,
+    a: &FqPoint<F>,
+    xi_assigned: &FqPoint<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let gate = fp2_chip.fp_chip.range.gate();
+    let a_val = Fp2Chip::<F>::get_assigned_value(a);
+    let is_square_val = a_val.map(|a| is_square(&a));
+    let root_val = a_val.zip(is_square_val).map(|(a, is_sq)| {
+        if is_sq {
+            a.sqrt().unwrap()
+        } else {
+            (xi() * a).sqrt().unwrap()
+        }
+    });
+
+    let is_sq = gate
+        .assign_region_smart(
+            ctx,
+            vec![Witness(is_square_val.map(|is_sq| F::from(is_sq as u64)))],
+            vec![],
+            vec![],
+            vec![],
+        )?
+        .pop()
+        .unwrap();
+    gate.assert_bit(ctx, &is_sq)?;
+    let root = fp2_chip.load_private(ctx, Fp2Chip::<F>::fe_to_witness(&root_val))?;
+    fp2_chip.range_check(ctx, &root)?;
+
+    let xi_a = fp2_chip.mul(ctx, xi_assigned, a)?;
+    let target = fp2_chip.select(ctx, a, &xi_a, &is_sq)?;
+    let root_sq = fp2_chip.mul_no_carry(ctx, &root, &root)?;
+    let diff = fp2_chip.sub_no_carry(ctx, &root_sq, &target)?;
+    fp2_chip.check_carry_mod_to_zero(ctx, &diff)?;
+    Ok(is_sq)
+}
+
+/// The Shallue-van de Woestijne map of RFC 9380, F.1, from Fp2 to the twist. The output is on the twist, but not
+/// necessarily in G2.
+pub fn map_to_g2<F: FieldExt>(
+    fp_chip: &FpChip<F>,
+    ctx: &mut Context<'_, F>,
+    u: &FqPoint<F>,
+) -> Result<G2Point<F>, Error> {
+    let fp2_chip = Fp2Chip::construct(fp_chip);
+    let gate = fp_chip.range.gate();
+    let mut constants = Vec::with_capacity(5);
+    for c in svdw_constants() {
+        constants.push(fp2_chip.load_constant(ctx, c)?);
+    }
+    let [z, c1, c2, c3, c4]: [FqPoint<F>; 5] = constants.try_into().unwrap();
+    let one = fp2_chip.load_constant(ctx, Fq2::one())?;
+    let zero = fp2_chip.load_constant(ctx, Fq2::zero())?;
+    let xi_assigned = fp2_chip.load_constant(ctx, xi())?;
+    let b = fp2_chip.load_constant(ctx, convert_fp2(G2Affine::b()))?;
+    let g = |ctx: &mut Context<'_, F>, x: &FqPoint<F>| {
+        let x_sq = fp2_chip.mul(ctx, x, x)?;
+        let x_cube = fp2_chip.mul_no_carry(ctx, &x_sq, x)?;
+        let gx = fp2_chip.add_no_carry(ctx, &x_cube, &b)?;
+        fp2_chip.carry_mod(ctx, &gx)
+    };
+
+    let tv1 = fp2_chip.mul(ctx, u, u)?;
+    let tv1 = fp2_chip.mul(ctx, &tv1, &c1)?;
+    let tv2 = fp2_chip.add_no_carry(ctx, &one, &tv1)?;
+    let tv2 = fp2_chip.carry_mod(ctx, &tv2)?;
+    let tv1 = fp2_chip.sub_no_carry(ctx, &one, &tv1)?;
+    let tv1 = fp2_chip.carry_mod(ctx, &tv1)?;
+    let tv3 = fp2_chip.mul(ctx, &tv1, &tv2)?;
+    // inv0(tv3), which is 0 for tv3 = 0
+    let tv3_is_zero = fp2_chip.is_zero(ctx, &tv3)?;
+    let tv3 = fp2_chip.select(ctx, &one, &tv3, &tv3_is_zero)?;
+    let tv3 = fp2_chip.divide(ctx, &one, &tv3)?;
+    let tv3 = fp2_chip.select(ctx, &zero, &tv3, &tv3_is_zero)?;
+    let tv4 = fp2_chip.mul(ctx, u, &tv1)?;
+    let tv4 = fp2_chip.mul(ctx, &tv4, &tv3)?;
+    let tv4 = fp2_chip.mul(ctx, &tv4, &c3)?;
+
+    let x1 = fp2_chip.sub_no_carry(ctx, &c2, &tv4)?;
+    let x1 = fp2_chip.carry_mod(ctx, &x1)?;
+    let x2 = fp2_chip.add_no_carry(ctx, &c2, &tv4)?;
+    let x2 = fp2_chip.carry_mod(ctx, &x2)?;
+    let x3 = fp2_chip.mul(ctx, &tv2, &tv2)?;
+    let x3 = fp2_chip.mul(ctx, &x3, &tv3)?;
+    let x3 = fp2_chip.mul(ctx, &x3, &x3)?;
+    let x3 = fp2_chip.mul_no_carry(ctx, &x3, &c4)?;
+    let x3 = fp2_chip.add_no_carry(ctx, &x3, &z)?;
+    let x3 = fp2_chip.carry_mod(ctx, &x3)?;
+
+    let gx1 = g(ctx, &x1)?;
+    let e1 = is_square_assigned(&fp2_chip, ctx, &gx1, &xi_assigned)?;
+    let gx2 = g(ctx, &x2)?;
+    let e2 = is_square_assigned(&fp2_chip, ctx, &gx2, &xi_assigned)?;
+    let x = fp2_chip.select(ctx, &x2, &x3, &e2)?;
+    let x = fp2_chip.select(ctx, &x1, &x, &e1)?;
+
+    // the square root of g(x) with the sign of u
+    let gx = g(ctx, &x)?;
+    let y_val = Fp2Chip::<F>::get_assigned_value(&gx).zip(Fp2Chip::<F>::get_assigned_value(u)).map(
+        |(gx, u)| {
+            let y = gx.sqrt().unwrap();
+            if sgn0(&u) != sgn0(&y) {
+                -y
+            } else {
+                y
+            }
+        },
+    );
+    let y = fp2_chip.load_private(ctx, Fp2Chip::<F>::fe_to_witness(&y_val))?;
+    fp2_chip.range_check(ctx, &y)?;
+    let y_sq = fp2_chip.mul_no_carry(ctx, &y, &y)?;
+    let diff = fp2_chip.sub_no_carry(ctx, &y_sq, &gx)?;
+    fp2_chip.check_carry_mod_to_zero(ctx, &diff)?;
+    let sgn0_u = sgn0_assigned(fp_chip, ctx, u)?;
+    let sgn0_y = sgn0_assigned(fp_chip, ctx, &y)?;
+    gate.assert_equal(ctx, &Existing(&sgn0_u), &Existing(&sgn0_y))?;
+
+    Ok(G2Point::construct(x, y))
+}
+
+/// hash_to_curve of RFC 9380 to G2, see the module comment. The bytes of `msg` are range checked, and `dst` is a
+/// constant of the circuit of at most 255 bytes.
+pub fn hash_to_g2<F: FieldExt>(
+    fp_chip: &FpChip<F>,
+    ctx: &mut Context<'_, F>,
+    msg: &[AssignedValue<F>],
+    dst: &[u8],
+) -> Result<G2Point<F>, Error> {
+    let [u_0, u_1] = hash_to_field(fp_chip, ctx, msg, dst)?;
+    let Q_0 = map_to_g2(fp_chip, ctx, &u_0)?;
+    let Q_1 = map_to_g2(fp_chip, ctx, &u_1)?;
+    let g2_chip = G2Chip::construct(fp_chip);
+    // `Q_0 = -Q_1` or `Q_0 = Q_1` needs a collision of the map, so the addition is strict
+    let R = g2_chip.add_unequal(ctx, &Q_0, &Q_1, true)?;
+    g2_chip.clear_cofactor(ctx, &R)
+}
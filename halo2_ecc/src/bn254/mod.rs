@@ -4,8 +4,12 @@ use crate::{
 };
 use halo2curves::bn256::{Fq, Fq12, Fq2, Fq6};
 
+pub mod bls;
 pub mod final_exp;
+pub mod g2;
+pub mod hash_to_curve;
 pub mod pairing;
+pub mod precompiles;
 
 type FpChip<F> = fp::FpConfig<F, Fq>;
 type FpPoint<F> = CRTInteger<F>;
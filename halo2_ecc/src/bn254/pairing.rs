@@ -1,5 +1,5 @@
 #![allow(non_snake_case)]
-use super::{Fp12Chip, Fp2Chip, FpChip, FpPoint, FqPoint};
+use super::{g2::G2Chip, Fp12Chip, Fp2Chip, FpChip, FpPoint, FqPoint};
 use crate::{
     ecc::{EccChip, EccPoint},
     fields::{fp::FpStrategy, fp12::mul_no_carry_w6},
@@ -7,7 +7,7 @@ use crate::{
 };
 use halo2_base::{
     utils::{biguint_to_fe, fe_to_biguint},
-    Context,
+    Context, ContextId,
 };
 use halo2_proofs::{
     arithmetic::FieldExt,
@@ -15,7 +15,7 @@ use halo2_proofs::{
     halo2curves::bn256::{self, G1Affine, G2Affine, SIX_U_PLUS_2_NAF},
     plonk::{ConstraintSystem, Error},
 };
-use halo2curves::bn256::{Fq, Fq2, FROBENIUS_COEFF_FQ12_C1};
+use halo2curves::bn256::{Fq, FROBENIUS_COEFF_FQ12_C1};
 use num_bigint::{BigInt, BigUint};
 use num_traits::Num;
 
@@ -85,17 +85,17 @@ pub fn sparse_line_function_equal<F: FieldExt>(
     let x_sq = fp2_chip.mul(ctx, x, x)?;
 
     let x_cube = fp2_chip.mul_no_carry(ctx, &x_sq, x)?;
-    let three_x_cu = fp2_chip.scalar_mul_no_carry(ctx, &x_cube, F::from(3))?;
+    let three_x_cu = fp2_chip.mul_u64_no_carry(ctx, &x_cube, 3)?;
     let y_sq = fp2_chip.mul_no_carry(ctx, y, y)?;
-    let two_y_sq = fp2_chip.scalar_mul_no_carry(ctx, &y_sq, F::from(2))?;
+    let two_y_sq = fp2_chip.mul_u64_no_carry(ctx, &y_sq, 2)?;
     let out0_left = fp2_chip.sub_no_carry(ctx, &three_x_cu, &two_y_sq)?;
     let out0 = mul_no_carry_w6::<F, FpChip<F>, XI_0>(fp2_chip.fp_chip, ctx, &out0_left)?;
 
     let x_sq_Px = fp2_chip.fp_mul_no_carry(ctx, &x_sq, &P.x)?;
-    let out4 = fp2_chip.scalar_mul_no_carry(ctx, &x_sq_Px, -F::from(3))?;
+    let out4 = fp2_chip.mul_small_signed_no_carry(ctx, &x_sq_Px, -3)?;
 
     let y_Py = fp2_chip.fp_mul_no_carry(ctx, y, &P.y)?;
-    let out3 = fp2_chip.scalar_mul_no_carry(ctx, &y_Py, F::from(2))?;
+    let out3 = fp2_chip.mul_u64_no_carry(ctx, &y_Py, 2)?;
 
     // so far we have not "carried mod p" for any of the outputs
     // we do this below
@@ -370,7 +370,7 @@ impl<'a, F: FieldExt> PairingChip<'a, F> {
         lookup_bits: usize,
         limb_bits: usize,
         num_limbs: usize,
-        context_id: String,
+        context_id: ContextId,
     ) -> FpChip<F> {
         FpChip::configure(
             meta,
@@ -403,17 +403,7 @@ impl<'a, F: FieldExt> PairingChip<'a, F> {
         ctx: &mut Context<'_, F>,
         point: Value<G2Affine>,
     ) -> Result<EccPoint<F, FieldExtPoint<FpPoint<F>>>, Error> {
-        let fp2_chip = Fp2Chip::construct(self.fp_chip);
-        let g2_chip = EccChip::construct(&fp2_chip);
-        // go from pse/pairing::bn256::Fq2 to forked public Fq2
-        let convert_fp2 = |c0: bn256::Fq, c1: bn256::Fq| Fq2 {
-            c0: biguint_to_fe(&fe_to_biguint(&c0)),
-            c1: biguint_to_fe(&fe_to_biguint(&c1)),
-        };
-        let x = point.map(|pt| convert_fp2(pt.x.c0, pt.x.c1));
-        let y = point.map(|pt| convert_fp2(pt.y.c0, pt.y.c1));
-
-        g2_chip.load_private(ctx, (x, y))
+        G2Chip::construct(self.fp_chip).load_private(ctx, point)
     }
 
     pub fn miller_loop(
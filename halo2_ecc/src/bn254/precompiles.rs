@@ -0,0 +1,442 @@
+// EVM precompiles of BN254: ECADD and ECMUL of EIP-196 and ECPAIRING of EIP-197
+//
+// Each precompile takes its input bytes and returns `(success, output)` instead of constraining the input to be valid.
+// `success` is 0 when a coordinate is not less than p, a point is not on its curve, a G2 point is not in the subgroup
+// of order r, or the ECPAIRING input is not a multiple of 192 bytes; the output is then all zero, as the EVM returns
+// no data from a failed precompile. Points are encoded as in the EIPs: 32 big-endian bytes per coordinate, an Fp2
+// element `a * u + b` as `a` followed by `b`, and the point at infinity as all zeros. As in the EVM, ECADD and ECMUL
+// pad a short input with zeros and ignore the bytes after their 128 and 96 bytes.
+//
+// Invalid points are replaced by a generator before any group operation, so a bad input never makes the circuit
+// unsatisfiable. The exception is the subgroup check of a G2 point on the twist whose order is not a multiple of r,
+// which may hit an exceptional case of `scalar_multiply`; such points have no component in the subgroup at all.
+#![allow(non_snake_case)]
+
+use super::{
+    g2::{convert_fp2, G2Chip},
+    pairing::PairingChip,
+    Fp12Chip, FpChip, FpPoint, FqPoint,
+};
+use crate::{
+    bigint::{big_less_than, CRTInteger, OverflowInteger},
+    ecc::{self, EccChip, EccPoint},
+    fields::{FieldChip, FieldExtPoint, Selectable},
+    uint256::Uint256Chip,
+};
+use group::prime::PrimeCurveAffine;
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    safe_types::SafeBytes32,
+    utils::{biguint_to_fe, fe_to_biguint, modulus},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field, FieldExt},
+    circuit::Value,
+    halo2curves::bn256::{self, G1Affine, G2Affine},
+    plonk::Error,
+};
+use halo2curves::bn256::Fq12;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Zero};
+
+pub const ECADD_INPUT_LEN: usize = 128;
+pub const ECMUL_INPUT_LEN: usize = 96;
+pub const ECPAIRING_PAIR_LEN: usize = 192;
+
+const WINDOW_BITS: usize = 4;
+// scalars are reduced to at most (r - 1) / 2 < 2^253 before `scalar_multiply`
+const SCALAR_BITS: usize = 253;
+
+// returns `(P is on y^2 = x^3 + b, P = (0, 0))` for `P` with coordinates less than p
+pub(crate) fn curve_flags<F: FieldExt, FC: FieldChip<F>>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &EccPoint<F, FC::FieldPoint>,
+    b: &FC::FieldPoint,
+) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+    let lhs = chip.mul_no_carry(ctx, &P.y, &P.y)?;
+    let mut rhs = chip.mul(ctx, &P.x, &P.x)?;
+    rhs = chip.mul_no_carry(ctx, &rhs, &P.x)?;
+    rhs = chip.add_no_carry(ctx, &rhs, b)?;
+    let mut diff = chip.sub_no_carry(ctx, &lhs, &rhs)?;
+    diff = chip.carry_mod(ctx, &diff)?;
+    let is_on_curve = chip.is_zero(ctx, &diff)?;
+
+    let x_is_zero = chip.is_zero(ctx, &P.x)?;
+    let y_is_zero = chip.is_zero(ctx, &P.y)?;
+    let is_infinity = chip.range().gate().and(ctx, &Existing(&x_is_zero), &Existing(&y_is_zero))?;
+    Ok((is_on_curve, is_infinity))
+}
+
+pub struct PrecompileChip<'a, F: FieldExt> {
+    pub fp_chip: &'a FpChip<F>,
+}
+
+impl<'a, F: FieldExt> PrecompileChip<'a, F> {
+    pub fn construct(fp_chip: &'a FpChip<F>) -> Self {
+        Self { fp_chip }
+    }
+
+    // the first `len` bytes of `input`, padded with zeros
+    fn pad_input(
+        &self,
+        ctx: &mut Context<'_, F>,
+        input: &[AssignedValue<F>],
+        len: usize,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let mut bytes: Vec<_> = input.iter().take(len).cloned().collect();
+        if bytes.len() < len {
+            let zero = self.fp_chip.range.gate().load_zero(ctx)?;
+            bytes.resize(len, zero);
+        }
+        Ok(bytes)
+    }
+
+    // returns the field element with the 32 big-endian bytes `bytes` and whether it is less than p;
+    // an element which is not less than p is replaced by 0, so that the field chip can use it
+    fn load_fp(
+        &self,
+        ctx: &mut Context<'_, F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Result<(FpPoint<F>, AssignedValue<F>), Error> {
+        assert_eq!(bytes.len(), 32);
+        let chip = self.fp_chip;
+        let gate = chip.range.gate();
+
+        // `limb_bits` need not be a multiple of 8, so the limbs are recomposed from the bits of the bytes
+        let mut bits = Vec::with_capacity(256);
+        for byte in bytes.iter().rev() {
+            bits.extend(chip.range.num_to_bits(ctx, byte, 8)?);
+        }
+        assert!(bits.len() <= chip.limb_bits * chip.num_limbs);
+        let mut limbs = Vec::with_capacity(chip.num_limbs);
+        for limb_bits in bits.chunks(chip.limb_bits) {
+            limbs.push(gate.bits_to_num(ctx, limb_bits, false)?);
+        }
+        while limbs.len() < chip.num_limbs {
+            limbs.push(gate.load_zero(ctx)?);
+        }
+        let a = OverflowInteger::construct(
+            limbs,
+            BigUint::one() << chip.limb_bits,
+            chip.limb_bits,
+            (BigUint::one() << 256) - 1usize,
+        );
+        let in_field = big_less_than::assign_constant(&chip.range, ctx, &a, &chip.p)?;
+
+        let mut limbs = Vec::with_capacity(chip.num_limbs);
+        for limb in a.limbs.iter() {
+            limbs.push(gate.mul(ctx, &Existing(limb), &Existing(&in_field))?);
+        }
+        let native =
+            OverflowInteger::evaluate(gate, &chip.bigint_chip, ctx, &limbs, chip.limb_bits)?;
+        let value = limbs.iter().rev().fold(Value::known(BigInt::zero()), |acc, limb| {
+            acc.zip(limb.value())
+                .map(|(acc, limb)| (acc << chip.limb_bits) + BigInt::from(fe_to_biguint(limb)))
+        });
+        let a = CRTInteger::construct(
+            OverflowInteger::construct(
+                limbs,
+                BigUint::one() << chip.limb_bits,
+                chip.limb_bits,
+                &chip.p - 1usize,
+            ),
+            native,
+            value,
+        );
+        Ok((a, in_field))
+    }
+
+    // the 32 big-endian bytes of `a`, after constraining `a < p`
+    fn fp_to_be_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &FpPoint<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let chip = self.fp_chip;
+        chip.enforce_less_than_p(ctx, a)?;
+        let mut bits = Vec::with_capacity(chip.limb_bits * chip.num_limbs);
+        for limb in a.truncation.limbs.iter() {
+            bits.extend(chip.range.num_to_bits(ctx, limb, chip.limb_bits)?);
+        }
+        // `a < p < 2^256`, so the bits from 256 on are zero
+        let mut bytes = Vec::with_capacity(32);
+        for byte_bits in bits[..256].chunks(8).rev() {
+            bytes.push(chip.range.gate().bits_to_num(ctx, byte_bits, false)?);
+        }
+        Ok(bytes)
+    }
+
+    fn g1_to_be_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &EccPoint<F, FpPoint<F>>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let mut bytes = self.fp_to_be_bytes(ctx, &P.x)?;
+        bytes.extend(self.fp_to_be_bytes(ctx, &P.y)?);
+        Ok(bytes)
+    }
+
+    pub(crate) fn load_g1_constant(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: G1Affine,
+    ) -> Result<EccPoint<F, FpPoint<F>>, Error> {
+        let x = self.fp_chip.load_constant(ctx, BigInt::from(fe_to_biguint(&P.x)))?;
+        let y = self.fp_chip.load_constant(ctx, BigInt::from(fe_to_biguint(&P.y)))?;
+        Ok(EccPoint::construct(x, y))
+    }
+
+    // the point at infinity, encoded as (0, 0)
+    fn load_g1_infinity(&self, ctx: &mut Context<'_, F>) -> Result<EccPoint<F, FpPoint<F>>, Error> {
+        let x = self.fp_chip.load_constant(ctx, BigInt::zero())?;
+        let y = self.fp_chip.load_constant(ctx, BigInt::zero())?;
+        Ok(EccPoint::construct(x, y))
+    }
+
+    /// Loads a G1 point from its 64 bytes and returns it with `(is_valid, is_infinity)`.
+    /// The coordinates which are not less than p are replaced by 0.
+    pub fn load_g1(
+        &self,
+        ctx: &mut Context<'_, F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Result<(EccPoint<F, FpPoint<F>>, AssignedValue<F>, AssignedValue<F>), Error> {
+        assert_eq!(bytes.len(), 64);
+        let gate = self.fp_chip.range.gate();
+        let (x, x_in_field) = self.load_fp(ctx, &bytes[..32])?;
+        let (y, y_in_field) = self.load_fp(ctx, &bytes[32..])?;
+        let P = EccPoint::construct(x, y);
+
+        let b = self.fp_chip.load_constant(ctx, BigInt::from(fe_to_biguint(&G1Affine::b())))?;
+        let (is_on_curve, is_infinity) = curve_flags(self.fp_chip, ctx, &P, &b)?;
+        // G1 has cofactor 1, so every point on the curve is in the subgroup
+        let in_field = gate.and(ctx, &Existing(&x_in_field), &Existing(&y_in_field))?;
+        let is_valid = gate.or(ctx, &Existing(&is_on_curve), &Existing(&is_infinity))?;
+        let is_valid = gate.and(ctx, &Existing(&is_valid), &Existing(&in_field))?;
+        Ok((P, is_valid, is_infinity))
+    }
+
+    /// Loads a G2 point from its 128 bytes and returns it with `(is_valid, is_infinity)`, where a valid point
+    /// other than the infinity is on the twist and in the subgroup of order r.
+    /// The coordinates which are not less than p are replaced by 0.
+    pub fn load_g2(
+        &self,
+        ctx: &mut Context<'_, F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Result<(EccPoint<F, FqPoint<F>>, AssignedValue<F>, AssignedValue<F>), Error> {
+        assert_eq!(bytes.len(), 128);
+        let gate = self.fp_chip.range.gate();
+        let mut coords = Vec::with_capacity(4);
+        let mut in_field = None;
+        for coord_bytes in bytes.chunks(32) {
+            let (coord, coord_in_field) = self.load_fp(ctx, coord_bytes)?;
+            in_field = Some(match in_field {
+                None => coord_in_field,
+                Some(acc) => gate.and(ctx, &Existing(&acc), &Existing(&coord_in_field))?,
+            });
+            coords.push(coord);
+        }
+        let in_field = in_field.unwrap();
+        let [x_c1, x_c0, y_c1, y_c0]: [FpPoint<F>; 4] = coords.try_into().unwrap();
+        let Q = EccPoint::construct(
+            FieldExtPoint::construct(vec![x_c0, x_c1]),
+            FieldExtPoint::construct(vec![y_c0, y_c1]),
+        );
+
+        let g2_chip = G2Chip::construct(self.fp_chip);
+        let b = g2_chip.fp2_chip.load_constant(ctx, convert_fp2(G2Affine::b()))?;
+        let (is_on_curve, is_infinity) = curve_flags(&g2_chip.fp2_chip, ctx, &Q, &b)?;
+
+        // the subgroup check needs a point on the twist other than the infinity
+        let is_finite = gate.not(ctx, &Existing(&is_infinity))?;
+        let is_usable = gate.and(ctx, &Existing(&is_on_curve), &Existing(&is_finite))?;
+        let generator = g2_chip.load_constant(ctx, G2Affine::generator())?;
+        let Q_safe = ecc::select(&g2_chip.fp2_chip, ctx, &Q, &generator, &is_usable)?;
+        let in_subgroup = g2_chip.is_in_subgroup(ctx, &Q_safe)?;
+
+        let is_valid = gate.or_and(
+            ctx,
+            &Existing(&is_infinity),
+            &Existing(&is_usable),
+            &Existing(&in_subgroup),
+        )?;
+        let is_valid = gate.and(ctx, &Existing(&is_valid), &Existing(&in_field))?;
+        Ok((Q, is_valid, is_infinity))
+    }
+
+    /// ECADD (0x06): the sum of the two G1 points in the first 128 bytes of `input`, as 64 bytes
+    pub fn ec_add(
+        &self,
+        ctx: &mut Context<'_, F>,
+        input: &[AssignedValue<F>],
+    ) -> Result<(AssignedValue<F>, Vec<AssignedValue<F>>), Error> {
+        let chip = self.fp_chip;
+        let gate = chip.range.gate();
+        let ecc_chip = EccChip::construct(chip);
+        let input = self.pad_input(ctx, input, ECADD_INPUT_LEN)?;
+        let (P, P_is_valid, P_is_infinity) = self.load_g1(ctx, &input[..64])?;
+        let (Q, Q_is_valid, Q_is_infinity) = self.load_g1(ctx, &input[64..])?;
+        let success = gate.and(ctx, &Existing(&P_is_valid), &Existing(&Q_is_valid))?;
+
+        let x_is_equal = chip.is_equal(ctx, &P.x, &Q.x)?;
+        let y_is_equal = chip.is_equal(ctx, &P.y, &Q.y)?;
+        let some_infinity = gate.or(ctx, &Existing(&P_is_infinity), &Existing(&Q_is_infinity))?;
+        let is_finite = gate.not(ctx, &Existing(&some_infinity))?;
+        let x_is_distinct = gate.not(ctx, &Existing(&x_is_equal))?;
+        let is_unequal = gate.and(ctx, &Existing(&is_finite), &Existing(&x_is_distinct))?;
+        // doubling needs `y != 0`, which holds on the curve, so an invalid point is never doubled
+        let is_double = gate.and(ctx, &Existing(&x_is_equal), &Existing(&y_is_equal))?;
+        let is_double = gate.and(ctx, &Existing(&is_double), &Existing(&is_finite))?;
+        let is_double = gate.and(ctx, &Existing(&is_double), &Existing(&success))?;
+
+        // `add_unequal` and `double` only see the inputs when they have no exceptional case,
+        // and otherwise (0, 0) + (1, 2) and 2 * (1, 2)
+        let infinity = self.load_g1_infinity(ctx)?;
+        let generator = self.load_g1_constant(ctx, G1Affine::generator())?;
+        let P_add = ecc::select(chip, ctx, &P, &infinity, &is_unequal)?;
+        let Q_add = ecc::select(chip, ctx, &Q, &generator, &is_unequal)?;
+        let sum = ecc_chip.add_unequal(ctx, &P_add, &Q_add, false)?;
+        let P_double = ecc::select(chip, ctx, &P, &generator, &is_double)?;
+        let double = ecc_chip.double(ctx, &P_double)?;
+
+        // the remaining case is P = -Q, with sum the infinity
+        let mut out = ecc::select(chip, ctx, &sum, &infinity, &is_unequal)?;
+        out = ecc::select(chip, ctx, &double, &out, &is_double)?;
+        out = ecc::select(chip, ctx, &P, &out, &Q_is_infinity)?;
+        out = ecc::select(chip, ctx, &Q, &out, &P_is_infinity)?;
+        out = ecc::select(chip, ctx, &out, &infinity, &success)?;
+        Ok((success, self.g1_to_be_bytes(ctx, &out)?))
+    }
+
+    /// ECMUL (0x07): the product of the G1 point in the first 64 bytes of `input` by the 256-bit big-endian scalar
+    /// in the next 32 bytes, as 64 bytes. The scalar may be any 256-bit number, including multiples of r.
+    pub fn ec_mul(
+        &self,
+        ctx: &mut Context<'_, F>,
+        input: &[AssignedValue<F>],
+    ) -> Result<(AssignedValue<F>, Vec<AssignedValue<F>>), Error> {
+        assert!(F::NUM_BITS as usize > SCALAR_BITS);
+        let chip = self.fp_chip;
+        let gate = chip.range.gate();
+        let ecc_chip = EccChip::construct(chip);
+        let uint_chip = Uint256Chip::construct(&chip.range);
+        let input = self.pad_input(ctx, input, ECMUL_INPUT_LEN)?;
+        let (P, success, P_is_infinity) = self.load_g1(ctx, &input[..64])?;
+
+        // `s (mod r)`, then `t = min(s, r - s)` so that `scalar_multiply` has no exceptional cases: every partial
+        // sum is `[k]P` with `0 < k < r / 2`, and the output is negated back when `t = r - s`
+        let scalar = SafeBytes32::new(&chip.range, ctx, input[64..].to_vec())?;
+        let scalar = uint_chip.from_be_bytes(ctx, &scalar)?;
+        let r = modulus::<bn256::Fr>();
+        let r_word = uint_chip.load_constant(ctx, &r)?;
+        let zero_word = uint_chip.load_constant(ctx, &BigUint::zero())?;
+        let half_word = uint_chip.load_constant(ctx, &((&r + 1usize) >> 1))?;
+        let s = uint_chip.addmod(ctx, &scalar, &zero_word, &r_word)?;
+        let (is_low, _) = uint_chip.compare(ctx, &s, &half_word)?;
+        let neg_s = uint_chip.sub(ctx, &r_word, &s)?;
+        let mut t_limbs = Vec::with_capacity(s.limbs.len());
+        for (s_limb, neg_s_limb) in s.limbs.iter().zip(neg_s.limbs.iter()) {
+            t_limbs.push(gate.select(
+                ctx,
+                &Existing(s_limb),
+                &Existing(neg_s_limb),
+                &Existing(&is_low),
+            )?);
+        }
+        let pows = (0..t_limbs.len())
+            .map(|i| Constant(biguint_to_fe(&(BigUint::one() << (s.limb_bits * i)))))
+            .collect();
+        let t = gate.inner_product(ctx, &t_limbs.iter().map(Existing).collect(), &pows)?;
+        let t_is_zero = chip.range.is_zero(ctx, &t)?;
+
+        let is_finite = gate.not(ctx, &Existing(&P_is_infinity))?;
+        let is_usable = gate.and(ctx, &Existing(&success), &Existing(&is_finite))?;
+        let generator = self.load_g1_constant(ctx, G1Affine::generator())?;
+        let P_safe = ecc::select(chip, ctx, &P, &generator, &is_usable)?;
+        let tP = ecc_chip.scalar_mult(ctx, &P_safe, &vec![t], SCALAR_BITS, WINDOW_BITS)?;
+
+        let infinity = self.load_g1_infinity(ctx)?;
+        let is_infinity = gate.or(ctx, &Existing(&t_is_zero), &Existing(&P_is_infinity))?;
+        let is_high = gate.not(ctx, &Existing(&is_low))?;
+        let mut out = ecc_chip.select_neg(ctx, &tP, &is_high)?;
+        out = ecc::select(chip, ctx, &infinity, &out, &is_infinity)?;
+        out = ecc::select(chip, ctx, &out, &infinity, &success)?;
+        Ok((success, self.g1_to_be_bytes(ctx, &out)?))
+    }
+
+    /// ECPAIRING (0x08): whether the product of the pairings of the (G1, G2) pairs of 192 bytes in `input` is 1,
+    /// as the 32 big-endian bytes of 0 or 1. The empty input has product 1.
+    pub fn ec_pairing(
+        &self,
+        ctx: &mut Context<'_, F>,
+        input: &[AssignedValue<F>],
+    ) -> Result<(AssignedValue<F>, Vec<AssignedValue<F>>), Error> {
+        let chip = self.fp_chip;
+        let gate = chip.range.gate();
+        let zero = gate.load_zero(ctx)?;
+        if input.len() % ECPAIRING_PAIR_LEN != 0 {
+            // the length of the input is fixed by the circuit, so the precompile always fails
+            return Ok((zero.clone(), vec![zero; 32]));
+        }
+
+        let fp12_chip = Fp12Chip::construct(chip);
+        let pairing_chip = PairingChip::construct(chip);
+        let one = fp12_chip.load_constant(ctx, Fq12::one())?;
+        let mut success = None;
+        let mut f: Option<FqPoint<F>> = None;
+        for pair in input.chunks(ECPAIRING_PAIR_LEN) {
+            let (P, P_is_valid, P_is_infinity) = self.load_g1(ctx, &pair[..64])?;
+            let (Q, Q_is_valid, Q_is_infinity) = self.load_g2(ctx, &pair[64..])?;
+            let is_valid = gate.and(ctx, &Existing(&P_is_valid), &Existing(&Q_is_valid))?;
+            success = Some(match success {
+                None => is_valid,
+                Some(acc) => gate.and(ctx, &Existing(&acc), &Existing(&is_valid))?,
+            });
+
+            // the Miller loop divides by coordinates of multiples of Q, so Q must be in the subgroup and not the
+            // infinity; P only enters through the line functions and is used as is
+            let is_finite = gate.not(ctx, &Existing(&Q_is_infinity))?;
+            let is_usable = gate.and(ctx, &Existing(&Q_is_valid), &Existing(&is_finite))?;
+            let g2_chip = G2Chip::construct(chip);
+            let generator = g2_chip.load_constant(ctx, G2Affine::generator())?;
+            let Q_safe = ecc::select(&g2_chip.fp2_chip, ctx, &Q, &generator, &is_usable)?;
+            let f_pair = pairing_chip.miller_loop(ctx, &Q_safe, &P)?;
+
+            // a pair with the point at infinity contributes 1
+            let is_trivial = gate.or(ctx, &Existing(&P_is_infinity), &Existing(&Q_is_infinity))?;
+            let mut coeffs = Vec::with_capacity(f_pair.coeffs.len());
+            for (one_coeff, f_coeff) in one.coeffs.iter().zip(f_pair.coeffs.iter()) {
+                coeffs.push(chip.select(ctx, one_coeff, f_coeff, &is_trivial)?);
+            }
+            let f_pair = FieldExtPoint::construct(coeffs);
+            f = Some(match f {
+                None => f_pair,
+                Some(f) => fp12_chip.mul(ctx, &f, &f_pair)?,
+            });
+        }
+
+        let mut output = vec![zero; 31];
+        match (success, f) {
+            (Some(success), Some(f)) => {
+                let f = fp12_chip.final_exp(ctx, &f)?;
+                let is_one = fp12_chip.is_equal(ctx, &f, &one)?;
+                output.push(gate.and(ctx, &Existing(&success), &Existing(&is_one))?);
+                Ok((success, output))
+            }
+            _ => {
+                let mut one = gate.assign_region_smart(
+                    ctx,
+                    vec![Constant(F::one())],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let one = one.pop().unwrap();
+                output.push(one.clone());
+                Ok((one, output))
+            }
+        }
+    }
+}
@@ -1,24 +1,36 @@
 #![allow(non_snake_case)]
 use ark_std::{end_timer, start_timer};
 use group::Curve;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::RefCell;
 use std::io::Write;
 use std::marker::PhantomData;
 
+use super::bls::verify_aggregate;
+use super::g2::G2Chip;
+use super::hash_to_curve::{
+    expand_message_xmd_native, hash_to_field_native, hash_to_g2, hash_to_g2_native,
+    map_to_g2_native,
+};
 use super::pairing::PairingChip;
+use super::precompiles::PrecompileChip;
 use super::*;
-use crate::{ecc::EccChip, fields::fp::FpStrategy};
+use crate::{
+    ecc::EccChip,
+    fields::{fp::FpStrategy, FieldChip},
+};
+use group::prime::PrimeCurveAffine;
 use halo2_base::{
-    gates::GateInstructions,
+    gates::{params::CircuitParams, GateInstructions},
     utils::{biguint_to_fe, fe_to_biguint, value_to_option},
-    Context, ContextParams,
+    AssignedValue, Context, ContextId, ContextParams,
     QuantumCell::Witness,
 };
 use halo2_proofs::{
-    arithmetic::{Field, FieldExt},
+    arithmetic::{CurveAffine, Field, FieldExt},
     circuit::{Layouter, SimpleFloorPlanner, Value},
     dev::MockProver,
-    halo2curves::bn256::{pairing, Bn256, Fr, G1Affine, G2Affine},
+    halo2curves::bn256::{pairing, Bn256, Fq, Fq2, Fr, G1Affine, G2Affine},
     plonk::*,
     poly::commitment::{Params, ParamsProver},
     poly::kzg::{
@@ -32,7 +44,24 @@ use halo2_proofs::{
 use num_bigint::BigUint;
 use num_traits::Num;
 
-#[derive(Serialize, Deserialize)]
+const PAIRING_CONFIG_PATH: &str = "./src/bn254/configs/pairing_circuit.config";
+const MSM_CONFIG_PATH: &str = "./src/bn254/configs/msm_circuit.config";
+
+/// Reads circuit params from a JSON config file, for the tests which do not set them in code
+fn read_params<P: DeserializeOwned>(path: &str) -> P {
+    let params_str =
+        std::fs::read_to_string(path).expect(format!("{} file should exist", path).as_str());
+    serde_json::from_str(params_str.as_str()).unwrap()
+}
+
+thread_local! {
+    // halo2 has no circuit parameters at `configure`, so the circuits below keep their params in a field and store
+    // them here when they are created, as `RangeCircuitBuilder::new` does, for `configure` to read back
+    static PAIRING_PARAMS: RefCell<Option<PairingCircuitParams>> = RefCell::new(None);
+    static MSM_PARAMS: RefCell<Option<MSMCircuitParams>> = RefCell::new(None);
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct PairingCircuitParams {
     strategy: FpStrategy,
     degree: u32,
@@ -44,41 +73,58 @@ struct PairingCircuitParams {
     num_limbs: usize,
 }
 
-#[derive(Default)]
+impl PairingCircuitParams {
+    // stores the params for `configure` on this thread
+    fn register(self) -> Self {
+        PAIRING_PARAMS.with(|params| *params.borrow_mut() = Some(self.clone()));
+        self
+    }
+
+    fn registered() -> Self {
+        PAIRING_PARAMS
+            .with(|params| params.borrow().clone())
+            .expect("a circuit should be created with its params on this thread before configure")
+    }
+}
+
 struct PairingCircuit<F: FieldExt> {
+    params: PairingCircuitParams,
     P: Option<G1Affine>,
     Q: Option<G2Affine>,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> Circuit<F> for PairingCircuit<F> {
-    type Config = FpChip<F>;
-    type FloorPlanner = SimpleFloorPlanner; // V1;
-
-    fn without_witnesses(&self) -> Self {
-        Self::default()
-    }
-
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let mut folder = std::path::PathBuf::new();
-        folder.push("./src/bn254");
-        folder.push("configs/pairing_circuit.config");
-        let params_str = std::fs::read_to_string(folder.as_path())
-            .expect("src/bn254/configs/pairing_circuit.config file should exist");
-        let params: PairingCircuitParams = serde_json::from_str(params_str.as_str()).unwrap();
-
+impl<F: FieldExt> PairingCircuit<F> {
+    /// Configures the pairing chip of the circuit without reading a config file
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: &PairingCircuitParams,
+    ) -> FpChip<F> {
         PairingChip::configure(
             meta,
-            params.strategy,
+            params.strategy.clone(),
             &[params.num_advice],
             &[params.num_lookup_advice],
             params.num_fixed,
             params.lookup_bits,
             params.limb_bits,
             params.num_limbs,
-            "default".to_string(),
+            ContextId::new("default"),
         )
     }
+}
+
+impl<F: FieldExt> Circuit<F> for PairingCircuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner; // V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self { params: self.params.clone(), P: None, Q: None, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Self::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
 
     fn synthesize(
         &self,
@@ -102,7 +148,7 @@ impl<F: FieldExt> Circuit<F> for PairingCircuit<F> {
                 let mut aux = Context::new(
                     region,
                     ContextParams {
-                        num_advice: vec![("default".to_string(), config.range.gate.num_advice)],
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
                     },
                 );
                 let ctx = &mut aux;
@@ -156,7 +202,7 @@ impl<F: FieldExt> Circuit<F> for PairingCircuit<F> {
                 // IMPORTANT: this assigns all constants to the fixed columns
                 // IMPORTANT: this copies cells to the lookup advice column to perform range check lookups
                 // This is not optional.
-                let (const_rows, total_fixed, _lookup_rows) = config.finalize(ctx)?;
+                let stats = config.finalize_with_stats(ctx)?;
 
                 #[cfg(feature = "display")]
                 if self.P != None {
@@ -168,34 +214,16 @@ impl<F: FieldExt> Circuit<F> for PairingCircuit<F> {
                     let num_limbs = config.num_limbs;
 
                     println!("Using:\nadvice columns: {}\nspecial lookup advice columns: {}\nfixed columns: {}\nlookup bits: {}\nlimb bits: {}\nnum limbs: {}", num_advice, num_lookup_advice, num_fixed, lookup_bits, limb_bits, num_limbs);
-                    let advice_rows = ctx.advice_rows["default"].iter();
-                    println!(
-                        "maximum rows used by an advice column: {}",
-                            advice_rows.clone().max().or(Some(&0)).unwrap(),
-                    );
-                    println!(
-                        "minimum rows used by an advice column: {}",
-                            advice_rows.clone().min().or(Some(&usize::MAX)).unwrap(),
-                    );
-                    let total_cells = advice_rows.sum::<usize>();
-                    println!("total cells used: {}", total_cells);
-                    println!("cells used in special lookup columns: {}", ctx.cells_to_lookup.len());
-                    println!("maximum rows used by a fixed column: {}", const_rows);
-
-                    println!("Suggestions:");
-                    let degree = lookup_bits + 1;
-                    println!(
-                        "Have you tried using {} advice columns?",
-                        (total_cells + (1 << degree) - 1) / (1 << degree)
-                    );
-                    println!(
-                        "Have you tried using {} lookup columns?",
-                        (ctx.cells_to_lookup.len() + (1 << degree) - 1) / (1 << degree)
-                    );
-                    println!(
-                        "Have you tried using {} fixed columns?",
-                        (total_fixed + (1 << degree) - 1) / (1 << degree)
+                    #[cfg(feature = "json-stats")]
+                    println!("{}", stats.to_json());
+
+                    // the fewest columns for the minimal degree, from the shape of this run
+                    let suggested = CircuitParams::for_degree(
+                        &stats.shape(),
+                        lookup_bits as u32 + 1,
+                        lookup_bits,
                     );
+                    println!("Suggested params: {:?}", suggested);
                 }
                 Ok(())
             }
@@ -203,7 +231,7 @@ impl<F: FieldExt> Circuit<F> for PairingCircuit<F> {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct MSMCircuitParams {
     strategy: FpStrategy,
     degree: u32,
@@ -217,6 +245,20 @@ struct MSMCircuitParams {
     window_bits: usize,
 }
 
+impl MSMCircuitParams {
+    // stores the params for `configure` on this thread
+    fn register(self) -> Self {
+        MSM_PARAMS.with(|params| *params.borrow_mut() = Some(self.clone()));
+        self
+    }
+
+    fn registered() -> Self {
+        MSM_PARAMS
+            .with(|params| params.borrow().clone())
+            .expect("a circuit should be created with its params on this thread before configure")
+    }
+}
+
 #[derive(Clone, Debug)]
 struct MSMConfig<F: FieldExt> {
     fp_chip: FpChip<F>,
@@ -237,7 +279,7 @@ impl<F: FieldExt> MSMConfig<F> {
         p: BigUint,
         batch_size: usize,
         window_bits: usize,
-        context_id: String,
+        context_id: ContextId,
     ) -> Self {
         let fp_chip = FpChip::<F>::configure(
             meta,
@@ -253,60 +295,55 @@ impl<F: FieldExt> MSMConfig<F> {
         );
         MSMConfig { fp_chip, batch_size, window_bits }
     }
+
+    /// Configures the MSM chip over the field with modulus `p` without reading a config file
+    fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: &MSMCircuitParams,
+        p: BigUint,
+    ) -> Self {
+        Self::configure(
+            meta,
+            params.strategy.clone(),
+            &[params.num_advice],
+            &[params.num_lookup_advice],
+            params.num_fixed,
+            params.lookup_bits,
+            params.limb_bits,
+            params.num_limbs,
+            p,
+            params.batch_size,
+            params.window_bits,
+            ContextId::new("default"),
+        )
+    }
 }
 
 struct MSMCircuit<F: FieldExt> {
+    params: MSMCircuitParams,
     bases: Vec<Option<G1Affine>>,
     scalars: Vec<Option<Fr>>,
-    batch_size: usize,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt> Default for MSMCircuit<F> {
-    fn default() -> Self {
-        Self {
-            bases: vec![None; 10],
-            scalars: vec![None; 10],
-            batch_size: 10,
-            _marker: PhantomData,
-        }
-    }
-}
-
 impl Circuit<Fr> for MSMCircuit<Fr> {
     type Config = MSMConfig<Fr>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
         Self {
-            bases: vec![None; self.batch_size],
-            scalars: vec![None; self.batch_size],
-            batch_size: self.batch_size,
+            params: self.params.clone(),
+            bases: vec![None; self.params.batch_size],
+            scalars: vec![None; self.params.batch_size],
             _marker: PhantomData,
         }
     }
 
     fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
-        let mut folder = std::path::PathBuf::new();
-        folder.push("./src/bn254");
-        folder.push("configs/msm_circuit.config");
-        let params_str = std::fs::read_to_string(folder.as_path())
-            .expect("src/bn254/configs/msm_circuit.config file should exist");
-        let params: MSMCircuitParams = serde_json::from_str(params_str.as_str()).unwrap();
-
-        MSMConfig::configure(
+        MSMConfig::configure_with_params(
             meta,
-            params.strategy,
-            &[params.num_advice],
-            &[params.num_lookup_advice],
-            params.num_fixed,
-            params.lookup_bits,
-            params.limb_bits,
-            params.num_limbs,
+            &MSMCircuitParams::registered(),
             BigUint::from_str_radix(&Fq::MODULUS[2..], 16).unwrap(),
-            params.batch_size,
-            params.window_bits,
-            "default".to_string(),
         )
     }
 
@@ -333,7 +370,7 @@ impl Circuit<Fr> for MSMCircuit<Fr> {
                 let mut aux = Context::new(
                     region,
                     ContextParams {
-                        num_advice: vec![(config.fp_chip.range.context_id.clone(), config.fp_chip.range.gate.num_advice)],
+                        num_advice: vec![(config.fp_chip.range.context_id, config.fp_chip.range.gate.num_advice)],
                     },
                 );
                 let ctx = &mut aux;
@@ -385,7 +422,7 @@ impl Circuit<Fr> for MSMCircuit<Fr> {
                     println!("correct: {:?}", msm_answer);
                 }
 
-                let (const_rows, total_fixed, _lookup_rows) = config.fp_chip.finalize(ctx)?;
+                let stats = config.fp_chip.finalize_with_stats(ctx)?;
 
                 #[cfg(feature = "display")]
                 if self.bases[0] != None {
@@ -397,34 +434,16 @@ impl Circuit<Fr> for MSMCircuit<Fr> {
                     let num_limbs = config.fp_chip.num_limbs;
 
                     println!("Using:\nadvice columns: {}\nspecial lookup advice columns: {}\nfixed columns: {}\nlookup bits: {}\nlimb bits: {}\nnum limbs: {}", num_advice, num_lookup_advice, num_fixed, lookup_bits, limb_bits, num_limbs);
-                    let advice_rows = ctx.advice_rows["default"].iter();
-                    println!(
-                        "maximum rows used by an advice column: {}",
-                            advice_rows.clone().max().or(Some(&0)).unwrap(),
-                    );
-                    println!(
-                        "minimum rows used by an advice column: {}",
-                            advice_rows.clone().min().or(Some(&usize::MAX)).unwrap(),
-                    );
-                    let total_cells = advice_rows.sum::<usize>();
-                    println!("total cells used: {}", total_cells);
-                    println!("cells used in special lookup column: {}", ctx.cells_to_lookup.len());
-                    println!("maximum rows used by a fixed column: {}", const_rows);
-
-                    println!("Suggestions:");
-                    let degree = lookup_bits + 1;
-                    println!(
-                        "Have you tried using {} advice columns?",
-                        (total_cells + (1 << degree) - 1) / (1 << degree)
-                    );
-                    println!(
-                        "Have you tried using {} lookup columns?",
-                        (ctx.cells_to_lookup.len() + (1 << degree) - 1) / (1 << degree)
-                    );
-                    println!(
-                        "Have you tried using {} fixed columns?",
-                        (total_fixed + (1 << degree) - 1) / (1 << degree)
+                    #[cfg(feature = "json-stats")]
+                    println!("{}", stats.to_json());
+
+                    // the fewest columns for the minimal degree, from the shape of this run
+                    let suggested = CircuitParams::for_degree(
+                        &stats.shape(),
+                        lookup_bits as u32 + 1,
+                        lookup_bits,
                     );
+                    println!("Suggested params: {:?}", suggested);
                 }
                 Ok(())
             }
@@ -437,12 +456,7 @@ impl Circuit<Fr> for MSMCircuit<Fr> {
 fn test_msm() {
     use ff::Field;
 
-    let mut folder = std::path::PathBuf::new();
-    folder.push("./src/bn254");
-    folder.push("configs/msm_circuit.config");
-    let params_str = std::fs::read_to_string(folder.as_path())
-        .expect("src/bn254/configs/msm_circuit.config file should exist");
-    let params: MSMCircuitParams = serde_json::from_str(params_str.as_str()).unwrap();
+    let params: MSMCircuitParams = read_params(MSM_CONFIG_PATH);
     let k = params.degree;
 
     let mut rng = rand::thread_rng();
@@ -458,7 +472,7 @@ fn test_msm() {
     }
 
     let circuit =
-        MSMCircuit::<Fr> { bases, scalars, batch_size: params.batch_size, _marker: PhantomData };
+        MSMCircuit::<Fr> { params: params.register(), bases, scalars, _marker: PhantomData };
 
     let prover = MockProver::run(k, &circuit, vec![]).unwrap();
     //    prover.assert_satisfied();
@@ -503,15 +517,6 @@ fn bench_msm() -> Result<(), Box<dyn std::error::Error>> {
         );
         let mut rng = rand::thread_rng();
 
-        {
-            folder.pop();
-            folder.push("configs/msm_circuit.config");
-            let mut f = std::fs::File::create(folder.as_path())?;
-            write!(f, "{}", serde_json::to_string(&bench_params).unwrap())?;
-            folder.pop();
-            folder.pop();
-            folder.push("data");
-        }
         let params_time = start_timer!(|| "Params construction");
         let params = {
             params_folder.push(format!("kzg_bn254_{}.params", bench_params.degree));
@@ -532,9 +537,9 @@ fn bench_msm() -> Result<(), Box<dyn std::error::Error>> {
         end_timer!(params_time);
 
         let circuit = MSMCircuit::<Fr> {
+            params: bench_params.clone().register(),
             bases: vec![None; bench_params.batch_size],
             scalars: vec![None; bench_params.batch_size],
-            batch_size: bench_params.batch_size,
             _marker: PhantomData,
         };
 
@@ -578,12 +583,8 @@ fn bench_msm() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         println!("{:?}", bench_params);
-        let proof_circuit = MSMCircuit::<Fr> {
-            bases,
-            scalars,
-            batch_size: bench_params.batch_size,
-            _marker: PhantomData,
-        };
+        let proof_circuit =
+            MSMCircuit::<Fr> { params: bench_params.clone(), bases, scalars, _marker: PhantomData };
 
         // create a proof
         let proof_time = start_timer!(|| "Proving time");
@@ -655,12 +656,7 @@ fn bench_msm() -> Result<(), Box<dyn std::error::Error>> {
 #[cfg(test)]
 #[test]
 fn test_pairing() {
-    let mut folder = std::path::PathBuf::new();
-    folder.push("./src/bn254");
-    folder.push("configs/pairing_circuit.config");
-    let params_str = std::fs::read_to_string(folder.as_path())
-        .expect("src/bn254/configs/pairing_circuit.config file should exist");
-    let params: PairingCircuitParams = serde_json::from_str(params_str.as_str()).unwrap();
+    let params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
     let k = params.degree;
 
     let mut rng = rand::thread_rng();
@@ -668,7 +664,7 @@ fn test_pairing() {
     let P = Some(G1Affine::random(&mut rng));
     let Q = Some(G2Affine::random(&mut rng));
 
-    let circuit = PairingCircuit::<Fr> { P, Q, _marker: PhantomData };
+    let circuit = PairingCircuit::<Fr> { params: params.register(), P, Q, _marker: PhantomData };
 
     let prover = MockProver::run(k, &circuit, vec![]).unwrap();
     //prover.assert_satisfied();
@@ -717,15 +713,6 @@ fn bench_pairing() -> Result<(), Box<dyn std::error::Error>> {
             bench_params.degree
         );
 
-        {
-            folder.pop();
-            folder.push("configs/pairing_circuit.config");
-            let mut f = std::fs::File::create(folder.as_path())?;
-            write!(f, "{}", serde_json::to_string(&bench_params).unwrap())?;
-            folder.pop();
-            folder.pop();
-            folder.push("data");
-        }
         let params_time = start_timer!(|| "Params construction");
         let params = {
             params_folder.push(format!("kzg_bn254_{}.params", bench_params.degree));
@@ -744,7 +731,12 @@ fn bench_pairing() -> Result<(), Box<dyn std::error::Error>> {
             params
         };
 
-        let circuit = PairingCircuit::<Fr>::default();
+        let circuit = PairingCircuit::<Fr> {
+            params: bench_params.clone().register(),
+            P: None,
+            Q: None,
+            _marker: PhantomData,
+        };
         end_timer!(params_time);
 
         let vk_time = start_timer!(|| "Generating vkey");
@@ -777,7 +769,8 @@ fn bench_pairing() -> Result<(), Box<dyn std::error::Error>> {
         let mut rng = rand::thread_rng();
         let P = Some(G1Affine::random(&mut rng));
         let Q = Some(G2Affine::random(&mut rng));
-        let proof_circuit = PairingCircuit::<Fr> { P, Q, _marker: PhantomData };
+        let proof_circuit =
+            PairingCircuit::<Fr> { params: bench_params.clone(), P, Q, _marker: PhantomData };
 
         // create a proof
         let proof_time = start_timer!(|| "Proving time");
@@ -859,3 +852,710 @@ fn plot_pairing() {
     halo2_proofs::dev::CircuitLayout::default().render(k, &circuit, &root).unwrap();
 }
 */
+
+#[derive(Clone, Copy, Debug)]
+enum Precompile {
+    Add,
+    Mul,
+    Pairing,
+}
+
+#[derive(Clone)]
+struct PrecompileCircuit<F: FieldExt> {
+    params: PairingCircuitParams,
+    precompile: Precompile,
+    input: Vec<u8>,
+    // the expected `(success, output)`
+    expected: (bool, Vec<u8>),
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for PrecompileCircuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // the length of the input is part of the circuit
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PairingCircuit::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        let chip = PrecompileChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "precompile",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let input = config.range.gate.assign_region_smart(
+                    ctx,
+                    self.input.iter().map(|b| Witness(Value::known(F::from(*b as u64)))).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let (success, output) = match self.precompile {
+                    Precompile::Add => chip.ec_add(ctx, &input)?,
+                    Precompile::Mul => chip.ec_mul(ctx, &input)?,
+                    Precompile::Pairing => chip.ec_pairing(ctx, &input)?,
+                };
+                assert_eq!(
+                    value_to_option(success.value().copied()),
+                    Some(F::from(self.expected.0 as u64))
+                );
+                let output: Vec<_> =
+                    output.iter().map(|b| value_to_option(b.value().copied()).unwrap()).collect();
+                let expected: Vec<_> = self.expected.1.iter().map(|b| F::from(*b as u64)).collect();
+                assert_eq!(output, expected);
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn run_precompile(precompile: Precompile, input: Vec<u8>, expected: (bool, Vec<u8>)) {
+    let params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
+    let k = params.degree;
+    let circuit = PrecompileCircuit::<Fr> {
+        params: params.register(),
+        precompile,
+        input,
+        expected,
+        _marker: PhantomData,
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+fn fq_to_be_bytes(a: &Fq) -> Vec<u8> {
+    a.to_bytes().into_iter().rev().collect()
+}
+
+// the encoding of EIP-196, with the infinity as 64 zero bytes
+fn g1_to_be_bytes(P: &G1Affine) -> Vec<u8> {
+    if bool::from(P.is_identity()) {
+        return vec![0; 64];
+    }
+    [fq_to_be_bytes(&P.x), fq_to_be_bytes(&P.y)].concat()
+}
+
+// the encoding of EIP-197, with `a * u + b` as `a` followed by `b`
+fn g2_to_be_bytes(x: &Fq2, y: &Fq2) -> Vec<u8> {
+    [fq_to_be_bytes(&x.c1), fq_to_be_bytes(&x.c0), fq_to_be_bytes(&y.c1), fq_to_be_bytes(&y.c0)]
+        .concat()
+}
+
+#[test]
+fn test_ec_add() {
+    let mut rng = rand::thread_rng();
+    let P = G1Affine::random(&mut rng);
+    let Q = G1Affine::random(&mut rng);
+    let O = G1Affine::identity();
+    let p_bytes = {
+        let p = BigUint::from_str_radix(&Fq::MODULUS[2..], 16).unwrap().to_bytes_be();
+        [vec![0; 32 - p.len()], p].concat()
+    };
+    let ok = |R: G1Affine| (true, g1_to_be_bytes(&R));
+    let fail = (false, vec![0; 64]);
+
+    let cases = vec![
+        ([g1_to_be_bytes(&P), g1_to_be_bytes(&Q)].concat(), ok((P + Q).to_affine())),
+        ([g1_to_be_bytes(&P), g1_to_be_bytes(&P)].concat(), ok((P + P).to_affine())),
+        ([g1_to_be_bytes(&P), g1_to_be_bytes(&-P)].concat(), ok(O)),
+        ([g1_to_be_bytes(&O), g1_to_be_bytes(&Q)].concat(), ok(Q)),
+        ([g1_to_be_bytes(&O), g1_to_be_bytes(&O)].concat(), ok(O)),
+        // a short input is padded with the infinity
+        (g1_to_be_bytes(&P), ok(P)),
+        // a coordinate equal to p
+        ([p_bytes.clone(), fq_to_be_bytes(&P.y), g1_to_be_bytes(&Q)].concat(), fail.clone()),
+        // (1, 3) is not on the curve
+        ([vec![0; 31], vec![1], vec![0; 31], vec![3], g1_to_be_bytes(&Q)].concat(), fail),
+    ];
+    for (input, expected) in cases {
+        run_precompile(Precompile::Add, input, expected);
+    }
+}
+
+#[test]
+fn test_ec_mul() {
+    let mut rng = rand::thread_rng();
+    let P = G1Affine::random(&mut rng);
+    let r = BigUint::from_str_radix(&Fr::MODULUS[2..], 16).unwrap();
+    let scalar_bytes = |s: &BigUint| {
+        let s = s.to_bytes_be();
+        [vec![0; 32 - s.len()], s].concat()
+    };
+    let product = |P: &G1Affine, s: &BigUint| {
+        let s: Fr = biguint_to_fe(&(s % &r));
+        g1_to_be_bytes(&(*P * s).to_affine())
+    };
+
+    let random = BigUint::from_bytes_be(&(0..32).map(|_| rand::random::<u8>()).collect::<Vec<_>>());
+    let scalars = vec![
+        random,
+        BigUint::from(0u64),
+        BigUint::from(1u64),
+        &r - 1usize,
+        (&r - 1usize) >> 1,
+        (&r + 1usize) >> 1,
+        &r + 5usize,
+        (BigUint::from(1u64) << 256) - 1usize,
+    ];
+    for s in scalars {
+        let input = [g1_to_be_bytes(&P), scalar_bytes(&s)].concat();
+        run_precompile(Precompile::Mul, input, (true, product(&P, &s)));
+    }
+    // the infinity, and a point not on the curve
+    let s = scalar_bytes(&BigUint::from(7u64));
+    run_precompile(Precompile::Mul, [vec![0; 64], s.clone()].concat(), (true, vec![0; 64]));
+    let off_curve = [vec![0; 31], vec![1], vec![0; 31], vec![3]].concat();
+    run_precompile(Precompile::Mul, [off_curve, s].concat(), (false, vec![0; 64]));
+}
+
+#[test]
+fn test_ec_pairing() {
+    let mut rng = rand::thread_rng();
+    let P = G1Affine::random(&mut rng);
+    let Q = G2Affine::random(&mut rng);
+    let pair =
+        |P: &G1Affine, Q: &G2Affine| [g1_to_be_bytes(P), g2_to_be_bytes(&Q.x, &Q.y)].concat();
+    let result = |b: u8| [vec![0; 31], vec![b]].concat();
+
+    // e(P, Q) * e(-P, Q) = 1
+    run_precompile(Precompile::Pairing, [pair(&P, &Q), pair(&-P, &Q)].concat(), (true, result(1)));
+    run_precompile(Precompile::Pairing, pair(&P, &Q), (true, result(0)));
+    // a pair with the infinity contributes 1
+    run_precompile(
+        Precompile::Pairing,
+        [vec![0; 64], g2_to_be_bytes(&Q.x, &Q.y)].concat(),
+        (true, result(1)),
+    );
+    run_precompile(Precompile::Pairing, vec![], (true, result(1)));
+    run_precompile(Precompile::Pairing, vec![0; 100], (false, vec![0; 32]));
+
+    // a point on the twist outside the subgroup of order r
+    let (x, y) = loop {
+        let x = Fq2::random(&mut rng);
+        let y = (x.square() * x + G2Affine::b()).sqrt();
+        if bool::from(y.is_some()) {
+            break (x, y.unwrap());
+        }
+    };
+    let input = [g1_to_be_bytes(&P), g2_to_be_bytes(&x, &y)].concat();
+    run_precompile(Precompile::Pairing, input, (false, vec![0; 32]));
+}
+
+#[derive(Clone)]
+struct BlsCircuit<F: FieldExt> {
+    params: PairingCircuitParams,
+    pubkeys: Vec<G2Affine>,
+    msg_hashes: Vec<G1Affine>,
+    signature: G1Affine,
+    expected: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for BlsCircuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PairingCircuit::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        let chip = PairingChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "bls",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let mut pubkeys = Vec::with_capacity(self.pubkeys.len());
+                for pubkey in self.pubkeys.iter() {
+                    pubkeys.push(chip.load_private_g2(ctx, Value::known(*pubkey))?);
+                }
+                let mut msg_hashes = Vec::with_capacity(self.msg_hashes.len());
+                for P in self.msg_hashes.iter() {
+                    msg_hashes.push(chip.load_private_g1(ctx, Value::known(*P))?);
+                }
+                let signature = chip.load_private_g1(ctx, Value::known(self.signature))?;
+
+                let is_valid = verify_aggregate(&config, ctx, &pubkeys, &msg_hashes, &signature)?;
+                assert_eq!(
+                    value_to_option(is_valid.value().copied()),
+                    Some(F::from(self.expected as u64))
+                );
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn run_bls(pubkeys: Vec<G2Affine>, msg_hashes: Vec<G1Affine>, signature: G1Affine, expected: bool) {
+    // two subgroup checks and three Miller loops need twice the rows of the pairing circuit
+    let mut params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
+    params.degree += 1;
+    params.lookup_bits += 1;
+    let k = params.degree;
+    let circuit = BlsCircuit::<Fr> {
+        params: params.register(),
+        pubkeys,
+        msg_hashes,
+        signature,
+        expected,
+        _marker: PhantomData,
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn test_bls_verify_aggregate() {
+    let mut rng = rand::thread_rng();
+    let sks = [Fr::random(&mut rng), Fr::random(&mut rng)];
+    let pubkeys: Vec<_> = sks.iter().map(|sk| (G2Affine::generator() * sk).to_affine()).collect();
+    let H = [G1Affine::random(&mut rng), G1Affine::random(&mut rng)];
+
+    // a common message
+    let signature = (H[0] * sks[0] + H[0] * sks[1]).to_affine();
+    run_bls(pubkeys.clone(), vec![H[0]], signature, true);
+    run_bls(pubkeys.clone(), vec![H[1]], signature, false);
+
+    // a repeated public key, whose signature counts twice
+    let signature = (H[0] * sks[0] + H[0] * sks[0]).to_affine();
+    run_bls(vec![pubkeys[0], pubkeys[0]], vec![H[0]], signature, true);
+    run_bls(vec![pubkeys[0], pubkeys[0]], vec![H[0]], (H[0] * sks[0]).to_affine(), false);
+
+    // distinct messages
+    let signature = (H[0] * sks[0] + H[1] * sks[1]).to_affine();
+    run_bls(pubkeys.clone(), H.to_vec(), signature, true);
+    run_bls(pubkeys.clone(), vec![H[1], H[0]], signature, false);
+
+    // a public key on the twist outside the subgroup of order r
+    let signature = (H[0] * sks[0]).to_affine();
+    run_bls(vec![pubkeys[0], random_off_subgroup()], vec![H[0]], signature, false);
+}
+
+// a random point on the twist, which is outside G2 with overwhelming probability since the cofactor is large
+fn random_off_subgroup() -> G2Affine {
+    let mut rng = rand::thread_rng();
+    loop {
+        let x = Fq2::random(&mut rng);
+        let y = (x.square() * x + G2Affine::b()).sqrt();
+        if bool::from(y.is_some()) {
+            return G2Affine { x, y: y.unwrap() };
+        }
+    }
+}
+
+#[derive(Clone)]
+struct G2Circuit<F: FieldExt> {
+    params: PairingCircuitParams,
+    P: G2Affine,
+    Q: G2Affine,
+    scalar: u128,
+    // a point on the twist outside G2, and a point off the twist
+    off_subgroup: G2Affine,
+    off_curve: G2Affine,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for G2Circuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PairingCircuit::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        let chip = G2Chip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "g2",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+                let flag = |a: &AssignedValue<F>| value_to_option(a.value().copied()).unwrap();
+
+                let P = chip.load_private(ctx, Value::known(self.P))?;
+                let Q = chip.load_private(ctx, Value::known(self.Q))?;
+                chip.assert_in_subgroup(ctx, &P)?;
+                chip.assert_in_subgroup(ctx, &Q)?;
+
+                let sum = chip.add_unequal(ctx, &P, &Q, true)?;
+                let expected = chip.load_constant(ctx, (self.P + self.Q).to_affine())?;
+                chip.assert_equal(ctx, &sum, &expected)?;
+                let diff = chip.sub_unequal(ctx, &P, &Q, true)?;
+                let expected = chip.load_constant(ctx, (self.P - self.Q).to_affine())?;
+                chip.assert_equal(ctx, &diff, &expected)?;
+                let double = chip.double(ctx, &P)?;
+                let expected = chip.load_constant(ctx, (self.P + self.P).to_affine())?;
+                chip.assert_equal(ctx, &double, &expected)?;
+                let neg = chip.negate(ctx, &P)?;
+                let expected = chip.load_constant(ctx, -self.P)?;
+                assert_eq!(flag(&chip.is_equal(ctx, &neg, &expected)?), F::one());
+                assert_eq!(flag(&chip.is_equal(ctx, &neg, &P)?), F::zero());
+
+                let scalar = config.range.gate.assign_region_smart(
+                    ctx,
+                    vec![Witness(Value::known(F::from_u128(self.scalar)))],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let product = chip.scalar_mult(ctx, &P, &scalar, 128, 4)?;
+                let expected =
+                    chip.load_constant(ctx, (self.P * Fr::from_u128(self.scalar)).to_affine())?;
+                chip.assert_equal(ctx, &product, &expected)?;
+
+                let off_subgroup = chip.load_private(ctx, Value::known(self.off_subgroup))?;
+                assert_eq!(flag(&chip.is_on_curve(ctx, &off_subgroup)?), F::one());
+                assert_eq!(flag(&chip.is_in_subgroup(ctx, &off_subgroup)?), F::zero());
+                let off_curve = chip.load_private(ctx, Value::known(self.off_curve))?;
+                assert_eq!(flag(&chip.is_on_curve(ctx, &off_curve)?), F::zero());
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_g2_chip() {
+    let mut rng = rand::thread_rng();
+    let off_subgroup = random_off_subgroup();
+    let off_curve = G2Affine { x: off_subgroup.x, y: off_subgroup.y + Fq2::one() };
+    let params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
+    let k = params.degree;
+    let circuit = G2Circuit::<Fr> {
+        params: params.register(),
+        P: G2Affine::random(&mut rng),
+        Q: G2Affine::random(&mut rng),
+        scalar: rand::random(),
+        off_subgroup,
+        off_curve,
+        _marker: PhantomData,
+    };
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// loads `Q` with `G2Chip::assign_point_checked`
+#[derive(Clone)]
+struct G2CheckedCircuit<F: FieldExt> {
+    params: PairingCircuitParams,
+    Q: G2Affine,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for G2CheckedCircuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PairingCircuit::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        let chip = G2Chip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "g2 checked",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+
+                chip.assign_point_checked(ctx, Value::known(self.Q))?;
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_g2_assign_point_checked() {
+    let params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
+    let k = params.degree;
+    for (Q, is_valid) in
+        [(G2Affine::random(rand::thread_rng()), true), (random_off_subgroup(), false)]
+    {
+        let circuit =
+            G2CheckedCircuit::<Fr> { params: params.clone().register(), Q, _marker: PhantomData };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify().is_ok(), is_valid);
+    }
+}
+
+#[test]
+fn test_expand_message_xmd_native() {
+    // RFC 9380, K.1
+    let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+    let cases: [(&[u8], &str); 2] = [
+        (b"", "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"),
+        (b"abc", "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"),
+    ];
+    for (msg, expected) in cases {
+        let out = expand_message_xmd_native(msg, dst, 0x20);
+        assert_eq!(BigUint::from_bytes_be(&out), BigUint::from_str_radix(expected, 16).unwrap());
+    }
+}
+
+#[derive(Clone)]
+struct HashToG2Circuit<F: FieldExt> {
+    params: PairingCircuitParams,
+    msg: Vec<u8>,
+    dst: Vec<u8>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for HashToG2Circuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PairingCircuit::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        let chip = G2Chip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "hash_to_g2",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let msg = config.range.gate.assign_region_smart(
+                    ctx,
+                    self.msg.iter().map(|b| Witness(Value::known(F::from(*b as u64)))).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let Q = hash_to_g2(&config, ctx, &msg, &self.dst)?;
+                let expected = chip.load_constant(ctx, hash_to_g2_native(&self.msg, &self.dst))?;
+                chip.assert_equal(ctx, &Q, &expected)?;
+                assert_eq!(
+                    value_to_option(chip.is_in_subgroup(ctx, &Q)?.value().copied()),
+                    Some(F::one())
+                );
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_hash_to_g2() {
+    let dst = b"QUUX-V01-CS02-with-BN254G2_XMD:SHA-256_SVDW_RO_".to_vec();
+    for msg in [b"".to_vec(), b"abc".to_vec()] {
+        let [u_0, u_1] = hash_to_field_native(&msg, &dst);
+        for u in [u_0, u_1] {
+            assert!(bool::from(map_to_g2_native(u).is_on_curve()));
+        }
+
+        let params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
+        let k = params.degree;
+        let circuit = HashToG2Circuit::<Fr> {
+            params: params.register(),
+            msg,
+            dst: dst.clone(),
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[derive(Clone)]
+struct GtCircuit<F: FieldExt> {
+    params: PairingCircuitParams,
+    a: Fq12,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for GtCircuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        PairingCircuit::configure_with_params(meta, &PairingCircuitParams::registered())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load_lookup_table(&mut layouter)?;
+        let chip = Fp12Chip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "gt",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams {
+                        num_advice: vec![(ContextId::new("default"), config.range.gate.num_advice)],
+                    },
+                );
+                let ctx = &mut aux;
+
+                let a =
+                    chip.load_private(ctx, Fp12Chip::<F>::fe_to_witness(&Value::known(self.a)))?;
+                chip.assert_in_gt(ctx, &a)?;
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn run_gt(a: Fq12) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    let params: PairingCircuitParams = read_params(PAIRING_CONFIG_PATH);
+    let k = params.degree;
+    let circuit = GtCircuit::<Fr> { params: params.register(), a, _marker: PhantomData };
+    MockProver::run(k, &circuit, vec![]).unwrap().verify()
+}
+
+#[test]
+fn test_assert_in_gt() {
+    let mut rng = rand::thread_rng();
+    let p = BigUint::from_str_radix(&Fq::MODULUS[2..], 16).unwrap();
+    let r = BigUint::from_str_radix(&Fr::MODULUS[2..], 16).unwrap();
+    let p2 = &p * &p;
+    let p6 = p2.pow(3);
+    // g^{(p^6 - 1)(p^2 + 1)} is in the cyclotomic subgroup, and g^{(p^12 - 1) / r} is in GT
+    let easy_exp = (&p6 - 1u64) * (&p2 + 1u64);
+    let gt_exp = (&p6 * &p6 - 1u64) / &r;
+    let g = Fq12::random(&mut rng);
+
+    assert_eq!(run_gt(g.pow_vartime(gt_exp.to_u64_digits())), Ok(()));
+    assert_eq!(run_gt(Fq12::one()), Ok(()));
+    assert!(run_gt(g.pow_vartime(easy_exp.to_u64_digits())).is_err());
+    assert!(run_gt(g).is_err());
+}
@@ -1,12 +1,12 @@
 #![allow(non_snake_case)]
 use crate::{
-    bigint::{CRTInteger, FixedCRTInteger},
+    bigint::{inner_product, CRTInteger, FixedCRTInteger},
     fields::{PrimeFieldChip, Selectable},
 };
 use ff::PrimeField;
 use halo2_base::{
     gates::{GateInstructions, RangeInstructions},
-    utils::{bigint_to_fe, fe_to_biguint, modulus},
+    utils::{batch_map, bigint_to_fe, fe_to_biguint, modulus},
     AssignedValue, Context,
     QuantumCell::{Constant, Existing},
 };
@@ -14,9 +14,14 @@ use halo2_proofs::{
     arithmetic::{CurveAffine, FieldExt},
     plonk::Error,
 };
+use num_bigint::BigInt;
+use rand_core::RngCore;
 use std::marker::PhantomData;
 
-use super::{ecc_add_unequal, select, select_from_bits, EccPoint};
+use super::{
+    ecc_add_unequal, ecc_double_n, ecc_sub_unequal, ecc_sub_with_identity, select,
+    select_from_bits, EccChip, EccPoint,
+};
 
 // this only works for curves GA with base field of prime order
 #[derive(Clone, Debug)]
@@ -63,6 +68,81 @@ where
     }
 }
 
+/// The multiples of a fixed point used by `fixed_base_scalar_multiply`, computed once out of circuit.
+/// For a well-known point such as a generator, build the table once (e.g. at keygen) and pass it to
+/// `fixed_base_scalar_multiply_with_table` on every call, which selects among its entries as constants in the
+/// fixed columns instead of assigning them as advice.
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<F: FieldExt, GA: CurveAffine> {
+    // windows[i][j - 1] holds j * 2^(i * w) for j in {1, ..., 2^w - 1}
+    pub windows: Vec<Vec<FixedEccPoint<F, GA>>>,
+    pub window_bits: usize,
+}
+
+impl<F: FieldExt, GA: CurveAffine> FixedBaseTable<F, GA>
+where
+    GA::Base: PrimeField,
+{
+    /// The table of `P` for scalars of up to `num_bits` bits, with windows of `window_bits` bits
+    pub fn new(
+        P: &GA,
+        num_limbs: usize,
+        limb_bits: usize,
+        num_bits: usize,
+        window_bits: usize,
+    ) -> Self {
+        let num_windows = (num_bits + window_bits - 1) / window_bits;
+
+        // j * 2^(i * w) for j in {1, ..., 2^w - 1}, window after window
+        let mut table = Vec::with_capacity(num_windows * ((1usize << window_bits) - 1));
+        let mut increment = *P;
+        for _i in 0..num_windows {
+            let mut curr = increment;
+            table.push(curr);
+            for _j in 2..(1usize << window_bits) {
+                curr = GA::from(curr + increment);
+                table.push(curr);
+            }
+            increment = GA::from(curr + increment);
+        }
+        // decomposing the points into limbs costs more than computing them, so it is done in parallel
+        let table =
+            batch_map(&table, |pt| FixedEccPoint::<F, GA>::from_g1(pt, num_limbs, limb_bits));
+        let windows =
+            table.chunks((1usize << window_bits) - 1).map(|window| window.to_vec()).collect();
+        Self { windows, window_bits }
+    }
+
+    /// The number of scalar bits the table covers
+    pub fn num_bits(&self) -> usize {
+        self.windows.len() * self.window_bits
+    }
+}
+
+// the entry of the window `points` for the little-endian bits `sel`, as in `select_from_bits`, with the
+// coordinates of the entries as constants; the entry for 0 is the entry for 1, see `is_zero_window` below
+fn select_fixed_from_bits<F, GA>(
+    gate: &impl GateInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    points: &[FixedEccPoint<F, GA>],
+    sel: &[AssignedValue<F>],
+) -> Result<EccPoint<F, CRTInteger<F>>, Error>
+where
+    F: FieldExt,
+    GA: CurveAffine,
+{
+    assert_eq!(1 << sel.len(), points.len() + 1);
+    let sel_quantum = sel.iter().map(|x| Existing(x)).collect();
+    let coeffs = gate.bits_to_indicator(ctx, &sel_quantum)?;
+    let x_coords: Vec<_> =
+        [&points[0]].into_iter().chain(points.iter()).map(|P| P.x.clone()).collect();
+    let y_coords: Vec<_> =
+        [&points[0]].into_iter().chain(points.iter()).map(|P| P.y.clone()).collect();
+    let Rx = inner_product::fixed_crt(gate, ctx, &x_coords, &coeffs)?;
+    let Ry = inner_product::fixed_crt(gate, ctx, &y_coords, &coeffs)?;
+    Ok(EccPoint::construct(Rx, Ry))
+}
+
 // computes `[scalar] * P` on y^2 = x^3 + b where `P` is fixed (constant)
 // - `scalar` is represented as a reference array of `AssignedCell`s
 // - `scalar = sum_i scalar_i * 2^{max_bits * i}`
@@ -70,6 +150,7 @@ where
 // assumes:
 // - `scalar_i < 2^{max_bits} for all i` (constrained by num_to_bits)
 // - `max_bits <= modulus::<F>.bits()`
+// the table of multiples of `P` is computed on every call; see `FixedBaseTable` to compute it once
 
 pub fn fixed_base_scalar_multiply<'a, F, FC, GA>(
     chip: &FC,
@@ -79,6 +160,29 @@ pub fn fixed_base_scalar_multiply<'a, F, FC, GA>(
     max_bits: usize,
     window_bits: usize,
 ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    F: FieldExt,
+    GA: CurveAffine,
+    GA::Base: PrimeField,
+    FC: PrimeFieldChip<F, FieldType = GA::Base, FieldPoint = CRTInteger<F>>
+        + Selectable<F, Point = FC::FieldPoint>,
+{
+    let base_pt = GA::from_xy(bigint_to_fe(&P.x.value), bigint_to_fe(&P.y.value)).unwrap();
+    let (num_limbs, limb_bits) = (P.x.truncation.limbs.len(), P.x.truncation.limb_bits);
+    let table =
+        FixedBaseTable::new(&base_pt, num_limbs, limb_bits, max_bits * scalar.len(), window_bits);
+    fixed_base_scalar_multiply_with_table(chip, ctx, &table, scalar, max_bits)
+}
+
+// `fixed_base_scalar_multiply` with the precomputed multiples `table` of the fixed point, which must cover
+// `max_bits * scalar.len()` bits
+pub fn fixed_base_scalar_multiply_with_table<'a, F, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    table: &FixedBaseTable<F, GA>,
+    scalar: &Vec<AssignedValue<F>>,
+    max_bits: usize,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
 where
     F: FieldExt,
     GA: CurveAffine,
@@ -89,38 +193,13 @@ where
     assert!(scalar.len() > 0);
     assert!((max_bits as u64) <= modulus::<F>().bits());
 
+    let window_bits = table.window_bits;
     let total_bits = max_bits * scalar.len();
     let num_windows = (total_bits + window_bits - 1) / window_bits;
     let rounded_bitlen = num_windows * window_bits;
-
-    // cached_points[i][j] holds j * 2^(i * w) for j in {0, ..., 2^w - 1}
-    let mut cached_points = Vec::with_capacity(num_windows);
-    let base_pt = GA::from_xy(bigint_to_fe(&P.x.value), bigint_to_fe(&P.y.value)).unwrap();
-    // let base_pt_assigned = P.assign(chip, ctx)?;
-
-    let mut increment = base_pt;
-    for _i in 0..num_windows {
-        let mut cache_vec = Vec::with_capacity(1usize << window_bits);
-        let mut curr = increment;
-
-        let increment_fixed = FixedEccPoint::from_g1(
-            &increment,
-            P.x.truncation.limbs.len(),
-            P.x.truncation.limb_bits,
-        );
-        let increment_assigned = increment_fixed.assign(chip, ctx)?;
-        cache_vec.push(increment_assigned.clone());
-        cache_vec.push(increment_assigned.clone());
-        for _j in 2..(1usize << window_bits) {
-            curr = GA::from(curr + increment);
-            let curr_fixed =
-                FixedEccPoint::from_g1(&curr, P.x.truncation.limbs.len(), P.x.truncation.limb_bits);
-            let curr_assigned = curr_fixed.assign(chip, ctx)?;
-            cache_vec.push(curr_assigned);
-        }
-        increment = GA::from(curr + increment);
-        cached_points.push(cache_vec);
-    }
+    assert!(table.windows.len() >= num_windows, "the table does not cover the scalar");
+    // the windows are selected among constants, so the table is never assigned
+    let cached_points = &table.windows[..num_windows];
 
     let mut bits = Vec::with_capacity(rounded_bitlen);
     for x in scalar {
@@ -158,25 +237,24 @@ where
             .map(|x| Existing(&x))
             .collect();
         let bit_sum = chip.range().gate().inner_product(ctx, &ones_vec, &temp_bits)?;
-        let is_zero = chip.range().is_zero(ctx, &bit_sum.2)?;
+        let is_zero = chip.range().is_zero(ctx, &bit_sum)?;
         is_zero_window.push(is_zero.clone());
     }
 
     // if all the starting window bits are 0, get start_point = P
-    let mut curr_point = select_from_bits(
-        chip,
+    let mut curr_point = select_fixed_from_bits(
+        chip.range().gate(),
         ctx,
         &cached_points[num_windows - 1],
-        &rounded_bits[rounded_bitlen - window_bits..rounded_bitlen].to_vec(),
+        &rounded_bits[rounded_bitlen - window_bits..rounded_bitlen],
     )?;
     for idx in 1..num_windows {
-        let add_point = select_from_bits(
-            chip,
+        let add_point = select_fixed_from_bits(
+            chip.range().gate(),
             ctx,
             &cached_points[num_windows - idx - 1],
             &rounded_bits
-                [rounded_bitlen - window_bits * (idx + 1)..rounded_bitlen - window_bits * idx]
-                .to_vec(),
+                [rounded_bitlen - window_bits * (idx + 1)..rounded_bitlen - window_bits * idx],
         )?;
         let sum = ecc_add_unequal(chip, ctx, &curr_point, &add_point, false)?;
         let zero_sum = select(chip, ctx, &curr_point, &sum, &is_zero_window[idx])?;
@@ -184,3 +262,143 @@ where
     }
     Ok(curr_point.clone())
 }
+
+// computes `sum_i scalars[i] * points[i]` where the `points` are fixed (constant) and any of them may be the point
+// at infinity; returns the sum and whether it is the point at infinity, in which case the sum is given as (0, 0)
+// - the points at infinity among `points` are known when the circuit is built, so their terms are left out
+// - a term whose scalar is 0 is left out with a select, as `fixed_base_scalar_multiply` does not handle it
+// - the terms are added to a random point `A` so the sum may cancel out, see `ecc_sub_with_identity`
+// assumes:
+// - `scalars[i]` is given as in `fixed_base_scalar_multiply`, with value less than the order of `points[i]`
+pub fn fixed_base_msm<F, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    points: &[GA],
+    scalars: &Vec<Vec<AssignedValue<F>>>,
+    max_bits: usize,
+    window_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<(EccPoint<F, FC::FieldPoint>, AssignedValue<F>), Error>
+where
+    F: FieldExt,
+    GA: CurveAffine,
+    GA::Base: PrimeField,
+    FC: PrimeFieldChip<F, FieldType = GA::Base, FieldPoint = CRTInteger<F>>
+        + Selectable<F, Point = FC::FieldPoint>,
+    FC::ConstantType: From<BigInt>,
+{
+    assert_eq!(points.len(), scalars.len());
+    let rand_point = EccChip::construct(chip).load_random_point_with_rng::<GA>(ctx, rng)?;
+    let (num_limbs, limb_bits) =
+        (rand_point.x.truncation.limbs.len(), rand_point.x.truncation.limb_bits);
+
+    let mut acc = rand_point.clone();
+    for (point, scalar) in points.iter().zip(scalars.iter()) {
+        if bool::from(point.is_identity()) {
+            continue;
+        }
+        let fixed_point = FixedEccPoint::from_g1(point, num_limbs, limb_bits);
+        let term =
+            fixed_base_scalar_multiply(chip, ctx, &fixed_point, scalar, max_bits, window_bits)?;
+
+        // the cells of `scalar` are range checked above, so their sum is 0 only if they all are
+        let ones = vec![Constant(F::one()); scalar.len()];
+        let limbs = scalar.iter().map(|x| Existing(x)).collect();
+        let limb_sum = chip.range().gate().inner_product(ctx, &ones, &limbs)?;
+        let scalar_is_zero = chip.range().is_zero(ctx, &limb_sum)?;
+
+        let sum = ecc_add_unequal(chip, ctx, &acc, &term, true)?;
+        acc = select(chip, ctx, &acc, &sum, &scalar_is_zero)?;
+    }
+    ecc_sub_with_identity(chip, ctx, &acc, &rand_point)
+}
+
+// computes `[a] * G + [b] * P` for a fixed (constant) point `G` and a variable point `P`, with one sequence of
+// doublings shared by both scalars, as in the Straus-Shamir trick:
+// - `a` and `b` are given as in `fixed_base_scalar_multiply`, with cells of at most `max_bits` bits
+// - every window adds `j * P` from a table of advice cells and `j * G` selected among constants
+// - the accumulator is offset by a random point `A`: each window adds `(1 - 2^w) * A` with the multiple of `P`,
+//   which cancels its doublings, so it ends at `[a] * G + [b] * P + A`, and all additions are strict
+// assumes:
+// - `P` is not the point at infinity, and the result is not the point at infinity
+pub fn fixed_plus_variable_scalar_multiply<F, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    G: &GA,
+    a: &Vec<AssignedValue<F>>,
+    P: &EccPoint<F, FC::FieldPoint>,
+    b: &Vec<AssignedValue<F>>,
+    max_bits: usize,
+    window_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    F: FieldExt,
+    GA: CurveAffine,
+    GA::Base: PrimeField,
+    FC: PrimeFieldChip<F, FieldType = GA::Base, FieldPoint = CRTInteger<F>>
+        + Selectable<F, Point = FC::FieldPoint>,
+{
+    assert!(a.len() > 0 && b.len() > 0);
+    assert!((max_bits as u64) <= modulus::<F>().bits());
+    let gate = chip.range().gate();
+
+    let num_windows = (max_bits * std::cmp::max(a.len(), b.len()) + window_bits - 1) / window_bits;
+    let rounded_bitlen = num_windows * window_bits;
+    let zero_cell = gate.load_zero(ctx)?;
+    let mut rounded_bits = Vec::with_capacity(2);
+    for scalar in [a, b] {
+        let mut bits = Vec::with_capacity(rounded_bitlen);
+        for x in scalar.iter() {
+            bits.append(&mut chip.range().num_to_bits(ctx, x, max_bits)?);
+        }
+        bits.resize(rounded_bitlen, zero_cell.clone());
+        rounded_bits.push(bits);
+    }
+    let window = |bits: &Vec<AssignedValue<F>>, idx: usize| {
+        bits[rounded_bitlen - window_bits * (idx + 1)..rounded_bitlen - window_bits * idx].to_vec()
+    };
+
+    // the random point is loaded as a witness and only constrained to be on the curve
+    let ecc_chip = EccChip::construct(chip);
+    let rand_point = ecc_chip.load_random_point_with_rng::<GA>(ctx, rng)?;
+    let rand_mult = ecc_double_n(chip, ctx, &rand_point, window_bits)?;
+
+    // cached_points[j] = (1 - 2^w) * A + j * P
+    let cache_size = 1usize << window_bits;
+    let mut cached_points = Vec::with_capacity(cache_size);
+    cached_points.push(ecc_sub_unequal(chip, ctx, &rand_point, &rand_mult, true)?);
+    for j in 1..cache_size {
+        let new_point = ecc_add_unequal(chip, ctx, &cached_points[j - 1], P, true)?;
+        cached_points.push(new_point);
+    }
+
+    // fixed_points[j - 1] = j * G for j in {1, ..., 2^w - 1}, the same table for every window
+    let (num_limbs, limb_bits) =
+        (rand_point.x.truncation.limbs.len(), rand_point.x.truncation.limb_bits);
+    let mut fixed_points = Vec::with_capacity(cache_size - 1);
+    let mut curr = *G;
+    for _ in 1..cache_size {
+        fixed_points.push(FixedEccPoint::<F, GA>::from_g1(&curr, num_limbs, limb_bits));
+        curr = GA::from(curr + *G);
+    }
+
+    let ones_vec = vec![Constant(F::one()); window_bits];
+    let mut curr_point = rand_point.clone();
+    for idx in 0..num_windows {
+        curr_point = ecc_double_n(chip, ctx, &curr_point, window_bits)?;
+        let b_window = window(&rounded_bits[1], num_windows - 1 - idx);
+        let add_point = select_from_bits(chip, ctx, &cached_points, &b_window)?;
+        curr_point = ecc_add_unequal(chip, ctx, &curr_point, &add_point, true)?;
+
+        // the entry for a zero window of `a` is `G`, so it is not added
+        let a_window = window(&rounded_bits[0], num_windows - 1 - idx);
+        let fixed_point = select_fixed_from_bits(gate, ctx, &fixed_points, &a_window)?;
+        let a_window_sum =
+            gate.inner_product(ctx, &ones_vec, &a_window.iter().map(Existing).collect())?;
+        let a_window_is_zero = chip.range().is_zero(ctx, &a_window_sum)?;
+        let sum = ecc_add_unequal(chip, ctx, &curr_point, &fixed_point, true)?;
+        curr_point = select(chip, ctx, &curr_point, &sum, &a_window_is_zero)?;
+    }
+    ecc_sub_unequal(chip, ctx, &curr_point, &rand_point, true)
+}
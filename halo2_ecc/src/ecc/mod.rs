@@ -1,5 +1,7 @@
 #![allow(non_snake_case)]
-use crate::bigint::{big_less_than, select, CRTInteger, OverflowInteger};
+use crate::bigint::{
+    big_is_equal, big_is_zero, big_less_than, select, sub, CRTInteger, OverflowInteger,
+};
 use crate::fields::{fp::FpConfig, fp_overflow::FpOverflowChip, Selectable};
 use crate::fields::{FieldChip, PrimeFieldChip};
 use ff::PrimeField;
@@ -15,13 +17,16 @@ use halo2_proofs::{
     circuit::Value,
     plonk::Error,
 };
-use num_bigint::BigInt;
-use rand_core::OsRng;
+use num_bigint::{BigInt, BigUint};
+use rand_core::{OsRng, RngCore};
 use std::marker::PhantomData;
 
 pub mod fixed;
 pub mod pippenger;
-use fixed::{fixed_base_scalar_multiply, FixedEccPoint};
+use fixed::{
+    fixed_base_msm, fixed_base_scalar_multiply, fixed_base_scalar_multiply_with_table,
+    fixed_plus_variable_scalar_multiply, FixedBaseTable, FixedEccPoint,
+};
 
 // EccPoint and EccChip take in a generic `FieldChip` to implement generic elliptic curve operations on arbitrary field extensions (provided chip exists) for short Weierstrass curves (currently further assuming a4 = 0 for optimization purposes)
 #[derive(Debug)]
@@ -43,6 +48,15 @@ impl<F: FieldExt, FieldPoint: Clone> EccPoint<F, FieldPoint> {
     }
 }
 
+impl<F: FieldExt> EccPoint<F, CRTInteger<F>> {
+    /// Exposes the limbs of `x` followed by the limbs of `y` as consecutive public inputs from `row`, see
+    /// `CRTInteger::expose_public`, and returns the next row
+    pub fn expose_public(&self, ctx: &mut Context<'_, F>, row: usize) -> usize {
+        let row = self.x.expose_public(ctx, row);
+        self.y.expose_public(ctx, row)
+    }
+}
+
 // Implements:
 //  Given P = (x_1, y_1) and Q = (x_2, y_2), ecc points over the field F_p
 //      assume x_1 != x_2
@@ -62,7 +76,7 @@ pub fn ecc_add_unequal<F: FieldExt, FC: FieldChip<F>>(
     if is_strict {
         // constrains that P.x != Q.x
         let x_is_equal = chip.is_equal(ctx, &P.x, &Q.x)?;
-        ctx.constants_to_assign.push((F::from(0), Some(x_is_equal.cell())));
+        ctx.assign_constant(F::from(0), Some(x_is_equal.cell()));
     }
 
     let dx = chip.sub_no_carry(ctx, &Q.x, &P.x)?;
@@ -102,7 +116,7 @@ pub fn ecc_sub_unequal<F: FieldExt, FC: FieldChip<F>>(
     if is_strict {
         // constrains that P.x != Q.x
         let x_is_equal = chip.is_equal(ctx, &P.x, &Q.x)?;
-        ctx.constants_to_assign.push((F::from(0), Some(x_is_equal.cell())));
+        ctx.assign_constant(F::from(0), Some(x_is_equal.cell()));
     }
 
     let dx = chip.sub_no_carry(ctx, &Q.x, &P.x)?;
@@ -130,6 +144,33 @@ pub fn ecc_sub_unequal<F: FieldExt, FC: FieldChip<F>>(
     Ok(EccPoint::construct(x_3, y_3))
 }
 
+// `P - Q` where `P` is an accumulator offset by `Q`, e.g. the random point of an MSM, so that `P - Q` may be the
+// point at infinity: returns `(P - Q, P == Q)`, with `P - Q` given as (0, 0) when `P == Q`
+// - `2Q` stands in for `P` when `P == Q`, so `Q` must not have order 3
+// - the subtraction is strict, so `P = -Q` is not allowed; this only occurs with negligible probability for a random `Q`
+pub fn ecc_sub_with_identity<F: FieldExt, FC>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &EccPoint<F, FC::FieldPoint>,
+    Q: &EccPoint<F, FC::FieldPoint>,
+) -> Result<(EccPoint<F, FC::FieldPoint>, AssignedValue<F>), Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    FC::ConstantType: From<BigInt>,
+{
+    let x_is_equal = chip.is_equal(ctx, &P.x, &Q.x)?;
+    let y_is_equal = chip.is_equal(ctx, &P.y, &Q.y)?;
+    let is_identity =
+        chip.range().gate().and(ctx, &Existing(&x_is_equal), &Existing(&y_is_equal))?;
+    let Q_double = ecc_double(chip, ctx, Q)?;
+    let minuend = select(chip, ctx, &Q_double, P, &is_identity)?;
+    let diff = ecc_sub_unequal(chip, ctx, &minuend, Q, true)?;
+    let zero = chip.load_constant(ctx, FC::ConstantType::from(BigInt::from(0)))?;
+    let identity = EccPoint::construct(zero.clone(), zero);
+    let out = select(chip, ctx, &identity, &diff, &is_identity)?;
+    Ok((out, is_identity))
+}
+
 // Implements:
 // computing 2P on elliptic curve E for P = (x, y)
 // formula from https://crypto.stanford.edu/pbc/notes/elliptic/explicit.html
@@ -148,14 +189,14 @@ pub fn ecc_double<F: FieldExt, FC: FieldChip<F>>(
     P: &EccPoint<F, FC::FieldPoint>,
 ) -> Result<EccPoint<F, FC::FieldPoint>, Error> {
     // removed optimization that computes `2 * lambda` while assigning witness to `lambda` simultaneously, in favor of readability. The difference is just copying `lambda` once
-    let two_y = chip.scalar_mul_no_carry(ctx, &P.y, F::from(2))?;
-    let three_x = chip.scalar_mul_no_carry(ctx, &P.x, F::from(3))?;
+    let two_y = chip.mul_u64_no_carry(ctx, &P.y, 2)?;
+    let three_x = chip.mul_u64_no_carry(ctx, &P.x, 3)?;
     let three_x_sq = chip.mul_no_carry(ctx, &three_x, &P.x)?;
     let lambda = chip.divide(ctx, &three_x_sq, &two_y)?;
 
     // x_3 = lambda^2 - 2 x % p
     let lambda_sq = chip.mul_no_carry(ctx, &lambda, &lambda)?;
-    let two_x = chip.scalar_mul_no_carry(ctx, &P.x, F::from(2))?;
+    let two_x = chip.mul_u64_no_carry(ctx, &P.x, 2)?;
     let x_3_no_carry = chip.sub_no_carry(ctx, &lambda_sq, &two_x)?;
     let x_3 = chip.carry_mod(ctx, &x_3_no_carry)?;
 
@@ -168,6 +209,56 @@ pub fn ecc_double<F: FieldExt, FC: FieldChip<F>>(
     Ok(EccPoint::construct(x_3, y_3))
 }
 
+// computes 2^n P with n chained doublings, as `ecc_double` n times
+// - `x` must be carried at every step as it is squared, but `y` only enters the next step through `2y * lambda`
+//   and `lambda (x - x_3) - y`, so its `carry_mod` is deferred whenever `FieldChip::can_mul_no_carry` allows
+// - the output is carried, and with no overflow budget to spare this is `ecc_double` n times, plus one
+//   `mul_u64_no_carry` per step to try the deferral; e.g. 88 bit limbs never defer and 64 bit limbs always do
+pub fn ecc_double_n<F: FieldExt, FC: FieldChip<F>>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &EccPoint<F, FC::FieldPoint>,
+    n: usize,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error> {
+    if n == 0 {
+        return Ok(P.clone());
+    }
+    let (mut x, mut y) = (P.x.clone(), P.y.clone());
+    let mut two_y = chip.mul_u64_no_carry(ctx, &y, 2)?;
+    for i in 0..n {
+        let three_x = chip.mul_u64_no_carry(ctx, &x, 3)?;
+        let three_x_sq = chip.mul_no_carry(ctx, &three_x, &x)?;
+        let lambda = chip.divide(ctx, &three_x_sq, &two_y)?;
+
+        // x_3 = lambda^2 - 2 x % p
+        let lambda_sq = chip.mul_no_carry(ctx, &lambda, &lambda)?;
+        let two_x = chip.mul_u64_no_carry(ctx, &x, 2)?;
+        let x_3_no_carry = chip.sub_no_carry(ctx, &lambda_sq, &two_x)?;
+        let x_3 = chip.carry_mod(ctx, &x_3_no_carry)?;
+
+        // y_3 = lambda (x - x_3) - y % p
+        let dx = chip.sub_no_carry(ctx, &x, &x_3)?;
+        let lambda_dx = chip.mul_no_carry(ctx, &lambda, &dx)?;
+        let y_3_no_carry = chip.sub_no_carry(ctx, &lambda_dx, &y)?;
+        x = x_3;
+        if i + 1 == n {
+            y = chip.carry_mod(ctx, &y_3_no_carry)?;
+            break;
+        }
+
+        // `x_3` has the bounds of the next `lambda`, which multiplies `2 y_3`
+        let two_y_3 = chip.mul_u64_no_carry(ctx, &y_3_no_carry, 2)?;
+        if chip.can_mul_no_carry(&two_y_3, &x) {
+            y = y_3_no_carry;
+            two_y = two_y_3;
+        } else {
+            y = chip.carry_mod(ctx, &y_3_no_carry)?;
+            two_y = chip.mul_u64_no_carry(ctx, &y, 2)?;
+        }
+    }
+    Ok(EccPoint::construct(x, y))
+}
+
 pub fn select<F: FieldExt, FC>(
     chip: &FC,
     ctx: &mut Context<'_, F>,
@@ -183,6 +274,22 @@ where
     Ok(EccPoint::construct(Rx, Ry))
 }
 
+// returns `-P` if `flag` is 1 and `P` if it is 0
+// only `y` is negated and selected, which saves the select of `x` in `select(P, negate(P))`
+pub fn select_neg<F: FieldExt, FC>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &EccPoint<F, FC::FieldPoint>,
+    flag: &AssignedValue<F>,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+{
+    let neg_y = chip.negate(ctx, &P.y)?;
+    let y = chip.select(ctx, &neg_y, &P.y, flag)?;
+    Ok(EccPoint::construct(P.x.clone(), y))
+}
+
 // takes the dot product of points with sel, where each is intepreted as
 // a _vector_
 pub fn inner_product<F: FieldExt, FC>(
@@ -287,7 +394,7 @@ where
             .map(|x| Existing(&x))
             .collect();
         let bit_sum = chip.range().gate().inner_product(ctx, &ones_vec, &temp_bits)?;
-        let is_zero = chip.range().is_zero(ctx, &bit_sum.2)?;
+        let is_zero = chip.range().is_zero(ctx, &bit_sum)?;
         is_zero_window.push(is_zero.clone());
     }
 
@@ -315,10 +422,7 @@ where
     )?;
 
     for idx in 1..num_windows {
-        let mut mult_point = curr_point.clone();
-        for _ in 0..window_bits {
-            mult_point = ecc_double(chip, ctx, &mult_point)?;
-        }
+        let mult_point = ecc_double_n(chip, ctx, &curr_point, window_bits)?;
         let add_point = select_from_bits(
             chip,
             ctx,
@@ -336,6 +440,44 @@ where
     Ok(curr_point.clone())
 }
 
+// computes `[c] * P` for a constant scalar `c > 0` with the double-and-add chain of its NAF, so the scalar has no
+// bits to witness and no points to select: every nonzero digit costs one addition of `P` or `-P`
+// - the additions are strict; the partial sums before an addition are `[k] * P` with `k >= 2`, so their
+//   exceptional cases only occur when the order of `P` divides `k - 1` or `k + 1`, e.g. for `P` of small order,
+//   which makes the circuit unsatisfiable
+pub fn scalar_multiply_constant<F: FieldExt, FC: FieldChip<F>>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &EccPoint<F, FC::FieldPoint>,
+    c: &BigUint,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error> {
+    assert!(c > &BigUint::from(0u64), "the point at infinity has no representation");
+    let mut naf = get_naf(c.to_u64_digits());
+    while naf.last() == Some(&0) {
+        naf.pop();
+    }
+    // the leading digit of the NAF of a positive integer is 1
+    let top = naf.len() - 1;
+    let neg_P = if naf.iter().any(|digit| *digit == -1) {
+        Some(EccPoint::construct(P.x.clone(), chip.negate(ctx, &P.y)?))
+    } else {
+        None
+    };
+
+    let mut curr_point = P.clone();
+    let mut num_doublings = 0;
+    for idx in (0..top).rev() {
+        num_doublings += 1;
+        if naf[idx] != 0 {
+            curr_point = ecc_double_n(chip, ctx, &curr_point, num_doublings)?;
+            let add_point = if naf[idx] == 1 { P } else { neg_P.as_ref().unwrap() };
+            curr_point = ecc_add_unequal(chip, ctx, &curr_point, add_point, true)?;
+            num_doublings = 0;
+        }
+    }
+    ecc_double_n(chip, ctx, &curr_point, num_doublings)
+}
+
 pub fn is_on_curve<F: FieldExt, FC: FieldChip<F>>(
     chip: &FC,
     ctx: &mut Context<'_, F>,
@@ -365,59 +507,198 @@ pub fn multi_scalar_multiply<F: FieldExt, FC, GA>(
     max_bits: usize,
     window_bits: usize,
 ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    GA: CurveAffine<Base = FC::FieldType>,
+{
+    multi_scalar_multiply_with_rng::<F, FC, GA>(
+        chip,
+        ctx,
+        P,
+        scalars,
+        b,
+        max_bits,
+        window_bits,
+        &mut rand::thread_rng(),
+    )
+}
+
+// `multi_scalar_multiply` with the random base point drawn from `rng`, e.g. a seeded RNG for a reproducible witness
+pub fn multi_scalar_multiply_with_rng<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &Vec<EccPoint<F, FC::FieldPoint>>,
+    scalars: &Vec<Vec<AssignedValue<F>>>,
+    b: F,
+    max_bits: usize,
+    window_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    GA: CurveAffine<Base = FC::FieldType>,
+{
+    multi_scalar_multiply_var_bits_with_rng::<F, FC, GA>(
+        chip,
+        ctx,
+        P,
+        scalars,
+        b,
+        &vec![max_bits; P.len()],
+        window_bits,
+        rng,
+    )
+}
+
+// `multi_scalar_multiply` where the cells of `scalars[i]` have at most `max_bits[i]` bits, and `scalars[i]` may have
+// its own number of cells: each scalar only gets the windows its bits need, so short scalars in an MSM with a long
+// one are not padded to its length
+// - the windows are aligned at the least significant bit, and the points of a scalar with `m` windows only join
+//   the shared doublings for the last `m` windows
+// - the bases are grouped by their number of windows, and each group joins with its share of the random
+//   accumulation point, so the correction at the end is the same as when all scalars have the same length
+// - every `max_bits[i]` must be positive
+pub fn multi_scalar_multiply_var_bits_with_rng<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &Vec<EccPoint<F, FC::FieldPoint>>,
+    scalars: &Vec<Vec<AssignedValue<F>>>,
+    b: F,
+    max_bits: &[usize],
+    window_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    GA: CurveAffine<Base = FC::FieldType>,
+{
+    let (acc, start_point) = multi_scalar_multiply_accumulate::<F, FC, GA>(
+        chip,
+        ctx,
+        P,
+        scalars,
+        b,
+        max_bits,
+        window_bits,
+        rng,
+    )?;
+    ecc_sub_unequal(chip, ctx, &acc, &start_point, true)
+}
+
+// `multi_scalar_multiply` for bases that may be the point at infinity and a result that may be it, see
+// `ecc_sub_with_identity`: returns the result and whether it is the point at infinity
+// - each base is constrained to be on the curve or (0, 0), the point at infinity
+// - a base (0, 0) is replaced by the generator of `GA` with its scalar set to 0, so it adds nothing
+pub fn multi_scalar_multiply_with_identity<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &Vec<EccPoint<F, FC::FieldPoint>>,
+    scalars: &Vec<Vec<AssignedValue<F>>>,
+    max_bits: usize,
+    window_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<(EccPoint<F, FC::FieldPoint>, AssignedValue<F>), Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    FC::ConstantType: From<BigInt>,
+    GA: CurveAffine<Base = FC::FieldType>,
+    GA::Base: PrimeField,
+{
+    assert_eq!(P.len(), scalars.len());
+    let ecc_chip = EccChip::construct(chip);
+    let gen = GA::generator().coordinates().unwrap();
+    let gen_x = chip.load_constant(ctx, FC::ConstantType::from(fe_to_biguint(gen.x()).into()))?;
+    let gen_y = chip.load_constant(ctx, FC::ConstantType::from(fe_to_biguint(gen.y()).into()))?;
+    let gen = EccPoint::construct(gen_x, gen_y);
+
+    let mut bases = Vec::with_capacity(P.len());
+    let mut base_scalars = Vec::with_capacity(P.len());
+    for (base, scalar) in P.iter().zip(scalars.iter()) {
+        let is_valid = ecc_chip.is_on_curve_or_infinity::<GA>(ctx, base)?;
+        chip.range().gate().assert_is_const(ctx, &is_valid, F::one());
+        // on the curve or (0, 0), so `x = y = 0` only for the point at infinity
+        let x_is_zero = chip.is_zero(ctx, &base.x)?;
+        let y_is_zero = chip.is_zero(ctx, &base.y)?;
+        let is_identity =
+            chip.range().gate().and(ctx, &Existing(&x_is_zero), &Existing(&y_is_zero))?;
+        bases.push(select(chip, ctx, &gen, base, &is_identity)?);
+        let mut new_scalar = Vec::with_capacity(scalar.len());
+        for limb in scalar.iter() {
+            new_scalar.push(chip.range().gate().select(
+                ctx,
+                &Constant(F::zero()),
+                &Existing(limb),
+                &Existing(&is_identity),
+            )?);
+        }
+        base_scalars.push(new_scalar);
+    }
+
+    let b = biguint_to_fe::<F>(&fe_to_biguint(&GA::b()));
+    let (acc, start_point) = multi_scalar_multiply_accumulate::<F, FC, GA>(
+        chip,
+        ctx,
+        &bases,
+        &base_scalars,
+        b,
+        &vec![max_bits; P.len()],
+        window_bits,
+        rng,
+    )?;
+    ecc_sub_with_identity(chip, ctx, &acc, &start_point)
+}
+
+// the accumulator `\sum_i x_i P_i + (2^k - 1) * A` of `multi_scalar_multiply_var_bits_with_rng` and `(2^k - 1) * A`
+// for its random point `A`
+fn multi_scalar_multiply_accumulate<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &Vec<EccPoint<F, FC::FieldPoint>>,
+    scalars: &Vec<Vec<AssignedValue<F>>>,
+    b: F,
+    max_bits: &[usize],
+    window_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<(EccPoint<F, FC::FieldPoint>, EccPoint<F, FC::FieldPoint>), Error>
 where
     FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
     GA: CurveAffine<Base = FC::FieldType>,
 {
     let k = P.len();
     assert_eq!(k, scalars.len());
+    assert_eq!(k, max_bits.len());
     assert!(k > 0);
-    assert!(scalars[0].len() > 0);
-    assert!((max_bits as u64) <= modulus::<F>().bits());
-
-    let total_bits = max_bits * scalars[0].len();
-    let num_windows = (total_bits + window_bits - 1) / window_bits;
-    let rounded_bitlen = num_windows * window_bits;
+    assert!(scalars.iter().all(|scalar| scalar.len() > 0));
+    // a scalar without windows would never join the accumulator, which leaves its share of A in the correction
+    assert!(max_bits.iter().all(|bits| *bits > 0 && (*bits as u64) <= modulus::<F>().bits()));
+
+    // the bases by decreasing number of windows, so each group of equal length is a range of positions
+    let num_windows_of =
+        |i: usize| (max_bits[i] * scalars[i].len() + window_bits - 1) / window_bits;
+    let mut order: Vec<usize> = (0..k).collect();
+    order.sort_by_key(|i| std::cmp::Reverse(num_windows_of(*i)));
+    let num_windows_vec: Vec<usize> = order.iter().map(|i| num_windows_of(*i)).collect();
+    let num_windows = num_windows_vec[0];
 
     let zero_cell = chip.range().gate().load_zero(ctx)?;
     let mut rounded_bits_vec = Vec::with_capacity(k);
-    for scalar in scalars {
+    for (pos, i) in order.iter().enumerate() {
+        let rounded_bitlen = num_windows_vec[pos] * window_bits;
         let mut bits = Vec::with_capacity(rounded_bitlen);
-        for x in scalar {
-            let mut new_bits = chip.range().num_to_bits(ctx, x, max_bits)?;
+        for x in scalars[*i].iter() {
+            let mut new_bits = chip.range().num_to_bits(ctx, x, max_bits[*i])?;
             bits.append(&mut new_bits);
         }
         let mut rounded_bits = bits;
-        for _i in 0..(rounded_bitlen - total_bits) {
+        for _i in rounded_bits.len()..rounded_bitlen {
             rounded_bits.push(zero_cell.clone());
         }
         rounded_bits_vec.push(rounded_bits);
     }
 
-    let mut is_zero_window_vec = Vec::with_capacity(k);
-    let mut ones_vec = Vec::with_capacity(window_bits);
-    for _ in 0..window_bits {
-        ones_vec.push(Constant(F::from(1)));
-    }
-    for idx in 0..k {
-        let mut is_zero_window = Vec::with_capacity(num_windows);
-        for window_idx in 0..num_windows {
-            let temp_bits = rounded_bits_vec[idx][rounded_bitlen - window_bits * (window_idx + 1)
-                ..rounded_bitlen - window_bits * window_idx]
-                .iter()
-                .map(|x| Existing(&x))
-                .collect();
-            let bit_sum = chip.range().gate().inner_product(ctx, &ones_vec, &temp_bits)?;
-            let is_zero = RangeInstructions::is_zero(chip.range(), ctx, &bit_sum.2)?;
-            is_zero_window.push(is_zero.clone());
-        }
-        is_zero_window_vec.push(is_zero_window);
-    }
-
     // load random GA point as witness
     // note that while we load a random point, an adversary would load a specifically chosen point, so we must carefully handle edge cases with constraints
-    let mut rng = rand::thread_rng();
-    let base_point: GA = GA::CurveExt::random(&mut rng).to_affine();
+    let base_point: GA = GA::CurveExt::random(rng).to_affine();
     let base_point_coord = base_point.coordinates().unwrap();
     let pt_x = FC::fe_to_witness(&Value::known(*base_point_coord.x()));
     let pt_y = FC::fe_to_witness(&Value::known(*base_point_coord.y()));
@@ -453,56 +734,148 @@ where
     // add selector for whether P_i is the point at infinity (aka 0 in elliptic curve group)
     // this can be checked by P_i.y == 0 iff P_i == O
     let mut is_infinity = Vec::with_capacity(k);
-    for i in 0..k {
-        let is_zero = chip.is_zero(ctx, &P[i].y)?;
+    for i in order.iter() {
+        let is_zero = chip.is_zero(ctx, &P[*i].y)?;
         is_infinity.push(is_zero);
     }
 
     let cache_size = 1usize << window_bits;
     let mut cached_points_vec = Vec::with_capacity(k);
-    for idx in 0..k {
+    for (pos, i) in order.iter().enumerate() {
         let mut cached_points = Vec::with_capacity(cache_size);
-        cached_points.push(neg_mult_rand_start_vec[idx].clone());
+        cached_points.push(neg_mult_rand_start_vec[pos].clone());
         for cache_idx in 0..(cache_size - 1) {
             // adversary could pick `A` so add equal case occurs, so we must use strict add_unequal
             let mut new_point =
-                ecc_add_unequal(chip, ctx, &cached_points[cache_idx], &P[idx], true)?;
+                ecc_add_unequal(chip, ctx, &cached_points[cache_idx], &P[*i], true)?;
             // special case for when P[idx] = O
             new_point =
-                select(chip, ctx, &cached_points[cache_idx], &new_point, &is_infinity[idx])?;
+                select(chip, ctx, &cached_points[cache_idx], &new_point, &is_infinity[pos])?;
             cached_points.push(new_point);
         }
         cached_points_vec.push(cached_points);
     }
 
-    // initialize at (2^{k + 1} - 1) * A
-    // note k can be large (e.g., 800) so 2^{k+1} may be larger than the order of A
-    // random fact: 2^{k + 1} - 1 can be prime: see Mersenne primes
-    // TODO: I don't see a way to rule out 2^{k+1} A = +-A case in general, so will use strict sub_unequal
-    let start_point = ecc_sub_unequal(chip, ctx, &rand_start_vec[k], &rand_start_vec[0], true)?;
-    let mut curr_point = start_point.clone();
-
-    // compute \sum_i x_i P_i + (2^{k + 1} - 1) * A
+    // the group of positions [start, end) with `m` windows joins with (2^end - 2^start) * A, which the doublings of
+    // its windows scale by 2^{w m}, while its cached points contribute (1 - 2^{w m}) (2^end - 2^start) * A;
+    // in all, the accumulator ends at \sum_i x_i P_i + (2^k - 1) * A
+    // note k can be large (e.g., 800) so 2^k may be larger than the order of A
+    // TODO: I don't see a way to rule out 2^k A = +-A case in general, so will use strict sub_unequal
+    let mut curr_point: Option<EccPoint<F, FC::FieldPoint>> = None;
+    let mut join_points = Vec::new();
+    let mut start = 0;
+    // compute \sum_i x_i P_i + (2^k - 1) * A
     for idx in 0..num_windows {
-        for _ in 0..window_bits {
-            curr_point = ecc_double(chip, ctx, &curr_point)?;
+        let remaining = num_windows - idx;
+        if start < k && num_windows_vec[start] == remaining {
+            let end =
+                start + num_windows_vec[start..].iter().take_while(|m| **m == remaining).count();
+            let join_point =
+                ecc_sub_unequal(chip, ctx, &rand_start_vec[end], &rand_start_vec[start], true)?;
+            curr_point = Some(match curr_point {
+                None => join_point.clone(),
+                Some(curr) => ecc_add_unequal(chip, ctx, &curr, &join_point, true)?,
+            });
+            join_points.push(join_point);
+            start = end;
         }
-        for base_idx in 0..k {
+        let mut curr = ecc_double_n(chip, ctx, &curr_point.unwrap(), window_bits)?;
+        for pos in 0..start {
+            let rounded_bitlen = num_windows_vec[pos] * window_bits;
             let add_point = select_from_bits(
                 chip,
                 ctx,
-                &cached_points_vec[base_idx],
-                &rounded_bits_vec[base_idx]
-                    [rounded_bitlen - window_bits * (idx + 1)..rounded_bitlen - window_bits * idx]
+                &cached_points_vec[pos],
+                &rounded_bits_vec[pos][rounded_bitlen - window_bits * remaining
+                    ..rounded_bitlen - window_bits * (remaining - 1)]
                     .to_vec(),
             )?;
             // this all needs strict add_unequal since A can be non-randomly chosen by adversary
-            curr_point = ecc_add_unequal(chip, ctx, &curr_point, &add_point, true)?;
+            curr = ecc_add_unequal(chip, ctx, &curr, &add_point, true)?;
         }
+        curr_point = Some(curr);
     }
-    curr_point = ecc_sub_unequal(chip, ctx, &curr_point, &start_point, true)?;
+    // with a single group, its join point is (2^k - 1) * A already
+    let start_point = if join_points.len() == 1 {
+        join_points.pop().unwrap()
+    } else {
+        ecc_sub_unequal(chip, ctx, &rand_start_vec[k], &rand_start_vec[0], true)?
+    };
 
-    Ok(curr_point.clone())
+    Ok((curr_point.unwrap(), start_point))
+}
+
+// the window size minimizing the additions `2^w + ceil(scalar_bits / w)` per point of `multi_scalar_multiply`
+fn msm_small_window_bits(scalar_bits: usize) -> usize {
+    (1..=4).min_by_key(|w| (1usize << w) + (scalar_bits + w - 1) / w).unwrap()
+}
+
+// MSM for scalars of at most `scalar_bits` bits, e.g. the powers of a short challenge in aggregation circuits
+// - each scalar is a single cell, constrained to `scalar_bits` bits by num_to_bits
+// - the windows only cover `scalar_bits` bits instead of the full scalar field, with their size picked for it
+pub fn variable_base_msm_small<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    P: &Vec<EccPoint<F, FC::FieldPoint>>,
+    scalars: &[AssignedValue<F>],
+    b: F,
+    scalar_bits: usize,
+    rng: &mut impl RngCore,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    GA: CurveAffine<Base = FC::FieldType>,
+{
+    assert!(scalar_bits > 0);
+    let scalars = scalars.iter().map(|scalar| vec![scalar.clone()]).collect();
+    multi_scalar_multiply_with_rng::<F, FC, GA>(
+        chip,
+        ctx,
+        P,
+        &scalars,
+        b,
+        scalar_bits,
+        msm_small_window_bits(scalar_bits),
+        rng,
+    )
+}
+
+// the SEC1 compressed encoding of `P`: the prefix byte `2 + (y mod 2)` followed by the big-endian bytes of `x`,
+// each byte constrained to be in [0, 256)
+// - `x` and `y` are constrained to be less than p, so the encoding is that of their canonical representatives
+// assumes the limbs of `P` are in proper representation, e.g. the output of `carry_mod`
+pub fn ecc_to_compressed_bytes<F: FieldExt, CF: PrimeField>(
+    base_chip: &FpConfig<F, CF>,
+    ctx: &mut Context<'_, F>,
+    P: &EccPoint<F, CRTInteger<F>>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let range = base_chip.range();
+    let gate = range.gate();
+    base_chip.enforce_less_than_p(ctx, &P.x)?;
+    base_chip.enforce_less_than_p(ctx, &P.y)?;
+
+    let num_bytes = (CF::NUM_BITS as usize + 7) / 8;
+    let mut bits = Vec::with_capacity(8 * num_bytes);
+    for limb in P.x.truncation.limbs.iter() {
+        bits.extend(range.num_to_bits(ctx, limb, base_chip.limb_bits)?);
+    }
+    // x < p < 2^{8 * num_bytes}, so any bits past the last byte are 0
+    bits.truncate(8 * num_bytes);
+    let zero = gate.load_zero(ctx)?;
+    bits.resize(8 * num_bytes, zero);
+
+    let mut bytes = Vec::with_capacity(num_bytes + 1);
+    let (_, y_parity) = range.div_mod(
+        ctx,
+        &Existing(&P.y.truncation.limbs[0]),
+        &BigUint::from(2u64),
+        base_chip.limb_bits,
+    )?;
+    bytes.push(gate.add(ctx, &Constant(F::from(2)), &Existing(&y_parity))?);
+    for byte_bits in bits.chunks(8).rev() {
+        bytes.push(gate.bits_to_num(ctx, byte_bits, false)?);
+    }
+    Ok(bytes)
 }
 
 // CF is the coordinate field of GA
@@ -520,6 +893,62 @@ pub fn ecdsa_verify_no_pubkey_check<F: FieldExt, CF: PrimeField, SF: PrimeField,
     var_window_bits: usize,
     fixed_window_bits: usize,
 ) -> Result<AssignedValue<F>, Error>
+where
+    GA: CurveAffine<Base = CF, ScalarExt = SF>,
+{
+    ecdsa_verify::<F, CF, SF, GA>(
+        base_chip,
+        ctx,
+        pubkey,
+        r,
+        s,
+        msghash,
+        var_window_bits,
+        fixed_window_bits,
+        false,
+    )
+}
+
+// Same as `ecdsa_verify_no_pubkey_check`, but checks `r == x1 (mod n)` with `ecdsa_r_matches_x` instead of
+// `r == x1` as integers, so signatures with `x1 >= n` are also accepted, as consensus rules require
+// Valid whenever p < 2n
+pub fn ecdsa_verify_reduced_no_pubkey_check<F: FieldExt, CF: PrimeField, SF: PrimeField, GA>(
+    base_chip: &FpConfig<F, CF>,
+    ctx: &mut Context<'_, F>,
+    pubkey: &EccPoint<F, <FpConfig<F, CF> as FieldChip<F>>::FieldPoint>,
+    r: &OverflowInteger<F>,
+    s: &OverflowInteger<F>,
+    msghash: &OverflowInteger<F>,
+    var_window_bits: usize,
+    fixed_window_bits: usize,
+) -> Result<AssignedValue<F>, Error>
+where
+    GA: CurveAffine<Base = CF, ScalarExt = SF>,
+{
+    ecdsa_verify::<F, CF, SF, GA>(
+        base_chip,
+        ctx,
+        pubkey,
+        r,
+        s,
+        msghash,
+        var_window_bits,
+        fixed_window_bits,
+        true,
+    )
+}
+
+fn ecdsa_verify<F: FieldExt, CF: PrimeField, SF: PrimeField, GA>(
+    base_chip: &FpConfig<F, CF>,
+    ctx: &mut Context<'_, F>,
+    pubkey: &EccPoint<F, <FpConfig<F, CF> as FieldChip<F>>::FieldPoint>,
+    r: &OverflowInteger<F>,
+    s: &OverflowInteger<F>,
+    msghash: &OverflowInteger<F>,
+    var_window_bits: usize,
+    fixed_window_bits: usize,
+    reduce_x: bool,
+) -> Result<AssignedValue<F>, Error>
 where
     GA: CurveAffine<Base = CF, ScalarExt = SF>,
 {
@@ -545,8 +974,6 @@ where
     let u1 = scalar_chip.divide(ctx, msghash, s)?;
     let u2 = scalar_chip.divide(ctx, r, s)?;
 
-    let r_crt = scalar_chip.to_crt(ctx, r)?;
-
     // compute u1 * G and u2 * pubkey
     let u1_mul =
         fixed_base_scalar_multiply(base_chip, ctx, &G, &u1.limbs, u1.limb_bits, fixed_window_bits)?;
@@ -560,11 +987,17 @@ where
     let u1_u2_x_eq = base_chip.is_equal(ctx, &u1_mul.x, &u2_mul.x)?;
     let u1_u2_not_neg = base_chip.range.gate().not(ctx, &Existing(&u1_u2_x_eq))?;
 
-    // compute (x1, y1) = u1 * G + u2 * pubkey and check (r mod n) == x1 as integers
-    // WARNING: For optimization reasons, does not reduce x1 mod n, which is
-    //          invalid unless p is very close to n in size.
+    // compute (x1, y1) = u1 * G + u2 * pubkey and check r == x1 mod n
+    // WARNING: x1 is only reduced mod n if `reduce_x`, see `ecdsa_r_matches_x`. Otherwise r == x1 is checked as
+    //          integers for optimization reasons, which rejects the valid signatures with x1 >= n, so this is only
+    //          acceptable when p is very close to n in size.
     let sum = ecc_add_unequal(base_chip, ctx, &u1_mul, &u2_mul, false)?;
-    let equal_check = base_chip.is_equal(ctx, &sum.x, &r_crt)?;
+    let equal_check = if reduce_x {
+        ecdsa_r_matches_x::<F, CF, SF>(base_chip, ctx, r, &sum.x)?
+    } else {
+        let r_crt = scalar_chip.to_crt(ctx, r)?;
+        base_chip.is_equal(ctx, &sum.x, &r_crt)?
+    };
 
     // TODO: maybe the big_less_than is optional?
     let u1_small = big_less_than::assign(base_chip.range(), ctx, &u1, &n)?;
@@ -579,6 +1012,72 @@ where
     Ok(res5)
 }
 
+// The checks of ECDSA signatures required by the consensus rules of Ethereum and Bitcoin, each returning a flag
+// `a`, `r` and `s` are scalars in proper representation, e.g. loaded by `FpOverflowChip::load_private`
+
+// returns whether 1 <= a < n, for r and s of a signature
+pub fn ecdsa_is_in_range<F: FieldExt, SF: PrimeField>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let is_zero = big_is_zero::assign(range, ctx, a)?;
+    let is_nonzero = range.gate().not(ctx, &Existing(&is_zero))?;
+    let is_less = big_less_than::assign_constant(range, ctx, a, &modulus::<SF>())?;
+    range.gate().and(ctx, &Existing(&is_nonzero), &Existing(&is_less))
+}
+
+// returns whether s <= n / 2, which rules out the malleable signature (r, n - s) (EIP-2, BIP-146)
+pub fn ecdsa_is_low_s<F: FieldExt, SF: PrimeField>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    s: &OverflowInteger<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let half_n = modulus::<SF>() / 2u32;
+    big_less_than::assign_constant(range, ctx, s, &(half_n + 1u32))
+}
+
+// returns whether x < p and r == x (mod n), for the x-coordinate x of u1 * G + u2 * pubkey
+// as p < 2n, x (mod n) is x - n if x >= n and x otherwise, so the rare signatures with r = x - n are accepted
+// assumes x is in proper representation, e.g. the output of `carry_mod`
+pub fn ecdsa_r_matches_x<F: FieldExt, CF: PrimeField, SF: PrimeField>(
+    base_chip: &FpConfig<F, CF>,
+    ctx: &mut Context<'_, F>,
+    r: &OverflowInteger<F>,
+    x: &CRTInteger<F>,
+) -> Result<AssignedValue<F>, Error> {
+    let n = modulus::<SF>();
+    assert!(base_chip.p < &n << 1, "x mod n should be x or x - n");
+    let range = base_chip.range();
+    let x_is_reduced = big_less_than::assign_constant(range, ctx, &x.truncation, &base_chip.p)?;
+
+    let scalar_chip = FpOverflowChip::<F, SF>::construct(
+        &base_chip.range,
+        base_chip.limb_bits,
+        base_chip.num_limbs,
+        n,
+    );
+    let x_mod_n = reduce_once(&scalar_chip, ctx, &x.truncation)?;
+
+    let r_eq = big_is_equal::assign(range, ctx, r, &x_mod_n)?;
+    range.gate().and(ctx, &Existing(&x_is_reduced), &Existing(&r_eq))
+}
+
+// returns a (mod n) in proper representation for the modulus n of `scalar_chip`
+// assumes a is in proper representation and a < 2n, e.g. a coordinate of the base field or a 256 bit hash for a
+// curve whose n is close to 2^256
+pub fn reduce_once<F: FieldExt, SF: PrimeField>(
+    scalar_chip: &FpOverflowChip<F, SF>,
+    ctx: &mut Context<'_, F>,
+    a: &OverflowInteger<F>,
+) -> Result<OverflowInteger<F>, Error> {
+    let n = scalar_chip.load_constant(ctx, BigInt::from(scalar_chip.p.clone()))?;
+    // underflow != 0 iff a < n
+    let (a_minus_n, underflow) = sub::assign(scalar_chip.range(), ctx, a, &n)?;
+    let a_ge_n = scalar_chip.range.is_zero(ctx, &underflow)?;
+    select::assign(scalar_chip.range.gate(), ctx, &a_minus_n, a, &a_ge_n)
+}
+
 pub fn get_naf(mut exp: Vec<u64>) -> Vec<i8> {
     // https://en.wikipedia.org/wiki/Non-adjacent_form
     // NAF for exp:
@@ -623,6 +1122,58 @@ pub fn get_naf(mut exp: Vec<u64>) -> Vec<i8> {
     naf
 }
 
+// the signed digits of the scalar `sum_i scalar_i * 2^{max_bits * i}`, as in `scalar_multiply`, in windows of
+// `window_bits` bits: little-endian pairs `(is_negative, magnitude)` with `magnitude <= 2^{w - 1}`, such that
+// `scalar = sum_i (-1)^{is_negative_i} * magnitude_i * 2^{w i}`
+// - unlike `get_naf`, the digits are constrained: the bits of the scalar are constrained by `num_to_bits`, and
+//   each window `u_i` with the carry `c_i` into it gives the digit `u_i + c_i - 2^w c_{i + 1}`, where
+//   `c_{i + 1} = 1` exactly when `u_i + c_i >= 2^{w - 1}`, so the recomposition telescopes to the scalar
+// - the last digit is the final carry, which is 0 or 1
+pub fn signed_window_digits<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    scalar: &Vec<AssignedValue<F>>,
+    max_bits: usize,
+    window_bits: usize,
+) -> Result<Vec<(AssignedValue<F>, AssignedValue<F>)>, Error> {
+    assert!(scalar.len() > 0 && window_bits > 0);
+    assert!((max_bits as u64) <= modulus::<F>().bits());
+    let gate = range.gate();
+
+    let total_bits = max_bits * scalar.len();
+    let num_windows = (total_bits + window_bits - 1) / window_bits;
+    let zero_cell = gate.load_zero(ctx)?;
+    let mut bits = Vec::with_capacity(num_windows * window_bits);
+    for x in scalar.iter() {
+        bits.append(&mut range.num_to_bits(ctx, x, max_bits)?);
+    }
+    bits.resize(num_windows * window_bits, zero_cell.clone());
+
+    let half = F::from(1u64 << (window_bits - 1));
+    let mut digits = Vec::with_capacity(num_windows + 1);
+    let mut carry = zero_cell.clone();
+    for window in bits.chunks(window_bits) {
+        let u = gate.bits_to_num(ctx, window, false)?;
+        // u + carry >= 2^{w - 1} exactly when the top bit of u is 1, or u = 2^{w - 1} - 1 and carry = 1
+        let is_half_minus_one = range.is_equal(ctx, &Existing(&u), &Constant(half - F::one()))?;
+        let next_carry = gate.or_and(
+            ctx,
+            &Existing(&window[window_bits - 1]),
+            &Existing(&carry),
+            &Existing(&is_half_minus_one),
+        )?;
+        let t = gate.add(ctx, &Existing(&u), &Existing(&carry))?;
+        // the digit is t - 2^w if the carry out is 1, and t otherwise
+        let neg_magnitude = gate.sub(ctx, &Constant(half + half), &Existing(&t))?;
+        let magnitude =
+            gate.select(ctx, &Existing(&neg_magnitude), &Existing(&t), &Existing(&next_carry))?;
+        digits.push((next_carry.clone(), magnitude));
+        carry = next_carry;
+    }
+    digits.push((zero_cell, carry));
+    Ok(digits)
+}
+
 pub struct EccChip<'a, F: FieldExt, FC: FieldChip<F>> {
     pub field_chip: &'a FC,
     _marker: PhantomData<F>,
@@ -667,7 +1218,20 @@ impl<'a, F: FieldExt, FC: FieldChip<F>> EccChip<'a, F, FC> {
         C: CurveAffine<Base = FC::FieldType>,
         C::Base: PrimeField,
     {
-        let pt: C = C::CurveExt::random(OsRng).to_affine();
+        self.load_random_point_with_rng::<C>(ctx, &mut OsRng)
+    }
+
+    /// `load_random_point` with the point drawn from `rng`, e.g. a seeded RNG for a reproducible witness
+    pub fn load_random_point_with_rng<C>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        rng: &mut impl RngCore,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+    where
+        C: CurveAffine<Base = FC::FieldType>,
+        C::Base: PrimeField,
+    {
+        let pt: C = C::CurveExt::random(rng).to_affine();
         let assigned = self.assign_point(ctx, Value::known(pt))?;
         self.assert_is_on_curve::<C>(ctx, &assigned)?;
         Ok(assigned)
@@ -760,6 +1324,26 @@ impl<'a, F: FieldExt, FC: FieldChip<F>> EccChip<'a, F, FC> {
         ecc_double(self.field_chip, ctx, P)
     }
 
+    /// `[c] * P` for a constant scalar `c > 0`, see `scalar_multiply_constant`
+    pub fn scalar_mult_constant(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &EccPoint<F, FC::FieldPoint>,
+        c: &BigUint,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error> {
+        scalar_multiply_constant(self.field_chip, ctx, P, c)
+    }
+
+    /// `2^n P`, see `ecc_double_n`
+    pub fn double_n(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &EccPoint<F, FC::FieldPoint>,
+        n: usize,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error> {
+        ecc_double_n(self.field_chip, ctx, P, n)
+    }
+
     pub fn is_equal(
         &self,
         ctx: &mut Context<'_, F>,
@@ -788,6 +1372,16 @@ impl<F: FieldExt, FC: FieldChip<F>> EccChip<'_, F, FC>
 where
     FC: Selectable<F, Point = FC::FieldPoint>,
 {
+    /// `-P` if `flag` is 1 and `P` if it is 0, see `select_neg`
+    pub fn select_neg(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &EccPoint<F, FC::FieldPoint>,
+        flag: &AssignedValue<F>,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error> {
+        select_neg(self.field_chip, ctx, P, flag)
+    }
+
     pub fn scalar_mult(
         &self,
         ctx: &mut Context<'_, F>,
@@ -807,6 +1401,30 @@ where
         max_bits: usize,
         window_bits: usize,
     ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+    where
+        GA: CurveAffine<Base = FC::FieldType>,
+        GA::Base: PrimeField,
+    {
+        self.multi_scalar_mult_with_rng::<GA>(
+            ctx,
+            P,
+            scalars,
+            max_bits,
+            window_bits,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// `multi_scalar_mult` with its random base point drawn from `rng`, e.g. a seeded RNG for a reproducible witness
+    pub fn multi_scalar_mult_with_rng<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &Vec<EccPoint<F, FC::FieldPoint>>,
+        scalars: &Vec<Vec<AssignedValue<F>>>,
+        max_bits: usize,
+        window_bits: usize,
+        rng: &mut impl RngCore,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
     where
         GA: CurveAffine<Base = FC::FieldType>,
         GA::Base: PrimeField,
@@ -816,7 +1434,7 @@ where
 
         let curve_b = biguint_to_fe::<F>(&fe_to_biguint(&GA::b()));
         if P.len() < 25 {
-            multi_scalar_multiply::<F, FC, GA>(
+            multi_scalar_multiply_with_rng::<F, FC, GA>(
                 self.field_chip,
                 ctx,
                 P,
@@ -824,6 +1442,7 @@ where
                 curve_b,
                 max_bits,
                 window_bits,
+                rng,
             )
         } else {
             /*let mut radix = (f64::from((max_bits * scalars[0].len()) as u32)
@@ -834,7 +1453,7 @@ where
                 radix = 1;
             }*/
             let radix = 1;
-            pippenger::multi_exp::<F, FC, GA>(
+            pippenger::multi_exp_with_rng::<F, FC, GA>(
                 self.field_chip,
                 ctx,
                 P,
@@ -843,9 +1462,87 @@ where
                 max_bits,
                 radix,
                 window_bits,
+                rng,
             )
         }
     }
+
+    /// MSM where the cells of `scalars[i]` have at most `max_bits[i]` bits, see
+    /// `multi_scalar_multiply_var_bits_with_rng`
+    pub fn multi_scalar_mult_var_bits<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &Vec<EccPoint<F, FC::FieldPoint>>,
+        scalars: &Vec<Vec<AssignedValue<F>>>,
+        max_bits: &[usize],
+        window_bits: usize,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+    where
+        GA: CurveAffine<Base = FC::FieldType>,
+        GA::Base: PrimeField,
+    {
+        let curve_b = biguint_to_fe::<F>(&fe_to_biguint(&GA::b()));
+        multi_scalar_multiply_var_bits_with_rng::<F, FC, GA>(
+            self.field_chip,
+            ctx,
+            P,
+            scalars,
+            curve_b,
+            max_bits,
+            window_bits,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// MSM with single-cell scalars of at most `scalar_bits` bits, see `variable_base_msm_small`
+    pub fn variable_base_msm_small<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &Vec<EccPoint<F, FC::FieldPoint>>,
+        scalars: &[AssignedValue<F>],
+        scalar_bits: usize,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+    where
+        GA: CurveAffine<Base = FC::FieldType>,
+        GA::Base: PrimeField,
+    {
+        let curve_b = biguint_to_fe::<F>(&fe_to_biguint(&GA::b()));
+        variable_base_msm_small::<F, FC, GA>(
+            self.field_chip,
+            ctx,
+            P,
+            scalars,
+            curve_b,
+            scalar_bits,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// MSM where the bases and the result may be the point at infinity, given as (0, 0); also returns whether
+    /// the result is the point at infinity. See `multi_scalar_multiply_with_identity`
+    pub fn multi_scalar_mult_with_identity<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &Vec<EccPoint<F, FC::FieldPoint>>,
+        scalars: &Vec<Vec<AssignedValue<F>>>,
+        max_bits: usize,
+        window_bits: usize,
+    ) -> Result<(EccPoint<F, FC::FieldPoint>, AssignedValue<F>), Error>
+    where
+        FC::ConstantType: From<BigInt>,
+        GA: CurveAffine<Base = FC::FieldType>,
+        GA::Base: PrimeField,
+    {
+        multi_scalar_multiply_with_identity::<F, FC, GA>(
+            self.field_chip,
+            ctx,
+            P,
+            scalars,
+            max_bits,
+            window_bits,
+            &mut rand::thread_rng(),
+        )
+    }
 }
 
 impl<'a, F: FieldExt, FC: PrimeFieldChip<F>> EccChip<'a, F, FC>
@@ -868,6 +1565,111 @@ where
     {
         fixed_base_scalar_multiply(self.field_chip, ctx, P, scalar, max_bits, window_bits)
     }
+
+    pub fn fixed_base_scalar_mult_with_table<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        table: &FixedBaseTable<F, GA>,
+        scalar: &Vec<AssignedValue<F>>,
+        max_bits: usize,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+    where
+        GA: CurveAffine,
+        GA::Base: PrimeField,
+        FC: PrimeFieldChip<F, FieldType = GA::Base, FieldPoint = CRTInteger<F>>
+            + Selectable<F, Point = FC::FieldPoint>,
+    {
+        fixed_base_scalar_multiply_with_table(self.field_chip, ctx, table, scalar, max_bits)
+    }
+
+    /// `[a] * G + [b] * P` for a fixed point `G`, e.g. the generator in ECDSA, and a variable point `P`, sharing
+    /// the doublings of both multiplications. See `fixed_plus_variable_scalar_multiply`
+    pub fn fixed_plus_variable_scalar_mult<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        G: &GA,
+        a: &Vec<AssignedValue<F>>,
+        P: &EccPoint<F, FC::FieldPoint>,
+        b: &Vec<AssignedValue<F>>,
+        max_bits: usize,
+        window_bits: usize,
+    ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+    where
+        GA: CurveAffine,
+        GA::Base: PrimeField,
+        FC: PrimeFieldChip<F, FieldType = GA::Base, FieldPoint = CRTInteger<F>>
+            + Selectable<F, Point = FC::FieldPoint>,
+    {
+        fixed_plus_variable_scalar_multiply(
+            self.field_chip,
+            ctx,
+            G,
+            a,
+            P,
+            b,
+            max_bits,
+            window_bits,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// `sum_i scalars[i] * points[i]` for fixed `points`, any of which may be the point at infinity; also
+    /// returns whether the result is the point at infinity, given as (0, 0). See `fixed_base_msm`
+    pub fn fixed_base_msm<GA>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        points: &[GA],
+        scalars: &Vec<Vec<AssignedValue<F>>>,
+        max_bits: usize,
+        window_bits: usize,
+    ) -> Result<(EccPoint<F, FC::FieldPoint>, AssignedValue<F>), Error>
+    where
+        GA: CurveAffine,
+        GA::Base: PrimeField,
+        FC: PrimeFieldChip<F, FieldType = GA::Base, FieldPoint = CRTInteger<F>>
+            + Selectable<F, Point = FC::FieldPoint>,
+        FC::ConstantType: From<BigInt>,
+    {
+        fixed_base_msm(
+            self.field_chip,
+            ctx,
+            points,
+            scalars,
+            max_bits,
+            window_bits,
+            &mut rand::thread_rng(),
+        )
+    }
+}
+
+impl<'a, F: FieldExt, CF: PrimeField> EccChip<'a, F, FpConfig<F, CF>> {
+    /// Loads `g` and constrains it to be on the curve `C`, with coordinates less than p.
+    ///
+    /// This does not check subgroup membership, which is implied for curves of prime order such as BN254 G1 and
+    /// secp256k1. For other curves, check it separately, e.g. with `G2Chip::assign_point_checked` for BN254 G2.
+    pub fn assign_point_checked<C>(
+        &self,
+        ctx: &mut Context<'_, F>,
+        g: Value<C>,
+    ) -> Result<EccPoint<F, CRTInteger<F>>, Error>
+    where
+        C: CurveAffine<Base = CF>,
+    {
+        let P = self.assign_point(ctx, g)?;
+        self.field_chip.enforce_less_than_p(ctx, &P.x)?;
+        self.field_chip.enforce_less_than_p(ctx, &P.y)?;
+        self.assert_is_on_curve::<C>(ctx, &P)?;
+        Ok(P)
+    }
+
+    /// The SEC1 compressed encoding of `P` as range checked bytes, see `ecc_to_compressed_bytes`
+    pub fn to_compressed_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        P: &EccPoint<F, CRTInteger<F>>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        ecc_to_compressed_bytes(self.field_chip, ctx, P)
+    }
 }
 
 #[cfg(test)]
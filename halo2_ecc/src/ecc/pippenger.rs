@@ -1,5 +1,6 @@
 use super::{
-    ecc_add_unequal, ecc_double, ecc_sub_unequal, is_on_curve, select, select_from_bits, EccPoint,
+    ecc_add_unequal, ecc_double, ecc_double_n, ecc_sub_unequal, is_on_curve, select,
+    select_from_bits, EccPoint,
 };
 use crate::fields::{FieldChip, Selectable};
 use group::{Curve, Group};
@@ -12,7 +13,7 @@ use halo2_proofs::{
     circuit::Value,
     plonk::Error,
 };
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 
 // Reference: https://jbootle.github.io/Misc/pippenger.pdf
 
@@ -75,6 +76,31 @@ pub fn multi_product<F: FieldExt, FC, GA>(
     curve_b: F,
     clumping_factor: usize,
 ) -> Result<(Vec<EccPoint<F, FC::FieldPoint>>, EccPoint<F, FC::FieldPoint>), Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    GA: CurveAffine<Base = FC::FieldType>,
+{
+    multi_product_with_rng::<F, FC, GA>(
+        chip,
+        ctx,
+        points,
+        bool_scalars,
+        curve_b,
+        clumping_factor,
+        &mut OsRng,
+    )
+}
+
+// `multi_product` with the random base point drawn from `rng`
+pub fn multi_product_with_rng<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    points: &Vec<EccPoint<F, FC::FieldPoint>>,
+    bool_scalars: &Vec<Vec<AssignedValue<F>>>,
+    curve_b: F,
+    clumping_factor: usize,
+    rng: &mut impl RngCore,
+) -> Result<(Vec<EccPoint<F, FC::FieldPoint>>, EccPoint<F, FC::FieldPoint>), Error>
 where
     FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
     GA: CurveAffine<Base = FC::FieldType>,
@@ -95,7 +121,7 @@ where
     // note that while we load a random point, an adversary could load a specifically chosen point, so we must carefully handle edge cases with constraints
     // TODO: an alternate approach is to use Fiat-Shamir transform (with Poseidon) to hash all the inputs (points, bool_scalars, ...) to get the random point. This could be worth it for large MSMs as we get savings from `add_unequal` in "non-strict" mode. Perhaps not worth the trouble / security concern, though.
     let rand_base = {
-        let base_point: GA = GA::CurveExt::random(OsRng).to_affine();
+        let base_point: GA = GA::CurveExt::random(rng).to_affine();
         let base_point_coord = base_point.coordinates().unwrap();
         let pt_x = FC::fe_to_witness(&Value::known(*base_point_coord.x()));
         let pt_y = FC::fe_to_witness(&Value::known(*base_point_coord.y()));
@@ -160,6 +186,35 @@ pub fn multi_exp<F: FieldExt, FC, GA>(
     radix: usize,
     clump_factor: usize,
 ) -> Result<EccPoint<F, FC::FieldPoint>, Error>
+where
+    FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
+    GA: CurveAffine<Base = FC::FieldType>,
+{
+    multi_exp_with_rng::<F, FC, GA>(
+        chip,
+        ctx,
+        points,
+        scalars,
+        curve_b,
+        max_scalar_bits_per_cell,
+        radix,
+        clump_factor,
+        &mut OsRng,
+    )
+}
+
+// `multi_exp` with the random base point of `multi_product` drawn from `rng`
+pub fn multi_exp_with_rng<F: FieldExt, FC, GA>(
+    chip: &FC,
+    ctx: &mut Context<'_, F>,
+    points: &Vec<EccPoint<F, FC::FieldPoint>>,
+    scalars: &Vec<Vec<AssignedValue<F>>>,
+    curve_b: F,
+    max_scalar_bits_per_cell: usize,
+    radix: usize,
+    clump_factor: usize,
+    rng: &mut impl RngCore,
+) -> Result<EccPoint<F, FC::FieldPoint>, Error>
 where
     FC: FieldChip<F> + Selectable<F, Point = FC::FieldPoint>,
     GA: CurveAffine<Base = FC::FieldType>,
@@ -188,17 +243,15 @@ where
     println!("clumping factor: {}", c);
 
     let (mut agg, rand_point) =
-        multi_product::<F, FC, GA>(chip, ctx, &points, &bool_scalars, curve_b, c)?;
+        multi_product_with_rng::<F, FC, GA>(chip, ctx, &points, &bool_scalars, curve_b, c, rng)?;
 
     // compute sum_{k=0..t} agg[k] * 2^{radix * k} - (sum_k 2^{radix * k}) * rand_point
     // (sum_{k=0..t} 2^{radix * k}) * rand_point = (2^{radix * t} - 1)/(2^radix - 1)
     let mut sum = agg.pop().unwrap();
     let mut rand_sum = rand_point.clone();
     for g in agg.iter().rev() {
-        for _ in 0..radix {
-            sum = ecc_double(chip, ctx, &sum)?;
-            rand_sum = ecc_double(chip, ctx, &rand_sum)?;
-        }
+        sum = ecc_double_n(chip, ctx, &sum, radix)?;
+        rand_sum = ecc_double_n(chip, ctx, &rand_sum, radix)?;
         sum = ecc_add_unequal(chip, ctx, &sum, g, true)?;
 
         if radix != 1 {
@@ -3,9 +3,12 @@ use super::*;
 use crate::fields::fp::{FpConfig, FpStrategy};
 use crate::fields::fp2::Fp2Chip;
 use ff::PrimeField;
-use group::Group;
+use group::{prime::PrimeCurveAffine, Group};
 use halo2_base::utils::bigint_to_fe;
-use halo2_base::{gates::range::RangeStrategy, utils::value_to_option, ContextParams};
+use halo2_base::{
+    gates::range::RangeStrategy, utils::value_to_option, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::*,
@@ -13,7 +16,9 @@ use halo2_proofs::{
     halo2curves::bn256::{Fq, Fr, G1Affine, G2Affine, G1, G2},
     plonk::*,
 };
+use halo2curves::secp256k1::{Fp as SecpFp, Fq as SecpFq};
 use num_bigint::{BigInt, RandBigInt};
+use rand::{rngs::StdRng, SeedableRng};
 use std::marker::PhantomData;
 
 #[derive(Default)]
@@ -45,7 +50,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
             88,
             3,
             modulus::<Fq>(),
-            "default".to_string(),
+            ContextId::new("default"),
         )
     }
 
@@ -70,7 +75,7 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
 
                 let mut aux = Context::new(
                     region,
-                    ContextParams { num_advice: vec![("default".to_string(), NUM_ADVICE)] },
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
                 );
                 let ctx = &mut aux;
 
@@ -119,10 +124,47 @@ impl<F: FieldExt> Circuit<F> for MyCircuit<F> {
                     println!("double witness OK");
                 }
 
+                // test scalar_mult_constant, for a small constant and one whose NAF has negative digits
+                for c in [8u64, 0xb7e1_5162_8aed_2a6b] {
+                    let out = chip.scalar_mult_constant(ctx, &P_assigned, &BigUint::from(c))?;
+                    if let Some(P) = self.P {
+                        let expected = G1Affine::from(P * Fr::from(c));
+                        out.x.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), expected.x));
+                        out.y.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), expected.y));
+                    }
+                }
+
+                // test double_n
+                {
+                    let doub = chip.double_n(ctx, &P_assigned, 5)?;
+                    if self.P != None {
+                        let actual_doub = G1Affine::from(self.P.unwrap() * Fr::from(32));
+                        doub.x.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), actual_doub.x));
+                        doub.y.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), actual_doub.y));
+                    }
+                }
+
+                // test select_neg
+                for flag in [0u64, 1] {
+                    let flag_assigned = config.range.gate.assign_region_smart(
+                        ctx,
+                        vec![Witness(Value::known(F::from(flag)))],
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?;
+                    let out = chip.select_neg(ctx, &P_assigned, &flag_assigned[0])?;
+                    if let Some(P) = self.P {
+                        let expected = if flag == 1 { -P } else { P };
+                        out.x.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), expected.x));
+                        out.y.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), expected.y));
+                    }
+                }
+
                 println!("Using {} advice columns and {} fixed columns", NUM_ADVICE, NUM_FIXED);
                 println!(
                     "maximum rows used by an advice column: {}",
-                    ctx.advice_rows["default"].iter().max().unwrap()
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
                 );
                 let (const_rows, _, _) = chip.field_chip.finalize(ctx)?;
                 println!("maximum rows used by a fixed column: {}", const_rows);
@@ -330,3 +372,1091 @@ fn plot_ecc_g2() {
     halo2_proofs::dev::CircuitLayout::default().render(k, &circuit, &root).unwrap();
 }
 */
+
+// loads random points drawn from RNGs seeded with `seeds` and checks that equal seeds give equal points
+#[derive(Default)]
+struct SeededPointCircuit<F> {
+    seeds: Vec<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SeededPointCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { seeds: self.seeds.clone(), _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            11,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "seeded points",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut points = Vec::with_capacity(self.seeds.len());
+                for seed in self.seeds.iter() {
+                    let mut rng = StdRng::seed_from_u64(*seed);
+                    let point = chip.load_random_point_with_rng::<G1Affine>(ctx, &mut rng)?;
+                    points.push(value_to_option(point.x.value.clone()).unwrap());
+                }
+                for (i, seed) in self.seeds.iter().enumerate() {
+                    for (j, other) in self.seeds.iter().enumerate() {
+                        assert_eq!(seed == other, points[i] == points[j]);
+                    }
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_load_random_point_with_rng() {
+    let circuit = SeededPointCircuit::<Fr> { seeds: vec![1, 2, 1], _marker: PhantomData };
+    let prover = MockProver::run(12, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[derive(Default)]
+struct EcdsaChecksCircuit<F> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for EcdsaChecksCircuit<F> {
+    type Config = FpConfig<F, SecpFp>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            12,
+            88,
+            3,
+            modulus::<SecpFp>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let scalar_chip = FpOverflowChip::<F, SecpFq>::construct(
+            &config.range,
+            config.limb_bits,
+            config.num_limbs,
+            modulus::<SecpFq>(),
+        );
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "ecdsa checks",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let n = BigInt::from(modulus::<SecpFq>());
+                let p = BigInt::from(modulus::<SecpFp>());
+                let assert_flag = |flag: &AssignedValue<F>, expected: bool| {
+                    flag.value().map(|v| assert_eq!(*v, F::from(expected)));
+                };
+
+                for (a, expected) in [
+                    (BigInt::from(0), false),
+                    (BigInt::from(1), true),
+                    (&n - 1, true),
+                    (n.clone(), false),
+                ] {
+                    let a = scalar_chip.load_private(ctx, Value::known(a))?;
+                    let flag = ecdsa_is_in_range::<F, SecpFq>(&config.range, ctx, &a)?;
+                    assert_flag(&flag, expected);
+                }
+
+                for (s, expected) in
+                    [(BigInt::from(1), true), (&n / 2, true), (&n / 2 + 1, false), (&n - 1, false)]
+                {
+                    let s = scalar_chip.load_private(ctx, Value::known(s))?;
+                    let flag = ecdsa_is_low_s::<F, SecpFq>(&config.range, ctx, &s)?;
+                    assert_flag(&flag, expected);
+                }
+
+                for (r, x, expected) in [
+                    (BigInt::from(5), BigInt::from(5), true),
+                    // the r + n edge case, where x is in [n, p)
+                    (BigInt::from(5), &n + 5, true),
+                    (&n + 5, &n + 5, false),
+                    (BigInt::from(6), &n + 5, false),
+                    // x is not a reduced coordinate
+                    (&p + 5 - &n, &p + 5, false),
+                ] {
+                    let r = scalar_chip.load_private(ctx, Value::known(r))?;
+                    let x = config.load_private(ctx, Value::known(x))?;
+                    let flag = ecdsa_r_matches_x::<F, SecpFp, SecpFq>(&config, ctx, &r, &x)?;
+                    assert_flag(&flag, expected);
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_ecdsa_checks() {
+    let circuit = EcdsaChecksCircuit::<Fr>::default();
+    let prover = MockProver::run(13, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[derive(Default)]
+struct FixedBaseTableCircuit<F> {
+    scalars: Vec<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for FixedBaseTableCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            16,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+        // the table of the generator is built once and shared by every multiplication
+        let table = FixedBaseTable::<F, G1Affine>::new(&G1Affine::generator(), 3, 88, 64, 4);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "fixed base table",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                for scalar in self.scalars.iter() {
+                    let assigned = config.range.gate.assign_region_smart(
+                        ctx,
+                        vec![Witness(Value::known(F::from(*scalar)))],
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?;
+                    let out = chip.fixed_base_scalar_mult_with_table(ctx, &table, &assigned, 64)?;
+                    let expected = (G1Affine::generator() * Fr::from(*scalar)).to_affine();
+                    out.x.value.as_ref().map(|x| assert_eq!(bigint_to_fe::<Fq>(x), expected.x));
+                    out.y.value.as_ref().map(|y| assert_eq!(bigint_to_fe::<Fq>(y), expected.y));
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_fixed_base_scalar_mult_with_table() {
+    let mut rng = rand::thread_rng();
+    let scalars = vec![rng.next_u64(), rng.next_u64(), 1, u64::MAX];
+    let circuit = FixedBaseTableCircuit::<Fr> { scalars, _marker: PhantomData };
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[derive(Default)]
+struct MsmSmallCircuit<F> {
+    points: Vec<G1Affine>,
+    scalars: Vec<u64>,
+    scalar_bits: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MsmSmallCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            16,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "msm small",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut points = Vec::with_capacity(self.points.len());
+                for P in self.points.iter() {
+                    points.push(chip.load_private(ctx, (Value::known(P.x), Value::known(P.y)))?);
+                }
+                let scalars = config.range.gate.assign_region_smart(
+                    ctx,
+                    self.scalars.iter().map(|s| Witness(Value::known(F::from(*s)))).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let msm = chip.variable_base_msm_small::<G1Affine>(
+                    ctx,
+                    &points,
+                    &scalars,
+                    self.scalar_bits,
+                )?;
+                let expected = self
+                    .points
+                    .iter()
+                    .zip(self.scalars.iter())
+                    .fold(G1::identity(), |acc, (P, s)| acc + *P * Fr::from(*s))
+                    .to_affine();
+                msm.x.value.as_ref().map(|x| assert_eq!(bigint_to_fe::<Fq>(x), expected.x));
+                msm.y.value.as_ref().map(|y| assert_eq!(bigint_to_fe::<Fq>(y), expected.y));
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_variable_base_msm_small() {
+    let mut rng = rand::thread_rng();
+    for scalar_bits in [16, 64] {
+        let mask = u64::MAX >> (64 - scalar_bits);
+        let circuit = MsmSmallCircuit::<Fr> {
+            points: (0..3).map(|_| G1Affine::random(&mut rng)).collect(),
+            scalars: vec![rng.next_u64() & mask, mask, 0],
+            scalar_bits,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[derive(Default)]
+struct MsmVarBitsCircuit<F> {
+    points: Vec<G1Affine>,
+    // the limbs of each scalar, of `max_bits[i]` bits each
+    scalars: Vec<Vec<u64>>,
+    max_bits: Vec<usize>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MsmVarBitsCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "msm var bits",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut points = Vec::with_capacity(self.points.len());
+                for P in self.points.iter() {
+                    points.push(chip.load_private(ctx, (Value::known(P.x), Value::known(P.y)))?);
+                }
+                let mut scalars = Vec::with_capacity(self.scalars.len());
+                for limbs in self.scalars.iter() {
+                    scalars.push(config.range.gate.assign_region_smart(
+                        ctx,
+                        limbs.iter().map(|s| Witness(Value::known(F::from(*s)))).collect(),
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?);
+                }
+                let msm = chip.multi_scalar_mult_var_bits::<G1Affine>(
+                    ctx,
+                    &points,
+                    &scalars,
+                    &self.max_bits,
+                    4,
+                )?;
+
+                let mut expected = G1::identity();
+                for ((P, limbs), bits) in
+                    self.points.iter().zip(self.scalars.iter()).zip(self.max_bits.iter())
+                {
+                    let scalar = limbs.iter().rev().fold(Fr::from(0), |acc, limb| {
+                        acc * Fr::from(1u64 << bits) + Fr::from(*limb)
+                    });
+                    expected = expected + *P * scalar;
+                }
+                let expected = expected.to_affine();
+                msm.x.value.as_ref().map(|x| assert_eq!(bigint_to_fe::<Fq>(x), expected.x));
+                msm.y.value.as_ref().map(|y| assert_eq!(bigint_to_fe::<Fq>(y), expected.y));
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_multi_scalar_mult_var_bits() {
+    let mut rng = rand::thread_rng();
+    let mut limb = |bits: usize| rng.next_u64() >> (64 - bits);
+    // a long scalar of four 63-bit limbs, two 32-bit scalars and a 9-bit one, given in shuffled order
+    let scalars =
+        vec![vec![limb(32)], (0..4).map(|_| limb(63)).collect(), vec![limb(9)], vec![limb(32)]];
+    let circuit = MsmVarBitsCircuit::<Fr> {
+        points: (0..4).map(|_| G1Affine::random(&mut rng)).collect(),
+        scalars,
+        max_bits: vec![32, 63, 9, 32],
+        _marker: PhantomData,
+    };
+    let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[derive(Default)]
+struct MsmIdentityCircuit<F> {
+    points: Vec<G1Affine>,
+    scalars: Vec<u64>,
+    // whether the points are fixed, using `fixed_base_msm`
+    fixed: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for MsmIdentityCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { points: self.points.clone(), fixed: self.fixed, ..Default::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "msm with identity",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut scalars = Vec::with_capacity(self.scalars.len());
+                for s in self.scalars.iter() {
+                    scalars.push(config.range.gate.assign_region_smart(
+                        ctx,
+                        vec![Witness(Value::known(F::from(*s)))],
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?);
+                }
+                let (msm, is_identity) = if self.fixed {
+                    chip.fixed_base_msm(ctx, &self.points, &scalars, 64, 4)?
+                } else {
+                    let mut points = Vec::with_capacity(self.points.len());
+                    for P in self.points.iter() {
+                        // the point at infinity is (0, 0) in affine coordinates
+                        points
+                            .push(chip.load_private(ctx, (Value::known(P.x), Value::known(P.y)))?);
+                    }
+                    chip.multi_scalar_mult_with_identity::<G1Affine>(ctx, &points, &scalars, 64, 4)?
+                };
+
+                let mut expected = G1::identity();
+                for (P, s) in self.points.iter().zip(self.scalars.iter()) {
+                    expected = expected + *P * Fr::from(*s);
+                }
+                let expected = expected.to_affine();
+                let expected_is_identity = bool::from(expected.is_identity());
+                is_identity.value().map(|v| assert_eq!(*v, F::from(expected_is_identity as u64)));
+                msm.x.value.as_ref().map(|x| assert_eq!(bigint_to_fe::<Fq>(x), expected.x));
+                msm.y.value.as_ref().map(|y| assert_eq!(bigint_to_fe::<Fq>(y), expected.y));
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_msm_with_identity() {
+    let mut rng = rand::thread_rng();
+    let P = G1Affine::random(&mut rng);
+    let Q = G1Affine::random(&mut rng);
+    let s = rng.next_u64();
+    let cases = vec![
+        // a base at infinity among others
+        (vec![P, G1Affine::identity(), Q], vec![s, rng.next_u64(), rng.next_u64()]),
+        // terms that cancel out, so the result is the point at infinity
+        (vec![P, G1Affine::identity(), -P], vec![s, rng.next_u64(), s]),
+        // zero scalars only
+        (vec![P, Q], vec![0, 0]),
+    ];
+    for fixed in [false, true] {
+        for (points, scalars) in cases.iter() {
+            let circuit = MsmIdentityCircuit::<Fr> {
+                points: points.clone(),
+                scalars: scalars.clone(),
+                fixed,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+}
+
+#[derive(Default)]
+struct CompressedCircuit<F> {
+    P: Option<G1Affine>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for CompressedCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "compressed",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let P =
+                    chip.assign_point(ctx, Value::known(self.P.unwrap_or(G1Affine::generator())))?;
+                let bytes = chip.to_compressed_bytes(ctx, &P)?;
+
+                if let Some(P) = self.P {
+                    let mut expected = vec![2 + (P.y.to_repr()[0] & 1)];
+                    expected.extend(P.x.to_repr().iter().rev());
+                    assert_eq!(bytes.len(), expected.len());
+                    for (byte, expected) in bytes.iter().zip(expected.iter()) {
+                        byte.value().map(|v| assert_eq!(*v, F::from(*expected as u64)));
+                    }
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_to_compressed_bytes() {
+    let P = G1Affine::random(rand::thread_rng());
+    // P and -P have y of opposite parity
+    for P in [P, -P, G1Affine::generator()] {
+        let circuit = CompressedCircuit::<Fr> { P: Some(P), _marker: PhantomData };
+        let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[derive(Default)]
+struct CheckedPointCircuit<F> {
+    P: Option<G1Affine>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for CheckedPointCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "checked point",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let P = self.P.map(Value::known).unwrap_or(Value::unknown());
+                chip.assign_point_checked::<G1Affine>(ctx, P)?;
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_assign_point_checked() {
+    let P = G1Affine::random(rand::thread_rng());
+    let circuit = CheckedPointCircuit::<Fr> { P: Some(P), _marker: PhantomData };
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let off_curve = G1Affine { x: P.x, y: P.y + Fq::from(1) };
+    let circuit = CheckedPointCircuit::<Fr> { P: Some(off_curve), _marker: PhantomData };
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[derive(Default)]
+struct FixedPlusVariableCircuit<F> {
+    G: G1Affine,
+    P: Option<G1Affine>,
+    // the limbs of `a` and `b`, of 64 bits each
+    a: Vec<u64>,
+    b: Vec<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for FixedPlusVariableCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { G: self.G, ..Default::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "fixed plus variable",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let P =
+                    chip.assign_point(ctx, self.P.map(Value::known).unwrap_or(Value::unknown()))?;
+                let mut scalars = Vec::with_capacity(2);
+                for limbs in [&self.a, &self.b] {
+                    scalars.push(config.range.gate.assign_region_smart(
+                        ctx,
+                        limbs.iter().map(|s| Witness(Value::known(F::from(*s)))).collect(),
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?);
+                }
+                let out = chip.fixed_plus_variable_scalar_mult(
+                    ctx,
+                    &self.G,
+                    &scalars[0],
+                    &P,
+                    &scalars[1],
+                    64,
+                    4,
+                )?;
+
+                if let Some(P) = self.P {
+                    let to_scalar = |limbs: &Vec<u64>| {
+                        limbs.iter().rev().fold(Fr::from(0), |acc, limb| {
+                            acc * Fr::from_u128(1u128 << 64) + Fr::from(*limb)
+                        })
+                    };
+                    let expected =
+                        (self.G * to_scalar(&self.a) + P * to_scalar(&self.b)).to_affine();
+                    out.x.value.as_ref().map(|x| assert_eq!(bigint_to_fe::<Fq>(x), expected.x));
+                    out.y.value.as_ref().map(|y| assert_eq!(bigint_to_fe::<Fq>(y), expected.y));
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_fixed_plus_variable_scalar_mult() {
+    let mut rng = rand::thread_rng();
+    let cases = vec![
+        // full width scalars, as for ECDSA
+        ((0..4).map(|_| rng.next_u64()).collect(), (0..4).map(|_| rng.next_u64()).collect()),
+        // scalars of different lengths, with zero windows in `a`
+        (vec![0, rng.next_u64() >> 40], vec![rng.next_u64()]),
+    ];
+    for (a, b) in cases {
+        let circuit = FixedPlusVariableCircuit::<Fr> {
+            G: G1Affine::generator(),
+            P: Some(G1Affine::random(&mut rng)),
+            a,
+            b,
+            _marker: PhantomData,
+        };
+        let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[derive(Default)]
+struct SignedDigitsCircuit<F> {
+    // the limbs of the scalar, of 64 bits each
+    scalar: Vec<u64>,
+    window_bits: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SignedDigitsCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { window_bits: self.window_bits, ..Default::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            88,
+            3,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "signed digits",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let scalar = config.range.gate.assign_region_smart(
+                    ctx,
+                    self.scalar.iter().map(|s| Witness(Value::known(F::from(*s)))).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let digits =
+                    signed_window_digits(&config.range, ctx, &scalar, 64, self.window_bits)?;
+
+                let half = BigInt::from(1u64 << (self.window_bits - 1));
+                let mut recomposed = Value::known(BigInt::from(0));
+                for (is_negative, magnitude) in digits.iter().rev() {
+                    recomposed = recomposed.zip(is_negative.value()).zip(magnitude.value()).map(
+                        |((acc, is_negative), magnitude)| {
+                            let magnitude = BigInt::from(fe_to_biguint(magnitude));
+                            assert!(magnitude <= half);
+                            let digit =
+                                if *is_negative == F::one() { -magnitude } else { magnitude };
+                            (acc << self.window_bits) + digit
+                        },
+                    );
+                }
+                let expected = self
+                    .scalar
+                    .iter()
+                    .rev()
+                    .fold(BigInt::from(0), |acc, limb| (acc << 64) + BigInt::from(*limb));
+                recomposed.map(|recomposed| assert_eq!(recomposed, expected));
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_signed_window_digits() {
+    let mut rng = rand::thread_rng();
+    // all ones, so every window carries, and a random scalar
+    for scalar in [vec![u64::MAX; 4], (0..4).map(|_| rng.next_u64()).collect()] {
+        for window_bits in [1, 4, 5] {
+            let circuit = SignedDigitsCircuit::<Fr> {
+                scalar: scalar.clone(),
+                window_bits,
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+}
+
+// compares `double_n` with chained `double`s for 64 bit limbs, which leave enough overflow budget to defer the
+// `carry_mod` of `y` at every step
+#[derive(Default)]
+struct DoubleNCircuit<F> {
+    P: Option<G1Affine>,
+    n: usize,
+    // compare `double_n` with `P` instead, which must fail
+    wrong: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for DoubleNCircuit<F> {
+    type Config = FpConfig<F, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { P: None, n: self.n, wrong: self.wrong, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            16,
+            64,
+            4,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = EccChip::construct(&config);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "double_n",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+                let cells = |ctx: &Context<'_, F>| {
+                    ctx.advice_rows[&ContextId::new("default")].iter().sum::<usize>()
+                };
+
+                let P = chip.load_private(
+                    ctx,
+                    match self.P {
+                        Some(P) => (Value::known(P.x), Value::known(P.y)),
+                        None => (Value::unknown(), Value::unknown()),
+                    },
+                )?;
+
+                let start = cells(ctx);
+                let chained = (0..self.n).try_fold(P.clone(), |Q, _| chip.double(ctx, &Q))?;
+                let chained_cells = cells(ctx) - start;
+                let start = cells(ctx);
+                let doub = chip.double_n(ctx, &P, self.n)?;
+                let double_n_cells = cells(ctx) - start;
+                // each deferred `carry_mod` saves more than the `mul_u64_no_carry`s it costs
+                assert!(double_n_cells < chained_cells);
+                chip.assert_equal(ctx, &doub, if self.wrong { &P } else { &chained })?;
+
+                if let Some(P) = self.P {
+                    let expected = G1Affine::from(P * Fr::from(1u64 << self.n));
+                    doub.x.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), expected.x));
+                    doub.y.value.map(|v| assert_eq!(bigint_to_fe::<Fq>(&v), expected.y));
+                }
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_double_n_deferred() {
+    let P = Some(G1Affine::random(rand::thread_rng()));
+    let circuit = DoubleNCircuit::<Fr> { P, n: 8, wrong: false, _marker: PhantomData };
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let circuit = DoubleNCircuit::<Fr> { P, n: 8, wrong: true, _marker: PhantomData };
+    let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -1,26 +1,33 @@
-use super::{FieldChip, PrimeFieldChip, Selectable};
+use super::{
+    fp_lazy::FpLazyAccumulator, FieldChip, MontgomeryFieldChip, PrimeFieldChip, Selectable,
+};
 use crate::bigint::{
-    add_no_carry, big_is_equal, big_is_zero, big_less_than, carry_mod, check_carry_mod_to_zero,
-    inner_product, mul_no_carry, scalar_mul_and_add_no_carry, scalar_mul_no_carry, select, sub,
-    sub_no_carry, BigIntConfig, BigIntStrategy, CRTInteger, OverflowInteger,
+    add_no_carry, barrett_mod, big_is_equal, big_is_zero, big_less_than, carry_mod,
+    check_carry_mod_to_zero, inner_product, mont_reduce, mul_no_carry, scalar_mul_and_add_no_carry,
+    scalar_mul_no_carry, select, sub, sub_no_carry, BigIntConfig, BigIntStrategy, CRTInteger,
+    OverflowInteger,
 };
 use ff::PrimeField;
 use halo2_base::{
     gates::{
         range::{RangeConfig, RangeStrategy},
+        stats::CircuitStats,
         GateInstructions, RangeInstructions,
     },
-    utils::{bigint_to_fe, decompose_bigint, decompose_bigint_option, fe_to_bigint, fe_to_biguint},
-    AssignedValue, Context,
+    utils::{
+        bigint_to_fe, decompose_bigint, decompose_bigint_option, fe_to_bigint, fe_to_biguint,
+        modulus as native_modulus,
+    },
+    AssignedValue, Context, ContextId,
     QuantumCell::{self, Constant, Existing, Witness},
 };
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Layouter, Value},
+    circuit::{Cell, Layouter, Value},
     plonk::{ConstraintSystem, Error},
 };
 use num_bigint::{BigInt, BigUint};
-use num_traits::Signed;
+use num_traits::{One, Signed};
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
@@ -28,6 +35,12 @@ use std::marker::PhantomData;
 pub enum FpStrategy {
     Simple,
     SimplePlus,
+    // same gates as `Simple`, but `mul` reduces products by Barrett reduction instead of the CRT method
+    Barrett,
+    // same as `Simple`, but with a dedicated custom gate for the inner products in `carry_mod` and `check_carry_mod_to_zero`
+    SimpleDot,
+    // same as `Simple`, but inner products use a wide gate over all advice columns, see `GateStrategy::Horizontal`
+    SimpleHorizontal,
 }
 
 #[derive(Clone, Debug)]
@@ -37,6 +50,10 @@ pub struct FpConfig<F: FieldExt, Fp: PrimeField> {
     pub limb_bits: usize,
     pub num_limbs: usize,
     pub p: BigUint,
+    pub strategy: FpStrategy,
+    // if true, `no_carry` operations carry their inputs first whenever the output would exceed the overflow budget
+    // with the "display" feature, each inserted `carry_mod` is counted in `ctx.op_count`, see `auto_carry_report`
+    pub auto_carry: bool,
     _marker: PhantomData<Fp>,
 }
 
@@ -51,19 +68,22 @@ impl<F: FieldExt, Fp: PrimeField> FpConfig<F, Fp> {
         limb_bits: usize,
         num_limbs: usize,
         p: BigUint,
-        gate_context_id: String,
+        gate_context_id: ContextId,
     ) -> Self {
         let range = RangeConfig::<F>::configure(
             meta,
             match strategy {
                 FpStrategy::Simple => RangeStrategy::Vertical,
                 FpStrategy::SimplePlus => RangeStrategy::PlonkPlus,
+                FpStrategy::Barrett => RangeStrategy::Vertical,
+                FpStrategy::SimpleDot => RangeStrategy::VerticalDot,
+                FpStrategy::SimpleHorizontal => RangeStrategy::Horizontal,
             },
             num_advice,
             num_lookup_advice,
             num_fixed,
             lookup_bits,
-            gate_context_id.clone(),
+            gate_context_id,
         );
 
         let bigint_chip = BigIntConfig::<F>::configure(
@@ -71,19 +91,72 @@ impl<F: FieldExt, Fp: PrimeField> FpConfig<F, Fp> {
             match strategy {
                 FpStrategy::Simple => BigIntStrategy::Simple,
                 FpStrategy::SimplePlus => BigIntStrategy::Simple,
+                FpStrategy::Barrett => BigIntStrategy::Simple,
+                FpStrategy::SimpleDot => BigIntStrategy::Simple,
+                FpStrategy::SimpleHorizontal => BigIntStrategy::Simple,
             },
             limb_bits,
             num_limbs,
             &range.gate,
-            "unused".to_string(),
+            ContextId::new("unused"),
         );
-        FpConfig { range, bigint_chip, limb_bits, num_limbs, p, _marker: PhantomData }
+        FpConfig {
+            range,
+            bigint_chip,
+            limb_bits,
+            num_limbs,
+            p,
+            strategy,
+            auto_carry: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// An `FpConfig` on an already configured `range`, e.g. the config of a `RangeCircuitBuilder`, with the strategy
+    /// of its gate. On the vertical gate, `strategy` may then be set to `FpStrategy::Barrett`.
+    pub fn construct(
+        range: RangeConfig<F>,
+        limb_bits: usize,
+        num_limbs: usize,
+        p: BigUint,
+    ) -> Self {
+        let strategy = match range.strategy() {
+            RangeStrategy::Vertical => FpStrategy::Simple,
+            RangeStrategy::PlonkPlus => FpStrategy::SimplePlus,
+            RangeStrategy::VerticalDot => FpStrategy::SimpleDot,
+            RangeStrategy::Horizontal => FpStrategy::SimpleHorizontal,
+        };
+        FpConfig {
+            range,
+            bigint_chip: BigIntConfig::default(),
+            limb_bits,
+            num_limbs,
+            p,
+            strategy,
+            auto_carry: false,
+            _marker: PhantomData,
+        }
     }
 
     pub fn load_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         self.range.load_lookup_table(layouter)
     }
 
+    /// Adds an instance column for the public inputs of the circuit, see `FlexGateConfig::enable_instance`
+    pub fn enable_instance(&mut self, meta: &mut ConstraintSystem<F>) {
+        self.range.gate.enable_instance(meta);
+    }
+
+    /// Constrains the cells exposed with `Context::expose_public`, e.g. by `CRTInteger::expose_public`, to equal the
+    /// instance column, see `FlexGateConfig::expose_public_cells`
+    pub fn expose_public_cells(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        cells: &[(Cell, usize)],
+    ) -> Result<(), Error> {
+        self.range.gate.expose_public_cells(layouter, cells)
+    }
+
     pub fn load_constant_overflow(
         &self,
         ctx: &mut Context<'_, F>,
@@ -111,19 +184,198 @@ impl<F: FieldExt, Fp: PrimeField> FpConfig<F, Fp> {
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
     ) -> Result<(), Error> {
-        let p_assigned = self.load_constant_overflow(ctx, BigInt::from(self.p.clone()))?;
-        let is_lt_p = big_less_than::assign(self.range(), ctx, &a.truncation, &p_assigned)?;
-        ctx.constants_to_assign.push((F::from(1), Some(is_lt_p.cell())));
+        let is_lt_p = big_less_than::assign_constant(self.range(), ctx, &a.truncation, &self.p)?;
+        ctx.assign_constant(F::from(1), Some(is_lt_p.cell()));
         Ok(())
     }
 
+    /// Returns a `CRTInteger` equal to `a (mod p)` whose `truncation` represents its value exactly:
+    /// if `a.truncation` has `num_limbs` limbs and `a.max_size < 2^{limb_bits * num_limbs}`, we constrain
+    /// `a.native` to equal the evaluation of `a.truncation`, otherwise we `carry_mod` first
+    pub fn exact_truncation(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        if a.truncation.limbs.len() == self.num_limbs
+            && a.truncation.max_size.bits() as usize <= self.limb_bits * self.num_limbs
+        {
+            let native = OverflowInteger::evaluate(
+                self.range.gate(),
+                &self.bigint_chip,
+                ctx,
+                &a.truncation.limbs,
+                self.limb_bits,
+            )?;
+            self.range.gate().assert_equal(ctx, &Existing(&native), &Existing(&a.native))?;
+            Ok(a.clone())
+        } else {
+            self.carry_mod(ctx, a)
+        }
+    }
+
+    /// Returns whether `carry_mod` can still be applied to `a`, i.e., whether `a` is within the overflow budget
+    pub fn can_carry(&self, a: &CRTInteger<F>) -> bool {
+        let n = self.limb_bits;
+        let k = self.num_limbs;
+        let native_bits = native_modulus::<F>().bits() as usize;
+        // in `carry_mod::crt` the limbs of `out + modulus * quotient - a` are bounded by
+        // `2^n + k * 2^{2n} + a.max_limb_size`, and `check_carry_to_zero` needs to range check
+        // carries of this size with windows of at least one limb
+        let check_max_limb =
+            (BigUint::one() << n) + (BigUint::from(k) << (2 * n)) + &a.truncation.max_limb_size;
+        let check_bits = check_max_limb.bits() as usize;
+        if check_bits + self.range.lookup_bits() + 2 > native_bits {
+            return false;
+        }
+        // `carry_mod::crt` needs `|a| < 2^{n * k - 1} * native_modulus` with room for `modulus * quotient`
+        let bound = (BigUint::one() << (n * k - 2)) * native_modulus::<F>();
+        a.truncation.max_size < bound
+    }
+
+    // the bounds of `a * b` from `mul_no_carry`, or `None` if its limbs may exceed half the native modulus
+    pub(crate) fn mul_bound(&self, a: &CRTInteger<F>, b: &CRTInteger<F>) -> Option<CRTInteger<F>> {
+        let max_limb = BigUint::from(self.num_limbs)
+            * &a.truncation.max_limb_size
+            * &b.truncation.max_limb_size;
+        if max_limb > native_modulus::<F>() / 2u32 {
+            return None;
+        }
+        let mut out = a.clone();
+        out.truncation.max_limb_size = max_limb;
+        out.truncation.max_size = &a.truncation.max_size * &b.truncation.max_size;
+        Some(out)
+    }
+
+    /// Returns the call sites where `auto_carry` inserted a `carry_mod` in the synthesis of `ctx`, with the number of
+    /// carries at each
+    #[cfg(feature = "display")]
+    pub fn auto_carry_report(ctx: &Context<'_, F>) -> Vec<(String, usize)> {
+        let mut report: Vec<_> = ctx
+            .op_count
+            .iter()
+            .filter(|(op, _)| op.starts_with(AUTO_CARRY_PREFIX))
+            .map(|(op, count)| (op[AUTO_CARRY_PREFIX.len()..].to_string(), *count))
+            .collect();
+        report.sort();
+        report
+    }
+
+    // with `auto_carry` enabled, carries `a` and/or `b` (larger first) until `fits(a, b)` holds
+    // panics if the operation does not fit even after carrying both inputs
+    #[track_caller]
+    fn auto_carry_inputs(
+        &self,
+        ctx: &mut Context<'_, F>,
+        op: &str,
+        a: &CRTInteger<F>,
+        b: &CRTInteger<F>,
+        fits: impl Fn(&CRTInteger<F>, &CRTInteger<F>) -> bool,
+    ) -> Result<(CRTInteger<F>, CRTInteger<F>), Error> {
+        let (mut a, mut b) = (a.clone(), b.clone());
+        if !self.auto_carry || fits(&a, &b) {
+            return Ok((a, b));
+        }
+        let location = std::panic::Location::caller();
+        for a_first in [a.truncation.max_size >= b.truncation.max_size, false, true] {
+            if fits(&a, &b) {
+                break;
+            }
+            let x = if a_first { &mut a } else { &mut b };
+            // an input that was already carried has no overflow left to remove
+            if x.truncation.max_size < self.p {
+                continue;
+            }
+            *x = self.carry_mod(ctx, x)?;
+            #[cfg(feature = "display")]
+            {
+                let key = format!("{}carry_mod before {} at {}", AUTO_CARRY_PREFIX, op, location);
+                *ctx.op_count.entry(key).or_insert(0) += 1;
+            }
+        }
+        assert!(
+            fits(&a, &b),
+            "{} at {} exceeds the overflow budget even after carrying",
+            op,
+            location
+        );
+        Ok((a, b))
+    }
+
+    // `auto_carry_inputs` for multiplication of `a` by a constant of absolute value `c_abs`
+    #[track_caller]
+    fn auto_carry_small(
+        &self,
+        ctx: &mut Context<'_, F>,
+        op: &str,
+        a: &CRTInteger<F>,
+        c_abs: &BigUint,
+    ) -> Result<CRTInteger<F>, Error> {
+        let (a, _) =
+            self.auto_carry_inputs(ctx, op, a, a, |a, _| self.can_carry(&scalar_bound(a, c_abs)))?;
+        Ok(a)
+    }
+
+    /// Starts a chain of lazily reduced operations with accumulator `a`
+    pub fn lazy(&self, a: &CRTInteger<F>) -> FpLazyAccumulator<'_, F, Fp> {
+        FpLazyAccumulator::new(self, a)
+    }
+
     pub fn finalize(&self, ctx: &mut Context<'_, F>) -> Result<(usize, usize, Vec<usize>), Error> {
         self.range.finalize(ctx)
     }
+
+    pub fn finalize_with_stats(&self, ctx: &mut Context<'_, F>) -> Result<CircuitStats, Error> {
+        self.range.finalize_with_stats(ctx)
+    }
 }
 
 impl<F: FieldExt, Fp: PrimeField> PrimeFieldChip<F> for FpConfig<F, Fp> {}
 
+impl<F: FieldExt, Fp: PrimeField> MontgomeryFieldChip<F> for FpConfig<F, Fp> {
+    fn to_montgomery(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        // a * R = REDC(a * R^2)
+        let r = BigUint::one() << (self.limb_bits * self.num_limbs);
+        let r_sq = self.load_constant(ctx, BigInt::from(&r * &r % &self.p))?;
+        self.mont_mul(ctx, a, &r_sq)
+    }
+
+    fn from_montgomery(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        let a = self.exact_truncation(ctx, a)?;
+        self.mont_reduce(ctx, &a)
+    }
+
+    fn mont_mul_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+        b: &CRTInteger<F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        let a = self.exact_truncation(ctx, a)?;
+        let b = self.exact_truncation(ctx, b)?;
+        let out_trunc = mul_no_carry::assign(self.range.gate(), ctx, &a.truncation, &b.truncation)?;
+        let out_native = self.range.gate().mul(ctx, &Existing(&a.native), &Existing(&b.native))?;
+        let out_val = a.value.as_ref().zip(b.value.as_ref()).map(|(a, b)| a * b);
+        Ok(CRTInteger::construct(out_trunc, out_native, out_val))
+    }
+
+    fn mont_reduce(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        mont_reduce::crt(self.range(), &self.bigint_chip, ctx, a, &self.p, self.num_limbs)
+    }
+}
+
 impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
     type ConstantType = BigInt;
     type WitnessType = Value<BigInt>;
@@ -202,13 +454,17 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
     }
 
     // signed overflow BigInt functions
+    #[track_caller]
     fn add_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
         b: &CRTInteger<F>,
     ) -> Result<CRTInteger<F>, Error> {
-        add_no_carry::crt(self.range.gate(), ctx, a, b)
+        let (a, b) = self.auto_carry_inputs(ctx, "add_no_carry", a, b, |a, b| {
+            self.can_carry(&sum_bound(a, b, &BigUint::one()))
+        })?;
+        add_no_carry::crt(self.range.gate(), ctx, &a, &b)
     }
 
     fn add_native_constant_no_carry(
@@ -235,13 +491,17 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
         ))
     }
 
+    #[track_caller]
     fn sub_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
         b: &CRTInteger<F>,
     ) -> Result<CRTInteger<F>, Error> {
-        sub_no_carry::crt(self.range.gate(), ctx, a, b)
+        let (a, b) = self.auto_carry_inputs(ctx, "sub_no_carry", a, b, |a, b| {
+            self.can_carry(&sum_bound(a, b, &BigUint::one()))
+        })?;
+        sub_no_carry::crt(self.range.gate(), ctx, &a, &b)
     }
 
     // Input: a
@@ -253,21 +513,48 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
         let p = self.load_constant(ctx, BigInt::from(self.p.clone()))?;
         let (out_or_p, underflow) = sub::crt(self.range(), ctx, &p, &a)?;
         // constrain underflow to equal 0
-        ctx.constants_to_assign.push((F::from(0), Some(underflow.cell())));
+        ctx.assign_constant(F::from(0), Some(underflow.cell()));
 
         let a_is_zero = big_is_zero::assign(self.range(), ctx, &a.truncation)?;
         select::crt(self.range.gate(), ctx, a, &out_or_p, &a_is_zero)
     }
 
+    #[track_caller]
     fn scalar_mul_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
         b: F,
     ) -> Result<CRTInteger<F>, Error> {
-        scalar_mul_no_carry::crt(self.range.gate(), ctx, a, b)
+        let b_abs = fe_to_bigint(&b).abs().to_biguint().unwrap();
+        let a = self.auto_carry_small(ctx, "scalar_mul_no_carry", a, &b_abs)?;
+        scalar_mul_no_carry::crt(self.range.gate(), ctx, &a, b)
+    }
+
+    #[track_caller]
+    fn mul_u64_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+        c: u64,
+    ) -> Result<CRTInteger<F>, Error> {
+        let a = self.auto_carry_small(ctx, "mul_u64_no_carry", a, &BigUint::from(c))?;
+        scalar_mul_no_carry::crt_u64(self.range.gate(), ctx, &a, c)
     }
 
+    #[track_caller]
+    fn mul_small_signed_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+        c: i64,
+    ) -> Result<CRTInteger<F>, Error> {
+        let c_abs = BigUint::from(c.unsigned_abs());
+        let a = self.auto_carry_small(ctx, "mul_small_signed_no_carry", a, &c_abs)?;
+        scalar_mul_no_carry::crt_small_signed(self.range.gate(), ctx, &a, c)
+    }
+
+    #[track_caller]
     fn scalar_mul_and_add_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
@@ -275,16 +562,52 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
         b: &CRTInteger<F>,
         c: F,
     ) -> Result<CRTInteger<F>, Error> {
-        scalar_mul_and_add_no_carry::crt(self.range.gate(), ctx, a, b, c)
+        let c_abs = fe_to_bigint(&c).abs().to_biguint().unwrap();
+        let (a, b) = self.auto_carry_inputs(ctx, "scalar_mul_and_add_no_carry", a, b, |a, b| {
+            self.can_carry(&sum_bound(b, a, &c_abs))
+        })?;
+        scalar_mul_and_add_no_carry::crt(self.range.gate(), ctx, &a, &b, c)
     }
 
+    #[track_caller]
     fn mul_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
         b: &CRTInteger<F>,
     ) -> Result<CRTInteger<F>, Error> {
-        mul_no_carry::crt(self.range.gate(), &self.bigint_chip, ctx, a, b)
+        let (a, b) = self.auto_carry_inputs(ctx, "mul_no_carry", a, b, |a, b| {
+            self.mul_bound(a, b).map_or(false, |out| self.can_carry(&out))
+        })?;
+        mul_no_carry::crt(self.range.gate(), &self.bigint_chip, ctx, &a, &b)
+    }
+
+    // With `FpStrategy::Barrett`, products of integers whose `truncation` is exact are reduced with Barrett reduction.
+    // Otherwise (e.g., the inputs are themselves outputs of `mul_no_carry`) we fall back to the CRT method
+    fn mul(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &CRTInteger<F>,
+        b: &CRTInteger<F>,
+    ) -> Result<CRTInteger<F>, Error> {
+        let trunc_bits = (self.limb_bits * self.num_limbs) as u64;
+        if self.strategy == FpStrategy::Barrett
+            && a.truncation.max_size.bits() <= trunc_bits
+            && b.truncation.max_size.bits() <= trunc_bits
+            && a.truncation.limbs.len() == self.num_limbs
+            && b.truncation.limbs.len() == self.num_limbs
+            && barrett_mod::is_supported(
+                &(&a.truncation.max_size * &b.truncation.max_size),
+                &self.p,
+                self.num_limbs,
+                self.limb_bits,
+            )
+        {
+            barrett_mod::mul_crt(self.range(), &self.bigint_chip, ctx, a, b, &self.p)
+        } else {
+            let no_carry = self.mul_no_carry(ctx, a, b)?;
+            self.carry_mod(ctx, &no_carry)
+        }
     }
 
     fn check_carry_mod_to_zero(
@@ -292,7 +615,10 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
     ) -> Result<(), Error> {
-        check_carry_mod_to_zero::crt(self.range(), &self.bigint_chip, ctx, a, &self.p)
+        ctx.push_tag("fp check_carry_mod_to_zero");
+        let res = check_carry_mod_to_zero::crt(self.range(), &self.bigint_chip, ctx, a, &self.p);
+        ctx.pop_tag();
+        res
     }
 
     fn carry_mod(
@@ -300,7 +626,16 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
         ctx: &mut Context<'_, F>,
         a: &CRTInteger<F>,
     ) -> Result<CRTInteger<F>, Error> {
-        carry_mod::crt(self.range(), &self.bigint_chip, ctx, a, &self.p)
+        ctx.push_tag("fp carry_mod");
+        let res = carry_mod::crt(self.range(), &self.bigint_chip, ctx, a, &self.p);
+        ctx.pop_tag();
+        res
+    }
+
+    fn can_mul_no_carry(&self, a: &CRTInteger<F>, b: &CRTInteger<F>) -> bool {
+        // the subtracted product of proper values is at most as large as `a * b`
+        self.mul_bound(a, b)
+            .map_or(false, |out| self.can_carry(&sum_bound(&out, &out, &BigUint::one())))
     }
 
     fn range_check(&self, ctx: &mut Context<'_, F>, a: &CRTInteger<F>) -> Result<(), Error> {
@@ -403,7 +738,7 @@ impl<F: FieldExt, Fp: PrimeField> FieldChip<F> for FpConfig<F, Fp> {
         self.enforce_less_than_p(ctx, a)?;
         self.enforce_less_than_p(ctx, b)?;
         // a.native and b.native are derived from `a.truncation, b.truncation`, so no need to check if they're equal
-        for (limb_a, limb_b) in a.truncation.limbs.iter().zip(a.truncation.limbs.iter()) {
+        for (limb_a, limb_b) in a.truncation.limbs.iter().zip(b.truncation.limbs.iter()) {
             self.range.gate.assert_equal(ctx, &Existing(limb_a), &Existing(limb_b))?;
         }
         Ok(())
@@ -433,6 +768,30 @@ impl<F: FieldExt, Fp: PrimeField> Selectable<F> for FpConfig<F, Fp> {
     }
 }
 
+// returns `a + c * b` with only the overflow bounds updated, to test against the overflow budget
+pub(crate) fn sum_bound<F: FieldExt>(
+    a: &CRTInteger<F>,
+    b: &CRTInteger<F>,
+    c: &BigUint,
+) -> CRTInteger<F> {
+    let mut out = a.clone();
+    out.truncation.max_limb_size += &b.truncation.max_limb_size * c;
+    out.truncation.max_size += &b.truncation.max_size * c;
+    out
+}
+
+// returns `c * a` with only the overflow bounds updated, to test against the overflow budget
+pub(crate) fn scalar_bound<F: FieldExt>(a: &CRTInteger<F>, c: &BigUint) -> CRTInteger<F> {
+    let mut out = a.clone();
+    out.truncation.max_limb_size *= c;
+    out.truncation.max_size *= c;
+    out
+}
+
+// prefix of the `op_count` entries of the carries inserted by `auto_carry`
+#[cfg(feature = "display")]
+const AUTO_CARRY_PREFIX: &str = "auto_carry: ";
+
 #[cfg(test)]
 pub(crate) mod tests {
     use std::marker::PhantomData;
@@ -441,16 +800,27 @@ pub(crate) mod tests {
     use halo2_proofs::{
         arithmetic::FieldExt,
         circuit::*,
-        dev::MockProver,
+        dev::{MockProver, VerifyFailure},
         halo2curves::bn256::{Fq, Fr},
         plonk::*,
     };
     use rand::rngs::OsRng;
 
+    use crate::bigint::{
+        add_no_carry, big_less_than, mul_no_carry, resize, select, sub_no_carry, CRTInteger,
+        FixedCRTInteger,
+    };
     use crate::fields::fp::FpConfig;
-    use crate::fields::FieldChip;
-    use halo2_base::utils::{fe_to_bigint, modulus};
-    use halo2_base::{Context, ContextParams};
+    use crate::fields::{FieldChip, MontgomeryFieldChip};
+    use halo2_base::gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+        GateInstructions, RangeInstructions,
+    };
+    use halo2_base::utils::{biguint_to_fe, fe_to_bigint, fe_to_biguint, modulus, value_to_option};
+    use halo2_base::{Context, ContextId, ContextParams, QuantumCell::Witness};
+    use num_traits::Signed;
 
     use super::FpStrategy;
 
@@ -483,7 +853,7 @@ pub(crate) mod tests {
                 88,
                 3,
                 modulus::<Fq>(),
-                "default".to_string(),
+                ContextId::new("default"),
             )
         }
 
@@ -507,7 +877,7 @@ pub(crate) mod tests {
 
                     let mut aux = Context::new(
                         region,
-                        ContextParams { num_advice: vec![("default".to_string(), NUM_ADVICE)] },
+                        ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
                     );
                     let ctx = &mut aux;
 
@@ -539,10 +909,14 @@ pub(crate) mod tests {
                     */
 
                     println!("Using {} advice columns and {} fixed columns", NUM_ADVICE, NUM_FIXED);
-                    println!("total cells: {}", ctx.advice_rows["default"].iter().sum::<usize>());
+                    println!(
+                        "total cells: {}",
+                        ctx.advice_rows[&ContextId::new("default")].iter().sum::<usize>()
+                    );
+                    println!("cells used in special lookup columns: {}", ctx.cells_to_lookup.len());
                     println!(
                         "maximum rows used by an advice column: {}",
-                        ctx.advice_rows["default"].iter().max().unwrap()
+                        ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
                     );
                     // IMPORTANT: this assigns all constants to the fixed columns
                     // This is not optional.
@@ -568,6 +942,310 @@ pub(crate) mod tests {
         assert_eq!(prover.verify(), Ok(()));
     }
 
+    // runs `synthesize` on an `FpConfig` for `Fq` with 3 limbs of 88 bits and `strategy`, in a `RangeCircuitBuilder`
+    // of degree `k` with `num_advice` advice columns
+    fn verify<Fun>(
+        strategy: FpStrategy,
+        k: u32,
+        num_advice: usize,
+        synthesize: Fun,
+    ) -> Result<(), Vec<VerifyFailure>>
+    where
+        Fun: Fn(&FpConfig<Fr, Fq>, &mut Context<'_, Fr>) -> Result<(), Error> + Clone,
+    {
+        let range_strategy = match strategy {
+            FpStrategy::Simple | FpStrategy::Barrett => RangeStrategy::Vertical,
+            FpStrategy::SimplePlus => RangeStrategy::PlonkPlus,
+            FpStrategy::SimpleDot => RangeStrategy::VerticalDot,
+            FpStrategy::SimpleHorizontal => RangeStrategy::Horizontal,
+        };
+        let params = CircuitParams {
+            degree: k,
+            num_advice,
+            num_lookup_advice: 1,
+            num_fixed: NUM_FIXED,
+            lookup_bits: 11,
+        };
+        let circuit = RangeCircuitBuilder::new(
+            range_strategy,
+            params,
+            move |range: &RangeConfig<Fr>, ctx: &mut Context<'_, Fr>| {
+                let mut chip = FpConfig::construct(range.clone(), 88, 3, modulus::<Fq>());
+                // `Barrett` shares its range strategy with `Simple`
+                chip.strategy = strategy.clone();
+                synthesize(&chip, ctx)
+            },
+        );
+        MockProver::run(k, &circuit, vec![]).unwrap().verify()
+    }
+
+    fn load(
+        chip: &FpConfig<Fr, Fq>,
+        ctx: &mut Context<'_, Fr>,
+        a: Fq,
+    ) -> Result<CRTInteger<Fr>, Error> {
+        chip.load_private(ctx, Value::known(fe_to_bigint(&a)))
+    }
+
+    fn value(a: &CRTInteger<Fr>) -> Option<Fq> {
+        value_to_option(FpConfig::<Fr, Fq>::get_assigned_value(a))
+    }
+
+    #[test]
+    fn test_fp_barrett() {
+        let (a, b) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::Barrett, 13, NUM_ADVICE, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let prod = chip.mul(ctx, &a_assigned, &b_assigned)?;
+            assert_eq!(value(&prod), Some(a * b));
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_fp_simple_dot() {
+        let (a, b) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimpleDot, 13, NUM_ADVICE, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let prod = chip.mul(ctx, &a_assigned, &b_assigned)?;
+            // `divide` constrains the quotient with `check_carry_mod_to_zero`
+            let quot = chip.divide(ctx, &a_assigned, &b_assigned)?;
+            assert_eq!(value(&prod), Some(a * b));
+            assert_eq!(value(&quot), Some(a * b.invert().unwrap()));
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_fp_simple_horizontal() {
+        // the horizontal gate computes 2 products per row
+        let num_advice = 5;
+        let (a, b) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimpleHorizontal, 12, num_advice, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let prod = chip.mul(ctx, &a_assigned, &b_assigned)?;
+            let quot = chip.divide(ctx, &a_assigned, &b_assigned)?;
+            assert_eq!(value(&prod), Some(a * b));
+            assert_eq!(value(&quot), Some(a * b.invert().unwrap()));
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_fp_montgomery() {
+        // multiplies `a, b` by converting to and from Montgomery form
+        let (a, b) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimplePlus, 14, NUM_ADVICE, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let a_mont = chip.to_montgomery(ctx, &a_assigned)?;
+            let b_mont = chip.to_montgomery(ctx, &b_assigned)?;
+            let ab_mont = chip.mont_mul(ctx, &a_mont, &b_mont)?;
+            let ab = chip.from_montgomery(ctx, &ab_mont)?;
+            assert_eq!(value(&ab), Some(a * b));
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_fp_auto_carry() {
+        // computes `a * b^4` with `mul_no_carry` only, relying on `auto_carry` to insert the carries
+        let (a, b) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimplePlus, 13, NUM_ADVICE, move |chip, ctx| {
+            let mut chip = chip.clone();
+            chip.auto_carry = true;
+            let b_assigned = load(&chip, ctx, b)?;
+            let mut out = load(&chip, ctx, a)?;
+            for _ in 0..4 {
+                out = chip.mul_no_carry(ctx, &out, &b_assigned)?;
+            }
+            let out = chip.carry_mod(ctx, &out)?;
+            assert_eq!(value(&out), Some(a * b.square().square()));
+
+            #[cfg(feature = "display")]
+            {
+                let report = FpConfig::<Fr, Fq>::auto_carry_report(ctx);
+                for (site, count) in report.iter() {
+                    println!("{} ({}x)", site, count);
+                }
+                assert!(!report.is_empty());
+            }
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    // computes `(a * b^4 * c + a - b) * b` with `FpLazyAccumulator`, where `c` is a native constant
+    fn verify_lazy(a: Fq, b: Fq, c: Fr) -> Result<(), Vec<VerifyFailure>> {
+        verify(FpStrategy::SimplePlus, 13, NUM_ADVICE, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let mut lazy = chip.lazy(&a_assigned);
+            for _ in 0..4 {
+                lazy.mul(ctx, &b_assigned)?;
+            }
+            lazy.scalar_mul(ctx, c)?;
+            lazy.add(ctx, &a_assigned)?;
+            lazy.sub(ctx, &b_assigned)?;
+            lazy.mul(ctx, &b_assigned)?;
+            // a product of three integers exceeds the overflow budget, so the accumulator was carried
+            assert!(lazy.num_carries > 0);
+            let num_carries = lazy.num_carries;
+            let out = lazy.finalize(ctx)?;
+
+            // `c` as an element of `Fq`, by its signed representative in the native field
+            let c = fe_to_bigint(&c);
+            let c_abs: Fq = biguint_to_fe(c.magnitude());
+            let c = if c.is_negative() { -c_abs } else { c_abs };
+            assert_eq!(value(&out), Some((a * b.square().square() * c + a - b) * b));
+            println!("carries inserted by the lazy accumulator: {}", num_carries + 1);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_fp_lazy() {
+        for c in [Fr::from(3), -Fr::from(5), Fr::from(1u64 << 40)] {
+            assert_eq!(verify_lazy(Fq::random(OsRng), Fq::random(OsRng), c), Ok(()));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fp_lazy_scalar_too_large() {
+        // a carried accumulator times `2^200` exceeds the overflow budget, so `scalar_mul` must panic
+        verify_lazy(Fq::random(OsRng), Fq::random(OsRng), Fr::from(2).pow_vartime([200])).ok();
+    }
+
+    #[test]
+    fn test_fp_small_scalar() {
+        // checks `mul_u64_no_carry, mul_small_signed_no_carry` against native multiplication
+        let a = Fq::random(OsRng);
+        let result = verify(FpStrategy::SimplePlus, 13, NUM_ADVICE, move |chip, ctx| {
+            let a_assigned = load(chip, ctx, a)?;
+            for c in [0u64, 1, 2, 3, 8] {
+                let out = chip.mul_u64_no_carry(ctx, &a_assigned, c)?;
+                let out = chip.carry_mod(ctx, &out)?;
+                assert_eq!(value(&out), Some(a * Fq::from(c)));
+            }
+            for c in [-1i64, -2, -3, 5] {
+                let out = chip.mul_small_signed_no_carry(ctx, &a_assigned, c)?;
+                let out = chip.carry_mod(ctx, &out)?;
+                let c_fe = Fq::from(c.unsigned_abs());
+                let c_fe = if c < 0 { -c_fe } else { c_fe };
+                assert_eq!(value(&out), Some(a * c_fe));
+            }
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_big_less_than() {
+        // compares integers with different limb counts and against constants, including the circuit constant `c`
+        let (a, b, c) = (Fq::random(OsRng), Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimplePlus, 13, NUM_ADVICE, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let (a_big, b_big) = (fe_to_biguint(&a), fe_to_biguint(&b));
+            let bit = |x: bool| Some(Fr::from(x));
+
+            let b_long =
+                resize::assign(chip.range.gate(), ctx, &b_assigned.truncation, chip.num_limbs + 1)?;
+            for (x, y, expected) in [
+                (&a_assigned.truncation, &b_long, a_big < b_big),
+                (&b_long, &a_assigned.truncation, b_big < a_big),
+            ] {
+                let lt = big_less_than::assign(chip.range(), ctx, x, y)?;
+                assert_eq!(value_to_option(lt.value().copied()), bit(expected));
+            }
+
+            let (lt, eq) = big_less_than::compare(
+                chip.range(),
+                ctx,
+                &a_assigned.truncation,
+                &b_assigned.truncation,
+            )?;
+            assert_eq!(value_to_option(lt.value().copied()), bit(a_big < b_big));
+            assert_eq!(value_to_option(eq.value().copied()), bit(a == b));
+            let (lt, eq) = big_less_than::compare(
+                chip.range(),
+                ctx,
+                &a_assigned.truncation,
+                &a_assigned.truncation,
+            )?;
+            assert_eq!(value_to_option(lt.value().copied()), bit(false));
+            assert_eq!(value_to_option(eq.value().copied()), bit(true));
+
+            let lt_p = big_less_than::assign_constant(
+                chip.range(),
+                ctx,
+                &a_assigned.truncation,
+                &modulus::<Fq>(),
+            )?;
+            assert_eq!(value_to_option(lt_p.value().copied()), bit(true));
+            let lt_c = big_less_than::assign_constant(
+                chip.range(),
+                ctx,
+                &a_assigned.truncation,
+                &fe_to_biguint(&c),
+            )?;
+            assert_eq!(value_to_option(lt_c.value().copied()), bit(a_big < fe_to_biguint(&c)));
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_bigint_select_from_idx() {
+        // selects from a vector of `CRTInteger`s by a witness index
+        let (a, b) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimplePlus, 13, NUM_ADVICE, move |chip, ctx| {
+            let (a_assigned, b_assigned) = (load(chip, ctx, a)?, load(chip, ctx, b)?);
+            let table = vec![a_assigned.clone(), b_assigned, a_assigned];
+            for (i, expected) in [a, b, a].into_iter().enumerate() {
+                let idx = chip
+                    .range
+                    .gate()
+                    .assign_region_smart(
+                        ctx,
+                        vec![Witness(Value::known(Fr::from(i as u64)))],
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?
+                    .pop()
+                    .unwrap();
+                let out = select::crt_select_from_idx(&chip.range, ctx, &table, &idx)?;
+                assert_eq!(value(&out), Some(expected));
+            }
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_fixed_crt_ops() {
+        // adds, subtracts and multiplies by a `FixedCRTInteger` constant `c`, which is part of the circuit
+        let (a, c) = (Fq::random(OsRng), Fq::random(OsRng));
+        let result = verify(FpStrategy::SimplePlus, 13, NUM_ADVICE, move |chip, ctx| {
+            let a_assigned = load(chip, ctx, a)?;
+            let c_fixed =
+                FixedCRTInteger::from_native(fe_to_bigint(&c), chip.num_limbs, chip.limb_bits);
+
+            let sum = add_no_carry::crt_fixed(chip.range.gate(), ctx, &a_assigned, &c_fixed)?;
+            let diff = sub_no_carry::crt_fixed(chip.range.gate(), ctx, &a_assigned, &c_fixed)?;
+            let prod = mul_no_carry::crt_fixed(chip.range.gate(), ctx, &a_assigned, &c_fixed)?;
+            for (out, expected) in [(sum, a + c), (diff, a - c), (prod, a * c)] {
+                let out = chip.carry_mod(ctx, &out)?;
+                assert_eq!(value(&out), Some(expected));
+            }
+            Ok(())
+        });
+        assert_eq!(result, Ok(()));
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fp() {
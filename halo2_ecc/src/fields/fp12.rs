@@ -223,6 +223,34 @@ where
         Ok(Self::FieldPoint::construct(out_coeffs))
     }
 
+    fn mul_u64_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        c: u64,
+    ) -> Result<Self::FieldPoint, Error> {
+        let mut out_coeffs = Vec::with_capacity(a.coeffs.len());
+        for i in 0..a.coeffs.len() {
+            let coeff = self.fp_chip.mul_u64_no_carry(ctx, &a.coeffs[i], c)?;
+            out_coeffs.push(coeff);
+        }
+        Ok(Self::FieldPoint::construct(out_coeffs))
+    }
+
+    fn mul_small_signed_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        c: i64,
+    ) -> Result<Self::FieldPoint, Error> {
+        let mut out_coeffs = Vec::with_capacity(a.coeffs.len());
+        for i in 0..a.coeffs.len() {
+            let coeff = self.fp_chip.mul_small_signed_no_carry(ctx, &a.coeffs[i], c)?;
+            out_coeffs.push(coeff);
+        }
+        Ok(Self::FieldPoint::construct(out_coeffs))
+    }
+
     fn scalar_mul_and_add_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
@@ -446,7 +474,7 @@ pub(crate) mod tests {
     use crate::fields::fp::{FpConfig, FpStrategy};
     use crate::fields::FieldChip;
     use halo2_base::utils::modulus;
-    use halo2_base::ContextParams;
+    use halo2_base::{ContextId, ContextParams};
 
     #[derive(Default)]
     struct MyCircuit<F> {
@@ -478,7 +506,7 @@ pub(crate) mod tests {
                 88,
                 3,
                 modulus::<Fq>(),
-                "default".to_string(),
+                ContextId::new("default"),
             )
         }
 
@@ -503,7 +531,7 @@ pub(crate) mod tests {
 
                     let mut aux = Context::new(
                         region,
-                        ContextParams { num_advice: vec![("default".to_string(), NUM_ADVICE)] },
+                        ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
                     );
                     let ctx = &mut aux;
 
@@ -524,7 +552,7 @@ pub(crate) mod tests {
                     println!("Using {} advice columns and {} fixed columns", NUM_ADVICE, NUM_FIXED);
                     println!(
                         "maximum rows used by an advice column: {}",
-                        ctx.advice_rows["default"].iter().max().unwrap()
+                        ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
                     );
                     // IMPORTANT: this assigns all constants to the fixed columns
                     // This is not optional.
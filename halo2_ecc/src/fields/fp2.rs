@@ -1,4 +1,6 @@
-use super::{FieldChip, FieldExtConstructor, FieldExtPoint, PrimeFieldChip, Selectable};
+use super::{
+    FieldChip, FieldExtConstructor, FieldExtPoint, MontgomeryFieldChip, PrimeFieldChip, Selectable,
+};
 use ff::PrimeField;
 use halo2_base::{
     gates::{GateInstructions, RangeInstructions},
@@ -57,6 +59,34 @@ where
         Ok(FieldExtPoint::construct(out_coeffs))
     }
 
+    /// Montgomery multiplication: if the coefficients of `a, b` are in Montgomery form with respect to `fp_chip`, so are the output coefficients
+    /// We only do one Montgomery reduction per output coefficient
+    pub fn mont_mul(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &FieldExtPoint<FpChip::FieldPoint>,
+        b: &FieldExtPoint<FpChip::FieldPoint>,
+    ) -> Result<FieldExtPoint<FpChip::FieldPoint>, Error>
+    where
+        FpChip: MontgomeryFieldChip<F>,
+    {
+        assert_eq!(a.coeffs.len(), 2);
+        assert_eq!(b.coeffs.len(), 2);
+        // (a_0 + a_1 * u) * (b_0 + b_1 * u) = (a_0 b_0 - a_1 b_1) + (a_0 b_1 + a_1 b_0) * u
+        let a0b0 = self.fp_chip.mont_mul_no_carry(ctx, &a.coeffs[0], &b.coeffs[0])?;
+        let a1b1 = self.fp_chip.mont_mul_no_carry(ctx, &a.coeffs[1], &b.coeffs[1])?;
+        let a0b1 = self.fp_chip.mont_mul_no_carry(ctx, &a.coeffs[0], &b.coeffs[1])?;
+        let a1b0 = self.fp_chip.mont_mul_no_carry(ctx, &a.coeffs[1], &b.coeffs[0])?;
+        let a0b0_minus_a1b1 = self.fp_chip.sub_no_carry(ctx, &a0b0, &a1b1)?;
+        let a0b1_plus_a1b0 = self.fp_chip.add_no_carry(ctx, &a0b1, &a1b0)?;
+
+        let out_coeffs = vec![
+            self.fp_chip.mont_reduce(ctx, &a0b0_minus_a1b1)?,
+            self.fp_chip.mont_reduce(ctx, &a0b1_plus_a1b0)?,
+        ];
+        Ok(FieldExtPoint::construct(out_coeffs))
+    }
+
     pub fn conjugate(
         &self,
         ctx: &mut Context<'_, F>,
@@ -99,6 +129,40 @@ where
     }
 }
 
+impl<'a, F, FpChip, Fp2> Selectable<F> for Fp2Chip<'a, F, FpChip, Fp2>
+where
+    F: FieldExt,
+    FpChip: PrimeFieldChip<F> + Selectable<F, Point = FpChip::FieldPoint>,
+    FpChip::FieldType: PrimeField,
+    Fp2: Field + FieldExtConstructor<FpChip::FieldType, 2>,
+{
+    type Point = FieldExtPoint<FpChip::FieldPoint>;
+
+    fn select(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &FieldExtPoint<FpChip::FieldPoint>,
+        b: &FieldExtPoint<FpChip::FieldPoint>,
+        sel: &AssignedValue<F>,
+    ) -> Result<FieldExtPoint<FpChip::FieldPoint>, Error> {
+        Fp2Chip::select(self, ctx, a, b, sel)
+    }
+
+    fn inner_product(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Vec<FieldExtPoint<FpChip::FieldPoint>>,
+        coeffs: &Vec<AssignedValue<F>>,
+    ) -> Result<FieldExtPoint<FpChip::FieldPoint>, Error> {
+        let mut out_coeffs = Vec::with_capacity(2);
+        for i in 0..2 {
+            let a_i = a.iter().map(|a| a.coeffs[i].clone()).collect();
+            out_coeffs.push(self.fp_chip.inner_product(ctx, &a_i, coeffs)?);
+        }
+        Ok(FieldExtPoint::construct(out_coeffs))
+    }
+}
+
 impl<'a, F, FpChip, Fp2> FieldChip<F> for Fp2Chip<'a, F, FpChip, Fp2>
 where
     F: FieldExt,
@@ -215,6 +279,34 @@ where
         Ok(Self::FieldPoint::construct(out_coeffs))
     }
 
+    fn mul_u64_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        c: u64,
+    ) -> Result<Self::FieldPoint, Error> {
+        let mut out_coeffs = Vec::with_capacity(a.coeffs.len());
+        for i in 0..a.coeffs.len() {
+            let coeff = self.fp_chip.mul_u64_no_carry(ctx, &a.coeffs[i], c)?;
+            out_coeffs.push(coeff);
+        }
+        Ok(Self::FieldPoint::construct(out_coeffs))
+    }
+
+    fn mul_small_signed_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        c: i64,
+    ) -> Result<Self::FieldPoint, Error> {
+        let mut out_coeffs = Vec::with_capacity(a.coeffs.len());
+        for i in 0..a.coeffs.len() {
+            let coeff = self.fp_chip.mul_small_signed_no_carry(ctx, &a.coeffs[i], c)?;
+            out_coeffs.push(coeff);
+        }
+        Ok(Self::FieldPoint::construct(out_coeffs))
+    }
+
     fn scalar_mul_and_add_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
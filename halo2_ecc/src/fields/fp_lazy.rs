@@ -0,0 +1,99 @@
+use super::{
+    fp::{scalar_bound, sum_bound, FpConfig},
+    FieldChip,
+};
+use crate::bigint::CRTInteger;
+use ff::PrimeField;
+use halo2_base::{utils::fe_to_bigint, Context};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use num_traits::{One, Signed};
+
+/// Accumulates a chain of `add`, `sub`, `scalar_mul`, `mul` operations on `CRTInteger`s without carrying,
+/// and only inserts a `carry_mod` when the next operation would exceed the overflow budget, or on `finalize`.
+///
+/// The overflow budget is determined by the assertions in `mul_no_carry::truncate` and `carry_mod::crt`:
+/// * `k * a.max_limb_size * b.max_limb_size <= native_modulus / 2` for a multiplication
+/// * the limbs of `a` and `a.max_size` must be small enough that `carry_mod` can still reduce `a`
+pub struct FpLazyAccumulator<'a, F: FieldExt, Fp: PrimeField> {
+    chip: &'a FpConfig<F, Fp>,
+    acc: CRTInteger<F>,
+    // number of `carry_mod`s inserted by the accumulator, including the one in `finalize`
+    pub num_carries: usize,
+}
+
+impl<'a, F: FieldExt, Fp: PrimeField> FpLazyAccumulator<'a, F, Fp> {
+    pub fn new(chip: &'a FpConfig<F, Fp>, a: &CRTInteger<F>) -> Self {
+        Self { chip, acc: a.clone(), num_carries: 0 }
+    }
+
+    /// Returns the current un-reduced accumulator
+    pub fn value(&self) -> &CRTInteger<F> {
+        &self.acc
+    }
+
+    // carries the accumulator if `pred` says the next operation would exceed the overflow budget
+    fn carry_if(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        pred: impl Fn(&Self) -> bool,
+    ) -> Result<(), Error> {
+        if pred(self) {
+            self.acc = self.chip.carry_mod(ctx, &self.acc)?;
+            self.num_carries += 1;
+        }
+        Ok(())
+    }
+
+    // the overflow budget is `FpConfig::can_carry`, with the same bounds as the `auto_carry` checks of the chip
+    fn add_fits(&self, b: &CRTInteger<F>) -> bool {
+        self.chip.can_carry(&sum_bound(&self.acc, b, &BigUint::one()))
+    }
+
+    fn mul_fits(&self, b: &CRTInteger<F>) -> bool {
+        self.chip.mul_bound(&self.acc, b).map_or(false, |out| self.chip.can_carry(&out))
+    }
+
+    pub fn add(&mut self, ctx: &mut Context<'_, F>, b: &CRTInteger<F>) -> Result<(), Error> {
+        self.carry_if(ctx, |s| !s.add_fits(b))?;
+        assert!(self.add_fits(b), "`b` must be carried before it can be added");
+        self.acc = self.chip.add_no_carry(ctx, &self.acc, b)?;
+        Ok(())
+    }
+
+    pub fn sub(&mut self, ctx: &mut Context<'_, F>, b: &CRTInteger<F>) -> Result<(), Error> {
+        self.carry_if(ctx, |s| !s.add_fits(b))?;
+        assert!(self.add_fits(b), "`b` must be carried before it can be subtracted");
+        self.acc = self.chip.sub_no_carry(ctx, &self.acc, b)?;
+        Ok(())
+    }
+
+    pub fn scalar_mul(&mut self, ctx: &mut Context<'_, F>, c: F) -> Result<(), Error> {
+        let c_abs = fe_to_bigint(&c).abs().to_biguint().unwrap();
+        let fits = |s: &Self| s.chip.can_carry(&scalar_bound(&s.acc, &c_abs));
+        self.carry_if(ctx, |s| !fits(s))?;
+        assert!(fits(self), "`c` is too large to multiply even a carried accumulator");
+        self.acc = self.chip.scalar_mul_no_carry(ctx, &self.acc, c)?;
+        Ok(())
+    }
+
+    /// Multiplies the accumulator by `b`. If `b` itself has too much overflow, it is carried first.
+    pub fn mul(&mut self, ctx: &mut Context<'_, F>, b: &CRTInteger<F>) -> Result<(), Error> {
+        self.carry_if(ctx, |s| !s.mul_fits(b))?;
+        if self.mul_fits(b) {
+            self.acc = self.chip.mul_no_carry(ctx, &self.acc, b)?;
+        } else {
+            let b = self.chip.carry_mod(ctx, b)?;
+            self.num_carries += 1;
+            assert!(self.mul_fits(&b));
+            self.acc = self.chip.mul_no_carry(ctx, &self.acc, &b)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the accumulator reduced by a single `carry_mod`
+    pub fn finalize(mut self, ctx: &mut Context<'_, F>) -> Result<CRTInteger<F>, Error> {
+        self.carry_if(ctx, |_| true)?;
+        Ok(self.acc)
+    }
+}
@@ -157,7 +157,7 @@ impl<'a, F: FieldExt, Fp: PrimeField> FieldChip<F> for FpOverflowChip<'a, F, Fp>
         // Compute p - a.truncation using carries
         let p = self.load_constant(ctx, BigInt::from(self.p.clone()))?;
         let (out_or_p, underflow) = sub::assign(self.range(), ctx, &p, &a)?;
-        ctx.constants_to_assign.push((F::from(0), Some(underflow.cell())));
+        ctx.assign_constant(F::from(0), Some(underflow.cell()));
 
         let a_is_zero = big_is_zero::assign(self.range(), ctx, a)?;
         select::assign(self.range.gate(), ctx, a, &out_or_p, &a_is_zero)
@@ -172,6 +172,24 @@ impl<'a, F: FieldExt, Fp: PrimeField> FieldChip<F> for FpOverflowChip<'a, F, Fp>
         scalar_mul_no_carry::assign(self.range.gate(), ctx, a, b)
     }
 
+    fn mul_u64_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        c: u64,
+    ) -> Result<OverflowInteger<F>, Error> {
+        scalar_mul_no_carry::assign_u64(self.range.gate(), ctx, a, c)
+    }
+
+    fn mul_small_signed_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        c: i64,
+    ) -> Result<OverflowInteger<F>, Error> {
+        scalar_mul_no_carry::assign_small_signed(self.range.gate(), ctx, a, c)
+    }
+
     fn scalar_mul_and_add_no_carry(
         &self,
         ctx: &mut Context<'_, F>,
@@ -10,7 +10,9 @@ use std::fmt::Debug;
 pub mod fp;
 pub mod fp12;
 pub mod fp2;
+pub mod fp_lazy;
 pub mod fp_overflow;
+pub mod native;
 
 #[derive(Clone, Debug)]
 pub struct FieldExtPoint<FieldPoint: Clone + Debug> {
@@ -94,6 +96,27 @@ pub trait FieldChip<F: FieldExt> {
         b: F,
     ) -> Result<Self::FieldPoint, Error>;
 
+    /// a * c for a small constant `c`, e.g. `2y, 3x, 8x` in curve formulas
+    fn mul_u64_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        c: u64,
+    ) -> Result<Self::FieldPoint, Error> {
+        self.scalar_mul_no_carry(ctx, a, F::from(c))
+    }
+
+    /// a * c for a small signed constant `c`
+    fn mul_small_signed_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        c: i64,
+    ) -> Result<Self::FieldPoint, Error> {
+        let c_abs = F::from(c.unsigned_abs());
+        self.scalar_mul_no_carry(ctx, a, if c < 0 { -c_abs } else { c_abs })
+    }
+
     /// a * c + b
     fn scalar_mul_and_add_no_carry(
         &self,
@@ -124,6 +147,13 @@ pub trait FieldChip<F: FieldExt> {
 
     fn range_check(&self, ctx: &mut Context<'_, F>, a: &Self::FieldPoint) -> Result<(), Error>;
 
+    /// Whether `mul_no_carry(a, b)`, followed by subtracting a product of proper values and
+    /// `check_carry_mod_to_zero`, stays within the overflow budget without carrying `a` or `b` first.
+    /// Chips that do not track the overflow of their points always return false
+    fn can_mul_no_carry(&self, _a: &Self::FieldPoint, _b: &Self::FieldPoint) -> bool {
+        false
+    }
+
     // Assumes the witness for a is 0
     // Constrains that the underlying big integer is 0 and < p.
     // For field extensions, checks coordinate-wise.
@@ -251,6 +281,58 @@ where
     // for now there is nothing here
 }
 
+// Prime field chips that support Montgomery multiplication
+pub trait MontgomeryFieldChip<F: FieldExt>: PrimeFieldChip<F>
+where
+    Self::FieldType: PrimeField,
+{
+    // Montgomery form: `a` is represented by `a * R (mod p)` where `R = 2^{limb_bits * num_limbs}`
+    // `add_no_carry, sub_no_carry, scalar_mul_no_carry, carry_mod, is_zero, is_equal` are linear or injective, so they can be used on
+    // Montgomery form points unchanged. Multiplication must go through `mont_mul_no_carry` and `mont_reduce`.
+    // The `value` of a Montgomery form point is `a * R (mod p)`, so conversions should only happen at load/expose boundaries.
+
+    /// Converts `a` to Montgomery form
+    fn to_montgomery(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+    ) -> Result<Self::FieldPoint, Error>;
+
+    /// Converts `a` from Montgomery form
+    fn from_montgomery(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+    ) -> Result<Self::FieldPoint, Error>;
+
+    /// Returns the exact product `a * b` without reduction; the output must be reduced with `mont_reduce`
+    fn mont_mul_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        b: &Self::FieldPoint,
+    ) -> Result<Self::FieldPoint, Error>;
+
+    /// Input: `a` is a linear combination of outputs of `mont_mul_no_carry`
+    /// Output: `a * R^{-1} (mod p)`
+    fn mont_reduce(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+    ) -> Result<Self::FieldPoint, Error>;
+
+    /// Montgomery multiplication: if `a, b` are in Montgomery form, so is the output
+    fn mont_mul(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Self::FieldPoint,
+        b: &Self::FieldPoint,
+    ) -> Result<Self::FieldPoint, Error> {
+        let no_carry = self.mont_mul_no_carry(ctx, a, b)?;
+        self.mont_reduce(ctx, &no_carry)
+    }
+}
+
 // helper trait so we can actually construct and read the Fp2 struct
 // needs to be implemented for Fp2 struct for use cases below
 pub trait FieldExtConstructor<Fp: PrimeField, const DEGREE: usize> {
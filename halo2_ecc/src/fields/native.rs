@@ -0,0 +1,239 @@
+use super::{FieldChip, Selectable};
+use halo2_base::{
+    gates::{range::RangeConfig, GateInstructions, RangeInstructions},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::Error,
+};
+
+// `FieldChip` for the native field `F` of the circuit, e.g. the base field of Grumpkin when `F` is BN254 Fr
+// Each point is a single cell, so there are no limbs to carry: `carry_mod` and `range_check` are free, every
+// `*_no_carry` operation is exact, and `check_carry_mod_to_zero` is an equality with 0
+#[derive(Clone, Debug)]
+pub struct NativeFieldChip<'a, F: FieldExt> {
+    pub range: &'a RangeConfig<F>,
+}
+
+impl<'a, F: FieldExt> NativeFieldChip<'a, F> {
+    pub fn construct(range: &'a RangeConfig<F>) -> Self {
+        Self { range }
+    }
+
+    pub fn load_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.range.load_lookup_table(layouter)
+    }
+}
+
+impl<'a, F: FieldExt> FieldChip<F> for NativeFieldChip<'a, F> {
+    type ConstantType = F;
+    type WitnessType = Value<F>;
+    type FieldPoint = AssignedValue<F>;
+    type FieldType = F;
+    type RangeChip = RangeConfig<F>;
+
+    fn range(&self) -> &Self::RangeChip {
+        self.range
+    }
+
+    fn get_assigned_value(x: &AssignedValue<F>) -> Value<F> {
+        x.value().copied()
+    }
+
+    fn fe_to_witness(x: &Value<F>) -> Value<F> {
+        *x
+    }
+
+    fn load_private(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: Value<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let assigned =
+            self.range.gate().assign_region_smart(ctx, vec![Witness(a)], vec![], vec![], vec![])?;
+        Ok(assigned[0].clone())
+    }
+
+    fn load_constant(&self, ctx: &mut Context<'_, F>, a: F) -> Result<AssignedValue<F>, Error> {
+        let assigned = self.range.gate().assign_region_smart(
+            ctx,
+            vec![Constant(a)],
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(assigned[0].clone())
+    }
+
+    fn add_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().add(ctx, &Existing(a), &Existing(b))
+    }
+
+    fn add_native_constant_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        c: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().add(ctx, &Existing(a), &Constant(c))
+    }
+
+    fn sub_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().sub(ctx, &Existing(a), &Existing(b))
+    }
+
+    fn negate(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().neg(ctx, &Existing(a))
+    }
+
+    fn scalar_mul_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().mul(ctx, &Existing(a), &Constant(b))
+    }
+
+    fn scalar_mul_and_add_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        c: F,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().mul_add(ctx, &Existing(a), &Constant(c), &Existing(b))
+    }
+
+    fn mul_no_carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().mul(ctx, &Existing(a), &Existing(b))
+    }
+
+    fn check_carry_mod_to_zero(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        self.range.gate().assert_is_const(ctx, a, F::zero());
+        Ok(())
+    }
+
+    fn carry_mod(
+        &self,
+        _ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        Ok(a.clone())
+    }
+
+    fn range_check(&self, _ctx: &mut Context<'_, F>, _a: &AssignedValue<F>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // every cell is already reduced, so there is never any overflow to defer
+    fn can_mul_no_carry(&self, _a: &AssignedValue<F>, _b: &AssignedValue<F>) -> bool {
+        true
+    }
+
+    fn is_soft_zero(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.is_zero(ctx, a)
+    }
+
+    fn is_soft_nonzero(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let is_zero = self.range.is_zero(ctx, a)?;
+        self.range.gate().not(ctx, &Existing(&is_zero))
+    }
+
+    fn is_zero(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.is_zero(ctx, a)
+    }
+
+    fn is_equal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.is_equal(ctx, &Existing(a), &Existing(b))
+    }
+
+    fn assert_equal(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        self.range.gate().assert_equal(ctx, &Existing(a), &Existing(b))
+    }
+
+    // `div_unsafe` witnesses `c = a / b` and constrains `b * c = a`, which leaves `c` unconstrained when `a = b = 0`
+    fn divide(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().div_unsafe(ctx, &Existing(a), &Existing(b))
+    }
+}
+
+impl<'a, F: FieldExt> Selectable<F> for NativeFieldChip<'a, F> {
+    type Point = AssignedValue<F>;
+
+    fn select(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        sel: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().select(ctx, &Existing(a), &Existing(b), &Existing(sel))
+    }
+
+    fn inner_product(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &Vec<AssignedValue<F>>,
+        coeffs: &Vec<AssignedValue<F>>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.range.gate().inner_product(
+            ctx,
+            &a.iter().map(Existing).collect(),
+            &coeffs.iter().map(Existing).collect(),
+        )
+    }
+}
@@ -0,0 +1,48 @@
+#![allow(non_snake_case)]
+use crate::ecc::{is_on_curve, EccChip, EccPoint};
+use crate::fields::{native::NativeFieldChip, FieldChip};
+use halo2_base::{AssignedValue, Context};
+use halo2_proofs::{halo2curves::bn256::Fr, plonk::Error};
+
+#[cfg(test)]
+mod tests;
+
+// Grumpkin is the curve y^2 = x^3 - 17 over BN254 Fr, whose group order is the modulus of BN254 Fq, so BN254 and
+// Grumpkin form a 2-cycle. In a circuit over BN254 Fr its coordinates are native cells, and curve operations cost a
+// handful of gates instead of CRT arithmetic, e.g. for Pedersen commitments or accumulation schemes.
+// There is no `CurveAffine` type for Grumpkin, so the `EccChip` methods generic over a curve (random points, the MSMs
+// with a random offset, `assert_is_on_curve`) are not available; use the helpers below with `add_unequal, double,
+// scalar_mult, scalar_mult_constant` instead. Scalars are BN254 Fq elements, which can exceed Fr, so `scalar_mult`
+// needs them in limbs of at most `GRUMPKIN_SCALAR_LIMB_BITS` bits.
+pub type GrumpkinFieldChip<'a> = NativeFieldChip<'a, Fr>;
+pub type GrumpkinChip<'a> = EccChip<'a, Fr, GrumpkinFieldChip<'a>>;
+pub type GrumpkinPoint = EccPoint<Fr, AssignedValue<Fr>>;
+
+pub const GRUMPKIN_SCALAR_LIMB_BITS: usize = 128;
+
+pub fn grumpkin_b() -> Fr {
+    -Fr::from(17)
+}
+
+// (1, sqrt(-16)), the generator used by Aztec and Noir
+pub fn grumpkin_generator() -> (Fr, Fr) {
+    (Fr::from(1), Fr::from_raw([0x833fc48d823f272c, 0x2d270d45f1181294, 0xcf135e7506a45d63, 0x2]))
+}
+
+pub fn load_grumpkin_generator(
+    chip: &GrumpkinChip,
+    ctx: &mut Context<'_, Fr>,
+) -> Result<GrumpkinPoint, Error> {
+    let (x, y) = grumpkin_generator();
+    let x = chip.field_chip.load_constant(ctx, x)?;
+    let y = chip.field_chip.load_constant(ctx, y)?;
+    Ok(EccPoint::construct(x, y))
+}
+
+pub fn assert_is_on_grumpkin(
+    chip: &GrumpkinChip,
+    ctx: &mut Context<'_, Fr>,
+    P: &GrumpkinPoint,
+) -> Result<(), Error> {
+    is_on_curve(chip.field_chip, ctx, P, grumpkin_b())
+}
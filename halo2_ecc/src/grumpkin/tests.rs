@@ -0,0 +1,169 @@
+#![allow(unused_imports, unused_variables)]
+use super::*;
+use crate::fields::native::NativeFieldChip;
+use ff::Field;
+use halo2_base::{
+    gates::range::{RangeConfig, RangeStrategy},
+    utils::{biguint_to_fe, modulus},
+    ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    circuit::*,
+    dev::MockProver,
+    halo2curves::bn256::{Fq, Fr},
+    plonk::*,
+};
+use num_bigint::{BigUint, RandBigInt};
+
+const NUM_ADVICE: usize = 2;
+const NUM_FIXED: usize = 1;
+
+// native affine arithmetic on Grumpkin to check the circuit against, with `None` for the point at infinity
+fn native_add(P: Option<(Fr, Fr)>, Q: Option<(Fr, Fr)>) -> Option<(Fr, Fr)> {
+    let ((px, py), (qx, qy)) = match (P, Q) {
+        (None, _) => return Q,
+        (_, None) => return P,
+        (Some(P), Some(Q)) => (P, Q),
+    };
+    let lambda = if px == qx {
+        if py != qy || py == Fr::zero() {
+            return None;
+        }
+        px * px * Fr::from(3) * (py * Fr::from(2)).invert().unwrap()
+    } else {
+        (qy - py) * (qx - px).invert().unwrap()
+    };
+    let x = lambda * lambda - px - qx;
+    Some((x, lambda * (px - x) - py))
+}
+
+fn native_mul(P: (Fr, Fr), scalar: &BigUint) -> Option<(Fr, Fr)> {
+    let mut out = None;
+    for i in (0..scalar.bits()).rev() {
+        out = native_add(out, out);
+        if scalar.bit(i) {
+            out = native_add(out, Some(P));
+        }
+    }
+    out
+}
+
+#[derive(Default)]
+struct GrumpkinCircuit {
+    scalar: BigUint,
+}
+
+impl Circuit<Fr> for GrumpkinCircuit {
+    type Config = RangeConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            17,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let field_chip = NativeFieldChip::construct(&config);
+        field_chip.load_lookup_table(&mut layouter)?;
+        let chip = GrumpkinChip::construct(&field_chip);
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "grumpkin",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let G = grumpkin_generator();
+                let check = |point: &GrumpkinPoint, expected: Option<(Fr, Fr)>| {
+                    let (x, y) = expected.unwrap();
+                    point.x.value().map(|v| assert_eq!(*v, x));
+                    point.y.value().map(|v| assert_eq!(*v, y));
+                };
+
+                let G_const = load_grumpkin_generator(&chip, ctx)?;
+                let G_assigned = chip.load_private(ctx, (Value::known(G.0), Value::known(G.1)))?;
+                assert_is_on_grumpkin(&chip, ctx, &G_assigned)?;
+                chip.assert_equal(ctx, &G_assigned, &G_const)?;
+
+                let three_G = native_mul(G, &BigUint::from(3u64)).unwrap();
+                let Q =
+                    chip.load_private(ctx, (Value::known(three_G.0), Value::known(three_G.1)))?;
+                assert_is_on_grumpkin(&chip, ctx, &Q)?;
+
+                // test add_unequal
+                let sum = chip.add_unequal(ctx, &G_assigned, &Q, true)?;
+                check(&sum, native_mul(G, &BigUint::from(4u64)));
+
+                // test double
+                let doub = chip.double(ctx, &Q)?;
+                check(&doub, native_mul(G, &BigUint::from(6u64)));
+
+                // test scalar_mult_constant
+                let out = chip.scalar_mult_constant(ctx, &G_assigned, &BigUint::from(0xb7e1u64))?;
+                check(&out, native_mul(G, &BigUint::from(0xb7e1u64)));
+
+                // test scalar_mult with the scalar in limbs, as it may exceed the modulus of Fr
+                let mask = (BigUint::from(1u64) << GRUMPKIN_SCALAR_LIMB_BITS) - 1u64;
+                let limbs = config.gate.assign_region_smart(
+                    ctx,
+                    vec![
+                        Witness(Value::known(biguint_to_fe(&(&self.scalar & &mask)))),
+                        Witness(Value::known(biguint_to_fe(
+                            &(&self.scalar >> GRUMPKIN_SCALAR_LIMB_BITS),
+                        ))),
+                    ],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let out =
+                    chip.scalar_mult(ctx, &G_assigned, &limbs, GRUMPKIN_SCALAR_LIMB_BITS, 4)?;
+                assert_is_on_grumpkin(&chip, ctx, &out)?;
+                check(&out, native_mul(G, &self.scalar));
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_grumpkin() {
+    let mut rng = rand::thread_rng();
+    // the group order is the modulus of BN254 Fq, so `(q - 1) G = -G`
+    let q_minus_one = modulus::<Fq>() - 1u64;
+    assert_eq!(native_mul(grumpkin_generator(), &modulus::<Fq>()), None);
+    let minus_G = native_mul(grumpkin_generator(), &q_minus_one).unwrap();
+    assert_eq!(minus_G, (grumpkin_generator().0, -grumpkin_generator().1));
+
+    for scalar in [q_minus_one, rng.gen_biguint_below(&modulus::<Fq>())] {
+        let circuit = GrumpkinCircuit { scalar };
+        let prover = MockProver::run(17, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
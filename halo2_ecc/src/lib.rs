@@ -1,24 +1,28 @@
 // #![allow(unused_imports, unused_variables)]
 
-// different memory allocator options:
-// empirically jemalloc still seems to give best speeds for witness generation
-#[cfg(feature = "jemalloc")]
-use jemallocator::Jemalloc;
-
-#[cfg(feature = "jemalloc")]
+// different memory allocator options, each behind its feature, with the system allocator by default so that
+// downstream binaries can set their own. Empirically jemalloc still seems to give best speeds for witness generation,
+// so it wins if both features are enabled, e.g. by `--all-features`. Neither builds for `wasm32`, which always uses
+// the system allocator
+#[cfg(all(feature = "jemalloc", not(target_arch = "wasm32")))]
 #[global_allocator]
-static GLOBAL: Jemalloc = Jemalloc;
-
-//#[global_allocator]
-//static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
-//use mimalloc::MiMalloc;
-//#[global_allocator]
-//static GLOBAL: MiMalloc = MiMalloc;
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc"), not(target_arch = "wasm32")))]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+#[cfg(feature = "ark")]
+pub mod ark;
 pub mod bigint;
+pub mod bitcoin;
 pub mod ecc;
 pub mod fields;
+pub mod grumpkin;
+pub mod modexp;
+pub mod rsa;
+pub mod transcript;
+pub mod uint256;
 
 pub mod bn254;
 pub mod secp256k1;
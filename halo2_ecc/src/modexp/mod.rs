@@ -0,0 +1,211 @@
+// The MODEXP precompile of EIP-198
+//
+// The input is the calldata of the precompile: the lengths of the base, exponent and modulus as three 32-byte
+// big-endian words, followed by the base, exponent and modulus themselves as big-endian bytes of those lengths. The
+// output is `base^exp (mod modulus)` as `mod_len` big-endian bytes, which is all zeros for the moduli 0 and 1, as in
+// the EVM. Calldata shorter than the lengths require is read as padded with zeros.
+//
+// The lengths are witnesses bounded by the maximums of the chip, so a single circuit proves calls with any lengths
+// up to them. The bytes of a field are moved into place with barrel shifters controlled by the bits of the lengths.
+// A call with a length above its maximum cannot be proven by the circuit.
+
+use crate::{
+    bigint::{big_is_zero, OverflowInteger},
+    rsa::{RsaChip, RSA_LIMB_BITS},
+};
+use halo2_base::{
+    gates::{
+        bytes::{mask_bytes, shift_bytes},
+        range::RangeConfig,
+        GateInstructions, RangeInstructions,
+    },
+    utils::biguint_to_fe,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+use num_traits::One;
+
+// the lengths in the header, as 32-byte words
+pub const MODEXP_HEADER_LEN: usize = 96;
+
+// the number of bits of `n`
+fn bit_length(n: usize) -> usize {
+    (usize::BITS - n.leading_zeros()) as usize
+}
+
+#[derive(Debug)]
+pub struct ModExpChip<'a, F: FieldExt> {
+    pub range: &'a RangeConfig<F>,
+    // maximum lengths in bytes
+    pub max_base_len: usize,
+    pub max_exp_len: usize,
+    pub max_mod_len: usize,
+}
+
+impl<'a, F: FieldExt> ModExpChip<'a, F> {
+    pub fn construct(
+        range: &'a RangeConfig<F>,
+        max_base_len: usize,
+        max_exp_len: usize,
+        max_mod_len: usize,
+    ) -> Self {
+        assert!(max_base_len > 0 && max_exp_len > 0 && max_mod_len > 0);
+        Self { range, max_base_len, max_exp_len, max_mod_len }
+    }
+
+    // limbs of 64 bits which hold `max_mod_len` bytes
+    fn num_limbs(&self) -> usize {
+        (self.max_mod_len * 8 + RSA_LIMB_BITS - 1) / RSA_LIMB_BITS
+    }
+
+    // the integer with the big-endian `bytes`, as limbs of 64 bits
+    fn from_be_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Result<OverflowInteger<F>, Error> {
+        let bytes_per_limb = RSA_LIMB_BITS / 8;
+        let pows: Vec<_> = (0..bytes_per_limb)
+            .map(|i| Constant(biguint_to_fe(&(BigUint::one() << (8 * i)))))
+            .collect();
+        let mut limbs = Vec::with_capacity((bytes.len() + bytes_per_limb - 1) / bytes_per_limb);
+        for limb_bytes in bytes.rchunks(bytes_per_limb) {
+            limbs.push(self.range.gate().inner_product(
+                ctx,
+                &limb_bytes.iter().rev().map(Existing).collect(),
+                &pows[..limb_bytes.len()].to_vec(),
+            )?);
+        }
+        let num_bits = 8 * bytes.len();
+        Ok(OverflowInteger::construct(
+            limbs,
+            (BigUint::one() << RSA_LIMB_BITS) - 1usize,
+            RSA_LIMB_BITS,
+            (BigUint::one() << num_bits) - 1usize,
+        ))
+    }
+
+    // reads a length of the header, a 32-byte big-endian word, and constrains it to be at most `max_len`
+    fn load_len(
+        &self,
+        ctx: &mut Context<'_, F>,
+        word: &[AssignedValue<F>],
+        max_len: usize,
+    ) -> Result<AssignedValue<F>, Error> {
+        assert_eq!(word.len(), 32);
+        let gate = self.range.gate();
+        // `max_len` is far below 2^64, so only the last 8 bytes can be nonzero
+        for byte in word[..24].iter() {
+            gate.assert_is_const(ctx, byte, F::zero());
+        }
+        let len = self.from_be_bytes(ctx, &word[24..])?.limbs.pop().unwrap();
+        self.range.check_less_than(
+            ctx,
+            &Existing(&len),
+            &Constant(F::from(max_len as u64 + 1)),
+            RSA_LIMB_BITS,
+        )?;
+        Ok(len)
+    }
+
+    /// Returns the output of MODEXP for the calldata `input`: `mod_len` and `max_mod_len` bytes, of which the
+    /// first `mod_len` are the output of the precompile and the rest are zero.
+    /// The bytes of `input` are range checked.
+    pub fn modexp(
+        &self,
+        ctx: &mut Context<'_, F>,
+        input: &[AssignedValue<F>],
+    ) -> Result<(AssignedValue<F>, Vec<AssignedValue<F>>), Error> {
+        let gate = self.range.gate();
+        let data_len = self.max_base_len + self.max_exp_len + self.max_mod_len;
+        let mut input: Vec<_> = input.iter().take(MODEXP_HEADER_LEN + data_len).cloned().collect();
+        for byte in input.iter() {
+            self.range.range_check(ctx, byte, 8)?;
+        }
+        let zero = gate.load_zero(ctx)?;
+        input.resize(MODEXP_HEADER_LEN + data_len, zero);
+
+        let base_len = self.load_len(ctx, &input[..32], self.max_base_len)?;
+        let exp_len = self.load_len(ctx, &input[32..64], self.max_exp_len)?;
+        let mod_len = self.load_len(ctx, &input[64..96], self.max_mod_len)?;
+
+        // each field starts at the beginning of `data` after shifting out the fields before it
+        let data = &input[MODEXP_HEADER_LEN..];
+        let base = data[..self.max_base_len].to_vec();
+        let exp =
+            shift_bytes(self.range, ctx, data, &base_len, bit_length(self.max_base_len), true)?;
+        let exp = exp[..self.max_exp_len].to_vec();
+        let offset = gate.add(ctx, &Existing(&base_len), &Existing(&exp_len))?;
+        let offset_bits = bit_length(self.max_base_len + self.max_exp_len);
+        let modulus = shift_bytes(self.range, ctx, data, &offset, offset_bits, true)?;
+        let modulus = modulus[..self.max_mod_len].to_vec();
+
+        let output =
+            self.modexp_bytes(ctx, &base, &base_len, &exp, &exp_len, &modulus, &mod_len)?;
+        Ok((mod_len, output))
+    }
+
+    /// Returns `base^exp (mod modulus)` as `max_mod_len` bytes, of which the first `mod_len` are its big-endian
+    /// bytes and the rest are zero, or all zeros if `modulus` is 0.
+    ///
+    /// Each of `base, exp, modulus` has its maximum length and holds the big-endian bytes of the number in its
+    /// first `len` entries, as in the calldata; the entries after them are ignored.
+    /// Assumes the bytes are range checked and `len <= max_len` for each length, as in `modexp`.
+    pub fn modexp_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        base: &[AssignedValue<F>],
+        base_len: &AssignedValue<F>,
+        exp: &[AssignedValue<F>],
+        exp_len: &AssignedValue<F>,
+        modulus: &[AssignedValue<F>],
+        mod_len: &AssignedValue<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        assert_eq!(base.len(), self.max_base_len);
+        assert_eq!(exp.len(), self.max_exp_len);
+        assert_eq!(modulus.len(), self.max_mod_len);
+        let gate = self.range.gate();
+        let rsa_chip = RsaChip::construct(self.range, RSA_LIMB_BITS, self.num_limbs());
+
+        // right align each number, i.e. pad it with leading zeros to its maximum length
+        let align = |ctx: &mut Context<'_, F>,
+                     bytes: &[AssignedValue<F>],
+                     len: &AssignedValue<F>| {
+            let bytes = mask_bytes(self.range, ctx, bytes, len, bit_length(bytes.len()))?;
+            let shift = gate.sub(ctx, &Constant(F::from(bytes.len() as u64)), &Existing(len))?;
+            shift_bytes(self.range, ctx, &bytes, &shift, bit_length(bytes.len()), false)
+        };
+        let base = align(ctx, base, base_len)?;
+        let exp = align(ctx, exp, exp_len)?;
+        let modulus = align(ctx, modulus, mod_len)?;
+
+        let base = self.from_be_bytes(ctx, &base)?;
+        let mut exp_bits = Vec::with_capacity(8 * exp.len());
+        for byte in exp.iter().rev() {
+            exp_bits.extend(self.range.num_to_bits(ctx, byte, 8)?);
+        }
+        // `x (mod 1) = 0`, so reducing by `n + (n == 0)` gives 0 for a zero modulus
+        let mut n = self.from_be_bytes(ctx, &modulus)?;
+        let n_is_zero = big_is_zero::assign(self.range, ctx, &n)?;
+        n.limbs[0] = gate.add(ctx, &Existing(&n.limbs[0]), &Existing(&n_is_zero))?;
+
+        let out = rsa_chip.modpow(ctx, &base, &n, &exp_bits)?;
+
+        // `out < n <= 256^max_mod_len`, so only the last `max_mod_len` bytes can be nonzero
+        let mut bytes = Vec::with_capacity(8 * out.limbs.len());
+        for limb in out.limbs.iter().rev() {
+            let limb_bytes = self.range.decompose_bytes(ctx, limb, RSA_LIMB_BITS / 8)?;
+            bytes.extend(limb_bytes.into_iter().rev());
+        }
+        let bytes = bytes.split_off(bytes.len() - self.max_mod_len);
+        // left align the output to its `mod_len` bytes
+        let shift =
+            gate.sub(ctx, &Constant(F::from(self.max_mod_len as u64)), &Existing(mod_len))?;
+        shift_bytes(self.range, ctx, &bytes, &shift, bit_length(self.max_mod_len), true)
+    }
+}
+
+#[cfg(test)]
+mod tests;
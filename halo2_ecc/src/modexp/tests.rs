@@ -0,0 +1,136 @@
+use super::*;
+use halo2_base::{
+    gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+    },
+    utils::value_to_option,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{circuit::Value, dev::MockProver, halo2curves::bn256::Fr};
+use num_bigint::RandBigInt;
+use num_traits::ToPrimitive;
+use rand::rngs::OsRng;
+
+const PARAMS: CircuitParams = CircuitParams {
+    degree: 16,
+    num_advice: 6,
+    num_lookup_advice: 1,
+    num_fixed: 1,
+    lookup_bits: 15,
+};
+
+const MAX_BASE_LEN: usize = 16;
+const MAX_EXP_LEN: usize = 8;
+const MAX_MOD_LEN: usize = 16;
+
+fn len_word(len: usize) -> Vec<u8> {
+    let mut word = vec![0u8; 32];
+    word[24..].copy_from_slice(&(len as u64).to_be_bytes());
+    word
+}
+
+fn calldata(base: &[u8], exp: &[u8], modulus: &[u8]) -> Vec<u8> {
+    [
+        len_word(base.len()),
+        len_word(exp.len()),
+        len_word(modulus.len()),
+        base.to_vec(),
+        exp.to_vec(),
+        modulus.to_vec(),
+    ]
+    .concat()
+}
+
+// the output of the precompile, as the EVM computes it
+fn modexp_native(input: &[u8]) -> Vec<u8> {
+    let mut input = input.to_vec();
+    input.resize(MODEXP_HEADER_LEN, 0);
+    let len = |i: usize| BigUint::from_bytes_be(&input[32 * i..32 * (i + 1)]).to_usize().unwrap();
+    let (base_len, exp_len, mod_len) = (len(0), len(1), len(2));
+    let mut data = input[MODEXP_HEADER_LEN..].to_vec();
+    data.resize(base_len + exp_len + mod_len, 0);
+    let base = BigUint::from_bytes_be(&data[..base_len]);
+    let exp = BigUint::from_bytes_be(&data[base_len..base_len + exp_len]);
+    let modulus = BigUint::from_bytes_be(&data[base_len + exp_len..]);
+    if mod_len == 0 || modulus == BigUint::from(0u64) {
+        return vec![0; mod_len];
+    }
+    let out = base.modpow(&exp, &modulus).to_bytes_be();
+    [vec![0; mod_len - out.len()], out].concat()
+}
+
+fn modexp_circuit(input: Vec<u8>) -> impl halo2_proofs::plonk::Circuit<Fr> {
+    let mut expected = modexp_native(&input);
+    let mod_len = expected.len();
+    expected.resize(MAX_MOD_LEN, 0);
+    RangeCircuitBuilder::new(
+        RangeStrategy::Vertical,
+        PARAMS,
+        move |range: &RangeConfig<Fr>, ctx| {
+            let chip = ModExpChip::construct(range, MAX_BASE_LEN, MAX_EXP_LEN, MAX_MOD_LEN);
+            let input = range.gate().assign_region_smart(
+                ctx,
+                input.iter().map(|b| Witness(Value::known(Fr::from(*b as u64)))).collect(),
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            let (out_len, out) = chip.modexp(ctx, &input)?;
+            assert_eq!(value_to_option(out_len.value().copied()), Some(Fr::from(mod_len as u64)));
+            let out: Vec<_> =
+                out.iter().map(|b| value_to_option(b.value().copied()).unwrap()).collect();
+            assert_eq!(out, expected.iter().map(|b| Fr::from(*b as u64)).collect::<Vec<_>>());
+            Ok(())
+        },
+    )
+}
+
+#[test]
+fn test_modexp() {
+    let random = |bytes: usize| OsRng.gen_biguint(8 * bytes as u64).to_bytes_be();
+    let cases = vec![
+        calldata(&random(10), &random(3), &random(16)),
+        calldata(&random(16), &random(8), &random(5)),
+        // leading zeros of the modulus stay in the output
+        calldata(&[3], &[0xff, 0xff], &[0, 0, 0, 0x61]),
+        // zero exponent, zero base and the moduli 0 and 1
+        calldata(&random(4), &[], &random(9)),
+        calldata(&[], &random(2), &random(9)),
+        calldata(&random(4), &random(2), &[0, 0]),
+        calldata(&random(4), &random(2), &[1]),
+        calldata(&[], &[], &[]),
+        // calldata ending inside the modulus is padded with zeros
+        calldata(&random(2), &random(2), &random(12))[..MODEXP_HEADER_LEN + 10].to_vec(),
+    ];
+    for input in cases {
+        let circuit = modexp_circuit(input);
+        let prover = MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
+
+#[test]
+fn test_modexp_length_out_of_range() {
+    let mut input = calldata(&[2], &[3], &[5]);
+    input[..32].copy_from_slice(&len_word(MAX_BASE_LEN + 1));
+    let circuit = RangeCircuitBuilder::new(
+        RangeStrategy::Vertical,
+        PARAMS,
+        move |range: &RangeConfig<Fr>, ctx| {
+            let chip = ModExpChip::construct(range, MAX_BASE_LEN, MAX_EXP_LEN, MAX_MOD_LEN);
+            let input = range.gate().assign_region_smart(
+                ctx,
+                input.iter().map(|b| Witness(Value::known(Fr::from(*b as u64)))).collect(),
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            chip.modexp(ctx, &input)?;
+            Ok(())
+        },
+    );
+    let prover = MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,223 @@
+use crate::bigint::{big_less_than, mul_no_carry, select, var_carry_mod, OverflowInteger};
+use halo2_base::{
+    gates::{range::RangeConfig, GateInstructions, RangeInstructions},
+    utils::{decompose_bigint_option, decompose_biguint},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_traits::One;
+
+/// Limb parameters for RSA moduli: limbs of 64 bits keep `mul_no_carry` and `var_carry_mod` well within the
+/// overflow budget of a ~254 bit native field
+pub const RSA_LIMB_BITS: usize = 64;
+pub const RSA_2048_NUM_LIMBS: usize = 32;
+pub const RSA_4096_NUM_LIMBS: usize = 64;
+
+/// The public RSA exponent used by almost all keys
+pub const RSA_DEFAULT_E: u64 = 65537;
+
+/// DER encoding of the `DigestInfo` prefix for SHA-256 from RFC 8017, Section 9.2
+pub const SHA256_DIGEST_INFO_PREFIX: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20,
+];
+
+#[derive(Debug)]
+pub struct RsaChip<'a, F: FieldExt> {
+    pub range: &'a RangeConfig<F>,
+    pub limb_bits: usize,
+    pub num_limbs: usize,
+}
+
+impl<'a, F: FieldExt> RsaChip<'a, F> {
+    pub fn construct(range: &'a RangeConfig<F>, limb_bits: usize, num_limbs: usize) -> Self {
+        assert!(limb_bits <= RSA_LIMB_BITS);
+        Self { range, limb_bits, num_limbs }
+    }
+
+    /// Loads `a` as a witness in proper representation with `self.num_limbs` limbs, each range checked
+    pub fn load_private(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: Value<BigUint>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let a_vec = decompose_bigint_option::<F>(
+            &a.map(|a| BigInt::from(a)),
+            self.num_limbs,
+            self.limb_bits,
+        );
+        let limbs = self.range.gate().assign_region_smart(
+            ctx,
+            a_vec.iter().map(|x| Witness(*x)).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        for limb in limbs.iter() {
+            self.range.range_check(ctx, limb, self.limb_bits)?;
+        }
+        Ok(OverflowInteger::construct(
+            limbs,
+            (BigUint::one() << self.limb_bits) - 1usize,
+            self.limb_bits,
+            (BigUint::one() << (self.limb_bits * self.num_limbs)) - 1usize,
+        ))
+    }
+
+    pub fn load_constant(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &BigUint,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let a_vec = decompose_biguint::<F>(a, self.num_limbs, self.limb_bits);
+        let limbs = self.range.gate().assign_region_smart(
+            ctx,
+            a_vec.iter().map(|x| Constant(*x)).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(OverflowInteger::construct(
+            limbs,
+            (BigUint::one() << self.limb_bits) - 1usize,
+            self.limb_bits,
+            a.clone(),
+        ))
+    }
+
+    /// Returns `a * b (mod n)` in proper representation, where `n` is a witness modulus
+    pub fn mul_mod(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+        n: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let prod = mul_no_carry::assign(self.range.gate(), ctx, a, b)?;
+        var_carry_mod::assign(self.range, ctx, &prod, n)
+    }
+
+    /// Returns `base^e (mod n)` for a constant exponent `e`, e.g., `e = 65537`
+    /// Uses left-to-right square-and-multiply, so this costs `e.bits() - 1` squarings and `e.count_ones() - 1` multiplications
+    pub fn modpow_public(
+        &self,
+        ctx: &mut Context<'_, F>,
+        base: &OverflowInteger<F>,
+        n: &OverflowInteger<F>,
+        e: u64,
+    ) -> Result<OverflowInteger<F>, Error> {
+        assert!(e > 0);
+        // reduce `base` so that intermediate values stay in `[0, n)`
+        let mut out = var_carry_mod::assign(self.range, ctx, base, n)?;
+        let base = out.clone();
+        for i in (0..63 - e.leading_zeros()).rev() {
+            out = self.mul_mod(ctx, &out, &out, n)?;
+            if (e >> i) & 1 == 1 {
+                out = self.mul_mod(ctx, &out, &base, n)?;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Returns `base^e (mod n)` for a witness exponent given by its little-endian bits `e_bits`
+    /// * assumes each element of `e_bits` is constrained to be a bit, e.g., by `RangeInstructions::num_to_bits`
+    pub fn modpow(
+        &self,
+        ctx: &mut Context<'_, F>,
+        base: &OverflowInteger<F>,
+        n: &OverflowInteger<F>,
+        e_bits: &[AssignedValue<F>],
+    ) -> Result<OverflowInteger<F>, Error> {
+        assert!(!e_bits.is_empty());
+        let base = var_carry_mod::assign(self.range, ctx, base, n)?;
+        let one = self.load_constant(ctx, &BigUint::one())?;
+        let mut out =
+            select::assign(self.range.gate(), ctx, &base, &one, &e_bits[e_bits.len() - 1])?;
+        for bit in e_bits.iter().rev().skip(1) {
+            out = self.mul_mod(ctx, &out, &out, n)?;
+            let mul = self.mul_mod(ctx, &out, &base, n)?;
+            out = select::assign(self.range.gate(), ctx, &mul, &out, bit)?;
+        }
+        Ok(out)
+    }
+
+    /// Returns the EMSA-PKCS1-v1_5 encoding `0x00 || 0x01 || 0xff..0xff || 0x00 || DigestInfo || H` of a SHA-256 digest
+    /// for a modulus of `num_bytes` bytes, with the 32 bytes of `H` left as zero
+    pub fn pkcs1v15_sha256_padding(num_bytes: usize) -> BigUint {
+        assert!(num_bytes >= 3 + 8 + SHA256_DIGEST_INFO_PREFIX.len() + 32);
+        let mut em = vec![0x00u8, 0x01];
+        em.extend(vec![0xffu8; num_bytes - 3 - SHA256_DIGEST_INFO_PREFIX.len() - 32]);
+        em.push(0x00);
+        em.extend(SHA256_DIGEST_INFO_PREFIX);
+        em.extend([0u8; 32]);
+        BigUint::from_bytes_be(&em)
+    }
+
+    /// Verifies an RSASSA-PKCS1-v1_5 signature with public exponent `e` over a SHA-256 digest
+    ///
+    /// Input:
+    /// * `n` is the public modulus, `sig` is the signature, both in proper representation
+    /// * `key_bytes` is the length `k` of `n` in bytes from RFC 8017, which sets the length of the encoding
+    /// * `msg_hash` is the SHA-256 digest as four 64-bit words in little-endian order, i.e., `msg_hash[0]` is
+    ///   the last 8 bytes of the digest read as a big-endian integer
+    ///
+    /// Output: a boolean that is `1` iff `sig < n` and `sig^e (mod n)` equals the EMSA-PKCS1-v1_5 encoding of
+    /// `msg_hash`
+    ///
+    /// Constrains `n` to have exactly `key_bytes` bytes, i.e., `2^{8 (key_bytes - 1)} <= n < 2^{8 key_bytes}`.
+    /// Requires `self.limb_bits == 64` so that the digest words line up with the limbs.
+    /// RSASSA-PSS is not supported since it needs an in-circuit hash for the mask generation function.
+    pub fn verify_pkcs1v15_sha256(
+        &self,
+        ctx: &mut Context<'_, F>,
+        n: &OverflowInteger<F>,
+        sig: &OverflowInteger<F>,
+        e: u64,
+        key_bytes: usize,
+        msg_hash: &[AssignedValue<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        assert_eq!(self.limb_bits, 64);
+        assert_eq!(msg_hash.len(), 4);
+        assert!(key_bytes <= self.num_limbs * 8);
+        let gate = self.range.gate();
+
+        let n_lt_max = big_less_than::assign_constant(
+            self.range,
+            ctx,
+            n,
+            &(BigUint::one() << (8 * key_bytes)),
+        )?;
+        gate.assert_is_const(ctx, &n_lt_max, F::one());
+        let n_lt_min = big_less_than::assign_constant(
+            self.range,
+            ctx,
+            n,
+            &(BigUint::one() << (8 * (key_bytes - 1))),
+        )?;
+        gate.assert_is_const(ctx, &n_lt_min, F::zero());
+
+        // without `sig < n`, both `sig` and `sig + n` would be accepted
+        let sig_lt_n = big_less_than::assign(self.range, ctx, sig, n)?;
+        let em = self.modpow_public(ctx, sig, n, e)?;
+
+        let padding = Self::pkcs1v15_sha256_padding(key_bytes);
+        let padding_limbs = decompose_biguint::<F>(&padding, self.num_limbs, self.limb_bits);
+
+        let mut is_valid = sig_lt_n;
+        for (i, limb) in em.limbs.iter().enumerate() {
+            let expected = if i < msg_hash.len() {
+                Existing(&msg_hash[i])
+            } else {
+                Constant(padding_limbs[i])
+            };
+            let eq = self.range.is_equal(ctx, &Existing(limb), &expected)?;
+            is_valid = gate.and(ctx, &Existing(&is_valid), &Existing(&eq))?;
+        }
+        Ok(is_valid)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests;
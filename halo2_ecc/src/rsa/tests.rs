@@ -0,0 +1,380 @@
+use super::*;
+use halo2_base::{
+    gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+    },
+    utils::value_to_option,
+    ContextId, ContextParams,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::{MockProver, VerifyFailure},
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use num_bigint::RandBigInt;
+use std::marker::PhantomData;
+
+const NUM_ADVICE: usize = 4;
+const NUM_LOOKUP_ADVICE: usize = 1;
+const NUM_FIXED: usize = 1;
+const LOOKUP_BITS: usize = 16;
+
+struct RsaCircuit<F> {
+    base: Option<BigUint>,
+    n: Option<BigUint>,
+    modulus_bits: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for RsaCircuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { base: None, n: None, modulus_bits: self.modulus_bits, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[NUM_LOOKUP_ADVICE],
+            NUM_FIXED,
+            LOOKUP_BITS,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = RsaChip::construct(
+            &config,
+            RSA_LIMB_BITS,
+            (self.modulus_bits + RSA_LIMB_BITS - 1) / RSA_LIMB_BITS,
+        );
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "rsa",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let to_value = |x: &Option<BigUint>| match x {
+                    Some(x) => Value::known(x.clone()),
+                    None => Value::unknown(),
+                };
+                let base = chip.load_private(ctx, to_value(&self.base))?;
+                let n = chip.load_private(ctx, to_value(&self.n))?;
+
+                let out = chip.modpow_public(ctx, &base, &n, RSA_DEFAULT_E)?;
+                if let (Some(base), Some(n)) = (&self.base, &self.n) {
+                    let expected = base.modpow(&BigUint::from(RSA_DEFAULT_E), n);
+                    assert_eq!(value_to_option(out.to_bigint()), Some(BigInt::from(expected)));
+                }
+
+                if self.n != None {
+                    println!("RSA-{} modpow with e = {}:", self.modulus_bits, RSA_DEFAULT_E);
+                    println!(
+                        "total advice cells: {}",
+                        ctx.advice_rows[&ContextId::new("default")].iter().sum::<usize>()
+                    );
+                    println!("cells used in special lookup columns: {}", ctx.cells_to_lookup.len());
+                }
+
+                // IMPORTANT: this assigns all constants to the fixed columns
+                // IMPORTANT: this copies cells to the lookup advice column to perform range check lookups
+                // This is not optional.
+                let (const_rows, _, _) = config.finalize(ctx)?;
+
+                if self.n != None {
+                    println!(
+                        "maximum rows used by an advice column: {}",
+                        ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                    );
+                    println!("maximum rows used by a fixed column: {}", const_rows);
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+fn random_circuit(modulus_bits: usize) -> RsaCircuit<Fr> {
+    let mut rng = rand::thread_rng();
+    // the modulus does not need to be an actual RSA modulus to test `modpow`
+    let n = rng.gen_biguint(modulus_bits as u64) | (BigUint::one() << (modulus_bits - 1));
+    let base = rng.gen_biguint_below(&n);
+    RsaCircuit { base: Some(base), n: Some(n), modulus_bits, _marker: PhantomData }
+}
+
+#[test]
+fn test_rsa_2048_modpow() {
+    let k = 18;
+    let circuit = random_circuit(RSA_LIMB_BITS * RSA_2048_NUM_LIMBS);
+
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+// `modpow` of 256 bit integers with the 20 bits of `e` as a witness
+fn run_modpow(base: BigUint, n: BigUint, e: u64) -> Result<(), Vec<VerifyFailure>> {
+    let params = CircuitParams {
+        degree: 15,
+        num_advice: NUM_ADVICE,
+        num_lookup_advice: NUM_LOOKUP_ADVICE,
+        num_fixed: NUM_FIXED,
+        lookup_bits: 14,
+    };
+    let circuit = RangeCircuitBuilder::new(
+        RangeStrategy::Vertical,
+        params,
+        move |range: &RangeConfig<Fr>, ctx| {
+            let chip = RsaChip::construct(range, RSA_LIMB_BITS, 4);
+            let base_int = chip.load_private(ctx, Value::known(base.clone()))?;
+            let n_int = chip.load_private(ctx, Value::known(n.clone()))?;
+            let e_cell = range.gate().assign_region_smart(
+                ctx,
+                vec![Witness(Value::known(Fr::from(e)))],
+                vec![],
+                vec![],
+                vec![],
+            )?;
+            let e_bits = range.num_to_bits(ctx, &e_cell[0], 20)?;
+            let out = chip.modpow(ctx, &base_int, &n_int, &e_bits)?;
+            if n.bits() > 0 {
+                let expected = base.modpow(&BigUint::from(e), &n);
+                assert_eq!(value_to_option(out.to_bigint()), Some(BigInt::from(expected)));
+            }
+            Ok(())
+        },
+    );
+    MockProver::run(params.degree, &circuit, vec![]).unwrap().verify()
+}
+
+#[test]
+fn test_modpow() {
+    let mut rng = rand::thread_rng();
+    // `n + 5` also fits in the 4 limbs
+    let n = rng.gen_biguint(255) | (BigUint::one() << 254);
+    let base = rng.gen_biguint_below(&n);
+    for e in [0xb5a3d, 1, 0] {
+        assert_eq!(run_modpow(base.clone(), n.clone(), e), Ok(()));
+    }
+    // a base which is not reduced mod `n`
+    assert_eq!(run_modpow(&n + 5u64, n.clone(), 0xb5a3d), Ok(()));
+
+    // there is no `0 <= base < 0` to reduce to
+    assert!(run_modpow(base, BigUint::from(0u64), 0xb5a3d).is_err());
+}
+
+// RSA-2048 key, SHA-256 digest and signature of the message "halo2-lib rsa test vector", generated with
+// `openssl genrsa 2048` and `openssl dgst -sha256 -sign`, and checked with `openssl dgst -sha256 -verify`
+const PKCS1V15_N: [&str; 8] = [
+    "a433f79a9e2c0b59e20d5eddc97d35b5220cba6004414db6e13102e740230a56",
+    "970cf9cc785f71c1aa7bbc0af0349ff1b98c2860e8c5ad77aac946c723c175a7",
+    "39fff134d233f0290cb18986594d2e586b3c7510d955f87f5b4e7653f67d197e",
+    "f550fd1b3ebdefeeba89986a5edb4c7032317f2a7f2b37187fd1dfdb6b5fca58",
+    "f4bfc13fb1421d2bd48814ac8c03aa5fadc7de2ebcfb0ee2fd11eec50e170b97",
+    "e018bd7adbcee021eebd72356ca89225edd6ed6c40c024b0ce1fd64c859a4da8",
+    "09a074d8730235f440b8b8c1366b598eaccfa11420ef1f15e8d007a80986e234",
+    "f7c10eff0c4f47efbdde15e281430e02c91cb2c802209b8946884c6069e1580b",
+];
+const PKCS1V15_SIG: [&str; 8] = [
+    "3a05c38c05de7c19c43714edf8a25af9976fb721754b02543deb2c5ef0de5a16",
+    "314452ede95fb06959510414c2b234e5a4690cc36bcb41230fb87ac66d6cd867",
+    "7aa024081829420d424692e80d6bd2c702b1b0f9364ba11245e0c3f674ebaa11",
+    "0ea6c99b43a59b6f417747f6abf75017e86b37e1d13e192d838e8fa68dd83524",
+    "95a20eb3af7f02fe987fec4599a74a570477522e626a83931fe529e5f586da5c",
+    "bcc49248f9b808da1e2bcd9c60a77f59bc1822df586d68b63d1692c93b25ac09",
+    "48175278b584089b54ef4014ec3781d2118153597b763bba47e6a379f0661f1f",
+    "db1c1185f5dd92a2f1e171d84e8216b467a564e4bd89200908a0fb36018898a8",
+];
+const PKCS1V15_DIGEST: &str = "2a0ccfe17ff2c4349cbbff2964be7e11d66cdd57dc0df0cc80f1897f30b1da9d";
+
+fn from_hex(chunks: &[&str]) -> BigUint {
+    BigUint::parse_bytes(chunks.concat().as_bytes(), 16).unwrap()
+}
+
+struct Pkcs1v15Circuit<F> {
+    n: Option<BigUint>,
+    sig: Option<BigUint>,
+    digest: Option<BigUint>,
+    key_bytes: usize,
+    expected: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for Pkcs1v15Circuit<F> {
+    type Config = RangeConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            n: None,
+            sig: None,
+            digest: None,
+            key_bytes: self.key_bytes,
+            expected: self.expected,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        RangeConfig::configure(
+            meta,
+            RangeStrategy::Vertical,
+            &[NUM_ADVICE],
+            &[NUM_LOOKUP_ADVICE],
+            NUM_FIXED,
+            LOOKUP_BITS,
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load_lookup_table(&mut layouter)?;
+        let chip = RsaChip::construct(&config, RSA_LIMB_BITS, RSA_2048_NUM_LIMBS);
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "pkcs1v15",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let to_value = |x: &Option<BigUint>| match x {
+                    Some(x) => Value::known(x.clone()),
+                    None => Value::unknown(),
+                };
+                let n = chip.load_private(ctx, to_value(&self.n))?;
+                let sig = chip.load_private(ctx, to_value(&self.sig))?;
+                // the digest as four 64-bit words, least significant first
+                let words = decompose_bigint_option::<F>(
+                    &to_value(&self.digest).map(BigInt::from),
+                    4,
+                    RSA_LIMB_BITS,
+                );
+                let msg_hash = config.gate().assign_region_smart(
+                    ctx,
+                    words.into_iter().map(Witness).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                for word in msg_hash.iter() {
+                    config.range_check(ctx, word, RSA_LIMB_BITS)?;
+                }
+
+                let is_valid = chip.verify_pkcs1v15_sha256(
+                    ctx,
+                    &n,
+                    &sig,
+                    RSA_DEFAULT_E,
+                    self.key_bytes,
+                    &msg_hash,
+                )?;
+                config.gate().assert_is_const(ctx, &is_valid, F::from(self.expected as u64));
+
+                config.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn run_pkcs1v15(
+    sig: BigUint,
+    digest: BigUint,
+    key_bytes: usize,
+    expected: bool,
+) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    let k = 18;
+    let circuit = Pkcs1v15Circuit::<Fr> {
+        n: Some(from_hex(&PKCS1V15_N)),
+        sig: Some(sig),
+        digest: Some(digest),
+        key_bytes,
+        expected,
+        _marker: PhantomData,
+    };
+    MockProver::run(k, &circuit, vec![]).unwrap().verify()
+}
+
+fn pkcs1v15_digest() -> BigUint {
+    BigUint::parse_bytes(PKCS1V15_DIGEST.as_bytes(), 16).unwrap()
+}
+
+#[test]
+fn test_pkcs1v15_sha256() {
+    let sig = from_hex(&PKCS1V15_SIG);
+    assert_eq!(run_pkcs1v15(sig.clone(), pkcs1v15_digest(), 256, true), Ok(()));
+    assert!(run_pkcs1v15(sig, pkcs1v15_digest(), 256, false).is_err());
+}
+
+#[test]
+fn test_pkcs1v15_sha256_tampered_signature() {
+    let sig = from_hex(&PKCS1V15_SIG) ^ BigUint::one();
+    assert_eq!(run_pkcs1v15(sig, pkcs1v15_digest(), 256, false), Ok(()));
+}
+
+#[test]
+fn test_pkcs1v15_sha256_wrong_digest() {
+    let digest = pkcs1v15_digest() ^ (BigUint::one() << 200);
+    assert_eq!(run_pkcs1v15(from_hex(&PKCS1V15_SIG), digest, 256, false), Ok(()));
+}
+
+#[test]
+fn test_pkcs1v15_sha256_unreduced_signature() {
+    // `sig + n` still fits in 2048 bits and has the same `sig^e (mod n)`, so only `sig < n` rejects it
+    let sig = from_hex(&PKCS1V15_SIG) + from_hex(&PKCS1V15_N);
+    assert!(sig.bits() <= 2048);
+    assert_eq!(run_pkcs1v15(sig, pkcs1v15_digest(), 256, false), Ok(()));
+}
+
+#[test]
+fn test_pkcs1v15_sha256_wrong_key_size() {
+    // the 2048-bit modulus does not have 255 bytes
+    assert!(run_pkcs1v15(from_hex(&PKCS1V15_SIG), pkcs1v15_digest(), 255, false).is_err());
+}
+
+#[test]
+fn bench_rsa() {
+    for (k, num_limbs) in [(18, RSA_2048_NUM_LIMBS), (20, RSA_4096_NUM_LIMBS)] {
+        let circuit = random_circuit(RSA_LIMB_BITS * num_limbs);
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
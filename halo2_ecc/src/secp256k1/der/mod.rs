@@ -0,0 +1,165 @@
+// Strict DER encoding of ECDSA signatures (BIP-66)
+//
+// A signature is encoded as `0x30 len 0x02 r_len r 0x02 s_len s`, where `r` and `s` are the minimal big-endian two's
+// complement encodings of the integers: at most 33 bytes, not negative, and without a leading zero unless the next
+// byte has its top bit set. `parse_der_signature` reads the lengths from assigned bytes, moves `r` and `s` into place
+// with barrel shifters controlled by the bits of the lengths, and returns them as the limbed integers taken by
+// `ecdsa_verify_no_pubkey_check`, together with a flag for whether the bytes are a strict DER encoding.
+// Bitcoin appends a sighash byte to the encoding, which the caller should split off first.
+
+use super::Fp;
+use crate::{
+    bigint::{bytes::from_bytes_le, OverflowInteger},
+    fields::{fp::FpConfig, FieldChip},
+};
+use halo2_base::{
+    gates::{
+        bytes::{mask_bytes, shift_bytes},
+        GateInstructions, RangeInstructions,
+    },
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use num_bigint::BigUint;
+
+// the encoding of two integers of `MAX_INT_LEN` bytes
+pub const MAX_DER_SIGNATURE_LEN: usize = 72;
+// the maximum length of the encoding of `r` or `s`, a 256 bit integer with a leading zero byte
+const MAX_INT_LEN: usize = 33;
+// the number of bits of the lengths and shifts, which are at most `MAX_INT_LEN`
+const LEN_BITS: usize = 6;
+
+/// The integers of a DER-encoded signature and whether the encoding is strict DER
+#[derive(Clone, Debug)]
+pub struct DerSignature<F: FieldExt> {
+    pub r: OverflowInteger<F>,
+    pub s: OverflowInteger<F>,
+    pub is_strict: AssignedValue<F>,
+}
+
+/// The strict DER encoding of the signature `(r, s)`
+pub fn encode_der_signature_native(r: &BigUint, s: &BigUint) -> Vec<u8> {
+    let int = |x: &BigUint| {
+        let mut bytes = x.to_bytes_be();
+        if bytes[0] >= 0x80 {
+            bytes.insert(0, 0);
+        }
+        [vec![0x02, bytes.len() as u8], bytes].concat()
+    };
+    let body = [int(r), int(s)].concat();
+    [vec![0x30, body.len() as u8], body].concat()
+}
+
+// returns `(len_is_valid, len)` if `1 <= len <= MAX_INT_LEN`, and `(0, 1)` otherwise, so that a malformed length
+// only clears the flag instead of making the shifts unsatisfiable
+fn clamp_len<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    len: &AssignedValue<F>,
+) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+    let gate = range.gate();
+    let is_zero = range.is_zero(ctx, len)?;
+    let is_nonzero = gate.not(ctx, &Existing(&is_zero))?;
+    let is_small =
+        range.is_less_than(ctx, &Existing(len), &Constant(F::from(MAX_INT_LEN as u64 + 1)), 8)?;
+    let is_valid = gate.and(ctx, &Existing(&is_nonzero), &Existing(&is_small))?;
+    let len = gate.select(ctx, &Existing(len), &Constant(F::one()), &Existing(&is_valid))?;
+    Ok((is_valid, len))
+}
+
+// Input: the `MAX_INT_LEN` bytes starting at an encoded integer of `len` bytes, with `1 <= len <= MAX_INT_LEN`
+// Output: the 32 big-endian bytes of the integer and whether its encoding is minimal, non-negative and fits in them
+fn parse_int<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    window: &[AssignedValue<F>],
+    len: &AssignedValue<F>,
+) -> Result<(Vec<AssignedValue<F>>, AssignedValue<F>), Error> {
+    assert_eq!(window.len(), MAX_INT_LEN);
+    let gate = range.gate();
+
+    // right align the integer, i.e. zero the bytes after it and pad it with leading zeros
+    let masked = mask_bytes(range, ctx, window, len, LEN_BITS)?;
+    let pad = gate.sub(ctx, &Constant(F::from(MAX_INT_LEN as u64)), &Existing(len))?;
+    let mut aligned = shift_bytes(range, ctx, &masked, &pad, LEN_BITS, false)?;
+
+    let top = Constant(F::from(0x80));
+    let is_nonnegative = range.is_less_than(ctx, &Existing(&window[0]), &top, 8)?;
+    // a leading zero is only allowed before a byte with the top bit set
+    let has_zero = range.is_zero(ctx, &window[0])?;
+    let next_is_low = range.is_less_than(ctx, &Existing(&window[1]), &top, 8)?;
+    let is_long = range.is_less_than(ctx, &Constant(F::one()), &Existing(len), LEN_BITS)?;
+    let redundant_zero = gate.and(ctx, &Existing(&has_zero), &Existing(&next_is_low))?;
+    let redundant_zero = gate.and(ctx, &Existing(&redundant_zero), &Existing(&is_long))?;
+    let is_minimal = gate.not(ctx, &Existing(&redundant_zero))?;
+    // the integer has at most 256 bits
+    let fits = range.is_zero(ctx, &aligned[0])?;
+
+    let is_strict = gate.and(ctx, &Existing(&is_nonnegative), &Existing(&is_minimal))?;
+    let is_strict = gate.and(ctx, &Existing(&is_strict), &Existing(&fits))?;
+    Ok((aligned.split_off(1), is_strict))
+}
+
+/// Input: a DER-encoded signature padded with zeros to `MAX_DER_SIGNATURE_LEN` bytes. The bytes are range checked;
+/// those after the encoding are ignored.
+///
+/// Output: `r` and `s` in proper representation with the limbs of `fp_chip`, whose `limb_bits` must be a multiple
+/// of 8, which are only meaningful if `is_strict` is 1. The caller still needs the range checks of `ecdsa_verify_no_pubkey_check` or
+/// `ecdsa_is_in_range`, since DER allows integers up to 2^256, and e.g. `ecdsa_is_low_s` for Bitcoin.
+pub fn parse_der_signature<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+) -> Result<DerSignature<F>, Error> {
+    assert_eq!(bytes.len(), MAX_DER_SIGNATURE_LEN);
+    let range = fp_chip.range();
+    let gate = range.gate();
+    for byte in bytes.iter() {
+        range.range_check(ctx, byte, 8)?;
+    }
+
+    let mut flags = Vec::new();
+    flags.push(range.is_equal(ctx, &Existing(&bytes[0]), &Constant(F::from(0x30)))?);
+    flags.push(range.is_equal(ctx, &Existing(&bytes[2]), &Constant(F::from(0x02)))?);
+    let (r_len_is_valid, r_len) = clamp_len(range, ctx, &bytes[3])?;
+    flags.push(r_len_is_valid);
+    let (r, r_is_strict) = parse_int(range, ctx, &bytes[4..4 + MAX_INT_LEN], &r_len)?;
+    flags.push(r_is_strict);
+
+    // `rest[i] = bytes[i + r_len]`, so `s` is encoded from `rest[4]` on
+    let rest = shift_bytes(range, ctx, bytes, &r_len, LEN_BITS, true)?;
+    flags.push(range.is_equal(ctx, &Existing(&rest[4]), &Constant(F::from(0x02)))?);
+    let (s_len_is_valid, s_len) = clamp_len(range, ctx, &rest[5])?;
+    flags.push(s_len_is_valid);
+    let (s, s_is_strict) = parse_int(range, ctx, &rest[6..6 + MAX_INT_LEN], &s_len)?;
+    flags.push(s_is_strict);
+
+    let body_len = gate.add(ctx, &Existing(&r_len), &Existing(&s_len))?;
+    let body_len = gate.add(ctx, &Existing(&body_len), &Constant(F::from(4)))?;
+    flags.push(range.is_equal(ctx, &Existing(&bytes[1]), &Existing(&body_len))?);
+
+    let mut is_strict = flags[0].clone();
+    for flag in flags[1..].iter() {
+        is_strict = gate.and(ctx, &Existing(&is_strict), &Existing(flag))?;
+    }
+
+    let to_integer = |ctx: &mut Context<'_, F>, be_bytes: Vec<AssignedValue<F>>| {
+        let le_bytes: Vec<_> = be_bytes.into_iter().rev().collect();
+        from_bytes_le(
+            range,
+            &fp_chip.bigint_chip,
+            ctx,
+            &le_bytes,
+            fp_chip.limb_bits,
+            fp_chip.num_limbs,
+        )
+        .map(|a| a.truncation)
+    };
+    let r = to_integer(ctx, r)?;
+    let s = to_integer(ctx, s)?;
+    Ok(DerSignature { r, s, is_strict })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,159 @@
+use super::*;
+use crate::fields::fp::FpStrategy;
+use halo2_base::{
+    utils::{modulus, value_to_option},
+    ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use halo2curves::secp256k1::Fq;
+use num_bigint::RandBigInt;
+use rand::rngs::OsRng;
+
+const K: u32 = 17;
+const NUM_ADVICE: usize = 2;
+
+#[derive(Clone, Default)]
+struct DerCircuit<F> {
+    // each encoding with its `(r, s)` if it is strict DER
+    cases: Vec<(Vec<u8>, Option<(BigUint, BigUint)>)>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for DerCircuit<F> {
+    type Config = FpConfig<F, Fp>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            16,
+            88,
+            3,
+            modulus::<Fp>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        fp_chip: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        fp_chip.range.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "der",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                for (encoding, expected) in self.cases.iter() {
+                    let mut encoding = encoding.clone();
+                    encoding.resize(MAX_DER_SIGNATURE_LEN, 0);
+                    let bytes = fp_chip.range.gate().assign_region_smart(
+                        ctx,
+                        encoding
+                            .iter()
+                            .map(|b| Witness(Value::known(F::from(*b as u64))))
+                            .collect(),
+                        vec![],
+                        vec![],
+                        vec![],
+                    )?;
+                    let sig = parse_der_signature(&fp_chip, ctx, &bytes)?;
+                    assert_eq!(
+                        value_to_option(sig.is_strict.value().copied()),
+                        Some(F::from(expected.is_some()))
+                    );
+                    if let Some((r, s)) = expected {
+                        let value = |a: &OverflowInteger<F>| {
+                            value_to_option(a.to_bigint()).unwrap().to_biguint().unwrap()
+                        };
+                        assert_eq!(&value(&sig.r), r);
+                        assert_eq!(&value(&sig.s), s);
+                    }
+                }
+
+                fp_chip.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_encode_der_signature_native() {
+    let r = BigUint::from(0x80u64);
+    let s = BigUint::from(0x7fu64);
+    assert_eq!(
+        encode_der_signature_native(&r, &s),
+        vec![0x30, 0x07, 0x02, 0x02, 0x00, 0x80, 0x02, 0x01, 0x7f]
+    );
+}
+
+#[test]
+fn test_parse_der_signature() {
+    let n = modulus::<Fq>();
+    let mut r = OsRng.gen_biguint_below(&n);
+    r.set_bit(255, true);
+    let s = BigUint::from(5u64);
+    let strict = |r: &BigUint, s: &BigUint| {
+        (encode_der_signature_native(r, s), Some((r.clone(), s.clone())))
+    };
+    let not_strict = |encoding: Vec<u8>| (encoding, None);
+
+    let valid = encode_der_signature_native(&r, &s);
+    // `r` without its leading zero is negative
+    let mut negative = valid.clone();
+    negative.remove(4);
+    negative[1] -= 1;
+    negative[3] -= 1;
+    // `s` with a redundant leading zero
+    let padded = vec![0x30, 0x27, 0x02, 0x21]
+        .into_iter()
+        .chain(valid[4..37].iter().copied())
+        .chain([0x02, 0x02, 0x00, 0x05])
+        .collect();
+    let mut wrong_len = valid.clone();
+    wrong_len[1] += 1;
+    let mut wrong_tag = valid.clone();
+    wrong_tag[37] = 0x03;
+
+    let cases = vec![
+        strict(&r, &s),
+        strict(&s, &r),
+        strict(&BigUint::from(0u64), &n),
+        not_strict(negative),
+        not_strict(padded),
+        not_strict(wrong_len),
+        not_strict(wrong_tag),
+        // lengths of 0 and above 33 bytes
+        not_strict(vec![0x30, 0x06, 0x02, 0x00, 0x02, 0x02, 0x01, 0x01]),
+        not_strict(vec![0x30, 0x44, 0x02, 0x40]),
+    ];
+    let circuit = DerCircuit::<Fr> { cases, _marker: std::marker::PhantomData };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
@@ -1,6 +1,7 @@
 #![allow(non_snake_case)]
 use ark_std::{end_timer, start_timer};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::io::Write;
 use std::marker::PhantomData;
 
@@ -24,22 +25,39 @@ use crate::{
 };
 use halo2_base::{
     utils::{biguint_to_fe, fe_to_biguint, modulus},
-    Context, ContextParams,
+    Context, ContextId, ContextParams,
 };
 
-#[derive(Serialize, Deserialize)]
-struct CircuitParams {
-    strategy: FpStrategy,
-    degree: u32,
-    num_advice: usize,
-    num_lookup_advice: usize,
-    num_fixed: usize,
-    lookup_bits: usize,
-    limb_bits: usize,
-    num_limbs: usize,
+const ECDSA_CONFIG_PATH: &str = "./src/secp256k1/configs/ecdsa_circuit.config";
+
+thread_local! {
+    // the params of the last `ECDSACircuit` created on this thread, read by `configure`
+    static ECDSA_PARAMS: RefCell<Option<CircuitParams>> = RefCell::new(None);
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitParams {
+    pub strategy: FpStrategy,
+    pub degree: u32,
+    pub num_advice: usize,
+    pub num_lookup_advice: usize,
+    pub num_fixed: usize,
+    pub lookup_bits: usize,
+    pub limb_bits: usize,
+    pub num_limbs: usize,
+}
+
+impl CircuitParams {
+    /// Reads the params from a JSON config file, e.g. for tests and benchmarks
+    pub fn from_file(path: &str) -> Self {
+        let params_str =
+            std::fs::read_to_string(path).expect(format!("{} file should exist", path).as_str());
+        serde_json::from_str(params_str.as_str()).unwrap()
+    }
 }
 
 pub struct ECDSACircuit<F> {
+    pub params: CircuitParams,
     pub r: Option<Fq>,
     pub s: Option<Fq>,
     pub msghash: Option<Fq>,
@@ -47,9 +65,17 @@ pub struct ECDSACircuit<F> {
     pub G: Secp256k1Affine,
     pub _marker: PhantomData<F>,
 }
-impl<F: FieldExt> Default for ECDSACircuit<F> {
-    fn default() -> Self {
+
+impl<F: FieldExt> ECDSACircuit<F> {
+    /// A circuit with `params` and no witnesses.
+    ///
+    /// halo2 has no circuit parameters at `configure`, so this stores `params` in a thread-local which `configure`
+    /// reads, as `RangeCircuitBuilder::new` does. The circuit must therefore be created on the thread that runs
+    /// keygen, `MockProver::run` or the prover; set the witnesses with struct update syntax.
+    pub fn new(params: CircuitParams) -> Self {
+        ECDSA_PARAMS.with(|cell| *cell.borrow_mut() = Some(params.clone()));
         Self {
+            params,
             r: None,
             s: None,
             msghash: None,
@@ -58,27 +84,15 @@ impl<F: FieldExt> Default for ECDSACircuit<F> {
             _marker: PhantomData,
         }
     }
-}
-
-impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
-    type Config = FpChip<F>;
-    type FloorPlanner = SimpleFloorPlanner;
-
-    fn without_witnesses(&self) -> Self {
-        Self::default()
-    }
-
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let mut folder = std::path::PathBuf::new();
-        folder.push("./src/secp256k1");
-        folder.push("configs/ecdsa_circuit.config");
-        let params_str = std::fs::read_to_string(folder.as_path())
-            .expect("src/secp256k1/configs/ecdsa_circuit.config file should exist");
-        let params: CircuitParams = serde_json::from_str(params_str.as_str()).unwrap();
 
+    /// Configures the chip of the circuit without reading a config file
+    pub fn configure_with_params(
+        meta: &mut ConstraintSystem<F>,
+        params: &CircuitParams,
+    ) -> FpChip<F> {
         FpChip::<F>::configure(
             meta,
-            params.strategy,
+            params.strategy.clone(),
             &[params.num_advice],
             &[params.num_lookup_advice],
             params.num_fixed,
@@ -86,9 +100,33 @@ impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
             params.limb_bits,
             params.num_limbs,
             modulus::<Fp>(),
-            "ecdsa".to_string(),
+            ContextId::new("ecdsa"),
         )
     }
+}
+
+impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
+    type Config = FpChip<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            params: self.params.clone(),
+            r: None,
+            s: None,
+            msghash: None,
+            pk: None,
+            G: self.G,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let params = ECDSA_PARAMS
+            .with(|params| params.borrow().clone())
+            .expect("an ECDSACircuit should be created with `new` on this thread before configure");
+        Self::configure_with_params(meta, &params)
+    }
 
     fn synthesize(
         &self,
@@ -117,7 +155,7 @@ impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
                 let mut aux = Context::new(
                     region,
                     ContextParams {
-                        num_advice: vec![("ecdsa".to_string(), num_advice)],
+                        num_advice: vec![(ContextId::new("ecdsa"), num_advice)],
                     },
                 );
                 let ctx = &mut aux;
@@ -155,7 +193,7 @@ impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
             // IMPORTANT: this assigns all constants to the fixed columns
             // IMPORTANT: this copies cells to the lookup advice column to perform range check lookups
             // This is not optional.
-            let (const_rows, total_fixed, _lookup_rows) = fp_chip.finalize(ctx)?;
+            let stats = fp_chip.finalize_with_stats(ctx)?;
 
             #[cfg(feature = "display")]
             if self.r != None {
@@ -164,39 +202,16 @@ impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
                 let num_lookup_advice = fp_chip.range.lookup_advice.len();
 
                 println!("Using:\nadvice columns: {}\nspecial lookup advice columns: {}\nfixed columns: {}\nlookup bits: {}\nlimb bits: {}\nnum limbs: {}", num_advice, num_lookup_advice, num_fixed, lookup_bits, limb_bits, num_limbs);
-                let advice_rows = ctx.advice_rows["ecdsa"].iter();
-                println!(
-                    "maximum rows used by an advice column: {}",
-                        advice_rows.clone().max().or(Some(&0)).unwrap(),
-                );
-                println!(
-                    "minimum rows used by an advice column: {}",
-                        advice_rows.clone().min().or(Some(&usize::MAX)).unwrap(),
-                );
-
-                let total_cells =
-                    advice_rows.sum::<usize>();
-                println!("total cells used: {}", total_cells);
-                println!(
-                    "cells used in special lookup column: {}",
-                    ctx.cells_to_lookup.len()
-                );
-                println!("maximum rows used by a fixed column: {}", const_rows);
-
-                println!("Suggestions:");
-                let degree = lookup_bits + 1;
-                println!(
-                    "Have you tried using {} advice columns?",
-                    (total_cells + (1 << degree) - 1) / (1 << degree)
-                );
-                println!(
-                    "Have you tried using {} lookup columns?",
-                    (ctx.cells_to_lookup.len() + (1 << degree) - 1) / (1 << degree)
-                );
-                println!(
-                    "Have you tried using {} fixed columns?",
-                    (total_fixed + (1 << degree) - 1) / (1 << degree)
+                #[cfg(feature = "json-stats")]
+                println!("{}", stats.to_json());
+
+                // the fewest columns for the minimal degree, from the shape of this run
+                let suggested = halo2_base::gates::params::CircuitParams::for_degree(
+                    &stats.shape(),
+                    lookup_bits as u32 + 1,
+                    lookup_bits,
                 );
+                println!("Suggested params: {:?}", suggested);
             }
         
         /*
@@ -298,12 +313,7 @@ impl<F: FieldExt> Circuit<F> for ECDSACircuit<F> {
 #[cfg(test)]
 #[test]
 fn test_secp() {
-    let mut folder = std::path::PathBuf::new();
-    folder.push("./src/secp256k1");
-    folder.push("configs/ecdsa_circuit.config");
-    let params_str = std::fs::read_to_string(folder.as_path())
-        .expect("src/secp256k1/configs/ecdsa_circuit.config file should exist");
-    let params: CircuitParams = serde_json::from_str(params_str.as_str()).unwrap();
+    let params = CircuitParams::from_file(ECDSA_CONFIG_PATH);
     let K = params.degree;
 
     // generate random pub key and sign random message
@@ -327,7 +337,7 @@ fn test_secp() {
         msghash: Some(msg_hash),
         pk: Some(pubkey),
         G,
-        _marker: PhantomData,
+        ..ECDSACircuit::new(params)
     };
 
     let prover = MockProver::run(K, &circuit, vec![]).unwrap();
@@ -392,15 +402,6 @@ fn bench_secp() -> Result<(), Box<dyn std::error::Error>> {
             bench_params.degree
         );
 
-        {
-            folder.pop();
-            folder.push("configs/ecdsa_circuit.config");
-            let mut f = std::fs::File::create(folder.as_path())?;
-            write!(f, "{}", serde_json::to_string(&bench_params).unwrap())?;
-            folder.pop();
-            folder.pop();
-            folder.push("data");
-        }
         let params_time = start_timer!(|| "Time elapsed in circuit & params construction");
         let params = {
             params_folder.push(format!("kzg_bn254_{}.params", bench_params.degree));
@@ -419,7 +420,7 @@ fn bench_secp() -> Result<(), Box<dyn std::error::Error>> {
             params
         };
 
-        let circuit = ECDSACircuit::<Fr>::default();
+        let circuit = ECDSACircuit::<Fr>::new(bench_params.clone());
         end_timer!(params_time);
 
         let vk_time = start_timer!(|| "Time elapsed in generating vkey");
@@ -468,7 +469,7 @@ fn bench_secp() -> Result<(), Box<dyn std::error::Error>> {
             msghash: Some(msg_hash),
             pk: Some(pubkey),
             G,
-            _marker: PhantomData,
+            ..ECDSACircuit::new(bench_params.clone())
         };
         let rng = rand::thread_rng();
 
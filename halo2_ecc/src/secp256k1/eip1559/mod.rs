@@ -0,0 +1,351 @@
+// EIP-1559 (type 2) transactions: the RLP encoding of the signing payload, its hash and the recovery of the signer
+//
+// The signature is over `keccak256(0x02 || rlp([chain_id, nonce, max_priority_fee_per_gas, max_fee_per_gas,
+// gas_limit, to, value, data, access_list]))` and the signer is `keccak256(x || y)[12..]` for the public key `(x, y)`
+// recovered from the signature. `verify_transaction` constrains the payload from the assigned fields, its keccak
+// hash, the recovered public key and its address, and outputs the signer with the nonce, recipient and value.
+// The `_native` functions compute the same values out of circuit, for witness generation.
+// Only transactions with an empty access list are supported.
+
+use super::FqOverflowChip;
+use crate::{
+    bigint::{CRTInteger, OverflowInteger},
+    ecc::{
+        ecc_add_unequal, ecdsa_is_in_range, ecdsa_is_low_s,
+        fixed::{fixed_base_scalar_multiply, FixedEccPoint},
+        scalar_multiply, EccChip, EccPoint,
+    },
+    fields::{fp::FpConfig, FieldChip},
+};
+use ff::Field;
+use group::Curve;
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    hashes::keccak::{keccak256, keccak256_native, keccak256_var_len},
+    mpt::rlp::{
+        concat_assigned, encode_bytes, encode_bytes_assigned, encode_list, encode_list_assigned,
+        encode_uint, encode_uint_assigned, AssignedVarBytes,
+    },
+    utils::{biguint_to_fe, fe_to_biguint, modulus},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    plonk::Error,
+};
+use halo2curves::secp256k1::{Fp, Fq, Secp256k1Affine};
+use num_bigint::{BigInt, BigUint};
+use num_traits::One;
+
+#[cfg(test)]
+mod tests;
+
+pub const EIP1559_TX_TYPE: u8 = 0x02;
+
+/// An EIP-1559 transaction with an empty access list
+#[derive(Clone, Debug)]
+pub struct Eip1559Transaction {
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub max_priority_fee_per_gas: BigUint,
+    pub max_fee_per_gas: BigUint,
+    pub gas_limit: u64,
+    pub to: [u8; 20],
+    pub value: BigUint,
+    pub data: Vec<u8>,
+}
+
+impl Eip1559Transaction {
+    /// `0x02 || rlp([chain_id, nonce, max_priority_fee_per_gas, max_fee_per_gas, gas_limit, to, value, data, []])`
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let uint = |x: &BigUint| encode_uint(&x.to_bytes_be());
+        let items = vec![
+            encode_uint(&self.chain_id.to_be_bytes()),
+            encode_uint(&self.nonce.to_be_bytes()),
+            uint(&self.max_priority_fee_per_gas),
+            uint(&self.max_fee_per_gas),
+            encode_uint(&self.gas_limit.to_be_bytes()),
+            encode_bytes(&self.to),
+            uint(&self.value),
+            encode_bytes(&self.data),
+            encode_list(&[]),
+        ];
+        let mut payload = vec![EIP1559_TX_TYPE];
+        payload.extend(encode_list(&items));
+        payload
+    }
+
+    pub fn signing_hash_native(&self) -> [u8; 32] {
+        keccak256_native(&self.signing_payload())
+    }
+}
+
+/// The Ethereum address of a public key: the last 20 bytes of `keccak256(x || y)`
+pub fn address_native(pubkey: &Secp256k1Affine) -> [u8; 20] {
+    let coordinates = pubkey.coordinates().unwrap();
+    let mut bytes = Vec::with_capacity(64);
+    for coordinate in [coordinates.x(), coordinates.y()] {
+        let be_bytes = fe_to_biguint(coordinate).to_bytes_be();
+        bytes.extend(vec![0u8; 32 - be_bytes.len()]);
+        bytes.extend(be_bytes);
+    }
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&keccak256_native(&bytes)[12..]);
+    address
+}
+
+/// Out of circuit ecrecover: the public key `r^{-1} (s R - z G)` where `R` is the point with x-coordinate `r`
+/// and y-coordinate of parity `y_parity`. Returns `None` if there is no such point.
+///
+/// Only handles `R.x = r`, which is the case for all but a negligible fraction of signatures.
+pub fn recover_pubkey_native(
+    msg_hash: &[u8; 32],
+    r: &Fq,
+    s: &Fq,
+    y_parity: bool,
+) -> Option<Secp256k1Affine> {
+    let x: Fp = biguint_to_fe(&fe_to_biguint(r));
+    let y = Option::<Fp>::from((x.square() * x + Fp::from(7)).sqrt())?;
+    let y = if (fe_to_biguint(&y) % 2u64 == BigUint::one()) == y_parity { y } else { -y };
+    let big_r = Option::<Secp256k1Affine>::from(Secp256k1Affine::from_xy(x, y))?;
+
+    let z: Fq = biguint_to_fe(&(BigUint::from_bytes_be(msg_hash) % modulus::<Fq>()));
+    let r_inv = Option::<Fq>::from(r.invert())?;
+    let pubkey = (big_r * *s - Secp256k1Affine::generator() * z) * r_inv;
+    Some(pubkey.to_affine())
+}
+
+/// The fields of an EIP-1559 transaction, each as its big-endian bytes, which are assumed to be in [0, 2^8)
+///
+/// Integers have fixed widths (8 bytes for `chain_id`, `nonce` and `gas_limit`, 32 bytes for fees and `value`)
+/// and are stripped of their leading zeros when encoded. `data` has a constant length other than 1.
+#[derive(Clone, Debug)]
+pub struct AssignedEip1559Transaction<F: FieldExt> {
+    pub chain_id: Vec<AssignedValue<F>>,
+    pub nonce: Vec<AssignedValue<F>>,
+    pub max_priority_fee_per_gas: Vec<AssignedValue<F>>,
+    pub max_fee_per_gas: Vec<AssignedValue<F>>,
+    pub gas_limit: Vec<AssignedValue<F>>,
+    pub to: Vec<AssignedValue<F>>,
+    pub value: Vec<AssignedValue<F>>,
+    pub data: Vec<AssignedValue<F>>,
+}
+
+/// In circuit `Eip1559Transaction::signing_payload`, as a byte string of variable length
+pub fn signing_payload<F: FieldExt>(
+    range: &impl RangeInstructions<F>,
+    ctx: &mut Context<'_, F>,
+    tx: &AssignedEip1559Transaction<F>,
+) -> Result<AssignedVarBytes<F>, Error> {
+    assert_eq!(tx.chain_id.len(), 8);
+    assert_eq!(tx.nonce.len(), 8);
+    assert_eq!(tx.max_priority_fee_per_gas.len(), 32);
+    assert_eq!(tx.max_fee_per_gas.len(), 32);
+    assert_eq!(tx.gas_limit.len(), 8);
+    assert_eq!(tx.to.len(), 20);
+    assert_eq!(tx.value.len(), 32);
+    let gate = range.gate();
+
+    let items = vec![
+        encode_uint_assigned(range, ctx, &tx.chain_id)?,
+        encode_uint_assigned(range, ctx, &tx.nonce)?,
+        encode_uint_assigned(range, ctx, &tx.max_priority_fee_per_gas)?,
+        encode_uint_assigned(range, ctx, &tx.max_fee_per_gas)?,
+        encode_uint_assigned(range, ctx, &tx.gas_limit)?,
+        encode_bytes_assigned(gate, ctx, &tx.to)?,
+        encode_uint_assigned(range, ctx, &tx.value)?,
+        encode_bytes_assigned(gate, ctx, &tx.data)?,
+        AssignedVarBytes::constant(gate, ctx, &encode_list(&[]))?,
+    ];
+    let items = concat_assigned(range, ctx, &items)?;
+    let list = encode_list_assigned(range, ctx, &items)?;
+    let tx_type = AssignedVarBytes::constant(gate, ctx, &[EIP1559_TX_TYPE])?;
+    concat_assigned(range, ctx, &[tx_type, list])
+}
+
+/// Composes the 32 big-endian bytes of a message hash, assumed to be in [0, 2^8), into an integer with the limbs
+/// of `fp_chip`
+pub fn msg_hash_to_integer<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    msg_hash: &[AssignedValue<F>],
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(msg_hash.len(), 32);
+    assert_eq!(fp_chip.limb_bits % 8, 0);
+    assert!(fp_chip.limb_bits * fp_chip.num_limbs >= 256);
+    let gate = fp_chip.range().gate();
+    let bytes_per_limb = fp_chip.limb_bits / 8;
+
+    let le_bytes: Vec<_> = msg_hash.iter().rev().collect();
+    let mut limbs = Vec::with_capacity(fp_chip.num_limbs);
+    for chunk in le_bytes.chunks(bytes_per_limb) {
+        let limb = gate.inner_product(
+            ctx,
+            &chunk.iter().map(|x| Existing(*x)).collect(),
+            &(0..chunk.len())
+                .map(|j| Constant(biguint_to_fe(&(BigUint::one() << (8 * j)))))
+                .collect(),
+        )?;
+        limbs.push(limb);
+    }
+    while limbs.len() < fp_chip.num_limbs {
+        limbs.push(gate.load_zero(ctx)?);
+    }
+    Ok(OverflowInteger::construct(
+        limbs,
+        (BigUint::one() << fp_chip.limb_bits) - 1usize,
+        fp_chip.limb_bits,
+        (BigUint::one() << 256) - 1usize,
+    ))
+}
+
+// the 32 big-endian bytes of a coordinate, which is constrained to be less than p
+// assumes `a` is in proper representation, e.g. the output of `carry_mod`
+fn coordinate_to_bytes<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    a: &CRTInteger<F>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let range = fp_chip.range();
+    fp_chip.enforce_less_than_p(ctx, a)?;
+
+    let mut bits = Vec::with_capacity(fp_chip.limb_bits * fp_chip.num_limbs);
+    for limb in a.truncation.limbs.iter() {
+        bits.extend(range.num_to_bits(ctx, limb, fp_chip.limb_bits)?);
+    }
+    // a < p < 2^256, so any bits past the last byte are 0
+    bits.truncate(256);
+    let zero = range.gate().load_zero(ctx)?;
+    bits.resize(256, zero);
+
+    let mut bytes = Vec::with_capacity(32);
+    for byte_bits in bits.chunks(8).rev() {
+        bytes.push(range.gate().bits_to_num(ctx, byte_bits, false)?);
+    }
+    Ok(bytes)
+}
+
+/// In circuit `address_native`: the last 20 bytes of `keccak256(x || y)`, where the coordinates of `pubkey` are
+/// constrained to be less than p and encoded as 32 big-endian bytes each
+///
+/// Assumes the coordinates of `pubkey` are in proper representation, e.g. the output of `carry_mod`.
+pub fn pubkey_to_address<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    pubkey: &EccPoint<F, CRTInteger<F>>,
+) -> Result<Vec<AssignedValue<F>>, Error> {
+    let mut bytes = coordinate_to_bytes(fp_chip, ctx, &pubkey.x)?;
+    bytes.extend(coordinate_to_bytes(fp_chip, ctx, &pubkey.y)?);
+    let hash = keccak256(fp_chip.range(), ctx, &bytes)?;
+    Ok(hash[12..].to_vec())
+}
+
+/// In circuit `recover_pubkey_native`: constrains the output to be the public key `r^{-1} (s R - z G)`, where `R` is
+/// the point with x-coordinate `r` and y-coordinate of parity `y_parity`, and `z` is `msg_hash` (32 big-endian
+/// bytes, assumed to be in [0, 2^8)) as an integer
+///
+/// Range checks `r` and `s`. The circuit is only satisfiable for signatures that Ethereum accepts for transactions:
+/// `r` and `s` are constrained to be in [1, n - 1], `s <= n / 2` (EIP-2) and `y_parity` to be a bit.
+/// As in `recover_pubkey_native`, only `R.x = r` is handled. The strict addition of `u1 * G` and `u2 * R` also
+/// excludes the negligible cases where the public key is the point at infinity or `2 u1 G`.
+pub fn ecrecover<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    msg_hash: &[AssignedValue<F>],
+    r: &OverflowInteger<F>,
+    s: &OverflowInteger<F>,
+    y_parity: &AssignedValue<F>,
+) -> Result<EccPoint<F, CRTInteger<F>>, Error> {
+    let range = fp_chip.range();
+    let gate = range.gate();
+    let scalar_chip = FqOverflowChip::construct(
+        &fp_chip.range,
+        fp_chip.limb_bits,
+        fp_chip.num_limbs,
+        modulus::<Fq>(),
+    );
+    scalar_chip.range_check(ctx, r)?;
+    scalar_chip.range_check(ctx, s)?;
+    let r_valid = ecdsa_is_in_range::<F, Fq>(range, ctx, r)?;
+    let s_valid = ecdsa_is_in_range::<F, Fq>(range, ctx, s)?;
+    let s_low = ecdsa_is_low_s::<F, Fq>(range, ctx, s)?;
+    for flag in [r_valid, s_valid, s_low] {
+        gate.assert_is_const(ctx, &flag, F::one());
+    }
+
+    // R = (r, y) with y^2 = r^3 + 7 and y = y_parity (mod 2), where r < n < p is a coordinate as is
+    let x = scalar_chip.to_crt(ctx, r)?;
+    let y_val = x.value.as_ref().zip(y_parity.value()).map(|(x, y_parity)| {
+        let x: Fp = biguint_to_fe(&x.to_biguint().unwrap());
+        // if there is no such point the circuit is not satisfiable
+        let y = Option::<Fp>::from((x.square() * x + Fp::from(7)).sqrt()).unwrap_or(Fp::zero());
+        let is_odd = fe_to_biguint(&y) % 2u64 == BigUint::one();
+        BigInt::from(fe_to_biguint(&if is_odd == (*y_parity == F::one()) { y } else { -y }))
+    });
+    let y = fp_chip.load_private(ctx, y_val)?;
+    fp_chip.range_check(ctx, &y)?;
+    fp_chip.enforce_less_than_p(ctx, &y)?;
+    gate.assert_bit(ctx, y_parity)?;
+    let (_, y_low_bit) = range.div_mod(
+        ctx,
+        &Existing(&y.truncation.limbs[0]),
+        &BigUint::from(2u64),
+        fp_chip.limb_bits,
+    )?;
+    gate.assert_equal(ctx, &Existing(&y_low_bit), &Existing(y_parity))?;
+    let big_r = EccPoint::construct(x, y);
+    EccChip::construct(fp_chip).assert_is_on_curve::<Secp256k1Affine>(ctx, &big_r)?;
+
+    // pubkey = u1 * G + u2 * R with u1 = -z / r and u2 = s / r (mod n)
+    // the scalars are not reduced, which does not change the products as G and R have order n
+    let z = msg_hash_to_integer(fp_chip, ctx, msg_hash)?;
+    let u1 = scalar_chip.neg_divide(ctx, &z, r)?;
+    let u2 = scalar_chip.divide(ctx, s, r)?;
+    let generator =
+        FixedEccPoint::from_g1(&Secp256k1Affine::generator(), fp_chip.num_limbs, fp_chip.limb_bits);
+    let u1_g = fixed_base_scalar_multiply(fp_chip, ctx, &generator, &u1.limbs, u1.limb_bits, 4)?;
+    let u2_r = scalar_multiply(fp_chip, ctx, &big_r, &u2.limbs, u2.limb_bits, 4)?;
+    ecc_add_unequal(fp_chip, ctx, &u1_g, &u2_r, true)
+}
+
+/// The outputs of `verify_transaction`, each as its big-endian bytes
+#[derive(Clone, Debug)]
+pub struct VerifiedEip1559Transaction<F: FieldExt> {
+    pub signer: Vec<AssignedValue<F>>,
+    pub nonce: Vec<AssignedValue<F>>,
+    pub to: Vec<AssignedValue<F>>,
+    pub value: Vec<AssignedValue<F>>,
+}
+
+/// Constrains `(r, s, y_parity)` to be a signature of `tx` and outputs its signer with the nonce, recipient and
+/// value of `tx`
+///
+/// The signing payload is `signing_payload` of `tx`, hashed with `keccak256_var_len`, the public key is recovered
+/// with `ecrecover` and the signer is its `pubkey_to_address`. See `ecrecover` for the signatures accepted.
+pub fn verify_transaction<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    tx: &AssignedEip1559Transaction<F>,
+    r: &OverflowInteger<F>,
+    s: &OverflowInteger<F>,
+    y_parity: &AssignedValue<F>,
+) -> Result<VerifiedEip1559Transaction<F>, Error> {
+    let range = fp_chip.range();
+    let payload = signing_payload(range, ctx, tx)?;
+    let msg_hash = keccak256_var_len(range, ctx, &payload.bytes, &payload.len)?;
+    let pubkey = ecrecover(fp_chip, ctx, &msg_hash, r, s, y_parity)?;
+    let signer = pubkey_to_address(fp_chip, ctx, &pubkey)?;
+
+    #[cfg(feature = "display")]
+    {
+        let count = ctx.op_count.entry("eip1559_verify_transaction".to_string()).or_insert(0);
+        *count += 1;
+    }
+    Ok(VerifiedEip1559Transaction {
+        signer,
+        nonce: tx.nonce.clone(),
+        to: tx.to.clone(),
+        value: tx.value.clone(),
+    })
+}
@@ -0,0 +1,301 @@
+use super::*;
+use crate::{fields::fp::FpStrategy, secp256k1::FqOverflowChip};
+use halo2_base::{mpt::rlp, ContextId, ContextParams, QuantumCell::Witness};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use rand_core::OsRng;
+
+const NUM_ADVICE: usize = 16;
+const NUM_LOOKUP_ADVICE: usize = 1;
+const NUM_FIXED: usize = 1;
+const LOOKUP_BITS: usize = 18;
+const LIMB_BITS: usize = 88;
+const NUM_LIMBS: usize = 3;
+
+fn sample_tx() -> Eip1559Transaction {
+    Eip1559Transaction {
+        chain_id: 1,
+        nonce: 0x42,
+        max_priority_fee_per_gas: BigUint::from(1_500_000_000u64),
+        max_fee_per_gas: BigUint::from(30_000_000_000u64),
+        gas_limit: 21000,
+        to: [0x35u8; 20],
+        value: BigUint::from(10u64).pow(18),
+        data: vec![0xa9, 0x05, 0x9c, 0xbb],
+    }
+}
+
+// returns the signature `(r, s, y_parity)` of `msg_hash` by `sk`
+fn sign(sk: Fq, msg_hash: &[u8; 32]) -> (Fq, Fq, bool) {
+    let k = Fq::random(OsRng);
+    let big_r = (Secp256k1Affine::generator() * k).to_affine();
+    let coordinates = big_r.coordinates().unwrap();
+    let r: Fq = biguint_to_fe(&fe_to_biguint(coordinates.x()));
+    let z: Fq = biguint_to_fe(&BigUint::from_bytes_be(msg_hash));
+    let s = k.invert().unwrap() * (z + r * sk);
+    (r, s, fe_to_biguint(coordinates.y()) % 2u64 == BigUint::one())
+}
+
+fn be_bytes(x: &BigUint, width: usize) -> Vec<u8> {
+    let bytes = x.to_bytes_be();
+    let mut out = vec![0u8; width - bytes.len()];
+    out.extend(bytes);
+    out
+}
+
+#[test]
+fn test_signing_payload_native() {
+    let tx = sample_tx();
+    let payload = tx.signing_payload();
+    assert_eq!(payload[0], EIP1559_TX_TYPE);
+    let items = rlp::decode(&payload[1..]).unwrap();
+    let items = items.as_list().unwrap();
+    assert_eq!(items.len(), 9);
+    assert_eq!(items[0].as_bytes().unwrap(), &[0x01]);
+    assert_eq!(items[1].as_bytes().unwrap(), &[0x42]);
+    assert_eq!(items[4].as_bytes().unwrap(), &[0x52, 0x08]);
+    assert_eq!(items[5].as_bytes().unwrap(), &tx.to);
+    assert_eq!(items[6].as_bytes().unwrap(), &tx.value.to_bytes_be()[..]);
+    assert_eq!(items[7].as_bytes().unwrap(), &tx.data[..]);
+    assert!(items[8].as_list().unwrap().is_empty());
+}
+
+#[test]
+fn test_recover_pubkey_native() {
+    let sk = Fq::random(OsRng);
+    let pubkey = (Secp256k1Affine::generator() * sk).to_affine();
+    let msg_hash = sample_tx().signing_hash_native();
+    let (r, s, y_parity) = sign(sk, &msg_hash);
+
+    assert_eq!(recover_pubkey_native(&msg_hash, &r, &s, y_parity), Some(pubkey));
+    assert_ne!(recover_pubkey_native(&msg_hash, &r, &s, !y_parity), Some(pubkey));
+    assert_eq!(address_native(&pubkey).len(), 20);
+}
+
+#[test]
+fn test_keccak_native() {
+    // the native keccak agrees with the `sha3` based hash, and the well-known address of the secret key 1
+    assert_eq!(
+        sample_tx().signing_hash_native(),
+        halo2_base::mpt::keccak256(&sample_tx().signing_payload())
+    );
+    let address = address_native(&Secp256k1Affine::generator());
+    assert_eq!(
+        address.to_vec(),
+        vec![
+            0x7e, 0x5f, 0x45, 0x52, 0x09, 0x1a, 0x69, 0x12, 0x5d, 0x5d, 0xfc, 0xb7, 0xb8, 0xc2,
+            0x65, 0x90, 0x29, 0x39, 0x5b, 0xdf
+        ]
+    );
+}
+
+struct Eip1559Circuit<F> {
+    tx: Option<Eip1559Transaction>,
+    r: Option<Fq>,
+    s: Option<Fq>,
+    y_parity: Option<bool>,
+    data_len: usize,
+    expected_payload: Vec<u8>,
+    expected_signer: [u8; 20],
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for Eip1559Circuit<F> {
+    type Config = FpConfig<F, Fp>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            tx: None,
+            r: None,
+            s: None,
+            y_parity: None,
+            data_len: self.data_len,
+            expected_payload: self.expected_payload.clone(),
+            expected_signer: self.expected_signer,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[NUM_LOOKUP_ADVICE],
+            NUM_FIXED,
+            LOOKUP_BITS,
+            LIMB_BITS,
+            NUM_LIMBS,
+            modulus::<Fp>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        fp_chip: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        fp_chip.range.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "eip1559",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+                let gate = fp_chip.range.gate();
+
+                // the transaction fields followed by the y-parity of the signature
+                let widths = [8, 8, 32, 32, 8, 20, 32, self.data_len, 1];
+                let bytes: Vec<Value<F>> = match &self.tx {
+                    Some(tx) => [
+                        be_bytes(&BigUint::from(tx.chain_id), 8),
+                        be_bytes(&BigUint::from(tx.nonce), 8),
+                        be_bytes(&tx.max_priority_fee_per_gas, 32),
+                        be_bytes(&tx.max_fee_per_gas, 32),
+                        be_bytes(&BigUint::from(tx.gas_limit), 8),
+                        tx.to.to_vec(),
+                        be_bytes(&tx.value, 32),
+                        tx.data.clone(),
+                        vec![self.y_parity.unwrap() as u8],
+                    ]
+                    .concat()
+                    .into_iter()
+                    .map(|b| Value::known(F::from(b as u64)))
+                    .collect(),
+                    None => vec![Value::unknown(); widths.iter().sum()],
+                };
+                let mut assigned = gate.assign_region_smart(
+                    ctx,
+                    bytes.into_iter().map(Witness).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let mut fields = Vec::with_capacity(widths.len());
+                for width in widths.iter().rev() {
+                    fields.push(assigned.split_off(assigned.len() - width));
+                }
+                let mut fields = fields.into_iter().rev();
+                let mut next = || fields.next().unwrap();
+                let tx = AssignedEip1559Transaction {
+                    chain_id: next(),
+                    nonce: next(),
+                    max_priority_fee_per_gas: next(),
+                    max_fee_per_gas: next(),
+                    gas_limit: next(),
+                    to: next(),
+                    value: next(),
+                    data: next(),
+                };
+                let y_parity = next().pop().unwrap();
+
+                let payload = signing_payload(&fp_chip.range, ctx, &tx)?;
+                gate.assert_is_const(
+                    ctx,
+                    &payload.len,
+                    F::from(self.expected_payload.len() as u64),
+                );
+                for (i, byte) in payload.bytes.iter().enumerate() {
+                    let expected = self.expected_payload.get(i).copied().unwrap_or(0);
+                    gate.assert_is_const(ctx, byte, F::from(expected as u64));
+                }
+
+                let fq_chip = FqOverflowChip::construct(
+                    &fp_chip.range,
+                    LIMB_BITS,
+                    NUM_LIMBS,
+                    modulus::<Fq>(),
+                );
+                let to_value = |x: Option<Fq>| x.map_or(Value::unknown(), Value::known);
+                let r = fq_chip
+                    .load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&to_value(self.r)))?;
+                let s = fq_chip
+                    .load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&to_value(self.s)))?;
+                let verified = verify_transaction(&fp_chip, ctx, &tx, &r, &s, &y_parity)?;
+                for (byte, expected) in verified.signer.iter().zip(self.expected_signer.iter()) {
+                    gate.assert_is_const(ctx, byte, F::from(*expected as u64));
+                }
+                assert_eq!(verified.nonce.len(), 8);
+                assert_eq!(verified.to.len(), 20);
+                assert_eq!(verified.value.len(), 32);
+
+                fp_chip.finalize(ctx)?;
+                println!(
+                    "maximum rows used by an advice column: {}",
+                    ctx.advice_rows[&ContextId::new("default")].iter().max().unwrap()
+                );
+                Ok(())
+            },
+        )
+    }
+}
+
+// proves that `tx` is signed by `signer` with a signature of `tx_signed`, and checks the signer against the address
+// of `expected_signer`
+fn run_eip1559(
+    tx: Eip1559Transaction,
+    tx_signed: &Eip1559Transaction,
+    signer: Fq,
+    expected_signer: Fq,
+    flip_parity: bool,
+) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    let k = 19;
+    let (r, s, y_parity) = sign(signer, &tx_signed.signing_hash_native());
+    let circuit = Eip1559Circuit::<Fr> {
+        data_len: tx.data.len(),
+        expected_payload: tx.signing_payload(),
+        tx: Some(tx),
+        r: Some(r),
+        s: Some(s),
+        y_parity: Some(y_parity ^ flip_parity),
+        expected_signer: address_native(
+            &(Secp256k1Affine::generator() * expected_signer).to_affine(),
+        ),
+        _marker: std::marker::PhantomData,
+    };
+    MockProver::run(k, &circuit, vec![]).unwrap().verify()
+}
+
+#[test]
+fn test_eip1559_transaction() {
+    let sk = Fq::random(OsRng);
+    assert_eq!(run_eip1559(sample_tx(), &sample_tx(), sk, sk, false), Ok(()));
+}
+
+#[test]
+fn test_eip1559_wrong_signer() {
+    let (sk, other) = (Fq::random(OsRng), Fq::random(OsRng));
+    assert!(run_eip1559(sample_tx(), &sample_tx(), sk, other, false).is_err());
+}
+
+#[test]
+fn test_eip1559_wrong_parity() {
+    // the other point with x-coordinate r recovers a different public key
+    let sk = Fq::random(OsRng);
+    assert!(run_eip1559(sample_tx(), &sample_tx(), sk, sk, true).is_err());
+}
+
+#[test]
+fn test_eip1559_tampered_transaction() {
+    let sk = Fq::random(OsRng);
+    let mut tampered = sample_tx();
+    tampered.value += 1u64;
+    assert!(run_eip1559(tampered, &sample_tx(), sk, sk, false).is_err());
+}
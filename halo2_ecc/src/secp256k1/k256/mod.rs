@@ -0,0 +1,100 @@
+// Ingestion of `k256` ECDSA signatures, behind the `k256` feature
+//
+// Converts a `k256::ecdsa::Signature`, `VerifyingKey` and message prehash into the halo2curves values taken by
+// `ECDSACircuit`, and loads them as the limbed witnesses taken by `ecdsa_verify_no_pubkey_check`: the signature and
+// message hash with `FqOverflowChip` and the public key with `EccChip`, all with the limbs of the base field chip.
+// The message hash is read from the prehash as `k256` does, so a signature which `k256` verifies also verifies in
+// the circuit.
+
+use super::FqOverflowChip;
+use crate::{
+    bigint::{CRTInteger, OverflowInteger},
+    ecc::{EccChip, EccPoint},
+    fields::{fp::FpConfig, FieldChip},
+};
+use ::k256::ecdsa::{Signature, VerifyingKey};
+use halo2_base::{
+    utils::{biguint_to_fe, modulus},
+    Context,
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Value,
+    plonk::Error,
+};
+use halo2curves::secp256k1::{Fp, Fq, Secp256k1Affine};
+use num_bigint::BigUint;
+
+// the length of a field element in bytes
+const FIELD_BYTES: usize = 32;
+
+/// The `(r, s)` of `sig`
+pub fn signature_to_fe(sig: &Signature) -> (Fq, Fq) {
+    let (r, s) = sig.split_bytes();
+    (biguint_to_fe(&BigUint::from_bytes_be(&r)), biguint_to_fe(&BigUint::from_bytes_be(&s)))
+}
+
+/// The public key of `vk`
+pub fn verifying_key_to_affine(vk: &VerifyingKey) -> Secp256k1Affine {
+    let point = vk.to_encoded_point(false);
+    let x: Fp = biguint_to_fe(&BigUint::from_bytes_be(point.x().unwrap()));
+    let y: Fp = biguint_to_fe(&BigUint::from_bytes_be(point.y().unwrap()));
+    Secp256k1Affine::from_xy(x, y).unwrap()
+}
+
+/// The message hash of `prehash` as `VerifyingKey::verify_prehash` reads it: its first 32 bytes, or all of them
+/// left padded with zeros if shorter, reduced modulo the order of the curve. Panics if `prehash` is shorter than
+/// 16 bytes, which `k256` rejects.
+pub fn prehash_to_fe(prehash: &[u8]) -> Fq {
+    assert!(prehash.len() >= FIELD_BYTES / 2, "prehash too short");
+    let bytes = &prehash[..prehash.len().min(FIELD_BYTES)];
+    biguint_to_fe(&(BigUint::from_bytes_be(bytes) % modulus::<Fq>()))
+}
+
+// the scalar field chip with the limbs of `fp_chip`, as `ECDSACircuit` constructs it
+fn scalar_chip<F: FieldExt>(fp_chip: &FpConfig<F, Fp>) -> FqOverflowChip<'_, F> {
+    FqOverflowChip::construct(
+        fp_chip.range(),
+        fp_chip.limb_bits,
+        fp_chip.num_limbs,
+        modulus::<Fq>(),
+    )
+}
+
+/// Loads the `(r, s)` of `sig` for `ecdsa_verify_no_pubkey_check`
+pub fn load_signature<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    sig: Value<&Signature>,
+) -> Result<(OverflowInteger<F>, OverflowInteger<F>), Error> {
+    let scalar_chip = scalar_chip(fp_chip);
+    let rs = sig.map(signature_to_fe);
+    let r = scalar_chip
+        .load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&rs.as_ref().map(|rs| rs.0)))?;
+    let s = scalar_chip
+        .load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&rs.as_ref().map(|rs| rs.1)))?;
+    Ok((r, s))
+}
+
+/// Loads the message hash of `prehash` for `ecdsa_verify_no_pubkey_check`, see `prehash_to_fe`
+pub fn load_prehash<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    prehash: Value<&[u8]>,
+) -> Result<OverflowInteger<F>, Error> {
+    let msg_hash = prehash.map(prehash_to_fe);
+    scalar_chip(fp_chip).load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&msg_hash))
+}
+
+/// Loads the public key of `vk` for `ecdsa_verify_no_pubkey_check`. `vk` is a valid point, but as the value is
+/// only known to the prover, the caller should still constrain the loaded point to be on the curve.
+pub fn load_verifying_key<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    vk: Value<&VerifyingKey>,
+) -> Result<EccPoint<F, CRTInteger<F>>, Error> {
+    EccChip::construct(fp_chip).assign_point(ctx, vk.map(verifying_key_to_affine))
+}
+
+#[cfg(test)]
+mod tests;
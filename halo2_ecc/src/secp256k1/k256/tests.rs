@@ -0,0 +1,144 @@
+use super::*;
+use crate::{ecc::ecdsa_verify_no_pubkey_check, fields::fp::FpStrategy};
+use ::k256::ecdsa::{
+    signature::hazmat::{PrehashSigner, PrehashVerifier},
+    SigningKey,
+};
+use ff::Field;
+use group::Curve;
+use halo2_base::{utils::fe_to_biguint, ContextId, ContextParams};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use rand_core::OsRng;
+
+const K: u32 = 18;
+const NUM_ADVICE: usize = 2;
+const NUM_LOOKUP_ADVICE: usize = 1;
+const NUM_FIXED: usize = 1;
+const LOOKUP_BITS: usize = 17;
+const LIMB_BITS: usize = 88;
+const NUM_LIMBS: usize = 3;
+
+fn sign(prehash: &[u8]) -> (VerifyingKey, Signature) {
+    let sk = SigningKey::random(&mut OsRng);
+    let sig: Signature = sk.sign_prehash(prehash).unwrap();
+    let vk = *sk.verifying_key();
+    vk.verify_prehash(prehash, &sig).unwrap();
+    (vk, sig)
+}
+
+#[test]
+fn test_k256_conversions() {
+    // hashes of the length of the field, and shorter and longer ones
+    for len in [32, 20, 64] {
+        let prehash: Vec<u8> = (0..len).map(|i| (7 * i + 0xc3) as u8).collect();
+        let (vk, sig) = sign(&prehash);
+        let (r, s) = signature_to_fe(&sig);
+        let pubkey = verifying_key_to_affine(&vk);
+        let msg_hash = prehash_to_fe(&prehash);
+
+        // `r` is the x-coordinate of `s^{-1} (z G + r P)`
+        let s_inv = s.invert().unwrap();
+        let big_r =
+            (Secp256k1Affine::generator() * (msg_hash * s_inv) + pubkey * (r * s_inv)).to_affine();
+        let x = fe_to_biguint(big_r.coordinates().unwrap().x()) % modulus::<Fq>();
+        assert_eq!(biguint_to_fe::<Fq>(&x), r);
+    }
+}
+
+#[derive(Default)]
+struct K256Circuit<F> {
+    prehash: Option<Vec<u8>>,
+    vk: Option<VerifyingKey>,
+    sig: Option<Signature>,
+    expected: bool,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for K256Circuit<F> {
+    type Config = FpConfig<F, Fp>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { expected: self.expected, ..Default::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[NUM_LOOKUP_ADVICE],
+            NUM_FIXED,
+            LOOKUP_BITS,
+            LIMB_BITS,
+            NUM_LIMBS,
+            modulus::<Fp>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        fp_chip: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        fp_chip.range.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "k256",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let prehash =
+                    self.prehash.as_ref().map_or(Value::unknown(), |h| Value::known(&h[..]));
+                let vk = self.vk.as_ref().map_or(Value::unknown(), Value::known);
+                let sig = self.sig.as_ref().map_or(Value::unknown(), Value::known);
+                let msg_hash = load_prehash(&fp_chip, ctx, prehash)?;
+                let pubkey = load_verifying_key(&fp_chip, ctx, vk)?;
+                let (r, s) = load_signature(&fp_chip, ctx, sig)?;
+                let is_valid = ecdsa_verify_no_pubkey_check::<F, Fp, Fq, Secp256k1Affine>(
+                    &fp_chip, ctx, &pubkey, &r, &s, &msg_hash, 4, 4,
+                )?;
+                if self.sig.is_some() {
+                    is_valid.value().map(|v| assert_eq!(*v, F::from(self.expected)));
+                }
+
+                fp_chip.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_k256_verify() {
+    let prehash = vec![0x5au8; 32];
+    let (vk, sig) = sign(&prehash);
+    let mut wrong_prehash = prehash.clone();
+    wrong_prehash[31] ^= 1;
+    for (prehash, expected) in [(prehash, true), (wrong_prehash, false)] {
+        let circuit = K256Circuit::<Fr> {
+            prehash: Some(prehash),
+            vk: Some(vk),
+            sig: Some(sig),
+            expected,
+            _marker: std::marker::PhantomData,
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,93 @@
+// ECDSA over secp256k1 of a message hashed in the circuit
+//
+// `verify_signed_message` hashes the message bytes with the SHA-256 chip, converts the digest into the scalar
+// `z = int(digest) (mod n)` and verifies the signature of `z`. The digest has as many bits as n, so ECDSA takes all
+// of it as a big-endian integer, which may be at least n and is reduced with `reduce_once`. Ethereum signs
+// keccak-256 digests instead, which needs a keccak chip that `halo2_base::hashes` does not provide yet.
+
+use super::{Fp, FqOverflowChip};
+use crate::{
+    bigint::{bytes::from_bytes_le, CRTInteger, OverflowInteger},
+    ecc::{ecdsa_verify_reduced_no_pubkey_check, reduce_once, EccPoint},
+    fields::{fp::FpConfig, FieldChip},
+};
+use halo2_base::{
+    hashes::sha256::{sha256, sha256_native},
+    utils::{biguint_to_fe, modulus},
+    AssignedValue, Context,
+};
+use halo2_proofs::{arithmetic::FieldExt, plonk::Error};
+use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+use num_bigint::BigUint;
+
+/// Out of circuit scalar of a 32 byte digest: the digest as a big-endian integer, reduced mod n
+pub fn digest_to_scalar_native(digest: &[u8]) -> Fq {
+    assert_eq!(digest.len(), 32);
+    biguint_to_fe(&(BigUint::from_bytes_be(digest) % modulus::<Fq>()))
+}
+
+/// Out of circuit scalar of the SHA-256 digest of `msg`, the message hash signed by `verify_signed_message`
+pub fn message_hash_native(msg: &[u8]) -> Fq {
+    digest_to_scalar_native(&sha256_native(msg))
+}
+
+/// Input: the 32 bytes of a digest. Each byte is range checked to be in [0, 2^8).
+///
+/// Output: the digest as a big-endian integer, reduced mod n, in proper representation with the limbs of `fp_chip`,
+/// whose `limb_bits` must be a multiple of 8
+pub fn digest_to_scalar<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    digest: &[AssignedValue<F>],
+) -> Result<OverflowInteger<F>, Error> {
+    assert_eq!(digest.len(), 32);
+    let le_bytes: Vec<_> = digest.iter().rev().cloned().collect();
+    let z = from_bytes_le(
+        fp_chip.range(),
+        &fp_chip.bigint_chip,
+        ctx,
+        &le_bytes,
+        fp_chip.limb_bits,
+        fp_chip.num_limbs,
+    )?;
+    let scalar_chip = FqOverflowChip::construct(
+        fp_chip.range(),
+        fp_chip.limb_bits,
+        fp_chip.num_limbs,
+        modulus::<Fq>(),
+    );
+    // 2^256 < 2n, so a single subtraction of n reduces the digest
+    reduce_once(&scalar_chip, ctx, &z.truncation)
+}
+
+/// Returns whether `(r, s)` is a valid signature of the SHA-256 digest of `msg` for `pubkey`.
+///
+/// The bytes of `msg` are range checked. `r` and `s` are in proper representation, e.g. loaded by
+/// `FqOverflowChip::load_private` or `parse_der_signature`, and `pubkey` is assumed to be on the curve as in
+/// `ecdsa_verify_no_pubkey_check`.
+pub fn verify_signed_message<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    msg: &[AssignedValue<F>],
+    r: &OverflowInteger<F>,
+    s: &OverflowInteger<F>,
+    pubkey: &EccPoint<F, CRTInteger<F>>,
+    var_window_bits: usize,
+    fixed_window_bits: usize,
+) -> Result<AssignedValue<F>, Error> {
+    let digest = sha256(fp_chip.range(), ctx, msg)?;
+    let msg_hash = digest_to_scalar(fp_chip, ctx, &digest)?;
+    ecdsa_verify_reduced_no_pubkey_check::<F, Fp, Fq, Secp256k1Affine>(
+        fp_chip,
+        ctx,
+        pubkey,
+        r,
+        s,
+        &msg_hash,
+        var_window_bits,
+        fixed_window_bits,
+    )
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,155 @@
+use super::*;
+use crate::{ecc::EccChip, fields::fp::FpStrategy};
+use ff::Field;
+use halo2_base::{
+    gates::GateInstructions,
+    utils::{fe_to_biguint, value_to_option},
+    ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use rand_core::OsRng;
+
+const K: u32 = 18;
+const NUM_ADVICE: usize = 4;
+
+// signs `msg_hash` with a random key, returning the public key and `(r, s)`
+fn sign(msg_hash: Fq) -> (Secp256k1Affine, Fq, Fq) {
+    let g = Secp256k1Affine::generator();
+    let sk = Fq::random(OsRng);
+    let pubkey = Secp256k1Affine::from(g * sk);
+    let k = Fq::random(OsRng);
+    let x = *Secp256k1Affine::from(g * k).coordinates().unwrap().x();
+    let r = biguint_to_fe::<Fq>(&(fe_to_biguint(&x) % modulus::<Fq>()));
+    let s = k.invert().unwrap() * (msg_hash + r * sk);
+    (pubkey, r, s)
+}
+
+#[derive(Clone, Default)]
+struct SignedMessageCircuit<F> {
+    msg: Vec<u8>,
+    pubkey: Option<Secp256k1Affine>,
+    r: Fq,
+    s: Fq,
+    expected: bool,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SignedMessageCircuit<F> {
+    type Config = FpConfig<F, Fp>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            17,
+            88,
+            3,
+            modulus::<Fp>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        fp_chip: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        fp_chip.range.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "signed message",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let msg = fp_chip.range.gate().assign_region_smart(
+                    ctx,
+                    self.msg.iter().map(|b| Witness(Value::known(F::from(*b as u64)))).collect(),
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let scalar_chip = FqOverflowChip::construct(
+                    &fp_chip.range,
+                    fp_chip.limb_bits,
+                    fp_chip.num_limbs,
+                    modulus::<Fq>(),
+                );
+                let r = scalar_chip
+                    .load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&Value::known(self.r)))?;
+                let s = scalar_chip
+                    .load_private(ctx, FqOverflowChip::<F>::fe_to_witness(&Value::known(self.s)))?;
+                let pubkey = EccChip::construct(&fp_chip)
+                    .assign_point(ctx, self.pubkey.map_or(Value::unknown(), Value::known))?;
+
+                let digest = sha256(&fp_chip.range, ctx, &msg)?;
+                let msg_hash = digest_to_scalar(&fp_chip, ctx, &digest)?;
+                let expected_hash = fe_to_biguint(&message_hash_native(&self.msg));
+                assert_eq!(
+                    value_to_option(msg_hash.to_bigint()).unwrap().to_biguint(),
+                    Some(expected_hash)
+                );
+
+                let is_valid = verify_signed_message(&fp_chip, ctx, &msg, &r, &s, &pubkey, 4, 4)?;
+                assert_eq!(
+                    value_to_option(is_valid.value().copied()),
+                    Some(F::from(self.expected))
+                );
+
+                fp_chip.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_digest_to_scalar_native() {
+    // a digest of all ones exceeds n and is reduced
+    let digest = [0xffu8; 32];
+    let expected = BigUint::from_bytes_be(&digest) - modulus::<Fq>();
+    assert_eq!(fe_to_biguint(&digest_to_scalar_native(&digest)), expected);
+}
+
+#[test]
+fn test_verify_signed_message() {
+    let msg = b"halo2-lib signed message".to_vec();
+    let (pubkey, r, s) = sign(message_hash_native(&msg));
+    let mut wrong_msg = msg.clone();
+    wrong_msg[0] ^= 1;
+    for (msg, expected) in [(msg, true), (wrong_msg, false)] {
+        let circuit = SignedMessageCircuit::<Fr> {
+            msg,
+            pubkey: Some(pubkey),
+            r,
+            s,
+            expected,
+            _marker: std::marker::PhantomData,
+        };
+        let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
@@ -13,4 +13,10 @@ type Secp256k1Chip<'a, F> = ecc::EccChip<'a, F, FpChip<F>>;
 const SECP_B: u64 = 7;
 
 // #[cfg(test)]
+pub mod der;
 pub mod ecdsa;
+pub mod eip1559;
+#[cfg(feature = "k256")]
+pub mod k256;
+pub mod message;
+pub mod schnorr;
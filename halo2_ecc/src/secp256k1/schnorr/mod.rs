@@ -0,0 +1,304 @@
+// Batch verification of BIP-340 Schnorr signatures
+//
+// A signature `(r, s)` of the 32 byte message `m` under the public key `P = lift_x(pk)` is valid iff `r < p`,
+// `s < n` and `s G = R + e P`, where `R = lift_x(r)` and `e = int(hash_BIP0340/challenge(r || pk || m)) (mod n)`,
+// and `lift_x` returns the point with even y. `schnorr_batch_verify` checks all signatures of a batch at once with
+// `(sum_i a_i s_i) G = sum_i a_i R_i + sum_i (a_i e_i) P_i`, as in BIP-340, with `a_0 = 1` and `a_i` for `i > 0`
+// 128 bit challenges of a Poseidon transcript of all signatures, so the variable base points share a single MSM and
+// `G` a single fixed base multiplication. The prover provides the lifted points, which are constrained to be on the
+// curve with even y and the given x-coordinates.
+
+use super::{message::digest_to_scalar, Fp, FqOverflowChip, SECP_B};
+use crate::{
+    bigint::{big_is_equal, big_less_than, bytes::from_bytes_le, CRTInteger, OverflowInteger},
+    ecc::{
+        fixed::{fixed_base_scalar_multiply, FixedEccPoint},
+        is_on_curve, multi_scalar_multiply, EccChip, EccPoint,
+    },
+    fields::{fp::FpConfig, FieldChip},
+    transcript::TranscriptChip,
+};
+use ff::Field;
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    hashes::sha256::{sha256, sha256_native},
+    utils::{biguint_to_fe, fe_to_biguint, modulus},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Value,
+    plonk::Error,
+};
+use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+use num_bigint::{BigInt, BigUint};
+use num_traits::One;
+
+// the tag of the challenge hash
+pub const CHALLENGE_TAG: &[u8] = b"BIP0340/challenge";
+// the bits of the random coefficients `a_i`
+const BATCH_COEFF_BITS: usize = 128;
+// Poseidon parameters of the transcript deriving the coefficients
+const T: usize = 3;
+const RATE: usize = 2;
+const R_F: usize = 8;
+const R_P: usize = 57;
+
+/// A BIP-340 signature with its public key and message
+#[derive(Clone, Debug)]
+pub struct SchnorrSignature {
+    pub pubkey_x: [u8; 32],
+    pub msg: [u8; 32],
+    // `r || s`
+    pub sig: [u8; 64],
+}
+
+/// `SchnorrSignature` as assigned big-endian bytes, and the points `lift_x(pk)` and `lift_x(r)` computed by the prover
+#[derive(Clone, Debug)]
+pub struct AssignedSchnorrSignature<F: FieldExt> {
+    pub pubkey_x: Vec<AssignedValue<F>>,
+    pub msg: Vec<AssignedValue<F>>,
+    pub sig: Vec<AssignedValue<F>>,
+    pub pubkey: EccPoint<F, CRTInteger<F>>,
+    pub r_point: EccPoint<F, CRTInteger<F>>,
+}
+
+/// `SHA256(SHA256(tag) || SHA256(tag) || msg)`
+pub fn tagged_hash_native(tag: &[u8], msg: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256_native(tag);
+    sha256_native(&[&tag_hash[..], &tag_hash[..], msg].concat())
+}
+
+/// The point with x-coordinate `x` and even y, if `x < p` and there is one
+pub fn lift_x_native(x: &[u8]) -> Option<Secp256k1Affine> {
+    let x = BigUint::from_bytes_be(x);
+    if x >= modulus::<Fp>() {
+        return None;
+    }
+    let x = biguint_to_fe::<Fp>(&x);
+    let y = Option::<Fp>::from((x.square() * x + Fp::from(SECP_B)).sqrt())?;
+    let y = if fe_to_biguint(&y).bit(0) { -y } else { y };
+    Option::from(Secp256k1Affine::from_xy(x, y))
+}
+
+/// The challenge `e` of a signature
+pub fn challenge_native(sig: &SchnorrSignature) -> Fq {
+    let input = [&sig.sig[..32], &sig.pubkey_x[..], &sig.msg[..]].concat();
+    let digest = tagged_hash_native(CHALLENGE_TAG, &input);
+    biguint_to_fe(&(BigUint::from_bytes_be(&digest) % modulus::<Fq>()))
+}
+
+/// Out of circuit BIP-340 verification of a single signature
+pub fn schnorr_verify_native(sig: &SchnorrSignature) -> bool {
+    let (pubkey, r_point) = match (lift_x_native(&sig.pubkey_x), lift_x_native(&sig.sig[..32])) {
+        (Some(pubkey), Some(r_point)) => (pubkey, r_point),
+        _ => return false,
+    };
+    let s = BigUint::from_bytes_be(&sig.sig[32..]);
+    if s >= modulus::<Fq>() {
+        return false;
+    }
+    let s = biguint_to_fe::<Fq>(&s);
+    let e = challenge_native(sig);
+    Secp256k1Affine::from(Secp256k1Affine::generator() * s - pubkey * e) == r_point
+}
+
+/// Assigns the bytes of `sig` and the lifted points. If a point cannot be lifted, the generator is assigned instead,
+/// which `schnorr_batch_verify` rejects as its x-coordinate does not match.
+pub fn load_schnorr_signature<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    sig: Value<&SchnorrSignature>,
+) -> Result<AssignedSchnorrSignature<F>, Error> {
+    let gate = fp_chip.range().gate();
+    let load_bytes = |ctx: &mut Context<'_, F>, bytes: Vec<Value<u8>>| {
+        gate.assign_region_smart(
+            ctx,
+            bytes.into_iter().map(|b| Witness(b.map(|b| F::from(b as u64)))).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )
+    };
+    let pubkey_x = load_bytes(ctx, (0..32).map(|i| sig.map(|sig| sig.pubkey_x[i])).collect())?;
+    let msg = load_bytes(ctx, (0..32).map(|i| sig.map(|sig| sig.msg[i])).collect())?;
+    let sig_bytes = load_bytes(ctx, (0..64).map(|i| sig.map(|sig| sig.sig[i])).collect())?;
+
+    let lift = |x: &[u8]| lift_x_native(x).unwrap_or_else(Secp256k1Affine::generator);
+    let chip = EccChip::construct(fp_chip);
+    let pubkey = chip.assign_point(ctx, sig.map(|sig| lift(&sig.pubkey_x)))?;
+    let r_point = chip.assign_point(ctx, sig.map(|sig| lift(&sig.sig[..32])))?;
+    Ok(AssignedSchnorrSignature { pubkey_x, msg, sig: sig_bytes, pubkey, r_point })
+}
+
+// the 32 big-endian `bytes` as an integer with the limbs of `fp_chip`
+fn be_bytes_to_integer<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    bytes: &[AssignedValue<F>],
+) -> Result<CRTInteger<F>, Error> {
+    let le_bytes: Vec<_> = bytes.iter().rev().cloned().collect();
+    from_bytes_le(
+        fp_chip.range(),
+        &fp_chip.bigint_chip,
+        ctx,
+        &le_bytes,
+        fp_chip.limb_bits,
+        fp_chip.num_limbs,
+    )
+}
+
+// returns whether `point` is `lift_x(x)`, and constrains it to be on the curve
+fn is_lift_x<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    x: &CRTInteger<F>,
+    point: &EccPoint<F, CRTInteger<F>>,
+) -> Result<AssignedValue<F>, Error> {
+    let range = fp_chip.range();
+    is_on_curve(fp_chip, ctx, point, F::from(SECP_B))?;
+    // the parity of y is that of its canonical representative
+    fp_chip.enforce_less_than_p(ctx, &point.y)?;
+    let (_, y_parity) = range.div_mod(
+        ctx,
+        &Existing(&point.y.truncation.limbs[0]),
+        &BigUint::from(2u64),
+        fp_chip.limb_bits,
+    )?;
+    let y_is_even = range.is_zero(ctx, &y_parity)?;
+    let x_is_reduced = big_less_than::assign_constant(range, ctx, &x.truncation, &fp_chip.p)?;
+    let x_eq = big_is_equal::assign(range, ctx, &x.truncation, &point.x.truncation)?;
+    let out = range.gate().and(ctx, &Existing(&y_is_even), &Existing(&x_is_reduced))?;
+    range.gate().and(ctx, &Existing(&out), &Existing(&x_eq))
+}
+
+/// Returns whether all signatures of `sigs` are valid BIP-340 signatures, see the module comment.
+///
+/// The bytes of the signatures are range checked. `fp_chip.limb_bits` must be a multiple of 8.
+pub fn schnorr_batch_verify<F: FieldExt>(
+    fp_chip: &FpConfig<F, Fp>,
+    ctx: &mut Context<'_, F>,
+    sigs: &[AssignedSchnorrSignature<F>],
+    var_window_bits: usize,
+    fixed_window_bits: usize,
+) -> Result<AssignedValue<F>, Error> {
+    assert!(!sigs.is_empty());
+    let range = fp_chip.range();
+    let gate = range.gate();
+    let scalar_chip =
+        FqOverflowChip::construct(range, fp_chip.limb_bits, fp_chip.num_limbs, modulus::<Fq>());
+    let tag_hash = sha256_native(CHALLENGE_TAG);
+    let tag_prefix = gate.assign_region_smart(
+        ctx,
+        [tag_hash, tag_hash].concat().iter().map(|b| Constant(F::from(*b as u64))).collect(),
+        vec![],
+        vec![],
+        vec![],
+    )?;
+
+    let mut flags = Vec::new();
+    let mut transcript = TranscriptChip::<F, T, RATE>::new(ctx, gate, R_F, R_P)?;
+    let mut s_vec = Vec::with_capacity(sigs.len());
+    let mut e_vec = Vec::with_capacity(sigs.len());
+    for sig in sigs.iter() {
+        assert_eq!(sig.pubkey_x.len(), 32);
+        assert_eq!(sig.msg.len(), 32);
+        assert_eq!(sig.sig.len(), 64);
+        let pubkey_x = be_bytes_to_integer(fp_chip, ctx, &sig.pubkey_x)?;
+        let r = be_bytes_to_integer(fp_chip, ctx, &sig.sig[..32])?;
+        let s = be_bytes_to_integer(fp_chip, ctx, &sig.sig[32..])?.truncation;
+        for byte in sig.msg.iter() {
+            range.range_check(ctx, byte, 8)?;
+        }
+        flags.push(is_lift_x(fp_chip, ctx, &pubkey_x, &sig.pubkey)?);
+        flags.push(is_lift_x(fp_chip, ctx, &r, &sig.r_point)?);
+        flags.push(big_less_than::assign_constant(range, ctx, &s, &scalar_chip.p)?);
+
+        let challenge_input: Vec<_> = tag_prefix
+            .iter()
+            .chain(sig.sig[..32].iter())
+            .chain(sig.pubkey_x.iter())
+            .chain(sig.msg.iter())
+            .cloned()
+            .collect();
+        let digest = sha256(range, ctx, &challenge_input)?;
+        let e = digest_to_scalar(fp_chip, ctx, &digest)?;
+
+        for limbs in [&pubkey_x.truncation.limbs, &r.truncation.limbs, &s.limbs, &e.limbs] {
+            for limb in limbs.iter() {
+                transcript.common_scalar(ctx, gate, limb)?;
+            }
+        }
+        s_vec.push(s);
+        e_vec.push(e);
+    }
+
+    // a_0 = 1, and a_i are the low bits of challenges of the transcript, as limbs of `fp_chip`
+    let mut coeffs = vec![scalar_chip.load_constant(ctx, BigInt::one())?];
+    for _ in 1..sigs.len() {
+        let challenge = transcript.squeeze_challenge(ctx, gate)?;
+        let bits = range.num_to_bits_strict(ctx, &challenge)?;
+        let mut limbs = Vec::with_capacity(fp_chip.num_limbs);
+        for chunk in bits[..BATCH_COEFF_BITS].chunks(fp_chip.limb_bits) {
+            limbs.push(gate.bits_to_num(ctx, chunk, false)?);
+        }
+        while limbs.len() < fp_chip.num_limbs {
+            limbs.push(gate.load_zero(ctx)?);
+        }
+        coeffs.push(OverflowInteger::construct(
+            limbs,
+            (BigUint::one() << fp_chip.limb_bits) - 1usize,
+            fp_chip.limb_bits,
+            (BigUint::one() << BATCH_COEFF_BITS) - 1usize,
+        ));
+    }
+
+    // sum_i a_i s_i (mod n) and a_i e_i (mod n)
+    let mut s_sum = scalar_chip.mul_no_carry(ctx, &coeffs[0], &s_vec[0])?;
+    for (a, s) in coeffs.iter().zip(s_vec.iter()).skip(1) {
+        let term = scalar_chip.mul_no_carry(ctx, a, s)?;
+        s_sum = scalar_chip.add_no_carry(ctx, &s_sum, &term)?;
+    }
+    let s_sum = scalar_chip.carry_mod(ctx, &s_sum)?;
+    let mut points = Vec::with_capacity(2 * sigs.len());
+    let mut scalars = Vec::with_capacity(2 * sigs.len());
+    for ((a, e), sig) in coeffs.iter().zip(e_vec.iter()).zip(sigs.iter()) {
+        let ae = scalar_chip.mul(ctx, a, e)?;
+        points.push(sig.r_point.clone());
+        scalars.push(a.limbs.clone());
+        points.push(sig.pubkey.clone());
+        scalars.push(ae.limbs);
+    }
+
+    let g =
+        FixedEccPoint::from_g1(&Secp256k1Affine::generator(), fp_chip.num_limbs, fp_chip.limb_bits);
+    let lhs = fixed_base_scalar_multiply(
+        fp_chip,
+        ctx,
+        &g,
+        &s_sum.limbs,
+        fp_chip.limb_bits,
+        fixed_window_bits,
+    )?;
+    let rhs = multi_scalar_multiply::<F, FpConfig<F, Fp>, Secp256k1Affine>(
+        fp_chip,
+        ctx,
+        &points,
+        &scalars,
+        F::from(SECP_B),
+        fp_chip.limb_bits,
+        var_window_bits,
+    )?;
+    flags.push(fp_chip.is_equal(ctx, &lhs.x, &rhs.x)?);
+    flags.push(fp_chip.is_equal(ctx, &lhs.y, &rhs.y)?);
+
+    let mut is_valid = flags[0].clone();
+    for flag in flags[1..].iter() {
+        is_valid = gate.and(ctx, &Existing(&is_valid), &Existing(flag))?;
+    }
+    Ok(is_valid)
+}
+
+#[cfg(test)]
+mod tests;
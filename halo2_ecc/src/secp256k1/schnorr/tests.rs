@@ -0,0 +1,318 @@
+use super::*;
+use crate::fields::fp::FpStrategy;
+use halo2_base::{utils::value_to_option, ContextId, ContextParams};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::MockProver,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use rand_core::{OsRng, RngCore};
+
+const K: u32 = 19;
+const NUM_ADVICE: usize = 8;
+
+fn to_bytes_be(x: &Fp) -> [u8; 32] {
+    let bytes = fe_to_biguint(x).to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+// the point `k G` with even y, and `k` negated if needed
+fn even_point(k: Fq) -> (Secp256k1Affine, Fq) {
+    let point = Secp256k1Affine::from(Secp256k1Affine::generator() * k);
+    if fe_to_biguint(point.coordinates().unwrap().y()).bit(0) {
+        (-point, -k)
+    } else {
+        (point, k)
+    }
+}
+
+// signs `msg` with a random key as in BIP-340
+fn sign(msg: [u8; 32]) -> SchnorrSignature {
+    let (pubkey, sk) = even_point(Fq::random(OsRng));
+    let (r_point, k) = even_point(Fq::random(OsRng));
+    let mut sig = SchnorrSignature {
+        pubkey_x: to_bytes_be(pubkey.coordinates().unwrap().x()),
+        msg,
+        sig: [0u8; 64],
+    };
+    sig.sig[..32].copy_from_slice(&to_bytes_be(r_point.coordinates().unwrap().x()));
+    let s = k + challenge_native(&sig) * sk;
+    let s_bytes = fe_to_biguint(&s).to_bytes_be();
+    sig.sig[64 - s_bytes.len()..].copy_from_slice(&s_bytes);
+    sig
+}
+
+fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+    out
+}
+
+// the vectors of https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv with 32 byte messages, as
+// (index, public key, message, signature, result). Vector 4 is left out, and 15 to 18 sign messages of other lengths
+const BIP340_VECTORS: [(usize, &str, &str, &str, bool); 14] = [
+    (
+        0,
+        "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA8215\
+         25F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+        true,
+    ),
+    (
+        1,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE3341\
+         8906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+        true,
+    ),
+    (
+        2,
+        "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+        "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+        "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1B\
+         AB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+        true,
+    ),
+    (
+        3,
+        "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+        "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC\
+         97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+        true,
+    ),
+    // public key not on the curve
+    (
+        5,
+        "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+         69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+        false,
+    ),
+    // has_even_y(R) is false
+    (
+        6,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A1460297556\
+         3CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+        false,
+    ),
+    // negated message
+    (
+        7,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F\
+         28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD",
+        false,
+    ),
+    // negated s value
+    (
+        8,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+         961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6",
+        false,
+    ),
+    // sG - eP is infinite, x(inf) defined as 0
+    (
+        9,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "0000000000000000000000000000000000000000000000000000000000000000\
+         123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+        false,
+    ),
+    // sG - eP is infinite, x(inf) defined as 1
+    (
+        10,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "0000000000000000000000000000000000000000000000000000000000000001\
+         7615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+        false,
+    ),
+    // sig[0:32] is not an X coordinate on the curve
+    (
+        11,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D\
+         69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+        false,
+    ),
+    // sig[0:32] is equal to field size
+    (
+        12,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F\
+         69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+        false,
+    ),
+    // sig[32:64] is equal to curve order
+    (
+        13,
+        "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+         FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+        false,
+    ),
+    // public key is not a valid X coordinate because it exceeds the field size
+    (
+        14,
+        "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+        "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+        "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769\
+         69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+        false,
+    ),
+];
+
+fn bip340_vector(i: usize) -> (SchnorrSignature, bool) {
+    let (_, pubkey_x, msg, sig, result) =
+        BIP340_VECTORS.iter().find(|v| v.0 == i).expect("vector is in the table");
+    (
+        SchnorrSignature { pubkey_x: from_hex(pubkey_x), msg: from_hex(msg), sig: from_hex(sig) },
+        *result,
+    )
+}
+
+fn random_msg() -> [u8; 32] {
+    let mut msg = [0u8; 32];
+    OsRng.fill_bytes(&mut msg);
+    msg
+}
+
+#[derive(Clone, Default)]
+struct SchnorrBatchCircuit<F> {
+    sigs: Vec<SchnorrSignature>,
+    expected: bool,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> Circuit<F> for SchnorrBatchCircuit<F> {
+    type Config = FpConfig<F, Fp>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            1,
+            18,
+            88,
+            3,
+            modulus::<Fp>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        fp_chip: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        fp_chip.range.load_lookup_table(&mut layouter)?;
+
+        let mut first_pass = true;
+        layouter.assign_region(
+            || "schnorr batch",
+            |region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+
+                let mut sigs = Vec::with_capacity(self.sigs.len());
+                for sig in self.sigs.iter() {
+                    sigs.push(load_schnorr_signature(&fp_chip, ctx, Value::known(sig))?);
+                }
+                let is_valid = schnorr_batch_verify(&fp_chip, ctx, &sigs, 4, 4)?;
+                assert_eq!(
+                    value_to_option(is_valid.value().copied()),
+                    Some(F::from(self.expected))
+                );
+
+                fp_chip.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+fn run(sigs: Vec<SchnorrSignature>, expected: bool) {
+    let circuit = SchnorrBatchCircuit::<Fr> { sigs, expected, _marker: std::marker::PhantomData };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn test_schnorr_verify_native() {
+    let sig = sign(random_msg());
+    assert!(schnorr_verify_native(&sig));
+    let mut wrong_sig = sig.clone();
+    wrong_sig.sig[63] ^= 1;
+    assert!(!schnorr_verify_native(&wrong_sig));
+    // x = 5 is not on the curve, as 5^3 + 7 = 132 is not a square mod p
+    assert!(lift_x_native(&to_bytes_be(&Fp::from(5))).is_none());
+    assert!(lift_x_native(&[0xff; 32]).is_none());
+}
+
+#[test]
+fn test_schnorr_verify_native_bip340_vectors() {
+    for (i, ..) in BIP340_VECTORS.iter() {
+        let (sig, result) = bip340_vector(*i);
+        assert_eq!(schnorr_verify_native(&sig), result, "vector {i}");
+    }
+}
+
+#[test]
+fn test_schnorr_batch_verify_bip340_vectors() {
+    run(vec![bip340_vector(0).0, bip340_vector(1).0], true);
+    run(vec![bip340_vector(2).0, bip340_vector(3).0], true);
+    // R with odd y, r = p and s = n, each next to a valid signature
+    for i in [6, 12, 13] {
+        let (sig, result) = bip340_vector(i);
+        assert!(!result);
+        run(vec![bip340_vector(1).0, sig], false);
+    }
+}
+
+#[test]
+fn test_schnorr_batch_verify() {
+    let sigs: Vec<_> = (0..2).map(|_| sign(random_msg())).collect();
+    run(sigs.clone(), true);
+
+    // a single tampered message invalidates the batch
+    let mut wrong_msg = sigs.clone();
+    wrong_msg[1].msg[0] ^= 1;
+    run(wrong_msg, false);
+
+    // so does an `r` which is not the x-coordinate of a point
+    let mut wrong_r = sigs;
+    wrong_r[0].sig[..32].copy_from_slice(&to_bytes_be(&Fp::from(5)));
+    run(wrong_r, false);
+}
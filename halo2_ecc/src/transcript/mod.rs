@@ -0,0 +1,119 @@
+use crate::{bigint::CRTInteger, ecc::EccPoint};
+use ff::PrimeField;
+use halo2_base::{
+    gates::GateInstructions,
+    hashes::poseidon::{PoseidonChip, PoseidonSponge},
+    utils::{decompose_biguint, fe_to_biguint},
+    AssignedValue, Context,
+};
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    plonk::Error,
+};
+
+#[cfg(test)]
+mod tests;
+
+// A Fiat-Shamir transcript over the Poseidon sponge of `halo2_base::hashes::poseidon`.
+//
+// Scalars (elements of the native field `F`) are absorbed directly. Base field elements and curve points
+// are absorbed as their `num_limbs` limbs of `limb_bits` bits each, least significant limb first,
+// which is the same encoding as the truncation of a reduced `CRTInteger`.
+// Points are absorbed as `x` limbs followed by `y` limbs.
+//
+// The in-circuit `TranscriptChip` and the prover-side `PoseidonTranscript` produce the same challenges as long as
+// they are constructed with the same `T, RATE, r_f, r_p` and limb parameters. To verify proofs produced with
+// an external transcript (e.g. snark-verifier's Poseidon transcript) the same parameters and encoding must be used.
+
+/// Prover-side Poseidon transcript
+#[derive(Clone, Debug)]
+pub struct PoseidonTranscript<F: FieldExt, const T: usize, const RATE: usize> {
+    sponge: PoseidonSponge<F, T, RATE>,
+    pub limb_bits: usize,
+    pub num_limbs: usize,
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonTranscript<F, T, RATE> {
+    pub fn new(r_f: usize, r_p: usize, limb_bits: usize, num_limbs: usize) -> Self {
+        Self { sponge: PoseidonSponge::new(r_f, r_p), limb_bits, num_limbs }
+    }
+
+    pub fn common_scalar(&mut self, scalar: &F) {
+        self.sponge.update(&[*scalar]);
+    }
+
+    pub fn common_base<Fp: PrimeField>(&mut self, x: &Fp) {
+        let limbs = decompose_biguint::<F>(&fe_to_biguint(x), self.num_limbs, self.limb_bits);
+        self.sponge.update(&limbs);
+    }
+
+    pub fn common_point<C>(&mut self, point: &C)
+    where
+        C: CurveAffine,
+        C::Base: PrimeField,
+    {
+        let coordinates = point.coordinates().unwrap();
+        self.common_base(coordinates.x());
+        self.common_base(coordinates.y());
+    }
+
+    pub fn squeeze_challenge(&mut self) -> F {
+        self.sponge.squeeze()
+    }
+}
+
+/// In-circuit Poseidon transcript mirroring `PoseidonTranscript`
+#[derive(Clone, Debug)]
+pub struct TranscriptChip<F: FieldExt, const T: usize, const RATE: usize> {
+    poseidon: PoseidonChip<F, T, RATE>,
+}
+
+impl<F: FieldExt, const T: usize, const RATE: usize> TranscriptChip<F, T, RATE> {
+    pub fn new(
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        r_f: usize,
+        r_p: usize,
+    ) -> Result<Self, Error> {
+        Ok(Self { poseidon: PoseidonChip::new(ctx, gate, r_f, r_p)? })
+    }
+
+    pub fn common_scalar(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        scalar: &AssignedValue<F>,
+    ) -> Result<(), Error> {
+        self.poseidon.update(ctx, gate, &[scalar.clone()])
+    }
+
+    // `a` must be reduced and in proper representation (e.g. the output of `carry_mod` followed by
+    // `enforce_less_than_p`) so that its limbs are the canonical encoding of the field element
+    pub fn common_crt(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        a: &CRTInteger<F>,
+    ) -> Result<(), Error> {
+        self.poseidon.update(ctx, gate, &a.truncation.limbs)
+    }
+
+    // the coordinates of `point` must be reduced, see `common_crt`
+    pub fn common_point(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+        point: &EccPoint<F, CRTInteger<F>>,
+    ) -> Result<(), Error> {
+        self.common_crt(ctx, gate, &point.x)?;
+        self.common_crt(ctx, gate, &point.y)
+    }
+
+    pub fn squeeze_challenge(
+        &mut self,
+        ctx: &mut Context<'_, F>,
+        gate: &impl GateInstructions<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.poseidon.squeeze(ctx, gate)
+    }
+}
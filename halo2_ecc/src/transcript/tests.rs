@@ -0,0 +1,129 @@
+use super::{PoseidonTranscript, TranscriptChip};
+use crate::ecc::EccChip;
+use crate::fields::fp::{FpConfig, FpStrategy};
+use crate::fields::FieldChip;
+use group::{ff::Field, Curve, Group};
+use halo2_base::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::modulus,
+    Context, ContextId, ContextParams,
+    QuantumCell::Witness,
+};
+use halo2_proofs::{
+    circuit::*,
+    dev::MockProver,
+    halo2curves::bn256::{Fq, Fr, G1Affine, G1},
+    plonk::*,
+};
+use rand::rngs::OsRng;
+
+const T: usize = 3;
+const RATE: usize = 2;
+const R_F: usize = 8;
+const R_P: usize = 57;
+
+const NUM_ADVICE: usize = 2;
+const NUM_FIXED: usize = 1;
+const LIMB_BITS: usize = 88;
+const NUM_LIMBS: usize = 3;
+
+#[derive(Default)]
+struct TranscriptCircuit {
+    scalar: Value<Fr>,
+    point: Value<G1Affine>,
+    expected: Vec<Fr>,
+}
+
+impl Circuit<Fr> for TranscriptCircuit {
+    type Config = FpConfig<Fr, Fq>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { expected: self.expected.clone(), ..Self::default() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        FpConfig::configure(
+            meta,
+            FpStrategy::Simple,
+            &[NUM_ADVICE],
+            &[1],
+            NUM_FIXED,
+            15,
+            LIMB_BITS,
+            NUM_LIMBS,
+            modulus::<Fq>(),
+            ContextId::new("default"),
+        )
+    }
+
+    fn synthesize(&self, chip: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
+        chip.load_lookup_table(&mut layouter)?;
+
+        let using_simple_floor_planner = true;
+        let mut first_pass = true;
+
+        layouter.assign_region(
+            || "transcript",
+            |region| {
+                if first_pass && using_simple_floor_planner {
+                    first_pass = false;
+                    return Ok(());
+                }
+
+                let mut aux = Context::new(
+                    region,
+                    ContextParams { num_advice: vec![(ContextId::new("default"), NUM_ADVICE)] },
+                );
+                let ctx = &mut aux;
+                let gate = chip.range().gate();
+
+                let scalar = gate.assign_region_smart(
+                    ctx,
+                    vec![Witness(self.scalar)],
+                    vec![],
+                    vec![],
+                    vec![],
+                )?;
+                let ecc_chip = EccChip::construct(&chip);
+                let point = ecc_chip.assign_point(ctx, self.point)?;
+
+                let mut transcript = TranscriptChip::<Fr, T, RATE>::new(ctx, gate, R_F, R_P)?;
+                transcript.common_scalar(ctx, gate, &scalar[0])?;
+                let c0 = transcript.squeeze_challenge(ctx, gate)?;
+                transcript.common_point(ctx, gate, &point)?;
+                transcript.common_scalar(ctx, gate, &c0)?;
+                let c1 = transcript.squeeze_challenge(ctx, gate)?;
+
+                gate.assert_is_const(ctx, &c0, self.expected[0]);
+                gate.assert_is_const(ctx, &c1, self.expected[1]);
+
+                chip.finalize(ctx)?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[test]
+fn test_transcript_chip() {
+    let k = 16;
+    let scalar = Fr::random(OsRng);
+    let point = G1::random(OsRng).to_affine();
+
+    let mut transcript = PoseidonTranscript::<Fr, T, RATE>::new(R_F, R_P, LIMB_BITS, NUM_LIMBS);
+    transcript.common_scalar(&scalar);
+    let c0 = transcript.squeeze_challenge();
+    transcript.common_point(&point);
+    transcript.common_scalar(&c0);
+    let c1 = transcript.squeeze_challenge();
+
+    let circuit = TranscriptCircuit {
+        scalar: Value::known(scalar),
+        point: Value::known(point),
+        expected: vec![c0, c1],
+    };
+
+    let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
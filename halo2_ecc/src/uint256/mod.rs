@@ -0,0 +1,267 @@
+// EVM 256-bit words
+//
+// A word is an `OverflowInteger` with 4 limbs of 64 bits in proper representation, with the least significant limb
+// first as everywhere in `bigint`. Arithmetic follows the EVM: `add`, `sub` and `mul` wrap around mod 2^256, and
+// `addmod` and `mulmod` return 0 for a zero modulus instead of making the circuit unsatisfiable. Bytes are
+// big-endian, as in EVM memory and calldata.
+
+use crate::bigint::{
+    add_no_carry, big_is_zero, big_less_than, mul_no_carry, sub, var_carry_mod, OverflowInteger,
+};
+use halo2_base::{
+    gates::{range::RangeConfig, GateInstructions, RangeInstructions},
+    safe_types::SafeBytes32,
+    utils::{biguint_to_fe, decompose_bigint_option, decompose_biguint},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use halo2_proofs::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use num_bigint::{BigInt, BigUint};
+use num_traits::One;
+
+pub const UINT256_LIMB_BITS: usize = 64;
+pub const UINT256_NUM_LIMBS: usize = 4;
+
+#[derive(Debug)]
+pub struct Uint256Chip<'a, F: FieldExt> {
+    pub range: &'a RangeConfig<F>,
+}
+
+impl<'a, F: FieldExt> Uint256Chip<'a, F> {
+    pub fn construct(range: &'a RangeConfig<F>) -> Self {
+        Self { range }
+    }
+
+    fn word(limbs: Vec<AssignedValue<F>>, max_size: BigUint) -> OverflowInteger<F> {
+        assert_eq!(limbs.len(), UINT256_NUM_LIMBS);
+        OverflowInteger::construct(
+            limbs,
+            (BigUint::one() << UINT256_LIMB_BITS) - 1usize,
+            UINT256_LIMB_BITS,
+            max_size,
+        )
+    }
+
+    fn max_word() -> BigUint {
+        (BigUint::one() << (UINT256_LIMB_BITS * UINT256_NUM_LIMBS)) - 1usize
+    }
+
+    /// Loads `a` as a witness word, with each limb range checked to 64 bits
+    pub fn load_private(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: Value<BigUint>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let a_vec = decompose_bigint_option::<F>(
+            &a.map(BigInt::from),
+            UINT256_NUM_LIMBS,
+            UINT256_LIMB_BITS,
+        );
+        let limbs = self.range.gate().assign_region_smart(
+            ctx,
+            a_vec.into_iter().map(Witness).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        for limb in limbs.iter() {
+            self.range.range_check(ctx, limb, UINT256_LIMB_BITS)?;
+        }
+        Ok(Self::word(limbs, Self::max_word()))
+    }
+
+    pub fn load_constant(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &BigUint,
+    ) -> Result<OverflowInteger<F>, Error> {
+        assert!(a.bits() as usize <= UINT256_LIMB_BITS * UINT256_NUM_LIMBS);
+        let a_vec = decompose_biguint::<F>(a, UINT256_NUM_LIMBS, UINT256_LIMB_BITS);
+        let limbs = self.range.gate().assign_region_smart(
+            ctx,
+            a_vec.into_iter().map(Constant).collect(),
+            vec![],
+            vec![],
+            vec![],
+        )?;
+        Ok(Self::word(limbs, a.clone()))
+    }
+
+    // propagates the carries through the first 4 of `limbs`, which are non-negative with at most `max_bits` bits
+    // including the carry into them, and returns the proper word and the carry out of the top limb
+    fn carry(
+        &self,
+        ctx: &mut Context<'_, F>,
+        limbs: &[AssignedValue<F>],
+        max_bits: usize,
+    ) -> Result<(OverflowInteger<F>, AssignedValue<F>), Error> {
+        let base = BigUint::one() << UINT256_LIMB_BITS;
+        let mut out = Vec::with_capacity(UINT256_NUM_LIMBS);
+        let mut carry: Option<AssignedValue<F>> = None;
+        for limb in limbs[..UINT256_NUM_LIMBS].iter() {
+            let sum = match carry {
+                None => limb.clone(),
+                Some(carry) => self.range.gate().add(ctx, &Existing(limb), &Existing(&carry))?,
+            };
+            let (quot, rem) = self.range.div_mod(ctx, &Existing(&sum), &base, max_bits)?;
+            out.push(rem);
+            carry = Some(quot);
+        }
+        Ok((Self::word(out, Self::max_word()), carry.unwrap()))
+    }
+
+    /// Returns `a + b (mod 2^256)`
+    pub fn add(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let sum = add_no_carry::assign(self.range.gate(), ctx, a, b)?;
+        // each limb of the sum and the carry into it is less than 2^65
+        Ok(self.carry(ctx, &sum.limbs, UINT256_LIMB_BITS + 1)?.0)
+    }
+
+    /// Returns `a - b (mod 2^256)`
+    pub fn sub(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        // the borrow chain of `sub` already computes `a - b + 2^256` when `a < b`
+        Ok(sub::assign(self.range, ctx, a, b)?.0)
+    }
+
+    /// Returns `a * b (mod 2^256)`
+    pub fn mul(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        // limbs `>= 4` of the product are multiples of 2^256, so only the first 4 are carried: each is a sum of at
+        // most 4 products of 64 bit limbs, and the carry into it is less than 2^68
+        let prod = mul_no_carry::assign(self.range.gate(), ctx, a, b)?;
+        Ok(self.carry(ctx, &prod.limbs, 2 * UINT256_LIMB_BITS + 3)?.0)
+    }
+
+    // `a (mod n)`, or 0 if `n = 0`
+    fn reduce(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        n: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        // `a (mod 1) = 0`, so reducing by `n + (n == 0)` gives 0 for a zero modulus
+        let n_is_zero = big_is_zero::assign(self.range, ctx, n)?;
+        let mut limbs = n.limbs.clone();
+        limbs[0] = self.range.gate().add(ctx, &Existing(&n.limbs[0]), &Existing(&n_is_zero))?;
+        let n = Self::word(limbs, Self::max_word());
+        var_carry_mod::assign(self.range, ctx, a, &n)
+    }
+
+    /// Returns `(a + b) mod n` without wrapping the sum around 2^256, or 0 if `n = 0`, as the EVM `ADDMOD`
+    pub fn addmod(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+        n: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let sum = add_no_carry::assign(self.range.gate(), ctx, a, b)?;
+        self.reduce(ctx, &sum, n)
+    }
+
+    /// Returns `(a * b) mod n` without wrapping the product around 2^256, or 0 if `n = 0`, as the EVM `MULMOD`
+    pub fn mulmod(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+        n: &OverflowInteger<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let prod = mul_no_carry::assign(self.range.gate(), ctx, a, b)?;
+        self.reduce(ctx, &prod, n)
+    }
+
+    /// Returns `(a < b, a == b)` as unsigned integers
+    pub fn compare(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        big_less_than::compare(self.range, ctx, a, b)
+    }
+
+    /// Returns `(a < b, a == b)` as two's complement signed integers, as the EVM `SLT`
+    pub fn signed_compare(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+        b: &OverflowInteger<F>,
+    ) -> Result<(AssignedValue<F>, AssignedValue<F>), Error> {
+        // adding 2^255 mod 2^256, i.e. flipping the sign bit, maps the signed order to the unsigned order
+        let half = BigUint::one() << (UINT256_LIMB_BITS - 1);
+        let base = BigUint::one() << UINT256_LIMB_BITS;
+        let flip = |ctx: &mut Context<'_, F>, x: &OverflowInteger<F>| {
+            let top = x.limbs.last().unwrap();
+            let shifted =
+                self.range.gate().add(ctx, &Existing(top), &Constant(biguint_to_fe(&half)))?;
+            let (_, top) =
+                self.range.div_mod(ctx, &Existing(&shifted), &base, UINT256_LIMB_BITS + 1)?;
+            let mut limbs = x.limbs.clone();
+            *limbs.last_mut().unwrap() = top;
+            Ok::<_, Error>(Self::word(limbs, Self::max_word()))
+        };
+        let a = flip(ctx, a)?;
+        let b = flip(ctx, b)?;
+        self.compare(ctx, &a, &b)
+    }
+
+    pub fn is_zero(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        big_is_zero::assign(self.range, ctx, a)
+    }
+
+    /// Returns the 32 big-endian bytes of `a`, each range checked to be in [0, 2^8)
+    pub fn to_be_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        a: &OverflowInteger<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let mut bytes = Vec::with_capacity(32);
+        for limb in a.limbs.iter().rev() {
+            let limb_bytes = self.range.decompose_bytes(ctx, limb, UINT256_LIMB_BITS / 8)?;
+            bytes.extend(limb_bytes.into_iter().rev());
+        }
+        Ok(bytes)
+    }
+
+    /// Returns the word with the 32 big-endian bytes `bytes`
+    pub fn from_be_bytes(
+        &self,
+        ctx: &mut Context<'_, F>,
+        bytes: &SafeBytes32<F>,
+    ) -> Result<OverflowInteger<F>, Error> {
+        let pows: Vec<_> = (0..UINT256_LIMB_BITS / 8)
+            .map(|i| Constant(biguint_to_fe(&(BigUint::one() << (8 * i)))))
+            .collect();
+        let mut limbs = Vec::with_capacity(UINT256_NUM_LIMBS);
+        for limb_bytes in bytes.bytes().rchunks(UINT256_LIMB_BITS / 8) {
+            limbs.push(self.range.gate().inner_product(
+                ctx,
+                &limb_bytes.iter().rev().map(Existing).collect(),
+                &pows,
+            )?);
+        }
+        Ok(Self::word(limbs, Self::max_word()))
+    }
+}
+
+#[cfg(test)]
+mod tests;
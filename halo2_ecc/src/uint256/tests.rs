@@ -0,0 +1,97 @@
+use super::*;
+use halo2_base::{
+    gates::{
+        circuit::RangeCircuitBuilder,
+        params::CircuitParams,
+        range::{RangeConfig, RangeStrategy},
+    },
+    utils::{fe_to_biguint, value_to_option},
+};
+use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+use num_bigint::RandBigInt;
+use rand::rngs::OsRng;
+
+const PARAMS: CircuitParams = CircuitParams {
+    degree: 15,
+    num_advice: 4,
+    num_lookup_advice: 1,
+    num_fixed: 1,
+    lookup_bits: 14,
+};
+
+fn word_value(a: &OverflowInteger<Fr>) -> BigUint {
+    value_to_option(a.to_bigint()).unwrap().to_biguint().unwrap()
+}
+
+fn bit_value(a: &AssignedValue<Fr>) -> bool {
+    value_to_option(a.value().copied()).unwrap() == Fr::one()
+}
+
+// the EVM `SLT` of two words
+fn signed_lt(a: &BigUint, b: &BigUint) -> bool {
+    let flip = |x: &BigUint| x ^ (BigUint::one() << 255);
+    flip(a) < flip(b)
+}
+
+#[test]
+fn test_uint256() {
+    let modulus = BigUint::one() << 256;
+    let max = &modulus - 1usize;
+    let random = OsRng.gen_biguint(256);
+    let cases = vec![
+        (random.clone(), OsRng.gen_biguint(256), OsRng.gen_biguint(200)),
+        (max.clone(), BigUint::one(), BigUint::from(0u64)),
+        (BigUint::from(3u64), max.clone(), BigUint::one()),
+        (random.clone(), random, max.clone()),
+    ];
+    for (a, b, n) in cases {
+        let circuit = RangeCircuitBuilder::new(
+            RangeStrategy::Vertical,
+            PARAMS,
+            move |range: &RangeConfig<Fr>, ctx| {
+                let chip = Uint256Chip::construct(range);
+                let a_word = chip.load_private(ctx, Value::known(a.clone()))?;
+                let b_word = chip.load_private(ctx, Value::known(b.clone()))?;
+                let n_word = chip.load_private(ctx, Value::known(n.clone()))?;
+
+                let sum = chip.add(ctx, &a_word, &b_word)?;
+                assert_eq!(word_value(&sum), (&a + &b) % &modulus);
+                let diff = chip.sub(ctx, &a_word, &b_word)?;
+                assert_eq!(word_value(&diff), (&a + &modulus - &b) % &modulus);
+                let prod = chip.mul(ctx, &a_word, &b_word)?;
+                assert_eq!(word_value(&prod), (&a * &b) % &modulus);
+
+                let reduce = |x: BigUint| if n == BigUint::from(0u64) { x * 0u64 } else { x % &n };
+                let addmod = chip.addmod(ctx, &a_word, &b_word, &n_word)?;
+                assert_eq!(word_value(&addmod), reduce(&a + &b));
+                let mulmod = chip.mulmod(ctx, &a_word, &b_word, &n_word)?;
+                assert_eq!(word_value(&mulmod), reduce(&a * &b));
+
+                let (lt, eq) = chip.compare(ctx, &a_word, &b_word)?;
+                assert_eq!((bit_value(&lt), bit_value(&eq)), (a < b, a == b));
+                let (slt, seq) = chip.signed_compare(ctx, &a_word, &b_word)?;
+                assert_eq!((bit_value(&slt), bit_value(&seq)), (signed_lt(&a, &b), a == b));
+
+                let bytes = chip.to_be_bytes(ctx, &a_word)?;
+                let mut expected = a.to_bytes_be();
+                expected.splice(0..0, vec![0u8; 32 - expected.len()]);
+                let byte_values: Vec<_> = bytes
+                    .iter()
+                    .map(|x| fe_to_biguint(&value_to_option(x.value().copied()).unwrap()))
+                    .collect();
+                assert_eq!(
+                    byte_values,
+                    expected.iter().map(|b| BigUint::from(*b)).collect::<Vec<_>>()
+                );
+                let bytes = SafeBytes32::new(range, ctx, bytes)?;
+                let a_again = chip.from_be_bytes(ctx, &bytes)?;
+                for (x, y) in a_again.limbs.iter().zip(a_word.limbs.iter()) {
+                    range.gate().assert_equal(ctx, &Existing(x), &Existing(y))?;
+                }
+                Ok(())
+            },
+        );
+        let prover = MockProver::run(PARAMS.degree, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+    }
+}